@@ -48,6 +48,7 @@ use uuid::Uuid;
 
 use crate::resource_allocator::{ResourceAllocator, OperationPriority, OperationType};
 use crate::performance::PerformanceTracker;
+use crate::process_sampler::{ProcessSampler, ResourceSample};
 
 /// Errors specific to background processing
 #[derive(Error, Debug, Clone)]
@@ -260,6 +261,12 @@ impl SystemResourceMonitor {
         self.memory_usage.store(usage_u64, Ordering::Relaxed);
     }
 
+    /// Update CPU and memory usage from a process sample (system-wide figures)
+    pub fn update_from_sample(&self, sample: &ResourceSample) {
+        self.update_cpu_usage(sample.system_load);
+        self.update_memory_usage(sample.system_memory_usage);
+    }
+
     /// Get current CPU usage
     pub fn get_cpu_usage(&self) -> f64 {
         let usage_u64 = self.cpu_usage.load(Ordering::Relaxed);
@@ -447,6 +454,8 @@ pub struct BackgroundProcessor {
     task_queue: Arc<RwLock<TaskQueue>>,
     /// System resource monitor
     system_monitor: Arc<SystemResourceMonitor>,
+    /// Process and system resource sampler feeding the monitor
+    process_sampler: Arc<ProcessSampler>,
     /// Resource allocator for system resource management
     resource_allocator: Arc<ResourceAllocator>,
     /// Performance tracker
@@ -487,6 +496,7 @@ impl BackgroundProcessor {
             config,
             task_queue: Arc::new(RwLock::new(TaskQueue::default())),
             system_monitor,
+            process_sampler: Arc::new(ProcessSampler::default()),
             resource_allocator,
             performance_tracker,
             active_tasks: Arc::new(RwLock::new(HashMap::new())),
@@ -496,6 +506,12 @@ impl BackgroundProcessor {
         }
     }
 
+    /// Use a shared process sampler instead of a private one
+    pub fn with_process_sampler(mut self, process_sampler: Arc<ProcessSampler>) -> Self {
+        self.process_sampler = process_sampler;
+        self
+    }
+
     /// Start the background processor
     pub async fn start(&self) {
         eprintln!("🔄 Starting background processor...");
@@ -528,23 +544,32 @@ impl BackgroundProcessor {
     /// Start system monitoring task
     async fn start_system_monitoring(&self) {
         let system_monitor = self.system_monitor.clone();
+        let process_sampler = self.process_sampler.clone();
         let shutdown_signal = self.shutdown_signal.clone();
         
         tokio::spawn(async move {
             let mut interval = interval(Duration::from_secs(5));
+            let mut sampling_failed = false;
             
             while !shutdown_signal.load(Ordering::Relaxed) {
                 interval.tick().await;
                 
-                // Update system metrics (simplified - would integrate with real system monitoring)
-                let cpu_usage = Self::get_system_cpu_usage().await;
-                let memory_usage = Self::get_system_memory_usage().await;
-                
-                system_monitor.update_cpu_usage(cpu_usage);
-                system_monitor.update_memory_usage(memory_usage);
-                
-                eprintln!("📊 System metrics - CPU: {:.1}%, Memory: {:.1}%", 
-                         cpu_usage * 100.0, memory_usage * 100.0);
+                match process_sampler.sample() {
+                    Ok(sample) => {
+                        system_monitor.update_from_sample(&sample);
+                        sampling_failed = false;
+                        
+                        eprintln!("📊 System metrics - CPU: {:.1}%, Memory: {:.1}%, RSS: {:.1}MB", 
+                                 sample.system_load * 100.0, sample.system_memory_usage * 100.0, sample.rss_mb);
+                    }
+                    Err(e) => {
+                        // Keep the previous figures; report only the first failure in a row
+                        if !sampling_failed {
+                            eprintln!("⚠️ System metrics unavailable: {}", e);
+                            sampling_failed = true;
+                        }
+                    }
+                }
             }
         });
     }
@@ -780,36 +805,6 @@ impl BackgroundProcessor {
         }
     }
 
-    /// Simplified system CPU usage monitoring (would use real system APIs in production)
-    async fn get_system_cpu_usage() -> f64 {
-        // In a real implementation, this would use system APIs
-        // For now, simulate varying CPU usage
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut hasher = DefaultHasher::new();
-        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs().hash(&mut hasher);
-        let hash_value = hasher.finish();
-        
-        // Generate pseudo-random CPU usage between 0.1 and 0.7
-        0.1 + ((hash_value % 100) as f64 / 100.0) * 0.6
-    }
-
-    /// Simplified system memory usage monitoring
-    async fn get_system_memory_usage() -> f64 {
-        // In a real implementation, this would use system APIs
-        // For now, simulate varying memory usage
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut hasher = DefaultHasher::new();
-        (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 1).hash(&mut hasher);
-        let hash_value = hasher.finish();
-        
-        // Generate pseudo-random memory usage between 0.3 and 0.8
-        0.3 + ((hash_value % 100) as f64 / 100.0) * 0.5
-    }
-
     /// Get current processing statistics
     pub async fn get_stats(&self) -> ProcessingStats {
         self.stats.read().await.clone()
@@ -14,6 +14,8 @@
 //! ### Generator Configuration
 //! - `update_embedding_generator_config`: Update generator parameters
 //! - `get_embedding_generator_config`: Get current generator configuration
+//! - `set_embedding_provider`: Choose the embedding backend (Ollama, OpenAI-compatible, local hashing)
+//! - `get_embedding_provider_config`: Get the selected embedding backend
 //!
 //! ### Cache Management
 //! - `get_embedding_cache_metrics`: Get cache performance metrics
@@ -29,7 +31,7 @@
 //!
 //! 1. **Cache Check**: First check if embedding exists in cache
 //! 2. **Text Preprocessing**: Clean and prepare text for processing
-//! 3. **Model Invocation**: Send text to Ollama embedding model
+//! 3. **Model Invocation**: Send text to the selected embedding provider
 //! 4. **Vector Processing**: Process and normalize embedding vectors
 //! 5. **Cache Storage**: Store result in cache for future use
 //! 6. **Metrics Update**: Update performance and usage metrics
@@ -46,7 +48,7 @@
 //! ## Caching Strategy
 //!
 //! ### Cache Structure
-//! - **Key Format**: `{text_hash}:{model_name}`
+//! - **Key Format**: `{model_id}:{text_hash}`, where the model ID names the provider's vectors
 //! - **TTL Support**: Configurable time-to-live for cache entries
 //! - **LRU Eviction**: Least recently used entries removed when full
 //! - **Persistence**: Optional disk persistence for cache survival
@@ -81,8 +83,9 @@
 //! - Cache corruption and recovery
 //! - Memory and resource constraints
 
-use crate::globals::{get_embedding_cache, get_embedding_generator};
+use crate::globals::{self, get_embedding_cache, get_embedding_provider};
 use crate::embedding_generator::EmbeddingConfig;
+use crate::embedding_provider::EmbeddingProviderConfig;
 use crate::embedding_cache::{CacheMetrics, CacheConfig};
 
 /// Generate embedding vector for a single text
@@ -118,29 +121,11 @@ use crate::embedding_cache::{CacheMetrics, CacheConfig};
 #[tauri::command]
 pub async fn generate_embedding(text: String, model: String) -> Result<Vec<f32>, String> {
     let cache = get_embedding_cache().await;
+    let provider = get_embedding_provider().await;
     
-    // Try cache first
-    if let Ok(Some(cached_embedding)) = cache.get(&text, &model).await {
-        return Ok(cached_embedding);
-    }
-    
-    // Cache miss, generate embedding
-    let generator = get_embedding_generator().await;
-    let start_time = std::time::Instant::now();
-    
-    match generator.generate_embedding(text.clone(), model.clone()).await {
-        Ok(embedding) => {
-            let _generation_time = start_time.elapsed().as_millis() as f64;
-            
-            // Cache the result
-            if let Err(e) = cache.set(&text, &model, embedding.clone()).await {
-                eprintln!("⚠️ Failed to cache embedding: {}", e);
-            }
-            
-            Ok(embedding)
-        }
-        Err(e) => Err(format!("Failed to generate embedding: {}", e))
-    }
+    cache.get_or_embed(provider.as_ref(), &text, &model)
+        .await
+        .map_err(|e| format!("Failed to generate embedding: {}", e))
 }
 
 /// Generate embedding vectors for multiple texts in batch
@@ -196,7 +181,8 @@ pub async fn generate_batch_embeddings(texts: Vec<String>, model: String) -> Res
     }
     
     let cache = get_embedding_cache().await;
-    let generator = get_embedding_generator().await;
+    let provider = get_embedding_provider().await;
+    let model_id = provider.model_id(&model);
     
     eprintln!("🔄 Processing batch of {} embeddings with caching", texts.len());
     
@@ -207,7 +193,7 @@ pub async fn generate_batch_embeddings(texts: Vec<String>, model: String) -> Res
     
     // First pass: check cache for all texts
     for (i, text) in texts.iter().enumerate() {
-        if let Ok(Some(cached_embedding)) = cache.get(text, &model).await {
+        if let Ok(Some(cached_embedding)) = cache.get(text, &model_id).await {
             result_embeddings[i] = cached_embedding;
             hit_count += 1;
         } else {
@@ -223,7 +209,7 @@ pub async fn generate_batch_embeddings(texts: Vec<String>, model: String) -> Res
     // Second pass: generate embeddings for cache misses
     if !cache_misses.is_empty() {
         let generation_start = std::time::Instant::now();
-        match generator.generate_batch_embeddings(cache_misses.clone(), model.clone()).await {
+        match provider.embed_batch(&cache_misses, &model).await {
             Ok(new_embeddings) => {
                 let generation_time = generation_start.elapsed().as_millis() as f64;
                 eprintln!("⚡ Generated {} embeddings in {:.1}ms", new_embeddings.len(), generation_time);
@@ -235,7 +221,7 @@ pub async fn generate_batch_embeddings(texts: Vec<String>, model: String) -> Res
                         
                         // Cache the new embedding
                         if let Some(text) = cache_misses.get(miss_idx) {
-                            if let Err(e) = cache.set(text, &model, new_embedding).await {
+                            if let Err(e) = cache.set(text, &model_id, new_embedding).await {
                                 eprintln!("⚠️ Failed to cache embedding for text {}: {}", miss_idx, e);
                             }
                         }
@@ -365,6 +351,43 @@ pub async fn get_embedding_generator_config() -> Result<EmbeddingConfig, String>
     }
 }
 
+/// Choose the embedding backend
+///
+/// Indexing, the embedding queue and the embedding commands use the chosen
+/// provider from the next request on. Cached embeddings are keyed by the
+/// provider's model ID, so switching never returns another backend's vectors.
+///
+/// # Arguments
+/// * `config` - Backend to use: `ollama`, `openai_compatible` (llama.cpp server,
+///   LM Studio, ...) or the deterministic local `hashing` provider
+///
+/// # Returns
+/// * `Ok(())` - Provider selected
+/// * `Err(String)` - Error message if the configuration is invalid
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// await invoke('set_embedding_provider', {
+///     config: { type: 'openai_compatible', base_url: 'http://localhost:8080', max_batch_size: 16 }
+/// });
+/// await invoke('set_embedding_provider', { config: { type: 'ollama' } });
+/// ```
+#[tauri::command]
+pub async fn set_embedding_provider(config: EmbeddingProviderConfig) -> Result<(), String> {
+    globals::set_embedding_provider(config).await?;
+    eprintln!("🔌 Embedding provider changed");
+    Ok(())
+}
+
+/// Get the selected embedding backend
+///
+/// # Returns
+/// * `Ok(EmbeddingProviderConfig)` - Selected provider, `ollama` by default
+#[tauri::command]
+pub async fn get_embedding_provider_config() -> Result<EmbeddingProviderConfig, String> {
+    Ok(globals::get_embedding_provider_config().await)
+}
+
 /// Get comprehensive embedding cache performance metrics
 ///
 /// This command retrieves detailed performance and usage metrics from the
@@ -609,7 +632,8 @@ pub async fn generate_adaptive_batch_embeddings(
     }
 
     let cache = get_embedding_cache().await;
-    let generator = get_embedding_generator().await;
+    let provider = get_embedding_provider().await;
+    let model_id = provider.model_id(&model);
     
     // Determine optimal batch size based on system conditions
     let optimal_batch_size = calculate_optimal_batch_size(
@@ -637,7 +661,7 @@ pub async fn generate_adaptive_batch_embeddings(
         let mut _batch_hit_count = 0;
         
         for (_local_idx, (global_idx, text)) in batch_indices.iter().zip(batch_texts.iter()).enumerate() {
-            if let Ok(Some(cached_embedding)) = cache.get(text, &model).await {
+            if let Ok(Some(cached_embedding)) = cache.get(text, &model_id).await {
                 result_embeddings[*global_idx] = cached_embedding;
                 _batch_hit_count += 1;
             } else {
@@ -650,7 +674,7 @@ pub async fn generate_adaptive_batch_embeddings(
         if !cache_misses.is_empty() {
             let batch_start_time = std::time::Instant::now();
             
-            match generator.generate_batch_embeddings(cache_misses.clone(), model.clone()).await {
+            match provider.embed_batch(&cache_misses, &model).await {
                 Ok(new_embeddings) => {
                     let batch_time = batch_start_time.elapsed().as_millis() as f64;
                     let throughput = new_embeddings.len() as f64 / (batch_time / 1000.0);
@@ -665,7 +689,7 @@ pub async fn generate_adaptive_batch_embeddings(
                             
                             // Cache the new embedding
                             if let Some(text) = cache_misses.get(miss_idx) {
-                                if let Err(e) = cache.set(text, &model, new_embedding).await {
+                                if let Err(e) = cache.set(text, &model_id, new_embedding).await {
                                     eprintln!("⚠️ Failed to cache embedding: {}", e);
                                 }
                             }
@@ -801,5 +825,6 @@ pub async fn cleanup_expired_embeddings() -> Result<usize, String> {
 #[tauri::command]
 pub async fn check_embedding_cached(text: String, model: String) -> Result<bool, String> {
     let cache = get_embedding_cache().await;
-    cache.contains(&text, &model).await.map_err(|e| e.to_string())
+    let model_id = get_embedding_provider().await.model_id(&model);
+    cache.contains(&text, &model_id).await.map_err(|e| e.to_string())
 }
\ No newline at end of file
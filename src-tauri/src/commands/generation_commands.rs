@@ -0,0 +1,220 @@
+//! # Text Generation Commands
+//!
+//! This module contains the Tauri commands that run LLM text generation
+//! through Ollama's `/api/generate` and `/api/chat` endpoints and forward the
+//! streamed tokens to the frontend as events.
+//!
+//! ## Command Overview
+//!
+//! - `generate_text`: Complete a single prompt
+//! - `chat_completion`: Continue a chat conversation
+//! - `cancel_generation`: Stop a running generation
+//!
+//! ## Streaming Protocol
+//!
+//! The frontend picks a `stream_id`, subscribes to `ollama-generation-token`
+//! and then invokes a generation command. Every event carries the `stream_id`
+//! so concurrent generations can be told apart. The command resolves with the
+//! complete `GenerationResponse` once the model is done, or with an error if
+//! the generation failed or was cancelled.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{watch, RwLock};
+
+use crate::globals::OLLAMA_CLIENT;
+use crate::ollama_client::{ChatRequest, GenerateRequest, GenerationResponse, OllamaClient, OllamaClientError};
+
+/// Event emitted for every streamed token
+pub const GENERATION_TOKEN_EVENT: &str = "ollama-generation-token";
+
+/// Cancellation senders of running generations, by stream ID
+type GenerationRegistry = Arc<RwLock<HashMap<String, watch::Sender<bool>>>>;
+
+/// Generations that are currently running
+static ACTIVE_GENERATIONS: Lazy<GenerationRegistry> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+
+/// Payload of `ollama-generation-token` events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationTokenEvent {
+    /// Stream ID passed to the generation command
+    pub stream_id: String,
+    /// Newly generated text
+    pub token: String,
+}
+
+/// Generate a completion for a prompt, streaming tokens as events
+///
+/// # Arguments
+/// * `stream_id` - Caller-chosen ID attached to every token event
+/// * `request` - Model, prompt, optional system prompt and sampling options
+///
+/// # Returns
+/// * `Ok(GenerationResponse)` - Full generated text and token counts
+/// * `Err(String)` - Error message if generation failed or was cancelled
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const streamId = crypto.randomUUID();
+/// const unlisten = await listen('ollama-generation-token', (event) => {
+///     if (event.payload.stream_id === streamId) output += event.payload.token;
+/// });
+/// const response = await invoke('generate_text', {
+///     streamId,
+///     request: { model: 'llama3.2', prompt: 'Summarize my note', options: { temperature: 0.7, num_ctx: 4096 } }
+/// });
+/// unlisten();
+/// ```
+#[tauri::command]
+pub async fn generate_text(
+    app: AppHandle,
+    stream_id: String,
+    request: GenerateRequest,
+) -> Result<GenerationResponse, String> {
+    let cancel = register_generation(&stream_id).await?;
+    let client = get_or_create_client().await;
+
+    let result = client
+        .generate(&request, Some(cancel), |token| emit_token(&app, &stream_id, token))
+        .await;
+
+    unregister_generation(&stream_id).await;
+    result.map_err(|e| generation_error_message(&e))
+}
+
+/// Continue a chat conversation, streaming the assistant's reply as events
+///
+/// # Arguments
+/// * `stream_id` - Caller-chosen ID attached to every token event
+/// * `request` - Model, conversation messages and sampling options
+///
+/// # Returns
+/// * `Ok(GenerationResponse)` - Full assistant reply and token counts
+/// * `Err(String)` - Error message if generation failed or was cancelled
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const response = await invoke('chat_completion', {
+///     streamId,
+///     request: {
+///         model: 'llama3.2',
+///         messages: [{ role: 'user', content: 'Suggest a title for this note' }],
+///         options: { stop: ['\n\n'] }
+///     }
+/// });
+/// ```
+#[tauri::command]
+pub async fn chat_completion(
+    app: AppHandle,
+    stream_id: String,
+    request: ChatRequest,
+) -> Result<GenerationResponse, String> {
+    let cancel = register_generation(&stream_id).await?;
+    let client = get_or_create_client().await;
+
+    let result = client
+        .chat(&request, Some(cancel), |token| emit_token(&app, &stream_id, token))
+        .await;
+
+    unregister_generation(&stream_id).await;
+    result.map_err(|e| generation_error_message(&e))
+}
+
+/// Cancel a running generation
+///
+/// The pending `generate_text` or `chat_completion` call rejects with a
+/// cancellation error; tokens already emitted are not retracted.
+///
+/// # Returns
+/// * `Ok(true)` - A running generation was cancelled
+/// * `Ok(false)` - No generation with this stream ID is running
+#[tauri::command]
+pub async fn cancel_generation(stream_id: String) -> Result<bool, String> {
+    let generations = ACTIVE_GENERATIONS.read().await;
+    match generations.get(&stream_id) {
+        Some(sender) => {
+            let _ = sender.send(true);
+            log::info!("🛑 Generation {} cancellation requested", stream_id);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+pub(crate) async fn register_generation(stream_id: &str) -> Result<watch::Receiver<bool>, String> {
+    if stream_id.trim().is_empty() {
+        return Err("Stream ID cannot be empty".to_string());
+    }
+
+    let mut generations = ACTIVE_GENERATIONS.write().await;
+    if generations.contains_key(stream_id) {
+        return Err(format!("A generation with stream ID '{}' is already running", stream_id));
+    }
+    let (sender, receiver) = watch::channel(false);
+    generations.insert(stream_id.to_string(), sender);
+    Ok(receiver)
+}
+
+pub(crate) async fn unregister_generation(stream_id: &str) {
+    ACTIVE_GENERATIONS.write().await.remove(stream_id);
+}
+
+pub(crate) async fn get_or_create_client() -> OllamaClient {
+    let client_lock = OLLAMA_CLIENT.read().await;
+    if let Some(client) = client_lock.as_ref() {
+        return client.clone();
+    }
+    drop(client_lock);
+
+    let mut client_lock = OLLAMA_CLIENT.write().await;
+    client_lock.get_or_insert_with(OllamaClient::new).clone()
+}
+
+pub(crate) fn emit_token(app: &AppHandle, stream_id: &str, token: &str) {
+    let event = GenerationTokenEvent {
+        stream_id: stream_id.to_string(),
+        token: token.to_string(),
+    };
+    if let Err(e) = app.emit(GENERATION_TOKEN_EVENT, event) {
+        log::warn!("⚠️ Failed to emit generation token for {}: {}", stream_id, e);
+    }
+}
+
+pub(crate) fn generation_error_message(error: &OllamaClientError) -> String {
+    match error {
+        OllamaClientError::Cancelled => "Generation cancelled".to_string(),
+        other => format!("Generation failed: {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_generation_registry() {
+        assert!(register_generation("  ").await.is_err());
+
+        let mut receiver = register_generation("registry-test").await.unwrap();
+        assert!(register_generation("registry-test").await.is_err());
+
+        assert!(cancel_generation("registry-test".to_string()).await.unwrap());
+        receiver.changed().await.unwrap();
+        assert!(*receiver.borrow());
+
+        unregister_generation("registry-test").await;
+        assert!(!cancel_generation("registry-test".to_string()).await.unwrap());
+        assert!(register_generation("registry-test").await.is_ok());
+        unregister_generation("registry-test").await;
+    }
+
+    #[test]
+    fn test_generation_error_message() {
+        assert_eq!(generation_error_message(&OllamaClientError::Cancelled), "Generation cancelled");
+        let error = OllamaClientError::GenerationError { message: "boom".to_string() };
+        assert!(generation_error_message(&error).contains("boom"));
+    }
+}
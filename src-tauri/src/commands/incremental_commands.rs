@@ -20,12 +20,12 @@ use crate::vector_db::{
     incremental::{IncrementalConfig, UpdateStats},
     types::VectorDbResult,
 };
-use crate::globals::VECTOR_DATABASE;
+use crate::globals::{current_vault_config, VECTOR_DATABASE};
 
 /// Request structure for enabling incremental updates
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EnableIncrementalRequest {
-    /// Configuration for the incremental update system
+    /// Configuration for the incremental update system (default: the vault config's `[incremental]`)
     pub config: Option<IncrementalConfig>,
 }
 
@@ -78,7 +78,10 @@ pub struct ProcessUpdatesResponse {
 pub async fn enable_incremental_updates(
     request: EnableIncrementalRequest,
 ) -> Result<String, String> {
-    let config = request.config.unwrap_or_default();
+    let config = match request.config {
+        Some(config) => config,
+        None => current_vault_config().await.incremental.clone(),
+    };
     
     // Get mutable reference to the vector database
     let result: VectorDbResult<()> = {
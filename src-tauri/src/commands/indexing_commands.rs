@@ -40,8 +40,9 @@ use once_cell::sync::Lazy;
 use crate::indexing_pipeline::{
     IndexingPipeline, PipelineConfig, IndexingProgress, IndexingPriority, IndexingError
 };
-use crate::text_chunker::{ChunkProcessor, ChunkConfig};
-use crate::globals::get_embedding_generator;
+use crate::text_chunker::ChunkProcessor;
+use crate::globals::{current_vault_config, embedding_context_length, get_cached_embedding_provider, open_vault_config};
+use crate::vault_config::VaultConfig;
 
 /// Global indexing pipeline instance for managing vault indexing operations
 /// 
@@ -91,13 +92,12 @@ async fn get_indexing_pipeline() -> Result<Arc<IndexingPipeline>, String> {
             log::warn!("⚠️ Full indexing functionality requires vector database architecture refactoring");
             
             // Initialize dependencies
-            let embedding_generator = Arc::new(get_embedding_generator().await);
+            let embedding_provider = get_cached_embedding_provider().await;
             
-            // Create text chunker with default config
-            let chunk_config = ChunkConfig::default();
-            let chunk_processor = Arc::new(ChunkProcessor::new(chunk_config).map_err(|e| {
-                format!("Failed to create chunk processor: {}", e)
-            })?);
+            // Create text chunker sized for the embedding model's context
+            let vault_config = current_vault_config().await;
+            let config = vault_config.pipeline.clone();
+            let chunk_processor = Arc::new(chunk_processor_for_vault(&vault_config).await?);
             
             // Create a minimal vector database for compatibility
             // TODO: Replace with proper shared database integration
@@ -110,11 +110,10 @@ async fn get_indexing_pipeline() -> Result<Arc<IndexingPipeline>, String> {
             );
             
             // Create pipeline with default configuration
-            let config = PipelineConfig::default();
             let pipeline = Arc::new(IndexingPipeline::new(
                 config,
                 chunk_processor,
-                embedding_generator,
+                embedding_provider,
                 temp_vector_db,
             ));
             
@@ -125,6 +124,24 @@ async fn get_indexing_pipeline() -> Result<Arc<IndexingPipeline>, String> {
     }
 }
 
+/// Chunk processor whose chunks fit the embedding model's context length
+async fn chunk_processor_for_model(model_name: &str) -> Result<ChunkProcessor, String> {
+    ChunkProcessor::for_embedding_model(model_name, embedding_context_length(model_name).await)
+        .map_err(|e| format!("Failed to create chunk processor: {}", e))
+}
+
+/// Chunk processor for the vault's embedding model with the vault's chunking settings applied
+async fn chunk_processor_for_vault(vault_config: &VaultConfig) -> Result<ChunkProcessor, String> {
+    let mut processor = chunk_processor_for_model(&vault_config.pipeline.embedding_model).await?;
+    if vault_config.has_section("chunking") {
+        let config = vault_config.chunk_config_over(processor.config().clone());
+        processor
+            .set_config(config)
+            .map_err(|e| format!("Invalid [chunking] settings in the vault config: {}", e))?;
+    }
+    Ok(processor)
+}
+
 /// Start indexing an entire vault with comprehensive progress tracking
 ///
 /// This command initiates bulk indexing of all markdown files in the specified vault
@@ -140,6 +157,15 @@ async fn get_indexing_pipeline() -> Result<Arc<IndexingPipeline>, String> {
 /// * `Ok(request_ids)` - Vector of request IDs for tracking individual files
 /// * `Err(String)` - User-friendly error message describing the failure
 ///
+/// # Resuming
+/// Notes whose embeddings are up to date according to `{vault}/.ainote/index_manifest.json`
+/// are not queued, so re-running the command after a crash or restart only indexes new
+/// and changed notes. Embeddings of notes deleted since the last run are removed.
+///
+/// # Vault Config
+/// Pipeline and chunking settings come from `{vault}/.ainote/config.toml`, which is
+/// (re)loaded first. If it is invalid, the vault's last valid settings are used.
+///
 /// # Progress Tracking
 /// The command immediately returns request IDs, but indexing continues in the background.
 /// Use `get_indexing_progress()` to monitor progress and completion status.
@@ -199,11 +225,16 @@ pub async fn index_vault_notes(
     };
     
     // Initialize dependencies for vault-specific pipeline
-    let embedding_generator = Arc::new(get_embedding_generator().await);
-    let chunk_config = ChunkConfig::default();
-    let chunk_processor = Arc::new(ChunkProcessor::new(chunk_config).map_err(|e| {
-        format!("Failed to create chunk processor: {}", e)
-    })?);
+    let vault_settings = match open_vault_config(&vault_path).await {
+        Ok(vault_settings) => vault_settings,
+        Err(e) => {
+            log::warn!("⚠️ Indexing with the last valid vault settings: {}", e);
+            current_vault_config().await
+        }
+    };
+    let embedding_provider = get_cached_embedding_provider().await;
+    let config = vault_settings.pipeline.clone();
+    let chunk_processor = Arc::new(chunk_processor_for_vault(&vault_settings).await?);
     
     // Create vault-specific vector database
     let vault_vector_db = Arc::new(
@@ -213,11 +244,10 @@ pub async fn index_vault_notes(
     );
     
     // Create temporary pipeline for this vault
-    let config = PipelineConfig::default();
     let pipeline = Arc::new(IndexingPipeline::new(
         config,
         chunk_processor,
-        embedding_generator,
+        embedding_provider,
         vault_vector_db,
     ));
     
@@ -0,0 +1,207 @@
+//! # Link Graph Commands
+//!
+//! This module contains the commands that query the vault's link graph:
+//! the `[[wikilinks]]` and relative markdown links between notes.
+//!
+//! ## Command Overview
+//!
+//! - `get_outgoing_links`: Links of a note with their resolved targets
+//! - `get_backlinks`: Notes linking to a note
+//! - `get_unresolved_links`: Links pointing at notes that do not exist
+//! - `get_orphan_notes`: Notes without links to or from other notes
+//! - `get_link_graph_stats`: Note, link, unresolved-link and orphan counts
+//! - `rebuild_link_graph`: Re-parse every note of the vault
+//! - `rename_note`: Move a note or folder, rewriting links and moving embeddings
+//!
+//! ## Vault Selection
+//!
+//! Every command accepts an optional `vault_path`. When given, the vault's
+//! graph is opened (or reused if it is already open); otherwise the graph of
+//! the vault currently watched by the file monitor is used.
+
+use std::path::Path;
+
+use crate::globals::{open_vault_link_graph, open_vault_vector_database, LINK_GRAPH, VECTOR_DATABASE};
+use crate::link_graph::{Backlink, LinkGraph, LinkGraphStats, OutgoingLink};
+use crate::note_rename::{RenamePlan, RenameSummary};
+
+/// Get the links going out of a note
+///
+/// # Arguments
+/// * `file_path` - Note path, absolute or relative to the vault
+/// * `vault_path` - Vault to open, defaults to the open vault
+///
+/// # Returns
+/// * `Ok(Vec<OutgoingLink>)` - Links in document order; `target_path` is `null` for unresolved links
+/// * `Err(String)` - Error message if no vault is open or the note is outside it
+#[tauri::command]
+pub async fn get_outgoing_links(file_path: String, vault_path: Option<String>) -> Result<Vec<OutgoingLink>, String> {
+    with_link_graph(vault_path, |graph| {
+        graph.outgoing_links(Path::new(&file_path)).map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// Get the links from other notes pointing at a note
+///
+/// # Arguments
+/// * `file_path` - Note path, absolute or relative to the vault
+/// * `vault_path` - Vault to open, defaults to the open vault
+///
+/// # Returns
+/// * `Ok(Vec<Backlink>)` - Linking notes with the link text and position, sorted by path
+/// * `Err(String)` - Error message if no vault is open or the note is outside it
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const backlinks = await invoke('get_backlinks', { filePath: '/vault/Projects/Alpha.md' });
+/// for (const link of backlinks) {
+///     console.log(link.source_path, link.display_text ?? link.target);
+/// }
+/// ```
+#[tauri::command]
+pub async fn get_backlinks(file_path: String, vault_path: Option<String>) -> Result<Vec<Backlink>, String> {
+    with_link_graph(vault_path, |graph| {
+        graph.backlinks(Path::new(&file_path)).map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// Get all links whose target note does not exist
+///
+/// # Returns
+/// * `Ok(Vec<Backlink>)` - Unresolved links with the note containing them
+/// * `Err(String)` - Error message if no vault is open
+#[tauri::command]
+pub async fn get_unresolved_links(vault_path: Option<String>) -> Result<Vec<Backlink>, String> {
+    with_link_graph(vault_path, |graph| Ok(graph.unresolved_links())).await
+}
+
+/// Get notes that neither link to nor are linked from another note
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - Paths of orphan notes, sorted
+/// * `Err(String)` - Error message if no vault is open
+#[tauri::command]
+pub async fn get_orphan_notes(vault_path: Option<String>) -> Result<Vec<String>, String> {
+    with_link_graph(vault_path, |graph| Ok(graph.orphan_notes())).await
+}
+
+/// Get note, link, unresolved-link and orphan counts
+#[tauri::command]
+pub async fn get_link_graph_stats(vault_path: Option<String>) -> Result<LinkGraphStats, String> {
+    with_link_graph(vault_path, |graph| Ok(graph.stats())).await
+}
+
+/// Re-parse every note of a vault and save the graph
+///
+/// Only needed when the graph got out of sync, e.g. after notes were
+/// changed while the file monitor was not running.
+///
+/// # Returns
+/// * `Ok(LinkGraphStats)` - Counts after the rebuild
+/// * `Err(String)` - Error message if the vault cannot be scanned
+#[tauri::command]
+pub async fn rebuild_link_graph(vault_path: String) -> Result<LinkGraphStats, String> {
+    open_vault_link_graph(&vault_path).await?;
+
+    let mut graph_lock = LINK_GRAPH.write().await;
+    let graph = graph_lock
+        .as_mut()
+        .ok_or_else(|| "Link graph not initialized".to_string())?;
+    graph.rebuild().map_err(|e| format!("Failed to rebuild link graph: {}", e))?;
+    graph.save().map_err(|e| format!("Failed to save link graph: {}", e))?;
+
+    let stats = graph.stats();
+    log::info!("🔗 Rebuilt link graph: {} notes, {} links, {} unresolved",
+               stats.note_count, stats.link_count, stats.unresolved_link_count);
+    Ok(stats)
+}
+
+/// Rename or move a note or folder without breaking links or embeddings
+///
+/// Links to the moved notes are rewritten across the vault (keeping headings
+/// and aliases), relative links inside moved notes are adjusted, and the
+/// notes' embeddings are re-keyed to their new paths without re-embedding.
+/// If the embeddings cannot be moved, the files are restored and moved back.
+///
+/// # Arguments
+/// * `vault_path` - Vault containing the note
+/// * `old_path` - Current path of the note or folder
+/// * `new_path` - New path of the note or folder
+///
+/// # Returns
+/// * `Ok(RenameSummary)` - Moved notes, rewritten files and counts
+/// * `Err(String)` - Error message if nothing was renamed
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const summary = await invoke('rename_note', {
+///     vaultPath: '/vault',
+///     oldPath: '/vault/Projects',
+///     newPath: '/vault/Archive/Projects'
+/// });
+/// console.log(summary.moved_notes.length, summary.rewritten_links);
+/// ```
+#[tauri::command]
+pub async fn rename_note(vault_path: String, old_path: String, new_path: String) -> Result<RenameSummary, String> {
+    open_vault_link_graph(&vault_path).await?;
+
+    // Hold the graph for the whole rename so file monitor updates wait for it
+    let mut graph_lock = LINK_GRAPH.write().await;
+    let graph = graph_lock
+        .as_mut()
+        .ok_or_else(|| "Link graph not initialized".to_string())?;
+    graph.refresh().map_err(|e| format!("Failed to refresh link graph: {}", e))?;
+
+    let applied = RenamePlan::new(graph, Path::new(&old_path), Path::new(&new_path))
+        .and_then(RenamePlan::apply)
+        .map_err(|e| e.to_string())?;
+
+    let moves: Vec<(String, String)> = applied
+        .moves()
+        .iter()
+        .map(|note| (note.old_path.clone(), note.new_path.clone()))
+        .collect();
+    let migrated_embeddings = match move_embeddings(&vault_path, &moves).await {
+        Ok(count) => count,
+        Err(e) => {
+            if let Err(rollback_error) = applied.rollback() {
+                log::error!("❌ Failed to roll back rename of {}: {}", old_path, rollback_error);
+            }
+            return Err(format!("Failed to move embeddings, rename undone: {}", e));
+        }
+    };
+
+    // The rename is complete; a stale graph only costs a re-parse on next open
+    if let Err(e) = graph.update_notes(&applied.changed_paths()).and_then(|_| graph.save()) {
+        log::warn!("⚠️ Failed to update link graph after rename: {}", e);
+    }
+
+    Ok(applied.into_summary(migrated_embeddings))
+}
+
+async fn move_embeddings(vault_path: &str, moves: &[(String, String)]) -> Result<usize, String> {
+    open_vault_vector_database(vault_path).await?;
+
+    let db_guard = VECTOR_DATABASE.read().await;
+    let database = db_guard
+        .as_ref()
+        .ok_or_else(|| "Vector database not initialized".to_string())?;
+    database.move_file_embeddings(moves).await.map_err(|e| e.to_string())
+}
+
+async fn with_link_graph<T>(
+    vault_path: Option<String>,
+    query: impl FnOnce(&LinkGraph) -> Result<T, String>,
+) -> Result<T, String> {
+    if let Some(vault_path) = &vault_path {
+        open_vault_link_graph(vault_path).await?;
+    }
+
+    let graph_lock = LINK_GRAPH.read().await;
+    let graph = graph_lock
+        .as_ref()
+        .ok_or_else(|| "Link graph not initialized. Open a vault or pass vault_path.".to_string())?;
+    query(graph)
+}
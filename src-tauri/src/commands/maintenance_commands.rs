@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 
 use crate::vector_db::maintenance::{MaintenanceConfig, MaintenanceStats};
-use crate::globals::VECTOR_DATABASE;
+use crate::globals::{current_vault_config, VECTOR_DATABASE};
 
 /// Configuration request for enabling maintenance
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,9 +37,14 @@ pub struct EnableMaintenanceRequest {
 impl EnableMaintenanceRequest {
     /// Convert request to MaintenanceConfig with defaults
     pub fn to_config(&self) -> MaintenanceConfig {
+        self.to_config_over(MaintenanceConfig::default())
+    }
+    
+    /// Convert request to MaintenanceConfig, taking unset values from `base`
+    pub fn to_config_over(&self, base: MaintenanceConfig) -> MaintenanceConfig {
         let mut config = MaintenanceConfig {
             enable_automatic_maintenance: self.enable_automatic_maintenance,
-            ..Default::default()
+            ..base
         };
         
         if let Some(interval) = self.maintenance_interval_seconds {
@@ -126,7 +131,8 @@ pub async fn enable_database_maintenance(
 ) -> Result<MaintenanceResponse, String> {
     eprintln!("🔧 Enable database maintenance request: {:?}", request);
     
-    let config = request.to_config();
+    // Settings the request leaves out come from the vault config
+    let config = request.to_config_over(current_vault_config().await.maintenance.clone());
     
     // Get mutable reference to the vector database
     let mut db_guard = VECTOR_DATABASE.write().await;
@@ -23,8 +23,17 @@
 //!
 //! ### AI Integration
 //! - `ollama_commands`: Ollama client management and model operations
+//! - `generation_commands`: Streaming text generation and chat with Ollama models
 //! - `embedding_commands`: Embedding generation, caching, and configuration
 //! - `search_commands`: Similarity search and vector operations
+//! - `semantic_search_commands`: Text-query semantic search grouped by file
+//! - `vault_search_commands`: Keyword and regex search with line context, vault-wide find-and-replace
+//! - `rag_commands`: Question answering over the vault with cited sources
+//! - `related_notes_commands`: Notes related to a whole note, scored per note from its chunks
+//!
+//! ### Note Graph
+//! - `link_graph_commands`: Wikilinks, backlinks, unresolved links, orphan notes and link-aware renames
+//! - `model_namespace_commands`: Embedding model namespaces and vault re-embedding
 //!
 //! ### Performance & Monitoring
 //! - `performance_commands`: Benchmarking, baseline management, regression detection
@@ -78,6 +87,10 @@ pub mod text_processing;
 // Handles: Ollama client management, health checks, model operations, and monitoring
 pub mod ollama_commands;
 
+// Generation Commands Module
+// Handles: streaming LLM text generation and chat, token events, and cancellation
+pub mod generation_commands;
+
 // Embedding Commands Module
 // Handles: embedding generation, batch processing, caching, and configuration
 pub mod embedding_commands;
@@ -94,6 +107,30 @@ pub mod performance_commands;
 // Handles: similarity search operations and vector database queries
 pub mod search_commands;
 
+// Semantic Search Commands Module
+// Handles: text-query semantic search with backend query embedding and per-file result grouping
+pub mod semantic_search_commands;
+
+// Related Notes Commands Module
+// Handles: multi-query related note search with per-note score aggregation and suggestion caching
+pub mod related_notes_commands;
+
+// Vault Search Commands Module
+// Handles: grep-style keyword/regex search across notes, match context, find-and-replace, and cancellation
+pub mod vault_search_commands;
+
+// RAG Commands Module
+// Handles: retrieval-augmented question answering over the vault with streamed answers and citations
+pub mod rag_commands;
+
+// Link Graph Commands Module
+// Handles: wikilink and markdown link graph queries, backlinks, unresolved links, orphan notes, and link-aware rename/move
+pub mod link_graph_commands;
+
+// Model Namespace Commands Module
+// Handles: per-model embedding namespaces, active model switching, and background vault re-embedding
+pub mod model_namespace_commands;
+
 // Incremental Commands Module
 // Handles: incremental update system, file change monitoring, and automatic embedding updates
 pub mod incremental_commands;
@@ -136,10 +173,17 @@ pub use vault_operations::*;
 pub use state_management::*;
 pub use text_processing::*;
 pub use ollama_commands::*;
+pub use generation_commands::*;
 pub use embedding_commands::*;
 pub use embedding_queue_commands::*;
 pub use performance_commands::*;
 pub use search_commands::*;
+pub use semantic_search_commands::*;
+pub use related_notes_commands::*;
+pub use vault_search_commands::*;
+pub use rag_commands::*;
+pub use link_graph_commands::*;
+pub use model_namespace_commands::*;
 pub use incremental_commands::*;
 pub use maintenance_commands::*;
 pub use rebuilding_commands::*;
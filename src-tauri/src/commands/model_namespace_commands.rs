@@ -0,0 +1,206 @@
+//! # Model Namespace Commands
+//!
+//! This module contains the Tauri commands for managing embedding model
+//! namespaces in a vault's vector database and for migrating a vault to a
+//! different embedding model.
+//!
+//! ## Command Overview
+//!
+//! - `get_model_namespaces`: List the models with stored embeddings and the active one
+//! - `switch_embedding_model`: Activate a namespace that is already fully embedded
+//! - `delete_model_namespace`: Delete the embeddings of an inactive model
+//! - `start_model_migration`: Re-embed the vault with another model in the background
+//! - `get_model_migration_status`: Get progress of the current or last migration
+//! - `cancel_model_migration`: Stop a running migration, keeping the previous model active
+//!
+//! ## Migration Flow
+//!
+//! A migration builds the target model's namespace while the current one keeps
+//! serving searches, then switches the active model atomically. Starting the
+//! same migration again after a cancellation or failure skips files that were
+//! already embedded with the target model.
+
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::globals::{get_cached_embedding_provider, open_vault_vector_database, VECTOR_DATABASE};
+use crate::model_migration::{MigrationRequest, MigrationStatus, ModelMigration};
+use crate::vector_db::model_namespace::ModelNamespaceStats;
+use crate::vector_db::VectorDatabase;
+
+/// Global model migration job shared by the migration commands
+pub static MODEL_MIGRATION: Lazy<Arc<ModelMigration>> = Lazy::new(|| Arc::new(ModelMigration::new()));
+
+/// Response of the `get_model_namespaces` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelNamespacesResponse {
+    /// Model whose embeddings are searched
+    pub active_model: Option<String>,
+    /// Namespaces present in the vault, active namespace first
+    pub namespaces: Vec<ModelNamespaceStats>,
+}
+
+/// List the embedding model namespaces of a vault
+///
+/// # Arguments
+/// * `vault_path` - Vault whose vector database is inspected
+///
+/// # Returns
+/// * `Ok(ModelNamespacesResponse)` - Active model and per-model entry counts
+/// * `Err(String)` - Error message if the database cannot be opened or read
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const { active_model, namespaces } = await invoke('get_model_namespaces', {
+///     vaultPath: '/path/to/vault'
+/// });
+/// ```
+#[tauri::command]
+pub async fn get_model_namespaces(vault_path: String) -> Result<ModelNamespacesResponse, String> {
+    open_vault_vector_database(&vault_path).await?;
+
+    let db_guard = VECTOR_DATABASE.read().await;
+    let database = vault_database(db_guard.as_ref())?;
+    let namespaces = database
+        .list_model_namespaces()
+        .await
+        .map_err(|e| format!("Failed to list model namespaces: {}", e))?;
+
+    Ok(ModelNamespacesResponse {
+        active_model: database.active_model().await,
+        namespaces,
+    })
+}
+
+/// Make an already embedded model the one used for search
+///
+/// Use `start_model_migration` to embed the vault with a new model first;
+/// this command only switches between namespaces that already exist.
+///
+/// # Arguments
+/// * `vault_path` - Vault whose vector database is updated
+/// * `model` - Model namespace to activate, as listed by `get_model_namespaces`
+///
+/// # Returns
+/// * `Ok(usize)` - Number of vectors indexed for the activated model
+/// * `Err(String)` - Error message if the model has no embeddings or a migration is running
+#[tauri::command]
+pub async fn switch_embedding_model(vault_path: String, model: String) -> Result<usize, String> {
+    if MODEL_MIGRATION.status().await.is_running() {
+        return Err("Cannot switch models while a model migration is running".to_string());
+    }
+    open_vault_vector_database(&vault_path).await?;
+
+    let db_guard = VECTOR_DATABASE.read().await;
+    vault_database(db_guard.as_ref())?
+        .activate_model_namespace(&model)
+        .await
+        .map_err(|e| format!("Failed to switch embedding model: {}", e))
+}
+
+/// Delete the embeddings of an inactive model
+///
+/// # Arguments
+/// * `vault_path` - Vault whose vector database is updated
+/// * `model` - Model whose embeddings are deleted (must not be active)
+///
+/// # Returns
+/// * `Ok(usize)` - Number of embeddings deleted
+/// * `Err(String)` - Error message if the model is active or deletion fails
+#[tauri::command]
+pub async fn delete_model_namespace(vault_path: String, model: String) -> Result<usize, String> {
+    if MODEL_MIGRATION.status().await.is_running() {
+        return Err("Cannot delete model embeddings while a model migration is running".to_string());
+    }
+    open_vault_vector_database(&vault_path).await?;
+
+    let db_guard = VECTOR_DATABASE.read().await;
+    vault_database(db_guard.as_ref())?
+        .delete_model_namespace(&model)
+        .await
+        .map_err(|e| format!("Failed to delete model namespace: {}", e))
+}
+
+/// Re-embed a vault with another embedding model in the background
+///
+/// The command returns as soon as the migration has started. Searches keep
+/// using the current model until every file has been embedded with the target
+/// model, then the vault switches over atomically.
+///
+/// # Arguments
+/// * `vault_path` - Vault to migrate
+/// * `target_model` - Embedding model to migrate to
+/// * `drop_previous` - Delete the previous model's embeddings after switching (default: false)
+///
+/// # Returns
+/// * `Ok(MigrationStatus)` - Initial status of the started migration
+/// * `Err(String)` - Error message if a migration is running or the model is already active
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// await invoke('start_model_migration', {
+///     vaultPath: '/path/to/vault',
+///     targetModel: 'mxbai-embed-large',
+///     dropPrevious: true
+/// });
+/// const status = await invoke('get_model_migration_status');
+/// console.log(`${status.phase}: ${status.processed_files}/${status.total_files}`);
+/// ```
+#[tauri::command]
+pub async fn start_model_migration(
+    vault_path: String,
+    target_model: String,
+    drop_previous: Option<bool>,
+) -> Result<MigrationStatus, String> {
+    let request = MigrationRequest {
+        vault_path,
+        target_model: target_model.trim().to_string(),
+        drop_previous: drop_previous.unwrap_or(false),
+    };
+    let embedding_provider = get_cached_embedding_provider().await;
+
+    MODEL_MIGRATION.start(request, embedding_provider).await
+}
+
+/// Get the progress of the current (or last) model migration
+#[tauri::command]
+pub async fn get_model_migration_status() -> Result<MigrationStatus, String> {
+    Ok(MODEL_MIGRATION.status().await)
+}
+
+/// Cancel a running model migration
+///
+/// Embeddings already stored for the target model are kept, so starting the
+/// migration again continues where it stopped. The previous model stays active.
+#[tauri::command]
+pub async fn cancel_model_migration() -> Result<(), String> {
+    if MODEL_MIGRATION.status().await.is_running() {
+        MODEL_MIGRATION.cancel();
+        log::info!("🛑 Model migration cancellation requested");
+    }
+    Ok(())
+}
+
+fn vault_database(database: Option<&VectorDatabase>) -> Result<&VectorDatabase, String> {
+    database.ok_or_else(|| "Vector database not initialized".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_migration::MigrationPhase;
+
+    #[tokio::test]
+    async fn test_migration_status_starts_idle() {
+        let status = get_model_migration_status().await.unwrap();
+        assert_eq!(status.phase, MigrationPhase::Idle);
+        assert!(cancel_model_migration().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_start_model_migration_rejects_empty_model() {
+        let result = start_model_migration("/nonexistent/vault".to_string(), "  ".to_string(), None).await;
+        assert!(result.is_err());
+    }
+}
@@ -6,6 +6,7 @@
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
+use crate::globals::OPTIMIZATION_SCHEDULER;
 use crate::vector_db::optimization_scheduler::{
     OptimizationSchedulerConfig, OptimizationTrigger, OptimizationStatus,
     OptimizationPipelineResult, OptimizationResourceUsage, OptimizationPerformanceImprovement,
@@ -231,12 +232,15 @@ pub async fn configure_optimization_scheduling(
 pub async fn start_optimization_scheduler() -> Result<OptimizationResponse, String> {
     eprintln!("🚀 Starting optimization scheduler...");
     
-    // In a real implementation, this would:
-    // 1. Get the global optimization scheduler
-    // 2. Call start() method
-    // 3. Return success/error based on result
+    let mut scheduler_lock = OPTIMIZATION_SCHEDULER.write().await;
+    let Some(scheduler) = scheduler_lock.as_mut() else {
+        return Ok(OptimizationResponse::error("No vault is open"));
+    };
     
-    Ok(OptimizationResponse::success("Optimization scheduler started successfully"))
+    match scheduler.start().await {
+        Ok(()) => Ok(OptimizationResponse::success("Optimization scheduler started successfully")),
+        Err(e) => Ok(OptimizationResponse::error(format!("Failed to start optimization scheduler: {}", e))),
+    }
 }
 
 /// Stop automatic optimization scheduling
@@ -244,12 +248,15 @@ pub async fn start_optimization_scheduler() -> Result<OptimizationResponse, Stri
 pub async fn stop_optimization_scheduler() -> Result<OptimizationResponse, String> {
     eprintln!("⏹️ Stopping optimization scheduler...");
     
-    // In a real implementation, this would:
-    // 1. Get the global optimization scheduler
-    // 2. Call stop() method
-    // 3. Return success/error based on result
+    let mut scheduler_lock = OPTIMIZATION_SCHEDULER.write().await;
+    let Some(scheduler) = scheduler_lock.as_mut() else {
+        return Ok(OptimizationResponse::error("No vault is open"));
+    };
     
-    Ok(OptimizationResponse::success("Optimization scheduler stopped successfully"))
+    match scheduler.stop().await {
+        Ok(()) => Ok(OptimizationResponse::success("Optimization scheduler stopped successfully")),
+        Err(e) => Ok(OptimizationResponse::error(format!("Failed to stop optimization scheduler: {}", e))),
+    }
 }
 
 /// Manually trigger an optimization
@@ -284,6 +291,7 @@ pub async fn trigger_manual_optimization() -> Result<OptimizationResponse, Strin
             compression_ratio: 0.4,
             compression_time_ms: 800.0,
         }),
+        codebook_retraining_result: None,
         maintenance_result: Some(MaintenanceResult {
             orphaned_embeddings_removed: 7,
             storage_space_reclaimed: 1048576, // 1MB
@@ -0,0 +1,195 @@
+//! # RAG Commands
+//!
+//! This module contains the command that answers questions about the vault
+//! with retrieval-augmented generation: the question is embedded, the most
+//! similar note chunks are retrieved and packed into the prompt, and an
+//! Ollama chat model writes an answer that cites them.
+//!
+//! ## Command Overview
+//!
+//! - `ask_notes`: Answer a question from the vault's notes, streaming the answer
+//!
+//! ## Streaming Protocol
+//!
+//! `ask_notes` streams like `chat_completion`: tokens arrive as
+//! `ollama-generation-token` events carrying the caller's `stream_id`, and
+//! `cancel_generation` with the same `stream_id` stops the answer. The
+//! command resolves with the full answer and its citations.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use tauri::AppHandle;
+
+use crate::commands::embedding_commands::generate_embedding;
+use crate::commands::generation_commands::{
+    emit_token, generation_error_message, get_or_create_client, register_generation, unregister_generation,
+};
+use crate::commands::semantic_search_commands::{query_embedding_model, retrieve_hits};
+use crate::globals::{current_vault_config, open_vault_vector_database, VECTOR_DATABASE};
+use crate::ollama_client::ChatRequest;
+use crate::rag::{
+    build_citations, build_messages, estimate_tokens, load_chunk_text, pack_context, RagAnswer, RagOptions,
+    NO_CONTEXT_ANSWER,
+};
+use crate::similarity_search::{expand_hits_to_parents, SearchConfig, SIMILARITY_THRESHOLD_RANGE};
+
+/// Answer a question from the vault's notes, streaming tokens as events
+///
+/// The question is embedded with the vault's active embedding model and the
+/// top-k chunks are retrieved the way `semantic_search` ranks them: BM25
+/// matches fused with the HNSW index. Chunks are packed into the prompt in
+/// rank order, limited per file and by the
+/// token budget, and numbered so the model can cite them as `[n]`. When no
+/// chunk is relevant enough, the command answers without calling the model.
+///
+/// # Arguments
+/// * `stream_id` - Caller-chosen ID attached to every token event
+/// * `question` - Natural language question about the notes
+/// * `options` - Optional vault, models, retrieval limits and sampling options
+///
+/// # Returns
+/// * `Ok(RagAnswer)` - Answer with citations (file path and chunk byte span)
+/// * `Err(String)` - Error message if retrieval or generation failed or was cancelled
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const streamId = crypto.randomUUID();
+/// const unlisten = await listen('ollama-generation-token', (event) => {
+///     if (event.payload.stream_id === streamId) answer += event.payload.token;
+/// });
+/// const result = await invoke('ask_notes', {
+///     streamId,
+///     question: 'Where do my backups go?',
+///     options: { vault_path: '/path/to/vault', chat_model: 'llama3.2', max_context_tokens: 3000 }
+/// });
+/// unlisten();
+/// for (const citation of result.citations.filter(c => c.cited)) {
+///     console.log(citation.index, citation.file_path, citation.start_position);
+/// }
+/// ```
+#[tauri::command]
+pub async fn ask_notes(
+    app: AppHandle,
+    stream_id: String,
+    question: String,
+    options: Option<RagOptions>,
+) -> Result<RagAnswer, String> {
+    let options = options.unwrap_or_default();
+
+    let trimmed_question = question.trim();
+    if trimmed_question.is_empty() {
+        return Err("Question cannot be empty".to_string());
+    }
+    if options.chat_model.trim().is_empty() {
+        return Err("Chat model cannot be empty".to_string());
+    }
+    if let Some(min_score) = options.min_score.filter(|min_score| !SIMILARITY_THRESHOLD_RANGE.contains(min_score)) {
+        return Err(format!("min_score must be between -1.0 and 1.0, got {}", min_score));
+    }
+
+    // Register before retrieval so the question can be cancelled at any point
+    let cancel = register_generation(&stream_id).await?;
+    let result = answer_question(&app, &stream_id, trimmed_question, &options, cancel).await;
+    unregister_generation(&stream_id).await;
+
+    result.map(|answer| RagAnswer { question, ..answer })
+}
+
+async fn answer_question(
+    app: &AppHandle,
+    stream_id: &str,
+    question: &str,
+    options: &RagOptions,
+    cancel: tokio::sync::watch::Receiver<bool>,
+) -> Result<RagAnswer, String> {
+    let retrieval_start = Instant::now();
+    if let Some(vault_path) = &options.vault_path {
+        open_vault_vector_database(vault_path).await?;
+    }
+
+    let embedding_model = query_embedding_model(options.embedding_model.as_deref()).await?;
+    let query_vector = generate_embedding(question.to_string(), embedding_model.clone()).await?;
+
+    // Retrieve like semantic_search: hybrid ranking over the HNSW index
+    let search_config = search_config(options, &current_vault_config().await.search);
+    let hits = if options.top_k == 0 {
+        Vec::new()
+    } else {
+        let db_guard = VECTOR_DATABASE.read().await;
+        let database = db_guard
+            .as_ref()
+            .ok_or_else(|| "Vector database not initialized. Open a vault or pass vault_path.".to_string())?;
+        retrieve_hits(database, question, &query_vector, options.top_k, &search_config, true, true)
+            .await
+            .map_err(|e| format!("Retrieval failed: {}", e))?
+            .0
+    };
+
+    let hits = match options.expand_to {
+        Some(level) => expand_hits_to_parents(hits, level),
+        None => hits,
+    };
+
+    let mut notes = HashMap::new();
+    let context = pack_context(hits, options.max_chunks_per_file, options.max_context_tokens, |entry| {
+        load_chunk_text(entry, &mut notes)
+    });
+    let context_tokens = context.iter().map(|chunk| estimate_tokens(&chunk.text)).sum();
+    let retrieval_time_ms = retrieval_start.elapsed().as_secs_f64() * 1000.0;
+    log::info!("📚 Retrieved {} chunks (~{} tokens) for question in {:.1}ms",
+               context.len(), context_tokens, retrieval_time_ms);
+
+    if context.is_empty() {
+        emit_token(app, stream_id, NO_CONTEXT_ANSWER);
+        return Ok(RagAnswer {
+            question: question.to_string(),
+            answer: NO_CONTEXT_ANSWER.to_string(),
+            citations: Vec::new(),
+            model: options.chat_model.clone(),
+            embedding_model,
+            context_tokens: 0,
+            retrieval_time_ms,
+            generation_time_ms: 0.0,
+        });
+    }
+
+    let request = ChatRequest {
+        model: options.chat_model.clone(),
+        messages: build_messages(question, &context),
+        options: options.generation.clone(),
+    };
+    let generation_start = Instant::now();
+    let response = get_or_create_client()
+        .await
+        .chat(&request, Some(cancel), |token| emit_token(app, stream_id, token))
+        .await
+        .map_err(|e| generation_error_message(&e))?;
+
+    Ok(RagAnswer {
+        question: question.to_string(),
+        citations: build_citations(&response.content, &context),
+        answer: response.content,
+        model: response.model,
+        embedding_model,
+        context_tokens,
+        retrieval_time_ms,
+        generation_time_ms: generation_start.elapsed().as_secs_f64() * 1000.0,
+    })
+}
+
+/// Search configuration for retrieval
+///
+/// A `min_score` left unset falls back to the vault config's `[search]` section.
+fn search_config(options: &RagOptions, vault_search: &SearchConfig) -> SearchConfig {
+    SearchConfig {
+        min_threshold: options.min_score.unwrap_or(vault_search.min_threshold),
+        max_results: 0,
+        normalize_query: false,
+        enable_diversity_filter: false,
+        filter: match &options.vault_path {
+            Some(vault_path) => options.filter.clone().with_vault_root(vault_path),
+            None => options.filter.clone(),
+        },
+        ..vault_search.clone()
+    }
+}
@@ -0,0 +1,411 @@
+//! # Related Notes Commands
+//!
+//! This module contains the related notes command, which ranks the vault's
+//! notes by how related they are to a whole note rather than to a text query.
+//! The frontend no longer has to merge chunk-level `SearchResult`s itself: the
+//! note's own chunk embeddings are the queries, and scores are aggregated per
+//! target note on the backend.
+//!
+//! ## Command Overview
+//!
+//! - `related_notes`: Rank the notes most related to a note
+//!
+//! ## Search Pipeline
+//!
+//! 1. **Database Selection**: Open the vault's vector database if a vault path is given
+//! 2. **Source Chunks**: Load the note's embeddings with `find_embeddings_by_file_indexed`,
+//!    keeping those of the active embedding model
+//! 3. **Cache Lookup**: Return the cached ranking from `SuggestionCache` when the note's
+//!    indexed chunks and the options are unchanged
+//! 4. **Multi-Query Search**: Run one HNSW search per source chunk, excluding the note itself,
+//!    or an exact scan of the active namespace while the index is empty
+//! 5. **Aggregation**: Combine chunk scores per note (max, mean or top-n sum), then apply
+//!    diversity filtering to the note ranking
+//! 6. **Caching**: Store the ranking in `SuggestionCache`
+//!
+//! No embedding is generated: a note that has not been indexed yet has no
+//! related notes.
+
+use std::time::Instant;
+use serde::{Serialize, Deserialize};
+
+use crate::globals::{current_vault_config, get_suggestion_cache, open_vault_vector_database, VECTOR_DATABASE};
+use crate::similarity_search::{ScoreAggregation, SearchConfig, SearchResult, SimilaritySearch, SIMILARITY_THRESHOLD_RANGE};
+use crate::suggestion_cache::SuggestionContext;
+use crate::vector_db::types::EmbeddingEntry;
+use crate::vector_db::VectorDatabase;
+
+/// Options for the `related_notes` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelatedNotesOptions {
+    /// Vault to search; opens the vault's vector database when provided
+    pub vault_path: Option<String>,
+    /// How chunk scores are combined into a note score
+    pub aggregation: ScoreAggregation,
+    /// Minimum similarity for a chunk hit to count (-1.0 to 1.0); defaults to
+    /// the vault config's `search.min_threshold`
+    pub min_score: Option<f32>,
+    /// Chunk hits collected per source chunk before aggregation
+    pub chunks_per_query: usize,
+    /// Drop notes whose best chunk nearly duplicates a better ranked note's;
+    /// defaults to the vault config's `search.enable_diversity_filter`
+    pub enable_diversity_filter: Option<bool>,
+    /// Serve and store results through the suggestion cache
+    pub use_cache: bool,
+}
+
+impl Default for RelatedNotesOptions {
+    fn default() -> Self {
+        Self {
+            vault_path: None,
+            aggregation: ScoreAggregation::default(),
+            min_score: None,
+            chunks_per_query: 20,
+            enable_diversity_filter: None,
+            use_cache: true,
+        }
+    }
+}
+
+/// A note related to the source note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedNote {
+    /// Path of the related note
+    pub file_path: String,
+    /// Aggregated score of the note
+    pub score: f32,
+    /// Chunk of the related note that matched best
+    pub chunk_id: String,
+    /// Preview of the best matching chunk
+    pub preview: String,
+    /// Markdown headings enclosing the best matching chunk (outermost first)
+    pub heading_path: Vec<String>,
+}
+
+/// Response of the `related_notes` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedNotesResponse {
+    /// The source note
+    pub file_path: String,
+    /// Embedding model whose vectors were compared
+    pub model: String,
+    /// Aggregation used for the note scores
+    pub aggregation: ScoreAggregation,
+    /// Related notes ordered by score (descending)
+    pub results: Vec<RelatedNote>,
+    /// Number of source chunks used as queries
+    pub query_chunks: usize,
+    /// Whether the results came from the suggestion cache
+    pub from_cache: bool,
+    /// Time spent searching in milliseconds
+    pub search_time_ms: f64,
+}
+
+/// Find the notes most related to a note
+///
+/// Every indexed chunk of `file_path` is used as a query against the vector
+/// database, chunk hits are aggregated per target note and the note itself is
+/// left out. Results are cached in `SuggestionCache`, keyed by the note's
+/// indexed chunks and the options, so repeated calls while the note is open
+/// are served without searching.
+///
+/// # Arguments
+/// * `file_path` - Path of the source note, as stored in the vector database
+/// * `k` - Maximum number of related notes to return
+/// * `options` - Optional vault, aggregation, threshold and caching options
+///
+/// # Returns
+/// * `Ok(RelatedNotesResponse)` - Related notes ordered by aggregated score
+/// * `Err(String)` - Error message if the database is unavailable or the options are invalid
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const response = await invoke('related_notes', {
+///     filePath: '/path/to/vault/backups.md',
+///     k: 5,
+///     options: { vault_path: '/path/to/vault', aggregation: { top_n_sum: { n: 3 } } }
+/// });
+/// for (const note of response.results) {
+///     console.log(note.file_path, note.score, note.preview);
+/// }
+/// ```
+#[tauri::command]
+pub async fn related_notes(
+    file_path: String,
+    k: usize,
+    options: Option<RelatedNotesOptions>,
+) -> Result<RelatedNotesResponse, String> {
+    let options = options.unwrap_or_default();
+    let search_start = Instant::now();
+
+    if file_path.trim().is_empty() {
+        return Err("File path cannot be empty".to_string());
+    }
+    if let Some(min_score) = options.min_score.filter(|min_score| !SIMILARITY_THRESHOLD_RANGE.contains(min_score)) {
+        return Err(format!("min_score must be between -1.0 and 1.0, got {}", min_score));
+    }
+    let search_config = search_config(&file_path, k, &options, &current_vault_config().await.search);
+
+    if let Some(vault_path) = &options.vault_path {
+        open_vault_vector_database(vault_path).await?;
+    }
+
+    let db_guard = VECTOR_DATABASE.read().await;
+    let database = db_guard
+        .as_ref()
+        .ok_or_else(|| "Vector database not initialized. Open a vault or pass vault_path.".to_string())?;
+
+    // Only vectors of the active model are comparable with the rest of the vault
+    let active_model = database.active_model().await;
+    let sources: Vec<EmbeddingEntry> = database
+        .find_embeddings_by_file_indexed(&file_path)
+        .await
+        .map_err(|e| format!("Failed to load embeddings of '{}': {}", file_path, e))?
+        .into_iter()
+        .filter(|entry| active_model.as_ref().is_none_or(|model| entry.metadata.model_name == *model))
+        .collect();
+    let model = active_model
+        .or_else(|| sources.first().map(|entry| entry.metadata.model_name.clone()))
+        .unwrap_or_default();
+
+    let mut response = RelatedNotesResponse {
+        file_path: file_path.clone(),
+        model: model.clone(),
+        aggregation: options.aggregation,
+        results: Vec::new(),
+        query_chunks: sources.len(),
+        from_cache: false,
+        search_time_ms: 0.0,
+    };
+    if k == 0 || sources.is_empty() {
+        return Ok(response);
+    }
+
+    let cache_content = cache_content(&sources);
+    let cache_context = SuggestionContext::new(
+        Some(file_path.clone()),
+        options.vault_path.clone(),
+        cache_content.len(),
+        0,
+        cache_discriminator(k, &options, &search_config),
+    );
+    if options.use_cache {
+        let cache = get_suggestion_cache().await;
+        if let Ok(Some(cached)) = cache.get_suggestions(&cache_content, &model, &cache_context).await {
+            response.results = cached.into_iter().map(RelatedNote::from).collect();
+            response.from_cache = true;
+            response.search_time_ms = search_start.elapsed().as_secs_f64() * 1000.0;
+            return Ok(response);
+        }
+    }
+
+    let query_dimension = sources[0].vector.len();
+    let query_vectors: Vec<Vec<f32>> = sources
+        .into_iter()
+        .map(|entry| entry.vector)
+        .filter(|vector| vector.len() == query_dimension)
+        .collect();
+    let k_per_query = options.chunks_per_query.max(1);
+
+    let hits = if database.ann_index_len().await > 0 {
+        let hits_per_query = ann_chunk_hits(database, &query_vectors, k_per_query, &search_config).await?;
+        drop(db_guard);
+        SimilaritySearch::aggregate_note_hits(hits_per_query, options.aggregation, &search_config)
+    } else {
+        // Without an index (e.g. while it is rebuilt) the namespace is scanned exactly
+        let entries = database
+            .active_namespace_entries()
+            .await
+            .map_err(|e| format!("Failed to load embeddings: {}", e))?
+            .into_iter()
+            .filter(|entry| entry.vector.len() == query_dimension)
+            .collect::<Vec<_>>();
+        drop(db_guard);
+        SimilaritySearch::multi_query_note_search(&query_vectors, &entries, k_per_query, options.aggregation, &search_config)
+            .map_err(|e| format!("Related notes search failed: {}", e))?
+    };
+
+    if options.use_cache {
+        let cache = get_suggestion_cache().await;
+        if let Err(e) = cache.cache_suggestions(&cache_content, &model, &cache_context, hits.clone()).await {
+            log::warn!("Failed to cache related notes for '{}': {}", file_path, e);
+        }
+    }
+
+    response.results = hits.into_iter().map(RelatedNote::from).collect();
+    response.search_time_ms = search_start.elapsed().as_secs_f64() * 1000.0;
+    Ok(response)
+}
+
+/// Chunk hits of each query vector from the HNSW index
+///
+/// The note's own chunks are the nearest neighbors of its queries, so each
+/// query over-fetches by their count before `exclude_current_file` drops them.
+async fn ann_chunk_hits(
+    database: &VectorDatabase,
+    query_vectors: &[Vec<f32>],
+    k_per_query: usize,
+    search_config: &SearchConfig,
+) -> Result<Vec<Vec<SearchResult>>, String> {
+    let chunk_config = SimilaritySearch::note_query_config(search_config);
+    let mut hits_per_query = Vec::with_capacity(query_vectors.len());
+    for query_vector in query_vectors {
+        let mut hits = database
+            .approximate_search(query_vector, k_per_query + query_vectors.len(), &chunk_config)
+            .await
+            .map_err(|e| format!("Related notes search failed: {}", e))?;
+        hits.truncate(k_per_query);
+        hits_per_query.push(hits);
+    }
+    Ok(hits_per_query)
+}
+
+impl From<SearchResult> for RelatedNote {
+    fn from(hit: SearchResult) -> Self {
+        Self {
+            heading_path: hit.entry.metadata.heading_path(),
+            file_path: hit.entry.metadata.file_path,
+            score: hit.similarity,
+            chunk_id: hit.entry.metadata.chunk_id,
+            preview: hit.entry.metadata.content_preview,
+        }
+    }
+}
+
+/// Cache content for a note: the hashes of its indexed chunks
+///
+/// Related notes depend on what is indexed rather than on the editor buffer,
+/// so re-indexing the note (and only that) changes the cache key.
+fn cache_content(sources: &[EmbeddingEntry]) -> String {
+    let mut hashes: Vec<&str> = sources.iter().map(|entry| entry.metadata.text_hash.as_str()).collect();
+    hashes.sort_unstable();
+    hashes.join("\n")
+}
+
+/// Search configuration for the chunk queries of a note
+///
+/// Options left unset fall back to the vault config's `[search]` section.
+fn search_config(file_path: &str, k: usize, options: &RelatedNotesOptions, vault_search: &SearchConfig) -> SearchConfig {
+    SearchConfig {
+        min_threshold: options.min_score.unwrap_or(vault_search.min_threshold),
+        max_results: k,
+        normalize_query: false,
+        exclude_current_file: Some(file_path.to_string()),
+        enable_diversity_filter: options.enable_diversity_filter.unwrap_or(vault_search.enable_diversity_filter),
+        ..vault_search.clone()
+    }
+}
+
+/// Cache key component separating rankings computed with different options
+///
+/// Uses the resolved search configuration, so editing the vault config
+/// invalidates cached rankings.
+fn cache_discriminator(k: usize, options: &RelatedNotesOptions, search_config: &SearchConfig) -> String {
+    format!(
+        "related_notes k={} aggregation={:?} min_score={} chunks_per_query={} diversity={}",
+        k, options.aggregation, search_config.min_threshold, options.chunks_per_query, search_config.enable_diversity_filter
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(file_path: &str, chunk_id: &str, content: &str) -> EmbeddingEntry {
+        EmbeddingEntry::new(
+            vec![0.1, 0.2, 0.3],
+            file_path.to_string(),
+            chunk_id.to_string(),
+            content,
+            "nomic-embed-text".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_cache_key_tracks_indexed_chunks_and_options() {
+        let first = entry("/vault/a.md", "chunk_0", "Backups run nightly.");
+        let second = entry("/vault/a.md", "chunk_1", "Restores are tested monthly.");
+
+        // Chunk order does not matter, content does
+        assert_eq!(
+            cache_content(&[first.clone(), second.clone()]),
+            cache_content(&[second.clone(), first.clone()])
+        );
+        let edited = entry("/vault/a.md", "chunk_1", "Restores are tested weekly.");
+        assert_ne!(cache_content(&[first.clone(), second]), cache_content(&[first, edited]));
+
+        let options = RelatedNotesOptions::default();
+        let config = SearchConfig::default();
+        let top_n = RelatedNotesOptions { aggregation: ScoreAggregation::TopNSum { n: 3 }, ..options.clone() };
+        assert_ne!(cache_discriminator(5, &options, &config), cache_discriminator(5, &top_n, &config));
+        assert_ne!(cache_discriminator(5, &options, &config), cache_discriminator(10, &options, &config));
+
+        // A vault config change invalidates rankings that relied on it
+        let stricter = SearchConfig { min_threshold: 0.6, ..config.clone() };
+        assert_ne!(cache_discriminator(5, &options, &config), cache_discriminator(5, &options, &stricter));
+    }
+
+    #[test]
+    fn test_search_config_falls_back_to_vault_search() {
+        let vault_search = SearchConfig { min_threshold: 0.55, enable_diversity_filter: false, ..SearchConfig::default() };
+        let config = search_config("/vault/a.md", 5, &RelatedNotesOptions::default(), &vault_search);
+        assert_eq!(config.min_threshold, 0.55);
+        assert!(!config.enable_diversity_filter);
+        assert_eq!(config.exclude_current_file.as_deref(), Some("/vault/a.md"));
+
+        let options = RelatedNotesOptions { min_score: Some(0.2), enable_diversity_filter: Some(true), ..Default::default() };
+        let config = search_config("/vault/a.md", 5, &options, &vault_search);
+        assert_eq!(config.min_threshold, 0.2);
+        assert!(config.enable_diversity_filter);
+    }
+
+    #[tokio::test]
+    async fn test_ann_chunk_hits_skip_the_source_note() {
+        use crate::vector_db::types::VectorStorageConfig;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let database = VectorDatabase::new(VectorStorageConfig {
+            storage_dir: temp_dir.path().to_string_lossy().to_string(),
+            ..VectorStorageConfig::default()
+        })
+        .await
+        .unwrap();
+        let note = |file_path: &str, chunk_id: &str, vector: Vec<f32>| {
+            EmbeddingEntry::new(vector, file_path.to_string(), chunk_id.to_string(), chunk_id, "nomic-embed-text".to_string())
+        };
+        // The source note's chunks are closer to its queries than anything else
+        database
+            .store_embeddings_batch(vec![
+                note("/vault/source.md", "source_0", vec![1.0, 0.0, 0.0]),
+                note("/vault/source.md", "source_1", vec![0.99, 0.1, 0.0]),
+                note("/vault/source.md", "source_2", vec![0.98, 0.15, 0.0]),
+                note("/vault/near.md", "near_0", vec![0.9, 0.3, 0.1]),
+                note("/vault/far.md", "far_0", vec![0.2, 0.9, 0.3]),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(database.ann_index_len().await, 5);
+
+        let config = search_config("/vault/source.md", 5, &RelatedNotesOptions::default(), &SearchConfig::default());
+        let queries = vec![vec![1.0, 0.0, 0.0], vec![0.99, 0.1, 0.0], vec![0.98, 0.15, 0.0]];
+        let hits_per_query = ann_chunk_hits(&database, &queries, 1, &config).await.unwrap();
+
+        assert_eq!(hits_per_query.len(), 3);
+        for hits in &hits_per_query {
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].entry.metadata.file_path, "/vault/near.md");
+        }
+        let notes = SimilaritySearch::aggregate_note_hits(hits_per_query, ScoreAggregation::Max, &config);
+        assert_eq!(notes.len(), 1);
+    }
+
+    #[test]
+    fn test_related_notes_options_deserialize_partial() {
+        let options: RelatedNotesOptions = serde_json::from_str(r#"{"aggregation": "mean"}"#).unwrap();
+        assert_eq!(options.aggregation, ScoreAggregation::Mean);
+        assert_eq!(options.chunks_per_query, 20);
+        assert_eq!(options.enable_diversity_filter, None);
+        assert!(options.use_cache);
+    }
+}
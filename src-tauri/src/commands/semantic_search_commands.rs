@@ -0,0 +1,568 @@
+//! # Semantic Search Commands
+//!
+//! This module contains the text-query semantic search command. Instead of
+//! requiring the frontend to call `generate_embedding` and send the query
+//! vector back over IPC, `semantic_search` accepts the query text, embeds it
+//! on the backend and searches the vault's vector database in one call.
+//!
+//! ## Command Overview
+//!
+//! - `semantic_search`: Embed a text query and return matching notes grouped by file
+//!
+//! ## Search Pipeline
+//!
+//! 1. **Database Selection**: Open the vault's vector database if a vault path is given
+//! 2. **Query Embedding**: Embed the query through `EmbeddingCache` and `EmbeddingGenerator`
+//!    using the database's active embedding model
+//! 3. **Similarity Search**: Use the HNSW index when populated, exact k-NN otherwise,
+//!    restricted to the active model's embeddings and to notes passing `filter`
+//! 4. **Lexical Fusion**: In hybrid mode, fuse the ranking with BM25 term matches
+//! 5. **Expansion**: Optionally replace chunk hits by the paragraph or section enclosing
+//!    them and read that text back from the note
+//! 6. **Grouping**: Collapse chunk hits into one result per file, ordered by best rank
+//!
+//! ## Result Shape
+//!
+//! Each file result carries its best chunk score and up to `max_chunks_per_file`
+//! chunks, each with its content preview, similarity score and heading path.
+//! With `expand_to` set, the chunks are the paragraphs or sections enclosing
+//! the hits and also carry their full text.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use serde::{Serialize, Deserialize};
+
+use crate::commands::embedding_commands::generate_embedding;
+use crate::globals::{current_vault_config, get_embedding_provider, open_vault_vector_database, VECTOR_DATABASE};
+use crate::rag::load_chunk_text;
+use crate::similarity_search::{
+    expand_hits_to_parents, SearchConfig, SearchFilter, SearchResult, SimilaritySearch, SIMILARITY_THRESHOLD_RANGE,
+};
+use crate::text_chunker::ChunkLevel;
+use crate::vector_db::VectorDatabase;
+
+/// Options for the `semantic_search` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SemanticSearchOptions {
+    /// Vault to search; opens the vault's vector database when provided
+    pub vault_path: Option<String>,
+    /// Embedding model override (defaults to the database's active model and
+    /// must match it once the database holds embeddings)
+    pub model: Option<String>,
+    /// Maximum number of files to return
+    pub max_files: usize,
+    /// Maximum number of chunks returned per file
+    pub max_chunks_per_file: usize,
+    /// Minimum similarity score for a chunk to be included (-1.0 to 1.0);
+    /// defaults to the vault config's `search.min_threshold`
+    pub min_score: Option<f32>,
+    /// File to leave out of the results (usually the note being edited)
+    pub exclude_file: Option<String>,
+    /// Use the HNSW index instead of an exact scan when it is populated
+    pub use_approximate: bool,
+    /// Fuse vector results with BM25 lexical matches (reciprocal-rank fusion)
+    pub hybrid: bool,
+    /// Weight of the vector ranking in hybrid mode; defaults to the vault
+    /// config's `search.vector_weight`
+    pub vector_weight: Option<f32>,
+    /// Weight of the lexical ranking in hybrid mode; defaults to the vault
+    /// config's `search.lexical_weight`
+    pub lexical_weight: Option<f32>,
+    /// Restrict the search to notes with matching tags, folder, dates or
+    /// frontmatter fields (a relative folder is resolved against `vault_path`)
+    pub filter: SearchFilter,
+    /// Expand hits to their enclosing chunk at this level (paragraph or
+    /// section) and return its full text; flat chunks are kept as they are
+    pub expand_to: Option<ChunkLevel>,
+}
+
+impl Default for SemanticSearchOptions {
+    fn default() -> Self {
+        Self {
+            vault_path: None,
+            model: None,
+            max_files: 10,
+            max_chunks_per_file: 3,
+            min_score: None,
+            exclude_file: None,
+            use_approximate: true,
+            hybrid: true,
+            vector_weight: None,
+            lexical_weight: None,
+            filter: SearchFilter::default(),
+            expand_to: None,
+        }
+    }
+}
+
+/// A matching chunk within a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticSearchChunk {
+    /// Embedding entry ID of the chunk
+    pub entry_id: String,
+    /// Chunk identifier within the file
+    pub chunk_id: String,
+    /// Preview of the chunk content
+    pub preview: String,
+    /// Cosine similarity between the query and the chunk
+    pub score: f32,
+    /// Markdown headings enclosing the chunk (outermost first)
+    pub heading_path: Vec<String>,
+    /// Full text of the chunk read from the note, when `expand_to` is set
+    pub text: Option<String>,
+}
+
+/// Search results for a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticSearchFileResult {
+    /// Path of the matching file
+    pub file_path: String,
+    /// Best chunk score in this file
+    pub score: f32,
+    /// Matching chunks ordered by score (descending)
+    pub chunks: Vec<SemanticSearchChunk>,
+}
+
+/// Response of the `semantic_search` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticSearchResponse {
+    /// The original query text
+    pub query: String,
+    /// Embedding model used for the query
+    pub model: String,
+    /// Results grouped by file, ordered by best score (descending)
+    pub results: Vec<SemanticSearchFileResult>,
+    /// Number of chunk hits before grouping
+    pub total_chunks: usize,
+    /// Whether the HNSW index was used
+    pub used_approximate_search: bool,
+    /// Whether vector and lexical rankings were fused
+    pub used_hybrid_search: bool,
+    /// Time spent embedding the query in milliseconds
+    pub embedding_time_ms: f64,
+    /// Time spent searching and grouping in milliseconds
+    pub search_time_ms: f64,
+}
+
+/// Search the vault for notes semantically related to a text query
+///
+/// The query is embedded on the backend with the vault's embedding model
+/// (served from `EmbeddingCache` when possible), searched against the global
+/// vector database, and the chunk hits are grouped by file. In hybrid mode
+/// (the default) the vector ranking is fused with BM25 matches on the query
+/// text, so exact identifiers and names are found as well.
+///
+/// # Arguments
+/// * `query` - Natural language query text
+/// * `options` - Optional search options (vault, model, limits, threshold)
+///
+/// # Returns
+/// * `Ok(SemanticSearchResponse)` - Results grouped by file with chunk previews
+/// * `Err(String)` - Error message if the database is unavailable or embedding fails
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const response = await invoke('semantic_search', {
+///     query: 'how do I configure backups?',
+///     options: { vault_path: '/path/to/vault', max_files: 5 }
+/// });
+/// for (const file of response.results) {
+///     console.log(file.file_path, file.score, file.chunks[0].heading_path.join(' › '));
+/// }
+/// ```
+#[tauri::command]
+pub async fn semantic_search(
+    query: String,
+    options: Option<SemanticSearchOptions>,
+) -> Result<SemanticSearchResponse, String> {
+    let options = options.unwrap_or_default();
+
+    let trimmed_query = query.trim();
+    if trimmed_query.is_empty() {
+        return Err("Search query cannot be empty".to_string());
+    }
+    if let Some(min_score) = options.min_score.filter(|min_score| !SIMILARITY_THRESHOLD_RANGE.contains(min_score)) {
+        return Err(format!("min_score must be between -1.0 and 1.0, got {}", min_score));
+    }
+    if options.max_files == 0 || options.max_chunks_per_file == 0 {
+        return Ok(SemanticSearchResponse {
+            query,
+            model: resolve_search_model(
+                options.model.as_deref(),
+                None,
+                &current_vault_config().await.pipeline.embedding_model,
+            ),
+            results: Vec::new(),
+            total_chunks: 0,
+            used_approximate_search: false,
+            used_hybrid_search: false,
+            embedding_time_ms: 0.0,
+            search_time_ms: 0.0,
+        });
+    }
+
+    if let Some(vault_path) = &options.vault_path {
+        open_vault_vector_database(vault_path).await?;
+    }
+
+    let model = query_embedding_model(options.model.as_deref()).await?;
+
+    // Embed the query (cache first, then the embedding generator)
+    let embedding_start = Instant::now();
+    let query_vector = generate_embedding(trimmed_query.to_string(), model.clone()).await?;
+    let embedding_time_ms = embedding_start.elapsed().as_secs_f64() * 1000.0;
+
+    let search_start = Instant::now();
+    let search_config = search_config(&options, &current_vault_config().await.search);
+    // Fetch extra chunks so that files with many hits do not crowd out the rest
+    let k = options.max_files * options.max_chunks_per_file * 2;
+
+    let db_guard = VECTOR_DATABASE.read().await;
+    let database = db_guard
+        .as_ref()
+        .ok_or_else(|| "Vector database not initialized. Open a vault or pass vault_path.".to_string())?;
+
+    let (hits, use_approximate) = retrieve_hits(
+        database,
+        trimmed_query,
+        &query_vector,
+        k,
+        &search_config,
+        options.use_approximate,
+        options.hybrid,
+    )
+    .await
+    .map_err(|e| format!("Semantic search failed: {}", e))?;
+    drop(db_guard);
+
+    let total_chunks = hits.len();
+    let (hits, mut texts) = match options.expand_to {
+        Some(level) => {
+            let hits = expand_hits_to_parents(hits, level);
+            let mut notes = HashMap::new();
+            let texts: HashMap<String, String> = hits
+                .iter()
+                .map(|hit| (hit.entry.id.clone(), load_chunk_text(&hit.entry, &mut notes)))
+                .collect();
+            (hits, texts)
+        }
+        None => (hits, HashMap::new()),
+    };
+    let mut results = if options.hybrid {
+        group_ranked_hits(hits, options.max_files, options.max_chunks_per_file)
+    } else {
+        group_results_by_file(hits, options.max_files, options.max_chunks_per_file)
+    };
+    for chunk in results.iter_mut().flat_map(|result| result.chunks.iter_mut()) {
+        chunk.text = texts.remove(&chunk.entry_id);
+    }
+
+    Ok(SemanticSearchResponse {
+        query,
+        model,
+        results,
+        total_chunks,
+        used_approximate_search: use_approximate,
+        used_hybrid_search: options.hybrid,
+        embedding_time_ms,
+        search_time_ms: search_start.elapsed().as_secs_f64() * 1000.0,
+    })
+}
+
+/// Search configuration for a query
+///
+/// Options left unset fall back to the vault config's `[search]` section.
+fn search_config(options: &SemanticSearchOptions, vault_search: &SearchConfig) -> SearchConfig {
+    SearchConfig {
+        min_threshold: options.min_score.unwrap_or(vault_search.min_threshold),
+        max_results: 0,
+        normalize_query: false,
+        exclude_current_file: options.exclude_file.clone(),
+        enable_diversity_filter: false,
+        vector_weight: options.vector_weight.unwrap_or(vault_search.vector_weight),
+        lexical_weight: options.lexical_weight.unwrap_or(vault_search.lexical_weight),
+        filter: match &options.vault_path {
+            Some(vault_path) => options.filter.clone().with_vault_root(vault_path),
+            None => options.filter.clone(),
+        },
+        ..vault_search.clone()
+    }
+}
+
+/// Retrieve the chunk hits for an embedded query in rank order
+///
+/// This is the retrieval step shared by `semantic_search` and `ask_notes`.
+/// Hybrid mode fuses the vector ranking with BM25 matches on the query text
+/// and always uses the HNSW index when it is populated. Otherwise the HNSW
+/// index is used when requested and populated. Exact requests score the
+/// compressed index vectors and re-rank them with their f32 vectors when
+/// vector compression is enabled, and scan the active namespace when not.
+///
+/// # Returns
+/// The hits, and whether the HNSW index was used
+pub(crate) async fn retrieve_hits(
+    database: &VectorDatabase,
+    query: &str,
+    query_vector: &[f32],
+    k: usize,
+    search_config: &SearchConfig,
+    use_approximate: bool,
+    hybrid: bool,
+) -> Result<(Vec<SearchResult>, bool), String> {
+    let index_populated = database.ann_index_len().await > 0;
+    let use_approximate = (use_approximate || hybrid) && index_populated;
+    let hits = if hybrid {
+        // Hybrid search picks the vector path itself and returns hits in fused order
+        database
+            .hybrid_search(query, query_vector, k, search_config)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|hit| SearchResult { entry: hit.entry, similarity: hit.similarity })
+            .collect()
+    } else if use_approximate {
+        database
+            .approximate_search(query_vector, k, search_config)
+            .await
+            .map_err(|e| e.to_string())?
+    } else if index_populated && database.get_config().enable_vector_compression {
+        database
+            .compressed_search(query_vector, k, search_config)
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        let entries = database
+            .active_namespace_entries()
+            .await
+            .map_err(|e| format!("Failed to load embeddings: {}", e))?
+            .into_iter()
+            .filter(|entry| entry.vector.len() == query_vector.len())
+            .collect::<Vec<_>>();
+        SimilaritySearch::k_nearest_neighbors(query_vector, &entries, k, search_config)
+            .map_err(|e| e.to_string())?
+    };
+    Ok((hits, use_approximate))
+}
+
+/// Resolve the embedding model for a query and check it against the vault
+///
+/// Queries must be embedded with the model whose vectors are searched, so a
+/// model whose provider ID differs from the database's active model is rejected.
+pub(crate) async fn query_embedding_model(model_override: Option<&str>) -> Result<String, String> {
+    let active_model = match VECTOR_DATABASE.read().await.as_ref() {
+        Some(database) => database.active_model().await,
+        None => None,
+    };
+    // The active namespace is a model ID, which is also the model name only for providers keyed by name
+    let provider = get_embedding_provider().await;
+    let active_model_name = active_model.as_deref().filter(|model_id| provider.model_id(model_id) == *model_id);
+    let default_model = current_vault_config().await.pipeline.embedding_model.clone();
+    let model = resolve_search_model(model_override, active_model_name, &default_model);
+    let model_id = provider.model_id(&model);
+    if let Some(active_model) = active_model.filter(|active_model| *active_model != model_id) {
+        return Err(format!(
+            "The vault is indexed with '{}'; querying with '{}' would compare embeddings from different models",
+            active_model, model
+        ));
+    }
+    Ok(model)
+}
+
+/// Resolve the embedding model for a query
+///
+/// Queries must be embedded with the same model the vault was indexed with,
+/// so the caller's override wins, then the database's active model, then
+/// `default_model`, the model the open vault's pipeline indexes with.
+fn resolve_search_model(model_override: Option<&str>, active_model: Option<&str>, default_model: &str) -> String {
+    model_override
+        .filter(|model| !model.trim().is_empty())
+        .or(active_model)
+        .unwrap_or(default_model)
+        .to_string()
+}
+
+/// Group chunk-level hits into per-file results
+///
+/// Files are ordered by their best chunk score; chunks inside a file keep
+/// score order and are capped at `max_chunks_per_file`.
+fn group_results_by_file(
+    hits: Vec<SearchResult>,
+    max_files: usize,
+    max_chunks_per_file: usize,
+) -> Vec<SemanticSearchFileResult> {
+    let mut hits = hits;
+    hits.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+
+    group_ranked_hits(hits, max_files, max_chunks_per_file)
+}
+
+/// Group hits that are already in rank order into per-file results
+///
+/// Files are ordered by their first (best ranked) chunk and chunks inside a
+/// file keep rank order. The file score is the best chunk similarity.
+fn group_ranked_hits(
+    hits: Vec<SearchResult>,
+    max_files: usize,
+    max_chunks_per_file: usize,
+) -> Vec<SemanticSearchFileResult> {
+    let mut file_order: Vec<String> = Vec::new();
+    let mut grouped: HashMap<String, Vec<SemanticSearchChunk>> = HashMap::new();
+
+    for hit in hits {
+        let file_path = hit.entry.metadata.file_path.clone();
+        let chunks = grouped.entry(file_path.clone()).or_insert_with(|| {
+            file_order.push(file_path);
+            Vec::new()
+        });
+        if chunks.len() >= max_chunks_per_file {
+            continue;
+        }
+        chunks.push(SemanticSearchChunk {
+            heading_path: hit.entry.metadata.heading_path(),
+            entry_id: hit.entry.id,
+            chunk_id: hit.entry.metadata.chunk_id,
+            preview: hit.entry.metadata.content_preview,
+            score: hit.similarity,
+            text: None,
+        });
+    }
+
+    file_order
+        .into_iter()
+        .take(max_files)
+        .filter_map(|file_path| {
+            let chunks = grouped.remove(&file_path)?;
+            let score = chunks.iter().map(|chunk| chunk.score).fold(f32::MIN, f32::max);
+            Some(SemanticSearchFileResult { file_path, score, chunks })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::types::EmbeddingEntry;
+
+    fn hit(file_path: &str, chunk_id: &str, similarity: f32, heading_path: &[&str]) -> SearchResult {
+        let mut entry = EmbeddingEntry::new(
+            vec![0.1, 0.2, 0.3],
+            file_path.to_string(),
+            chunk_id.to_string(),
+            &format!("content of {}", chunk_id),
+            "nomic-embed-text".to_string(),
+        );
+        let heading_path: Vec<String> = heading_path.iter().map(|h| h.to_string()).collect();
+        entry.metadata.set_heading_path(&heading_path);
+        SearchResult { entry, similarity }
+    }
+
+    #[test]
+    fn test_group_results_by_file_orders_by_best_chunk() {
+        let hits = vec![
+            hit("/vault/b.md", "chunk_0", 0.55, &[]),
+            hit("/vault/a.md", "chunk_2", 0.91, &["Setup", "Backups"]),
+            hit("/vault/b.md", "chunk_3", 0.80, &["Notes"]),
+            hit("/vault/a.md", "chunk_1", 0.60, &["Setup"]),
+            hit("/vault/a.md", "chunk_5", 0.40, &[]),
+            hit("/vault/c.md", "chunk_0", 0.35, &[]),
+        ];
+
+        let results = group_results_by_file(hits, 2, 2);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].file_path, "/vault/a.md");
+        assert_eq!(results[0].score, 0.91);
+        assert_eq!(results[0].chunks.len(), 2);
+        assert_eq!(results[0].chunks[0].chunk_id, "chunk_2");
+        assert_eq!(results[0].chunks[0].heading_path, vec!["Setup", "Backups"]);
+        assert_eq!(results[0].chunks[1].chunk_id, "chunk_1");
+
+        assert_eq!(results[1].file_path, "/vault/b.md");
+        assert_eq!(results[1].chunks[0].chunk_id, "chunk_3");
+        assert_eq!(results[1].chunks[1].heading_path, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_group_ranked_hits_keeps_rank_order() {
+        // A lexical match with a low cosine score can still rank first after fusion
+        let hits = vec![
+            hit("/vault/ids.md", "chunk_0", 0.20, &["Identifiers"]),
+            hit("/vault/a.md", "chunk_1", 0.85, &[]),
+            hit("/vault/ids.md", "chunk_4", 0.60, &[]),
+        ];
+
+        let results = group_ranked_hits(hits, 10, 3);
+
+        assert_eq!(results[0].file_path, "/vault/ids.md");
+        assert_eq!(results[0].score, 0.60);
+        assert_eq!(results[0].chunks[0].chunk_id, "chunk_0");
+        assert_eq!(results[1].file_path, "/vault/a.md");
+    }
+
+    #[test]
+    fn test_resolve_search_model() {
+        assert_eq!(resolve_search_model(None, None, "vault-model"), "vault-model");
+        assert_eq!(resolve_search_model(None, Some("all-minilm"), "vault-model"), "all-minilm");
+        assert_eq!(resolve_search_model(Some("  "), None, "vault-model"), "vault-model");
+        assert_eq!(resolve_search_model(Some("mxbai-embed-large"), None, "vault-model"), "mxbai-embed-large");
+        assert_eq!(resolve_search_model(Some("mxbai-embed-large"), Some("all-minilm"), "vault-model"), "mxbai-embed-large");
+    }
+
+    #[test]
+    fn test_vault_search_config_applies_when_options_unset() {
+        use crate::vault_config::VaultConfig;
+        use std::path::Path;
+
+        let vault = VaultConfig::parse(
+            "version = 1\n\n[search]\nmin_threshold = 0.9\nvector_weight = 0.5\n",
+            Path::new("config.toml"),
+        )
+        .unwrap();
+        let options = SemanticSearchOptions::default();
+        let configured = search_config(&options, &vault.search);
+        let defaults = search_config(&options, &SearchConfig::default());
+
+        // The vault threshold drops the chunk at 0.8 similarity
+        let query = vec![1.0, 0.0];
+        let entry = |vector: Vec<f32>, file_path: &str| {
+            EmbeddingEntry::new(vector, file_path.to_string(), "chunk_0".to_string(), "content", "nomic-embed-text".to_string())
+        };
+        let entries = vec![entry(vec![1.0, 0.0], "/vault/exact.md"), entry(vec![0.8, 0.6], "/vault/near.md")];
+        assert_eq!(SimilaritySearch::k_nearest_neighbors(&query, &entries, 10, &defaults).unwrap().len(), 2);
+        assert_eq!(SimilaritySearch::k_nearest_neighbors(&query, &entries, 10, &configured).unwrap().len(), 1);
+
+        // A lower vector weight lets the lexical ranking decide the tie
+        let vector_first = hit("/vault/vector.md", "chunk_0", 0.6, &[]);
+        let lexical_first = hit("/vault/lexical.md", "chunk_0", 0.5, &[]);
+        let top = |config: &SearchConfig| {
+            SimilaritySearch::reciprocal_rank_fusion(
+                vec![vector_first.clone(), lexical_first.clone()],
+                vec![(lexical_first.clone(), 2.0), (vector_first.clone(), 1.0)],
+                2,
+                config,
+            )[0]
+            .entry
+            .metadata
+            .file_path
+            .clone()
+        };
+        assert_eq!(top(&defaults), "/vault/vector.md");
+        assert_eq!(top(&configured), "/vault/lexical.md");
+
+        // Explicit options still win over the vault config
+        let explicit = SemanticSearchOptions { min_score: Some(0.5), vector_weight: Some(1.0), ..options };
+        let explicit = search_config(&explicit, &vault.search);
+        assert_eq!(SimilaritySearch::k_nearest_neighbors(&query, &entries, 10, &explicit).unwrap().len(), 2);
+        assert_eq!(top(&explicit), "/vault/vector.md");
+    }
+
+    #[test]
+    fn test_semantic_search_options_deserialize_partial() {
+        let options: SemanticSearchOptions = serde_json::from_str(r#"{"max_files": 4}"#).unwrap();
+        assert_eq!(options.max_files, 4);
+        assert_eq!(options.max_chunks_per_file, 3);
+        assert!(options.use_approximate);
+        assert!(options.hybrid);
+    }
+}
@@ -17,6 +17,12 @@
 //! - `scan_vault_files_chunked`: Paginated scanning for large vaults
 //! - `watch_vault`: Set up filesystem watching for changes
 //!
+//! ### Vault Compatibility
+//! - `get_vault_compatibility`: Detected Obsidian/Logseq layout, unmapped features and unresolved references
+//!
+//! ### Vault Configuration
+//! - `get_vault_config`: Load, validate and apply the vault's `.ainote/config.toml`
+//!
 //! ## Cross-Platform Support
 //!
 //! - **macOS**: Uses native file dialogs via `rfd` crate
@@ -49,6 +55,9 @@
 
 use crate::vault_operations;
 use crate::types::FileInfo;
+use crate::vault_compat::{VaultCompat, VaultCompatibilityReport};
+use crate::vault_config::VaultConfig;
+use crate::globals::open_vault_config;
 use crate::commands::indexing_commands::{index_vault_notes, start_indexing_pipeline};
 use crate::file_monitor::get_file_monitor;
 
@@ -196,10 +205,11 @@ pub fn load_vault(vault_path: String) -> Result<Vec<FileInfo>, String> {
 /// # Operation Steps (Phase 2C)
 /// 1. Validate vault directory structure
 /// 2. Load and scan all vault files
-/// 3. Initialize indexing pipeline
-/// 4. Start automatic vault indexing in background
-/// 5. Activate real-time file monitoring
-/// 6. Return vault files with indexing status
+/// 3. Load the vault config (`.ainote/config.toml`)
+/// 4. Initialize indexing pipeline
+/// 5. Start automatic vault indexing in background
+/// 6. Activate real-time file monitoring
+/// 7. Return vault files with indexing status
 ///
 /// # Performance Features
 /// - Non-blocking indexing (runs in background)
@@ -241,6 +251,15 @@ pub async fn load_vault_with_indexing(
     
     log::info!("✅ Loaded {} files from vault", files.len());
     
+    // An invalid config file does not prevent loading; the defaults are used until it is fixed
+    let config_error = match open_vault_config(&vault_path_str).await {
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("⚠️ Invalid vault config: {}", e);
+            Some(e)
+        }
+    };
+    
     let should_index = auto_index.unwrap_or(true);
     let should_monitor = auto_monitor.unwrap_or(true);
     
@@ -311,6 +330,7 @@ pub async fn load_vault_with_indexing(
         indexing_error,
         monitoring_active,
         monitoring_error,
+        config_error,
         vault_path: vault_path_str,
     };
     
@@ -333,6 +353,8 @@ pub struct LoadVaultWithIndexingResult {
     pub monitoring_active: bool,
     /// Error message if file monitoring failed
     pub monitoring_error: Option<String>,
+    /// Error message if the vault config is invalid (default settings are used)
+    pub config_error: Option<String>,
     /// Path to the loaded vault
     pub vault_path: String,
 }
@@ -445,4 +467,62 @@ pub fn scan_vault_files_chunked(
 #[tauri::command]
 pub fn watch_vault(vault_path: String) -> Result<(), String> {
     vault_operations::watch_vault_internal(&vault_path).map_err(|e| e.into())
-}
\ No newline at end of file
+}
+/// Report how an Obsidian or Logseq vault is mapped
+///
+/// Detects the app the vault was created with and reads its settings: the
+/// ignore list honored by scanning and indexing, and the attachment folder
+/// used to resolve embedded attachments. The report also lists the features
+/// that could not be mapped and the embeds and block references that do not
+/// resolve. Every note is read, so this takes a moment on large vaults.
+///
+/// # Arguments
+/// * `vault_path` - Absolute path to the vault directory
+///
+/// # Returns
+/// * `Ok(VaultCompatibilityReport)` - Detected layout and unmapped features
+/// * `Err(String)` - Error message if the vault cannot be opened
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const report = await invoke('get_vault_compatibility', { vaultPath: '/path/to/vault' });
+/// console.log(`${report.flavor} vault with ${report.note_count} notes`);
+/// report.unmapped_features.forEach(f => console.warn(`${f.feature}: ${f.detail}`));
+/// ```
+#[tauri::command]
+pub fn get_vault_compatibility(vault_path: String) -> Result<VaultCompatibilityReport, String> {
+    VaultCompat::open(&vault_path)
+        .map(|vault| vault.report())
+        .map_err(|e| e.to_string())
+}
+
+/// Load, validate and apply the configuration file of a vault
+///
+/// Reads `{vault}/.ainote/config.toml`, checks every key against the
+/// settings of the subsystem it belongs to and makes it the configuration of
+/// the open vault. The file monitor does the same whenever the file changes;
+/// this command lets the frontend show the effective settings, or why the
+/// file was rejected.
+///
+/// # Arguments
+/// * `vault_path` - Absolute path to the vault directory
+///
+/// # Returns
+/// * `Ok(VaultConfig)` - Effective settings, defaults for keys the file leaves out
+/// * `Err(String)` - Error naming the file and the invalid key or value
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// try {
+///     const config = await invoke('get_vault_config', { vaultPath: '/path/to/vault' });
+///     console.log(`Indexing with ${config.pipeline.worker_count} workers`);
+/// } catch (error) {
+///     console.error(error); // e.g. "Unknown key `chunking.max_size` in ..."
+/// }
+/// ```
+#[tauri::command]
+pub async fn get_vault_config(vault_path: String) -> Result<VaultConfig, String> {
+    open_vault_config(&vault_path)
+        .await
+        .map(|config| config.as_ref().clone())
+}
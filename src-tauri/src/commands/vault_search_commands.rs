@@ -0,0 +1,159 @@
+//! # Vault Text Search Commands
+//!
+//! This module contains the commands for grep-style keyword and regular
+//! expression search across the vault's notes, and for vault-wide
+//! find-and-replace.
+//!
+//! ## Command Overview
+//!
+//! - `search_vault_text`: Find every occurrence of a text or regex with line context
+//! - `replace_in_vault`: Replace every occurrence, backing up each changed note
+//! - `cancel_vault_search`: Stop a running search or replace
+//!
+//! ## Cancellation
+//!
+//! The frontend picks a `search_id` for every search or replace. Passing the
+//! same ID to `cancel_vault_search` stops the operation between notes: a
+//! search then rejects with a cancellation error, a replace resolves with the
+//! notes it already changed and `cancelled` set.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use tokio::sync::RwLock;
+
+use crate::indexing_pipeline::CancellationToken;
+use crate::vault_search::{ReplaceReport, VaultSearch, VaultSearchOptions, VaultSearchReport};
+
+/// Cancellation tokens of running searches, by search ID
+type VaultSearchRegistry = Arc<RwLock<HashMap<String, Arc<CancellationToken>>>>;
+
+/// Searches and replaces that are currently running
+static ACTIVE_VAULT_SEARCHES: Lazy<VaultSearchRegistry> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+
+/// Find every occurrence of a text or regular expression in the vault
+///
+/// # Arguments
+/// * `search_id` - Caller-chosen ID used to cancel the search
+/// * `vault_path` - Vault to search
+/// * `options` - Pattern, matching mode, context size, match limit and excluded paths
+///
+/// # Returns
+/// * `Ok(VaultSearchReport)` - Matches with file, line, column and context lines
+/// * `Err(String)` - Error message if the pattern is invalid, the vault cannot be read or the search was cancelled
+///
+/// # Example Usage (from frontend)
+/// ```javascript
+/// const report = await invoke('search_vault_text', {
+///     searchId: 'find-1',
+///     vaultPath: '/path/to/vault',
+///     options: { pattern: 'TODO', whole_word: true, context_lines: 1 }
+/// });
+/// for (const match of report.matches) {
+///     console.log(`${match.file_path}:${match.line}:${match.column}`, match.line_text);
+/// }
+/// ```
+#[tauri::command]
+pub async fn search_vault_text(
+    search_id: String,
+    vault_path: String,
+    options: VaultSearchOptions,
+) -> Result<VaultSearchReport, String> {
+    let search = VaultSearch::new(options).map_err(|e| e.to_string())?;
+    run_vault_search(&search_id, move |token| search.search(&vault_path, &token).map_err(|e| e.to_string())).await
+}
+
+/// Replace every occurrence of a text or regular expression in the vault
+///
+/// Each changed note is locked while it is rewritten and backed up first.
+/// Notes that cannot be rewritten are listed in `skipped`.
+///
+/// # Arguments
+/// * `search_id` - Caller-chosen ID used to cancel the replace
+/// * `vault_path` - Vault to rewrite
+/// * `options` - Pattern and matching mode (context and match limit are ignored)
+/// * `replacement` - Replacement text; in regex mode `$1` and `${name}` insert capture groups
+///
+/// # Returns
+/// * `Ok(ReplaceReport)` - Changed notes, replacement count, backups and skipped notes
+/// * `Err(String)` - Error message if the pattern is invalid or the vault cannot be read
+#[tauri::command]
+pub async fn replace_in_vault(
+    search_id: String,
+    vault_path: String,
+    options: VaultSearchOptions,
+    replacement: String,
+) -> Result<ReplaceReport, String> {
+    let search = VaultSearch::new(options).map_err(|e| e.to_string())?;
+    run_vault_search(&search_id, move |token| {
+        search.replace(&vault_path, &replacement, &token).map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// Cancel a running search or replace
+///
+/// # Returns
+/// * `Ok(true)` - A running operation was asked to stop
+/// * `Ok(false)` - No operation with this search ID is running
+#[tauri::command]
+pub async fn cancel_vault_search(search_id: String) -> Result<bool, String> {
+    let searches = ACTIVE_VAULT_SEARCHES.read().await;
+    match searches.get(&search_id) {
+        Some(token) => {
+            token.cancel();
+            log::info!("🛑 Vault search {} cancellation requested", search_id);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Run a search on the blocking pool while it is registered for cancellation
+async fn run_vault_search<T, F>(search_id: &str, operation: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(Arc<CancellationToken>) -> Result<T, String> + Send + 'static,
+{
+    if search_id.trim().is_empty() {
+        return Err("Search ID cannot be empty".to_string());
+    }
+
+    let token = Arc::new(CancellationToken::new());
+    {
+        let mut searches = ACTIVE_VAULT_SEARCHES.write().await;
+        if searches.contains_key(search_id) {
+            return Err(format!("A search with ID '{}' is already running", search_id));
+        }
+        searches.insert(search_id.to_string(), Arc::clone(&token));
+    }
+
+    let result = tokio::task::spawn_blocking(move || operation(token))
+        .await
+        .unwrap_or_else(|e| Err(format!("Vault search task failed: {}", e)));
+
+    ACTIVE_VAULT_SEARCHES.write().await.remove(search_id);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_vault_search_registry() {
+        let vault = tempfile::TempDir::new().unwrap();
+        std::fs::write(vault.path().join("note.md"), "alpha beta\n").unwrap();
+        let vault_path = vault.path().to_string_lossy().to_string();
+        let options = VaultSearchOptions { pattern: "beta".to_string(), ..VaultSearchOptions::default() };
+
+        let report = search_vault_text("registry-test".to_string(), vault_path.clone(), options.clone())
+            .await
+            .unwrap();
+        assert_eq!(report.total_matches, 1);
+
+        // Finished searches are unregistered and the ID can be reused
+        assert!(!cancel_vault_search("registry-test".to_string()).await.unwrap());
+        assert!(search_vault_text(String::new(), vault_path, options).await.is_err());
+    }
+}
@@ -2,11 +2,16 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
-use sha2::{Sha256, Digest};
 use lru::LruCache;
 use std::num::NonZeroUsize;
 use thiserror::Error;
 
+use async_trait::async_trait;
+
+use crate::embedding_generator::{EmbeddingError, EmbeddingResult};
+use crate::embedding_provider::EmbeddingProvider;
+use crate::embedding_store::{hash_text, EmbeddingStore, TextHash};
+
 /// Errors that can occur during cache operations
 #[derive(Error, Debug)]
 pub enum CacheError {
@@ -37,10 +42,17 @@ pub struct CacheConfig {
     pub ttl_seconds: u64,
     /// Whether to persist cache to disk
     pub persist_to_disk: bool,
-    /// Cache persistence file path
+    /// Data file of the persistent store (defaults to ~/.ainote/embedding_cache.bin)
     pub cache_file_path: Option<String>,
     /// Enable detailed cache metrics
     pub enable_metrics: bool,
+    /// Size cap of the persistent store in bytes
+    #[serde(default = "default_max_disk_bytes")]
+    pub max_disk_bytes: u64,
+}
+
+fn default_max_disk_bytes() -> u64 {
+    256 * 1024 * 1024
 }
 
 impl Default for CacheConfig {
@@ -49,8 +61,9 @@ impl Default for CacheConfig {
             max_entries: 1000,          // Store up to 1000 embeddings
             ttl_seconds: 3600,          // 1 hour TTL
             persist_to_disk: true,      // Persist across sessions
-            cache_file_path: None,      // Will be set to ~/.ainote/embedding_cache.json
+            cache_file_path: None,      // Will be set to ~/.ainote/embedding_cache.bin
             enable_metrics: true,       // Enable metrics by default
+            max_disk_bytes: default_max_disk_bytes(), // 256MB on disk
         }
     }
 }
@@ -110,6 +123,9 @@ impl CacheEntry {
 pub struct CacheMetrics {
     /// Total number of cache hits
     pub hits: u64,
+    /// Hits served by the persistent store after a memory miss
+    #[serde(default)]
+    pub disk_hits: u64,
     /// Total number of cache misses
     pub misses: u64,
     /// Total number of cache insertions
@@ -166,26 +182,23 @@ impl CacheMetrics {
 /// Cache key for embeddings based on text hash and model
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 struct CacheKey {
-    text_hash: String,
+    text_hash: TextHash,
     model_name: String,
 }
 
 impl CacheKey {
     /// Generate cache key from text and model name
     fn from_text_and_model(text: &str, model: &str) -> CacheResult<Self> {
-        let mut hasher = Sha256::new();
-        hasher.update(text.as_bytes());
-        let text_hash = format!("{:x}", hasher.finalize());
-        
         Ok(Self {
-            text_hash,
+            text_hash: hash_text(text),
             model_name: model.to_string(),
         })
     }
     
     /// Convert to string for LRU cache key
     fn as_string(&self) -> String {
-        format!("{}:{}", self.model_name, self.text_hash)
+        let text_hash: String = self.text_hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("{}:{}", self.model_name, text_hash)
     }
 }
 
@@ -197,6 +210,8 @@ pub struct EmbeddingCache {
     config: CacheConfig,
     /// Cache metrics
     metrics: Arc<RwLock<CacheMetrics>>,
+    /// Persistent store behind the in-memory LRU, if persistence is enabled
+    disk: Option<Arc<EmbeddingStore>>,
     /// Background cleanup task handle
     cleanup_handle: Option<tokio::task::JoinHandle<()>>,
 }
@@ -208,6 +223,7 @@ impl Clone for EmbeddingCache {
             cache: self.cache.clone(),
             config: self.config.clone(),
             metrics: self.metrics.clone(),
+            disk: self.disk.clone(),
             cleanup_handle: None,
         }
     }
@@ -231,23 +247,20 @@ impl EmbeddingCache {
         let cache = Arc::new(RwLock::new(LruCache::new(cache_size)));
         let metrics = Arc::new(RwLock::new(CacheMetrics::default()));
         
+        // The persistent store is only read on first use
+        let disk = Self::open_disk_store(&config);
+        
         let mut cache_instance = Self {
             cache,
             config,
             metrics,
+            disk,
             cleanup_handle: None,
         };
         
         // Start background cleanup task
         cache_instance.start_cleanup_task();
         
-        // Load persisted cache if enabled
-        if cache_instance.config.persist_to_disk {
-            if let Err(e) = cache_instance.load_from_disk() {
-                eprintln!("⚠️ Failed to load cache from disk: {}", e);
-            }
-        }
-        
         cache_instance
     }
     
@@ -256,36 +269,48 @@ impl EmbeddingCache {
         let cache_key = CacheKey::from_text_and_model(text, model)?;
         let key_str = cache_key.as_string();
         
-        let mut cache = self.cache.write().await;
-        
-        if let Some(entry) = cache.get_mut(&key_str) {
-            // Check if entry has expired
-            if entry.is_expired() {
-                cache.pop(&key_str);
+        {
+            let mut cache = self.cache.write().await;
+            
+            if let Some(entry) = cache.get_mut(&key_str) {
+                // Check if entry has expired
+                if !entry.is_expired() {
+                    // Mark as accessed and return
+                    entry.mark_accessed();
+                    
+                    // Update metrics
+                    if self.config.enable_metrics {
+                        let mut metrics = self.metrics.write().await;
+                        metrics.hits += 1;
+                        metrics.update_hit_rate();
+                    }
+                    
+                    eprintln!("✅ Cache HIT for model '{}' (text length: {})", model, text.len());
+                    return Ok(Some(entry.embedding.clone()));
+                }
                 
-                // Update metrics
+                cache.pop(&key_str);
                 if self.config.enable_metrics {
-                    let mut metrics = self.metrics.write().await;
-                    metrics.expirations += 1;
-                    metrics.misses += 1;
-                    metrics.update_hit_rate();
+                    self.metrics.write().await.expirations += 1;
                 }
-                
-                return Ok(None);
             }
+        }
+        
+        // Memory miss, fall back to the persistent store without holding the cache lock
+        if let Some(embedding) = self.get_from_disk(&cache_key).await {
+            self.cache.write().await.put(
+                key_str,
+                CacheEntry::new(embedding.clone(), self.config.ttl_seconds, model.to_string(), text.len()),
+            );
             
-            // Mark as accessed and return
-            entry.mark_accessed();
-            
-            // Update metrics
             if self.config.enable_metrics {
                 let mut metrics = self.metrics.write().await;
                 metrics.hits += 1;
+                metrics.disk_hits += 1;
                 metrics.update_hit_rate();
             }
             
-            eprintln!("✅ Cache HIT for model '{}' (text length: {})", model, text.len());
-            return Ok(Some(entry.embedding.clone()));
+            return Ok(Some(embedding));
         }
         
         // Cache miss
@@ -305,6 +330,22 @@ impl EmbeddingCache {
         let key_str = cache_key.as_string();
         
         let embedding_size = embedding.len();
+        if let Some(disk) = &self.disk {
+            // A put can compact and rewrite the store, so it runs off the async runtime
+            let (disk, model_id, text_hash, vector) =
+                (Arc::clone(disk), model.to_string(), cache_key.text_hash, embedding.clone());
+            let persisted = tokio::task::spawn_blocking(move || {
+                disk.put(&model_id, &text_hash, &vector)
+                    .map_err(|e| format!("Failed to persist embedding to {:?}: {}", disk.data_path(), e))
+            })
+            .await;
+            match persisted {
+                Ok(Ok(())) => {}
+                Ok(Err(message)) => eprintln!("⚠️ {}", message),
+                Err(e) => eprintln!("⚠️ Failed to persist embedding: {}", e),
+            }
+        }
+        
         let entry = CacheEntry::new(
             embedding, 
             self.config.ttl_seconds, 
@@ -335,25 +376,93 @@ impl EmbeddingCache {
         eprintln!("💾 Cached embedding for model '{}' (text length: {}, vector size: {})", 
                   model, text.len(), embedding_size);
         
-        // Persist to disk if enabled
-        if self.config.persist_to_disk {
-            // Non-blocking persistence
-            let cache_clone = self.cache.clone();
-            let config_clone = self.config.clone();
-            tokio::spawn(async move {
-                if let Err(e) = Self::persist_cache_async(cache_clone, config_clone).await {
-                    eprintln!("⚠️ Failed to persist cache to disk: {}", e);
+        Ok(())
+    }
+    
+    /// Get an embedding from cache, embedding and caching it through `provider` on a miss
+    ///
+    /// Entries are keyed by the provider's model ID, so vectors from different
+    /// backends never mix. Cache failures fall back to the provider.
+    pub async fn get_or_embed(
+        &self,
+        provider: &dyn EmbeddingProvider,
+        text: &str,
+        model: &str,
+    ) -> EmbeddingResult<Vec<f32>> {
+        let model_id = provider.model_id(model);
+        if let Ok(Some(embedding)) = self.get(text, &model_id).await {
+            return Ok(embedding);
+        }
+
+        let start_time = std::time::Instant::now();
+        let embedding = provider.embed(text, model).await?;
+        if self.config.enable_metrics {
+            let mut metrics = self.metrics.write().await;
+            metrics.update_avg_generation_time(start_time.elapsed().as_secs_f64() * 1000.0);
+        }
+
+        if let Err(e) = self.set(text, &model_id, embedding.clone()).await {
+            eprintln!("⚠️ Failed to cache embedding: {}", e);
+        }
+        Ok(embedding)
+    }
+    
+    /// Get embeddings for several texts, embedding only the cache misses in one provider batch
+    ///
+    /// A [`EmbeddingError::BatchItem`] from the provider is re-indexed to the
+    /// position of the failing text in `texts`.
+    pub async fn get_or_embed_batch(
+        &self,
+        provider: &dyn EmbeddingProvider,
+        texts: &[String],
+        model: &str,
+    ) -> EmbeddingResult<Vec<Vec<f32>>> {
+        let model_id = provider.model_id(model);
+        let mut embeddings = vec![Vec::new(); texts.len()];
+        let mut missing = Vec::new();
+        for (index, text) in texts.iter().enumerate() {
+            match self.get(text, &model_id).await {
+                Ok(Some(embedding)) => embeddings[index] = embedding,
+                _ => missing.push(index),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(embeddings);
+        }
+
+        let start_time = std::time::Instant::now();
+        let missing_texts: Vec<String> = missing.iter().map(|&index| texts[index].clone()).collect();
+        let generated = provider.embed_batch(&missing_texts, model).await.map_err(|e| match e {
+            EmbeddingError::BatchItem { index, source } => EmbeddingError::BatchItem { index: missing[index], source },
+            e => e,
+        })?;
+        if self.config.enable_metrics {
+            let mut metrics = self.metrics.write().await;
+            metrics.update_avg_generation_time(start_time.elapsed().as_secs_f64() * 1000.0);
+        }
+
+        for (&index, embedding) in missing.iter().zip(generated) {
+            if !embedding.is_empty() {
+                if let Err(e) = self.set(&texts[index], &model_id, embedding.clone()).await {
+                    eprintln!("⚠️ Failed to cache embedding: {}", e);
                 }
-            });
+            }
+            embeddings[index] = embedding;
         }
-        
-        Ok(())
+        Ok(embeddings)
     }
     
     /// Clear all entries from cache
     pub async fn clear(&self) -> CacheResult<()> {
-        let mut cache = self.cache.write().await;
-        cache.clear();
+        self.cache.write().await.clear();
+        
+        if let Some(disk) = &self.disk {
+            let disk = Arc::clone(disk);
+            tokio::task::spawn_blocking(move || disk.clear())
+                .await
+                .map_err(|e| CacheError::OperationFailed { message: e.to_string() })?
+                .map_err(|e| CacheError::OperationFailed { message: e.to_string() })?;
+        }
         
         // Reset metrics
         if self.config.enable_metrics {
@@ -386,8 +495,16 @@ impl EmbeddingCache {
         let cache_key = CacheKey::from_text_and_model(text, model)?;
         let key_str = cache_key.as_string();
         
-        let cache = self.cache.read().await;
-        let contains = cache.peek(&key_str).is_some();
+        if self.cache.read().await.peek(&key_str).is_some() {
+            return Ok(true);
+        }
+        let Some(disk) = &self.disk else {
+            return Ok(false);
+        };
+        let (disk, model_id, text_hash) = (Arc::clone(disk), model.to_string(), cache_key.text_hash);
+        let contains = tokio::task::spawn_blocking(move || disk.contains(&model_id, &text_hash).unwrap_or(false))
+            .await
+            .unwrap_or(false);
         Ok(contains)
     }
     
@@ -463,44 +580,57 @@ impl EmbeddingCache {
         self.cleanup_handle = Some(handle);
     }
     
-    /// Load cache from disk (if persistence enabled)
-    fn load_from_disk(&self) -> CacheResult<()> {
-        if let Some(cache_file) = self.get_cache_file_path() {
-            // Implementation would load from file - simplified for now
-            eprintln!("📂 Loading cache from {}", cache_file);
+    /// Look up an entry in the persistent store
+    ///
+    /// The store loads lazily and reads files behind a blocking mutex, so the
+    /// lookup runs on the blocking pool.
+    async fn get_from_disk(&self, cache_key: &CacheKey) -> Option<Vec<f32>> {
+        let disk = Arc::clone(self.disk.as_ref()?);
+        let (model_id, text_hash) = (cache_key.model_name.clone(), cache_key.text_hash);
+        let lookup = tokio::task::spawn_blocking(move || {
+            disk.get(&model_id, &text_hash)
+                .map_err(|e| format!("Failed to read embedding from {:?}: {}", disk.data_path(), e))
+        })
+        .await;
+        match lookup {
+            Ok(Ok(embedding)) => embedding,
+            Ok(Err(message)) => {
+                eprintln!("⚠️ {}", message);
+                None
+            }
+            Err(e) => {
+                eprintln!("⚠️ Failed to read embedding: {}", e);
+                None
+            }
         }
-        Ok(())
     }
     
-    /// Persist cache to disk asynchronously
-    async fn persist_cache_async(
-        cache: Arc<RwLock<LruCache<String, CacheEntry>>>, 
-        config: CacheConfig
-    ) -> CacheResult<()> {
-        if let Some(cache_file) = Self::get_cache_file_path_static(&config) {
-            // Implementation would save to file - simplified for now
-            let cache_guard = cache.read().await;
-            eprintln!("💾 Persisting {} cache entries to {}", cache_guard.len(), cache_file);
+    /// Create the persistent store described by `config`, if persistence is enabled
+    fn open_disk_store(config: &CacheConfig) -> Option<Arc<EmbeddingStore>> {
+        if !config.persist_to_disk {
+            return None;
         }
-        Ok(())
-    }
-    
-    /// Get cache file path
-    fn get_cache_file_path(&self) -> Option<String> {
-        Self::get_cache_file_path_static(&self.config)
+        Self::get_cache_file_path_static(config)
+            .map(|path| Arc::new(EmbeddingStore::new(path, config.max_disk_bytes)))
     }
     
     /// Get cache file path (static version)
     fn get_cache_file_path_static(config: &CacheConfig) -> Option<String> {
         config.cache_file_path.clone().or_else(|| {
             dirs::home_dir().map(|home| {
-                home.join(".ainote").join("embedding_cache.json").to_string_lossy().to_string()
+                home.join(".ainote").join("embedding_cache.bin").to_string_lossy().to_string()
             })
         })
     }
     
     /// Update cache configuration
     pub fn update_config(&mut self, new_config: CacheConfig) {
+        let disk_changed = new_config.persist_to_disk != self.config.persist_to_disk
+            || new_config.cache_file_path != self.config.cache_file_path
+            || new_config.max_disk_bytes != self.config.max_disk_bytes;
+        if disk_changed {
+            self.disk = Self::open_disk_store(&new_config);
+        }
         self.config = new_config;
     }
     
@@ -510,6 +640,41 @@ impl EmbeddingCache {
     }
 }
 
+/// Embedding provider that serves texts from an [`EmbeddingCache`] before asking its inner provider
+///
+/// Wrapping the provider used for indexing means unchanged text is never sent
+/// to the model again, even after a restart when the cache persists to disk.
+pub struct CachedEmbeddingProvider {
+    inner: Arc<dyn EmbeddingProvider>,
+    cache: EmbeddingCache,
+}
+
+impl CachedEmbeddingProvider {
+    /// Serve `inner`'s embeddings through `cache`
+    pub fn new(inner: Arc<dyn EmbeddingProvider>, cache: EmbeddingCache) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CachedEmbeddingProvider {
+    async fn embed(&self, text: &str, model: &str) -> EmbeddingResult<Vec<f32>> {
+        self.cache.get_or_embed(self.inner.as_ref(), text, model).await
+    }
+
+    async fn embed_batch(&self, texts: &[String], model: &str) -> EmbeddingResult<Vec<Vec<f32>>> {
+        self.cache.get_or_embed_batch(self.inner.as_ref(), texts, model).await
+    }
+
+    fn dimension(&self, model: &str) -> Option<usize> {
+        self.inner.dimension(model)
+    }
+
+    fn model_id(&self, model: &str) -> String {
+        self.inner.model_id(model)
+    }
+}
+
 impl Drop for EmbeddingCache {
     fn drop(&mut self) {
         // Cancel background cleanup task
@@ -522,6 +687,7 @@ impl Drop for EmbeddingCache {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding_provider::HashingEmbeddingProvider;
     
     #[tokio::test]
     async fn test_cache_creation() {
@@ -539,9 +705,13 @@ mod tests {
         assert_ne!(key1, key3);
     }
     
+    fn memory_cache() -> EmbeddingCache {
+        EmbeddingCache::with_config(CacheConfig { persist_to_disk: false, ..CacheConfig::default() })
+    }
+    
     #[tokio::test]
     async fn test_cache_set_and_get() {
-        let cache = EmbeddingCache::new();
+        let cache = memory_cache();
         let embedding = vec![0.1, 0.2, 0.3, 0.4];
         
         // Cache miss initially
@@ -559,10 +729,28 @@ mod tests {
         assert_eq!(cache.size().await, 1);
     }
     
+    #[tokio::test]
+    async fn test_get_or_embed_keys_by_provider() {
+        let cache = memory_cache();
+        let small = HashingEmbeddingProvider::new(8);
+        let large = HashingEmbeddingProvider::new(16);
+        
+        let first = cache.get_or_embed(&small, "cached text", "test-model").await.unwrap();
+        let second = cache.get_or_embed(&small, "cached text", "test-model").await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.get_metrics().await.hits, 1);
+        
+        // Same model name through another provider is a separate entry
+        let other = cache.get_or_embed(&large, "cached text", "test-model").await.unwrap();
+        assert_eq!(other.len(), 16);
+        assert_eq!(cache.size().await, 2);
+    }
+    
     #[tokio::test]
     async fn test_cache_expiration() {
         let mut config = CacheConfig::default();
         config.ttl_seconds = 1; // 1 second TTL
+        config.persist_to_disk = false;
         
         let cache = EmbeddingCache::with_config(config);
         let embedding = vec![0.1, 0.2, 0.3];
@@ -584,7 +772,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_cache_metrics() {
-        let cache = EmbeddingCache::new();
+        let cache = memory_cache();
         let embedding = vec![0.1, 0.2];
         
         // Initial metrics
@@ -611,7 +799,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_cache_clear() {
-        let cache = EmbeddingCache::new();
+        let cache = memory_cache();
         let embedding = vec![0.1, 0.2, 0.3];
         
         // Add entries
@@ -652,4 +840,55 @@ mod tests {
         // Should be expired
         assert!(expired_entry.is_expired());
     }
+    
+    #[tokio::test]
+    async fn test_persistent_store_survives_restart() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        /// Counts the texts that reach the provider
+        struct CountingProvider {
+            inner: HashingEmbeddingProvider,
+            embedded: AtomicUsize,
+        }
+        
+        #[async_trait]
+        impl EmbeddingProvider for CountingProvider {
+            async fn embed(&self, text: &str, model: &str) -> EmbeddingResult<Vec<f32>> {
+                self.embedded.fetch_add(1, Ordering::SeqCst);
+                self.inner.embed(text, model).await
+            }
+            
+            fn dimension(&self, model: &str) -> Option<usize> {
+                self.inner.dimension(model)
+            }
+            
+            fn model_id(&self, model: &str) -> String {
+                self.inner.model_id(model)
+            }
+        }
+        
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = CacheConfig {
+            cache_file_path: Some(temp_dir.path().join("embedding_cache.bin").to_string_lossy().to_string()),
+            ..CacheConfig::default()
+        };
+        let provider = Arc::new(CountingProvider { inner: HashingEmbeddingProvider::new(8), embedded: AtomicUsize::new(0) });
+        let texts = vec!["first chunk".to_string(), "second chunk".to_string()];
+        
+        let cached = CachedEmbeddingProvider::new(provider.clone(), EmbeddingCache::with_config(config.clone()));
+        let before = cached.embed_batch(&texts, "test-model").await.unwrap();
+        assert_eq!(provider.embedded.load(Ordering::SeqCst), 2);
+        drop(cached);
+        
+        // A fresh cache on the same file serves both texts without the provider
+        let restarted = EmbeddingCache::with_config(config);
+        let cached = CachedEmbeddingProvider::new(provider.clone(), restarted.clone());
+        let after = cached.embed_batch(&texts, "test-model").await.unwrap();
+        assert_eq!(after, before);
+        assert_eq!(provider.embedded.load(Ordering::SeqCst), 2);
+        assert_eq!(restarted.get_metrics().await.disk_hits, 2);
+        
+        restarted.clear().await.unwrap();
+        assert!(!restarted.contains("first chunk", &provider.model_id("test-model")).await.unwrap());
+    }
 }
\ No newline at end of file
@@ -6,6 +6,7 @@ use thiserror::Error;
 use tokio::sync::RwLock;
 
 use crate::ollama_client::{OllamaConfig, OllamaClientError};
+use crate::embedding_provider::ObservedDimensions;
 use crate::text_processing::{TextProcessor, TextProcessingError};
 
 /// Errors that can occur during embedding generation
@@ -37,6 +38,16 @@ pub enum EmbeddingError {
     
     #[error("Connection pool error: {message}")]
     ConnectionPool { message: String },
+    
+    #[error("Batch item {index} failed: {source}")]
+    BatchItem { index: usize, source: Box<EmbeddingError> },
+}
+
+impl EmbeddingError {
+    /// Attribute an error to the input at `index` of a batch
+    pub fn batch_item(index: usize, error: EmbeddingError) -> Self {
+        Self::BatchItem { index, source: Box::new(error) }
+    }
 }
 
 pub type EmbeddingResult<T> = Result<T, EmbeddingError>;
@@ -71,16 +82,16 @@ struct EmbeddingResponse {
     prompt: Option<String>,
 }
 
-/// Batch embedding request for multiple texts
+/// Request payload for Ollama's `/api/embed` endpoint, which embeds several inputs at once
 #[derive(Debug, Serialize)]
-struct BatchEmbeddingRequest {
-    model: String,
-    prompts: Vec<String>,
+struct BatchEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
     #[serde(skip_serializing_if = "Option::is_none")]
     options: Option<EmbeddingOptions>,
 }
 
-/// Response for batch embedding requests
+/// Response from Ollama's `/api/embed` endpoint
 #[derive(Debug, Deserialize)]
 struct BatchEmbeddingResponse {
     embeddings: Vec<Vec<f32>>,
@@ -102,7 +113,7 @@ pub struct EmbeddingConfig {
     pub preprocess_text: bool,
     /// Maximum text length for single embedding
     pub max_text_length: usize,
-    /// Batch size for multiple embedding requests
+    /// Maximum number of texts sent in one `/api/embed` request
     pub batch_size: usize,
     /// Connection warmup on startup
     pub warmup_connections: bool,
@@ -143,6 +154,10 @@ pub struct EmbeddingGenerator {
     text_processor: TextProcessor,
     /// Network performance metrics for adaptive timeout
     network_metrics: Arc<RwLock<NetworkMetrics>>,
+    /// Per-text timings of `/api/embed` batches, kept apart from single requests
+    batch_metrics: Arc<RwLock<NetworkMetrics>>,
+    /// Vector dimensions returned so far, per model
+    dimensions: ObservedDimensions,
 }
 
 /// Network performance tracking for adaptive optimization
@@ -237,6 +252,8 @@ impl EmbeddingGenerator {
             embedding_config: embedding_config.clone(),
             text_processor: TextProcessor::new(),
             network_metrics: network_metrics.clone(),
+            batch_metrics: Arc::new(RwLock::new(NetworkMetrics::default())),
+            dimensions: ObservedDimensions::new(),
         };
 
         // Start connection warmup if enabled
@@ -316,7 +333,12 @@ impl EmbeddingGenerator {
         }))
     }
     
-    /// Generate embeddings for multiple texts in batch
+    /// Generate embeddings for multiple texts, in input order
+    ///
+    /// Texts are sent to Ollama's `/api/embed` endpoint `batch_size` at a time.
+    /// When Ollama rejects a batch, its texts are embedded one by one so the
+    /// failing input can be reported as [`EmbeddingError::BatchItem`] with its
+    /// index. Empty texts are not sent and get an empty vector.
     pub async fn generate_batch_embeddings(
         &self, 
         texts: Vec<String>, 
@@ -331,70 +353,77 @@ impl EmbeddingGenerator {
         // Validate model name
         self.validate_model_name(&model)?;
         
-        // Process texts in batches
-        let mut all_embeddings = Vec::new();
-        let batch_size = self.embedding_config.batch_size;
-        
-        for (batch_idx, batch_texts) in texts.chunks(batch_size).enumerate() {
-            eprintln!("🔄 Processing batch {} of {} (size: {})", 
-                      batch_idx + 1, 
-                      texts.len().div_ceil(batch_size), 
-                      batch_texts.len());
+        // Preprocess and validate every text before the first request
+        let mut embeddings = vec![Vec::new(); texts.len()];
+        let mut pending = Vec::with_capacity(texts.len());
+        for (index, text) in texts.into_iter().enumerate() {
+            if text.trim().is_empty() {
+                log::warn!("⚠️ Skipping empty text at index {}", index);
+                continue;
+            }
             
-            // Preprocess texts in parallel if enabled
-            let processed_batch: Result<Vec<String>, TextProcessingError> = if self.embedding_config.preprocess_text {
-                batch_texts.iter()
-                    .map(|text| self.text_processor.preprocess_text(text.clone()))
-                    .collect()
+            let text = if self.embedding_config.preprocess_text {
+                self.text_processor.preprocess_text(text)
+                    .map_err(|e| EmbeddingError::batch_item(index, e.into()))?
             } else {
-                Ok(batch_texts.to_vec())
+                text
             };
             
-            let processed_batch = processed_batch?;
-            
-            // Validate batch texts
-            for (i, text) in processed_batch.iter().enumerate() {
-                if text.trim().is_empty() {
-                    eprintln!("⚠️ Skipping empty text at index {}", batch_idx * batch_size + i);
-                    continue;
-                }
-                
-                if text.len() > self.embedding_config.max_text_length {
-                    return Err(EmbeddingError::TextProcessing(
-                        TextProcessingError::TextTooLong {
-                            length: text.len(),
-                            max_length: self.embedding_config.max_text_length,
-                        }
-                    ));
-                }
+            if text.len() > self.embedding_config.max_text_length {
+                return Err(EmbeddingError::batch_item(index, EmbeddingError::TextProcessing(
+                    TextProcessingError::TextTooLong {
+                        length: text.len(),
+                        max_length: self.embedding_config.max_text_length,
+                    }
+                )));
             }
             
-            // Try batch request first, fallback to individual requests
-            match self.generate_batch_embedding_request(&processed_batch, &model).await {
+            pending.push((index, text));
+        }
+        
+        let batch_size = self.embedding_config.batch_size.max(1);
+        let batch_count = pending.len().div_ceil(batch_size);
+        for (batch_idx, batch) in pending.chunks(batch_size).enumerate() {
+            log::debug!("🔄 Embedding batch {} of {} (size: {})", batch_idx + 1, batch_count, batch.len());
+            
+            let batch_texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+            let mut attempt = 0;
+            let batch_result = loop {
+                match self.generate_batch_embedding_request(&batch_texts, &model).await {
+                    Err(e @ (EmbeddingError::Network(_) | EmbeddingError::Timeout { .. }))
+                        if attempt < self.embedding_config.max_retries =>
+                    {
+                        // Exponential backoff, as for single embeddings
+                        let delay = Duration::from_millis(1000 * (2_u64.pow(attempt as u32)));
+                        log::warn!("⚠️ Batch request failed on attempt {}, retrying in {:?}: {}", attempt + 1, delay, e);
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    result => break result,
+                }
+            };
+            match batch_result {
                 Ok(batch_embeddings) => {
-                    all_embeddings.extend(batch_embeddings);
+                    for ((index, _), embedding) in batch.iter().zip(batch_embeddings) {
+                        embeddings[*index] = embedding;
+                    }
                 }
-                Err(e) => {
-                    eprintln!("⚠️ Batch request failed, falling back to individual requests: {}", e);
-                    
-                    // Fallback: Generate embeddings individually
-                    for text in processed_batch {
-                        if text.trim().is_empty() {
-                            all_embeddings.push(Vec::new()); // Empty embedding for empty text
-                            continue;
-                        }
-                        
-                        let embedding = self.generate_single_embedding_request(&text, &model).await?;
-                        all_embeddings.push(embedding);
+                // Ollama rejected the batch, find out which input it objects to
+                Err(e @ (EmbeddingError::Api { .. } | EmbeddingError::InvalidResponse { .. })) => {
+                    log::warn!("⚠️ Batch request failed, embedding its {} texts individually: {}", batch.len(), e);
+                    for (index, text) in batch {
+                        embeddings[*index] = self.generate_single_embedding_request(text, &model).await
+                            .map_err(|e| EmbeddingError::batch_item(*index, e))?;
                     }
                 }
+                Err(e) => return Err(e),
             }
         }
         
         let duration = start_time.elapsed();
-        log::info!("✅ Generated {} embeddings in {:?}", all_embeddings.len(), duration);
+        log::info!("✅ Generated {} embeddings with {} batch requests in {:?}", pending.len(), batch_count, duration);
         
-        Ok(all_embeddings)
+        Ok(embeddings)
     }
     
     /// Generate a single embedding via HTTP request with adaptive timeout
@@ -468,42 +497,59 @@ impl EmbeddingGenerator {
         Ok(embedding_response.embedding)
     }
     
-    /// Generate multiple embeddings via batch HTTP request
+    /// Generate multiple embeddings via one `/api/embed` request with a timeout scaled to the batch size
     async fn generate_batch_embedding_request(&self, texts: &[String], model: &str) -> EmbeddingResult<Vec<Vec<f32>>> {
-        // Note: Ollama API might not support batch requests yet, so this will try and fallback gracefully
+        let request_start = Instant::now();
+        
         let request = BatchEmbeddingRequest {
-            model: model.to_string(),
-            prompts: texts.to_vec(),
+            model,
+            input: texts,
             options: None,
         };
         
-        let url = format!("{}/api/embeddings/batch", self.ollama_config.base_url);
+        let url = format!("{}/api/embed", self.ollama_config.base_url);
+        
+        // A batch costs about one request per text, so the per-text timeout scales with its size
+        let per_text_timeout_ms = if self.embedding_config.adaptive_timeout {
+            let metrics = self.batch_metrics.read().await;
+            metrics.calculate_adaptive_timeout(self.embedding_config.timeout_ms)
+        } else {
+            self.embedding_config.timeout_ms
+        };
+        let timeout_ms = per_text_timeout_ms.saturating_mul(texts.len().max(1) as u64);
         
-        let response = self.client
-            .post(&url)
-            .json(&request)
-            .send()
-            .await?;
+        // The client's own timeout is sized for single requests, so the batch overrides it
+        let response = tokio::time::timeout(
+            Duration::from_millis(timeout_ms),
+            self.client
+                .post(&url)
+                .timeout(Duration::from_millis(timeout_ms))
+                .json(&request)
+                .send()
+        ).await
+        .map_err(|_| EmbeddingError::Timeout { duration_ms: timeout_ms })?
+        .map_err(EmbeddingError::from)?;
+        
+        let per_text_latency = request_start.elapsed().as_millis() as f64 / texts.len().max(1) as f64;
             
         if !response.status().is_success() {
-            let status_code = response.status().as_u16();
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            
-            // If batch endpoint doesn't exist, return a specific error for fallback
-            if status_code == 404 {
-                return Err(EmbeddingError::Api {
-                    status_code,
-                    message: "Batch embedding endpoint not available, using individual requests".to_string(),
-                });
+            // Update metrics with failure
+            {
+                let mut metrics = self.batch_metrics.write().await;
+                metrics.update_request_timing(per_text_latency, false);
             }
             
+            let status_code = response.status().as_u16();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
             return Err(EmbeddingError::Api {
                 status_code,
                 message: error_text,
             });
         }
         
-        let batch_response: BatchEmbeddingResponse = response.json().await?;
+        let batch_response: BatchEmbeddingResponse = response.json().await.map_err(|e| {
+            EmbeddingError::InvalidResponse { reason: format!("Failed to parse batch response: {}", e) }
+        })?;
         
         // Validate batch response
         if batch_response.embeddings.len() != texts.len() {
@@ -524,6 +570,12 @@ impl EmbeddingGenerator {
             }
         }
         
+        // Update metrics with success
+        {
+            let mut metrics = self.batch_metrics.write().await;
+            metrics.update_request_timing(per_text_latency, true);
+        }
+        
         Ok(batch_response.embeddings)
     }
     
@@ -556,6 +608,11 @@ impl EmbeddingGenerator {
         Ok(())
     }
     
+    /// Vector dimensions returned so far, per model
+    pub(crate) fn observed_dimensions(&self) -> &ObservedDimensions {
+        &self.dimensions
+    }
+    
     /// Update the Ollama configuration
     pub fn update_ollama_config(&mut self, config: OllamaConfig) {
         self.ollama_config = config;
@@ -651,6 +708,11 @@ impl EmbeddingGenerator {
     pub async fn get_network_metrics(&self) -> NetworkMetrics {
         self.network_metrics.read().await.clone()
     }
+
+    /// Get per-text performance metrics of batch requests
+    pub async fn get_batch_metrics(&self) -> NetworkMetrics {
+        self.batch_metrics.read().await.clone()
+    }
 }
 
 #[cfg(test)]
@@ -727,6 +789,41 @@ mod tests {
         assert_eq!(result.unwrap().len(), 0);
     }
     
+    #[tokio::test]
+    async fn test_batch_timeout_scales_with_batch_size() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .respond_with(|request: &Request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let inputs = body["input"].as_array().unwrap().len();
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"embeddings": vec![vec![0.6f32, 0.8]; inputs]}))
+                    .set_delay(Duration::from_millis(600))
+            })
+            .mount(&server)
+            .await;
+        let generator = EmbeddingGenerator::with_config(
+            OllamaConfig { base_url: server.uri(), ..Default::default() },
+            EmbeddingConfig { timeout_ms: 300, warmup_connections: false, health_check_interval_seconds: 0, ..Default::default() },
+        );
+
+        // Four texts get four single-request timeouts, enough for the slow response
+        let texts: Vec<String> = (0..4).map(|i| format!("text {}", i)).collect();
+        let embeddings = generator.generate_batch_embedding_request(&texts, "nomic-embed-text").await.unwrap();
+        assert_eq!(embeddings.len(), 4);
+
+        // A single text only gets one
+        assert!(generator.generate_batch_embedding_request(&texts[..1], "nomic-embed-text").await.is_err());
+
+        // Batch timings do not skew the single-request metrics
+        assert_eq!(generator.get_batch_metrics().await.success_count, 1);
+        assert_eq!(generator.get_network_metrics().await.success_count, 0);
+    }
+    
     #[test]
     fn test_embedding_config_defaults() {
         let config = EmbeddingConfig::default();
@@ -0,0 +1,480 @@
+//! # Embedding Providers
+//!
+//! This module defines the [`EmbeddingProvider`] trait that the indexing
+//! pipeline, the embedding queue and the embedding cache use to turn text
+//! into vectors, together with the available backends:
+//!
+//! - [`EmbeddingGenerator`]: Ollama's `/api/embeddings` endpoint
+//! - [`OpenAiCompatibleProvider`]: the OpenAI-compatible `/v1/embeddings`
+//!   endpoint served by llama.cpp server, LM Studio and similar tools
+//! - [`HashingEmbeddingProvider`]: deterministic feature hashing that runs
+//!   in-process, for offline tests and setups without a model server
+//!
+//! [`EmbeddingProviderConfig`] selects a backend at runtime.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::embedding_generator::{EmbeddingError, EmbeddingGenerator, EmbeddingResult};
+
+/// A backend that turns text into embedding vectors
+///
+/// The model is chosen per call so a single provider can serve every model
+/// namespace of a vault.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Generate the embedding of a single text
+    async fn embed(&self, text: &str, model: &str) -> EmbeddingResult<Vec<f32>>;
+
+    /// Generate embeddings for several texts, in input order
+    ///
+    /// The default implementation embeds the texts one at a time.
+    async fn embed_batch(&self, texts: &[String], model: &str) -> EmbeddingResult<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            embeddings.push(self.embed(text, model).await?);
+        }
+        Ok(embeddings)
+    }
+
+    /// Vector dimension produced for `model`, if known yet
+    ///
+    /// Remote providers only learn the dimension from their first response.
+    fn dimension(&self, model: &str) -> Option<usize>;
+
+    /// Identifier of the vectors this provider produces for `model`
+    ///
+    /// Two providers return the same identifier only if their vectors are
+    /// interchangeable, so it is safe to use as a cache key.
+    fn model_id(&self, model: &str) -> String;
+}
+
+/// Vector dimensions observed per model
+#[derive(Debug, Clone, Default)]
+pub struct ObservedDimensions {
+    dimensions: Arc<RwLock<HashMap<String, usize>>>,
+}
+
+impl ObservedDimensions {
+    /// Create an empty dimension record
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dimension last observed for `model`
+    pub fn get(&self, model: &str) -> Option<usize> {
+        self.dimensions.read().ok()?.get(model).copied()
+    }
+
+    /// Record the dimension of an embedding returned for `model`
+    pub fn record(&self, model: &str, embedding: &[f32]) {
+        if embedding.is_empty() {
+            return;
+        }
+        if let Ok(mut dimensions) = self.dimensions.write() {
+            dimensions.insert(model.to_string(), embedding.len());
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for EmbeddingGenerator {
+    async fn embed(&self, text: &str, model: &str) -> EmbeddingResult<Vec<f32>> {
+        let embedding = self.generate_embedding(text.to_string(), model.to_string()).await?;
+        self.observed_dimensions().record(model, &embedding);
+        Ok(embedding)
+    }
+
+    async fn embed_batch(&self, texts: &[String], model: &str) -> EmbeddingResult<Vec<Vec<f32>>> {
+        let embeddings = self.generate_batch_embeddings(texts.to_vec(), model.to_string()).await?;
+        if let Some(embedding) = embeddings.iter().find(|embedding| !embedding.is_empty()) {
+            self.observed_dimensions().record(model, embedding);
+        }
+        Ok(embeddings)
+    }
+
+    fn dimension(&self, model: &str) -> Option<usize> {
+        self.observed_dimensions().get(model)
+    }
+
+    fn model_id(&self, model: &str) -> String {
+        // Ollama was the only backend before providers existed, so its
+        // vectors keep the bare model name used by existing caches
+        model.to_string()
+    }
+}
+
+/// Configuration for an OpenAI-compatible embeddings server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OpenAiCompatibleConfig {
+    /// Server address, with or without the `/v1` suffix (e.g. "http://localhost:8080")
+    pub base_url: String,
+    /// Bearer token, for servers that require one
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Request timeout in milliseconds
+    #[serde(default = "default_openai_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Maximum number of texts sent in one request
+    #[serde(default = "default_openai_max_batch_size")]
+    pub max_batch_size: usize,
+}
+
+fn default_openai_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_openai_max_batch_size() -> usize {
+    32
+}
+
+impl Default for OpenAiCompatibleConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:8080".to_string(),
+            api_key: None,
+            timeout_ms: default_openai_timeout_ms(),
+            max_batch_size: default_openai_max_batch_size(),
+        }
+    }
+}
+
+/// Request payload for the OpenAI-compatible embeddings API
+#[derive(Debug, Serialize)]
+struct OpenAiEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// Response from the OpenAI-compatible embeddings API
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbeddingData>,
+}
+
+/// A single embedding in an OpenAI-compatible response
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: Option<usize>,
+}
+
+/// Provider for servers speaking the OpenAI-compatible `/v1/embeddings` protocol
+#[derive(Debug, Clone)]
+pub struct OpenAiCompatibleProvider {
+    client: Client,
+    config: OpenAiCompatibleConfig,
+    dimensions: ObservedDimensions,
+}
+
+impl OpenAiCompatibleProvider {
+    /// Create a provider for the server in `config`
+    pub fn new(config: OpenAiCompatibleConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Self {
+            client,
+            config,
+            dimensions: ObservedDimensions::new(),
+        }
+    }
+
+    /// Full URL of the embeddings endpoint
+    pub fn endpoint(&self) -> String {
+        let base_url = self.config.base_url.trim_end_matches('/');
+        if base_url.ends_with("/v1") {
+            format!("{}/embeddings", base_url)
+        } else {
+            format!("{}/v1/embeddings", base_url)
+        }
+    }
+
+    /// Embed one request's worth of texts
+    async fn request_embeddings(&self, texts: &[String], model: &str) -> EmbeddingResult<Vec<Vec<f32>>> {
+        let mut request = self.client
+            .post(self.endpoint())
+            .json(&OpenAiEmbeddingRequest { model, input: texts });
+        if let Some(api_key) = self.config.api_key.as_deref().filter(|key| !key.is_empty()) {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                EmbeddingError::Timeout { duration_ms: self.config.timeout_ms }
+            } else {
+                EmbeddingError::Network(e)
+            }
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(EmbeddingError::Api { status_code: status.as_u16(), message });
+        }
+
+        let mut body: OpenAiEmbeddingResponse = response.json().await.map_err(|e| {
+            EmbeddingError::InvalidResponse { reason: format!("Failed to parse embeddings response: {}", e) }
+        })?;
+
+        if body.data.len() != texts.len() {
+            return Err(EmbeddingError::InvalidResponse {
+                reason: format!("Expected {} embeddings, got {}", texts.len(), body.data.len()),
+            });
+        }
+        // The protocol allows data in any order, `index` ties it back to the input
+        if body.data.iter().all(|data| data.index.is_some()) {
+            body.data.sort_by_key(|data| data.index);
+        }
+        if body.data.iter().any(|data| data.embedding.is_empty()) {
+            return Err(EmbeddingError::InvalidResponse { reason: "Received empty embedding vector".to_string() });
+        }
+
+        Ok(body.data.into_iter().map(|data| data.embedding).collect())
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiCompatibleProvider {
+    async fn embed(&self, text: &str, model: &str) -> EmbeddingResult<Vec<f32>> {
+        let mut embeddings = self.embed_batch(&[text.to_string()], model).await?;
+        embeddings.pop().ok_or_else(|| EmbeddingError::InvalidResponse { reason: "No embedding returned".to_string() })
+    }
+
+    async fn embed_batch(&self, texts: &[String], model: &str) -> EmbeddingResult<Vec<Vec<f32>>> {
+        if texts.iter().any(|text| text.trim().is_empty()) {
+            return Err(EmbeddingError::EmptyText);
+        }
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.max_batch_size.max(1)) {
+            embeddings.extend(self.request_embeddings(batch, model).await?);
+        }
+        if let Some(embedding) = embeddings.first() {
+            self.dimensions.record(model, embedding);
+        }
+        Ok(embeddings)
+    }
+
+    fn dimension(&self, model: &str) -> Option<usize> {
+        self.dimensions.get(model)
+    }
+
+    fn model_id(&self, model: &str) -> String {
+        format!("openai:{}@{}", model, self.config.base_url.trim_end_matches('/'))
+    }
+}
+
+/// Default vector size of the hashing provider
+pub const DEFAULT_HASHING_DIMENSION: usize = 384;
+
+/// Deterministic in-process provider based on feature hashing
+///
+/// Each lowercased word and each pair of adjacent words is hashed into one of
+/// `dimension` buckets with a hash-derived sign, and the vector is normalized
+/// to unit length. Texts sharing vocabulary get similar vectors, which is
+/// enough for tests and offline use but no substitute for a trained model.
+#[derive(Debug, Clone)]
+pub struct HashingEmbeddingProvider {
+    dimension: usize,
+}
+
+impl Default for HashingEmbeddingProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASHING_DIMENSION)
+    }
+}
+
+impl HashingEmbeddingProvider {
+    /// Create a provider producing vectors of `dimension` entries
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension: dimension.max(1) }
+    }
+
+    /// Embed `text` without going through the async trait
+    pub fn embed_sync(&self, text: &str) -> EmbeddingResult<Vec<f32>> {
+        if text.trim().is_empty() {
+            return Err(EmbeddingError::EmptyText);
+        }
+
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect();
+
+        let mut vector = vec![0.0f32; self.dimension];
+        if words.is_empty() {
+            // Punctuation-only text still gets a stable non-zero vector
+            self.add_feature(&mut vector, text.trim(), 1.0);
+        }
+        for word in &words {
+            self.add_feature(&mut vector, word, 1.0);
+        }
+        for pair in words.windows(2) {
+            self.add_feature(&mut vector, &format!("{} {}", pair[0], pair[1]), 0.5);
+        }
+
+        let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|value| *value /= norm);
+        }
+        Ok(vector)
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature.as_bytes());
+        let bucket = (hash % self.dimension as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[bucket] += sign * weight;
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashingEmbeddingProvider {
+    async fn embed(&self, text: &str, _model: &str) -> EmbeddingResult<Vec<f32>> {
+        self.embed_sync(text)
+    }
+
+    async fn embed_batch(&self, texts: &[String], _model: &str) -> EmbeddingResult<Vec<Vec<f32>>> {
+        texts.iter().map(|text| self.embed_sync(text)).collect()
+    }
+
+    fn dimension(&self, _model: &str) -> Option<usize> {
+        Some(self.dimension)
+    }
+
+    fn model_id(&self, model: &str) -> String {
+        format!("hashing-{}:{}", self.dimension, model)
+    }
+}
+
+/// Selects the embedding backend
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EmbeddingProviderConfig {
+    /// Ollama, using the shared Ollama connection settings
+    #[default]
+    Ollama,
+    /// A server speaking the OpenAI-compatible `/v1/embeddings` protocol
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible(OpenAiCompatibleConfig),
+    /// The deterministic in-process hashing provider
+    Hashing {
+        #[serde(default = "default_hashing_dimension")]
+        dimension: usize,
+    },
+}
+
+fn default_hashing_dimension() -> usize {
+    DEFAULT_HASHING_DIMENSION
+}
+
+impl EmbeddingProviderConfig {
+    /// Check the settings before a provider is built from them
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Ollama => Ok(()),
+            Self::OpenAiCompatible(config) => {
+                if config.base_url.trim().is_empty() {
+                    return Err("Provider base URL cannot be empty".to_string());
+                }
+                if !config.base_url.starts_with("http://") && !config.base_url.starts_with("https://") {
+                    return Err(format!("Provider base URL must start with http:// or https://: {}", config.base_url));
+                }
+                if config.max_batch_size == 0 {
+                    return Err("Provider batch size must be greater than 0".to_string());
+                }
+                Ok(())
+            }
+            Self::Hashing { dimension } => {
+                if *dimension == 0 {
+                    return Err("Hashing dimension must be greater than 0".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Build the provider for a backend other than Ollama
+    ///
+    /// Returns `None` for Ollama, which is served by the shared
+    /// [`EmbeddingGenerator`] so it follows the Ollama connection settings.
+    pub fn build(&self) -> Option<Arc<dyn EmbeddingProvider>> {
+        match self {
+            Self::Ollama => None,
+            Self::OpenAiCompatible(config) => Some(Arc::new(OpenAiCompatibleProvider::new(config.clone()))),
+            Self::Hashing { dimension } => Some(Arc::new(HashingEmbeddingProvider::new(*dimension))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::similarity_search::SimilaritySearch;
+
+    #[tokio::test]
+    async fn test_hashing_provider_is_deterministic_and_normalized() {
+        let provider = HashingEmbeddingProvider::new(64);
+        let first = provider.embed("Rust ownership and borrowing", "test-model").await.unwrap();
+        let second = provider.embed("Rust ownership and borrowing", "test-model").await.unwrap();
+        let related = provider.embed("Ownership rules in Rust", "test-model").await.unwrap();
+        let unrelated = provider.embed("Banana bread recipe", "test-model").await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 64);
+        assert_eq!(provider.dimension("test-model"), Some(64));
+        let norm = first.iter().map(|value| value * value).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+
+        let related_score = SimilaritySearch::cosine_similarity(&first, &related).unwrap();
+        let unrelated_score = SimilaritySearch::cosine_similarity(&first, &unrelated).unwrap();
+        assert!(related_score > unrelated_score);
+
+        let batch = provider
+            .embed_batch(&["Rust ownership and borrowing".to_string(), "!!!".to_string()], "test-model")
+            .await
+            .unwrap();
+        assert_eq!(batch[0], first);
+        assert!(batch[1].iter().any(|value| *value != 0.0));
+        assert!(matches!(provider.embed("  ", "test-model").await, Err(EmbeddingError::EmptyText)));
+    }
+
+    #[test]
+    fn test_provider_config_and_model_ids() {
+        let config: EmbeddingProviderConfig = serde_json::from_str(
+            r#"{"type": "openai_compatible", "base_url": "http://localhost:1234/v1/"}"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        let EmbeddingProviderConfig::OpenAiCompatible(openai_config) = config else {
+            panic!("expected an OpenAI-compatible config");
+        };
+        assert_eq!(openai_config.max_batch_size, default_openai_max_batch_size());
+
+        let provider = OpenAiCompatibleProvider::new(openai_config);
+        assert_eq!(provider.endpoint(), "http://localhost:1234/v1/embeddings");
+        assert_eq!(provider.dimension("nomic-embed-text"), None);
+
+        let hashing = HashingEmbeddingProvider::new(32);
+        assert_ne!(provider.model_id("nomic-embed-text"), hashing.model_id("nomic-embed-text"));
+        assert!(EmbeddingProviderConfig::Hashing { dimension: 0 }.validate().is_err());
+        assert_eq!(EmbeddingProviderConfig::default(), EmbeddingProviderConfig::Ollama);
+    }
+}
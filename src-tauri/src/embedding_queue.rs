@@ -34,6 +34,7 @@ use thiserror::Error;
 use uuid::Uuid;
 
 use crate::embedding_generator::{EmbeddingGenerator, EmbeddingError};
+use crate::embedding_provider::EmbeddingProvider;
 use crate::ollama_client::OllamaConfig;
 
 /// Priority levels for embedding requests
@@ -232,7 +233,7 @@ struct QueueEntry {
 #[derive(Clone)]
 pub struct EmbeddingQueue {
     config: QueueConfig,
-    generator: Arc<EmbeddingGenerator>,
+    generator: Arc<dyn EmbeddingProvider>,
     
     // Queue management
     pending_queue: Arc<RwLock<VecDeque<QueueEntry>>>,
@@ -250,13 +251,13 @@ pub struct EmbeddingQueue {
 }
 
 impl EmbeddingQueue {
-    /// Create a new embedding queue with specified configuration
-    pub fn new(generator: EmbeddingGenerator, config: QueueConfig) -> Self {
+    /// Create a new embedding queue that embeds through `generator`
+    pub fn new(generator: Arc<dyn EmbeddingProvider>, config: QueueConfig) -> Self {
         let semaphore = Arc::new(Semaphore::new(config.max_concurrent_requests));
         
         Self {
             config,
-            generator: Arc::new(generator),
+            generator,
             pending_queue: Arc::new(RwLock::new(VecDeque::new())),
             active_requests: Arc::new(RwLock::new(HashMap::new())),
             completed_results: Arc::new(RwLock::new(HashMap::new())),
@@ -269,7 +270,7 @@ impl EmbeddingQueue {
     /// Create a new queue with default configuration
     pub fn with_default_config(ollama_config: OllamaConfig) -> Self {
         let generator = EmbeddingGenerator::new(ollama_config);
-        Self::new(generator, QueueConfig::default())
+        Self::new(Arc::new(generator), QueueConfig::default())
     }
 
     /// Start the background processing tasks
@@ -596,7 +597,7 @@ impl EmbeddingQueue {
                         
                         // Generate embedding
                         let embedding_result = generator_clone
-                            .generate_embedding(request.text.clone(), request.model.clone())
+                            .embed(&request.text, &request.model)
                             .await;
 
                         let processing_time = start_time.elapsed();
@@ -743,6 +744,7 @@ impl EmbeddingQueue {
 mod tests {
     use super::*;
     use crate::ollama_client::OllamaConfig;
+    use crate::embedding_provider::HashingEmbeddingProvider;
 
     fn create_test_config() -> QueueConfig {
         QueueConfig {
@@ -763,7 +765,7 @@ mod tests {
         let queue_config = create_test_config();
         let generator = EmbeddingGenerator::new(ollama_config);
         
-        let queue = EmbeddingQueue::new(generator, queue_config);
+        let queue = EmbeddingQueue::new(Arc::new(generator), queue_config);
         let metrics = queue.get_metrics().await;
         
         assert_eq!(metrics.total_requests, 0);
@@ -776,7 +778,7 @@ mod tests {
         let queue_config = create_test_config();
         let generator = EmbeddingGenerator::new(ollama_config);
         
-        let queue = EmbeddingQueue::new(generator, queue_config);
+        let queue = EmbeddingQueue::new(Arc::new(generator), queue_config);
         
         let request_id = queue.submit_request(
             "test text".to_string(),
@@ -796,7 +798,7 @@ mod tests {
         let queue_config = create_test_config();
         let generator = EmbeddingGenerator::new(ollama_config);
         
-        let queue = EmbeddingQueue::new(generator, queue_config);
+        let queue = EmbeddingQueue::new(Arc::new(generator), queue_config);
         
         let request_id = queue.submit_request(
             "test text".to_string(),
@@ -835,7 +837,7 @@ mod tests {
         let queue_config = create_test_config();
         let generator = EmbeddingGenerator::new(ollama_config);
         
-        let queue = EmbeddingQueue::new(generator, queue_config);
+        let queue = EmbeddingQueue::new(Arc::new(generator), queue_config);
         
         let initial_metrics = queue.get_metrics().await;
         assert_eq!(initial_metrics.total_requests, 0);
@@ -858,7 +860,7 @@ mod tests {
         queue_config.max_queue_size = 1; // Very small queue
         
         let generator = EmbeddingGenerator::new(ollama_config);
-        let queue = EmbeddingQueue::new(generator, queue_config);
+        let queue = EmbeddingQueue::new(Arc::new(generator), queue_config);
         
         // First request should succeed
         let result1 = queue.submit_request(
@@ -876,4 +878,21 @@ mod tests {
         ).await;
         assert!(matches!(result2, Err(QueueError::QueueFull { .. })));
     }
+
+    #[tokio::test]
+    async fn test_queue_with_local_provider() {
+        let provider = Arc::new(HashingEmbeddingProvider::new(16));
+        let queue = EmbeddingQueue::new(provider.clone(), create_test_config());
+        let (processor, cleanup) = queue.start().await;
+
+        let embedding = queue.submit_and_wait(
+            "offline text".to_string(),
+            "test-model".to_string(),
+            RequestPriority::High,
+        ).await.unwrap();
+        assert_eq!(embedding, provider.embed_sync("offline text").unwrap());
+
+        processor.abort();
+        cleanup.abort();
+    }
 }
\ No newline at end of file
@@ -0,0 +1,503 @@
+//! # Persistent Embedding Store
+//!
+//! A durable, content-addressed store for embedding vectors, keyed by the
+//! provider's model ID and the SHA-256 hash of the embedded text. It backs
+//! [`crate::embedding_cache::EmbeddingCache`] so unchanged text is never
+//! re-embedded, even across restarts.
+//!
+//! ## File Layout
+//!
+//! - `<name>.bin`: append-only log of records, each holding the model ID, the
+//!   32-byte text hash, the vector dimension and the little-endian `f32` values
+//! - `<name>.lru`: sidecar with the last-use tick of every key, used to decide
+//!   what to drop when the store outgrows its size cap
+//!
+//! The data file is scanned lazily on first use. When it exceeds the size cap
+//! the least recently used entries are dropped and the file is rewritten.
+//! A record cut short by a crash is truncated away on the next load.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Magic bytes at the start of the data file, including the format version
+const STORE_MAGIC: &[u8; 8] = b"AINEMB1\n";
+
+/// Uses between two writes of the LRU sidecar
+const SIDECAR_WRITE_INTERVAL: u64 = 256;
+
+/// Share of the size cap kept after a compaction, so the next one is not immediate
+const COMPACTION_TARGET_RATIO: f64 = 0.75;
+
+/// Errors that can occur in the persistent embedding store
+#[derive(Error, Debug)]
+pub enum EmbeddingStoreError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid store file {path}: {reason}")]
+    InvalidFormat { path: String, reason: String },
+
+    #[error("LRU sidecar error: {0}")]
+    Sidecar(#[from] bincode::Error),
+
+    #[error("Store lock poisoned")]
+    LockPoisoned,
+}
+
+pub type EmbeddingStoreResult<T> = Result<T, EmbeddingStoreError>;
+
+/// SHA-256 hash of an embedded text
+pub type TextHash = [u8; 32];
+
+/// Hash `text` the way the store keys it
+pub fn hash_text(text: &str) -> TextHash {
+    Sha256::digest(text.as_bytes()).into()
+}
+
+/// Key of a stored embedding
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+struct StoreKey {
+    model_id: String,
+    text_hash: TextHash,
+}
+
+/// Location and recency of a stored embedding
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    /// Offset of the vector values in the data file
+    vector_offset: u64,
+    /// Number of `f32` values
+    dimension: u32,
+    /// Tick of the last read or write
+    last_used: u64,
+}
+
+impl Slot {
+    fn record_len(&self, model_id: &str) -> u64 {
+        record_len(model_id, self.dimension as usize)
+    }
+}
+
+/// Size of a record in the data file
+fn record_len(model_id: &str, dimension: usize) -> u64 {
+    (2 + model_id.len() + 32 + 4 + dimension * 4) as u64
+}
+
+/// Statistics of the persistent store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingStoreStats {
+    /// Number of stored embeddings
+    pub entries: usize,
+    /// Bytes used by stored embeddings
+    pub live_bytes: u64,
+    /// Size of the data file, including superseded records
+    pub file_bytes: u64,
+    /// Size cap of the data file
+    pub max_bytes: u64,
+}
+
+/// State of a loaded store
+struct LoadedStore {
+    file: File,
+    index: HashMap<StoreKey, Slot>,
+    file_len: u64,
+    live_bytes: u64,
+    clock: u64,
+    uses_since_sidecar: u64,
+}
+
+/// Durable content-addressed embedding store with a size cap
+pub struct EmbeddingStore {
+    data_path: PathBuf,
+    lru_path: PathBuf,
+    max_bytes: u64,
+    /// `None` until the store is first used
+    state: Mutex<Option<LoadedStore>>,
+}
+
+impl EmbeddingStore {
+    /// Create a store backed by `data_path`, holding at most `max_bytes`
+    ///
+    /// Nothing is read until the first lookup or insert.
+    pub fn new(data_path: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        let data_path = data_path.into();
+        let lru_path = data_path.with_extension("lru");
+        Self {
+            data_path,
+            lru_path,
+            max_bytes,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Path of the data file
+    pub fn data_path(&self) -> &Path {
+        &self.data_path
+    }
+
+    /// Look up the embedding of a text hash for `model_id`
+    pub fn get(&self, model_id: &str, text_hash: &TextHash) -> EmbeddingStoreResult<Option<Vec<f32>>> {
+        self.with_state(|state| {
+            let key = StoreKey { model_id: model_id.to_string(), text_hash: *text_hash };
+            let Some(slot) = state.index.get_mut(&key) else {
+                return Ok(None);
+            };
+            state.clock += 1;
+            slot.last_used = state.clock;
+            let slot = *slot;
+
+            let mut bytes = vec![0u8; slot.dimension as usize * 4];
+            state.file.seek(SeekFrom::Start(slot.vector_offset))?;
+            state.file.read_exact(&mut bytes)?;
+            self.note_use(state)?;
+
+            Ok(Some(
+                bytes.chunks_exact(4).map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]])).collect(),
+            ))
+        })
+    }
+
+    /// Whether an embedding of a text hash is stored for `model_id`
+    pub fn contains(&self, model_id: &str, text_hash: &TextHash) -> EmbeddingStoreResult<bool> {
+        self.with_state(|state| {
+            Ok(state.index.contains_key(&StoreKey { model_id: model_id.to_string(), text_hash: *text_hash }))
+        })
+    }
+
+    /// Store the embedding of a text hash for `model_id`
+    ///
+    /// Storing a key that already exists only refreshes its recency.
+    pub fn put(&self, model_id: &str, text_hash: &TextHash, embedding: &[f32]) -> EmbeddingStoreResult<()> {
+        if embedding.is_empty() || model_id.len() > u16::MAX as usize {
+            return Ok(());
+        }
+
+        self.with_state(|state| {
+            state.clock += 1;
+            let key = StoreKey { model_id: model_id.to_string(), text_hash: *text_hash };
+            if let Some(slot) = state.index.get_mut(&key) {
+                slot.last_used = state.clock;
+                return self.note_use(state);
+            }
+
+            let record_start = state.file_len;
+            state.file.seek(SeekFrom::Start(record_start))?;
+            let mut writer = BufWriter::new(&state.file);
+            write_record(&mut writer, &key, embedding)?;
+            writer.flush()?;
+            drop(writer);
+
+            let len = record_len(model_id, embedding.len());
+            state.index.insert(key, Slot {
+                vector_offset: record_start + len - embedding.len() as u64 * 4,
+                dimension: embedding.len() as u32,
+                last_used: state.clock,
+            });
+            state.file_len += len;
+            state.live_bytes += len;
+
+            if state.file_len > self.max_bytes {
+                self.compact(state)?;
+            } else {
+                self.note_use(state)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Remove every stored embedding
+    pub fn clear(&self) -> EmbeddingStoreResult<()> {
+        let mut guard = self.state.lock().map_err(|_| EmbeddingStoreError::LockPoisoned)?;
+        *guard = None;
+        for path in [&self.data_path, &self.lru_path] {
+            match fs::remove_file(path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the LRU sidecar if the store is loaded
+    pub fn flush(&self) -> EmbeddingStoreResult<()> {
+        let mut guard = self.state.lock().map_err(|_| EmbeddingStoreError::LockPoisoned)?;
+        if let Some(state) = guard.as_mut() {
+            self.write_sidecar(state)?;
+        }
+        Ok(())
+    }
+
+    /// Statistics of the store, loading it if needed
+    pub fn stats(&self) -> EmbeddingStoreResult<EmbeddingStoreStats> {
+        self.with_state(|state| {
+            Ok(EmbeddingStoreStats {
+                entries: state.index.len(),
+                live_bytes: state.live_bytes,
+                file_bytes: state.file_len,
+                max_bytes: self.max_bytes,
+            })
+        })
+    }
+
+    /// Run `operation` on the loaded store, loading it on first use
+    fn with_state<T>(&self, operation: impl FnOnce(&mut LoadedStore) -> EmbeddingStoreResult<T>) -> EmbeddingStoreResult<T> {
+        let mut guard = self.state.lock().map_err(|_| EmbeddingStoreError::LockPoisoned)?;
+        if guard.is_none() {
+            *guard = Some(self.load()?);
+        }
+        operation(guard.as_mut().expect("store loaded above"))
+    }
+
+    /// Open the data file and index its records
+    fn load(&self) -> EmbeddingStoreResult<LoadedStore> {
+        if let Some(parent) = self.data_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&self.data_path)?;
+        let mut file_len = file.metadata()?.len();
+
+        if file_len == 0 {
+            file.write_all(STORE_MAGIC)?;
+            file_len = STORE_MAGIC.len() as u64;
+        }
+
+        let mut index = HashMap::new();
+        let mut live_bytes = 0;
+        file.seek(SeekFrom::Start(0))?;
+        let valid_len = {
+            let mut reader = BufReader::new(&file);
+            let mut magic = [0u8; 8];
+            if reader.read_exact(&mut magic).is_err() || &magic != STORE_MAGIC {
+                return Err(EmbeddingStoreError::InvalidFormat {
+                    path: self.data_path.to_string_lossy().to_string(),
+                    reason: "missing store header".to_string(),
+                });
+            }
+
+            let mut offset = STORE_MAGIC.len() as u64;
+            while offset < file_len {
+                let Some((key, dimension)) = read_record_header(&mut reader, file_len - offset) else {
+                    break;
+                };
+                let len = record_len(&key.model_id, dimension as usize);
+                if offset + len > file_len {
+                    break;
+                }
+                reader.seek_relative(dimension as i64 * 4)?;
+
+                let slot = Slot { vector_offset: offset + len - dimension as u64 * 4, dimension, last_used: 0 };
+                if let Some(previous) = index.insert(key.clone(), slot) {
+                    live_bytes -= previous.record_len(&key.model_id);
+                }
+                live_bytes += len;
+                offset += len;
+            }
+            offset
+        };
+
+        if valid_len < file_len {
+            eprintln!("⚠️ Embedding store {:?} ends with a partial record, truncating {} bytes",
+                      self.data_path, file_len - valid_len);
+            file.set_len(valid_len)?;
+            file_len = valid_len;
+        }
+
+        let clock = self.apply_sidecar(&mut index);
+        eprintln!("📂 Loaded {} stored embeddings ({} bytes) from {:?}", index.len(), file_len, self.data_path);
+
+        Ok(LoadedStore { file, index, file_len, live_bytes, clock, uses_since_sidecar: 0 })
+    }
+
+    /// Restore last-use ticks from the sidecar, returning the latest tick
+    fn apply_sidecar(&self, index: &mut HashMap<StoreKey, Slot>) -> u64 {
+        let recency: Vec<(StoreKey, u64)> = match fs::read(&self.lru_path) {
+            Ok(bytes) => bincode::deserialize(&bytes).unwrap_or_else(|e| {
+                eprintln!("⚠️ Ignoring unreadable embedding store sidecar {:?}: {}", self.lru_path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        let mut clock = 0;
+        for (key, last_used) in recency {
+            if let Some(slot) = index.get_mut(&key) {
+                slot.last_used = last_used;
+                clock = clock.max(last_used);
+            }
+        }
+        clock
+    }
+
+    /// Count a use and write the sidecar every few uses
+    fn note_use(&self, state: &mut LoadedStore) -> EmbeddingStoreResult<()> {
+        state.uses_since_sidecar += 1;
+        if state.uses_since_sidecar >= SIDECAR_WRITE_INTERVAL {
+            self.write_sidecar(state)?;
+        }
+        Ok(())
+    }
+
+    /// Write the last-use tick of every key to the sidecar
+    fn write_sidecar(&self, state: &mut LoadedStore) -> EmbeddingStoreResult<()> {
+        let recency: Vec<(&StoreKey, u64)> = state.index.iter().map(|(key, slot)| (key, slot.last_used)).collect();
+        let temp_path = self.lru_path.with_extension("lru.tmp");
+        fs::write(&temp_path, bincode::serialize(&recency)?)?;
+        fs::rename(&temp_path, &self.lru_path)?;
+        state.uses_since_sidecar = 0;
+        Ok(())
+    }
+
+    /// Rewrite the data file with the most recently used entries that fit the target size
+    fn compact(&self, state: &mut LoadedStore) -> EmbeddingStoreResult<()> {
+        let target = (self.max_bytes as f64 * COMPACTION_TARGET_RATIO) as u64;
+        let mut slots: Vec<(StoreKey, Slot)> = state.index.iter().map(|(key, slot)| (key.clone(), *slot)).collect();
+        slots.sort_by_key(|(_, slot)| std::cmp::Reverse(slot.last_used));
+
+        let temp_path = self.data_path.with_extension("bin.tmp");
+        let mut new_index = HashMap::new();
+        let mut new_len = STORE_MAGIC.len() as u64;
+        {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            writer.write_all(STORE_MAGIC)?;
+            for (key, slot) in slots {
+                let len = slot.record_len(&key.model_id);
+                if new_len + len > target {
+                    continue;
+                }
+
+                let mut bytes = vec![0u8; slot.dimension as usize * 4];
+                state.file.seek(SeekFrom::Start(slot.vector_offset))?;
+                state.file.read_exact(&mut bytes)?;
+                let embedding: Vec<f32> = bytes
+                    .chunks_exact(4)
+                    .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+                    .collect();
+                write_record(&mut writer, &key, &embedding)?;
+
+                new_index.insert(key, Slot { vector_offset: new_len + len - bytes.len() as u64, ..slot });
+                new_len += len;
+            }
+            writer.flush()?;
+        }
+        fs::rename(&temp_path, &self.data_path)?;
+
+        let dropped = state.index.len() - new_index.len();
+        state.file = OpenOptions::new().read(true).write(true).open(&self.data_path)?;
+        state.index = new_index;
+        state.file_len = new_len;
+        state.live_bytes = new_len - STORE_MAGIC.len() as u64;
+        self.write_sidecar(state)?;
+
+        eprintln!("🧹 Compacted embedding store: dropped {} least recently used entries, {} bytes left", dropped, new_len);
+        Ok(())
+    }
+}
+
+impl Drop for EmbeddingStore {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            eprintln!("⚠️ Failed to write embedding store sidecar: {}", e);
+        }
+    }
+}
+
+/// Append one record to `writer`
+fn write_record(writer: &mut impl Write, key: &StoreKey, embedding: &[f32]) -> std::io::Result<()> {
+    writer.write_all(&(key.model_id.len() as u16).to_le_bytes())?;
+    writer.write_all(key.model_id.as_bytes())?;
+    writer.write_all(&key.text_hash)?;
+    writer.write_all(&(embedding.len() as u32).to_le_bytes())?;
+    for value in embedding {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Read a record's key and dimension, or `None` if it is cut short or malformed
+fn read_record_header(reader: &mut impl Read, remaining: u64) -> Option<(StoreKey, u32)> {
+    let mut len_bytes = [0u8; 2];
+    reader.read_exact(&mut len_bytes).ok()?;
+    let model_len = u16::from_le_bytes(len_bytes) as u64;
+    if 2 + model_len + 32 + 4 > remaining {
+        return None;
+    }
+
+    let mut model_bytes = vec![0u8; model_len as usize];
+    reader.read_exact(&mut model_bytes).ok()?;
+    let mut text_hash = [0u8; 32];
+    reader.read_exact(&mut text_hash).ok()?;
+    let mut dimension_bytes = [0u8; 4];
+    reader.read_exact(&mut dimension_bytes).ok()?;
+
+    let model_id = String::from_utf8(model_bytes).ok()?;
+    Some((StoreKey { model_id, text_hash }, u32::from_le_bytes(dimension_bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_survives_reopen_and_truncated_tail() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("embeddings.bin");
+        let hash = hash_text("persisted text");
+
+        {
+            let store = EmbeddingStore::new(&path, 1024 * 1024);
+            assert_eq!(store.get("model", &hash).unwrap(), None);
+            store.put("model", &hash, &[0.25, -1.5, 3.0]).unwrap();
+            store.put("other-model", &hash, &[1.0]).unwrap();
+        }
+
+        // Simulate a crash in the middle of appending a record
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[5, 0, b'm']).unwrap();
+        drop(file);
+
+        let store = EmbeddingStore::new(&path, 1024 * 1024);
+        assert_eq!(store.get("model", &hash).unwrap(), Some(vec![0.25, -1.5, 3.0]));
+        assert_eq!(store.get("other-model", &hash).unwrap(), Some(vec![1.0]));
+        assert_eq!(store.get("model", &hash_text("unknown")).unwrap(), None);
+        assert_eq!(store.stats().unwrap().entries, 2);
+        assert_eq!(fs::metadata(&path).unwrap().len(), store.stats().unwrap().file_bytes);
+
+        store.clear().unwrap();
+        assert_eq!(store.stats().unwrap().entries, 0);
+    }
+
+    #[test]
+    fn test_store_evicts_least_recently_used_over_cap() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("embeddings.bin");
+        let record = record_len("model", 16);
+        let store = EmbeddingStore::new(&path, STORE_MAGIC.len() as u64 + record * 4);
+
+        let hashes: Vec<TextHash> = (0..4).map(|i| hash_text(&format!("text {}", i))).collect();
+        for hash in &hashes {
+            store.put("model", hash, &[0.5; 16]).unwrap();
+        }
+        // Touch the first entry so the second is the least recently used
+        store.get("model", &hashes[0]).unwrap();
+
+        store.put("model", &hash_text("text 4"), &[0.5; 16]).unwrap();
+        let stats = store.stats().unwrap();
+        assert!(stats.file_bytes <= stats.max_bytes);
+        assert!(store.get("model", &hashes[0]).unwrap().is_some());
+        assert!(store.get("model", &hashes[1]).unwrap().is_none());
+        drop(store);
+
+        // Recency survives a restart through the sidecar
+        assert!(path.with_extension("lru").exists());
+        let store = EmbeddingStore::new(&path, STORE_MAGIC.len() as u64 + record * 4);
+        assert_eq!(store.stats().unwrap().entries, stats.entries);
+    }
+}
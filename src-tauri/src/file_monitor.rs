@@ -10,6 +10,8 @@
 //! - **Debounced Processing**: Prevents excessive indexing during rapid file changes
 //! - **Markdown Filtering**: Only processes markdown files (.md) for efficiency
 //! - **Integration**: Seamlessly connects to the indexing pipeline for automatic updates
+//! - **Link Graph**: Keeps the vault's link graph (backlinks, unresolved links) current
+//! - **Vault Config**: Reloads `.ainote/config.toml` when it changes and applies it
 //! - **Error Handling**: Robust error recovery and logging for file system events
 //! - **Performance**: Minimal overhead monitoring suitable for large vaults
 //!
@@ -29,14 +31,20 @@
 //! ```
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use notify::{RecommendedWatcher, Watcher, RecursiveMode, Event, EventKind};
 use tokio::sync::mpsc;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 
 use crate::commands::indexing_commands::INDEXING_PIPELINE;
+use crate::globals::{open_vault_config, open_vault_link_graph, LINK_GRAPH};
+use crate::link_graph::LinkGraph;
+use crate::vault_config::VaultConfig;
 
 /// Global file monitor instance for managing vault file system changes
 /// 
@@ -71,7 +79,7 @@ pub enum FileEventKind {
 }
 
 /// Configuration for file monitoring behavior
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FileMonitorConfig {
     /// Debounce time in milliseconds for file changes (default: 1000ms)
     pub debounce_ms: u64,
@@ -94,10 +102,62 @@ impl Default for FileMonitorConfig {
     }
 }
 
+/// Debounces reloads of a vault's config file
+/// 
+/// Editors save in several steps, each producing an event. Every event bumps
+/// the generation; a scheduled reload only runs if no newer event arrived
+/// while it waited, and reloads run one at a time, so a save reloads once and
+/// an older read never lands after a newer one.
+struct ConfigReloader {
+    /// Generation of the latest config file event
+    generation: AtomicU64,
+    /// Held while a reload runs
+    running: tokio::sync::Mutex<()>,
+}
+
+impl ConfigReloader {
+    fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+    
+    /// Record a config file event and return its generation
+    fn record_event(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+    
+    fn is_latest(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+    
+    /// Wait out the debounce period, then run `reload` unless a newer event arrived
+    /// 
+    /// # Returns
+    /// * `true` if `reload` ran
+    async fn reload_if_latest<F, Fut>(&self, generation: u64, debounce: Duration, reload: F) -> bool
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        tokio::time::sleep(debounce).await;
+        if !self.is_latest(generation) {
+            return false;
+        }
+        let _running = self.running.lock().await;
+        if !self.is_latest(generation) {
+            return false;
+        }
+        reload().await;
+        true
+    }
+}
+
 /// File system monitor for real-time vault change detection
 pub struct FileMonitor {
-    /// Configuration for monitoring behavior
-    config: FileMonitorConfig,
+    /// Configuration for monitoring behavior, replaced when the vault config changes
+    config: Arc<RwLock<FileMonitorConfig>>,
     /// Active file watchers by vault path
     watchers: Arc<Mutex<HashMap<PathBuf, RecommendedWatcher>>>,
     /// Pending file changes for debouncing
@@ -113,12 +173,25 @@ impl FileMonitor {
     /// Create a new file monitor with custom configuration
     pub fn with_config(config: FileMonitorConfig) -> Self {
         Self {
-            config,
+            config: Arc::new(RwLock::new(config)),
             watchers: Arc::new(Mutex::new(HashMap::new())),
             pending_changes: Arc::new(Mutex::new(HashMap::new())),
         }
     }
     
+    /// Current monitoring configuration
+    pub fn config(&self) -> FileMonitorConfig {
+        self.config.read().unwrap().clone()
+    }
+    
+    /// Replace the monitoring configuration
+    /// 
+    /// Debouncing, extension filtering and automatic indexing follow the new
+    /// configuration right away; `recursive` applies to vaults watched afterwards.
+    pub fn set_config(&self, config: FileMonitorConfig) {
+        *self.config.write().unwrap() = config;
+    }
+    
     /// Start monitoring a vault directory for file changes
     /// 
     /// This method sets up file system monitoring for the specified vault directory
@@ -163,7 +236,9 @@ impl FileMonitor {
         // Set up event channel
         let (event_tx, mut event_rx) = mpsc::unbounded_channel();
         let vault_path_for_closure = vault_path_buf.clone();
-        let monitored_extensions = self.config.monitored_extensions.clone();
+        let config = Arc::clone(&self.config);
+        let config_path = VaultConfig::path(&vault_path_buf);
+        let config_path_for_watcher = config_path.clone();
         
         // Create watcher with event handler
         let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
@@ -178,11 +253,12 @@ impl FileMonitor {
                     };
                     
                     if let Some(kind) = event_kind {
+                        let monitored_extensions = config.read().unwrap().monitored_extensions.clone();
                         for path in event.paths {
-                            // Only process markdown files
+                            // Only process markdown files and the vault config
                             if let Some(extension) = path.extension() {
                                 let ext_str = extension.to_string_lossy().to_lowercase();
-                                if monitored_extensions.contains(&ext_str) {
+                                if monitored_extensions.contains(&ext_str) || path == config_path_for_watcher {
                                     let change_event = FileChangeEvent {
                                         file_path: path,
                                         event_kind: kind.clone(),
@@ -204,7 +280,7 @@ impl FileMonitor {
         }).map_err(|e| format!("Failed to create file watcher: {}", e))?;
         
         // Start watching the vault directory
-        let recursive_mode = if self.config.recursive {
+        let recursive_mode = if self.config().recursive {
             RecursiveMode::Recursive
         } else {
             RecursiveMode::NonRecursive
@@ -219,10 +295,15 @@ impl FileMonitor {
             watchers.insert(vault_path_buf.clone(), watcher);
         }
         
+        // Load the vault's link graph so file changes can keep it current
+        if let Err(e) = open_vault_link_graph(vault_path).await {
+            log::warn!("⚠️ Link graph unavailable for {:?}: {}", vault_path_buf, e);
+        }
+        
         // Start event processing task
         let pending_changes = Arc::clone(&self.pending_changes);
-        let debounce_ms = self.config.debounce_ms;
-        let auto_index = self.config.auto_index;
+        let config = Arc::clone(&self.config);
+        let config_reloader = Arc::new(ConfigReloader::new());
         
         tokio::spawn(async move {
             log::debug!("🔄 Started file change event processor for vault: {:?}", vault_path_for_closure);
@@ -231,6 +312,28 @@ impl FileMonitor {
                 log::debug!("📁 File change detected: {:?} ({:?})", 
                            change_event.file_path, change_event.event_kind);
                 
+                let (debounce_ms, auto_index) = {
+                    let config = config.read().unwrap();
+                    (config.debounce_ms, config.auto_index)
+                };
+                
+                // Reload the vault config once writes to it settle
+                if change_event.file_path == config_path {
+                    let vault_path = vault_path_for_closure.to_string_lossy().to_string();
+                    let reloader = Arc::clone(&config_reloader);
+                    let generation = reloader.record_event();
+                    tokio::spawn(async move {
+                        reloader
+                            .reload_if_latest(generation, Duration::from_millis(debounce_ms), || async {
+                                if let Err(e) = open_vault_config(&vault_path).await {
+                                    log::error!("❌ Keeping the previous vault config: {}", e);
+                                }
+                            })
+                            .await;
+                    });
+                    continue;
+                }
+                
                 // Add to pending changes for debouncing
                 {
                     let mut pending = pending_changes.lock().unwrap();
@@ -309,7 +412,9 @@ impl FileMonitor {
         
         log::debug!("🔄 Processing {} debounced file changes", changes.len());
         
-        // Filter out deleted files and collect paths for indexing
+        Self::update_link_graph(&changes).await;
+        
+        // Collect paths for indexing; deleted files are passed on so the notes embedding them are re-indexed
         let mut files_to_index = Vec::new();
         for change in changes {
             match change.event_kind {
@@ -321,8 +426,8 @@ impl FileMonitor {
                     }
                 }
                 FileEventKind::Deleted => {
-                    // For deleted files, we might want to remove from vector database
-                    // This is handled elsewhere in the system
+                    // Removing its embeddings is handled elsewhere in the system
+                    files_to_index.push(change.file_path.to_string_lossy().to_string());
                     log::debug!("🗑️ File deleted: {:?}", change.file_path);
                 }
             }
@@ -355,6 +460,65 @@ impl FileMonitor {
             }
         }
     }
+    
+    /// Apply file changes to the open link graph and persist it
+    /// 
+    /// Notes are read before the graph lock is taken and the graph file is
+    /// written after it is released, both on the blocking thread pool.
+    async fn update_link_graph(changes: &[FileChangeEvent]) {
+        let paths: Vec<PathBuf> = {
+            let graph_lock = LINK_GRAPH.read().await;
+            let Some(graph) = graph_lock.as_ref() else {
+                return;
+            };
+            changes.iter()
+                .filter(|change| graph.contains_path(&change.file_path))
+                .map(|change| change.file_path.clone())
+                .collect()
+        };
+        if paths.is_empty() {
+            return;
+        }
+        
+        let note_changes = match tokio::task::spawn_blocking(move || LinkGraph::read_changes(&paths)).await {
+            Ok(note_changes) => note_changes,
+            Err(e) => {
+                log::error!("❌ Failed to read changed notes for the link graph: {}", e);
+                return;
+            }
+        };
+        
+        let snapshot = {
+            let mut graph_lock = LINK_GRAPH.write().await;
+            let Some(graph) = graph_lock.as_mut() else {
+                return;
+            };
+            match graph.apply_changes(note_changes) {
+                Ok(0) => return,
+                Ok(updated) => {
+                    log::debug!("🔗 Updated links of {} notes", updated);
+                    graph.snapshot()
+                }
+                Err(e) => {
+                    log::error!("❌ Failed to update link graph: {}", e);
+                    return;
+                }
+            }
+        };
+        
+        let (storage_path, serialized) = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                log::error!("❌ Failed to save link graph: {}", e);
+                return;
+            }
+        };
+        match tokio::task::spawn_blocking(move || LinkGraph::write_snapshot(&storage_path, &serialized)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log::error!("❌ Failed to save link graph: {}", e),
+            Err(e) => log::error!("❌ Link graph writer failed: {}", e),
+        }
+    }
 }
 
 impl Default for FileMonitor {
@@ -376,10 +540,10 @@ mod tests {
     #[test]
     fn test_file_monitor_creation() {
         let monitor = FileMonitor::new();
-        assert_eq!(monitor.config.debounce_ms, 1000);
-        assert!(monitor.config.recursive);
-        assert!(monitor.config.auto_index);
-        assert_eq!(monitor.config.monitored_extensions, vec!["md"]);
+        assert_eq!(monitor.config().debounce_ms, 1000);
+        assert!(monitor.config().recursive);
+        assert!(monitor.config().auto_index);
+        assert_eq!(monitor.config().monitored_extensions, vec!["md"]);
     }
 
     #[test]
@@ -392,10 +556,10 @@ mod tests {
         };
         
         let monitor = FileMonitor::with_config(config.clone());
-        assert_eq!(monitor.config.debounce_ms, 500);
-        assert!(!monitor.config.recursive);
-        assert!(!monitor.config.auto_index);
-        assert_eq!(monitor.config.monitored_extensions.len(), 2);
+        assert_eq!(monitor.config().debounce_ms, 500);
+        assert!(!monitor.config().recursive);
+        assert!(!monitor.config().auto_index);
+        assert_eq!(monitor.config().monitored_extensions.len(), 2);
     }
 
     #[tokio::test]
@@ -430,6 +594,31 @@ mod tests {
         assert!(!monitor.is_monitoring(&vault_path));
     }
 
+    #[tokio::test]
+    async fn test_config_reload_runs_once_per_burst() {
+        let reloader = Arc::new(ConfigReloader::new());
+        let reloads = Arc::new(AtomicU64::new(0));
+        
+        // A multi-step save produces several events inside the debounce window
+        let mut tasks = Vec::new();
+        for _ in 0..5 {
+            let generation = reloader.record_event();
+            let (reloader, reloads) = (Arc::clone(&reloader), Arc::clone(&reloads));
+            tasks.push(tokio::spawn(async move {
+                reloader
+                    .reload_if_latest(generation, Duration::from_millis(50), || async {
+                        reloads.fetch_add(1, Ordering::SeqCst);
+                    })
+                    .await
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        
+        let ran: Vec<bool> = futures::future::join_all(tasks).await.into_iter().map(Result::unwrap).collect();
+        assert_eq!(ran, vec![false, false, false, false, true]);
+        assert_eq!(reloads.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_file_event_kind() {
         let event = FileChangeEvent {
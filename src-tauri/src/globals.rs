@@ -22,6 +22,15 @@
 //! ### EMBEDDING_CACHE
 //! Provides caching layer for generated embeddings to improve performance.
 //!
+//! ### OPTIMIZATION_SCHEDULER
+//! Runs index optimization for the open vault, including codebook retraining.
+//!
+//! ### LINK_GRAPH
+//! Tracks links and backlinks between the notes of the open vault.
+//!
+//! ### VAULT_CONFIG
+//! Holds the settings of the open vault from `.ainote/config.toml`.
+//!
 //! ## Usage Patterns
 //!
 //! ```rust
@@ -33,16 +42,24 @@
 //! let generator = get_embedding_generator().await;
 //! ```
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
 
 use crate::ollama_client::{OllamaClient, OllamaConfig};
 use crate::embedding_generator::EmbeddingGenerator;  
-use crate::embedding_cache::EmbeddingCache;
-use crate::embedding_queue::EmbeddingQueue;
+use crate::embedding_provider::{EmbeddingProvider, EmbeddingProviderConfig};
+use crate::embedding_cache::{CachedEmbeddingProvider, EmbeddingCache};
+use crate::embedding_queue::{EmbeddingQueue, QueueConfig};
 use crate::vector_db::VectorDatabase;
+use crate::vector_db::types::VectorStorageConfig;
+use crate::vector_db::optimization_scheduler::{AutomaticOptimizationScheduler, OptimizationSchedulerConfig};
+use crate::vector_db::performance_monitor::{IndexPerformanceMonitor, MonitoringConfig};
 use crate::suggestion_cache::SuggestionCache;
+use crate::link_graph::LinkGraph;
+use crate::vault_config::VaultConfig;
+use crate::file_monitor::get_file_monitor;
 
 /// Global Ollama client instance for AI model interactions
 /// 
@@ -60,6 +77,17 @@ pub static OLLAMA_CLIENT: Lazy<Arc<RwLock<Option<OllamaClient>>>> =
 pub static EMBEDDING_GENERATOR: Lazy<Arc<RwLock<Option<EmbeddingGenerator>>>> = 
     Lazy::new(|| Arc::new(RwLock::new(None)));
 
+/// Embedding backend used instead of Ollama, with the configuration it was built from
+pub type SelectedEmbeddingProvider = (EmbeddingProviderConfig, Arc<dyn EmbeddingProvider>);
+
+/// Global embedding provider selection
+///
+/// `None` selects Ollama through [`EMBEDDING_GENERATOR`]. Any other backend
+/// chosen through the provider commands is stored here and shared by
+/// indexing, the embedding queue and embedding commands.
+pub static EMBEDDING_PROVIDER: Lazy<Arc<RwLock<Option<SelectedEmbeddingProvider>>>> = 
+    Lazy::new(|| Arc::new(RwLock::new(None)));
+
 /// Global embedding cache instance for performance optimization
 ///
 /// Provides caching layer for generated embeddings to avoid redundant
@@ -84,6 +112,13 @@ pub static EMBEDDING_QUEUE: Lazy<Arc<RwLock<Option<EmbeddingQueue>>>> =
 pub static VECTOR_DATABASE: Lazy<Arc<RwLock<Option<VectorDatabase>>>> = 
     Lazy::new(|| Arc::new(RwLock::new(None)));
 
+/// Global optimization scheduler for the open vault
+/// 
+/// Built together with the vault's vector database, with product
+/// quantization codebook retraining registered against it.
+pub static OPTIMIZATION_SCHEDULER: Lazy<Arc<RwLock<Option<AutomaticOptimizationScheduler>>>> = 
+    Lazy::new(|| Arc::new(RwLock::new(None)));
+
 /// Global suggestion cache instance for AI suggestion optimization
 ///
 /// Provides intelligent caching for AI-powered note suggestions with
@@ -92,6 +127,22 @@ pub static VECTOR_DATABASE: Lazy<Arc<RwLock<Option<VectorDatabase>>>> =
 pub static SUGGESTION_CACHE: Lazy<Arc<RwLock<Option<SuggestionCache>>>> = 
     Lazy::new(|| Arc::new(RwLock::new(None)));
 
+/// Global link graph of the open vault
+///
+/// Tracks wikilinks and markdown links between notes for backlink and
+/// unresolved-link queries. Opened per vault and kept up to date by the
+/// file monitor while the vault is watched.
+pub static LINK_GRAPH: Lazy<Arc<RwLock<Option<LinkGraph>>>> = 
+    Lazy::new(|| Arc::new(RwLock::new(None)));
+
+/// Global configuration of the open vault
+///
+/// Loaded from `{vault}/.ainote/config.toml` when the vault is opened and
+/// reloaded by the file monitor when the file changes. Until a vault is
+/// opened, subsystems use their default configurations.
+pub static VAULT_CONFIG: Lazy<Arc<RwLock<Option<Arc<VaultConfig>>>>> = 
+    Lazy::new(|| Arc::new(RwLock::new(None)));
+
 /// Helper function to get or initialize the embedding cache
 ///
 /// This function uses the double-checked locking pattern to ensure
@@ -161,17 +212,7 @@ pub async fn get_embedding_queue() -> EmbeddingQueue {
         if let Some(queue) = queue_lock.as_ref() {
             queue.clone()
         } else {
-            // Get Ollama config from global client
-            let ollama_config = {
-                let client_lock = OLLAMA_CLIENT.read().await;
-                if let Some(client) = client_lock.as_ref() {
-                    client.get_config().clone()
-                } else {
-                    OllamaConfig::default()
-                }
-            };
-            
-            let queue = EmbeddingQueue::with_default_config(ollama_config);
+            let queue = EmbeddingQueue::new(get_embedding_provider().await, QueueConfig::default());
             
             // Start the queue's background processing tasks
             let (_processor_handle, _cleanup_handle) = queue.start().await;
@@ -231,6 +272,72 @@ pub async fn get_embedding_generator() -> EmbeddingGenerator {
     }
 }
 
+/// Helper function to get the selected embedding provider
+///
+/// Returns the backend chosen with [`set_embedding_provider`], or the
+/// shared Ollama embedding generator when none was chosen.
+///
+/// # Example
+///
+/// ```rust
+/// let provider = get_embedding_provider().await;
+/// let embedding = provider.embed("text", "nomic-embed-text").await?;
+/// ```
+pub async fn get_embedding_provider() -> Arc<dyn EmbeddingProvider> {
+    if let Some((_, provider)) = EMBEDDING_PROVIDER.read().await.as_ref() {
+        return Arc::clone(provider);
+    }
+    Arc::new(get_embedding_generator().await)
+}
+
+/// Context length in tokens of an embedding model, when its provider reports one
+///
+/// Ollama models are looked up through `/api/show`. Other providers and an
+/// unreachable Ollama yield `None`, which callers treat as unknown.
+pub async fn embedding_context_length(model_name: &str) -> Option<usize> {
+    if !matches!(get_embedding_provider_config().await, EmbeddingProviderConfig::Ollama) {
+        return None;
+    }
+    let client = OLLAMA_CLIENT.read().await.clone().unwrap_or_default();
+    match client.get_context_length(model_name).await {
+        Ok(context_length) => context_length,
+        Err(e) => {
+            log::warn!("⚠️ Could not read the context length of {}: {}", model_name, e);
+            None
+        }
+    }
+}
+
+/// Helper function to get the selected embedding provider behind the embedding cache
+///
+/// Used for indexing, so text that was embedded before, even in an earlier
+/// session, is served from the persistent cache instead of the model.
+pub async fn get_cached_embedding_provider() -> Arc<dyn EmbeddingProvider> {
+    Arc::new(CachedEmbeddingProvider::new(get_embedding_provider().await, get_embedding_cache().await))
+}
+
+/// Configuration of the selected embedding provider
+pub async fn get_embedding_provider_config() -> EmbeddingProviderConfig {
+    EMBEDDING_PROVIDER
+        .read()
+        .await
+        .as_ref()
+        .map(|(config, _)| config.clone())
+        .unwrap_or_default()
+}
+
+/// Select the embedding provider used from now on
+///
+/// The embedding queue is recreated on next use so queued requests go
+/// through the new provider.
+pub async fn set_embedding_provider(config: EmbeddingProviderConfig) -> Result<(), String> {
+    config.validate()?;
+    let provider = config.build();
+    *EMBEDDING_PROVIDER.write().await = provider.map(|provider| (config, provider));
+    *EMBEDDING_QUEUE.write().await = None;
+    Ok(())
+}
+
 /// Helper function to get or initialize the suggestion cache
 ///
 /// This function uses the double-checked locking pattern to ensure
@@ -266,4 +373,150 @@ pub async fn get_suggestion_cache() -> SuggestionCache {
             cache
         }
     }
-}
\ No newline at end of file
+}
+
+/// Helper function to open the global vector database for a vault
+///
+/// Initializes `VECTOR_DATABASE` with the vault's storage directory
+/// (`{vault}/.ainote/vectors`). Calling this again for the same vault is a
+/// no-op; opening a different vault replaces the current database.
+///
+/// # Arguments
+///
+/// * `vault_path` - Root directory of the vault
+///
+/// # Example
+///
+/// ```rust
+/// open_vault_vector_database("/path/to/vault").await?;
+/// let db = VECTOR_DATABASE.read().await;
+/// ```
+pub async fn open_vault_vector_database(vault_path: &str) -> Result<(), String> {
+    let config = VectorStorageConfig::for_vault(std::path::Path::new(vault_path));
+    
+    {
+        let db_lock = VECTOR_DATABASE.read().await;
+        if let Some(db) = db_lock.as_ref() {
+            if db.get_config().storage_dir == config.storage_dir {
+                return Ok(());
+            }
+        }
+    }
+    
+    let mut db_lock = VECTOR_DATABASE.write().await;
+    
+    // Double-check pattern to avoid race conditions
+    if let Some(db) = db_lock.as_ref() {
+        if db.get_config().storage_dir == config.storage_dir {
+            return Ok(());
+        }
+    }
+    
+    let database = VectorDatabase::new(config)
+        .await
+        .map_err(|e| format!("Failed to open vector database for vault: {}", e))?;
+    start_vault_optimization_scheduler(&database).await;
+    *db_lock = Some(database);
+    Ok(())
+}
+
+/// Replace the optimization scheduler with one for a newly opened database
+async fn start_vault_optimization_scheduler(database: &VectorDatabase) {
+    let mut scheduler_lock = OPTIMIZATION_SCHEDULER.write().await;
+    if let Some(mut previous) = scheduler_lock.take() {
+        if let Err(e) = previous.stop().await {
+            log::warn!("⚠️ Failed to stop the previous optimization scheduler: {}", e);
+        }
+    }
+    
+    let monitor = Arc::new(IndexPerformanceMonitor::new(MonitoringConfig::default()));
+    let mut scheduler = AutomaticOptimizationScheduler::new(OptimizationSchedulerConfig::default(), monitor);
+    scheduler.set_codebook_retraining_hook(Some(database.codebook_retraining_hook())).await;
+    if let Err(e) = scheduler.start().await {
+        log::warn!("⚠️ Failed to start the optimization scheduler: {}", e);
+    }
+    *scheduler_lock = Some(scheduler);
+}
+
+/// Helper function to open the global link graph for a vault
+///
+/// Loads `{vault}/.ainote/link_graph.json` into `LINK_GRAPH` and re-parses
+/// notes that changed since it was saved. Calling this again for the same
+/// vault is a no-op; opening a different vault replaces the current graph.
+///
+/// # Arguments
+///
+/// * `vault_path` - Root directory of the vault
+pub async fn open_vault_link_graph(vault_path: &str) -> Result<(), String> {
+    let vault_path_buf = std::path::PathBuf::from(vault_path);
+    
+    {
+        let graph_lock = LINK_GRAPH.read().await;
+        if let Some(graph) = graph_lock.as_ref() {
+            if graph.vault_path() == vault_path_buf {
+                return Ok(());
+            }
+        }
+    }
+    
+    let mut graph_lock = LINK_GRAPH.write().await;
+    
+    // Double-check pattern to avoid race conditions
+    if let Some(graph) = graph_lock.as_ref() {
+        if graph.vault_path() == vault_path_buf {
+            return Ok(());
+        }
+    }
+    
+    // Scanning a large vault is blocking file I/O
+    let graph = tokio::task::spawn_blocking(move || LinkGraph::open(vault_path_buf))
+        .await
+        .map_err(|e| format!("Link graph task failed: {}", e))?
+        .map_err(|e| format!("Failed to open link graph for vault: {}", e))?;
+    *graph_lock = Some(graph);
+    Ok(())
+}
+
+/// Helper function to load and apply the configuration of a vault
+///
+/// Reads `{vault}/.ainote/config.toml` into `VAULT_CONFIG` and applies the
+/// settings that take effect immediately (file monitoring). A vault without
+/// the file gets the defaults. If the file is invalid, the error is returned
+/// and the vault keeps the configuration it already had, or the defaults
+/// when it is being opened.
+///
+/// # Arguments
+///
+/// * `vault_path` - Root directory of the vault
+pub async fn open_vault_config(vault_path: &str) -> Result<Arc<VaultConfig>, String> {
+    let vault_path_buf = PathBuf::from(vault_path);
+    let loaded = VaultConfig::load(&vault_path_buf);
+    
+    let mut config_lock = VAULT_CONFIG.write().await;
+    let (config, result) = match loaded {
+        Ok(config) => {
+            let config = Arc::new(config);
+            (Arc::clone(&config), Ok(config))
+        }
+        Err(e) => {
+            let current = config_lock.as_ref()
+                .filter(|config| config.vault_path() == Some(vault_path_buf.as_path()))
+                .cloned();
+            let config = current.unwrap_or_else(|| Arc::new(VaultConfig::for_vault(&vault_path_buf)));
+            (config, Err(e.to_string()))
+        }
+    };
+    *config_lock = Some(Arc::clone(&config));
+    drop(config_lock);
+    
+    get_file_monitor().set_config(config.file_monitor.clone());
+    if result.is_ok() {
+        log::info!("⚙️ Applied vault config for {:?}", vault_path_buf);
+    }
+    result
+}
+
+/// Configuration of the open vault, or the defaults if no vault is open
+pub async fn current_vault_config() -> Arc<VaultConfig> {
+    VAULT_CONFIG.read().await.clone().unwrap_or_default()
+}
@@ -0,0 +1,553 @@
+//! # Index Manifest
+//!
+//! Records which version of every note of a vault has been embedded, so that
+//! indexing picks up where it left off after a crash or restart instead of
+//! re-embedding the whole vault.
+//!
+//! Each entry maps a note's vault-relative path to the modification time, size
+//! and SHA-256 hash of the content that was indexed, the embedding model and
+//! the IDs of the note's chunks. An entry is only written once all of a note's
+//! embeddings are stored, so a note interrupted half-way is indexed again.
+//! Notes that embed other notes also record the content hash of every note
+//! their embeds were read from.
+//!
+//! ## Staleness
+//!
+//! A note is current when its entry was written for the same model and its
+//! modification time and size are unchanged. When only the modification time
+//! differs, the content hash decides, so touching a note or restoring it from
+//! a backup does not re-embed it. A note is never current once a note it
+//! embeds changed or disappeared, since its chunks contain the embedded text.
+//!
+//! ## Persistence
+//!
+//! The manifest lives in `{vault}/.ainote/index_manifest.json`. Every change is
+//! appended to `{vault}/.ainote/index_manifest.journal` as one JSON record per
+//! line and synced to disk before the call returns. The journal is folded into
+//! the snapshot once it holds more records than the manifest has entries. A
+//! torn last record left by a crash is ignored on load.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// File name of the manifest snapshot inside `{vault}/.ainote`
+pub const INDEX_MANIFEST_FILE: &str = "index_manifest.json";
+
+/// File name of the manifest journal inside `{vault}/.ainote`
+pub const INDEX_MANIFEST_JOURNAL_FILE: &str = "index_manifest.journal";
+
+/// Version of the persisted format; other versions are rebuilt from scratch
+const INDEX_MANIFEST_VERSION: u32 = 1;
+
+/// Journal records always allowed before compaction, however small the manifest
+const MIN_JOURNAL_RECORDS: usize = 1024;
+
+/// Errors that can occur while reading or updating the index manifest
+#[derive(Error, Debug)]
+pub enum IndexManifestError {
+    #[error("Path is not inside the vault: {path}")]
+    OutsideVault { path: String },
+
+    #[error("I/O error: {message}")]
+    IOError { message: String },
+
+    #[error("Serialization error: {message}")]
+    SerializationError { message: String },
+
+    #[error("Index manifest lock poisoned")]
+    LockPoisoned,
+}
+
+pub type IndexManifestResult<T> = Result<T, IndexManifestError>;
+
+/// The indexed version of a note
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Modification time of the note in milliseconds since the epoch
+    pub modified_ms: u64,
+    /// Size of the note in bytes
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the indexed content
+    pub content_hash: String,
+    /// Embedding model the note was indexed with
+    pub model: String,
+    /// IDs of the note's chunks, in document order
+    pub chunk_ids: Vec<String>,
+    /// When the note was indexed, in seconds since the epoch
+    pub indexed_at: u64,
+    /// Content hashes of the notes embedded in this one, by vault-relative path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub embeds: BTreeMap<String, String>,
+}
+
+impl ManifestEntry {
+    /// Entry for content read from a note whose version was `(modified_ms, size)`
+    ///
+    /// Take the version with [`file_version`] before reading the note, so a
+    /// change made while it is indexed shows up as a newer modification time.
+    pub fn new(version: (u64, u64), content: &str, model: &str, chunk_ids: Vec<String>) -> Self {
+        Self {
+            modified_ms: version.0,
+            size: version.1,
+            content_hash: content_hash(content),
+            model: model.to_string(),
+            chunk_ids,
+            indexed_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            embeds: BTreeMap::new(),
+        }
+    }
+
+    /// Record the content hashes of the embedded notes, from [`IndexManifest::embed_hashes`]
+    pub fn with_embeds(mut self, embeds: BTreeMap<String, String>) -> Self {
+        self.embeds = embeds;
+        self
+    }
+}
+
+/// Manifest contents as stored in the snapshot
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedManifest {
+    version: u32,
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+/// One change appended to the journal
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalRecord {
+    Upsert { path: String, entry: ManifestEntry },
+    Remove { path: String },
+}
+
+/// Entries and the open journal, guarded together so records stay in order
+struct ManifestState {
+    entries: BTreeMap<String, ManifestEntry>,
+    journal: File,
+    journal_records: usize,
+}
+
+/// Per-vault record of the notes whose embeddings are up to date
+pub struct IndexManifest {
+    vault_path: PathBuf,
+    state: Mutex<ManifestState>,
+}
+
+impl IndexManifest {
+    /// Open the manifest of a vault, replaying the journal over the snapshot
+    pub fn open(vault_path: impl AsRef<Path>) -> IndexManifestResult<Self> {
+        let vault_path = vault_path.as_ref().to_path_buf();
+        let storage_dir = vault_path.join(".ainote");
+        std::fs::create_dir_all(&storage_dir).map_err(|e| IndexManifestError::IOError {
+            message: format!("Failed to create manifest directory: {}", e),
+        })?;
+
+        let mut entries = Self::load_snapshot(&storage_dir.join(INDEX_MANIFEST_FILE));
+        let journal_path = storage_dir.join(INDEX_MANIFEST_JOURNAL_FILE);
+        let journal_records = Self::replay_journal(&journal_path, &mut entries)?;
+        let journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&journal_path)
+            .map_err(|e| IndexManifestError::IOError { message: format!("Failed to open manifest journal: {}", e) })?;
+
+        let manifest = Self { vault_path, state: Mutex::new(ManifestState { entries, journal, journal_records }) };
+        {
+            let mut state = manifest.state.lock().map_err(|_| IndexManifestError::LockPoisoned)?;
+            if journal_records > 0 {
+                // Fold the replayed journal in, which also drops a torn last record
+                manifest.compact(&mut state)?;
+            }
+            log::info!("📒 Index manifest opened for {:?}: {} notes", manifest.vault_path, state.entries.len());
+        }
+        Ok(manifest)
+    }
+
+    /// Root directory of the vault
+    pub fn vault_path(&self) -> &Path {
+        &self.vault_path
+    }
+
+    /// Location of the manifest snapshot
+    pub fn storage_path(&self) -> PathBuf {
+        self.vault_path.join(".ainote").join(INDEX_MANIFEST_FILE)
+    }
+
+    /// Location of the manifest journal
+    pub fn journal_path(&self) -> PathBuf {
+        self.vault_path.join(".ainote").join(INDEX_MANIFEST_JOURNAL_FILE)
+    }
+
+    /// Number of notes in the manifest
+    pub fn len(&self) -> usize {
+        self.state.lock().map(|state| state.entries.len()).unwrap_or(0)
+    }
+
+    /// Whether the manifest has no notes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Indexed version of a note, if any
+    pub fn entry(&self, path: &Path) -> IndexManifestResult<Option<ManifestEntry>> {
+        let key = self.relative_key(path)?;
+        let state = self.state.lock().map_err(|_| IndexManifestError::LockPoisoned)?;
+        Ok(state.entries.get(&key).cloned())
+    }
+
+    /// Absolute paths of all notes in the manifest
+    pub fn indexed_paths(&self) -> Vec<PathBuf> {
+        self.state
+            .lock()
+            .map(|state| state.entries.keys().map(|key| self.vault_path.join(key)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Absolute paths of the notes that embed any of `paths`
+    pub fn embedding_notes(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let keys: HashSet<String> = paths.iter().filter_map(|path| self.relative_key(path).ok()).collect();
+        if keys.is_empty() {
+            return Vec::new();
+        }
+        self.state
+            .lock()
+            .map(|state| {
+                state
+                    .entries
+                    .iter()
+                    .filter(|(_, entry)| entry.embeds.keys().any(|key| keys.contains(key)))
+                    .map(|(key, _)| self.vault_path.join(key))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Content hashes of embedded notes, keyed for [`ManifestEntry::with_embeds`]
+    ///
+    /// Notes outside the vault are left out; unreadable notes get an empty
+    /// hash, which never matches, so the embedding note is indexed again.
+    pub fn embed_hashes(&self, sources: &[PathBuf]) -> BTreeMap<String, String> {
+        sources
+            .iter()
+            .filter_map(|path| {
+                let key = self.relative_key(path).ok()?;
+                let hash = std::fs::read_to_string(path).map(|content| content_hash(&content)).unwrap_or_default();
+                Some((key, hash))
+            })
+            .collect()
+    }
+
+    /// Whether a note's embeddings for `model` match its current content
+    ///
+    /// A note whose modification time changed but whose content hash did not
+    /// is current; its entry is updated to the new modification time. A note
+    /// whose embedded notes changed is not.
+    pub fn is_current(&self, path: &Path, model: &str) -> IndexManifestResult<bool> {
+        let Some(entry) = self.entry(path)? else {
+            return Ok(false);
+        };
+        let (modified_ms, size) = file_version(path);
+        if entry.model != model || entry.size != size || !self.embeds_unchanged(&entry) {
+            return Ok(false);
+        }
+        if entry.modified_ms == modified_ms {
+            return Ok(true);
+        }
+
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Ok(false);
+        };
+        if content_hash(&content) != entry.content_hash {
+            return Ok(false);
+        }
+        log::debug!("📒 Content of {:?} unchanged since it was indexed, keeping its embeddings", path);
+        self.record(path, ManifestEntry { modified_ms, ..entry })?;
+        Ok(true)
+    }
+
+    /// Whether every note embedded in an entry still has the content it was indexed with
+    fn embeds_unchanged(&self, entry: &ManifestEntry) -> bool {
+        entry.embeds.iter().all(|(key, hash)| {
+            std::fs::read_to_string(self.vault_path.join(key)).is_ok_and(|content| &content_hash(&content) == hash)
+        })
+    }
+
+    /// Record the indexed version of a note
+    pub fn record(&self, path: &Path, entry: ManifestEntry) -> IndexManifestResult<()> {
+        let key = self.relative_key(path)?;
+        self.apply(JournalRecord::Upsert { path: key, entry })
+    }
+
+    /// Forget a note, returning whether it was in the manifest
+    pub fn remove(&self, path: &Path) -> IndexManifestResult<bool> {
+        let key = self.relative_key(path)?;
+        let contained = {
+            let state = self.state.lock().map_err(|_| IndexManifestError::LockPoisoned)?;
+            state.entries.contains_key(&key)
+        };
+        if contained {
+            self.apply(JournalRecord::Remove { path: key })?;
+        }
+        Ok(contained)
+    }
+
+    /// Forget every note so the whole vault is indexed again
+    pub fn clear(&self) -> IndexManifestResult<()> {
+        let mut state = self.state.lock().map_err(|_| IndexManifestError::LockPoisoned)?;
+        state.entries.clear();
+        self.compact(&mut state)?;
+        log::info!("🗑️ Cleared index manifest for {:?}", self.vault_path);
+        Ok(())
+    }
+
+    /// Append a change to the journal and apply it to the entries
+    fn apply(&self, record: JournalRecord) -> IndexManifestResult<()> {
+        let mut line = serde_json::to_vec(&record).map_err(|e| IndexManifestError::SerializationError {
+            message: format!("Failed to serialize manifest record: {}", e),
+        })?;
+        line.push(b'\n');
+
+        let mut state = self.state.lock().map_err(|_| IndexManifestError::LockPoisoned)?;
+        state
+            .journal
+            .write_all(&line)
+            .and_then(|_| state.journal.sync_data())
+            .map_err(|e| IndexManifestError::IOError { message: format!("Failed to write manifest journal: {}", e) })?;
+        state.journal_records += 1;
+
+        match record {
+            JournalRecord::Upsert { path, entry } => {
+                state.entries.insert(path, entry);
+            }
+            JournalRecord::Remove { path } => {
+                state.entries.remove(&path);
+            }
+        }
+
+        if state.journal_records > state.entries.len().max(MIN_JOURNAL_RECORDS) {
+            self.compact(&mut state)?;
+        }
+        Ok(())
+    }
+
+    /// Write the entries to the snapshot and empty the journal
+    fn compact(&self, state: &mut ManifestState) -> IndexManifestResult<()> {
+        let storage_path = self.storage_path();
+        let persisted = PersistedManifest { version: INDEX_MANIFEST_VERSION, entries: state.entries.clone() };
+        let serialized = serde_json::to_vec(&persisted).map_err(|e| IndexManifestError::SerializationError {
+            message: format!("Failed to serialize index manifest: {}", e),
+        })?;
+
+        // The snapshot replaces the old one atomically; replaying the journal over it again is harmless
+        let temp_path = storage_path.with_extension("json.tmp");
+        File::create(&temp_path)
+            .and_then(|mut file| file.write_all(&serialized).and_then(|_| file.sync_all()))
+            .and_then(|_| std::fs::rename(&temp_path, &storage_path))
+            .and_then(|_| state.journal.set_len(0))
+            .and_then(|_| state.journal.sync_all())
+            .map_err(|e| IndexManifestError::IOError { message: format!("Failed to write index manifest: {}", e) })?;
+        state.journal_records = 0;
+
+        log::debug!("💾 Compacted index manifest with {} notes to {:?}", state.entries.len(), storage_path);
+        Ok(())
+    }
+
+    /// Entries of the snapshot, or none if it is missing or unreadable
+    fn load_snapshot(path: &Path) -> BTreeMap<String, ManifestEntry> {
+        let Ok(content) = std::fs::read(path) else {
+            return BTreeMap::new();
+        };
+        match serde_json::from_slice::<PersistedManifest>(&content) {
+            Ok(persisted) if persisted.version == INDEX_MANIFEST_VERSION => persisted.entries,
+            Ok(persisted) => {
+                log::info!("📒 Index manifest version {} is outdated, re-indexing the vault", persisted.version);
+                BTreeMap::new()
+            }
+            Err(e) => {
+                log::warn!("⚠️ Index manifest {:?} is unreadable, re-indexing the vault: {}", path, e);
+                BTreeMap::new()
+            }
+        }
+    }
+
+    /// Apply the journal to `entries`, returning the number of records read
+    fn replay_journal(path: &Path, entries: &mut BTreeMap<String, ManifestEntry>) -> IndexManifestResult<usize> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => {
+                return Err(IndexManifestError::IOError { message: format!("Failed to read manifest journal: {}", e) })
+            }
+        };
+
+        let mut records = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| IndexManifestError::IOError {
+                message: format!("Failed to read manifest journal: {}", e),
+            })?;
+            match serde_json::from_str::<JournalRecord>(&line) {
+                Ok(JournalRecord::Upsert { path, entry }) => {
+                    entries.insert(path, entry);
+                }
+                Ok(JournalRecord::Remove { path }) => {
+                    entries.remove(&path);
+                }
+                Err(e) => {
+                    // Only the record being written during a crash can be torn
+                    log::warn!("⚠️ Ignoring torn index manifest record after {} records: {}", records, e);
+                    break;
+                }
+            }
+            records += 1;
+        }
+        Ok(records)
+    }
+
+    /// Vault-relative key of a note, with `/` separators
+    fn relative_key(&self, path: &Path) -> IndexManifestResult<String> {
+        let outside = || IndexManifestError::OutsideVault { path: path.to_string_lossy().to_string() };
+        let relative = path.strip_prefix(&self.vault_path).map_err(|_| outside())?;
+
+        let mut parts = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+                Component::CurDir => {}
+                _ => return Err(outside()),
+            }
+        }
+        if parts.is_empty() {
+            return Err(outside());
+        }
+        Ok(parts.join("/"))
+    }
+}
+
+impl std::fmt::Debug for IndexManifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexManifest")
+            .field("vault_path", &self.vault_path)
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
+/// Modification time in milliseconds and size of a file, `(0, 0)` if it cannot be read
+pub fn file_version(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return (0, 0);
+    };
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    (modified_ms, metadata.len())
+}
+
+/// Hex-encoded SHA-256 of note content
+fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn index_note(manifest: &IndexManifest, path: &Path, content: &str) {
+        std::fs::write(path, content).unwrap();
+        let entry = ManifestEntry::new(file_version(path), content, "test-model", vec!["chunk-1".to_string()]);
+        manifest.record(path, entry).unwrap();
+    }
+
+    #[test]
+    fn test_staleness_by_version_hash_and_model() {
+        let vault = TempDir::new().unwrap();
+        let note = vault.path().join("note.md");
+        let manifest = IndexManifest::open(vault.path()).unwrap();
+
+        assert!(!manifest.is_current(&note, "test-model").unwrap());
+        index_note(&manifest, &note, "first version");
+        assert!(manifest.is_current(&note, "test-model").unwrap());
+        assert!(!manifest.is_current(&note, "other-model").unwrap());
+
+        // Same content with a new modification time stays current
+        let touched = File::options().write(true).open(&note).unwrap();
+        touched.set_modified(SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+        assert!(manifest.is_current(&note, "test-model").unwrap());
+        assert_eq!(manifest.entry(&note).unwrap().unwrap().modified_ms, file_version(&note).0);
+
+        // Same size, different content
+        std::fs::write(&note, "other version").unwrap();
+        touched.set_modified(SystemTime::now() + std::time::Duration::from_secs(10)).unwrap();
+        assert!(!manifest.is_current(&note, "test-model").unwrap());
+
+        assert!(manifest.record(Path::new("/elsewhere/note.md"), manifest.entry(&note).unwrap().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_changed_embeds_make_notes_stale() {
+        let vault = TempDir::new().unwrap();
+        let note = vault.path().join("note.md");
+        let embedded = vault.path().join("embedded.md");
+        let other = vault.path().join("other.md");
+        std::fs::write(&embedded, "embedded text").unwrap();
+        let manifest = IndexManifest::open(vault.path()).unwrap();
+
+        std::fs::write(&note, "intro ![[embedded]]").unwrap();
+        let embeds = manifest.embed_hashes(&[embedded.clone(), PathBuf::from("/elsewhere/note.md")]);
+        assert_eq!(embeds.keys().collect::<Vec<_>>(), vec!["embedded.md"]);
+        let entry = ManifestEntry::new(file_version(&note), "intro ![[embedded]]", "test-model", Vec::new()).with_embeds(embeds);
+        manifest.record(&note, entry).unwrap();
+        index_note(&manifest, &other, "no embeds");
+
+        assert!(manifest.is_current(&note, "test-model").unwrap());
+        assert_eq!(manifest.embedding_notes(std::slice::from_ref(&embedded)), vec![note.clone()]);
+        assert!(manifest.embedding_notes(std::slice::from_ref(&other)).is_empty());
+
+        std::fs::write(&embedded, "edited text").unwrap();
+        assert!(!manifest.is_current(&note, "test-model").unwrap());
+        std::fs::remove_file(&embedded).unwrap();
+        assert!(!manifest.is_current(&note, "test-model").unwrap());
+        assert!(manifest.is_current(&other, "test-model").unwrap());
+
+        // Embeds are kept across a reopen
+        drop(manifest);
+        let manifest = IndexManifest::open(vault.path()).unwrap();
+        assert_eq!(manifest.embedding_notes(&[embedded]), vec![note]);
+    }
+
+    #[test]
+    fn test_journal_survives_reopen_and_torn_record() {
+        let vault = TempDir::new().unwrap();
+        std::fs::create_dir(vault.path().join("folder")).unwrap();
+        let kept = vault.path().join("folder").join("kept.md");
+        let removed = vault.path().join("removed.md");
+
+        {
+            let manifest = IndexManifest::open(vault.path()).unwrap();
+            index_note(&manifest, &kept, "kept note");
+            index_note(&manifest, &removed, "removed note");
+            assert!(manifest.remove(&removed).unwrap());
+            assert!(!manifest.remove(&removed).unwrap());
+        }
+
+        // A crash in the middle of a record leaves half a line behind
+        let mut journal = OpenOptions::new().append(true).open(vault.path().join(".ainote").join(INDEX_MANIFEST_JOURNAL_FILE)).unwrap();
+        journal.write_all(b"{\"op\":\"upsert\",\"path\":\"tor").unwrap();
+        drop(journal);
+
+        let manifest = IndexManifest::open(vault.path()).unwrap();
+        assert_eq!(manifest.indexed_paths(), vec![kept.clone()]);
+        assert!(manifest.is_current(&kept, "test-model").unwrap());
+        assert_eq!(std::fs::metadata(manifest.journal_path()).unwrap().len(), 0);
+    }
+}
@@ -10,6 +10,9 @@
 //! - **Progress tracking**: Thread-safe progress reporting with minimal overhead
 //! - **Cancellation support**: Clean cancellation without data corruption
 //! - **Memory management**: Efficient resource usage for large vault processing
+//! - **Incremental re-indexing**: Only chunks whose content-addressed ID changed are re-embedded
+//! - **Resumable bulk indexing**: A per-vault manifest skips notes that are already embedded
+//! - **Vault compatibility**: Obsidian and Logseq ignore lists are honored and embeds are indexed as the text they show
 //! - **Error handling**: Comprehensive error recovery and logging
 //!
 //! ## Architecture
@@ -19,6 +22,7 @@
 //! - `IndexingQueue`: Priority-based queue for processing requests
 //! - `ProgressReporter`: Thread-safe progress tracking infrastructure
 //! - `CancellationToken`: Cooperative cancellation mechanism
+//! - `IndexManifest`: Indexed version of every note, updated as each file completes
 //!
 //! ## Usage
 //!
@@ -35,8 +39,8 @@
 //! pipeline.queue_file("path/to/file.md", Priority::UserTriggered).await?;
 //! ```
 
-use std::collections::{HashMap, VecDeque};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, RwLock};
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::thread::{self, JoinHandle};
@@ -46,9 +50,17 @@ use thiserror::Error;
 use tokio::time::timeout;
 use glob::glob;
 
-use crate::text_chunker::ChunkProcessor;
-use crate::embedding_generator::EmbeddingGenerator;
+use crate::text_chunker::{ChunkProcessor, TextChunk};
+use crate::note_metadata::NoteMetadata;
+use crate::embedding_generator::EmbeddingError;
+use crate::embedding_provider::EmbeddingProvider;
+use crate::index_manifest::{file_version, IndexManifest, ManifestEntry};
+use crate::vault_compat::VaultCompat;
 use crate::vector_db::VectorDatabase;
+use crate::vector_db::types::{
+    EmbeddingEntry, EmbeddingMetadata, CHUNK_SPAN_METADATA_KEY, HEADING_PATH_METADATA_KEY, NOTE_METADATA_KEYS,
+    PARENT_CHUNKS_METADATA_KEY,
+};
 
 /// Errors that can occur during indexing pipeline operations
 #[derive(Error, Debug)]
@@ -203,6 +215,9 @@ pub struct IndexingProgress {
     pub processing_files: u64,
     /// Number of files failed
     pub failed_files: u64,
+    /// Number of files skipped because their embeddings are up to date
+    #[serde(default)]
+    pub skipped_files: u64,
     /// Number of files queued
     pub queued_files: u64,
     /// Overall progress percentage (0-100)
@@ -224,6 +239,7 @@ impl Default for IndexingProgress {
             completed_files: 0,
             processing_files: 0,
             failed_files: 0,
+            skipped_files: 0,
             queued_files: 0,
             progress_percent: 0.0,
             files_per_second: 0.0,
@@ -247,6 +263,104 @@ pub struct PipelineState {
     pub config: PipelineConfig,
 }
 
+/// Difference between the stored embeddings of a file and its current chunks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChunkDiff {
+    /// Indices of chunks that have no matching embedding and must be embedded
+    pub to_embed: Vec<usize>,
+    /// Number of chunks whose embedding is reused as is
+    pub unchanged: usize,
+    /// Unchanged chunks whose byte span, parents or note metadata changed, as (chunk index, index into `existing`)
+    pub relocated: Vec<(usize, usize)>,
+    /// IDs of stored entries whose chunk no longer exists
+    pub stale_ids: Vec<String>,
+}
+
+impl ChunkDiff {
+    /// Compare stored entries with freshly chunked content
+    ///
+    /// A chunk is unchanged when an entry with the same chunk ID and the same
+    /// `EmbeddingMetadata::text_hash` exists. `existing` must only contain
+    /// entries of the file and model being indexed, and chunks must carry IDs
+    /// from `ChunkProcessor::assign_chunk_ids`.
+    pub fn compute(existing: &[EmbeddingEntry], chunks: &[TextChunk]) -> Self {
+        let current: HashMap<&str, String> = chunks
+            .iter()
+            .map(|chunk| (chunk.metadata.chunk_id.as_str(), EmbeddingMetadata::compute_text_hash(&chunk.content)))
+            .collect();
+        let stored: HashMap<&str, usize> = existing
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.metadata.chunk_id.as_str(), index))
+            .collect();
+
+        let mut diff = Self::default();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let matching = stored
+                .get(chunk.metadata.chunk_id.as_str())
+                .filter(|&&entry_index| current[chunk.metadata.chunk_id.as_str()] == existing[entry_index].metadata.text_hash);
+            match matching {
+                Some(&entry_index) => {
+                    diff.unchanged += 1;
+                    let span = (chunk.metadata.start_position, chunk.metadata.end_position);
+                    let parents = EmbeddingMetadata::encode_parent_chunks(&chunk.metadata.parents);
+                    let stored_metadata = &existing[entry_index].metadata;
+                    if stored_metadata.chunk_span() != Some(span)
+                        || stored_metadata.get_custom_metadata(PARENT_CHUNKS_METADATA_KEY) != parents.as_ref()
+                    {
+                        diff.relocated.push((index, entry_index));
+                    }
+                }
+                None => diff.to_embed.push(index),
+            }
+        }
+        diff.stale_ids = existing
+            .iter()
+            .filter(|entry| {
+                current.get(entry.metadata.chunk_id.as_str()) != Some(&entry.metadata.text_hash)
+            })
+            .map(|entry| entry.id.clone())
+            .collect();
+        diff
+    }
+
+    /// Mark unchanged chunks whose stored note metadata is outdated as relocated
+    ///
+    /// Editing a note's frontmatter or tags changes the metadata of every chunk
+    /// without changing their text, so those chunks are re-stored with their
+    /// existing vector instead of being re-embedded.
+    pub fn restamp_changed_note_metadata(
+        &mut self,
+        existing: &[EmbeddingEntry],
+        chunks: &[TextChunk],
+        note_metadata: &HashMap<String, String>,
+    ) {
+        let to_embed: HashSet<usize> = self.to_embed.iter().copied().collect();
+        let relocated: HashSet<usize> = self.relocated.iter().map(|&(chunk_index, _)| chunk_index).collect();
+        let stored: HashMap<&str, usize> = existing
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.metadata.chunk_id.as_str(), index))
+            .collect();
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            if to_embed.contains(&index) || relocated.contains(&index) {
+                continue;
+            }
+            let Some(&entry_index) = stored.get(chunk.metadata.chunk_id.as_str()) else {
+                continue;
+            };
+            let stored_metadata = &existing[entry_index].metadata.custom_metadata;
+            let outdated = NOTE_METADATA_KEYS
+                .iter()
+                .any(|key| stored_metadata.get(*key) != note_metadata.get(*key));
+            if outdated {
+                self.relocated.push((index, entry_index));
+            }
+        }
+    }
+}
+
 /// Thread-safe cancellation token for cooperative cancellation
 #[derive(Debug)]
 pub struct CancellationToken {
@@ -436,10 +550,14 @@ pub struct IndexingPipeline {
     failed_counter: Arc<AtomicU64>,
     /// Text chunker for processing files
     text_chunker: Arc<ChunkProcessor>,
-    /// Embedding generator for creating vectors
-    embedding_generator: Arc<EmbeddingGenerator>,
+    /// Embedding provider for creating vectors
+    embedding_provider: Arc<dyn EmbeddingProvider>,
     /// Vector database for storing embeddings
     vector_db: Arc<VectorDatabase>,
+    /// Manifest of the vault being indexed, opened by `bulk_index_vault`
+    manifest: Arc<RwLock<Option<Arc<IndexManifest>>>>,
+    /// Layout of the vault being indexed, opened by `bulk_index_vault`
+    vault_compat: Arc<RwLock<Option<Arc<VaultCompat>>>>,
 }
 
 impl IndexingPipeline {
@@ -447,7 +565,7 @@ impl IndexingPipeline {
     pub fn new(
         config: PipelineConfig,
         text_chunker: Arc<ChunkProcessor>,
-        embedding_generator: Arc<EmbeddingGenerator>,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
         vector_db: Arc<VectorDatabase>,
     ) -> Self {
         let queue = Arc::new(IndexingQueue::new(config.max_queue_size));
@@ -463,8 +581,10 @@ impl IndexingPipeline {
             completed_counter: Arc::new(AtomicU64::new(0)),
             failed_counter: Arc::new(AtomicU64::new(0)),
             text_chunker,
-            embedding_generator,
+            embedding_provider,
             vector_db,
+            manifest: Arc::new(RwLock::new(None)),
+            vault_compat: Arc::new(RwLock::new(None)),
         }
     }
     
@@ -562,6 +682,14 @@ impl IndexingPipeline {
     /// This method scans the vault directory for markdown files and queues them
     /// for bulk indexing with configurable concurrency.
     /// 
+    /// Files whose entry in the vault's index manifest is current are skipped,
+    /// and the embeddings of files that were deleted since they were indexed
+    /// are removed, so an interrupted run resumes where it stopped.
+    /// 
+    /// Files ignored by an Obsidian or Logseq vault are not indexed, and the
+    /// embeds and block references of its notes are indexed as the text they
+    /// stand for.
+    /// 
     /// # Arguments
     /// 
     /// * `vault_path` - Path to the vault directory
@@ -570,7 +698,7 @@ impl IndexingPipeline {
     /// 
     /// # Returns
     /// 
-    /// Vector of request IDs for the queued files
+    /// Vector of request IDs for the queued files (skipped files have none)
     pub async fn bulk_index_vault(
         &self,
         vault_path: PathBuf,
@@ -592,6 +720,21 @@ impl IndexingPipeline {
             });
         }
         
+        let manifest = Arc::new(IndexManifest::open(&vault_path).map_err(|e| IndexingError::IOError {
+            message: format!("Failed to open index manifest: {}", e),
+        })?);
+        *self.manifest.write().unwrap() = Some(Arc::clone(&manifest));
+        self.remove_deleted_files(&manifest).await?;
+        
+        let vault_compat = match VaultCompat::open(&vault_path) {
+            Ok(vault_compat) => Some(Arc::new(vault_compat)),
+            Err(e) => {
+                log::warn!("⚠️ Indexing without vault compatibility: {}", e);
+                None
+            }
+        };
+        *self.vault_compat.write().unwrap() = vault_compat.clone();
+        
         // Build glob pattern for markdown files
         let pattern = file_pattern.unwrap_or_else(|| "**/*.md".to_string());
         let full_pattern = vault_path.join(&pattern);
@@ -607,7 +750,8 @@ impl IndexingPipeline {
                 for entry in entries {
                     match entry {
                         Ok(path) => {
-                            if path.is_file() {
+                            let ignored = vault_compat.as_ref().is_some_and(|compat| compat.ignores(&path));
+                            if path.is_file() && !ignored {
                                 markdown_files.push(path);
                             }
                         }
@@ -624,12 +768,24 @@ impl IndexingPipeline {
             }
         }
         
-        log::info!("📝 Found {} markdown files to index", markdown_files.len());
+        // Files embedded by an earlier run are not queued again
+        let found_files = markdown_files.len();
+        let mut stale_files = Vec::new();
+        for file_path in markdown_files {
+            if !self.is_indexed(&manifest, &file_path).await {
+                stale_files.push(file_path);
+            }
+        }
+        let skipped_files = (found_files - stale_files.len()) as u64;
+        self.progress.write().unwrap().skipped_files += skipped_files;
+        
+        log::info!("📝 Found {} markdown files, {} already indexed, {} to index", 
+                   found_files, skipped_files, stale_files.len());
         
-        // Queue all files for indexing
+        // Queue stale and new files for indexing
         let mut request_ids = Vec::new();
         
-        for file_path in markdown_files {
+        for file_path in stale_files {
             match self.queue_file(file_path.clone(), priority) {
                 Ok(request_id) => {
                     request_ids.push(request_id);
@@ -652,14 +808,69 @@ impl IndexingPipeline {
         Ok(request_ids)
     }
     
+    /// Manifest of the vault opened by the last `bulk_index_vault` call
+    pub fn index_manifest(&self) -> Option<Arc<IndexManifest>> {
+        self.manifest.read().unwrap().clone()
+    }
+    
+    /// Whether a file's manifest entry is current and its embeddings are still stored
+    async fn is_indexed(&self, manifest: &IndexManifest, file_path: &Path) -> bool {
+        let model_id = self.embedding_provider.model_id(&self.config.embedding_model);
+        let entry = match manifest.is_current(file_path, &model_id) {
+            Ok(true) => manifest.entry(file_path).ok().flatten(),
+            Ok(false) => return false,
+            Err(e) => {
+                log::warn!("⚠️ Failed to check index manifest for {:?}: {}", file_path, e);
+                return false;
+            }
+        };
+        
+        // Embeddings removed behind the manifest's back (e.g. a rebuilt database) are recreated
+        match entry {
+            Some(entry) if !entry.chunk_ids.is_empty() => {
+                self.vector_db.has_embeddings_for_file(&file_path.to_string_lossy()).await
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+    
+    /// Delete the embeddings of files in the manifest that no longer exist
+    async fn remove_deleted_files(&self, manifest: &IndexManifest) -> IndexingResult<usize> {
+        let mut removed_files = 0;
+        
+        for file_path in manifest.indexed_paths() {
+            if file_path.exists() {
+                continue;
+            }
+            
+            let file_path_str = file_path.to_string_lossy().to_string();
+            let deleted = self.vector_db.delete_embeddings_by_file(&file_path_str).await.map_err(|e| {
+                IndexingError::FileProcessingError {
+                    path: file_path_str.clone(),
+                    reason: format!("Failed to delete embeddings of deleted file: {}", e),
+                }
+            })?;
+            manifest.remove(&file_path).map_err(|e| IndexingError::IOError {
+                message: format!("Failed to update index manifest: {}", e),
+            })?;
+            
+            log::info!("🗑️ Removed {} embeddings of deleted file {:?}", deleted, file_path);
+            removed_files += 1;
+        }
+        
+        Ok(removed_files)
+    }
+    
     /// Index files with real-time debouncing
     /// 
     /// This method handles real-time file changes with debouncing to avoid
-    /// excessive indexing when files are rapidly modified.
+    /// excessive indexing when files are rapidly modified. Notes whose
+    /// manifest entry records an embed of one of the files are queued too.
     /// 
     /// # Arguments
     /// 
-    /// * `file_paths` - Files that have changed
+    /// * `file_paths` - Files that have changed or were deleted
     /// * `debounce_ms` - Debounce time in milliseconds (default: 1000ms)
     /// 
     /// # Returns
@@ -682,9 +893,18 @@ impl IndexingPipeline {
             return Err(IndexingError::Cancelled);
         }
         
+        // Notes that embed a changed or deleted note hold its old text
+        let embedding_notes = self.index_manifest()
+            .map(|manifest| manifest.embedding_notes(&file_paths))
+            .unwrap_or_default();
+        let mut queued: HashSet<PathBuf> = HashSet::new();
         let mut request_ids = Vec::new();
         
-        for file_path in file_paths {
+        for file_path in file_paths.into_iter().chain(embedding_notes) {
+            if !queued.insert(file_path.clone()) {
+                continue;
+            }
+            
             // Check if file still exists after debounce period
             if !file_path.exists() {
                 log::debug!("📁 File no longer exists, skipping: {:?}", file_path);
@@ -870,10 +1090,12 @@ impl IndexingPipeline {
             let completed_counter = Arc::clone(&self.completed_counter);
             let failed_counter = Arc::clone(&self.failed_counter);
             let text_chunker = Arc::clone(&self.text_chunker);
-            let embedding_generator = Arc::clone(&self.embedding_generator);
+            let embedding_provider = Arc::clone(&self.embedding_provider);
             let vector_db = Arc::clone(&self.vector_db);
             let timeout = Duration::from_secs(self.config.file_timeout_seconds);
             let embedding_model = self.config.embedding_model.clone();
+            let manifest = Arc::clone(&self.manifest);
+            let vault_compat = Arc::clone(&self.vault_compat);
             
             let worker = thread::Builder::new()
                 .name(format!("indexing-worker-{}", worker_id))
@@ -891,8 +1113,10 @@ impl IndexingPipeline {
                         completed_counter,
                         failed_counter,
                         text_chunker,
-                        embedding_generator,
+                        embedding_provider,
                         vector_db,
+                        manifest,
+                        vault_compat,
                         timeout,
                         embedding_model,
                     ));
@@ -914,8 +1138,10 @@ impl IndexingPipeline {
         completed_counter: Arc<AtomicU64>,
         failed_counter: Arc<AtomicU64>,
         text_chunker: Arc<ChunkProcessor>,
-        embedding_generator: Arc<EmbeddingGenerator>,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
         vector_db: Arc<VectorDatabase>,
+        manifest: Arc<RwLock<Option<Arc<IndexManifest>>>>,
+        vault_compat: Arc<RwLock<Option<Arc<VaultCompat>>>>,
         file_timeout: Duration,
         embedding_model: String,
     ) {
@@ -928,6 +1154,8 @@ impl IndexingPipeline {
                 
                 let file_path = request.file_path.clone();
                 let request_id = request.id;
+                let chunker = Self::chunker_for(&text_chunker, &vault_compat, &file_path);
+                let embeds = Self::embed_hashes(&manifest, &vault_compat, &file_path);
                 
                 // Process the file with timeout
                 let processing_result = timeout(
@@ -935,8 +1163,8 @@ impl IndexingPipeline {
                     Self::process_file(
                         worker_id,
                         &file_path,
-                        &text_chunker,
-                        &embedding_generator,
+                        &chunker,
+                        embedding_provider.as_ref(),
                         &vector_db,
                         &cancellation_token,
                         &embedding_model,
@@ -944,8 +1172,9 @@ impl IndexingPipeline {
                 ).await;
                 
                 match processing_result {
-                    Ok(Ok(())) => {
-                        // File processed successfully
+                    Ok(Ok(manifest_entry)) => {
+                        // File processed successfully; record it only now that all its embeddings are stored
+                        Self::record_indexed_file(&manifest, &file_path, manifest_entry.with_embeds(embeds));
                         queue.update_request_status(request_id, IndexingStatus::Completed);
                         completed_counter.fetch_add(1, Ordering::SeqCst);
                         log::debug!("✅ Worker {} completed file: {:?}", worker_id, file_path);
@@ -978,22 +1207,77 @@ impl IndexingPipeline {
         log::debug!("🛑 Worker {} stopped", worker_id);
     }
     
+    /// Chunker that resolves the embeds of a note in the vault being indexed
+    fn chunker_for(
+        text_chunker: &Arc<ChunkProcessor>,
+        vault_compat: &RwLock<Option<Arc<VaultCompat>>>,
+        file_path: &Path,
+    ) -> Arc<ChunkProcessor> {
+        match vault_compat.read().unwrap().as_ref() {
+            Some(compat) if compat.contains_path(file_path) => Arc::new(
+                text_chunker.as_ref().clone().with_transclusion_resolver(compat.resolver_for(file_path)),
+            ),
+            _ => Arc::clone(text_chunker),
+        }
+    }
+    
+    /// Content hashes of the notes a file embeds, for its manifest entry
+    ///
+    /// Taken before the file is chunked, so an embedded note edited while the
+    /// file is indexed makes the file stale rather than current.
+    fn embed_hashes(
+        manifest: &RwLock<Option<Arc<IndexManifest>>>,
+        vault_compat: &RwLock<Option<Arc<VaultCompat>>>,
+        file_path: &Path,
+    ) -> BTreeMap<String, String> {
+        let Some(manifest) = manifest.read().unwrap().clone() else {
+            return BTreeMap::new();
+        };
+        let Some(compat) = vault_compat.read().unwrap().clone().filter(|compat| compat.contains_path(file_path)) else {
+            return BTreeMap::new();
+        };
+        let Ok(content) = std::fs::read_to_string(file_path) else {
+            return BTreeMap::new();
+        };
+        manifest.embed_hashes(&compat.transclusions(file_path, &content).sources)
+    }
+    
+    /// Record a processed file in the manifest of the vault being indexed
+    fn record_indexed_file(
+        manifest: &RwLock<Option<Arc<IndexManifest>>>,
+        file_path: &Path,
+        entry: ManifestEntry,
+    ) {
+        let Some(manifest) = manifest.read().unwrap().clone() else {
+            return;
+        };
+        if let Err(e) = manifest.record(file_path, entry) {
+            // The file is simply indexed again next time
+            log::warn!("⚠️ Failed to record {:?} in index manifest: {}", file_path, e);
+        }
+    }
+    
     /// Process a single file by chunking, generating embeddings, and storing them
-    async fn process_file(
+    ///
+    /// Re-indexing a file embeds only the chunks that are new or changed and
+    /// deletes the embeddings of chunks that disappeared. Returns the version
+    /// of the file that was indexed, for the index manifest.
+    pub(crate) async fn process_file(
         worker_id: usize,
         file_path: &PathBuf,
         text_chunker: &ChunkProcessor,
-        embedding_generator: &EmbeddingGenerator,
+        embedding_provider: &dyn EmbeddingProvider,
         vector_db: &VectorDatabase,
         cancellation_token: &CancellationToken,
         embedding_model: &str,
-    ) -> IndexingResult<()> {
+    ) -> IndexingResult<ManifestEntry> {
         // Check cancellation before starting
         if cancellation_token.is_cancelled() {
             return Err(IndexingError::Cancelled);
         }
         
-        // Read file content
+        // Read file content, taking its version first so a concurrent edit looks stale
+        let version = file_version(file_path);
         let content = std::fs::read_to_string(file_path).map_err(|e| {
             IndexingError::FileProcessingError {
                 path: file_path.to_string_lossy().to_string(),
@@ -1001,9 +1285,25 @@ impl IndexingPipeline {
             }
         })?;
         
+        // Entries are keyed by the provider's model ID so a provider switch re-embeds the note
+        let model_id = embedding_provider.model_id(embedding_model);
+        let file_path_str = file_path.to_string_lossy().to_string();
+        let existing: Vec<EmbeddingEntry> = vector_db
+            .find_embeddings_by_file(&file_path_str)
+            .await
+            .map_err(|e| IndexingError::FileProcessingError {
+                path: file_path_str.clone(),
+                reason: format!("Failed to load existing embeddings: {}", e),
+            })?
+            .into_iter()
+            .filter(|entry| entry.metadata.model_name == model_id)
+            .collect();
+        
         if content.is_empty() {
-            log::debug!("📄 File is empty, skipping: {:?}", file_path);
-            return Ok(());
+            log::debug!("📄 File is empty, removing {} existing embeddings: {:?}", existing.len(), file_path);
+            let stale_ids: Vec<String> = existing.iter().map(|e| e.id.clone()).collect();
+            Self::replace_file_embeddings(vector_db, &file_path_str, &stale_ids, Vec::new()).await?;
+            return Ok(ManifestEntry::new(version, &content, &model_id, Vec::new()));
         }
         
         log::debug!("📝 Worker {} read {} characters from {:?}", worker_id, content.len(), file_path);
@@ -1013,10 +1313,10 @@ impl IndexingPipeline {
             return Err(IndexingError::Cancelled);
         }
         
-        // Chunk the text content
-        let chunks = text_chunker.chunk_text(&content).map_err(|e| {
+        // Chunk the text content (sentence windows linked to their parents when the hierarchy is enabled)
+        let chunks = text_chunker.chunk_for_embedding(&content).map_err(|e| {
             IndexingError::FileProcessingError {
-                path: file_path.to_string_lossy().to_string(),
+                path: file_path_str.clone(),
                 reason: format!("Text chunking failed: {}", e),
             }
         })?;
@@ -1024,59 +1324,150 @@ impl IndexingPipeline {
         log::debug!("🧩 Worker {} created {} chunks from {:?}", worker_id, chunks.len(), file_path);
         
         if chunks.is_empty() {
-            log::debug!("📄 No chunks created from file, skipping: {:?}", file_path);
-            return Ok(());
+            log::debug!("📄 No chunks created from file, removing {} existing embeddings: {:?}", existing.len(), file_path);
+            let stale_ids: Vec<String> = existing.iter().map(|e| e.id.clone()).collect();
+            Self::replace_file_embeddings(vector_db, &file_path_str, &stale_ids, Vec::new()).await?;
+            return Ok(ManifestEntry::new(version, &content, &model_id, Vec::new()));
         }
         
-        let file_path_str = file_path.to_string_lossy().to_string();
+        let heading_paths = text_chunker.heading_paths(&content, &chunks);
+        let note_metadata = NoteMetadata::parse(&content).to_custom_metadata();
         
-        // Process each chunk
-        for (chunk_index, chunk) in chunks.iter().enumerate() {
+        // Only chunks without a matching stored embedding are sent to the model
+        let mut diff = ChunkDiff::compute(&existing, &chunks);
+        diff.restamp_changed_note_metadata(&existing, &chunks, &note_metadata);
+        log::debug!("🔍 Worker {} diffed {:?}: {} to embed, {} unchanged ({} moved), {} stale",
+                   worker_id, file_path, diff.to_embed.len(), diff.unchanged, diff.relocated.len(), diff.stale_ids.len());
+        
+        // Embed every new or changed chunk in as few requests as the provider allows
+        if cancellation_token.is_cancelled() {
+            return Err(IndexingError::Cancelled);
+        }
+        let embeddings = if diff.to_embed.is_empty() {
+            Vec::new()
+        } else {
+            let texts: Vec<String> = diff.to_embed.iter().map(|&index| chunks[index].content.clone()).collect();
+            log::info!("🤖 Worker {} requesting {} embeddings for {:?} using model '{}'", 
+                       worker_id, texts.len(), file_path, embedding_model);
+            embedding_provider.embed_batch(&texts, embedding_model).await.map_err(|e| {
+                let reason = match &e {
+                    EmbeddingError::BatchItem { index, source } => format!(
+                        "Embedding generation failed for chunk {}: {}",
+                        chunks[diff.to_embed[*index]].metadata.chunk_id, source
+                    ),
+                    _ => format!("Embedding generation failed: {}", e),
+                };
+                IndexingError::FileProcessingError { path: file_path_str.clone(), reason }
+            })?
+        };
+        
+        let mut entries: Vec<(EmbeddingEntry, &str)> = Vec::with_capacity(diff.to_embed.len() + diff.relocated.len());
+        for (&chunk_index, embedding) in diff.to_embed.iter().zip(embeddings) {
             // Check cancellation for each chunk
             if cancellation_token.is_cancelled() {
                 return Err(IndexingError::Cancelled);
             }
             
-            let chunk_id = format!("chunk_{}", chunk_index);
-            
-            log::debug!("🔄 Worker {} processing chunk {} ({} chars) from {:?}", 
-                       worker_id, chunk_index, chunk.content.len(), file_path);
-            
-            // Generate embedding for chunk
-            log::info!("🤖 Worker {} requesting embedding for chunk {} using model '{}'", 
-                       worker_id, chunk_index, embedding_model);
-            let embedding = embedding_generator.generate_embedding(chunk.content.clone(), embedding_model.to_string()).await.map_err(|e| {
-                IndexingError::FileProcessingError {
+            let chunk = &chunks[chunk_index];
+            let chunk_id = chunk.metadata.chunk_id.clone();
+            if embedding.is_empty() {
+                return Err(IndexingError::FileProcessingError {
                     path: file_path_str.clone(),
-                    reason: format!("Embedding generation failed for chunk {}: {}", chunk_index, e),
-                }
-            })?;
+                    reason: format!("Embedding generation failed for chunk {}: {}", chunk_id, EmbeddingError::EmptyText),
+                });
+            }
             
             log::debug!("🔢 Worker {} generated embedding (dim: {}) for chunk {} from {:?}", 
-                       worker_id, embedding.len(), chunk_index, file_path);
+                       worker_id, embedding.len(), chunk_id, file_path);
             
-            // Store embedding directly in vector database
-            let entry_id = vector_db.store_embedding(
-                embedding,
-                file_path_str.clone(),
-                chunk_id.clone(),
+            // Keep the heading path and span for search results
+            entries.push((
+                Self::chunk_entry(embedding, &file_path_str, chunk, &model_id, &heading_paths[chunk_index], &note_metadata),
                 &chunk.content,
-                embedding_model.to_string(),
-            ).await.map_err(|e| {
-                IndexingError::FileProcessingError {
-                    path: file_path_str.clone(),
-                    reason: format!("Failed to store embedding for chunk {}: {}", chunk_index, e),
-                }
-            })?;
-            
-            log::debug!("💾 Worker {} stored embedding {} for chunk {} from {:?}", 
-                       worker_id, entry_id, chunk_index, file_path);
+            ));
+        }
+        
+        // Unchanged chunks that moved or whose note metadata changed keep their vector
+        for &(chunk_index, entry_index) in &diff.relocated {
+            let chunk = &chunks[chunk_index];
+            entries.push((
+                Self::chunk_entry(
+                    existing[entry_index].vector.clone(),
+                    &file_path_str,
+                    chunk,
+                    &model_id,
+                    &heading_paths[chunk_index],
+                    &note_metadata,
+                ),
+                &chunk.content,
+            ));
         }
         
-        log::info!("✅ Worker {} successfully processed file {:?} ({} chunks)", 
-                  worker_id, file_path, chunks.len());
+        // New chunks and the removal of those that disappeared land together
+        Self::replace_file_embeddings(vector_db, &file_path_str, &diff.stale_ids, entries).await?;
+        log::debug!("💾 Worker {} stored {} embeddings from {:?}", 
+                   worker_id, diff.to_embed.len() + diff.relocated.len(), file_path);
         
-        Ok(())
+        log::info!("✅ Worker {} successfully processed file {:?} ({} chunks, {} embedded, {} unchanged, {} removed)", 
+                  worker_id, file_path, chunks.len(), diff.to_embed.len(), diff.unchanged, diff.stale_ids.len());
+        
+        let chunk_ids = chunks.into_iter().map(|chunk| chunk.metadata.chunk_id).collect();
+        Ok(ManifestEntry::new(version, &content, &model_id, chunk_ids))
+    }
+    
+    /// Custom metadata stored with a chunk's embedding (note metadata, heading path, byte span and parents)
+    fn chunk_custom_metadata(
+        chunk: &TextChunk,
+        heading_path: &[String],
+        note_metadata: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let mut custom_metadata = note_metadata.clone();
+        if let Some(heading_path) = EmbeddingMetadata::encode_heading_path(heading_path) {
+            custom_metadata.insert(HEADING_PATH_METADATA_KEY.to_string(), heading_path);
+        }
+        custom_metadata.insert(
+            CHUNK_SPAN_METADATA_KEY.to_string(),
+            EmbeddingMetadata::encode_chunk_span(chunk.metadata.start_position, chunk.metadata.end_position),
+        );
+        if let Some(parents) = EmbeddingMetadata::encode_parent_chunks(&chunk.metadata.parents) {
+            custom_metadata.insert(PARENT_CHUNKS_METADATA_KEY.to_string(), parents);
+        }
+        custom_metadata
+    }
+    
+    /// Embedding entry for a chunk of a file
+    fn chunk_entry(
+        vector: Vec<f32>,
+        file_path: &str,
+        chunk: &TextChunk,
+        model_id: &str,
+        heading_path: &[String],
+        note_metadata: &HashMap<String, String>,
+    ) -> EmbeddingEntry {
+        let mut entry = EmbeddingEntry::new(
+            vector,
+            file_path.to_string(),
+            chunk.metadata.chunk_id.clone(),
+            &chunk.content,
+            model_id.to_string(),
+        );
+        entry.metadata.custom_metadata.extend(Self::chunk_custom_metadata(chunk, heading_path, note_metadata));
+        entry
+    }
+    
+    /// Store a file's new chunk embeddings and delete its stale ones in one storage write
+    async fn replace_file_embeddings(
+        vector_db: &VectorDatabase,
+        file_path: &str,
+        stale_ids: &[String],
+        entries: Vec<(EmbeddingEntry, &str)>,
+    ) -> IndexingResult<()> {
+        vector_db.replace_embeddings(stale_ids, entries).await.map_err(|e| {
+            IndexingError::FileProcessingError {
+                path: file_path.to_string(),
+                reason: format!("Failed to update embeddings: {}", e),
+            }
+        })
     }
     
     fn start_progress_reporter(&self) {
@@ -1275,4 +1666,353 @@ mod tests {
         assert!(config.enable_resume);
         assert!(config.state_file_path.is_some());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_chunk_diff_embeds_only_changed_chunks() {
+        use crate::text_chunker::ChunkMetadata;
+
+        let chunk = |heading: &str, content: &str| {
+            let metadata = ChunkMetadata {
+                chunk_id: ChunkMetadata::content_id(&[heading.to_string()], content),
+                ..ChunkMetadata::default()
+            };
+            TextChunk::new(content.to_string(), metadata)
+        };
+        let stored = |chunk: &TextChunk| {
+            EmbeddingEntry::new(
+                vec![0.1, 0.2, 0.3],
+                "/vault/note.md".to_string(),
+                chunk.metadata.chunk_id.clone(),
+                &chunk.content,
+                "test-model".to_string(),
+            )
+        };
+
+        let kept = chunk("Intro", "Unchanged paragraph");
+        let removed = chunk("Intro", "Paragraph that was deleted");
+        let existing = vec![stored(&kept), stored(&removed)];
+
+        let chunks = vec![chunk("Intro", "Paragraph inserted above"), kept.clone()];
+        let diff = ChunkDiff::compute(&existing, &chunks);
+        assert_eq!(diff.to_embed, vec![0]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.stale_ids, vec![existing[1].id.clone()]);
+
+        // Nothing to do when the content is identical
+        let diff = ChunkDiff::compute(&existing, &[kept.clone(), removed.clone()]);
+        assert!(diff.to_embed.is_empty());
+        assert!(diff.stale_ids.is_empty());
+        assert_eq!(diff.unchanged, 2);
+
+        // Legacy positional IDs are re-embedded once and then removed
+        let legacy = EmbeddingEntry::new(
+            vec![0.1, 0.2, 0.3],
+            "/vault/note.md".to_string(),
+            "chunk_0".to_string(),
+            &kept.content,
+            "test-model".to_string(),
+        );
+        let diff = ChunkDiff::compute(std::slice::from_ref(&legacy), std::slice::from_ref(&kept));
+        assert_eq!(diff.to_embed, vec![0]);
+        assert_eq!(diff.stale_ids, vec![legacy.id]);
+
+        // Unchanged chunks that moved are reported so their span can be updated
+        let mut with_span = stored(&kept);
+        with_span.metadata.custom_metadata.insert(
+            CHUNK_SPAN_METADATA_KEY.to_string(),
+            EmbeddingMetadata::encode_chunk_span(0, kept.content.len()),
+        );
+        let mut moved = kept.clone();
+        moved.metadata.start_position = 30;
+        moved.metadata.end_position = 30 + kept.content.len();
+        let diff = ChunkDiff::compute(std::slice::from_ref(&with_span), &[moved]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.relocated, vec![(0, 0)]);
+
+        let mut in_place = kept.clone();
+        in_place.metadata.start_position = 0;
+        in_place.metadata.end_position = kept.content.len();
+        let mut diff = ChunkDiff::compute(std::slice::from_ref(&with_span), std::slice::from_ref(&in_place));
+        assert!(diff.relocated.is_empty());
+
+        // Changed tags restamp the chunk without re-embedding it
+        let note_metadata = NoteMetadata::parse("#project").to_custom_metadata();
+        diff.restamp_changed_note_metadata(std::slice::from_ref(&with_span), std::slice::from_ref(&in_place), &note_metadata);
+        assert!(diff.to_embed.is_empty());
+        assert_eq!(diff.relocated, vec![(0, 0)]);
+
+        with_span.metadata.custom_metadata.extend(note_metadata.clone());
+        let mut diff = ChunkDiff::compute(std::slice::from_ref(&with_span), std::slice::from_ref(&in_place));
+        diff.restamp_changed_note_metadata(std::slice::from_ref(&with_span), &[in_place], &note_metadata);
+        assert!(diff.relocated.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_process_file_embeds_chunks_in_one_request() {
+        use crate::embedding_generator::{EmbeddingConfig, EmbeddingGenerator};
+        use crate::ollama_client::OllamaConfig;
+        use crate::text_chunker::ChunkConfig;
+        use crate::vector_db::types::VectorStorageConfig;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .respond_with(|request: &Request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let inputs = body["input"].as_array().unwrap().len();
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"embeddings": vec![vec![0.6f32, 0.8]; inputs]}))
+            })
+            .mount(&server)
+            .await;
+        let generator = EmbeddingGenerator::with_config(
+            OllamaConfig { base_url: server.uri(), ..Default::default() },
+            EmbeddingConfig { warmup_connections: false, health_check_interval_seconds: 0, ..Default::default() },
+        );
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let note_path = temp_dir.path().join("note.md");
+        let section = |title: &str| format!(
+            "## {}\n\n{} is covered in this section with enough prose to stand as a chunk of its own. \
+             It keeps going for a while so the chunker has no reason to merge it with a neighbour.\n\n",
+            title, title
+        );
+        std::fs::write(&note_path, format!("{}{}{}", section("Alpha"), section("Beta"), section("Gamma"))).unwrap();
+
+        let chunker = ChunkProcessor::new(ChunkConfig { max_chunk_size: 200, min_chunk_size: 20, ..ChunkConfig::default() }).unwrap();
+        let vector_db = VectorDatabase::new(VectorStorageConfig {
+            storage_dir: temp_dir.path().join("vectors").to_string_lossy().to_string(),
+            ..VectorStorageConfig::default()
+        })
+        .await
+        .unwrap();
+
+        IndexingPipeline::process_file(0, &note_path, &chunker, &generator, &vector_db, &CancellationToken::new(), "nomic-embed-text")
+            .await
+            .unwrap();
+
+        let stored = vector_db.find_embeddings_by_file(&note_path.to_string_lossy()).await.unwrap();
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(stored.len() > 1);
+        assert_eq!(body["input"].as_array().unwrap().len(), stored.len());
+
+        // Dropping a section deletes its chunks and re-embeds only the one that overlapped it
+        std::fs::write(&note_path, format!("{}{}", section("Alpha"), section("Beta"))).unwrap();
+        IndexingPipeline::process_file(0, &note_path, &chunker, &generator, &vector_db, &CancellationToken::new(), "nomic-embed-text")
+            .await
+            .unwrap();
+        let remaining = vector_db.find_embeddings_by_file(&note_path.to_string_lossy()).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        assert!(!remaining.is_empty() && remaining.len() < stored.len());
+        assert!(stored.iter().any(|entry| entry.metadata.content_preview.contains("## Gamm")));
+        assert!(remaining.iter().all(|entry| !entry.metadata.content_preview.contains("## Gamm")));
+
+        // The swap is durable as a single storage mutation
+        drop(vector_db);
+        let reopened = VectorDatabase::new(VectorStorageConfig {
+            storage_dir: temp_dir.path().join("vectors").to_string_lossy().to_string(),
+            ..VectorStorageConfig::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(reopened.find_embeddings_by_file(&note_path.to_string_lossy()).await.unwrap().len(), remaining.len());
+    }
+
+    #[tokio::test]
+    async fn test_process_file_reembeds_after_provider_switch() {
+        use crate::embedding_provider::HashingEmbeddingProvider;
+        use crate::text_chunker::ChunkConfig;
+        use crate::vector_db::types::VectorStorageConfig;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let note_path = temp_dir.path().join("note.md");
+        std::fs::write(&note_path, "# Note\n\nThe same model name is served by two different providers here.").unwrap();
+
+        let chunker = ChunkProcessor::new(ChunkConfig::default()).unwrap();
+        let vector_db = VectorDatabase::new(VectorStorageConfig {
+            storage_dir: temp_dir.path().join("vectors").to_string_lossy().to_string(),
+            ..VectorStorageConfig::default()
+        })
+        .await
+        .unwrap();
+        let first = HashingEmbeddingProvider::new(16);
+        let second = HashingEmbeddingProvider::new(8);
+
+        let indexed = IndexingPipeline::process_file(0, &note_path, &chunker, &first, &vector_db, &CancellationToken::new(), "shared-model")
+            .await
+            .unwrap();
+        assert_eq!(indexed.model, first.model_id("shared-model"));
+
+        // Same model name, different provider: the note is embedded again under the new ID
+        let reindexed = IndexingPipeline::process_file(0, &note_path, &chunker, &second, &vector_db, &CancellationToken::new(), "shared-model")
+            .await
+            .unwrap();
+        assert_eq!(reindexed.model, second.model_id("shared-model"));
+
+        let stored = vector_db.find_embeddings_by_file(&note_path.to_string_lossy()).await.unwrap();
+        let (first_entries, second_entries): (Vec<_>, Vec<_>) = stored
+            .iter()
+            .partition(|entry| entry.metadata.model_name == first.model_id("shared-model"));
+        assert!(!first_entries.is_empty());
+        assert_eq!(first_entries.len(), second_entries.len());
+        assert!(first_entries.iter().all(|entry| entry.vector.len() == 16));
+        assert!(second_entries.iter().all(|entry| entry.vector.len() == 8 && entry.metadata.model_name == second.model_id("shared-model")));
+    }
+
+    #[test]
+    fn test_chunker_for_resolves_vault_embeds() {
+        use crate::text_chunker::ChunkConfig;
+
+        let vault = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(vault.path().join(".obsidian")).unwrap();
+        std::fs::write(vault.path().join("Glossary.md"), "Embeddings map text to vectors.").unwrap();
+        let note_path = vault.path().join("note.md");
+        let outside_path = tempfile::TempDir::new().unwrap().path().join("note.md");
+
+        let text_chunker = Arc::new(ChunkProcessor::new(ChunkConfig::default()).unwrap());
+        let vault_compat = RwLock::new(Some(Arc::new(VaultCompat::open(vault.path()).unwrap())));
+        let text = "# Notes\n\nSee ![[Glossary]] for the basics.";
+
+        let chunker = IndexingPipeline::chunker_for(&text_chunker, &vault_compat, &note_path);
+        let chunks = chunker.chunk_for_embedding(text).unwrap();
+        assert!(chunks.iter().any(|chunk| chunk.content.contains("See Embeddings map text to vectors. for")));
+
+        let chunker = IndexingPipeline::chunker_for(&text_chunker, &vault_compat, &outside_path);
+        assert!(Arc::ptr_eq(&chunker, &text_chunker));
+    }
+
+    #[test]
+    fn test_bulk_index_resumes_from_manifest() {
+        use crate::embedding_provider::HashingEmbeddingProvider;
+        use crate::text_chunker::ChunkConfig;
+        use crate::vector_db::types::VectorStorageConfig;
+
+        // The pipeline blocks on its own runtime when dropped, so it must outlive this one
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let vault = tempfile::TempDir::new().unwrap();
+        let first = vault.path().join("first.md");
+        let second = vault.path().join("second.md");
+        std::fs::write(&first, "# First\n\nThe first note has a paragraph worth embedding.").unwrap();
+        std::fs::write(&second, "# Second\n\nThe second note has a paragraph worth embedding.").unwrap();
+
+        let pipeline = runtime.block_on(async {
+            let vector_db = VectorDatabase::new(VectorStorageConfig {
+                storage_dir: vault.path().join(".ainote").join("vectors").to_string_lossy().to_string(),
+                ..VectorStorageConfig::default()
+            })
+            .await
+            .unwrap();
+            IndexingPipeline::new(
+                PipelineConfig { worker_count: 1, enable_resume: false, state_file_path: None, ..PipelineConfig::default() },
+                Arc::new(ChunkProcessor::new(ChunkConfig::default()).unwrap()),
+                Arc::new(HashingEmbeddingProvider::new(16)),
+                Arc::new(vector_db),
+            )
+        });
+
+        runtime.block_on(async {
+            let wait_for_completed = |count: u64| {
+                let pipeline = &pipeline;
+                async move {
+                    for _ in 0..500 {
+                        if pipeline.completed_counter.load(Ordering::SeqCst) >= count && pipeline.queue.is_empty() {
+                            return;
+                        }
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                    panic!("indexing did not complete");
+                }
+            };
+
+            pipeline.start().await.unwrap();
+            let queued = pipeline.bulk_index_vault(vault.path().to_path_buf(), IndexingPriority::UserTriggered, None).await.unwrap();
+            assert_eq!(queued.len(), 2);
+            wait_for_completed(2).await;
+            assert_eq!(pipeline.index_manifest().unwrap().len(), 2);
+
+            // Nothing changed: nothing is queued again
+            let queued = pipeline.bulk_index_vault(vault.path().to_path_buf(), IndexingPriority::UserTriggered, None).await.unwrap();
+            assert!(queued.is_empty());
+            assert_eq!(pipeline.get_progress().skipped_files, 2);
+
+            // Only the edited note is queued, and the deleted note's embeddings are removed
+            std::fs::write(&first, "# First\n\nThe first note was edited since it was indexed.").unwrap();
+            std::fs::remove_file(&second).unwrap();
+            let queued = pipeline.bulk_index_vault(vault.path().to_path_buf(), IndexingPriority::UserTriggered, None).await.unwrap();
+            assert_eq!(queued.len(), 1);
+            wait_for_completed(3).await;
+            assert!(!pipeline.vector_db.has_embeddings_for_file(&second.to_string_lossy()).await);
+            assert_eq!(pipeline.index_manifest().unwrap().indexed_paths(), vec![first.clone()]);
+
+            pipeline.stop().await;
+        });
+        drop(runtime);
+    }
+
+    #[test]
+    fn test_notes_embedding_a_changed_note_are_reindexed() {
+        use crate::embedding_provider::HashingEmbeddingProvider;
+        use crate::text_chunker::ChunkConfig;
+        use crate::vector_db::types::VectorStorageConfig;
+
+        // The pipeline blocks on its own runtime when dropped, so it must outlive this one
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let vault = tempfile::TempDir::new().unwrap();
+        let overview = vault.path().join("Overview.md");
+        let glossary = vault.path().join("Glossary.md");
+        std::fs::write(&overview, "# Overview\n\nThe terms we use: ![[Glossary]]").unwrap();
+        std::fs::write(&glossary, "Embeddings map text to vectors.").unwrap();
+
+        let pipeline = runtime.block_on(async {
+            let vector_db = VectorDatabase::new(VectorStorageConfig {
+                storage_dir: vault.path().join(".ainote").join("vectors").to_string_lossy().to_string(),
+                ..VectorStorageConfig::default()
+            })
+            .await
+            .unwrap();
+            IndexingPipeline::new(
+                PipelineConfig { worker_count: 1, enable_resume: false, state_file_path: None, ..PipelineConfig::default() },
+                Arc::new(ChunkProcessor::new(ChunkConfig::default()).unwrap()),
+                Arc::new(HashingEmbeddingProvider::new(16)),
+                Arc::new(vector_db),
+            )
+        });
+
+        runtime.block_on(async {
+            let wait_for_completed = |count: u64| {
+                let pipeline = &pipeline;
+                async move {
+                    for _ in 0..500 {
+                        if pipeline.completed_counter.load(Ordering::SeqCst) >= count && pipeline.queue.is_empty() {
+                            return;
+                        }
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                    panic!("indexing did not complete");
+                }
+            };
+
+            pipeline.start().await.unwrap();
+            pipeline.bulk_index_vault(vault.path().to_path_buf(), IndexingPriority::UserTriggered, None).await.unwrap();
+            wait_for_completed(2).await;
+            let manifest = pipeline.index_manifest().unwrap();
+            let entry = manifest.entry(&overview).unwrap().unwrap();
+            assert_eq!(entry.embeds.keys().collect::<Vec<_>>(), vec!["Glossary.md"]);
+            assert!(manifest.entry(&glossary).unwrap().unwrap().embeds.is_empty());
+
+            // Editing the embedded note makes the embedding note stale and queues it with the change
+            std::fs::write(&glossary, "Embeddings map text to dense vectors.").unwrap();
+            assert!(!manifest.is_current(&overview, &entry.model).unwrap());
+            let queued = pipeline.index_files_debounced(vec![glossary.clone()], Some(0)).await.unwrap();
+            assert_eq!(queued.len(), 2);
+            wait_for_completed(4).await;
+            assert!(manifest.is_current(&overview, &entry.model).unwrap());
+
+            pipeline.stop().await;
+        });
+        drop(runtime);
+    }
+}
@@ -40,13 +40,25 @@ pub mod validation;
 // Core infrastructure modules  
 pub mod ollama_client;          // Ollama HTTP client and connection management
 pub mod embedding_generator;    // Embedding generation engine
+pub mod embedding_provider;     // Pluggable embedding backends (Ollama, OpenAI-compatible, local hashing)
 pub mod embedding_cache;        // Embedding cache management
+pub mod embedding_store;        // Persistent content-addressed embedding store behind the cache
 pub mod embedding_queue;        // Advanced embedding request queue with cancellation and performance optimization
 pub mod suggestion_cache;       // AI suggestion caching system with context awareness
 pub mod vector_db;             // Vector database storage and operations
 pub mod similarity_search;     // Similarity search algorithms
 pub mod text_chunker;          // Text chunking algorithms and infrastructure
+pub mod tokenizer;             // WordPiece and heuristic token counting for token-aware chunk sizing
 pub mod indexing_pipeline;     // Automated vault indexing pipeline with worker threads
+pub mod index_manifest;        // Per-vault manifest of indexed note versions for resumable indexing
+pub mod model_migration;       // Background re-embedding of a vault with another embedding model
+pub mod link_graph;            // Wikilink and markdown link graph with backlinks
+pub mod note_metadata;         // YAML frontmatter and tag extraction for notes
+pub mod vault_compat;          // Obsidian and Logseq vault layouts, embeds and block references
+pub mod vault_config;          // Versioned per-vault settings file (.ainote/config.toml)
+pub mod note_rename;           // Link-aware note and folder rename with embedding migration
+pub mod vault_search;          // Grep-style keyword/regex search and find-and-replace across notes
+pub mod rag;                   // Retrieval-augmented question answering over the vault
 pub mod file_monitor;          // File system monitoring for real-time indexing integration
 
 // Performance and benchmarking modules
@@ -54,6 +66,7 @@ pub mod benchmarks;
 pub mod performance_baseline;
 pub mod regression_detection;
 pub mod memory_manager;        // Advanced memory management system
+pub mod process_sampler;       // Process RSS, CPU and system load sampled from /proc
 pub mod resource_allocator;    // CPU and I/O resource allocation system
 pub mod background_processor;  // Background processing system for non-critical AI operations
 pub mod ai_operation_manager;  // Intelligent AI operation prioritization and management system
@@ -79,7 +92,10 @@ pub use ollama_client::{
     ModelInfo, ModelCompatibility, ModelVerificationResult, DownloadStatus, DownloadProgress, DownloadConfig
 };
 pub use embedding_generator::{EmbeddingGenerator, EmbeddingError, EmbeddingResult, EmbeddingConfig};
-pub use embedding_cache::{EmbeddingCache, CacheError, CacheResult, CacheConfig, CacheMetrics};
+pub use embedding_provider::{
+    EmbeddingProvider, EmbeddingProviderConfig, HashingEmbeddingProvider, OpenAiCompatibleConfig, OpenAiCompatibleProvider
+};
+pub use embedding_cache::{EmbeddingCache, CachedEmbeddingProvider, CacheError, CacheResult, CacheConfig, CacheMetrics};
 pub use embedding_queue::{
     EmbeddingQueue, QueueConfig, QueueMetrics, QueueError, QueueResult,
     RequestPriority, RequestStatus, RequestId, CancellationToken as EmbeddingCancellationToken, EmbeddingRequest, EmbeddingRequestResult
@@ -99,6 +115,9 @@ pub use memory_manager::{
     MemoryManager, MemoryManagerConfig, MemoryError, MemoryResult, MemoryMetrics,
     AllocationLimiter, AllocationType, MemoryAllocation
 };
+pub use process_sampler::{
+    ProcessSampler, ProcSources, ProcSnapshot, ResourceSample, SamplerError, SamplerResult
+};
 pub use resource_allocator::{
     ResourceAllocator, ResourceAllocatorConfig, ResourceError, ResourceResult, ResourceMetrics,
     OperationPriority, OperationType
@@ -120,6 +139,11 @@ pub fn run() {
             app_setup::setup_window_state(&window);
             app_setup::setup_window_events(&window);
             
+            // Tokenizer vocabularies ship as resources for models without an installed one
+            if let Ok(resource_dir) = app.path().resource_dir() {
+                tokenizer::set_bundled_tokenizer_dir(resource_dir.join("tokenizers"));
+            }
+            
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -144,6 +168,8 @@ pub fn run() {
             commands::vault_operations::scan_vault_files,
             commands::vault_operations::scan_vault_files_chunked,
             commands::vault_operations::watch_vault,
+            commands::vault_operations::get_vault_compatibility,
+            commands::vault_operations::get_vault_config,
             
             // State Management
             commands::state_management::load_app_state,
@@ -178,11 +204,18 @@ pub fn run() {
             commands::ollama_commands::cancel_download,
             commands::ollama_commands::clear_completed_downloads,
             
+            // Ollama Text Generation (streaming)
+            commands::generation_commands::generate_text,
+            commands::generation_commands::chat_completion,
+            commands::generation_commands::cancel_generation,
+            
             // Embedding Processing
             commands::embedding_commands::generate_embedding,
             commands::embedding_commands::generate_batch_embeddings,
             commands::embedding_commands::update_embedding_generator_config,
             commands::embedding_commands::get_embedding_generator_config,
+            commands::embedding_commands::set_embedding_provider,
+            commands::embedding_commands::get_embedding_provider_config,
             commands::embedding_commands::get_embedding_cache_metrics,
             commands::embedding_commands::clear_embedding_cache,
             commands::embedding_commands::get_embedding_cache_size,
@@ -233,6 +266,37 @@ pub fn run() {
             similarity_search_commands::configure_search_performance,
             similarity_search_commands::test_search_functionality,
             
+            // Search & Similarity - Text query (backend embedding)
+            commands::semantic_search_commands::semantic_search,
+            
+            // Search & Similarity - Related notes (per-note aggregation)
+            commands::related_notes_commands::related_notes,
+            
+            // Search - Keyword/regex text search and vault-wide replace
+            commands::vault_search_commands::search_vault_text,
+            commands::vault_search_commands::replace_in_vault,
+            commands::vault_search_commands::cancel_vault_search,
+            
+            // Question Answering over the Vault (RAG, streaming)
+            commands::rag_commands::ask_notes,
+            
+            // Link Graph (wikilinks & backlinks)
+            commands::link_graph_commands::get_outgoing_links,
+            commands::link_graph_commands::get_backlinks,
+            commands::link_graph_commands::get_unresolved_links,
+            commands::link_graph_commands::get_orphan_notes,
+            commands::link_graph_commands::get_link_graph_stats,
+            commands::link_graph_commands::rebuild_link_graph,
+            commands::link_graph_commands::rename_note,
+            
+            // Embedding Model Namespaces & Migration
+            commands::model_namespace_commands::get_model_namespaces,
+            commands::model_namespace_commands::switch_embedding_model,
+            commands::model_namespace_commands::delete_model_namespace,
+            commands::model_namespace_commands::start_model_migration,
+            commands::model_namespace_commands::get_model_migration_status,
+            commands::model_namespace_commands::cancel_model_migration,
+            
             // Maintenance Operations
             commands::maintenance_commands::enable_database_maintenance,
             commands::maintenance_commands::start_automatic_maintenance,
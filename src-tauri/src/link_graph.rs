@@ -0,0 +1,1166 @@
+//! # Link Graph
+//!
+//! Tracks how the notes of a vault link to each other. Every note's
+//! `[[wikilinks]]` and relative markdown links are extracted with the
+//! chunker's markdown parser and resolved against the notes in the vault,
+//! which gives outgoing links, backlinks, unresolved links and orphan notes.
+//!
+//! ## Link Resolution
+//!
+//! - **Wikilinks** (`[[Note]]`, `[[Folder/Note#Heading|alias]]`) resolve like
+//!   Obsidian: to the note whose vault path ends with the target, preferring
+//!   the shortest path. `[[#Heading]]` links to the note itself.
+//! - **Markdown links** (`[text](../Other%20Note.md)`) resolve relative to the
+//!   linking note, or to the vault root when they start with `/`.
+//! - Matching is case-insensitive and `.md` is implied when the target has
+//!   no extension. Links to attachments, URLs and in-note anchors are ignored.
+//!
+//! ## Persistence
+//!
+//! The raw links of each note are stored in `{vault}/.ainote/link_graph.json`
+//! together with the note's modification time and size. Opening a vault re-parses
+//! only notes that changed since the graph was saved, and `FileMonitor`
+//! applies file changes while the vault is watched.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use walkdir::WalkDir;
+
+use crate::text_chunker::{extract_markdown_links, MarkdownElement};
+
+/// File name of the persisted graph inside `{vault}/.ainote`
+pub const LINK_GRAPH_FILE: &str = "link_graph.json";
+
+/// Version of the persisted format; other versions are rebuilt from scratch
+const LINK_GRAPH_VERSION: u32 = 1;
+
+/// Errors that can occur while building or querying the link graph
+#[derive(Error, Debug)]
+pub enum LinkGraphError {
+    #[error("Vault path does not exist or is not a directory: {path}")]
+    VaultNotFound { path: String },
+
+    #[error("Path is not inside the vault: {path}")]
+    OutsideVault { path: String },
+
+    #[error("I/O error: {message}")]
+    IOError { message: String },
+
+    #[error("Serialization error: {message}")]
+    SerializationError { message: String },
+}
+
+pub type LinkGraphResult<T> = Result<T, LinkGraphError>;
+
+/// Syntax a link was written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// `[[target]]` or `![[target]]`
+    WikiLink,
+    /// `[text](target.md)`
+    Markdown,
+}
+
+/// A link to another note, as written in the linking note
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteLink {
+    /// Link syntax
+    pub kind: LinkKind,
+    /// Link target without heading or block reference (empty for `[[#Heading]]`)
+    pub target: String,
+    /// Heading or block reference after `#`, if any
+    pub subpath: Option<String>,
+    /// Wikilink alias or markdown link text
+    pub display_text: Option<String>,
+    /// Byte offset of the link in the linking note
+    pub position: usize,
+}
+
+/// An outgoing link with its resolved target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutgoingLink {
+    #[serde(flatten)]
+    pub link: NoteLink,
+    /// Path of the linked note, `None` if no note matches
+    pub target_path: Option<String>,
+}
+
+/// A link pointing at a note from another note (or from itself)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backlink {
+    /// Path of the linking note
+    pub source_path: String,
+    #[serde(flatten)]
+    pub link: NoteLink,
+}
+
+/// A link whose target text has to change so it keeps pointing at its note
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkRewrite {
+    /// Path of the linking note before the move
+    pub source_path: String,
+    #[serde(flatten)]
+    pub link: NoteLink,
+    /// Target to write instead of `link.target`, without heading or encoding
+    pub new_target: String,
+}
+
+/// Summary of the graph
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkGraphStats {
+    /// Number of notes in the graph
+    pub note_count: usize,
+    /// Number of links to notes, resolved or not
+    pub link_count: usize,
+    /// Links whose target does not exist
+    pub unresolved_link_count: usize,
+    /// Notes without links to or from other notes
+    pub orphan_count: usize,
+}
+
+/// Links of a single note
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct NoteEntry {
+    /// Modification time (milliseconds since the epoch) and size when the note was parsed
+    modified_ms: u64,
+    #[serde(default)]
+    size: u64,
+    links: Vec<NoteLink>,
+}
+
+/// A note re-read from disk, ready to be applied to the graph
+///
+/// Produced by [`LinkGraph::read_changes`] without access to the graph, so
+/// notes can be read before taking the lock that guards it.
+#[derive(Debug, Clone)]
+pub struct NoteChange {
+    path: PathBuf,
+    /// Parsed note, `None` when the file no longer exists
+    entry: Option<NoteEntry>,
+}
+
+/// On-disk representation of the graph
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedLinkGraph {
+    version: u32,
+    notes: BTreeMap<String, NoteEntry>,
+}
+
+/// Link graph of a vault
+///
+/// Notes are keyed by their vault-relative path with `/` separators; paths
+/// returned by queries are absolute.
+#[derive(Debug, Clone)]
+pub struct LinkGraph {
+    vault_path: PathBuf,
+    /// Canonical vault path, for matching paths reported by the file watcher
+    canonical_vault_path: Option<PathBuf>,
+    notes: BTreeMap<String, NoteEntry>,
+    /// Resolved target of every link, parallel to `NoteEntry::links`
+    resolved: HashMap<String, Vec<Option<String>>>,
+    /// Incoming links by target note: (source note, index into its links)
+    incoming: HashMap<String, Vec<(String, usize)>>,
+    /// Notes with a link naming a file, by lowercase file name; these are the
+    /// notes whose links may resolve differently when such a file is added or removed
+    linking: HashMap<String, HashSet<String>>,
+}
+
+impl LinkGraph {
+    /// Create an empty graph for a vault
+    pub fn new(vault_path: impl Into<PathBuf>) -> Self {
+        let vault_path = vault_path.into();
+        Self {
+            canonical_vault_path: vault_path.canonicalize().ok(),
+            vault_path,
+            notes: BTreeMap::new(),
+            resolved: HashMap::new(),
+            incoming: HashMap::new(),
+            linking: HashMap::new(),
+        }
+    }
+
+    /// Load a vault's graph and bring it up to date
+    ///
+    /// Starts from the persisted graph when there is one, re-parses notes
+    /// that changed since it was saved and saves the result.
+    pub fn open(vault_path: impl Into<PathBuf>) -> LinkGraphResult<Self> {
+        let mut graph = Self::new(vault_path);
+        if !graph.vault_path.is_dir() {
+            return Err(LinkGraphError::VaultNotFound { path: graph.vault_path.display().to_string() });
+        }
+
+        let loaded = match graph.load() {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log::warn!("⚠️ Discarding unreadable link graph: {}", e);
+                false
+            }
+        };
+        let changed = graph.refresh()?;
+        if changed || !loaded {
+            graph.save()?;
+        }
+
+        log::info!("🔗 Link graph ready for {:?}: {} notes", graph.vault_path, graph.notes.len());
+        Ok(graph)
+    }
+
+    /// Root directory of the vault
+    pub fn vault_path(&self) -> &Path {
+        &self.vault_path
+    }
+
+    /// Location of the persisted graph
+    pub fn storage_path(&self) -> PathBuf {
+        self.vault_path.join(".ainote").join(LINK_GRAPH_FILE)
+    }
+
+    /// Whether a path lies inside the vault
+    pub fn contains_path(&self, path: &Path) -> bool {
+        self.relative_path(path).is_ok()
+    }
+
+    /// Re-scan the vault, re-parsing notes whose modification time changed
+    ///
+    /// Returns whether any note was added, changed or removed.
+    pub fn refresh(&mut self) -> LinkGraphResult<bool> {
+        let mut seen = HashSet::new();
+        let mut changed = false;
+
+        for path in markdown_files(&self.vault_path) {
+            let key = self.relative_path(&path)?;
+            let (modified_ms, size) = file_version(&path);
+            seen.insert(key.clone());
+
+            if self.notes.get(&key).is_some_and(|entry| entry.modified_ms == modified_ms && entry.size == size) {
+                continue;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    self.notes.insert(key, NoteEntry { modified_ms, size, links: parse_links(&content) });
+                    changed = true;
+                }
+                Err(e) => log::warn!("⚠️ Skipping unreadable note {:?}: {}", path, e),
+            }
+        }
+
+        let before = self.notes.len();
+        self.notes.retain(|key, _| seen.contains(key));
+        changed |= self.notes.len() != before;
+
+        self.rebuild_index();
+        Ok(changed)
+    }
+
+    /// Drop everything and parse the whole vault again
+    pub fn rebuild(&mut self) -> LinkGraphResult<()> {
+        self.notes.clear();
+        self.refresh()?;
+        Ok(())
+    }
+
+    /// Apply changes to notes: re-parse existing files and drop deleted ones
+    ///
+    /// Returns the number of notes that were updated or removed.
+    pub fn update_notes(&mut self, paths: &[PathBuf]) -> LinkGraphResult<usize> {
+        self.apply_changes(Self::read_changes(paths))
+    }
+
+    /// Read and parse changed notes
+    ///
+    /// Unreadable notes are logged and skipped so one bad file does not hold
+    /// back the rest of the batch.
+    pub fn read_changes(paths: &[PathBuf]) -> Vec<NoteChange> {
+        paths
+            .iter()
+            .filter(|path| is_markdown(path))
+            .filter_map(|path| {
+                if !path.is_file() {
+                    return Some(NoteChange { path: path.clone(), entry: None });
+                }
+                match std::fs::read_to_string(path) {
+                    Ok(content) => {
+                        let (modified_ms, size) = file_version(path);
+                        let entry = NoteEntry { modified_ms, size, links: parse_links(&content) };
+                        Some(NoteChange { path: path.clone(), entry: Some(entry) })
+                    }
+                    Err(e) => {
+                        log::warn!("⚠️ Skipping unreadable note {:?}: {}", path, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Apply notes read by [`LinkGraph::read_changes`]
+    ///
+    /// Only the changed notes and the notes linking to a note that was added
+    /// or removed are resolved again. Returns the number of notes that were
+    /// updated or removed.
+    pub fn apply_changes(&mut self, changes: Vec<NoteChange>) -> LinkGraphResult<usize> {
+        let mut updated = 0;
+        let mut sources = HashSet::new();
+        let mut added_or_removed = HashSet::new();
+        for change in changes {
+            let key = self.relative_path(&change.path)?;
+            if !is_note_key(&key) {
+                continue;
+            }
+            match change.entry {
+                Some(entry) => {
+                    self.unindex_source(&key);
+                    if self.notes.insert(key.clone(), entry).is_none() {
+                        added_or_removed.insert(file_name_key(&key));
+                    }
+                    sources.insert(key);
+                }
+                None if self.notes.contains_key(&key) => {
+                    self.unindex_source(&key);
+                    self.notes.remove(&key);
+                    added_or_removed.insert(file_name_key(&key));
+                }
+                None => continue,
+            }
+            updated += 1;
+        }
+
+        for name in &added_or_removed {
+            if let Some(linking) = self.linking.get(name) {
+                sources.extend(linking.iter().cloned());
+            }
+        }
+        self.index_sources(&sources);
+        Ok(updated)
+    }
+
+    /// Load the persisted graph; returns false if there is none
+    fn load(&mut self) -> LinkGraphResult<bool> {
+        let storage_path = self.storage_path();
+        if !storage_path.exists() {
+            return Ok(false);
+        }
+
+        let content = std::fs::read_to_string(&storage_path).map_err(|e| LinkGraphError::IOError {
+            message: format!("Failed to read link graph: {}", e),
+        })?;
+        let persisted: PersistedLinkGraph = serde_json::from_str(&content).map_err(|e| {
+            LinkGraphError::SerializationError { message: format!("Failed to parse link graph: {}", e) }
+        })?;
+        if persisted.version != LINK_GRAPH_VERSION {
+            log::info!("🔄 Link graph format changed, rebuilding");
+            return Ok(false);
+        }
+
+        self.notes = persisted.notes;
+        self.rebuild_index();
+        Ok(true)
+    }
+
+    /// Persist the graph to `{vault}/.ainote/link_graph.json`
+    pub fn save(&self) -> LinkGraphResult<()> {
+        let (storage_path, serialized) = self.snapshot()?;
+        Self::write_snapshot(&storage_path, &serialized)
+    }
+
+    /// Serialize the graph, returning its storage path and contents
+    ///
+    /// Pair with [`LinkGraph::write_snapshot`] to write the file after
+    /// releasing the lock that guards the graph.
+    pub fn snapshot(&self) -> LinkGraphResult<(PathBuf, String)> {
+        let persisted = PersistedLinkGraph { version: LINK_GRAPH_VERSION, notes: self.notes.clone() };
+        let serialized = serde_json::to_string(&persisted).map_err(|e| LinkGraphError::SerializationError {
+            message: format!("Failed to serialize link graph: {}", e),
+        })?;
+        Ok((self.storage_path(), serialized))
+    }
+
+    /// Write a graph serialized by [`LinkGraph::snapshot`]
+    ///
+    /// A snapshot written out of order is harmless: opening the vault
+    /// re-parses every note whose modification time or size differs.
+    pub fn write_snapshot(storage_path: &Path, serialized: &str) -> LinkGraphResult<()> {
+        if let Some(parent_dir) = storage_path.parent() {
+            std::fs::create_dir_all(parent_dir).map_err(|e| LinkGraphError::IOError {
+                message: format!("Failed to create link graph directory: {}", e),
+            })?;
+        }
+
+        // Write to a temporary file first so a crash never leaves a truncated graph
+        let temp_path = storage_path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serialized)
+            .and_then(|_| std::fs::rename(&temp_path, storage_path))
+            .map_err(|e| LinkGraphError::IOError { message: format!("Failed to write link graph: {}", e) })?;
+
+        log::debug!("💾 Saved link graph to {:?}", storage_path);
+        Ok(())
+    }
+
+    /// Links going out of a note, in document order
+    pub fn outgoing_links(&self, path: &Path) -> LinkGraphResult<Vec<OutgoingLink>> {
+        let key = self.relative_path(path)?;
+        let Some(entry) = self.notes.get(&key) else {
+            return Ok(Vec::new());
+        };
+        let resolved = &self.resolved[&key];
+
+        Ok(entry
+            .links
+            .iter()
+            .zip(resolved)
+            .map(|(link, target)| OutgoingLink {
+                link: link.clone(),
+                target_path: target.as_ref().map(|target| self.absolute_path(target)),
+            })
+            .collect())
+    }
+
+    /// Links from other notes (and the note itself) pointing at a note
+    ///
+    /// Sorted by linking note, then by position within it.
+    pub fn backlinks(&self, path: &Path) -> LinkGraphResult<Vec<Backlink>> {
+        let key = self.relative_path(path)?;
+        let mut sources = self.incoming.get(&key).cloned().unwrap_or_default();
+        sources.sort();
+
+        Ok(sources
+            .into_iter()
+            .map(|(source, index)| Backlink {
+                source_path: self.absolute_path(&source),
+                link: self.notes[&source].links[index].clone(),
+            })
+            .collect())
+    }
+
+    /// Links whose target does not match any note, sorted by linking note
+    pub fn unresolved_links(&self) -> Vec<Backlink> {
+        self.notes
+            .iter()
+            .flat_map(|(source, entry)| {
+                entry
+                    .links
+                    .iter()
+                    .zip(&self.resolved[source])
+                    .filter(|(_, target)| target.is_none())
+                    .map(|(link, _)| Backlink { source_path: self.absolute_path(source), link: link.clone() })
+            })
+            .collect()
+    }
+
+    /// Notes that neither link to nor are linked from another note, sorted by path
+    pub fn orphan_notes(&self) -> Vec<String> {
+        self.notes
+            .keys()
+            .filter(|key| self.is_orphan(key))
+            .map(|key| self.absolute_path(key))
+            .collect()
+    }
+
+    /// Counts of notes, links, unresolved links and orphans
+    pub fn stats(&self) -> LinkGraphStats {
+        let resolved = self.resolved.values().flatten();
+        let (link_count, unresolved_link_count) =
+            resolved.fold((0, 0), |(links, unresolved), target| (links + 1, unresolved + target.is_none() as usize));
+        LinkGraphStats {
+            note_count: self.notes.len(),
+            link_count,
+            unresolved_link_count,
+            orphan_count: self.notes.keys().filter(|key| self.is_orphan(key)).count(),
+        }
+    }
+
+    /// Notes at a path: the note itself, or every note inside a folder
+    pub fn notes_under(&self, path: &Path) -> LinkGraphResult<Vec<String>> {
+        let key = self.relative_path(path)?;
+        let prefix = format!("{}/", key);
+        Ok(self
+            .notes
+            .keys()
+            .filter(|note| **note == key || note.starts_with(&prefix))
+            .map(|note| self.absolute_path(note))
+            .collect())
+    }
+
+    /// Links that break when notes move, with the targets that fix them
+    ///
+    /// `moves` maps note paths before the move to paths after it. Every
+    /// resolved link in the vault is resolved again against the moved notes;
+    /// links that would point elsewhere get a new target. Wikilinks use the
+    /// shortest target that is unambiguous, markdown links a path relative to
+    /// the (possibly moved) linking note, or to the vault root if the link
+    /// was written that way. Links that are unresolved now are left alone.
+    pub fn plan_moves(&self, moves: &[(PathBuf, PathBuf)]) -> LinkGraphResult<Vec<LinkRewrite>> {
+        let mut moved = HashMap::with_capacity(moves.len());
+        for (old_path, new_path) in moves {
+            moved.insert(self.relative_path(old_path)?, self.relative_path(new_path)?);
+        }
+        let moved_key = |key: &str| moved.get(key).cloned().unwrap_or_else(|| key.to_string());
+
+        let new_keys: Vec<String> = self.notes.keys().map(|key| moved_key(key)).collect();
+        let (by_name, by_path) = lookup_tables(&new_keys);
+
+        let mut rewrites = Vec::new();
+        for (source, entry) in &self.notes {
+            let new_source = moved_key(source);
+            for (link, target) in entry.links.iter().zip(&self.resolved[source]) {
+                let Some(target) = target else { continue };
+                if link.target.is_empty() {
+                    continue;
+                }
+                let new_target = moved_key(target);
+                if resolve_link(&new_source, link, &by_name, &by_path).as_ref() == Some(&new_target) {
+                    continue;
+                }
+
+                let keep_extension = link.target.to_lowercase().ends_with(".md");
+                let written = |key: &str| match keep_extension {
+                    true => key.to_string(),
+                    false => key.strip_suffix(".md").unwrap_or(key).to_string(),
+                };
+                let replacement = match link.kind {
+                    LinkKind::WikiLink => {
+                        let parts: Vec<&str> = new_target.split('/').collect();
+                        (1..=parts.len())
+                            .map(|count| written(&parts[parts.len() - count..].join("/")))
+                            .find(|candidate| {
+                                let candidate_link = NoteLink { target: candidate.clone(), ..link.clone() };
+                                resolve_link(&new_source, &candidate_link, &by_name, &by_path).as_ref()
+                                    == Some(&new_target)
+                            })
+                            .unwrap_or_else(|| written(&new_target))
+                    }
+                    LinkKind::Markdown if link.target.starts_with('/') => format!("/{}", written(&new_target)),
+                    LinkKind::Markdown => {
+                        let source_dir = new_source.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+                        written(&relative_key(source_dir, &new_target))
+                    }
+                };
+
+                rewrites.push(LinkRewrite {
+                    source_path: self.absolute_path(source),
+                    link: link.clone(),
+                    new_target: replacement,
+                });
+            }
+        }
+        Ok(rewrites)
+    }
+
+    fn is_orphan(&self, key: &str) -> bool {
+        let links_out = self.resolved[key].iter().flatten().any(|target| target != key);
+        let links_in = self
+            .incoming
+            .get(key)
+            .is_some_and(|sources| sources.iter().any(|(source, _)| source != key));
+        !links_out && !links_in
+    }
+
+    /// Resolve every link and rebuild the backlink index
+    fn rebuild_index(&mut self) {
+        self.resolved.clear();
+        self.incoming.clear();
+        self.linking.clear();
+        let sources: HashSet<String> = self.notes.keys().cloned().collect();
+        self.index_sources(&sources);
+    }
+
+    /// Resolve the links of `sources` and add them to the backlink index
+    ///
+    /// Sources that are no longer notes are skipped. Links already indexed
+    /// for a source are replaced.
+    fn index_sources(&mut self, sources: &HashSet<String>) {
+        if sources.is_empty() {
+            return;
+        }
+        for source in sources {
+            self.unindex_source(source);
+        }
+
+        let (by_name, by_path) = lookup_tables(self.notes.keys());
+        for source in sources {
+            let Some(entry) = self.notes.get(source) else { continue };
+            let targets: Vec<Option<String>> = entry
+                .links
+                .iter()
+                .map(|link| resolve_link(source, link, &by_name, &by_path))
+                .collect();
+            for (index, target) in targets.iter().enumerate() {
+                if let Some(target) = target {
+                    self.incoming.entry(target.clone()).or_default().push((source.clone(), index));
+                }
+            }
+            for name in entry.links.iter().filter_map(link_file_name) {
+                self.linking.entry(name).or_default().insert(source.clone());
+            }
+            self.resolved.insert(source.clone(), targets);
+        }
+    }
+
+    /// Remove a note's links from the backlink index
+    ///
+    /// Must run before the note's entry is replaced, since its current links
+    /// locate the index entries to drop.
+    fn unindex_source(&mut self, source: &str) {
+        if let Some(targets) = self.resolved.remove(source) {
+            let targets: HashSet<String> = targets.into_iter().flatten().collect();
+            for target in targets {
+                if let Some(links) = self.incoming.get_mut(&target) {
+                    links.retain(|(linking_source, _)| linking_source != source);
+                    if links.is_empty() {
+                        self.incoming.remove(&target);
+                    }
+                }
+            }
+        }
+        if let Some(entry) = self.notes.get(source) {
+            for name in entry.links.iter().filter_map(link_file_name) {
+                if let Some(linking) = self.linking.get_mut(&name) {
+                    linking.remove(source);
+                    if linking.is_empty() {
+                        self.linking.remove(&name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Vault-relative key of a path (absolute or already relative)
+    fn relative_path(&self, path: &Path) -> LinkGraphResult<String> {
+        let relative = if path.is_relative() {
+            Some(path.to_path_buf())
+        } else {
+            path.strip_prefix(&self.vault_path)
+                .ok()
+                .or_else(|| self.canonical_vault_path.as_ref().and_then(|vault| path.strip_prefix(vault).ok()))
+                .map(Path::to_path_buf)
+        };
+
+        relative
+            .and_then(|relative| normalize_components(&relative))
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| LinkGraphError::OutsideVault { path: path.display().to_string() })
+    }
+
+    fn absolute_path(&self, key: &str) -> String {
+        self.vault_path.join(key).to_string_lossy().to_string()
+    }
+}
+
+/// Lowercase file name of a note key, as looked up by links
+fn file_name_key(key: &str) -> String {
+    let lower = key.to_lowercase();
+    lower.rsplit('/').next().unwrap_or(&lower).to_string()
+}
+
+/// Lowercase file name a link points at, `None` for links to the note itself
+fn link_file_name(link: &NoteLink) -> Option<String> {
+    if link.target.is_empty() {
+        return None;
+    }
+    let mut target = link.target.to_lowercase();
+    if !target.ends_with(".md") {
+        target.push_str(".md");
+    }
+    Some(target.rsplit('/').next().unwrap_or(&target).to_string())
+}
+
+/// Notes by lowercase file name (for wikilinks) and by lowercase path
+fn lookup_tables<'a>(
+    keys: impl IntoIterator<Item = &'a String>,
+) -> (HashMap<String, Vec<&'a str>>, HashMap<String, &'a str>) {
+    let mut by_name: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut by_path: HashMap<String, &str> = HashMap::new();
+    for key in keys {
+        let lower = key.to_lowercase();
+        let name = lower.rsplit('/').next().unwrap_or(&lower).to_string();
+        by_name.entry(name).or_default().push(key);
+        by_path.insert(lower, key);
+    }
+    (by_name, by_path)
+}
+
+/// Path of note `to` relative to folder `from_dir`, both vault-relative keys
+fn relative_key(from_dir: &str, to: &str) -> String {
+    let from: Vec<&str> = from_dir.split('/').filter(|part| !part.is_empty()).collect();
+    let to: Vec<&str> = to.split('/').collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+    parts.join("/")
+}
+
+/// Replace link targets in a note's content
+///
+/// Each rewrite gives a link as stored in the graph and its new target; the
+/// heading, alias and link text are kept. Targets of markdown links are
+/// percent-encoded unless the link used `<...>`. Returns `None` if a link is
+/// no longer where it was parsed, i.e. the note changed in the meantime.
+pub fn rewrite_link_targets(content: &str, rewrites: &[(NoteLink, String)]) -> Option<String> {
+    let mut rewrites: Vec<&(NoteLink, String)> = rewrites.iter().collect();
+    rewrites.sort_by_key(|(link, _)| std::cmp::Reverse(link.position));
+
+    let mut rewritten = content.to_string();
+    for (link, new_target) in rewrites {
+        let (range, angle_brackets) = link_target_range(content, link)?;
+        let replacement = match link.kind {
+            LinkKind::Markdown if !angle_brackets => percent_encode_target(new_target),
+            _ => new_target.clone(),
+        };
+        rewritten.replace_range(range, &replacement);
+    }
+    Some(rewritten)
+}
+
+/// Byte range of a link's target in the note, and whether it is wrapped in `<...>`
+fn link_target_range(content: &str, link: &NoteLink) -> Option<(std::ops::Range<usize>, bool)> {
+    let rest = content.get(link.position..)?;
+    match link.kind {
+        LinkKind::WikiLink => {
+            let inner_start = link.position + 2;
+            let after_brackets = rest.strip_prefix("[[")?;
+            let inner = &after_brackets[..after_brackets.find("]]")?];
+            let target_end = inner.find('|').unwrap_or(inner.len());
+            let path = &inner[..inner[..target_end].find('#').unwrap_or(target_end)];
+            let start = inner_start + (path.len() - path.trim_start().len());
+            (path.trim() == link.target).then(|| (start..start + path.trim().len(), false))
+        }
+        LinkKind::Markdown => {
+            let bracket_end = rest.find(']')?;
+            let url_start = link.position + bracket_end + 2;
+            let url_region = rest.get(bracket_end + 1..)?.strip_prefix('(')?;
+            let url_region = &url_region[..url_region.find(')')?];
+            let url = &url_region[..url_region.find('"').unwrap_or(url_region.len())];
+            let mut start = url_start + (url.len() - url.trim_start().len());
+            let mut token = url.trim();
+            let angle_brackets = token.starts_with('<');
+            if angle_brackets {
+                token = token.trim_start_matches('<').trim_end_matches('>');
+                start += 1;
+            }
+            let path = &token[..token.find('#').unwrap_or(token.len())];
+            (percent_decode(path).trim() == link.target).then(|| (start..start + path.len(), angle_brackets))
+        }
+    }
+}
+
+/// Escape the characters that would end or split a markdown link target
+fn percent_encode_target(target: &str) -> String {
+    let mut encoded = String::with_capacity(target.len());
+    for c in target.chars() {
+        match c {
+            ' ' => encoded.push_str("%20"),
+            '(' => encoded.push_str("%28"),
+            ')' => encoded.push_str("%29"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Markdown files of a vault, skipping hidden files and directories
+fn markdown_files(vault_path: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkDir::new(vault_path)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && is_markdown(entry.path()))
+        .map(|entry| entry.into_path())
+}
+
+/// Whether a vault-relative path is a note tracked by the graph
+fn is_note_key(key: &str) -> bool {
+    is_markdown(Path::new(key)) && !key.split('/').any(|part| part.starts_with('.'))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("md"))
+}
+
+/// Modification time in milliseconds and size of a file, for change detection
+fn file_version(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return (0, 0);
+    };
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    (modified_ms, metadata.len())
+}
+
+/// Extract the links of a note that can point at other notes
+fn parse_links(content: &str) -> Vec<NoteLink> {
+    extract_markdown_links(content)
+        .into_iter()
+        .filter_map(|element| match element {
+            MarkdownElement::WikiLink(raw_target, alias, position) => {
+                let (target, subpath) = split_subpath(&raw_target);
+                if has_non_markdown_extension(&target) {
+                    return None;
+                }
+                Some(NoteLink { kind: LinkKind::WikiLink, target, subpath, display_text: alias, position })
+            }
+            MarkdownElement::Link(text, url, _, position) => {
+                let url = url.trim_start_matches('<').trim_end_matches('>');
+                if url.is_empty() || url.starts_with('#') || is_external(url) {
+                    return None;
+                }
+                let (target, subpath) = split_subpath(&percent_decode(url));
+                if has_non_markdown_extension(&target) {
+                    return None;
+                }
+                let display_text = Some(text).filter(|text| !text.is_empty());
+                Some(NoteLink { kind: LinkKind::Markdown, target, subpath, display_text, position })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Split `target#subpath` into its parts
+fn split_subpath(raw: &str) -> (String, Option<String>) {
+    match raw.split_once('#') {
+        Some((target, subpath)) => (target.trim().to_string(), Some(subpath.trim().to_string()).filter(|s| !s.is_empty())),
+        None => (raw.trim().to_string(), None),
+    }
+}
+
+pub(crate) fn has_non_markdown_extension(target: &str) -> bool {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    match name.rsplit_once('.') {
+        Some((stem, extension)) => {
+            !stem.is_empty()
+                && !extension.eq_ignore_ascii_case("md")
+                && extension.len() <= 5
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        None => false,
+    }
+}
+
+/// Whether a link URL has a scheme (`https:`, `mailto:`, `obsidian:`, ...)
+fn is_external(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1 && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Decode `%XX` escapes in a markdown link URL
+fn percent_decode(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let hex = |byte: u8| (byte as char).to_digit(16);
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| url.to_string())
+}
+
+/// Join path components with `/`, resolving `.` and `..`
+///
+/// Returns `None` if the path escapes its root.
+fn normalize_components(path: &Path) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Resolve a link of `source` to the key of the note it points at
+fn resolve_link(
+    source: &str,
+    link: &NoteLink,
+    by_name: &HashMap<String, Vec<&str>>,
+    by_path: &HashMap<String, &str>,
+) -> Option<String> {
+    if link.target.is_empty() {
+        // [[#Heading]] points at the note itself
+        return Some(source.to_string());
+    }
+
+    let mut target = link.target.to_lowercase();
+    if !target.ends_with(".md") {
+        target.push_str(".md");
+    }
+
+    match link.kind {
+        LinkKind::WikiLink => {
+            let target = target.trim_start_matches('/');
+            let name = target.rsplit('/').next().unwrap_or(target);
+            let suffix = format!("/{}", target);
+            by_name
+                .get(name)?
+                .iter()
+                .filter(|key| {
+                    let lower = key.to_lowercase();
+                    lower == target || lower.ends_with(&suffix)
+                })
+                .min_by_key(|key| (key.len(), **key))
+                .map(|key| key.to_string())
+        }
+        LinkKind::Markdown => {
+            let joined = match target.strip_prefix('/') {
+                Some(from_root) => PathBuf::from(from_root),
+                None => Path::new(source).parent().unwrap_or(Path::new("")).join(&target),
+            };
+            let key = normalize_components(&joined)?;
+            by_path.get(&key.to_lowercase()).map(|key| key.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_note(vault: &Path, relative: &str, content: &str) -> PathBuf {
+        let path = vault.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn sample_vault() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = temp_dir.path();
+        write_note(vault, "Index.md", "See [[Project Alpha]], [[Projects/Beta#Risks|Beta risks]] and [[Missing]].\n");
+        write_note(vault, "Projects/Project Alpha.md", "Back to [the index](../Index.md) and [[#Goals]].\n![[chart.png]]\n");
+        write_note(vault, "Projects/Beta.md", "# Risks\n[Alpha](Project%20Alpha.md) [site](https://example.com)\n");
+        write_note(vault, "Archive/Beta.md", "Old beta notes.\n");
+        write_note(vault, "Lonely.md", "No links here.\n```\n[[Index]]\n```\n");
+        write_note(vault, ".obsidian/ignored.md", "[[Index]]\n");
+        temp_dir
+    }
+
+    #[test]
+    fn test_links_resolve_and_backlinks() {
+        let temp_dir = sample_vault();
+        let vault = temp_dir.path();
+        let graph = LinkGraph::open(vault).unwrap();
+
+        let outgoing = graph.outgoing_links(&vault.join("Index.md")).unwrap();
+        let targets: Vec<Option<String>> = outgoing.iter().map(|link| link.target_path.clone()).collect();
+        assert_eq!(targets, vec![
+            Some(vault.join("Projects/Project Alpha.md").to_string_lossy().to_string()),
+            Some(vault.join("Projects/Beta.md").to_string_lossy().to_string()),
+            None,
+        ]);
+        assert_eq!(outgoing[1].link.subpath.as_deref(), Some("Risks"));
+        assert_eq!(outgoing[1].link.display_text.as_deref(), Some("Beta risks"));
+
+        // Attachments and URLs are not note links
+        assert_eq!(graph.outgoing_links(Path::new("Projects/Project Alpha.md")).unwrap().len(), 2);
+        assert_eq!(graph.outgoing_links(Path::new("Projects/Beta.md")).unwrap().len(), 1);
+
+        let backlinks = graph.backlinks(&vault.join("Projects/Project Alpha.md")).unwrap();
+        let sources: Vec<String> = backlinks.into_iter().map(|link| link.source_path).collect();
+        assert_eq!(sources, vec![
+            vault.join("Index.md").to_string_lossy().to_string(),
+            vault.join("Projects/Beta.md").to_string_lossy().to_string(),
+            vault.join("Projects/Project Alpha.md").to_string_lossy().to_string(),
+        ]);
+
+        let unresolved = graph.unresolved_links();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].link.target, "Missing");
+
+        let orphans = graph.orphan_notes();
+        assert_eq!(orphans, vec![
+            vault.join("Archive/Beta.md").to_string_lossy().to_string(),
+            vault.join("Lonely.md").to_string_lossy().to_string(),
+        ]);
+
+        let stats = graph.stats();
+        assert_eq!(stats.note_count, 5);
+        assert_eq!(stats.unresolved_link_count, 1);
+        assert_eq!(stats.orphan_count, 2);
+
+        assert!(graph.backlinks(Path::new("/elsewhere/note.md")).is_err());
+    }
+
+    #[test]
+    fn test_wikilinks_prefer_shortest_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = temp_dir.path();
+        write_note(vault, "Deep/Nested/Topic.md", "");
+        write_note(vault, "Area/Topic.md", "");
+        write_note(vault, "Home.md", "[[topic]] [[Nested/Topic]] [[deep/nested/topic.md]]");
+
+        let graph = LinkGraph::open(vault).unwrap();
+        let targets: Vec<String> = graph
+            .outgoing_links(Path::new("Home.md"))
+            .unwrap()
+            .into_iter()
+            .map(|link| link.target_path.unwrap())
+            .collect();
+        assert_eq!(targets, vec![
+            vault.join("Area/Topic.md").to_string_lossy().to_string(),
+            vault.join("Deep/Nested/Topic.md").to_string_lossy().to_string(),
+            vault.join("Deep/Nested/Topic.md").to_string_lossy().to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_updates_and_persistence() {
+        let temp_dir = sample_vault();
+        let vault = temp_dir.path();
+        let mut graph = LinkGraph::open(vault).unwrap();
+        assert!(graph.storage_path().exists());
+
+        // Creating the missing note resolves the dangling link
+        let missing = write_note(vault, "Missing.md", "Now links to [[Lonely]].");
+        assert_eq!(graph.update_notes(std::slice::from_ref(&missing)).unwrap(), 1);
+        assert!(graph.unresolved_links().is_empty());
+        assert_eq!(graph.backlinks(&vault.join("Lonely.md")).unwrap().len(), 1);
+
+        // Deleting a note turns links to it into unresolved links again
+        std::fs::remove_file(&missing).unwrap();
+        assert_eq!(graph.update_notes(&[missing]).unwrap(), 1);
+        assert_eq!(graph.unresolved_links().len(), 1);
+        assert!(graph.update_notes(&[PathBuf::from("/elsewhere/note.md")]).is_err());
+        let hidden = write_note(vault, ".trash/Old.md", "[[Index]]");
+        assert_eq!(graph.update_notes(&[hidden]).unwrap(), 0);
+        graph.save().unwrap();
+
+        // Reopening picks up changes made while the vault was not watched
+        write_note(vault, "Lonely.md", "Linked to [[Index]] now.");
+        std::fs::remove_file(vault.join("Archive/Beta.md")).unwrap();
+        let reopened = LinkGraph::open(vault).unwrap();
+        assert_eq!(reopened.stats().note_count, 4);
+        assert!(reopened.orphan_notes().is_empty());
+    }
+
+    #[test]
+    fn test_incremental_updates_match_full_rebuild() {
+        let temp_dir = sample_vault();
+        let vault = temp_dir.path();
+        let mut graph = LinkGraph::open(vault).unwrap();
+
+        // A shorter path takes over [[Project Alpha]], a rewrite drops a link,
+        // a deletion leaves [[Projects/Beta#Risks]] dangling, and an
+        // unreadable note is skipped without failing the batch
+        let alpha = write_note(vault, "Project Alpha.md", "Top-level alpha.");
+        let index = write_note(vault, "Index.md", "See [[Project Alpha]] and [[Projects/Beta#Risks]].");
+        std::fs::remove_file(vault.join("Projects/Beta.md")).unwrap();
+        let unreadable = vault.join("Binary.md");
+        std::fs::write(&unreadable, [0xff, 0xfe, 0x00]).unwrap();
+        let paths = vec![alpha, index, vault.join("Projects/Beta.md"), unreadable];
+        assert_eq!(graph.update_notes(&paths).unwrap(), 3);
+
+        let mut rebuilt = LinkGraph::new(vault);
+        rebuilt.rebuild().unwrap();
+        assert_eq!(graph.stats(), rebuilt.stats());
+        assert_eq!(graph.orphan_notes(), rebuilt.orphan_notes());
+        assert_eq!(graph.unresolved_links(), rebuilt.unresolved_links());
+        for note in ["Index.md", "Project Alpha.md", "Projects/Project Alpha.md", "Archive/Beta.md"] {
+            let path = vault.join(note);
+            assert_eq!(graph.outgoing_links(&path).unwrap(), rebuilt.outgoing_links(&path).unwrap());
+            assert_eq!(graph.backlinks(&path).unwrap(), rebuilt.backlinks(&path).unwrap());
+        }
+        assert_eq!(graph.backlinks(&vault.join("Project Alpha.md")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_plan_moves_rewrites_broken_links() {
+        let temp_dir = sample_vault();
+        let vault = temp_dir.path();
+        let index = write_note(
+            vault,
+            "Index.md",
+            "See [[Project Alpha]], [[Projects/Beta#Risks|Beta risks]] and [goals](Projects/Project%20Alpha.md#Goals).\n",
+        );
+        let graph = LinkGraph::open(vault).unwrap();
+
+        let moves = vec![(vault.join("Projects/Project Alpha.md"), vault.join("Areas/Work/Alpha Plan.md"))];
+        let rewrites = graph.plan_moves(&moves).unwrap();
+        let planned: Vec<(String, &str, &str)> = rewrites
+            .iter()
+            .map(|rewrite| (rewrite.source_path.clone(), rewrite.link.target.as_str(), rewrite.new_target.as_str()))
+            .collect();
+        let path = |relative: &str| vault.join(relative).to_string_lossy().to_string();
+        assert_eq!(planned, vec![
+            (path("Index.md"), "Project Alpha", "Alpha Plan"),
+            (path("Index.md"), "Projects/Project Alpha.md", "Areas/Work/Alpha Plan.md"),
+            (path("Projects/Beta.md"), "Project Alpha.md", "../Areas/Work/Alpha Plan.md"),
+            (path("Projects/Project Alpha.md"), "../Index.md", "../../Index.md"),
+        ]);
+
+        let content = std::fs::read_to_string(&index).unwrap();
+        let index_rewrites: Vec<(NoteLink, String)> = rewrites
+            .iter()
+            .filter(|rewrite| rewrite.source_path == path("Index.md"))
+            .map(|rewrite| (rewrite.link.clone(), rewrite.new_target.clone()))
+            .collect();
+        assert_eq!(
+            rewrite_link_targets(&content, &index_rewrites).unwrap(),
+            "See [[Alpha Plan]], [[Projects/Beta#Risks|Beta risks]] and [goals](Areas/Work/Alpha%20Plan.md#Goals).\n"
+        );
+        assert!(rewrite_link_targets("Rewritten meanwhile", &index_rewrites).is_none());
+    }
+
+    #[test]
+    fn test_plan_moves_keeps_wikilinks_unambiguous() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = temp_dir.path();
+        write_note(vault, "Deep/Nested/Topic.md", "");
+        write_note(vault, "Area/Topic.md", "");
+        write_note(vault, "Home.md", "[[topic]] [[Nested/Topic|alias]] ![[deep/nested/topic.md]]");
+        let graph = LinkGraph::open(vault).unwrap();
+
+        // The moved note takes over `[[topic]]`, which must now spell out its folder
+        let moves = vec![(vault.join("Deep/Nested/Topic.md"), vault.join("Topic.md"))];
+        let rewrites: Vec<(NoteLink, String)> = graph
+            .plan_moves(&moves)
+            .unwrap()
+            .into_iter()
+            .map(|rewrite| (rewrite.link, rewrite.new_target))
+            .collect();
+        let content = std::fs::read_to_string(vault.join("Home.md")).unwrap();
+        assert_eq!(
+            rewrite_link_targets(&content, &rewrites).unwrap(),
+            "[[Area/Topic]] [[Topic|alias]] ![[Topic.md]]"
+        );
+    }
+
+    #[test]
+    fn test_link_helpers() {
+        assert_eq!(percent_decode("My%20Note.md"), "My Note.md");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert!(is_external("https://example.com"));
+        assert!(is_external("mailto:me@example.com"));
+        assert!(!is_external("C/notes.md"));
+        assert!(has_non_markdown_extension("images/photo.PNG"));
+        assert!(!has_non_markdown_extension("Version 1.2 notes"));
+        assert!(!has_non_markdown_extension("Note.md"));
+        assert_eq!(normalize_components(Path::new("a/./b/../c.md")).as_deref(), Some("a/c.md"));
+        assert_eq!(normalize_components(Path::new("../outside.md")), None);
+        assert_eq!(relative_key("Projects/Beta", "Projects/Alpha.md"), "../Alpha.md");
+        assert_eq!(relative_key("", "Projects/Alpha.md"), "Projects/Alpha.md");
+    }
+}
@@ -21,6 +21,13 @@ use serde::{Serialize, Deserialize};
 use thiserror::Error;
 
 use crate::performance::PerformanceTracker;
+use crate::process_sampler::ProcessSampler;
+
+/// Process samples younger than this are reused instead of re-reading /proc
+const PROCESS_SAMPLE_MAX_AGE_MS: u64 = 1000;
+
+/// Share of system memory the process may hold when no RSS budget is configured
+const DEFAULT_RSS_BUDGET_FRACTION: f64 = 0.25;
 
 /// Memory management errors
 #[derive(Error, Debug, Clone)]
@@ -46,8 +53,12 @@ pub type MemoryResult<T> = Result<T, MemoryError>;
 /// Memory management configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryManagerConfig {
-    /// Maximum total memory usage in MB
+    /// Maximum memory registered through `track_allocation` in MB
     pub max_memory_mb: usize,
+    /// Resident set size budget for the whole process in MB
+    /// (None: a quarter of system memory)
+    #[serde(default)]
+    pub rss_budget_mb: Option<usize>,
     /// Memory limit for AI operations in MB
     pub ai_operations_limit_mb: usize,
     /// Memory monitoring interval in seconds
@@ -70,6 +81,7 @@ impl Default for MemoryManagerConfig {
     fn default() -> Self {
         Self {
             max_memory_mb: 100,                    // 100MB base target
+            rss_budget_mb: None,                   // Quarter of system memory
             ai_operations_limit_mb: 50,            // 50MB for AI operations
             monitoring_interval_seconds: 10,       // Monitor every 10 seconds
             enable_auto_gc: true,                  // Auto GC enabled
@@ -121,15 +133,15 @@ pub enum AllocationType {
 /// Memory usage metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryMetrics {
-    /// Total memory usage in MB
+    /// Memory registered through `track_allocation` in MB
     pub total_memory_mb: f64,
     /// AI operations memory usage in MB  
     pub ai_operations_memory_mb: f64,
     /// Cache memory usage in MB
     pub cache_memory_mb: f64,
-    /// Free memory in MB
+    /// Tracked memory left below `max_memory_mb` in MB
     pub free_memory_mb: f64,
-    /// Memory usage percentage
+    /// Tracked memory as a percentage of `max_memory_mb`
     pub usage_percentage: f64,
     /// Number of active allocations
     pub active_allocations: usize,
@@ -137,8 +149,20 @@ pub struct MemoryMetrics {
     pub detected_leaks: usize,
     /// Last GC timestamp
     pub last_gc_timestamp: u64,
-    /// Memory pressure level (0.0 to 1.0)
+    /// Memory pressure level (0.0 to 1.0), the higher of tracked and RSS usage
     pub memory_pressure: f64,
+    /// Resident set size of the process in MB (None where /proc is unavailable)
+    #[serde(default)]
+    pub process_rss_mb: Option<f64>,
+    /// Resident set size budget in MB
+    #[serde(default)]
+    pub rss_budget_mb: Option<f64>,
+    /// Resident set size as a percentage of the budget
+    #[serde(default)]
+    pub rss_usage_percentage: Option<f64>,
+    /// Peak resident set size of the process in MB
+    #[serde(default)]
+    pub peak_rss_mb: Option<f64>,
     /// Timestamp of metrics
     pub timestamp: u64,
 }
@@ -214,6 +238,7 @@ pub struct MemoryManager {
     allocation_limiter: AllocationLimiter,
     leak_detection: Arc<RwLock<HashMap<String, LeakDetectionEntry>>>,
     metrics_history: Arc<RwLock<Vec<MemoryMetrics>>>,
+    process_sampler: Arc<ProcessSampler>,
     is_running: Arc<AtomicBool>,
     monitoring_handle: Option<tokio::task::JoinHandle<()>>,
     cleanup_handle: Option<tokio::task::JoinHandle<()>>,
@@ -230,12 +255,19 @@ impl MemoryManager {
             allocation_limiter,
             leak_detection: Arc::new(RwLock::new(HashMap::new())),
             metrics_history: Arc::new(RwLock::new(Vec::new())),
+            process_sampler: Arc::new(ProcessSampler::default()),
             is_running: Arc::new(AtomicBool::new(false)),
             monitoring_handle: None,
             cleanup_handle: None,
         }
     }
 
+    /// Use a shared process sampler instead of a private one
+    pub fn with_process_sampler(mut self, process_sampler: Arc<ProcessSampler>) -> Self {
+        self.process_sampler = process_sampler;
+        self
+    }
+
     /// Start memory monitoring
     pub async fn start(&mut self) -> MemoryResult<()> {
         if self.is_running.load(Ordering::Acquire) {
@@ -366,8 +398,19 @@ impl MemoryManager {
         let total_mb = total_bytes as f64 / (1024.0 * 1024.0);
         let ai_mb = ai_bytes as f64 / (1024.0 * 1024.0);
         let cache_mb = cache_bytes as f64 / (1024.0 * 1024.0);
+        
         let free_mb = self.config.max_memory_mb as f64 - total_mb;
         let usage_percent = (total_mb / self.config.max_memory_mb as f64) * 100.0;
+        
+        // The whole process is measured against its own, much larger budget
+        let process_sample = self.process_sampler.latest_or_sample(PROCESS_SAMPLE_MAX_AGE_MS).ok();
+        let rss_budget_mb = process_sample.as_ref().map(|sample| {
+            self.config.rss_budget_mb
+                .map_or(sample.system_memory_mb * DEFAULT_RSS_BUDGET_FRACTION, |budget| budget as f64)
+        });
+        let rss_usage_percent = process_sample.as_ref().zip(rss_budget_mb)
+            .filter(|(_, budget)| *budget > 0.0)
+            .map(|(sample, budget)| sample.rss_mb / budget * 100.0);
 
         let leak_count = self.leak_detection.read().await.len();
         
@@ -385,7 +428,11 @@ impl MemoryManager {
             active_allocations: allocations.values().filter(|a| a.is_active).count(),
             detected_leaks: leak_count,
             last_gc_timestamp: 0, // TODO: track actual GC timestamp
-            memory_pressure: (usage_percent / 100.0).min(1.0),
+            memory_pressure: (usage_percent.max(rss_usage_percent.unwrap_or(0.0)) / 100.0).min(1.0),
+            process_rss_mb: process_sample.as_ref().map(|sample| sample.rss_mb),
+            rss_budget_mb,
+            rss_usage_percentage: rss_usage_percent,
+            peak_rss_mb: process_sample.as_ref().map(|sample| sample.peak_rss_mb),
             timestamp,
         };
 
@@ -507,12 +554,13 @@ impl MemoryManager {
     async fn check_memory_limits(&self) -> MemoryResult<()> {
         let metrics = self.get_memory_metrics().await?;
         
-        if metrics.usage_percentage > self.config.alert_threshold_percent {
-            eprintln!("⚠️ Memory usage at {:.1}%", metrics.usage_percentage);
+        let pressure_percent = metrics.memory_pressure * 100.0;
+        if pressure_percent > self.config.alert_threshold_percent {
+            eprintln!("⚠️ Memory usage at {:.1}%", pressure_percent);
             
             // Trigger GC if auto-enabled and above threshold
             if self.config.enable_auto_gc && 
-               metrics.usage_percentage > self.config.gc_trigger_threshold_percent {
+               pressure_percent > self.config.gc_trigger_threshold_percent {
                 self.trigger_gc().await?;
             }
         }
@@ -523,6 +571,14 @@ impl MemoryManager {
                 limit_mb: self.config.max_memory_mb,
             });
         }
+        if let (Some(rss_mb), Some(budget_mb)) = (metrics.process_rss_mb, metrics.rss_budget_mb) {
+            if rss_mb > budget_mb {
+                return Err(MemoryError::MemoryLimitExceeded {
+                    used_mb: rss_mb as usize,
+                    limit_mb: budget_mb as usize,
+                });
+            }
+        }
         
         Ok(())
     }
@@ -531,6 +587,7 @@ impl MemoryManager {
         let allocation_tracker = Arc::clone(&self.allocation_tracker);
         let leak_detection = Arc::clone(&self.leak_detection);
         let metrics_history = Arc::clone(&self.metrics_history);
+        let process_sampler = Arc::clone(&self.process_sampler);
         let is_running = Arc::clone(&self.is_running);
         let config = self.config.clone();
         
@@ -547,6 +604,7 @@ impl MemoryManager {
                     allocation_limiter: AllocationLimiter::new(config.ai_operations_limit_mb),
                     leak_detection: Arc::clone(&leak_detection),
                     metrics_history: Arc::clone(&metrics_history),
+                    process_sampler: Arc::clone(&process_sampler),
                     is_running: Arc::clone(&is_running),
                     monitoring_handle: None,
                     cleanup_handle: None,
@@ -562,9 +620,18 @@ impl MemoryManager {
                         history.remove(0);
                     }
                     
+                    drop(history);
+                    
                     // Check for memory pressure
                     if metrics.memory_pressure > 0.8 {
-                        eprintln!("🚨 High memory pressure: {:.1}%", metrics.usage_percentage);
+                        eprintln!("🚨 High memory pressure: {:.1}%", metrics.memory_pressure * 100.0);
+                    }
+                    
+                    // Reclaim released allocations once tracked memory or RSS crosses the GC threshold
+                    if config.enable_auto_gc && metrics.memory_pressure * 100.0 > config.gc_trigger_threshold_percent {
+                        if let Err(e) = temp_manager.trigger_gc().await {
+                            eprintln!("⚠️ Auto GC failed: {}", e);
+                        }
                     }
                 }
                 
@@ -742,4 +809,42 @@ mod tests {
         
         println!("Memory leak detection test completed successfully");
     }
+    
+    #[tokio::test]
+    async fn test_memory_metrics_use_process_rss() {
+        use crate::process_sampler::ProcSources;
+        
+        let config = MemoryManagerConfig {
+            rss_budget_mb: Some(384),
+            ..Default::default()
+        };
+        let sampler = Arc::new(ProcessSampler::new(ProcSources::from_root("test_fixtures/proc/t1"), 4));
+        let manager = MemoryManager::new(config).with_process_sampler(sampler);
+        
+        manager.track_allocation(
+            "cache".to_string(),
+            "component".to_string(),
+            1024 * 1024,
+            AllocationType::EmbeddingCache,
+        ).await.unwrap();
+        
+        // Tracked memory is measured against max_memory_mb, the fixture's 192MB RSS against its budget
+        let metrics = manager.get_memory_metrics().await.unwrap();
+        assert!((metrics.total_memory_mb - 1.0).abs() < 0.1);
+        assert!((metrics.usage_percentage - 1.0).abs() < 1e-9);
+        assert!((metrics.free_memory_mb - 99.0).abs() < 1e-9);
+        assert_eq!(metrics.process_rss_mb, Some(192.0));
+        assert!(metrics.peak_rss_mb.unwrap() > 195.0);
+        assert_eq!(metrics.rss_budget_mb, Some(384.0));
+        assert!((metrics.rss_usage_percentage.unwrap() - 50.0).abs() < 1e-9);
+        assert!((metrics.memory_pressure - 0.5).abs() < 1e-9);
+        
+        // Without a configured budget the process may use a quarter of the fixture's 16000MB
+        let sampler = Arc::new(ProcessSampler::new(ProcSources::from_root("test_fixtures/proc/t1"), 4));
+        let manager = MemoryManager::new(MemoryManagerConfig::default()).with_process_sampler(sampler);
+        let metrics = manager.get_memory_metrics().await.unwrap();
+        assert!((metrics.rss_budget_mb.unwrap() - 4000.0).abs() < 1e-9);
+        assert!((metrics.rss_usage_percentage.unwrap() - 4.8).abs() < 1e-9);
+        assert!((metrics.memory_pressure - 0.048).abs() < 1e-9);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,454 @@
+//! # Embedding Model Migration
+//!
+//! Re-embeds an indexed vault with a different embedding model. The target
+//! model's vectors are stored in their own namespace of the vault's vector
+//! database while the current namespace keeps serving searches. Once every
+//! file has been re-embedded the database switches namespaces atomically and,
+//! if requested, drops the previous model's embeddings.
+//!
+//! ## Phases
+//!
+//! 1. **Planning**: Collect the files indexed under the current model
+//! 2. **Embedding**: Chunk each file and embed it with the target model
+//! 3. **Switching**: Build the target namespace's HNSW index and activate it
+//! 4. **Cleanup**: Delete the previous namespace (optional)
+//!
+//! Files that already have embeddings from the target model are skipped, so
+//! a cancelled or failed migration resumes where it stopped when it is
+//! started again. If any file fails, the switch does not happen and the
+//! previous model stays active.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::embedding_provider::EmbeddingProvider;
+use crate::globals::{embedding_context_length, open_vault_vector_database, VECTOR_DATABASE};
+use crate::indexing_pipeline::{CancellationToken, IndexingError, IndexingPipeline};
+use crate::text_chunker::ChunkProcessor;
+use crate::vector_db::types::{EmbeddingEntry, VectorStorageConfig};
+use crate::vector_db::VectorDatabase;
+
+/// Maximum number of per-file errors kept in the status
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// Phase of a model migration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MigrationPhase {
+    /// No migration has been started
+    Idle,
+    /// Collecting the files to re-embed
+    Planning,
+    /// Embedding files with the target model
+    Embedding,
+    /// Building the target index and switching the active model
+    Switching,
+    /// Deleting the previous model's embeddings
+    Cleanup,
+    /// The target model is active
+    Completed,
+    /// Stopped on request; the previous model stays active
+    Cancelled,
+    /// Stopped by an error; the previous model stays active
+    Failed,
+}
+
+/// Progress of the current (or last) model migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    /// Current phase
+    pub phase: MigrationPhase,
+    /// Vault being migrated
+    pub vault_path: Option<String>,
+    /// Model active when the migration started
+    pub source_model: Option<String>,
+    /// Model being migrated to
+    pub target_model: Option<String>,
+    /// Number of files to re-embed
+    pub total_files: usize,
+    /// Files embedded with the target model so far
+    pub processed_files: usize,
+    /// Files skipped because they were already embedded or no longer exist
+    pub skipped_files: usize,
+    /// Files that could not be embedded
+    pub failed_files: usize,
+    /// First errors encountered (capped)
+    pub errors: Vec<String>,
+    /// Number of embeddings deleted from the previous namespace
+    pub dropped_embeddings: usize,
+    /// Start timestamp (seconds since epoch)
+    pub started_at: Option<u64>,
+    /// End timestamp (seconds since epoch)
+    pub finished_at: Option<u64>,
+}
+
+impl Default for MigrationStatus {
+    fn default() -> Self {
+        Self {
+            phase: MigrationPhase::Idle,
+            vault_path: None,
+            source_model: None,
+            target_model: None,
+            total_files: 0,
+            processed_files: 0,
+            skipped_files: 0,
+            failed_files: 0,
+            errors: Vec::new(),
+            dropped_embeddings: 0,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+}
+
+impl MigrationStatus {
+    /// Whether a migration is in progress
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.phase,
+            MigrationPhase::Planning | MigrationPhase::Embedding | MigrationPhase::Switching | MigrationPhase::Cleanup
+        )
+    }
+
+    /// Percentage of planned files handled so far
+    pub fn progress_percent(&self) -> f64 {
+        if self.total_files == 0 {
+            return if self.phase == MigrationPhase::Completed { 100.0 } else { 0.0 };
+        }
+        (self.processed_files + self.failed_files) as f64 / self.total_files as f64 * 100.0
+    }
+
+    fn record_error(&mut self, error: String) {
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+}
+
+/// Parameters of a model migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationRequest {
+    /// Vault whose vector database is migrated
+    pub vault_path: String,
+    /// Embedding model to migrate to
+    pub target_model: String,
+    /// Delete the previous model's embeddings after switching
+    pub drop_previous: bool,
+}
+
+/// Files to re-embed for a migration
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationPlan {
+    /// Files indexed under the source model but not yet under the target model
+    pub files_to_embed: Vec<String>,
+    /// Files that already have embeddings from the target model
+    pub already_embedded: usize,
+}
+
+impl MigrationPlan {
+    /// Plan a migration from the entries currently stored in the database
+    ///
+    /// Every file indexed under `source_model` (or under any model other than
+    /// the target when there is no source) is re-embedded unless the target
+    /// model already has entries for it.
+    pub fn from_entries(entries: &[EmbeddingEntry], source_model: Option<&str>, target_model: &str) -> Self {
+        let embedded: HashSet<&str> = entries
+            .iter()
+            .filter(|entry| entry.metadata.model_name == target_model)
+            .map(|entry| entry.metadata.file_path.as_str())
+            .collect();
+
+        let source_files: BTreeSet<&str> = entries
+            .iter()
+            .filter(|entry| match source_model {
+                Some(source_model) => entry.metadata.model_name == source_model,
+                None => entry.metadata.model_name != target_model,
+            })
+            .map(|entry| entry.metadata.file_path.as_str())
+            .collect();
+
+        let (already_embedded, files_to_embed): (Vec<&str>, Vec<&str>) =
+            source_files.into_iter().partition(|file_path| embedded.contains(file_path));
+
+        Self {
+            files_to_embed: files_to_embed.into_iter().map(str::to_string).collect(),
+            already_embedded: already_embedded.len(),
+        }
+    }
+}
+
+/// Background "re-embed vault with model X" job
+pub struct ModelMigration {
+    status: Arc<RwLock<MigrationStatus>>,
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl Default for ModelMigration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelMigration {
+    /// Create an idle migration job
+    pub fn new() -> Self {
+        Self {
+            status: Arc::new(RwLock::new(MigrationStatus::default())),
+            cancellation_token: Arc::new(CancellationToken::new()),
+        }
+    }
+
+    /// Get a snapshot of the migration status
+    pub async fn status(&self) -> MigrationStatus {
+        self.status.read().await.clone()
+    }
+
+    /// Request cancellation; the job stops before the next chunk is embedded
+    pub fn cancel(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    /// Start a migration in the background
+    ///
+    /// Fails if a migration is already running or the target model is already
+    /// active. Progress is reported through [`Self::status`].
+    pub async fn start(
+        self: &Arc<Self>,
+        request: MigrationRequest,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
+    ) -> Result<MigrationStatus, String> {
+        if request.target_model.trim().is_empty() {
+            return Err("Target model cannot be empty".to_string());
+        }
+        open_vault_vector_database(&request.vault_path).await?;
+
+        let source_model = {
+            let db_guard = VECTOR_DATABASE.read().await;
+            let database = vault_database(db_guard.as_ref(), &request.vault_path)?;
+            database.active_model().await
+        };
+        if source_model == Some(embedding_provider.model_id(&request.target_model)) {
+            return Err(format!("Model '{}' is already active", request.target_model));
+        }
+
+        let initial_status = {
+            let mut status = self.status.write().await;
+            if status.is_running() {
+                return Err("A model migration is already running".to_string());
+            }
+            *status = MigrationStatus {
+                phase: MigrationPhase::Planning,
+                vault_path: Some(request.vault_path.clone()),
+                source_model,
+                target_model: Some(request.target_model.clone()),
+                started_at: Some(unix_timestamp()),
+                ..MigrationStatus::default()
+            };
+            status.clone()
+        };
+        self.cancellation_token.reset();
+
+        let migration = Arc::clone(self);
+        tokio::spawn(async move {
+            let outcome = migration.run(&request, embedding_provider.as_ref()).await;
+
+            let mut status = migration.status.write().await;
+            status.finished_at = Some(unix_timestamp());
+            match outcome {
+                Ok(()) => {
+                    status.phase = MigrationPhase::Completed;
+                    eprintln!("✅ Model migration to '{}' completed", request.target_model);
+                }
+                Err(IndexingError::Cancelled) => {
+                    status.phase = MigrationPhase::Cancelled;
+                    eprintln!("🛑 Model migration to '{}' cancelled", request.target_model);
+                }
+                Err(e) => {
+                    status.phase = MigrationPhase::Failed;
+                    status.record_error(e.to_string());
+                    eprintln!("❌ Model migration to '{}' failed: {}", request.target_model, e);
+                }
+            }
+        });
+
+        Ok(initial_status)
+    }
+
+    async fn run(&self, request: &MigrationRequest, embedding_provider: &dyn EmbeddingProvider) -> Result<(), IndexingError> {
+        let source_model = self.status.read().await.source_model.clone();
+        // Stored embeddings and namespaces are keyed by the provider's model ID
+        let target_model_id = embedding_provider.model_id(&request.target_model);
+
+        // Phase 1: plan from what is currently indexed
+        let plan = {
+            let db_guard = VECTOR_DATABASE.read().await;
+            let database = vault_database(db_guard.as_ref(), &request.vault_path).map_err(migration_error)?;
+            let entry_ids = database.list_embedding_ids().await;
+            let entries = database.retrieve_embeddings(&entry_ids).await.map_err(|e| migration_error(e.to_string()))?;
+            MigrationPlan::from_entries(&entries, source_model.as_deref(), &target_model_id)
+        };
+        {
+            let mut status = self.status.write().await;
+            status.phase = MigrationPhase::Embedding;
+            status.total_files = plan.files_to_embed.len();
+            status.skipped_files = plan.already_embedded;
+        }
+        eprintln!(
+            "🔁 Re-embedding {} files with '{}' ({} already embedded)",
+            plan.files_to_embed.len(),
+            request.target_model,
+            plan.already_embedded
+        );
+
+        // Phase 2: embed every file into the target namespace
+        let text_chunker = ChunkProcessor::for_embedding_model(&request.target_model, embedding_context_length(&request.target_model).await)
+            .map_err(|e| migration_error(format!("Failed to create chunk processor: {}", e)))?;
+
+        for file_path in &plan.files_to_embed {
+            if self.cancellation_token.is_cancelled() {
+                return Err(IndexingError::Cancelled);
+            }
+            if !Path::new(file_path).exists() {
+                self.status.write().await.skipped_files += 1;
+                continue;
+            }
+
+            let result = {
+                let db_guard = VECTOR_DATABASE.read().await;
+                let database = vault_database(db_guard.as_ref(), &request.vault_path).map_err(migration_error)?;
+                IndexingPipeline::process_file(
+                    0,
+                    &PathBuf::from(file_path),
+                    &text_chunker,
+                    embedding_provider,
+                    database,
+                    &self.cancellation_token,
+                    &request.target_model,
+                )
+                .await
+            };
+
+            let mut status = self.status.write().await;
+            match result {
+                Ok(_) => status.processed_files += 1,
+                Err(IndexingError::Cancelled) => return Err(IndexingError::Cancelled),
+                Err(e) => {
+                    status.failed_files += 1;
+                    status.record_error(e.to_string());
+                }
+            }
+        }
+
+        let failed_files = self.status.read().await.failed_files;
+        if failed_files > 0 {
+            return Err(migration_error(format!(
+                "{} files could not be embedded; the previous model stays active. Start the migration again to retry them",
+                failed_files
+            )));
+        }
+
+        // Phase 3: switch the active namespace
+        self.status.write().await.phase = MigrationPhase::Switching;
+        {
+            let db_guard = VECTOR_DATABASE.read().await;
+            let database = vault_database(db_guard.as_ref(), &request.vault_path).map_err(migration_error)?;
+            database
+                .activate_model_namespace(&target_model_id)
+                .await
+                .map_err(|e| migration_error(e.to_string()))?;
+        }
+
+        // Phase 4: drop the previous namespace
+        if let (true, Some(source_model)) = (request.drop_previous, source_model) {
+            self.status.write().await.phase = MigrationPhase::Cleanup;
+            let db_guard = VECTOR_DATABASE.read().await;
+            let database = vault_database(db_guard.as_ref(), &request.vault_path).map_err(migration_error)?;
+            let dropped = database
+                .delete_model_namespace(&source_model)
+                .await
+                .map_err(|e| migration_error(e.to_string()))?;
+            self.status.write().await.dropped_embeddings = dropped;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the global database if it belongs to the given vault
+///
+/// The migration gives up if another vault is opened while it runs.
+fn vault_database<'a>(database: Option<&'a VectorDatabase>, vault_path: &str) -> Result<&'a VectorDatabase, String> {
+    let storage_dir = VectorStorageConfig::for_vault(Path::new(vault_path)).storage_dir;
+    match database {
+        Some(database) if database.get_config().storage_dir == storage_dir => Ok(database),
+        Some(_) => Err("A different vault was opened during the model migration".to_string()),
+        None => Err("Vector database is not initialized".to_string()),
+    }
+}
+
+fn migration_error(message: String) -> IndexingError {
+    IndexingError::WorkerError { message }
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_entry(file: &str, chunk: &str, model: &str) -> EmbeddingEntry {
+        EmbeddingEntry::new(
+            vec![0.1, 0.2, 0.3],
+            file.to_string(),
+            chunk.to_string(),
+            &format!("{} {}", file, chunk),
+            model.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_plan_skips_files_already_embedded() {
+        let entries = vec![
+            create_entry("/vault/a.md", "chunk_0", "old-model"),
+            create_entry("/vault/a.md", "chunk_1", "old-model"),
+            create_entry("/vault/b.md", "chunk_0", "old-model"),
+            create_entry("/vault/c.md", "chunk_0", "old-model"),
+            create_entry("/vault/b.md", "chunk_0", "new-model"),
+            create_entry("/vault/d.md", "chunk_0", "other-model"),
+        ];
+
+        let plan = MigrationPlan::from_entries(&entries, Some("old-model"), "new-model");
+        assert_eq!(plan.files_to_embed, vec!["/vault/a.md".to_string(), "/vault/c.md".to_string()]);
+        assert_eq!(plan.already_embedded, 1);
+
+        let plan = MigrationPlan::from_entries(&entries, None, "new-model");
+        assert_eq!(plan.files_to_embed.len(), 3);
+    }
+
+    #[test]
+    fn test_status_progress() {
+        let mut status = MigrationStatus::default();
+        assert!(!status.is_running());
+        assert_eq!(status.progress_percent(), 0.0);
+
+        status.phase = MigrationPhase::Embedding;
+        status.total_files = 4;
+        status.processed_files = 2;
+        status.failed_files = 1;
+        assert!(status.is_running());
+        assert_eq!(status.progress_percent(), 75.0);
+
+        for i in 0..MAX_REPORTED_ERRORS + 5 {
+            status.record_error(format!("error {}", i));
+        }
+        assert_eq!(status.errors.len(), MAX_REPORTED_ERRORS);
+    }
+}
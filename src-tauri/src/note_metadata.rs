@@ -0,0 +1,262 @@
+//! # Note Metadata
+//!
+//! Extracts the note-level metadata that search can filter on: the fields of
+//! a YAML frontmatter block and the note's tags, taken both from the
+//! frontmatter (`tags:`) and from inline `#tags` in the body.
+//!
+//! ## Frontmatter
+//!
+//! A frontmatter block starts on the first line of the note with `---` and
+//! ends with a line containing only `---` or `...`. Besides keeping every
+//! field, a few well-known keys are interpreted:
+//!
+//! - `tags` / `tag`: a list, or a string separated by commas or spaces
+//! - `aliases` / `alias`: a list or a single string
+//! - `created` / `date`: creation date
+//! - `updated` / `modified` / `last_modified`: last update date
+//!
+//! Dates may be written as `2024-03-01`, `2024-03-01 14:30`, or RFC 3339.
+//! A malformed frontmatter block is treated as absent, so indexing never fails
+//! because of it.
+//!
+//! ## Storage
+//!
+//! `NoteMetadata::to_custom_metadata` encodes the metadata under the
+//! `vector_db::types::NOTE_METADATA_KEYS`, which the indexing pipeline stores
+//! with every chunk of the note.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+
+use crate::text_chunker::extract_inline_tags;
+use crate::vector_db::types::{
+    ALIASES_METADATA_KEY, FRONTMATTER_METADATA_KEY, NOTE_CREATED_METADATA_KEY, NOTE_UPDATED_METADATA_KEY,
+    TAGS_METADATA_KEY,
+};
+
+/// Frontmatter keys holding tags
+const TAG_KEYS: [&str; 2] = ["tags", "tag"];
+
+/// Frontmatter keys holding aliases
+const ALIAS_KEYS: [&str; 2] = ["aliases", "alias"];
+
+/// Frontmatter keys holding the creation date, by priority
+const CREATED_KEYS: [&str; 2] = ["created", "date"];
+
+/// Frontmatter keys holding the update date, by priority
+const UPDATED_KEYS: [&str; 3] = ["updated", "modified", "last_modified"];
+
+/// Metadata of a note
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteMetadata {
+    /// Frontmatter and inline tags, lowercase, without `#`, sorted and deduplicated
+    pub tags: Vec<String>,
+    /// Alternative names of the note from the frontmatter
+    pub aliases: Vec<String>,
+    /// Creation date from the frontmatter (Unix seconds)
+    pub created: Option<u64>,
+    /// Update date from the frontmatter (Unix seconds)
+    pub updated: Option<u64>,
+    /// All frontmatter fields
+    pub fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl NoteMetadata {
+    /// Extract the metadata of a note from its content
+    pub fn parse(content: &str) -> Self {
+        let (frontmatter, body) = match split_frontmatter(content) {
+            Some((yaml, body_start)) => (parse_frontmatter(yaml), &content[body_start..]),
+            None => (BTreeMap::new(), content),
+        };
+
+        let mut tags = BTreeSet::new();
+        for key in TAG_KEYS {
+            if let Some(value) = frontmatter.get(key) {
+                tags.extend(string_list(value, true).iter().map(|tag| normalize_tag(tag)));
+            }
+        }
+        tags.extend(extract_inline_tags(body).iter().map(|tag| normalize_tag(tag)));
+        tags.remove("");
+
+        let mut aliases = Vec::new();
+        for key in ALIAS_KEYS {
+            if let Some(value) = frontmatter.get(key) {
+                aliases.extend(string_list(value, false));
+            }
+        }
+
+        let first_date = |keys: &[&str]| keys.iter().find_map(|key| frontmatter.get(*key).and_then(parse_date));
+        let created = first_date(&CREATED_KEYS);
+        let updated = first_date(&UPDATED_KEYS);
+
+        let fields = frontmatter
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), serde_json::to_value(value).ok()?)))
+            .collect();
+
+        Self { tags: tags.into_iter().collect(), aliases, created, updated, fields }
+    }
+
+    /// Whether the note has no metadata at all
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.aliases.is_empty() && self.fields.is_empty()
+    }
+
+    /// Encode the metadata as embedding custom metadata
+    ///
+    /// Empty values are left out, so notes without metadata add nothing.
+    pub fn to_custom_metadata(&self) -> HashMap<String, String> {
+        let mut custom_metadata = HashMap::new();
+        let mut insert_json = |key: &str, value: serde_json::Result<String>| {
+            if let Ok(encoded) = value {
+                custom_metadata.insert(key.to_string(), encoded);
+            }
+        };
+        if !self.tags.is_empty() {
+            insert_json(TAGS_METADATA_KEY, serde_json::to_string(&self.tags));
+        }
+        if !self.aliases.is_empty() {
+            insert_json(ALIASES_METADATA_KEY, serde_json::to_string(&self.aliases));
+        }
+        if !self.fields.is_empty() {
+            insert_json(FRONTMATTER_METADATA_KEY, serde_json::to_string(&self.fields));
+        }
+        if let Some(created) = self.created {
+            custom_metadata.insert(NOTE_CREATED_METADATA_KEY.to_string(), created.to_string());
+        }
+        if let Some(updated) = self.updated {
+            custom_metadata.insert(NOTE_UPDATED_METADATA_KEY.to_string(), updated.to_string());
+        }
+        custom_metadata
+    }
+}
+
+/// Locate a note's frontmatter block
+///
+/// Returns the YAML between the delimiters and the byte offset where the
+/// body starts, or `None` if the note does not start with a frontmatter block.
+pub fn split_frontmatter(content: &str) -> Option<(&str, usize)> {
+    let first_line_end = content.find('\n')?;
+    if content[..first_line_end].trim_end() != "---" {
+        return None;
+    }
+
+    let yaml_start = first_line_end + 1;
+    let mut position = yaml_start;
+    for line in content[yaml_start..].split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some((&content[yaml_start..position], position + line.len()));
+        }
+        position += line.len();
+    }
+    None
+}
+
+/// Parse frontmatter YAML into its top-level fields
+fn parse_frontmatter(yaml: &str) -> BTreeMap<String, YamlValue> {
+    let mapping = match serde_yaml::from_str::<YamlValue>(yaml) {
+        Ok(YamlValue::Mapping(mapping)) => mapping,
+        Ok(_) => return BTreeMap::new(),
+        Err(e) => {
+            log::debug!("⚠️ Ignoring malformed frontmatter: {}", e);
+            return BTreeMap::new();
+        }
+    };
+
+    mapping
+        .into_iter()
+        .filter_map(|(key, value)| match key {
+            YamlValue::String(key) => Some((key.to_lowercase(), value)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Strings of a scalar or list value; `split` also splits strings on commas and spaces
+fn string_list(value: &YamlValue, split: bool) -> Vec<String> {
+    match value {
+        YamlValue::Sequence(items) => items.iter().flat_map(|item| string_list(item, split)).collect(),
+        YamlValue::String(text) if split => text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(str::to_string)
+            .collect(),
+        YamlValue::String(text) => vec![text.trim().to_string()].into_iter().filter(|text| !text.is_empty()).collect(),
+        YamlValue::Number(number) => vec![number.to_string()],
+        YamlValue::Bool(flag) => vec![flag.to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// Lowercase a tag and strip its `#`
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim_matches('/').to_lowercase()
+}
+
+/// Parse a frontmatter date into Unix seconds
+fn parse_date(value: &YamlValue) -> Option<u64> {
+    let text = value.as_str()?.trim();
+    let timestamp = if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+        date_time.timestamp()
+    } else if let Some(date_time) = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    {
+        date_time.and_utc().timestamp()
+    } else {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0)?.and_utc().timestamp()
+    };
+    u64::try_from(timestamp).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frontmatter_and_inline_tags() {
+        let content = "---\n\
+                       title: Weekly Review\n\
+                       tags: [Planning, '#work/reviews']\n\
+                       aliases: Review\n\
+                       created: 2024-03-01\n\
+                       updated: 2024-03-08 18:30\n\
+                       rating: 4\n\
+                       ---\n\
+                       # Week 10\n\
+                       Went well #planning #Health, see `#not-a-tag`.\n";
+
+        let metadata = NoteMetadata::parse(content);
+        assert_eq!(metadata.tags, vec!["health", "planning", "work/reviews"]);
+        assert_eq!(metadata.aliases, vec!["Review"]);
+        assert_eq!(metadata.created, Some(1_709_251_200));
+        assert_eq!(metadata.updated, Some(1_709_922_600));
+        assert_eq!(metadata.fields["title"], serde_json::json!("Weekly Review"));
+        assert_eq!(metadata.fields["rating"], serde_json::json!(4));
+
+        let custom_metadata = metadata.to_custom_metadata();
+        assert_eq!(custom_metadata[TAGS_METADATA_KEY], r#"["health","planning","work/reviews"]"#);
+        assert_eq!(custom_metadata[NOTE_UPDATED_METADATA_KEY], "1709922600");
+    }
+
+    #[test]
+    fn test_frontmatter_edge_cases() {
+        // Comma-separated tags, RFC 3339 dates and `...` as the closing delimiter
+        let metadata = NoteMetadata::parse("---\ntags: alpha, beta\nmodified: 2024-01-02T03:04:05Z\n...\nBody");
+        assert_eq!(metadata.tags, vec!["alpha", "beta"]);
+        assert_eq!(metadata.updated, Some(1_704_164_645));
+
+        // Malformed or unterminated frontmatter is ignored, inline tags still count
+        let malformed = NoteMetadata::parse("---\ntags: [unclosed\n---\n#inline");
+        assert_eq!(malformed.tags, vec!["inline"]);
+        assert!(malformed.fields.is_empty());
+        assert_eq!(split_frontmatter("---\nno end"), None);
+
+        // A horizontal rule later in the note is not frontmatter
+        assert!(NoteMetadata::parse("Intro\n---\ntags: x\n---\n").is_empty());
+        assert_eq!(split_frontmatter("---\r\na: 1\r\n---\r\nBody"), Some(("a: 1\r\n", 16)));
+    }
+}
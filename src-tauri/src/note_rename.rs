@@ -0,0 +1,351 @@
+//! # Note Rename
+//!
+//! Moves a note or a folder of notes inside a vault without breaking the
+//! vault: links pointing at the moved notes are rewritten wherever they are,
+//! relative links inside the moved notes are adjusted to their new location,
+//! and the caller re-keys the notes' embeddings instead of embedding them again.
+//!
+//! ## Consistency
+//!
+//! A rename is planned first: `RenamePlan::new` resolves which links break
+//! and computes every rewritten note in memory, so nothing is touched when a
+//! note cannot be rewritten. `RenamePlan::apply` then moves the file or folder
+//! and writes the rewritten notes, undoing its own steps if one fails. The
+//! returned `AppliedRename` can be rolled back as a whole, which callers do
+//! when the embedding migration that follows it fails.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::errors::FileSystemError;
+use crate::file_operations::rename_file_internal;
+use crate::link_graph::{rewrite_link_targets, LinkGraph, LinkGraphError, NoteLink};
+
+/// Errors that can occur while renaming notes
+#[derive(Error, Debug)]
+pub enum NoteRenameError {
+    #[error("File system error: {0}")]
+    FileSystem(#[from] FileSystemError),
+
+    #[error("Link graph error: {0}")]
+    LinkGraph(#[from] LinkGraphError),
+
+    #[error("Note changed while its links were being updated, try again: {path}")]
+    NoteChanged { path: String },
+
+    #[error("I/O error: {message}")]
+    IOError { message: String },
+}
+
+pub type NoteRenameResult<T> = Result<T, NoteRenameError>;
+
+/// A note moved by a rename
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteMove {
+    pub old_path: String,
+    pub new_path: String,
+}
+
+/// Outcome of a rename
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenameSummary {
+    /// Notes that moved; a folder rename lists every note inside it
+    pub moved_notes: Vec<NoteMove>,
+    /// Notes whose links were rewritten, at their paths after the rename
+    pub updated_files: Vec<String>,
+    /// Number of links rewritten across all notes
+    pub rewritten_links: usize,
+    /// Number of embeddings re-keyed to the new paths
+    pub migrated_embeddings: usize,
+}
+
+/// A note whose links change, with its content before and after
+#[derive(Debug, Clone)]
+struct NoteEdit {
+    /// Path after the rename
+    path: PathBuf,
+    original: String,
+    rewritten: String,
+}
+
+/// A rename whose link rewrites have been computed but not written
+#[derive(Debug, Clone)]
+pub struct RenamePlan {
+    old_path: PathBuf,
+    new_path: PathBuf,
+    moves: Vec<NoteMove>,
+    edits: Vec<NoteEdit>,
+    rewritten_links: usize,
+    /// Folders created to hold `new_path`, innermost first
+    created_dirs: Vec<PathBuf>,
+}
+
+impl RenamePlan {
+    /// Plan moving `old_path` (a note or a folder) to `new_path`
+    ///
+    /// The graph must be up to date with the notes on disk; a note whose
+    /// links are not where the graph recorded them fails the plan with
+    /// `NoteRenameError::NoteChanged`.
+    pub fn new(graph: &LinkGraph, old_path: &Path, new_path: &Path) -> NoteRenameResult<Self> {
+        let old_path = graph.vault_path().join(old_path);
+        let new_path = graph.vault_path().join(new_path);
+        if !old_path.exists() {
+            return Err(FileSystemError::FileNotFound { path: old_path.display().to_string() }.into());
+        }
+        if !graph.contains_path(&new_path) {
+            return Err(LinkGraphError::OutsideVault { path: new_path.display().to_string() }.into());
+        }
+
+        let mut note_moves = Vec::new();
+        for note in graph.notes_under(&old_path)? {
+            let note = PathBuf::from(note);
+            let moved = match note.strip_prefix(&old_path) {
+                Ok(inside) if !inside.as_os_str().is_empty() => new_path.join(inside),
+                _ => new_path.clone(),
+            };
+            note_moves.push((note, moved));
+        }
+        let destinations: HashMap<&Path, &Path> =
+            note_moves.iter().map(|(from, to)| (from.as_path(), to.as_path())).collect();
+
+        let mut rewrites_by_note: BTreeMap<String, Vec<(NoteLink, String)>> = BTreeMap::new();
+        for rewrite in graph.plan_moves(&note_moves)? {
+            rewrites_by_note
+                .entry(rewrite.source_path)
+                .or_default()
+                .push((rewrite.link, rewrite.new_target));
+        }
+
+        let mut edits = Vec::with_capacity(rewrites_by_note.len());
+        let mut rewritten_links = 0;
+        for (source, rewrites) in rewrites_by_note {
+            let source = PathBuf::from(source);
+            let original = std::fs::read_to_string(&source).map_err(|e| NoteRenameError::IOError {
+                message: format!("Failed to read {:?}: {}", source, e),
+            })?;
+            let rewritten = rewrite_link_targets(&original, &rewrites)
+                .ok_or_else(|| NoteRenameError::NoteChanged { path: source.display().to_string() })?;
+            let path = destinations.get(source.as_path()).map(|to| to.to_path_buf()).unwrap_or(source);
+            rewritten_links += rewrites.len();
+            edits.push(NoteEdit { path, original, rewritten });
+        }
+
+        let moves = note_moves
+            .into_iter()
+            .map(|(from, to)| NoteMove {
+                old_path: from.to_string_lossy().to_string(),
+                new_path: to.to_string_lossy().to_string(),
+            })
+            .collect();
+
+        Ok(Self { old_path, new_path, moves, edits, rewritten_links, created_dirs: Vec::new() })
+    }
+
+    /// Notes moved by this rename
+    pub fn moves(&self) -> &[NoteMove] {
+        &self.moves
+    }
+
+    /// Move the file or folder and write the rewritten notes
+    ///
+    /// If a note cannot be written, notes already written are restored and
+    /// the move is undone before the error is returned.
+    pub fn apply(mut self) -> NoteRenameResult<AppliedRename> {
+        // The move creates missing parent folders; remember them so a rollback removes them
+        self.created_dirs = self
+            .new_path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        if let Err(e) = rename_file_internal(&self.old_path.to_string_lossy(), &self.new_path.to_string_lossy()) {
+            remove_created_dirs(&self.created_dirs);
+            return Err(e.into());
+        }
+
+        for (written, edit) in self.edits.iter().enumerate() {
+            if let Err(e) = write_note(&edit.path, &edit.rewritten) {
+                let applied = AppliedRename { plan: self.clone_with_edits(written) };
+                if let Err(rollback_error) = applied.rollback() {
+                    log::error!("❌ Failed to undo partial rename of {:?}: {}", self.old_path, rollback_error);
+                }
+                return Err(e);
+            }
+        }
+
+        log::info!("📝 Moved {:?} to {:?}: {} notes, {} links rewritten in {} files",
+                   self.old_path, self.new_path, self.moves.len(), self.rewritten_links, self.edits.len());
+        Ok(AppliedRename { plan: self })
+    }
+
+    /// Copy of the plan limited to its first `count` edits
+    fn clone_with_edits(&self, count: usize) -> Self {
+        Self { edits: self.edits[..count].to_vec(), ..self.clone() }
+    }
+}
+
+/// A rename that happened on disk and can still be undone
+#[derive(Debug)]
+pub struct AppliedRename {
+    plan: RenamePlan,
+}
+
+impl AppliedRename {
+    /// Notes moved by this rename
+    pub fn moves(&self) -> &[NoteMove] {
+        &self.plan.moves
+    }
+
+    /// Every note path touched by the rename, before and after it
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        self.plan
+            .moves
+            .iter()
+            .flat_map(|note| [PathBuf::from(&note.old_path), PathBuf::from(&note.new_path)])
+            .chain(self.plan.edits.iter().map(|edit| edit.path.clone()))
+            .collect()
+    }
+
+    /// Restore the rewritten notes and move the file or folder back
+    pub fn rollback(self) -> NoteRenameResult<()> {
+        let mut first_error = None;
+        for edit in &self.plan.edits {
+            if let Err(e) = write_note(&edit.path, &edit.original) {
+                first_error.get_or_insert(e);
+            }
+        }
+        match std::fs::rename(&self.plan.new_path, &self.plan.old_path) {
+            Ok(()) => remove_created_dirs(&self.plan.created_dirs),
+            Err(e) => {
+                first_error.get_or_insert(NoteRenameError::IOError {
+                    message: format!("Failed to move {:?} back: {}", self.plan.new_path, e),
+                });
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => {
+                log::info!("↩️ Rolled back rename of {:?}", self.plan.old_path);
+                Ok(())
+            }
+        }
+    }
+
+    /// Summary of the rename, once the embeddings followed
+    pub fn into_summary(self, migrated_embeddings: usize) -> RenameSummary {
+        RenameSummary {
+            updated_files: self.plan.edits.iter().map(|edit| edit.path.to_string_lossy().to_string()).collect(),
+            moved_notes: self.plan.moves,
+            rewritten_links: self.plan.rewritten_links,
+            migrated_embeddings,
+        }
+    }
+}
+
+/// Remove folders a rename created, innermost first
+///
+/// A folder that is no longer empty holds files added since and is kept.
+fn remove_created_dirs(dirs: &[PathBuf]) {
+    for dir in dirs {
+        if let Err(e) = std::fs::remove_dir(dir) {
+            log::warn!("⚠️ Keeping folder {:?} created by the rename: {}", dir, e);
+            break;
+        }
+    }
+}
+
+/// Replace a note's content through a temporary file so it is never half written
+fn write_note(path: &Path, content: &str) -> NoteRenameResult<()> {
+    let temp_path = path.with_extension("md.tmp");
+    std::fs::write(&temp_path, content)
+        .and_then(|_| std::fs::rename(&temp_path, path))
+        .map_err(|e| NoteRenameError::IOError { message: format!("Failed to write {:?}: {}", path, e) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(vault: &Path, relative: &str, content: &str) {
+        let path = vault.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn read(vault: &Path, relative: &str) -> String {
+        std::fs::read_to_string(vault.join(relative)).unwrap()
+    }
+
+    #[test]
+    fn test_folder_move_rewrites_links_and_rolls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = temp_dir.path();
+        write(vault, "Home.md", "[[Projects/Alpha]] and [beta](Projects/Beta.md)\n");
+        write(vault, "Projects/Alpha.md", "Up to [home](../Home.md), across to [[Beta]]\n");
+        write(vault, "Projects/Beta.md", "[Alpha](Alpha.md#Goals)\n");
+        let graph = LinkGraph::open(vault).unwrap();
+
+        let plan = RenamePlan::new(&graph, Path::new("Projects"), Path::new("Archive/2024")).unwrap();
+        assert_eq!(plan.moves().len(), 2);
+        let applied = plan.apply().unwrap();
+
+        assert!(!vault.join("Projects").exists());
+        assert_eq!(read(vault, "Home.md"), "[[Alpha]] and [beta](Archive/2024/Beta.md)\n");
+        assert_eq!(read(vault, "Archive/2024/Alpha.md"), "Up to [home](../../Home.md), across to [[Beta]]\n");
+        // Links between notes that moved together stay as they were
+        assert_eq!(read(vault, "Archive/2024/Beta.md"), "[Alpha](Alpha.md#Goals)\n");
+
+        applied.rollback().unwrap();
+        // Folders the move created are removed again
+        assert!(!vault.join("Archive").exists());
+        assert_eq!(read(vault, "Home.md"), "[[Projects/Alpha]] and [beta](Projects/Beta.md)\n");
+        assert_eq!(read(vault, "Projects/Alpha.md"), "Up to [home](../Home.md), across to [[Beta]]\n");
+    }
+
+    #[test]
+    fn test_note_rename_summary() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = temp_dir.path();
+        write(vault, "Index.md", "See [[Old Name|the plan]] and [[Old Name#Budget]]\n");
+        write(vault, "Old Name.md", "# Budget\n");
+        let graph = LinkGraph::open(vault).unwrap();
+
+        let applied = RenamePlan::new(&graph, &vault.join("Old Name.md"), &vault.join("Plans/New Name.md"))
+            .unwrap()
+            .apply()
+            .unwrap();
+        assert_eq!(read(vault, "Index.md"), "See [[New Name|the plan]] and [[New Name#Budget]]\n");
+
+        let changed = applied.changed_paths();
+        assert!(changed.contains(&vault.join("Old Name.md")) && changed.contains(&vault.join("Index.md")));
+        let summary = applied.into_summary(3);
+        assert_eq!(summary.moved_notes, vec![NoteMove {
+            old_path: vault.join("Old Name.md").to_string_lossy().to_string(),
+            new_path: vault.join("Plans/New Name.md").to_string_lossy().to_string(),
+        }]);
+        assert_eq!(summary.updated_files, vec![vault.join("Index.md").to_string_lossy().to_string()]);
+        assert_eq!(summary.rewritten_links, 2);
+        assert_eq!(summary.migrated_embeddings, 3);
+
+        // A destination that exists is refused before anything is written
+        write(vault, "Taken.md", "");
+        let graph = LinkGraph::open(vault).unwrap();
+        let plan = RenamePlan::new(&graph, &vault.join("Plans/New Name.md"), &vault.join("Taken.md")).unwrap();
+        assert!(plan.apply().is_err());
+
+        // Rolling back keeps folders that existed before the rename
+        let applied = RenamePlan::new(&graph, &vault.join("Plans/New Name.md"), &vault.join("Plans/Drafts/New Name.md"))
+            .unwrap()
+            .apply()
+            .unwrap();
+        applied.rollback().unwrap();
+        assert!(vault.join("Plans/New Name.md").exists());
+        assert!(!vault.join("Plans/Drafts").exists());
+        assert_eq!(read(vault, "Index.md"), "See [[New Name|the plan]] and [[New Name#Budget]]\n");
+    }
+}
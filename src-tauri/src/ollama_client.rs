@@ -1,10 +1,12 @@
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
-// StreamExt is used for processing download streams
-// use futures::StreamExt; // Currently unused, but kept for future streaming features
+use futures::StreamExt;
+
+/// Upper bound for a whole streamed generation; the per-token wait is bounded by `OllamaConfig::timeout_ms`
+const MAX_GENERATION_DURATION: Duration = Duration::from_secs(30 * 60);
 
 /// Configuration for Ollama client connection
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +76,23 @@ pub struct ModelInfo {
     pub template: Option<String>,
     pub parameter_size: Option<String>,
     pub quantization_level: Option<String>,
+    /// Maximum input length in tokens, as reported by Ollama's `/api/show`
+    #[serde(default)]
+    pub context_length: Option<usize>,
+}
+
+/// Context length in tokens from an Ollama `/api/show` response
+/// 
+/// Ollama reports it in `model_info` under a key prefixed with the model's
+/// architecture, such as `nomic-bert.context_length` or `bert.context_length`.
+pub fn context_length_from_show_response(response: &serde_json::Value) -> Option<usize> {
+    response
+        .get("model_info")?
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64())
+        .map(|context_length| context_length as usize)
 }
 
 /// Model compatibility status for embedding models
@@ -171,6 +190,120 @@ impl Default for ConnectionState {
     }
 }
 
+/// Sampling options forwarded in the `options` object of generation requests
+///
+/// Unset fields are omitted so Ollama falls back to the model's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    /// Sampling temperature (higher is more creative)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Context window size in tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    /// Maximum number of tokens to generate (-1 for unlimited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+    /// Seed for reproducible output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// Sequences that end generation when produced
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+}
+
+/// Request for `/api/generate`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerateRequest {
+    pub model: String,
+    pub prompt: String,
+    /// System prompt overriding the one defined in the model file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub options: GenerationOptions,
+}
+
+/// Author of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+/// Single message of a chat conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+/// Request for `/api/chat`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatRequest {
+    pub model: String,
+    /// Conversation so far, oldest message first
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub options: GenerationOptions,
+}
+
+/// Final result of a streamed generation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationResponse {
+    pub model: String,
+    /// Concatenation of all streamed tokens
+    pub content: String,
+    /// Why generation stopped (`stop`, `length`, ...)
+    pub done_reason: Option<String>,
+    /// Number of prompt tokens evaluated
+    pub prompt_eval_count: Option<u64>,
+    /// Number of tokens generated
+    pub eval_count: Option<u64>,
+    /// Wall-clock duration measured by the client
+    pub duration_ms: u64,
+}
+
+/// One NDJSON line of a `/api/generate` or `/api/chat` stream
+#[derive(Debug, Default, Deserialize)]
+struct StreamLine {
+    #[serde(default)]
+    response: Option<String>,
+    #[serde(default)]
+    message: Option<StreamMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    content: String,
+}
+
+impl StreamLine {
+    /// Token carried by the line (`response` for generate, `message.content` for chat)
+    fn token(&self) -> &str {
+        self.response
+            .as_deref()
+            .or(self.message.as_ref().map(|message| message.content.as_str()))
+            .unwrap_or("")
+    }
+}
+
 /// Main Ollama client for service detection and health monitoring
 #[derive(Debug, Clone)]
 pub struct OllamaClient {
@@ -416,7 +549,7 @@ impl OllamaClient {
                             message: format!("Failed to parse models response: {}", e) 
                         })?;
                     
-                    let models = json.get("models")
+                    let mut models: Vec<ModelInfo> = json.get("models")
                         .and_then(|m| m.as_array())
                         .map(|arr| {
                             arr.iter()
@@ -430,12 +563,21 @@ impl OllamaClient {
                                         template: model.get("template").and_then(|t| t.as_str()).map(|s| s.to_string()),
                                         parameter_size: model.get("details").and_then(|d| d.get("parameter_size")).and_then(|p| p.as_str()).map(|s| s.to_string()),
                                         quantization_level: model.get("details").and_then(|d| d.get("quantization_level")).and_then(|q| q.as_str()).map(|s| s.to_string()),
+                                        context_length: None,
                                     })
                                 })
                                 .collect()
                         })
                         .unwrap_or_default();
 
+                    // The model list does not carry context lengths, so each model is looked up
+                    for model in &mut models {
+                        model.context_length = self.get_context_length(&model.name).await.unwrap_or_else(|e| {
+                            log::debug!("No context length for {}: {}", model.name, e);
+                            None
+                        });
+                    }
+
                     let elapsed = start_time.elapsed();
                     if elapsed > Duration::from_millis(5000) {
                         eprintln!("Warning: Model list retrieval took {:?} (target: <5s)", elapsed);
@@ -456,6 +598,33 @@ impl OllamaClient {
         }
     }
 
+    /// Get a model's context length in tokens from `/api/show`
+    ///
+    /// Returns `Ok(None)` when Ollama does not report one for the model.
+    pub async fn get_context_length(&self, model_name: &str) -> Result<Option<usize>, OllamaClientError> {
+        let show_url = format!("{}/api/show", self.config.base_url);
+        let response = self.client
+            .post(&show_url)
+            .json(&serde_json::json!({ "model": model_name }))
+            .send()
+            .await
+            .map_err(|e| OllamaClientError::NetworkError {
+                message: format!("Failed to connect to Ollama for model details: {}", e),
+                is_timeout: e.is_timeout(),
+            })?;
+        if !response.status().is_success() {
+            return Err(OllamaClientError::HttpError {
+                status_code: response.status().as_u16(),
+                message: format!("Failed to get model details: HTTP {}", response.status()),
+            });
+        }
+
+        let json: serde_json::Value = response.json().await.map_err(|e| OllamaClientError::ConfigError {
+            message: format!("Failed to parse model details: {}", e),
+        })?;
+        Ok(context_length_from_show_response(&json))
+    }
+
     /// Verify if a specific model is available and compatible
     pub async fn verify_model(&self, model_name: &str) -> Result<ModelVerificationResult, OllamaClientError> {
         let start_time = Instant::now();
@@ -709,7 +878,7 @@ impl OllamaClient {
         let mut last_progress_update = Instant::now();
 
         // Process streaming response
-        while let Some(chunk_result) = futures::StreamExt::next(&mut stream).await {
+        while let Some(chunk_result) = stream.next().await {
             let chunk = chunk_result.map_err(|e| OllamaClientError::NetworkError {
                 message: format!("Stream error during download: {}", e),
                 is_timeout: false,
@@ -875,6 +1044,239 @@ impl OllamaClient {
             )
         });
     }
+
+    // === TEXT GENERATION METHODS ===
+
+    /// Generate a completion for a prompt, streaming tokens as they arrive
+    ///
+    /// `on_token` is called for every non-empty token. Setting the `cancel`
+    /// channel to `true` aborts the request and returns `Cancelled`; the tokens
+    /// delivered so far are not rolled back.
+    pub async fn generate<F>(
+        &self,
+        request: &GenerateRequest,
+        cancel: Option<watch::Receiver<bool>>,
+        on_token: F,
+    ) -> Result<GenerationResponse, OllamaClientError>
+    where
+        F: FnMut(&str),
+    {
+        Self::validate_generation_model(&request.model)?;
+        if request.prompt.trim().is_empty() {
+            return Err(OllamaClientError::GenerationError {
+                message: "Prompt cannot be empty".to_string(),
+            });
+        }
+
+        let mut body = serde_json::json!({
+            "model": request.model,
+            "prompt": request.prompt,
+            "stream": true,
+            "options": request.options,
+        });
+        if let Some(system) = &request.system {
+            body["system"] = serde_json::Value::String(system.clone());
+        }
+
+        self.stream_completion("generate", &body, &request.model, cancel, on_token).await
+    }
+
+    /// Continue a chat conversation, streaming the assistant's reply token by token
+    ///
+    /// Cancellation and token delivery behave as in `generate`.
+    pub async fn chat<F>(
+        &self,
+        request: &ChatRequest,
+        cancel: Option<watch::Receiver<bool>>,
+        on_token: F,
+    ) -> Result<GenerationResponse, OllamaClientError>
+    where
+        F: FnMut(&str),
+    {
+        Self::validate_generation_model(&request.model)?;
+        if request.messages.is_empty() {
+            return Err(OllamaClientError::GenerationError {
+                message: "Chat requires at least one message".to_string(),
+            });
+        }
+
+        let body = serde_json::json!({
+            "model": request.model,
+            "messages": request.messages,
+            "stream": true,
+            "options": request.options,
+        });
+
+        self.stream_completion("chat", &body, &request.model, cancel, on_token).await
+    }
+
+    fn validate_generation_model(model: &str) -> Result<(), OllamaClientError> {
+        if model.trim().is_empty() {
+            return Err(OllamaClientError::GenerationError {
+                message: "Model name cannot be empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Send a streaming request and fold the NDJSON lines into a response
+    async fn stream_completion<F>(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+        model: &str,
+        mut cancel: Option<watch::Receiver<bool>>,
+        mut on_token: F,
+    ) -> Result<GenerationResponse, OllamaClientError>
+    where
+        F: FnMut(&str),
+    {
+        let start_time = Instant::now();
+        let url = format!("{}/api/{}", self.config.base_url, endpoint);
+        let response = tokio::select! {
+            _ = Self::wait_for_cancel(&mut cancel) => return Err(OllamaClientError::Cancelled),
+            response = self.open_generation_stream(&url, body) => response?,
+        };
+
+        let token_timeout = Duration::from_millis(self.config.timeout_ms);
+        let mut stream = response.bytes_stream();
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut content = String::new();
+
+        loop {
+            let next_chunk = tokio::select! {
+                _ = Self::wait_for_cancel(&mut cancel) => {
+                    log::info!("🛑 Generation with '{}' cancelled after {} chars", model, content.len());
+                    return Err(OllamaClientError::Cancelled);
+                }
+                next_chunk = tokio::time::timeout(token_timeout, stream.next()) => next_chunk,
+            };
+
+            let stream_ended = match next_chunk {
+                Err(_) => {
+                    return Err(OllamaClientError::NetworkError {
+                        message: format!("No token received from '{}' within {}ms", model, self.config.timeout_ms),
+                        is_timeout: true,
+                    });
+                }
+                Ok(Some(chunk)) => {
+                    let chunk = chunk.map_err(|e| OllamaClientError::NetworkError {
+                        message: format!("Stream error during generation: {}", e),
+                        is_timeout: e.is_timeout(),
+                    })?;
+                    buffer.extend_from_slice(&chunk);
+                    false
+                }
+                // Ollama ends every line with a newline; terminate a trailing partial line anyway
+                Ok(None) => {
+                    buffer.push(b'\n');
+                    true
+                }
+            };
+
+            while let Some(newline) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let parsed: StreamLine = serde_json::from_str(line.trim()).map_err(|e| OllamaClientError::GenerationError {
+                    message: format!("Invalid stream line from Ollama: {}", e),
+                })?;
+                if let Some(error) = parsed.error {
+                    return Err(OllamaClientError::GenerationError {
+                        message: format!("Ollama generation error: {}", error),
+                    });
+                }
+
+                let token = parsed.token();
+                if !token.is_empty() {
+                    content.push_str(token);
+                    on_token(token);
+                }
+
+                if parsed.done {
+                    let duration_ms = start_time.elapsed().as_millis() as u64;
+                    log::info!("✅ Generated {} chars with '{}' in {}ms", content.len(), model, duration_ms);
+                    return Ok(GenerationResponse {
+                        model: model.to_string(),
+                        content,
+                        done_reason: parsed.done_reason,
+                        prompt_eval_count: parsed.prompt_eval_count,
+                        eval_count: parsed.eval_count,
+                        duration_ms,
+                    });
+                }
+            }
+
+            if stream_ended {
+                return Err(OllamaClientError::GenerationError {
+                    message: "Generation stream ended before completion".to_string(),
+                });
+            }
+        }
+    }
+
+    /// Start a streaming request, retrying connection failures and server errors with backoff
+    ///
+    /// Only the request itself is retried: once tokens have been delivered a
+    /// retry would repeat them, so stream errors are returned to the caller.
+    async fn open_generation_stream(
+        &self,
+        url: &str,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, OllamaClientError> {
+        let mut retry_count = 0;
+        let mut delay_ms = self.config.initial_retry_delay_ms;
+
+        loop {
+            let error = match self.client.post(url).json(body).timeout(MAX_GENERATION_DURATION).send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let detail = response
+                        .json::<StreamLine>()
+                        .await
+                        .ok()
+                        .and_then(|line| line.error)
+                        .unwrap_or_else(|| status.to_string());
+                    let error = OllamaClientError::HttpError {
+                        status_code: status.as_u16(),
+                        message: format!("Generation request failed: {}", detail),
+                    };
+                    // Client errors such as an unknown model will not succeed on retry
+                    if !status.is_server_error() {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(e) => OllamaClientError::NetworkError {
+                    message: format!("Failed to start generation: {}", e),
+                    is_timeout: e.is_timeout(),
+                },
+            };
+
+            if retry_count >= self.config.max_retries {
+                return Err(error);
+            }
+            retry_count += 1;
+            log::warn!("⚠️ Generation request failed (attempt {}/{}): {}", retry_count, self.config.max_retries + 1, error);
+
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            delay_ms = std::cmp::min(delay_ms * 2, self.config.max_retry_delay_ms);
+        }
+    }
+
+    /// Resolve once the cancel channel is set to `true` (never without a channel)
+    async fn wait_for_cancel(cancel: &mut Option<watch::Receiver<bool>>) {
+        if let Some(receiver) = cancel {
+            if receiver.wait_for(|cancelled| *cancelled).await.is_ok() {
+                return;
+            }
+        }
+        std::future::pending::<()>().await
+    }
 }
 
 /// Errors that can occur during Ollama client operations
@@ -897,6 +1299,12 @@ pub enum OllamaClientError {
     
     #[error("Disk space error: {message}")]
     DiskSpaceError { message: String },
+    
+    #[error("Generation error: {message}")]
+    GenerationError { message: String },
+    
+    #[error("Operation cancelled")]
+    Cancelled,
 }
 
 impl From<reqwest::Error> for OllamaClientError {
@@ -1199,6 +1607,7 @@ mod tests {
             template: Some("embed".to_string()),
             parameter_size: Some("137M".to_string()),
             quantization_level: Some("f16".to_string()),
+            context_length: None,
         };
 
         let serialized = serde_json::to_string(&model_info).unwrap();
@@ -1209,6 +1618,47 @@ mod tests {
         assert_eq!(deserialized.digest, Some("sha256:123abc".to_string()));
     }
 
+    #[test]
+    fn test_context_length_from_show_response() {
+        let nomic = serde_json::json!({
+            "model_info": { "general.architecture": "nomic-bert", "nomic-bert.context_length": 2048 }
+        });
+        let mxbai = serde_json::json!({ "model_info": { "bert.context_length": 512, "bert.block_count": 24 } });
+        assert_eq!(context_length_from_show_response(&nomic), Some(2048));
+        assert_eq!(context_length_from_show_response(&mxbai), Some(512));
+        assert_eq!(context_length_from_show_response(&serde_json::json!({ "model_info": {} })), None);
+        assert_eq!(context_length_from_show_response(&serde_json::json!({ "details": {} })), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_available_models_reads_context_length() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{ "name": "nomic-embed-text:latest" }, { "name": "custom-embed" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/show"))
+            .and(body_json(serde_json::json!({ "model": "nomic-embed-text:latest" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model_info": { "nomic-bert.context_length": 2048 }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::with_config(OllamaConfig { base_url: server.uri(), ..OllamaConfig::default() });
+        let models = client.get_available_models().await.unwrap();
+        assert_eq!(models[0].context_length, Some(2048));
+        // Unknown to /api/show (404 here): no context length
+        assert_eq!(models[1].context_length, None);
+    }
+
     #[tokio::test]
     async fn test_model_compatibility_serialization() {
         let compatibilities = vec![
@@ -1240,6 +1690,7 @@ mod tests {
                 template: None,
                 parameter_size: None,
                 quantization_level: None,
+                context_length: None,
             }),
             verification_time_ms: 150,
         };
@@ -1355,6 +1806,7 @@ mod tests {
             template: Some("embed template".to_string()),
             parameter_size: Some("137M".to_string()),
             quantization_level: Some("f16".to_string()),
+            context_length: None,
         };
 
         let model_size = std::mem::size_of_val(&model_info);
@@ -1378,6 +1830,7 @@ mod tests {
             template: None,
             parameter_size: None,
             quantization_level: None,
+            context_length: None,
         };
         
         let result = ModelVerificationResult {
@@ -0,0 +1,417 @@
+//! Process Resource Sampling for aiNote
+//!
+//! This module measures what the aiNote process and the machine are actually
+//! using, so memory management and throttling decisions are based on real
+//! figures instead of self-reported allocations or placeholder values.
+//!
+//! ## Sources (Linux procfs)
+//! - `/proc/self/status`: resident set size (`VmRSS`), peak RSS (`VmHWM`) and thread count
+//! - `/proc/self/stat`: CPU ticks spent by the process (`utime` + `stime`)
+//! - `/proc/stat`: CPU ticks of the whole machine (aggregate `cpu` line)
+//! - `/proc/meminfo`: total and available system memory
+//!
+//! CPU figures are rates: each sample compares its tick counters with the
+//! previous sample's. Process and system ticks are both counted over all CPUs,
+//! so their ratio is the share of the machine used by aiNote.
+//!
+//! Samples are kept in a fixed-size ring buffer for time-series views. On
+//! platforms without procfs, sampling fails with an I/O error and callers keep
+//! their previous figures.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+/// Samples kept by default (one hour at a 5 second interval)
+pub const DEFAULT_HISTORY_CAPACITY: usize = 720;
+
+/// Process sampling errors
+#[derive(Error, Debug)]
+pub enum SamplerError {
+    #[error("Failed to read {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+
+    #[error("Malformed {path}: {message}")]
+    Parse { path: String, message: String },
+}
+
+pub type SamplerResult<T> = Result<T, SamplerError>;
+
+/// Locations of the procfs files read by the sampler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcSources {
+    /// Per-process status (`/proc/self/status`)
+    pub self_status: PathBuf,
+    /// Per-process CPU counters (`/proc/self/stat`)
+    pub self_stat: PathBuf,
+    /// System-wide CPU counters (`/proc/stat`)
+    pub system_stat: PathBuf,
+    /// System memory (`/proc/meminfo`)
+    pub meminfo: PathBuf,
+}
+
+impl Default for ProcSources {
+    fn default() -> Self {
+        Self::from_root("/proc")
+    }
+}
+
+impl ProcSources {
+    /// Sources under a procfs-like directory (`/proc`, or a fixture directory in tests)
+    pub fn from_root(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            self_status: root.join("self").join("status"),
+            self_stat: root.join("self").join("stat"),
+            system_stat: root.join("stat"),
+            meminfo: root.join("meminfo"),
+        }
+    }
+}
+
+/// Raw counters from one read of procfs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcSnapshot {
+    /// Resident set size in bytes
+    pub rss_bytes: u64,
+    /// Peak resident set size in bytes
+    pub peak_rss_bytes: u64,
+    /// Number of threads in the process
+    pub threads: u64,
+    /// CPU ticks spent by the process (user + system)
+    pub process_cpu_ticks: u64,
+    /// CPU ticks of all CPUs since boot
+    pub system_total_ticks: u64,
+    /// Idle (including I/O wait) ticks of all CPUs since boot
+    pub system_idle_ticks: u64,
+    /// Total system memory in bytes
+    pub memory_total_bytes: u64,
+    /// Memory available to new allocations in bytes
+    pub memory_available_bytes: u64,
+}
+
+impl ProcSnapshot {
+    /// Read all counters from `sources`
+    pub fn read(sources: &ProcSources) -> SamplerResult<Self> {
+        let status = read_source(&sources.self_status)?;
+        let self_stat = read_source(&sources.self_stat)?;
+        let system_stat = read_source(&sources.system_stat)?;
+        let meminfo = read_source(&sources.meminfo)?;
+
+        let required_field = |content: &str, path: &Path, key: &str| {
+            numeric_field(content, key).ok_or_else(|| parse_error(path, format!("missing {}", key)))
+        };
+        let (system_total_ticks, system_idle_ticks) = parse_system_cpu(&system_stat)
+            .ok_or_else(|| parse_error(&sources.system_stat, "missing aggregate cpu line"))?;
+
+        Ok(Self {
+            rss_bytes: required_field(&status, &sources.self_status, "VmRSS")? * 1024,
+            peak_rss_bytes: numeric_field(&status, "VmHWM").unwrap_or(0) * 1024,
+            threads: numeric_field(&status, "Threads").unwrap_or(0),
+            process_cpu_ticks: parse_process_cpu(&self_stat)
+                .ok_or_else(|| parse_error(&sources.self_stat, "missing utime/stime"))?,
+            system_total_ticks,
+            system_idle_ticks,
+            memory_total_bytes: required_field(&meminfo, &sources.meminfo, "MemTotal")? * 1024,
+            memory_available_bytes: required_field(&meminfo, &sources.meminfo, "MemAvailable")? * 1024,
+        })
+    }
+}
+
+/// Resource usage derived from a snapshot and the one before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceSample {
+    /// Sample time in milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Resident set size in MB
+    pub rss_mb: f64,
+    /// Peak resident set size in MB
+    pub peak_rss_mb: f64,
+    /// Number of threads in the process
+    pub threads: u64,
+    /// Share of total CPU capacity used by the process (0.0-1.0)
+    pub process_cpu: f64,
+    /// Busy share of total CPU capacity across the system (0.0-1.0)
+    pub system_load: f64,
+    /// Used share of system memory (0.0-1.0)
+    pub system_memory_usage: f64,
+    /// Total system memory in MB
+    #[serde(default)]
+    pub system_memory_mb: f64,
+}
+
+impl ResourceSample {
+    /// Derive usage from `current`, using `previous` for the CPU rates
+    ///
+    /// Without a previous snapshot the system load covers the time since boot
+    /// and the process CPU share is 0.
+    pub fn from_snapshots(previous: Option<&ProcSnapshot>, current: &ProcSnapshot, timestamp_ms: u64) -> Self {
+        let (process_cpu, system_load) = match previous {
+            Some(previous) => {
+                let total = current.system_total_ticks.saturating_sub(previous.system_total_ticks);
+                let idle = current.system_idle_ticks.saturating_sub(previous.system_idle_ticks);
+                let process = current.process_cpu_ticks.saturating_sub(previous.process_cpu_ticks);
+                (ratio(process, total), ratio(total.saturating_sub(idle), total))
+            }
+            None => (
+                0.0,
+                ratio(current.system_total_ticks.saturating_sub(current.system_idle_ticks), current.system_total_ticks),
+            ),
+        };
+
+        Self {
+            timestamp_ms,
+            rss_mb: current.rss_bytes as f64 / (1024.0 * 1024.0),
+            peak_rss_mb: current.peak_rss_bytes as f64 / (1024.0 * 1024.0),
+            threads: current.threads,
+            process_cpu,
+            system_load,
+            system_memory_usage: ratio(
+                current.memory_total_bytes.saturating_sub(current.memory_available_bytes),
+                current.memory_total_bytes,
+            ),
+            system_memory_mb: current.memory_total_bytes as f64 / (1024.0 * 1024.0),
+        }
+    }
+}
+
+/// Samples process and system resources and keeps a time series of them
+#[derive(Debug)]
+pub struct ProcessSampler {
+    sources: ProcSources,
+    previous: Mutex<Option<ProcSnapshot>>,
+    history: Mutex<VecDeque<ResourceSample>>,
+    history_capacity: usize,
+}
+
+impl Default for ProcessSampler {
+    fn default() -> Self {
+        Self::new(ProcSources::default(), DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl ProcessSampler {
+    /// Create a sampler reading `sources` and keeping up to `history_capacity` samples
+    pub fn new(sources: ProcSources, history_capacity: usize) -> Self {
+        Self {
+            sources,
+            previous: Mutex::new(None),
+            history: Mutex::new(VecDeque::with_capacity(history_capacity.min(DEFAULT_HISTORY_CAPACITY))),
+            history_capacity: history_capacity.max(1),
+        }
+    }
+
+    /// Read procfs, derive a sample and append it to the history
+    pub fn sample(&self) -> SamplerResult<ResourceSample> {
+        let snapshot = ProcSnapshot::read(&self.sources)?;
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let sample = {
+            let mut previous = self.previous.lock().unwrap_or_else(|e| e.into_inner());
+            let sample = ResourceSample::from_snapshots(previous.as_ref(), &snapshot, timestamp_ms);
+            *previous = Some(snapshot);
+            sample
+        };
+
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        if history.len() == self.history_capacity {
+            history.pop_front();
+        }
+        history.push_back(sample.clone());
+
+        Ok(sample)
+    }
+
+    /// Most recent sample, if any
+    pub fn latest(&self) -> Option<ResourceSample> {
+        self.history.lock().unwrap_or_else(|e| e.into_inner()).back().cloned()
+    }
+
+    /// Most recent sample if it is at most `max_age_ms` old, otherwise a new one
+    pub fn latest_or_sample(&self, max_age_ms: u64) -> SamplerResult<ResourceSample> {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        match self.latest() {
+            Some(sample) if now_ms.saturating_sub(sample.timestamp_ms) <= max_age_ms => Ok(sample),
+            _ => self.sample(),
+        }
+    }
+
+    /// Samples oldest first, limited to the most recent `limit` if given
+    pub fn history(&self, limit: Option<usize>) -> Vec<ResourceSample> {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let skip = limit.map_or(0, |limit| history.len().saturating_sub(limit));
+        history.iter().skip(skip).cloned().collect()
+    }
+
+    /// Maximum number of samples kept
+    pub fn history_capacity(&self) -> usize {
+        self.history_capacity
+    }
+}
+
+fn read_source(path: &Path) -> SamplerResult<String> {
+    fs::read_to_string(path).map_err(|source| SamplerError::Io {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+fn parse_error(path: &Path, message: impl Into<String>) -> SamplerError {
+    SamplerError::Parse {
+        path: path.display().to_string(),
+        message: message.into(),
+    }
+}
+
+/// First number of a `Key:  value [kB]` line in status and meminfo files
+fn numeric_field(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim() != key {
+            return None;
+        }
+        value.split_whitespace().next()?.parse().ok()
+    })
+}
+
+/// `utime + stime` from `/proc/self/stat`
+///
+/// The command name is parenthesized and may itself contain spaces and
+/// parentheses, so fields are counted from the last `)`.
+fn parse_process_cpu(content: &str) -> Option<u64> {
+    let (_, fields) = content.rsplit_once(')')?;
+    let mut fields = fields.split_whitespace();
+    // Fields after the name start at field 3 (state); utime and stime are fields 14 and 15
+    let utime: u64 = fields.nth(11)?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Total and idle ticks from the aggregate `cpu` line of `/proc/stat`
+fn parse_system_cpu(content: &str) -> Option<(u64, u64)> {
+    let line = content.lines().find(|line| line.split_whitespace().next() == Some("cpu"))?;
+    let ticks: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .map(|value| value.parse().ok())
+        .collect::<Option<_>>()?;
+    if ticks.len() < 4 {
+        return None;
+    }
+
+    // user nice system idle iowait irq softirq steal; guest time is already in user
+    let total = ticks.iter().take(8).sum();
+    let idle = ticks[3] + ticks.get(4).copied().unwrap_or(0);
+    Some((total, idle))
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 / whole as f64).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: &str = "test_fixtures/proc/t0";
+    const T1: &str = "test_fixtures/proc/t1";
+
+    fn copy_fixture(from: &str, to: &Path) {
+        fs::create_dir_all(to.join("self")).unwrap();
+        for file in ["self/status", "self/stat", "stat", "meminfo"] {
+            fs::copy(Path::new(from).join(file), to.join(file)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_snapshot_parses_fixture() {
+        let snapshot = ProcSnapshot::read(&ProcSources::from_root(T0)).unwrap();
+
+        assert_eq!(snapshot.rss_bytes, 131072 * 1024);
+        assert_eq!(snapshot.peak_rss_bytes, 150000 * 1024);
+        assert_eq!(snapshot.threads, 12);
+        // Command name "ainote (dev) worker" contains spaces and parentheses
+        assert_eq!(snapshot.process_cpu_ticks, 1500 + 500);
+        assert_eq!(snapshot.system_total_ticks, 94500);
+        assert_eq!(snapshot.system_idle_ticks, 81000);
+        assert_eq!(snapshot.memory_total_bytes, 16384000 * 1024);
+        assert_eq!(snapshot.memory_available_bytes, 8192000 * 1024);
+    }
+
+    #[test]
+    fn test_sample_rates_between_snapshots() {
+        let t0 = ProcSnapshot::read(&ProcSources::from_root(T0)).unwrap();
+        let t1 = ProcSnapshot::read(&ProcSources::from_root(T1)).unwrap();
+
+        let first = ResourceSample::from_snapshots(None, &t0, 1);
+        assert_eq!(first.process_cpu, 0.0);
+        assert!((first.system_load - 13500.0 / 94500.0).abs() < 1e-9);
+        assert!((first.rss_mb - 128.0).abs() < 1e-9);
+        assert!((first.system_memory_usage - 0.5).abs() < 1e-9);
+
+        // 400 of 2000 ticks for the process, 1000 idle
+        let second = ResourceSample::from_snapshots(Some(&t0), &t1, 2);
+        assert!((second.process_cpu - 0.2).abs() < 1e-9);
+        assert!((second.system_load - 0.5).abs() < 1e-9);
+        assert!((second.rss_mb - 192.0).abs() < 1e-9);
+        assert!((second.system_memory_usage - 0.75).abs() < 1e-9);
+
+        // Counters that did not move (or went backwards) give zero rather than garbage
+        let idle = ResourceSample::from_snapshots(Some(&t1), &t0, 3);
+        assert_eq!((idle.process_cpu, idle.system_load), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_sampler_keeps_ring_buffer() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        copy_fixture(T0, temp_dir.path());
+        let sampler = ProcessSampler::new(ProcSources::from_root(temp_dir.path()), 2);
+        assert!(sampler.latest().is_none());
+
+        sampler.sample().unwrap();
+        copy_fixture(T1, temp_dir.path());
+        let second = sampler.sample().unwrap();
+        assert!((second.process_cpu - 0.2).abs() < 1e-9);
+        assert_eq!(sampler.latest(), Some(second.clone()));
+
+        // Oldest sample is dropped once the buffer is full
+        sampler.sample().unwrap();
+        let history = sampler.history(None);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0], second);
+        assert_eq!(history[1].process_cpu, 0.0);
+        assert_eq!(sampler.history(Some(1)).len(), 1);
+
+        // A fresh sample is reused instead of re-reading procfs
+        let cached = sampler.latest_or_sample(60_000).unwrap();
+        assert_eq!(cached, history[1]);
+        assert_eq!(sampler.history(None).len(), 2);
+    }
+
+    #[test]
+    fn test_sampler_errors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let sampler = ProcessSampler::new(ProcSources::from_root(temp_dir.path()), 4);
+        assert!(matches!(sampler.sample(), Err(SamplerError::Io { .. })));
+
+        copy_fixture(T0, temp_dir.path());
+        fs::write(temp_dir.path().join("stat"), "intr 0\n").unwrap();
+        assert!(matches!(sampler.sample(), Err(SamplerError::Parse { .. })));
+        assert!(sampler.history(None).is_empty());
+    }
+}
@@ -0,0 +1,445 @@
+//! # Retrieval-Augmented Generation
+//!
+//! Answers questions about the vault with an Ollama chat model, grounded in
+//! the note chunks most similar to the question. This module holds the
+//! retrieval-independent parts of the pipeline: packing retrieved chunks into
+//! a context that fits a token budget, building the prompt and turning the
+//! sources into citations. The `ask_notes` command wires it to the vector
+//! database and the generation client.
+//!
+//! ## Pipeline
+//!
+//! 1. **Retrieval**: Embed the question and fetch the top-k chunks of the
+//!    active embedding model, ranked like `semantic_search` (hybrid BM25 and
+//!    HNSW ranking)
+//! 2. **Expansion**: Optionally replace each chunk by the paragraph or section
+//!    enclosing it, for vaults indexed with the chunk hierarchy
+//! 3. **Packing**: Keep chunks in rank order, at most `max_chunks_per_file`
+//!    per file, without duplicate text, until the token budget is used up
+//! 4. **Generation**: Send the numbered sources and the question to the chat
+//!    model, which cites sources as `[n]`
+//! 5. **Citations**: Report every source with its file and byte span, flagged
+//!    with whether the answer cited it
+//!
+//! ## Chunk Text
+//!
+//! Embeddings only store a short preview of their chunk. The indexing pipeline
+//! records each chunk's byte span, so the full text is read back from the
+//! note and verified against the stored text hash. When the note changed
+//! since it was indexed, the preview is used instead.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::ollama_client::{ChatMessage, ChatRole, GenerationOptions};
+use crate::similarity_search::{SearchFilter, SearchResult};
+use crate::text_chunker::ChunkLevel;
+use crate::vector_db::types::{EmbeddingEntry, EmbeddingMetadata};
+
+/// Default chat model used to answer questions
+pub const DEFAULT_RAG_CHAT_MODEL: &str = "llama3.2";
+
+/// Approximate number of characters per token used for budgeting
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Instructions given to the chat model
+const RAG_SYSTEM_PROMPT: &str = "You answer questions about the user's personal notes. \
+Use only the numbered sources provided with the question. Cite the sources you rely on \
+with their number in square brackets, for example [1] or [2][3]. If the sources do not \
+contain the answer, say that the notes do not cover it instead of guessing.";
+
+/// Answer returned when retrieval finds no relevant chunks
+pub const NO_CONTEXT_ANSWER: &str = "I couldn't find anything in your notes that answers this question.";
+
+/// Options for answering a question over the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RagOptions {
+    /// Vault to search; opens the vault's vector database when provided
+    pub vault_path: Option<String>,
+    /// Chat model that writes the answer
+    pub chat_model: String,
+    /// Embedding model override (defaults to the database's active model and
+    /// must match it once the database holds embeddings)
+    pub embedding_model: Option<String>,
+    /// Number of chunks retrieved before packing
+    pub top_k: usize,
+    /// Maximum number of chunks taken from a single file
+    pub max_chunks_per_file: usize,
+    /// Minimum similarity score for a chunk to be used (-1.0 to 1.0);
+    /// defaults to the vault config's `search.min_threshold`
+    pub min_score: Option<f32>,
+    /// Token budget for the sources included in the prompt
+    pub max_context_tokens: usize,
+    /// Expand retrieved chunks to their enclosing chunk at this level
+    /// (paragraph or section) before packing; flat chunks are kept as they are
+    pub expand_to: Option<ChunkLevel>,
+    /// Sampling options for the chat model
+    pub generation: GenerationOptions,
+    /// Restrict retrieval to notes with matching tags, folder, dates or
+    /// frontmatter fields (a relative folder is resolved against `vault_path`)
+    pub filter: SearchFilter,
+}
+
+impl Default for RagOptions {
+    fn default() -> Self {
+        Self {
+            vault_path: None,
+            chat_model: DEFAULT_RAG_CHAT_MODEL.to_string(),
+            embedding_model: None,
+            top_k: 12,
+            max_chunks_per_file: 2,
+            min_score: None,
+            max_context_tokens: 2048,
+            expand_to: Some(ChunkLevel::Paragraph),
+            generation: GenerationOptions::default(),
+            filter: SearchFilter::default(),
+        }
+    }
+}
+
+/// A chunk selected as a source for the answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextChunk {
+    /// Path of the note the chunk belongs to
+    pub file_path: String,
+    /// Chunk identifier within the note
+    pub chunk_id: String,
+    /// Byte span of the chunk in the note, when known
+    pub span: Option<(usize, usize)>,
+    /// Markdown headings enclosing the chunk (outermost first)
+    pub heading_path: Vec<String>,
+    /// Similarity between the question and the chunk
+    pub score: f32,
+    /// Text given to the model
+    pub text: String,
+}
+
+/// A source of an answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RagCitation {
+    /// Source number used in the prompt and answer (`[index]`)
+    pub index: usize,
+    /// Path of the note
+    pub file_path: String,
+    /// Chunk identifier within the note
+    pub chunk_id: String,
+    /// Byte offset where the chunk starts, when known
+    pub start_position: Option<usize>,
+    /// Byte offset where the chunk ends, when known
+    pub end_position: Option<usize>,
+    /// Markdown headings enclosing the chunk (outermost first)
+    pub heading_path: Vec<String>,
+    /// Similarity between the question and the chunk
+    pub score: f32,
+    /// Whether the answer refers to this source
+    pub cited: bool,
+}
+
+/// Answer to a question over the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagAnswer {
+    /// The original question
+    pub question: String,
+    /// Generated answer with `[n]` source markers
+    pub answer: String,
+    /// Sources given to the model, in prompt order
+    pub citations: Vec<RagCitation>,
+    /// Chat model that wrote the answer
+    pub model: String,
+    /// Embedding model used for retrieval
+    pub embedding_model: String,
+    /// Estimated tokens of the packed sources
+    pub context_tokens: usize,
+    /// Time spent embedding the question and retrieving chunks in milliseconds
+    pub retrieval_time_ms: f64,
+    /// Time spent generating the answer in milliseconds
+    pub generation_time_ms: f64,
+}
+
+/// Estimate the number of tokens in a text
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Select the chunks given to the model
+///
+/// Hits are taken in retrieval rank order. A file contributes at most
+/// `max_chunks_per_file` chunks, chunks whose text was already included are
+/// skipped, and chunks that would exceed `max_context_tokens` are left out
+/// while smaller ones may still fit. `load_text` supplies each chunk's text.
+pub fn pack_context<F>(
+    hits: Vec<SearchResult>,
+    max_chunks_per_file: usize,
+    max_context_tokens: usize,
+    mut load_text: F,
+) -> Vec<ContextChunk>
+where
+    F: FnMut(&EmbeddingEntry) -> String,
+{
+    let mut chunks_per_file: HashMap<String, usize> = HashMap::new();
+    let mut seen_texts: HashSet<String> = HashSet::new();
+    let mut used_tokens = 0;
+    let mut packed = Vec::new();
+
+    for hit in hits {
+        let metadata = &hit.entry.metadata;
+        let file_count = chunks_per_file.get(&metadata.file_path).copied().unwrap_or(0);
+        if file_count >= max_chunks_per_file || seen_texts.contains(&metadata.text_hash) {
+            continue;
+        }
+
+        let text = load_text(&hit.entry);
+        let tokens = estimate_tokens(&text);
+        if text.trim().is_empty() || used_tokens + tokens > max_context_tokens {
+            continue;
+        }
+
+        used_tokens += tokens;
+        seen_texts.insert(metadata.text_hash.clone());
+        chunks_per_file.insert(metadata.file_path.clone(), file_count + 1);
+        packed.push(ContextChunk {
+            file_path: metadata.file_path.clone(),
+            chunk_id: metadata.chunk_id.clone(),
+            span: metadata.chunk_span(),
+            heading_path: metadata.heading_path(),
+            score: hit.similarity,
+            text,
+        });
+    }
+
+    packed
+}
+
+/// Read a chunk's full text from its note
+///
+/// Notes are read once and cached in `notes`. Falls back to the stored
+/// content preview when the span is unknown or the note no longer contains
+/// the indexed text at that span.
+pub fn load_chunk_text(entry: &EmbeddingEntry, notes: &mut HashMap<String, Option<String>>) -> String {
+    let metadata = &entry.metadata;
+    let note = notes
+        .entry(metadata.file_path.clone())
+        .or_insert_with(|| std::fs::read_to_string(Path::new(&metadata.file_path)).ok());
+
+    note.as_deref()
+        .and_then(|content| chunk_text_from_note(metadata, content))
+        .unwrap_or_else(|| metadata.content_preview.clone())
+}
+
+/// Cut a chunk out of its note if the note still matches the indexed text
+fn chunk_text_from_note(metadata: &EmbeddingMetadata, content: &str) -> Option<String> {
+    let (start, end) = metadata.chunk_span()?;
+    let text = content.get(start..end)?;
+    (EmbeddingMetadata::compute_text_hash(text) == metadata.text_hash).then(|| text.to_string())
+}
+
+/// Build the chat messages for a question and its sources
+pub fn build_messages(question: &str, context: &[ContextChunk]) -> Vec<ChatMessage> {
+    let mut prompt = String::from("Sources:\n\n");
+    for (index, chunk) in context.iter().enumerate() {
+        prompt.push_str(&format!("[{}] {}", index + 1, chunk.file_path));
+        if !chunk.heading_path.is_empty() {
+            prompt.push_str(&format!(" › {}", chunk.heading_path.join(" › ")));
+        }
+        prompt.push('\n');
+        prompt.push_str(chunk.text.trim());
+        prompt.push_str("\n\n");
+    }
+    prompt.push_str(&format!("Question: {}", question.trim()));
+
+    vec![
+        ChatMessage { role: ChatRole::System, content: RAG_SYSTEM_PROMPT.to_string() },
+        ChatMessage { role: ChatRole::User, content: prompt },
+    ]
+}
+
+/// Turn the sources into citations, flagging the ones the answer refers to
+pub fn build_citations(answer: &str, context: &[ContextChunk]) -> Vec<RagCitation> {
+    let cited = cited_source_numbers(answer);
+    context
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| RagCitation {
+            index: index + 1,
+            file_path: chunk.file_path.clone(),
+            chunk_id: chunk.chunk_id.clone(),
+            start_position: chunk.span.map(|(start, _)| start),
+            end_position: chunk.span.map(|(_, end)| end),
+            heading_path: chunk.heading_path.clone(),
+            score: chunk.score,
+            cited: cited.contains(&(index + 1)),
+        })
+        .collect()
+}
+
+/// Collect the source numbers referenced as `[n]` or `[n, m]` in an answer
+fn cited_source_numbers(answer: &str) -> HashSet<usize> {
+    let mut numbers = HashSet::new();
+    let mut rest = answer;
+    while let Some(open) = rest.find('[') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find(']') else { break };
+        let inner = &rest[..close];
+        let parsed: Option<Vec<usize>> = inner.split(',').map(|part| part.trim().parse().ok()).collect();
+        if let Some(parsed) = parsed {
+            numbers.extend(parsed);
+        }
+    }
+    numbers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::types::{CHUNK_SPAN_METADATA_KEY, HEADING_PATH_METADATA_KEY};
+
+    fn hit(file_path: &str, chunk_id: &str, text: &str, similarity: f32) -> SearchResult {
+        let entry = EmbeddingEntry::new(
+            vec![0.1, 0.2, 0.3],
+            file_path.to_string(),
+            chunk_id.to_string(),
+            text,
+            "test-model".to_string(),
+        );
+        SearchResult { entry, similarity }
+    }
+
+    fn full_text(entry: &EmbeddingEntry) -> String {
+        entry.metadata.content_preview.clone()
+    }
+
+    #[test]
+    fn test_pack_context_keeps_rank_order_limits_and_dedupes() {
+        // Hybrid ranking can put a lower similarity first
+        let hits = vec![
+            hit("a.md", "a2", "Backups go to the NAS", 0.9),
+            hit("c.md", "c1", "Restore with rsync", 0.6),
+            hit("b.md", "b1", "Backups go to the NAS", 0.85),
+            hit("a.md", "a3", "Backups are encrypted", 0.8),
+            hit("a.md", "a1", "Backups run nightly", 0.7),
+        ];
+
+        let packed = pack_context(hits, 2, 1000, full_text);
+        let ids: Vec<&str> = packed.iter().map(|chunk| chunk.chunk_id.as_str()).collect();
+        // b1 repeats a2's text and a.md is capped at two chunks
+        assert_eq!(ids, vec!["a2", "c1", "a3"]);
+    }
+
+    #[test]
+    fn test_pack_context_respects_token_budget() {
+        let long_text = "word ".repeat(100);
+        let hits = vec![
+            hit("a.md", "long", &long_text, 0.9),
+            hit("b.md", "short", "Short note", 0.8),
+        ];
+
+        let packed = pack_context(hits.clone(), 2, 20, full_text);
+        assert_eq!(packed.len(), 1);
+        assert_eq!(packed[0].chunk_id, "short");
+
+        assert!(pack_context(hits, 2, 0, full_text).is_empty());
+    }
+
+    #[test]
+    fn test_load_chunk_text_verifies_span() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let note_path = temp_dir.path().join("note.md");
+        let chunk = "This chunk is longer than the stored content preview, which only keeps the first hundred characters of the text.";
+        std::fs::write(&note_path, format!("# Title\n\n{}\n", chunk)).unwrap();
+
+        let mut entry = EmbeddingEntry::new(
+            vec![0.1, 0.2, 0.3],
+            note_path.to_string_lossy().to_string(),
+            "chunk".to_string(),
+            chunk,
+            "test-model".to_string(),
+        );
+        entry.metadata.custom_metadata.insert(
+            CHUNK_SPAN_METADATA_KEY.to_string(),
+            EmbeddingMetadata::encode_chunk_span(9, 9 + chunk.len()),
+        );
+
+        let mut notes = HashMap::new();
+        assert_eq!(load_chunk_text(&entry, &mut notes), chunk);
+
+        // A note edited since indexing falls back to the preview
+        std::fs::write(&note_path, format!("# Changed title\n\n{}\n", chunk)).unwrap();
+        let mut notes = HashMap::new();
+        assert_eq!(load_chunk_text(&entry, &mut notes), entry.metadata.content_preview);
+    }
+
+    #[test]
+    fn test_expanded_hits_load_parent_text() {
+        use crate::similarity_search::expand_hits_to_parents;
+        use crate::text_chunker::{ChunkConfig, ChunkProcessor};
+        use crate::vector_db::types::PARENT_CHUNKS_METADATA_KEY;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let note_path = temp_dir.path().join("note.md");
+        let note = "# Backups\n\nBackups run nightly. They go to the NAS. Old copies expire. The NAS is encrypted.\n\nRestores use rsync.\n";
+        std::fs::write(&note_path, note).unwrap();
+
+        let config = ChunkConfig { hierarchy: crate::text_chunker::HierarchyConfig { enabled: true, ..Default::default() }, ..ChunkConfig::default() };
+        let hierarchy = ChunkProcessor::new(config).unwrap().chunk_hierarchy(note).unwrap();
+        let hits: Vec<SearchResult> = hierarchy
+            .windows
+            .iter()
+            .map(|window| {
+                let mut hit = hit(&note_path.to_string_lossy(), &window.metadata.chunk_id, &window.content, 0.9);
+                hit.entry.metadata.custom_metadata.insert(
+                    PARENT_CHUNKS_METADATA_KEY.to_string(),
+                    EmbeddingMetadata::encode_parent_chunks(&window.metadata.parents).unwrap(),
+                );
+                hit
+            })
+            .collect();
+        assert_eq!(hits.len(), 3);
+
+        // Both windows of the first paragraph expand to it once
+        let paragraphs = expand_hits_to_parents(hits.clone(), ChunkLevel::Paragraph);
+        assert_eq!(paragraphs.len(), 2);
+        let mut notes = HashMap::new();
+        assert_eq!(load_chunk_text(&paragraphs[0].entry, &mut notes), hierarchy.paragraphs[0].content);
+        assert_eq!(paragraphs[0].entry.metadata.parent_id(), Some(hierarchy.sections[0].metadata.chunk_id.clone()));
+
+        let sections = expand_hits_to_parents(hits, ChunkLevel::Section);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(load_chunk_text(&sections[0].entry, &mut notes), note.trim_end());
+    }
+
+    #[test]
+    fn test_prompt_and_citations() {
+        let mut cited_hit = hit("notes/backup.md", "b1", "Backups go to the NAS", 0.9);
+        cited_hit.entry.metadata.custom_metadata.insert(
+            HEADING_PATH_METADATA_KEY.to_string(),
+            EmbeddingMetadata::encode_heading_path(&["Setup".to_string(), "Backups".to_string()]).unwrap(),
+        );
+        cited_hit.entry.metadata.custom_metadata.insert(
+            CHUNK_SPAN_METADATA_KEY.to_string(),
+            EmbeddingMetadata::encode_chunk_span(10, 31),
+        );
+        let hits = vec![cited_hit, hit("notes/other.md", "o1", "Unrelated", 0.5)];
+        let context = pack_context(hits, 2, 1000, full_text);
+
+        let messages = build_messages("Where do backups go?", &context);
+        assert_eq!(messages[0].role, ChatRole::System);
+        assert!(messages[1].content.contains("[1] notes/backup.md › Setup › Backups\nBackups go to the NAS"));
+        assert!(messages[1].content.contains("[2] notes/other.md\nUnrelated"));
+        assert!(messages[1].content.ends_with("Question: Where do backups go?"));
+
+        let citations = build_citations("They go to the NAS [1].", &context);
+        assert_eq!(citations.len(), 2);
+        assert!(citations[0].cited);
+        assert_eq!((citations[0].start_position, citations[0].end_position), (Some(10), Some(31)));
+        assert!(!citations[1].cited);
+        assert_eq!(citations[1].start_position, None);
+    }
+
+    #[test]
+    fn test_cited_source_numbers() {
+        let numbers = cited_source_numbers("First [1], then [2, 3] and [see notes] [4");
+        assert_eq!(numbers, HashSet::from([1, 2, 3]));
+    }
+}
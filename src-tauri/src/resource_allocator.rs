@@ -28,6 +28,7 @@ use thiserror::Error;
 use rayon::ThreadPoolBuilder;
 
 use crate::performance::PerformanceTracker;
+use crate::process_sampler::ProcessSampler;
 
 /// Resource allocation errors
 #[derive(Error, Debug, Clone)]
@@ -101,6 +102,13 @@ pub struct ResourceAllocatorConfig {
     pub io_scheduling_enabled: bool,
     /// Background task limit per priority level
     pub background_task_limits: HashMap<OperationPriority, usize>,
+    /// Interval between CPU load samples in milliseconds
+    #[serde(default = "default_sampling_interval_ms")]
+    pub sampling_interval_ms: u64,
+}
+
+fn default_sampling_interval_ms() -> u64 {
+    1000
 }
 
 impl Default for ResourceAllocatorConfig {
@@ -120,6 +128,7 @@ impl Default for ResourceAllocatorConfig {
             cpu_throttling_enabled: true,
             io_scheduling_enabled: true,
             background_task_limits,
+            sampling_interval_ms: default_sampling_interval_ms(),
         }
     }
 }
@@ -127,7 +136,7 @@ impl Default for ResourceAllocatorConfig {
 /// Resource usage metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceMetrics {
-    /// Current CPU usage (0.0-1.0)
+    /// Share of total CPU capacity used by aiNote (0.0-1.0)
     pub cpu_usage: f64,
     /// Active thread count by priority
     pub active_threads: HashMap<OperationPriority, usize>,
@@ -137,7 +146,7 @@ pub struct ResourceMetrics {
     pub avg_io_latency_ms: f64,
     /// Operations throttled in last minute
     pub throttled_operations: usize,
-    /// Current system load
+    /// Busy share of total CPU capacity across the system, used for throttling (0.0-1.0)
     pub system_load: f64,
     /// Timestamp of metrics collection
     pub timestamp: u64,
@@ -306,14 +315,15 @@ impl IoScheduler {
 /// Main resource allocator
 pub struct ResourceAllocator {
     config: ResourceAllocatorConfig,
-    cpu_manager: CpuPriorityManager,
+    cpu_manager: Arc<CpuPriorityManager>,
     io_scheduler: IoScheduler,
     background_tasks: Arc<RwLock<HashMap<String, PriorityTask>>>,
     thread_pool: Arc<rayon::ThreadPool>,
     ai_semaphore: Arc<Semaphore>,
     metrics: Arc<RwLock<ResourceMetrics>>,
     performance_tracker: Arc<PerformanceTracker>,
-    is_active: AtomicBool,
+    process_sampler: Arc<ProcessSampler>,
+    is_active: Arc<AtomicBool>,
 }
 
 impl ResourceAllocator {
@@ -327,7 +337,7 @@ impl ResourceAllocator {
                 resource: format!("Thread pool: {}", e) 
             })?;
         
-        let cpu_manager = CpuPriorityManager::new(performance_tracker.clone());
+        let cpu_manager = Arc::new(CpuPriorityManager::new(performance_tracker.clone()));
         let io_scheduler = IoScheduler::new(config.clone());
         let ai_semaphore = Arc::new(Semaphore::new(config.max_ai_operations));
         
@@ -353,10 +363,17 @@ impl ResourceAllocator {
             ai_semaphore,
             metrics: Arc::new(RwLock::new(metrics)),
             performance_tracker,
-            is_active: AtomicBool::new(false),
+            process_sampler: Arc::new(ProcessSampler::default()),
+            is_active: Arc::new(AtomicBool::new(false)),
         })
     }
     
+    /// Use a shared process sampler instead of a private one
+    pub fn with_process_sampler(mut self, process_sampler: Arc<ProcessSampler>) -> Self {
+        self.process_sampler = process_sampler;
+        self
+    }
+    
     /// Start resource allocation system
     pub async fn start(&self) -> ResourceResult<()> {
         if self.is_active.load(Ordering::Relaxed) {
@@ -365,11 +382,26 @@ impl ResourceAllocator {
         
         self.is_active.store(true, Ordering::Relaxed);
         
-        // Start background monitoring task
-        let _allocator_weak = Arc::downgrade(&Arc::new(self));
+        // Start CPU load sampling task
+        let cpu_manager = Arc::clone(&self.cpu_manager);
+        let process_sampler = Arc::clone(&self.process_sampler);
+        let is_active = Arc::clone(&self.is_active);
+        let sampling_interval = Duration::from_millis(self.config.sampling_interval_ms.max(100));
         tokio::spawn(async move {
-            // This would be a monitoring loop, but we need to avoid circular references
-            // Implementation would go in the monitoring task method
+            let mut interval = tokio::time::interval(sampling_interval);
+            
+            while is_active.load(Ordering::Relaxed) {
+                interval.tick().await;
+                
+                match process_sampler.sample() {
+                    Ok(sample) => cpu_manager.update_cpu_load(sample.system_load),
+                    Err(e) => {
+                        // Without procfs the load stays at its last value and throttling is unchanged
+                        eprintln!("⚠️ CPU load sampling unavailable: {}", e);
+                        break;
+                    }
+                }
+            }
         });
         
         Ok(())
@@ -467,11 +499,14 @@ impl ResourceAllocator {
     
     /// Get current resource metrics
     pub async fn get_metrics(&self) -> ResourceMetrics {
-        // Update CPU load
-        self.cpu_manager.update_cpu_load(0.3); // Placeholder - would integrate with system monitoring
-        
         let mut metrics = self.metrics.write().await;
-        metrics.cpu_usage = self.cpu_manager.get_cpu_load();
+        
+        // Update CPU load unless the sampling task has a recent sample
+        if let Ok(sample) = self.process_sampler.latest_or_sample(self.config.sampling_interval_ms) {
+            self.cpu_manager.update_cpu_load(sample.system_load);
+            metrics.cpu_usage = sample.process_cpu;
+        }
+        metrics.system_load = self.cpu_manager.get_cpu_load();
         
         // Update active thread counts
         let tasks = self.background_tasks.read().await;
@@ -495,7 +530,7 @@ impl ResourceAllocator {
     /// Check if system is under resource pressure
     pub async fn is_under_pressure(&self) -> bool {
         let metrics = self.get_metrics().await;
-        metrics.cpu_usage > self.config.max_cpu_threshold || 
+        metrics.system_load > self.config.max_cpu_threshold || 
         metrics.avg_io_latency_ms > self.config.io_timeout_ms as f64
     }
     
@@ -580,6 +615,43 @@ mod tests {
         assert!(result.is_ok());
     }
     
+    #[tokio::test]
+    async fn test_metrics_use_sampled_cpu_load() {
+        use crate::process_sampler::ProcSources;
+        use tempfile::TempDir;
+        
+        // Copy the fixture so the second snapshot can replace the first
+        let temp_dir = TempDir::new().unwrap();
+        let copy_snapshot = |name: &str| {
+            for file in ["self/status", "self/stat", "stat", "meminfo"] {
+                let target = temp_dir.path().join(file);
+                std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+                std::fs::copy(format!("test_fixtures/proc/{}/{}", name, file), target).unwrap();
+            }
+        };
+        
+        let sampler = Arc::new(ProcessSampler::new(ProcSources::from_root(temp_dir.path()), 4));
+        let config = ResourceAllocatorConfig {
+            max_cpu_threshold: 0.4,
+            ..Default::default()
+        };
+        let allocator = ResourceAllocator::new(config, Arc::new(PerformanceTracker::start("test")))
+            .unwrap()
+            .with_process_sampler(Arc::clone(&sampler));
+        
+        copy_snapshot("t0");
+        sampler.sample().unwrap();
+        copy_snapshot("t1");
+        sampler.sample().unwrap();
+        
+        // The recent sample is reused: 20% of the machine for aiNote, 50% system load
+        let metrics = allocator.get_metrics().await;
+        assert!((metrics.cpu_usage - 0.2).abs() < 1e-9);
+        assert!((metrics.system_load - 0.5).abs() < 1e-9);
+        assert!((allocator.cpu_manager.get_cpu_load() - 0.5).abs() < 1e-6);
+        assert!(allocator.is_under_pressure().await);
+    }
+    
     #[tokio::test]
     async fn test_ai_permit_acquisition() {
         let allocator = create_test_allocator();
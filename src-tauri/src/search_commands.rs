@@ -36,7 +36,7 @@ use serde::{Serialize, Deserialize};
 use thiserror::Error;
 
 // Import core functionality
-use crate::similarity_search::{SimilaritySearch, SearchConfig, SearchResult, SimilarityError};
+use crate::similarity_search::{SimilaritySearch, SearchConfig, SearchFilter, SearchResult, SimilarityError};
 use crate::vector_db::VectorDatabase;
 use crate::vector_db::types::{EmbeddingEntry, VectorDbError};
 
@@ -424,16 +424,14 @@ impl SearchEngine {
         // Get vector database
         let vector_db = self.get_vector_db()?;
         
-        // Get all embeddings from database
-        let all_embedding_ids = vector_db.list_embedding_ids().await;
-        
-        if all_embedding_ids.is_empty() {
+        if vector_db.is_empty().await {
             return Err(SearchCommandError::EmptyDatabase);
         }
         
-        // Retrieve all embeddings
+        // Retrieve the embeddings of the active model; vectors from other
+        // models are not comparable with the query
         let all_embeddings = vector_db
-            .retrieve_embeddings(&all_embedding_ids)
+            .active_namespace_entries()
             .await
             .map_err(SearchCommandError::from)?;
         
@@ -463,6 +461,10 @@ impl SearchEngine {
             recency_weight: 0.0,
             exclude_current_file: None,
             exclude_recent_suggestions: Vec::new(),
+            vector_weight: 1.0,
+            lexical_weight: 1.0,
+            rrf_k: 60.0,
+            filter: SearchFilter::default(),
         };
         
         // Perform similarity search with timeout
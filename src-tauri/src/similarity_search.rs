@@ -50,8 +50,9 @@
 //! - **Memory Usage:** O(k) for result storage plus input vectors
 //! - **Target Performance:** Single vector comparison <1ms, 1000 vectors <50ms
 
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::cmp::Ordering;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
@@ -60,7 +61,11 @@ use tokio::sync::{Semaphore, RwLock as AsyncRwLock};
 use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
+use crate::text_chunker::ChunkLevel;
 use crate::vector_db::types::EmbeddingEntry;
+use crate::vector_db::compression::{f16_bits_to_f32, QuantizedVectorRef};
+use crate::vector_db::hnsw::{HnswConfig, HnswIndex};
+use once_cell::sync::Lazy;
 
 /// Errors that can occur during similarity search operations
 #[derive(Error, Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +91,9 @@ pub enum SimilarityError {
 
 pub type SimilarityResult<T> = Result<T, SimilarityError>;
 
+/// Valid similarity thresholds (cosine similarity ranges from -1.0 to 1.0)
+pub const SIMILARITY_THRESHOLD_RANGE: std::ops::RangeInclusive<f32> = -1.0..=1.0;
+
 /// Configuration for similarity search operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchConfig {
@@ -109,6 +117,26 @@ pub struct SearchConfig {
     pub exclude_current_file: Option<String>,
     /// Recently suggested file paths to exclude (context filtering)
     pub exclude_recent_suggestions: Vec<String>,
+    /// Weight of the cosine ranking in hybrid rank fusion
+    #[serde(default = "default_fusion_weight")]
+    pub vector_weight: f32,
+    /// Weight of the BM25 lexical ranking in hybrid rank fusion
+    #[serde(default = "default_fusion_weight")]
+    pub lexical_weight: f32,
+    /// Reciprocal-rank fusion constant (larger values flatten rank differences)
+    #[serde(default = "default_rrf_k")]
+    pub rrf_k: f32,
+    /// Note metadata filter applied to candidates before they are scored
+    #[serde(default)]
+    pub filter: SearchFilter,
+}
+
+fn default_fusion_weight() -> f32 {
+    1.0
+}
+
+fn default_rrf_k() -> f32 {
+    60.0
 }
 
 impl Default for SearchConfig {
@@ -124,10 +152,108 @@ impl Default for SearchConfig {
             recency_weight: 0.1,           // Modest recency boost (10% factor)
             exclude_current_file: None,     // No exclusions by default
             exclude_recent_suggestions: Vec::new(), // No recent exclusions by default
+            vector_weight: default_fusion_weight(),  // Equal weight for both rankings
+            lexical_weight: default_fusion_weight(),
+            rrf_k: default_rrf_k(),        // Standard RRF constant
+            filter: SearchFilter::default(), // Search the whole vault
         }
     }
 }
 
+/// Restricts a search to notes with matching metadata
+/// 
+/// Every set criterion must match. Tags, dates and frontmatter fields are read
+/// from the note metadata the indexing pipeline stores with each embedding.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilter {
+    /// Keep notes carrying at least one of these tags (without `#`, case-insensitive)
+    pub tags: Vec<String>,
+    /// Keep notes inside this folder (a path prefix of the note's file path)
+    pub folder: Option<String>,
+    /// Keep notes updated at or after this time (Unix seconds)
+    pub updated_after: Option<u64>,
+    /// Keep notes updated at or before this time (Unix seconds)
+    pub updated_before: Option<u64>,
+    /// Keep notes whose frontmatter has these exact field values
+    pub fields: HashMap<String, String>,
+}
+
+impl SearchFilter {
+    /// Whether the filter lets every entry through
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+            && self.folder.is_none()
+            && self.updated_after.is_none()
+            && self.updated_before.is_none()
+            && self.fields.is_empty()
+    }
+    
+    /// Resolve a folder given relative to the vault against the vault path
+    /// 
+    /// Entries store absolute file paths, so `"projects"` becomes
+    /// `"<vault>/projects"`; absolute folders are kept as they are.
+    pub fn with_vault_root(mut self, vault_path: &str) -> Self {
+        if let Some(folder) = &self.folder {
+            if Path::new(folder).is_relative() {
+                self.folder = Some(Path::new(vault_path).join(folder).to_string_lossy().to_string());
+            }
+        }
+        self
+    }
+    
+    /// Check whether an entry passes the filter
+    /// 
+    /// The update time is the frontmatter date when the note has one and the
+    /// embedding's update time otherwise. Frontmatter values that are not
+    /// strings are compared with the expected value parsed as JSON (`4`, `true`).
+    pub fn matches(&self, entry: &EmbeddingEntry) -> bool {
+        let metadata = &entry.metadata;
+        
+        if let Some(folder) = &self.folder {
+            if !Path::new(&metadata.file_path).starts_with(folder) {
+                return false;
+            }
+        }
+        
+        if self.updated_after.is_some() || self.updated_before.is_some() {
+            let updated_at = metadata.note_updated_at();
+            if self.updated_after.is_some_and(|after| updated_at < after)
+                || self.updated_before.is_some_and(|before| updated_at > before)
+            {
+                return false;
+            }
+        }
+        
+        if !self.tags.is_empty() {
+            let note_tags = metadata.tags();
+            let tagged = self
+                .tags
+                .iter()
+                .any(|tag| note_tags.contains(&tag.trim_start_matches('#').to_lowercase()));
+            if !tagged {
+                return false;
+            }
+        }
+        
+        if !self.fields.is_empty() {
+            let frontmatter = metadata.frontmatter();
+            let fields_match = self.fields.iter().all(|(key, expected)| {
+                match frontmatter.get(&key.to_lowercase()) {
+                    Some(serde_json::Value::String(value)) => value == expected,
+                    Some(value) => serde_json::from_str::<serde_json::Value>(expected).is_ok_and(|expected| expected == *value),
+                    None => false,
+                }
+            });
+            if !fields_match {
+                return false;
+            }
+        }
+        
+        true
+    }
+}
+
 /// A similarity search result containing the entry and its similarity score
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -164,6 +290,68 @@ impl PartialOrd for SearchResult {
     }
 }
 
+impl SearchResult {
+    /// Replace the hit's chunk with its enclosing chunk at `level`
+    /// 
+    /// The entry keeps its vector and score; its metadata describes the parent
+    /// (see `EmbeddingMetadata::parent_metadata`). Hits on flat chunks, which
+    /// have no parents, are returned unchanged.
+    pub fn expand_to_parent(mut self, level: ChunkLevel) -> Self {
+        if let Some(metadata) = self.entry.metadata.parent_metadata(level) {
+            self.entry.metadata = metadata;
+        }
+        self
+    }
+}
+
+/// Expand hits to their enclosing chunks at `level`, keeping each parent once
+/// 
+/// Several windows of one paragraph or section expand to the same parent, so
+/// only the first (best ranked) hit per parent is kept and rank order is
+/// preserved.
+pub fn expand_hits_to_parents(hits: Vec<SearchResult>, level: ChunkLevel) -> Vec<SearchResult> {
+    let mut seen = HashSet::new();
+    hits.into_iter()
+        .map(|hit| hit.expand_to_parent(level))
+        .filter(|hit| seen.insert((hit.entry.metadata.file_path.clone(), hit.entry.metadata.chunk_id.clone())))
+        .collect()
+}
+
+/// How chunk scores are combined into a single score per note
+/// 
+/// Used by multi-query searches, where every chunk of a source note is a
+/// query and a target note collects hits on several of its chunks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreAggregation {
+    /// Best chunk score; favours notes sharing one strongly related passage
+    #[default]
+    Max,
+    /// Mean of the matched chunk scores; favours notes related throughout
+    Mean,
+    /// Sum of the `n` best chunk scores; favours notes matching in several places
+    TopNSum { n: usize },
+}
+
+impl ScoreAggregation {
+    /// Combine chunk scores (in any order) into a note score
+    pub fn aggregate(&self, scores: &[f32]) -> f32 {
+        if scores.is_empty() {
+            return 0.0;
+        }
+    
+        match self {
+            ScoreAggregation::Max => scores.iter().copied().fold(f32::MIN, f32::max),
+            ScoreAggregation::Mean => scores.iter().sum::<f32>() / scores.len() as f32,
+            ScoreAggregation::TopNSum { n } => {
+                let mut sorted = scores.to_vec();
+                sorted.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+                sorted.iter().take((*n).max(1)).sum()
+            }
+        }
+    }
+}
+
 /// Performance metrics for similarity search operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchMetrics {
@@ -248,6 +436,55 @@ pub struct EnhancedSearchResult {
     pub metrics: SearchMetrics,
 }
 
+/// A hybrid search result combining vector and lexical rankings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridSearchResult {
+    /// The embedding entry
+    pub entry: EmbeddingEntry,
+    /// Cosine similarity between the query and the entry [-1.0, 1.0]
+    pub similarity: f32,
+    /// BM25 score if the entry matched the query lexically
+    pub lexical_score: Option<f32>,
+    /// 1-based position in the vector ranking
+    pub vector_rank: Option<usize>,
+    /// 1-based position in the lexical ranking
+    pub lexical_rank: Option<usize>,
+    /// Reciprocal-rank fusion score used for ordering
+    pub fused_score: f32,
+}
+
+/// Configuration for searching quantized vectors in place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuantizedSearchConfig {
+    /// Minimum similarity threshold (checked on exact scores when re-ranking)
+    pub min_threshold: f32,
+    /// Candidates re-scored with exact f32 vectors per requested result (0 = no re-rank)
+    pub rerank_factor: usize,
+    /// Minimum candidate count to score in parallel
+    pub parallel_threshold: usize,
+}
+
+impl Default for QuantizedSearchConfig {
+    fn default() -> Self {
+        Self {
+            min_threshold: 0.3,       // Same cut-off as SearchConfig
+            rerank_factor: 4,         // Re-rank the best 4k quantized candidates
+            parallel_threshold: 500,  // Same as PerformanceConfig
+        }
+    }
+}
+
+/// A hit from a quantized search, identified by its position in the candidate slice
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QuantizedSearchHit {
+    /// Index of the candidate
+    pub index: usize,
+    /// Cosine similarity score [-1.0, 1.0]
+    pub similarity: f32,
+    /// Whether `similarity` was recomputed from the exact f32 vector
+    pub exact: bool,
+}
+
 /// Core similarity search algorithms implementation
 /// 
 /// This struct provides all mathematical algorithms needed for similarity-based
@@ -410,6 +647,217 @@ impl SimilaritySearch {
         Self::cosine_similarity_optimized(vector_a, vector_b)
     }
 
+    /// Cosine similarity between an f32 query and a stored vector, scored in place
+    /// 
+    /// The stored values are never decompressed into a `Vec<f32>`: 8-bit and
+    /// 16-bit affine codes and half floats are widened and dequantized in
+    /// registers, then accumulated into the dot product and the target's
+    /// magnitude in the same pass.
+    /// 
+    /// ## Kernels
+    /// 
+    /// - **AVX2/FMA**: 8 codes per iteration (`vpmovzx` widening, FMA dequantization)
+    /// - **F16C**: Half floats converted with `vcvtph2ps`, 8 per iteration
+    /// - **Scalar**: Used without AVX2/FMA (or F16C for half floats) and for remainders
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_vector` - Query vector in f32
+    /// * `target` - Borrowed stored vector
+    /// 
+    /// # Returns
+    /// 
+    /// Cosine similarity [-1.0, 1.0] between the query and the dequantized target
+    pub fn cosine_similarity_quantized(query_vector: &[f32], target: QuantizedVectorRef<'_>) -> SimilarityResult<f32> {
+        let query_norm = Self::validated_query_norm(query_vector)?;
+        Self::score_quantized(query_vector, query_norm, target)
+    }
+
+    /// Euclidean norm of a query, rejecting empty, non-finite and zero vectors
+    fn validated_query_norm(query_vector: &[f32]) -> SimilarityResult<f32> {
+        if query_vector.is_empty() {
+            return Err(SimilarityError::EmptyVector {
+                vector_type: "query_vector".to_string(),
+            });
+        }
+        
+        if query_vector.iter().any(|value| !value.is_finite()) {
+            return Err(SimilarityError::InvalidVector);
+        }
+        
+        let norm = query_vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if norm == 0.0 {
+            return Err(SimilarityError::ZeroMagnitude);
+        }
+        
+        Ok(norm)
+    }
+
+    /// Score a stored vector against a query whose norm is already known
+    fn score_quantized(query_vector: &[f32], query_norm: f32, target: QuantizedVectorRef<'_>) -> SimilarityResult<f32> {
+        if target.dimension() != query_vector.len() {
+            return Err(SimilarityError::DimensionMismatch {
+                query_dim: query_vector.len(),
+                target_dim: target.dimension(),
+            });
+        }
+        
+        let (dot_product, sum_sq) = Self::quantized_dot_and_norm(query_vector, target);
+        if !dot_product.is_finite() || !sum_sq.is_finite() {
+            return Err(SimilarityError::InvalidVector);
+        }
+        
+        let target_norm = sum_sq.sqrt();
+        if target_norm == 0.0 {
+            return Err(SimilarityError::ZeroMagnitude);
+        }
+        
+        Ok((dot_product / (query_norm * target_norm)).clamp(-1.0, 1.0))
+    }
+
+    /// Dot product with the query and squared magnitude of a stored vector
+    /// 
+    /// Dimensions must already match.
+    fn quantized_dot_and_norm(query: &[f32], target: QuantizedVectorRef<'_>) -> (f32, f32) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                match target {
+                    QuantizedVectorRef::Float32(values) => {
+                        return unsafe { Self::float32_dot_and_norm_avx2_fma(query, values) };
+                    }
+                    QuantizedVectorRef::Quantized8Bit { codes, min_value, scale } => {
+                        return unsafe { Self::quantized8_dot_and_norm_avx2_fma(query, codes, min_value, scale) };
+                    }
+                    QuantizedVectorRef::Quantized16Bit { codes, min_value, scale } => {
+                        return unsafe { Self::quantized16_dot_and_norm_avx2_fma(query, codes, min_value, scale) };
+                    }
+                    QuantizedVectorRef::Float16 { halves } if is_x86_feature_detected!("f16c") => {
+                        return unsafe { Self::float16_dot_and_norm_avx2_f16c(query, halves) };
+                    }
+                    QuantizedVectorRef::Float16 { .. } => {}
+                }
+            }
+        }
+        
+        Self::quantized_dot_and_norm_scalar(query, target)
+    }
+
+    /// Scalar dot product and squared magnitude (also handles SIMD remainders)
+    fn quantized_dot_and_norm_scalar(query: &[f32], target: QuantizedVectorRef<'_>) -> (f32, f32) {
+        let accumulate = |(dot, sum_sq): (f32, f32), (q, value): (&f32, f32)| (dot + q * value, sum_sq + value * value);
+        
+        match target {
+            QuantizedVectorRef::Float32(values) => {
+                query.iter().zip(values.iter().copied()).fold((0.0, 0.0), accumulate)
+            }
+            QuantizedVectorRef::Quantized8Bit { codes, min_value, scale } => query
+                .iter()
+                .zip(codes.iter().map(|&code| min_value + code as f32 * scale))
+                .fold((0.0, 0.0), accumulate),
+            QuantizedVectorRef::Quantized16Bit { codes, min_value, scale } => query
+                .iter()
+                .zip(codes.chunks_exact(2).map(|code| min_value + u16::from_le_bytes([code[0], code[1]]) as f32 * scale))
+                .fold((0.0, 0.0), accumulate),
+            QuantizedVectorRef::Float16 { halves } => query
+                .iter()
+                .zip(halves.chunks_exact(2).map(|half| f16_bits_to_f32(u16::from_le_bytes([half[0], half[1]]))))
+                .fold((0.0, 0.0), accumulate),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn float32_dot_and_norm_avx2_fma(query: &[f32], values: &[f32]) -> (f32, f32) {
+        let simd_len = query.len() & !7;
+        let mut dot_acc = _mm256_setzero_ps();
+        let mut sum_sq_acc = _mm256_setzero_ps();
+        
+        for i in (0..simd_len).step_by(8) {
+            let q = _mm256_loadu_ps(query.as_ptr().add(i));
+            let v = _mm256_loadu_ps(values.as_ptr().add(i));
+            dot_acc = _mm256_fmadd_ps(q, v, dot_acc);
+            sum_sq_acc = _mm256_fmadd_ps(v, v, sum_sq_acc);
+        }
+        
+        let (dot_tail, sum_sq_tail) = Self::quantized_dot_and_norm_scalar(
+            &query[simd_len..],
+            QuantizedVectorRef::Float32(&values[simd_len..]),
+        );
+        (Self::horizontal_sum_avx2(dot_acc) + dot_tail, Self::horizontal_sum_avx2(sum_sq_acc) + sum_sq_tail)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn quantized8_dot_and_norm_avx2_fma(query: &[f32], codes: &[u8], min_value: f32, scale: f32) -> (f32, f32) {
+        let simd_len = query.len() & !7;
+        let min_vec = _mm256_set1_ps(min_value);
+        let scale_vec = _mm256_set1_ps(scale);
+        let mut dot_acc = _mm256_setzero_ps();
+        let mut sum_sq_acc = _mm256_setzero_ps();
+        
+        for i in (0..simd_len).step_by(8) {
+            // Widen 8 codes to f32 and dequantize: value = min + code * scale
+            let raw = _mm_loadl_epi64(codes.as_ptr().add(i) as *const __m128i);
+            let v = _mm256_fmadd_ps(_mm256_cvtepi32_ps(_mm256_cvtepu8_epi32(raw)), scale_vec, min_vec);
+            let q = _mm256_loadu_ps(query.as_ptr().add(i));
+            dot_acc = _mm256_fmadd_ps(q, v, dot_acc);
+            sum_sq_acc = _mm256_fmadd_ps(v, v, sum_sq_acc);
+        }
+        
+        let (dot_tail, sum_sq_tail) = Self::quantized_dot_and_norm_scalar(
+            &query[simd_len..],
+            QuantizedVectorRef::Quantized8Bit { codes: &codes[simd_len..], min_value, scale },
+        );
+        (Self::horizontal_sum_avx2(dot_acc) + dot_tail, Self::horizontal_sum_avx2(sum_sq_acc) + sum_sq_tail)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn quantized16_dot_and_norm_avx2_fma(query: &[f32], codes: &[u8], min_value: f32, scale: f32) -> (f32, f32) {
+        let simd_len = query.len() & !7;
+        let min_vec = _mm256_set1_ps(min_value);
+        let scale_vec = _mm256_set1_ps(scale);
+        let mut dot_acc = _mm256_setzero_ps();
+        let mut sum_sq_acc = _mm256_setzero_ps();
+        
+        for i in (0..simd_len).step_by(8) {
+            // 8 little-endian u16 codes per 128-bit load
+            let raw = _mm_loadu_si128(codes.as_ptr().add(i * 2) as *const __m128i);
+            let v = _mm256_fmadd_ps(_mm256_cvtepi32_ps(_mm256_cvtepu16_epi32(raw)), scale_vec, min_vec);
+            let q = _mm256_loadu_ps(query.as_ptr().add(i));
+            dot_acc = _mm256_fmadd_ps(q, v, dot_acc);
+            sum_sq_acc = _mm256_fmadd_ps(v, v, sum_sq_acc);
+        }
+        
+        let (dot_tail, sum_sq_tail) = Self::quantized_dot_and_norm_scalar(
+            &query[simd_len..],
+            QuantizedVectorRef::Quantized16Bit { codes: &codes[simd_len * 2..], min_value, scale },
+        );
+        (Self::horizontal_sum_avx2(dot_acc) + dot_tail, Self::horizontal_sum_avx2(sum_sq_acc) + sum_sq_tail)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,fma,f16c")]
+    unsafe fn float16_dot_and_norm_avx2_f16c(query: &[f32], halves: &[u8]) -> (f32, f32) {
+        let simd_len = query.len() & !7;
+        let mut dot_acc = _mm256_setzero_ps();
+        let mut sum_sq_acc = _mm256_setzero_ps();
+        
+        for i in (0..simd_len).step_by(8) {
+            let v = _mm256_cvtph_ps(_mm_loadu_si128(halves.as_ptr().add(i * 2) as *const __m128i));
+            let q = _mm256_loadu_ps(query.as_ptr().add(i));
+            dot_acc = _mm256_fmadd_ps(q, v, dot_acc);
+            sum_sq_acc = _mm256_fmadd_ps(v, v, sum_sq_acc);
+        }
+        
+        let (dot_tail, sum_sq_tail) = Self::quantized_dot_and_norm_scalar(
+            &query[simd_len..],
+            QuantizedVectorRef::Float16 { halves: &halves[simd_len * 2..] },
+        );
+        (Self::horizontal_sum_avx2(dot_acc) + dot_tail, Self::horizontal_sum_avx2(sum_sq_acc) + sum_sq_tail)
+    }
+
     /// Scalar-optimized cosine similarity with manual loop unrolling
     /// 
     /// This provides a middle ground between the basic implementation and SIMD,
@@ -1021,6 +1469,11 @@ impl SimilaritySearch {
         // ==================================================================================
         
         for entry in database_entries {
+            // Skip entries outside the metadata filter before scoring them
+            if !config.filter.matches(entry) {
+                continue;
+            }
+            
             processed_count += 1;
             
             // Calculate similarity score using the appropriate method
@@ -1081,9 +1534,22 @@ impl SimilaritySearch {
         // STEP 7: RESULT COLLECTION AND ENHANCED PROCESSING
         // ==================================================================================
         
-        // Convert heap to sorted vector (highest similarity first)
-        let mut results: Vec<SearchResult> = result_heap.into_vec();
+        // Convert heap to vector and apply filtering, weighting and ranking
+        let results: Vec<SearchResult> = result_heap.into_vec();
         
+        Ok(Self::finalize_results(results, config))
+    }
+    
+    /// Apply the standard post-processing pipeline to raw search candidates
+    /// 
+    /// Used by exact k-NN and by index-backed approximate search so both paths
+    /// rank and filter results identically:
+    /// 1. Context filtering (current file, recent suggestions)
+    /// 2. Recency weighting (if enabled)
+    /// 3. Sort by final score (descending)
+    /// 4. Diversity filtering (if enabled)
+    /// 5. Truncate to `max_results`
+    pub fn finalize_results(mut results: Vec<SearchResult>, config: &SearchConfig) -> Vec<SearchResult> {
         // Apply context filtering (exclude current file and recent suggestions)
         results = Self::apply_context_filtering(results, config);
         
@@ -1109,7 +1575,7 @@ impl SimilaritySearch {
             results.truncate(config.max_results);
         }
         
-        Ok(results)
+        results
     }
     
     /// Batch process multiple queries for similarity search
@@ -1162,6 +1628,222 @@ impl SimilaritySearch {
         Ok(batch_results)
     }
     
+    /// Multi-query search ranking whole notes instead of chunks
+    /// 
+    /// Every query vector (typically one per chunk of a source note) runs an
+    /// exact k-NN search. A target chunk hit by several queries keeps its best
+    /// score, and the chunk scores of each target note are combined with
+    /// `aggregation`. Each note is represented by its best matching chunk,
+    /// with `similarity` replaced by the note score.
+    /// 
+    /// `config.exclude_current_file` and the other context filters apply to
+    /// every query. Diversity filtering and `max_results` apply to the note
+    /// ranking rather than to the per-query chunk hits.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_vectors` - Query vectors, usually the source note's chunk embeddings
+    /// * `database_entries` - Collection of embedding entries to search through
+    /// * `k_per_query` - Number of chunk hits collected per query
+    /// * `aggregation` - How chunk scores are combined per note
+    /// * `config` - Search configuration
+    /// 
+    /// # Returns
+    /// 
+    /// One result per note, sorted by note score (descending)
+    pub fn multi_query_note_search(
+        query_vectors: &[Vec<f32>],
+        database_entries: &[EmbeddingEntry],
+        k_per_query: usize,
+        aggregation: ScoreAggregation,
+        config: &SearchConfig,
+    ) -> SimilarityResult<Vec<SearchResult>> {
+        if k_per_query == 0 {
+            return Err(SimilarityError::InvalidK { k: k_per_query });
+        }
+    
+        let chunk_config = Self::note_query_config(config);
+        let hits_per_query = query_vectors
+            .iter()
+            .map(|query_vector| Self::k_nearest_neighbors(query_vector, database_entries, k_per_query, &chunk_config))
+            .collect::<SimilarityResult<Vec<_>>>()?;
+    
+        Ok(Self::aggregate_note_hits(hits_per_query, aggregation, config))
+    }
+    
+    /// Chunk-level configuration for each query of a note search
+    /// 
+    /// Context filters and the threshold carry over, while diversity
+    /// filtering and `max_results` are left to the note ranking.
+    pub fn note_query_config(config: &SearchConfig) -> SearchConfig {
+        SearchConfig {
+            max_results: 0,
+            enable_diversity_filter: false,
+            ..config.clone()
+        }
+    }
+    
+    /// Combine the chunk hits of several queries into a note ranking
+    /// 
+    /// A target chunk hit by several queries keeps its best score, and the
+    /// chunk scores of each note are combined with `aggregation`. Diversity
+    /// filtering and `max_results` from `config` apply to the note ranking.
+    pub fn aggregate_note_hits(
+        hits_per_query: Vec<Vec<SearchResult>>,
+        aggregation: ScoreAggregation,
+        config: &SearchConfig,
+    ) -> Vec<SearchResult> {
+        // Best score per target chunk, grouped by note
+        let mut notes: HashMap<String, HashMap<String, SearchResult>> = HashMap::new();
+        for hit in hits_per_query.into_iter().flatten() {
+            let chunks = notes.entry(hit.entry.metadata.file_path.clone()).or_default();
+            match chunks.get(&hit.entry.id) {
+                Some(existing) if existing.similarity >= hit.similarity => {}
+                _ => {
+                    chunks.insert(hit.entry.id.clone(), hit);
+                }
+            }
+        }
+    
+        let mut results: Vec<SearchResult> = notes
+            .into_values()
+            .filter_map(|chunks| {
+                let scores: Vec<f32> = chunks.values().map(|hit| hit.similarity).collect();
+                let score = aggregation.aggregate(&scores);
+                let mut best = chunks.into_values().max_by(|a, b| {
+                    a.similarity.partial_cmp(&b.similarity).unwrap_or(Ordering::Equal)
+                })?;
+                best.similarity = score;
+                Some(best)
+            })
+            .collect();
+    
+        results.sort_by(|a, b| {
+            b.similarity
+                .partial_cmp(&a.similarity)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.entry.metadata.file_path.cmp(&b.entry.metadata.file_path))
+        });
+    
+        if config.enable_diversity_filter {
+            results = Self::apply_diversity_filtering(results, config);
+        }
+    
+        if config.max_results > 0 && results.len() > config.max_results {
+            results.truncate(config.max_results);
+        }
+    
+        results
+    }
+    
+    /// k-NN search over stored vectors scored in place, with optional exact re-rank
+    /// 
+    /// Candidates are scored with `cosine_similarity_quantized`, so quantized
+    /// storage is searched without materializing an f32 vector per entry.
+    /// With `rerank_factor > 0` and an `exact_vector` lookup, the best
+    /// `k * rerank_factor` candidates are re-scored against their f32 vectors
+    /// before the threshold and the cut to `k`, recovering most of the ranking
+    /// lost to quantization while loading only a handful of full vectors.
+    /// 
+    /// Candidates with a zero-magnitude vector are skipped.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_vector` - Query vector in f32
+    /// * `candidates` - Stored vectors; hits refer to them by index
+    /// * `k` - Number of results to return
+    /// * `config` - Threshold, re-rank depth and parallelism
+    /// * `exact_vector` - Resolves a candidate index to its f32 vector for re-ranking;
+    ///   candidates it returns `None` for keep their quantized score
+    /// 
+    /// # Returns
+    /// 
+    /// Up to `k` hits sorted by similarity (descending)
+    pub fn quantized_k_nearest_neighbors(
+        query_vector: &[f32],
+        candidates: &[QuantizedVectorRef<'_>],
+        k: usize,
+        config: &QuantizedSearchConfig,
+        exact_vector: Option<&dyn Fn(usize) -> Option<Vec<f32>>>,
+    ) -> SimilarityResult<Vec<QuantizedSearchHit>> {
+        if k == 0 {
+            return Err(SimilarityError::InvalidK { k });
+        }
+        
+        if !(-1.0..=1.0).contains(&config.min_threshold) {
+            return Err(SimilarityError::InvalidThreshold {
+                threshold: config.min_threshold,
+            });
+        }
+        
+        let query_norm = Self::validated_query_norm(query_vector)?;
+        let exact_vector = exact_vector.filter(|_| config.rerank_factor > 0);
+        
+        // Quantization error can move scores across the threshold, so a
+        // re-ranked shortlist is only filtered once exact scores are known
+        let (shortlist_len, shortlist_threshold) = match exact_vector {
+            Some(_) => (k.saturating_mul(config.rerank_factor), -1.0),
+            None => (k, config.min_threshold),
+        };
+        
+        let score = |(index, target): (usize, &QuantizedVectorRef<'_>)| {
+            match Self::score_quantized(query_vector, query_norm, *target) {
+                Ok(similarity) if similarity >= shortlist_threshold => {
+                    Ok(Some(QuantizedSearchHit { index, similarity, exact: false }))
+                }
+                Ok(_) | Err(SimilarityError::ZeroMagnitude) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+        let scored: Vec<QuantizedSearchHit> = if candidates.len() >= config.parallel_threshold {
+            candidates
+                .par_iter()
+                .enumerate()
+                .map(score)
+                .filter_map(Result::transpose)
+                .collect::<SimilarityResult<_>>()?
+        } else {
+            candidates
+                .iter()
+                .enumerate()
+                .map(score)
+                .filter_map(Result::transpose)
+                .collect::<SimilarityResult<_>>()?
+        };
+        
+        let mut hits = Self::top_quantized_hits(scored, shortlist_len);
+        
+        if let Some(exact_vector) = exact_vector {
+            for hit in &mut hits {
+                if let Some(vector) = exact_vector(hit.index) {
+                    hit.similarity = Self::cosine_similarity_simd(query_vector, &vector)?;
+                    hit.exact = true;
+                }
+            }
+            hits.retain(|hit| hit.similarity >= config.min_threshold);
+            hits = Self::top_quantized_hits(hits, k);
+        }
+        
+        Ok(hits)
+    }
+    
+    /// Best `k` hits sorted by similarity (descending), ties broken by index
+    fn top_quantized_hits(mut hits: Vec<QuantizedSearchHit>, k: usize) -> Vec<QuantizedSearchHit> {
+        let by_similarity = |a: &QuantizedSearchHit, b: &QuantizedSearchHit| {
+            b.similarity
+                .partial_cmp(&a.similarity)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.index.cmp(&b.index))
+        };
+        
+        if hits.len() > k {
+            hits.select_nth_unstable_by(k, by_similarity);
+            hits.truncate(k);
+        }
+        hits.sort_by(by_similarity);
+        hits
+    }
+    
     /// Find all entries above a similarity threshold
     /// 
     /// This function returns all database entries that have cosine similarity
@@ -1316,6 +1998,10 @@ impl SimilaritySearch {
                 
                 // Process chunk sequentially within each thread
                 for entry in chunk {
+                    if !config.filter.matches(entry) {
+                        continue;
+                    }
+                    
                     let similarity = if config.normalize_query {
                         Self::cosine_similarity_normalized(&normalized_query, &entry.vector)?
                     } else {
@@ -1472,6 +2158,10 @@ impl SimilaritySearch {
         let mut result_heap = BinaryHeap::with_capacity(k);
         
         for (entry, normalized_vector) in normalized_database {
+            if !config.filter.matches(entry) {
+                continue;
+            }
+            
             let similarity = if config.normalize_query {
                 Self::cosine_similarity_normalized(&normalized_query, normalized_vector)?
             } else {
@@ -1508,15 +2198,17 @@ impl SimilaritySearch {
     
     /// Approximate nearest neighbors search for very large datasets (1000+ vectors)
     /// 
-    /// This implementation provides fast approximate search using:
-    /// - Random sampling for initial candidate selection
-    /// - Hierarchical filtering to reduce search space
-    /// - Confidence-based early termination
+    /// Builds an HNSW graph over `database_entries` and searches it instead of
+    /// scanning every vector. The graph is cached for the most recent entry set,
+    /// so repeated queries against the same entries skip construction.
+    /// 
+    /// Callers that keep a persistent index (such as `VectorDatabase`) should use
+    /// [`Self::approximate_nearest_neighbors_with_index`] directly.
     /// 
     /// Trade-offs:
-    /// - **Speed:** 5-10x faster for large datasets
-    /// - **Accuracy:** 95%+ accuracy compared to exact search
-    /// - **Memory:** Constant memory overhead
+    /// - **Speed:** Sub-linear query time once the graph is built
+    /// - **Accuracy:** Recall@10 typically above 95% on embedding data
+    /// - **Memory:** One normalized copy of each vector plus graph links
     pub fn approximate_nearest_neighbors(
         query_vector: &[f32],
         database_entries: &[EmbeddingEntry],
@@ -1525,16 +2217,14 @@ impl SimilaritySearch {
         perf_config: &PerformanceConfig,
     ) -> SimilarityResult<EnhancedSearchResult> {
         use std::time::Instant;
-        use rand::seq::SliceRandom;
-        use rand::thread_rng;
         
         let start_time = Instant::now();
-        let mut metrics = SearchMetrics::new();
-        metrics.vectors_processed = database_entries.len();
-        metrics.used_approximate_search = true;
         
-        // For smaller datasets, fall back to exact search
-        if database_entries.len() < perf_config.approximate_threshold {
+        // For smaller datasets, fall back to exact search; a metadata filter
+        // is applied during the scan so the graph is not used either
+        if database_entries.len() < perf_config.approximate_threshold || !config.filter.is_empty() {
+            let mut metrics = SearchMetrics::new();
+            metrics.vectors_processed = database_entries.len();
             let exact_result = Self::k_nearest_neighbors(query_vector, database_entries, k, config)?;
             metrics.total_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
             metrics.results_count = exact_result.len();
@@ -1548,74 +2238,253 @@ impl SimilaritySearch {
             });
         }
         
-        // Step 1: Random sampling to get initial candidates
-        let mut rng = thread_rng();
-        let sample_size = (database_entries.len() as f64).sqrt() as usize * 10; // Adaptive sampling
-        let sample_size = sample_size.min(database_entries.len()).max(k * 10);
+        let index = Self::cached_ann_index(database_entries);
+        Self::approximate_nearest_neighbors_with_index(
+            query_vector,
+            &index,
+            database_entries,
+            k,
+            config,
+            perf_config,
+        )
+    }
+    
+    /// Approximate nearest neighbors search using a prebuilt HNSW index
+    /// 
+    /// The index returns candidate IDs which are resolved against
+    /// `database_entries`; candidates missing from the entry set are skipped.
+    /// Results go through the same threshold, filtering and ranking pipeline as
+    /// exact k-NN. With a metadata filter the graph is skipped and
+    /// `database_entries` are scanned instead, so filtered-out neighbors cannot
+    /// crowd out the matching ones.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_vector` - Query embedding
+    /// * `index` - HNSW index covering (a superset of) `database_entries`
+    /// * `database_entries` - Entries used to resolve candidate IDs
+    /// * `k` - Number of results to return
+    /// * `config` - Search configuration
+    /// * `_perf_config` - Performance configuration (reserved for tuning)
+    pub fn approximate_nearest_neighbors_with_index(
+        query_vector: &[f32],
+        index: &HnswIndex,
+        database_entries: &[EmbeddingEntry],
+        k: usize,
+        config: &SearchConfig,
+        _perf_config: &PerformanceConfig,
+    ) -> SimilarityResult<EnhancedSearchResult> {
+        use std::time::Instant;
+        
+        let start_time = Instant::now();
+        let mut metrics = SearchMetrics::new();
+        
+        if !config.filter.is_empty() {
+            metrics.vectors_processed = database_entries.len();
+            let results = Self::k_nearest_neighbors(query_vector, database_entries, k, config)?;
+            metrics.total_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+            metrics.results_count = results.len();
+            metrics.calculate_throughput();
+            metrics.estimated_memory_bytes = Self::estimate_memory_usage(&results, database_entries.len());
+            return Ok(EnhancedSearchResult { results, metrics });
+        }
+        
+        metrics.used_approximate_search = true;
         
-        let mut sampled_indices: Vec<usize> = (0..database_entries.len()).collect();
-        sampled_indices.shuffle(&mut rng);
-        sampled_indices.truncate(sample_size);
+        let candidates = Self::search_ann_index(query_vector, index, k, config)?;
+        metrics.vectors_processed = candidates.len();
         
-        // Step 2: Exact search on sample
-        let sampled_entries: Vec<&EmbeddingEntry> = sampled_indices.iter()
-            .map(|&i| &database_entries[i])
+        let entries_by_id: HashMap<&str, &EmbeddingEntry> = database_entries
+            .iter()
+            .map(|entry| (entry.id.as_str(), entry))
             .collect();
-        
-        let mut initial_results = Vec::new();
-        for entry in &sampled_entries {
-            let similarity = Self::cosine_similarity(query_vector, &entry.vector)?;
-            if similarity >= config.min_threshold {
-                initial_results.push(SearchResult {
+        let resolved = candidates
+            .into_iter()
+            .filter_map(|(id, similarity)| {
+                entries_by_id.get(id.as_str()).map(|entry| SearchResult {
                     entry: (*entry).clone(),
                     similarity,
+                })
+            })
+            .collect();
+        
+        let results = Self::rank_ann_candidates(resolved, k, config);
+        
+        metrics.total_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+        metrics.results_count = results.len();
+        metrics.calculate_throughput();
+        metrics.estimated_memory_bytes = Self::estimate_memory_usage(&results, index.len());
+        
+        Ok(EnhancedSearchResult { results, metrics })
+    }
+    
+    /// Query an HNSW index for candidate `(entry_id, similarity)` pairs
+    /// 
+    /// Validates the request like exact k-NN and over-fetches candidates so that
+    /// context and diversity filtering can drop entries without leaving the
+    /// result set short. Pair with [`Self::rank_ann_candidates`] once the IDs
+    /// have been resolved to entries.
+    pub fn search_ann_index(
+        query_vector: &[f32],
+        index: &HnswIndex,
+        k: usize,
+        config: &SearchConfig,
+    ) -> SimilarityResult<Vec<(String, f32)>> {
+        if k == 0 {
+            return Err(SimilarityError::InvalidK { k });
+        }
+        if config.min_threshold < -1.0 || config.min_threshold > 1.0 {
+            return Err(SimilarityError::InvalidThreshold {
+                threshold: config.min_threshold,
+            });
+        }
+        if query_vector.is_empty() {
+            return Err(SimilarityError::EmptyVector {
+                vector_type: "query_vector".to_string(),
+            });
+        }
+        if let Some(dimension) = index.dimension() {
+            if dimension != query_vector.len() {
+                return Err(SimilarityError::DimensionMismatch {
+                    query_dim: query_vector.len(),
+                    target_dim: dimension,
                 });
             }
         }
         
-        // Sort and keep top candidates
-        initial_results.sort_by(|a, b| {
-            b.similarity.partial_cmp(&a.similarity).unwrap_or(Ordering::Equal)
-        });
-        initial_results.truncate(k * 2); // Keep more candidates for refinement
+        let fetch_k = Self::candidate_fetch_count(k, config);
+        let ef = index.config().ef_search.max(fetch_k);
         
-        // Step 3: Refinement phase - check neighbors of top candidates
-        if initial_results.len() >= k {
-            // We have enough good candidates, refine by checking similar vectors
-            let _threshold = initial_results[k - 1].similarity;
-            let mut refined_results = initial_results;
-            
-            // Optional: Add nearby vectors to candidates (simplified heuristic)
-            // In a full implementation, this would use spatial indexing
-            
-            refined_results.sort_by(|a, b| {
-                b.similarity.partial_cmp(&a.similarity).unwrap_or(Ordering::Equal)
+        let candidates = index
+            .search(query_vector, fetch_k, Some(ef))
+            .map_err(|_| SimilarityError::InvalidVector)?;
+        
+        Ok(candidates
+            .into_iter()
+            .filter(|(_, similarity)| *similarity >= config.min_threshold)
+            .collect())
+    }
+    
+    /// Candidates to fetch for `k` results so exclusions and diversity leave enough
+    pub fn candidate_fetch_count(k: usize, config: &SearchConfig) -> usize {
+        let effective_k = if config.max_results > 0 { k.min(config.max_results) } else { k };
+        let excluded = config.exclude_recent_suggestions.len()
+            + usize::from(config.exclude_current_file.is_some());
+        effective_k * 2 + excluded * 4
+    }
+    
+    /// Rank resolved ANN candidates and cut them down to `k` results
+    pub fn rank_ann_candidates(candidates: Vec<SearchResult>, k: usize, config: &SearchConfig) -> Vec<SearchResult> {
+        let mut results = Self::finalize_results(candidates, config);
+        results.truncate(k);
+        results
+    }
+    
+    /// Fuse vector and lexical rankings with weighted reciprocal-rank fusion
+    /// 
+    /// Each entry scores `weight / (rrf_k + rank)` per ranking it appears in,
+    /// using `vector_weight`, `lexical_weight` and `rrf_k` from the config.
+    /// Ranks rather than raw scores are fused, so cosine similarities and BM25
+    /// scores never have to be put on a common scale.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `vector_results` - Cosine results, already filtered and ranked
+    /// * `lexical_results` - Lexical hits as `(result, bm25_score)`; the result
+    ///   carries the entry's cosine similarity to the query for display
+    /// * `k` - Maximum number of fused results
+    /// * `config` - Fusion weights and context filters
+    /// 
+    /// # Returns
+    /// 
+    /// Up to `k` results sorted by fused score (descending)
+    pub fn reciprocal_rank_fusion(
+        vector_results: Vec<SearchResult>,
+        lexical_results: Vec<(SearchResult, f32)>,
+        k: usize,
+        config: &SearchConfig,
+    ) -> Vec<HybridSearchResult> {
+        let mut fused: HashMap<String, HybridSearchResult> = HashMap::new();
+        
+        for (index, result) in vector_results.into_iter().enumerate() {
+            let rank = index + 1;
+            fused.entry(result.entry.id.clone()).or_insert(HybridSearchResult {
+                entry: result.entry,
+                similarity: result.similarity,
+                lexical_score: None,
+                vector_rank: Some(rank),
+                lexical_rank: None,
+                fused_score: config.vector_weight / (config.rrf_k + rank as f32),
             });
-            refined_results.truncate(k);
-            
-            metrics.total_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
-            metrics.results_count = refined_results.len();
-            metrics.calculate_throughput();
-            metrics.estimated_memory_bytes = Self::estimate_memory_usage(&refined_results, sample_size);
-            
-            return Ok(EnhancedSearchResult {
-                results: refined_results,
-                metrics,
+        }
+        
+        let mut lexical_results = lexical_results;
+        lexical_results.retain(|(result, _)| {
+            config.exclude_current_file.as_ref() != Some(&result.entry.metadata.file_path)
+                && !config.exclude_recent_suggestions.contains(&result.entry.metadata.file_path)
+                && config.filter.matches(&result.entry)
+        });
+        lexical_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        
+        for (index, (result, lexical_score)) in lexical_results.into_iter().enumerate() {
+            let rank = index + 1;
+            let contribution = config.lexical_weight / (config.rrf_k + rank as f32);
+            let hybrid = fused.entry(result.entry.id.clone()).or_insert(HybridSearchResult {
+                entry: result.entry,
+                similarity: result.similarity,
+                lexical_score: None,
+                vector_rank: None,
+                lexical_rank: None,
+                fused_score: 0.0,
             });
+            hybrid.lexical_score = Some(lexical_score);
+            hybrid.lexical_rank = Some(rank);
+            hybrid.fused_score += contribution;
         }
         
-        // Fallback: Not enough candidates, fall back to exact search
-        let exact_result = Self::k_nearest_neighbors(query_vector, database_entries, k, config)?;
-        metrics.total_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
-        metrics.results_count = exact_result.len();
-        metrics.used_approximate_search = false; // Fell back to exact
-        metrics.calculate_throughput();
-        metrics.estimated_memory_bytes = Self::estimate_memory_usage(&exact_result, database_entries.len());
+        let mut results: Vec<HybridSearchResult> = fused.into_values().collect();
+        results.sort_by(|a, b| {
+            b.fused_score
+                .partial_cmp(&a.fused_score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.similarity.partial_cmp(&a.similarity).unwrap_or(Ordering::Equal))
+        });
+        results.truncate(k);
+        results
+    }
+    
+    /// Get (or build) the HNSW index for an ad-hoc entry set
+    /// 
+    /// The last built index is kept together with a fingerprint of the entry IDs,
+    /// so repeated searches over the same entries reuse it.
+    fn cached_ann_index(database_entries: &[EmbeddingEntry]) -> Arc<HnswIndex> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
         
-        Ok(EnhancedSearchResult {
-            results: exact_result,
-            metrics,
-        })
+        /// Fingerprint of the entry set and the index built for it
+        type CachedAnnIndex = Option<(u64, Arc<HnswIndex>)>;
+        static TRANSIENT_ANN_INDEX: Lazy<Mutex<CachedAnnIndex>> = Lazy::new(|| Mutex::new(None));
+        
+        let mut hasher = DefaultHasher::new();
+        database_entries.len().hash(&mut hasher);
+        for entry in database_entries {
+            entry.id.hash(&mut hasher);
+        }
+        let fingerprint = hasher.finish();
+        
+        if let Ok(cached) = TRANSIENT_ANN_INDEX.lock() {
+            if let Some((cached_fingerprint, index)) = cached.as_ref() {
+                if *cached_fingerprint == fingerprint {
+                    return Arc::clone(index);
+                }
+            }
+        }
+        
+        let index = Arc::new(HnswIndex::build_from_entries(HnswConfig::default(), database_entries));
+        if let Ok(mut cached) = TRANSIENT_ANN_INDEX.lock() {
+            *cached = Some((fingerprint, Arc::clone(&index)));
+        }
+        index
     }
     
     /// Estimate memory usage for search results
@@ -1636,9 +2505,15 @@ impl SimilaritySearch {
     /// This function removes search results that match:
     /// - The currently open file (to avoid suggesting the same file being edited)
     /// - Recently suggested files (to provide fresh suggestions)
+    /// - Notes outside the metadata filter (for candidates that were not scanned)
     /// 
     /// This helps ensure suggestions are contextually relevant and avoid redundancy.
     fn apply_context_filtering(mut results: Vec<SearchResult>, config: &SearchConfig) -> Vec<SearchResult> {
+        // Filter out notes outside the metadata filter
+        if !config.filter.is_empty() {
+            results.retain(|result| config.filter.matches(&result.entry));
+        }
+        
         // Filter out current file if specified
         if let Some(current_file) = &config.exclude_current_file {
             results.retain(|result| &result.entry.metadata.file_path != current_file);
@@ -2311,6 +3186,10 @@ mod tests {
             recency_weight: 0.0,
             exclude_current_file: None,
             exclude_recent_suggestions: Vec::new(),
+            vector_weight: 1.0,
+            lexical_weight: 1.0,
+            rrf_k: 60.0,
+            filter: SearchFilter::default(),
         };
         let results = SimilaritySearch::k_nearest_neighbors(&query, &entries, 2, &config).unwrap();
         
@@ -2467,7 +3346,50 @@ mod tests {
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].entry.metadata.file_path, "other_file.md");
     }
-    
+
+    #[test]
+    fn test_metadata_filter_applied_before_scoring() {
+        use crate::note_metadata::NoteMetadata;
+
+        let tagged = |vector: Vec<f32>, file_path: &str, content: &str| {
+            let mut entry = create_test_entry(vector, file_path, "chunk1");
+            entry.metadata.custom_metadata = NoteMetadata::parse(content).to_custom_metadata();
+            entry
+        };
+        let entries = vec![
+            tagged(vec![1.0, 0.0], "/vault/inbox/best.md", "#idea"),
+            tagged(vec![0.9, 0.1], "/vault/projects/alpha.md", "---\ntags: [project]\nstatus: active\nupdated: 2024-05-01\n---\n"),
+            tagged(vec![0.8, 0.2], "/vault/projects/beta.md", "---\ntags: [project]\nstatus: done\nupdated: 2023-01-01\n---\n"),
+        ];
+        let query = vec![1.0, 0.0];
+        let search = |filter: SearchFilter| {
+            let config = SearchConfig { filter, min_threshold: -1.0, ..SearchConfig::default() };
+            SimilaritySearch::k_nearest_neighbors(&query, &entries, 1, &config)
+                .unwrap()
+                .into_iter()
+                .map(|result| result.entry.metadata.file_path)
+                .collect::<Vec<_>>()
+        };
+
+        // The filter runs before top-k selection, so the best match is the best matching note
+        assert_eq!(search(SearchFilter::default()), vec!["/vault/inbox/best.md"]);
+        assert_eq!(search(SearchFilter { tags: vec!["#Project".to_string()], ..SearchFilter::default() }),
+                   vec!["/vault/projects/alpha.md"]);
+
+        let folder = SearchFilter { folder: Some("projects".to_string()), ..SearchFilter::default() }
+            .with_vault_root("/vault");
+        let status = SearchFilter {
+            fields: HashMap::from([("status".to_string(), "done".to_string())]),
+            ..folder.clone()
+        };
+        assert_eq!(search(status), vec!["/vault/projects/beta.md"]);
+
+        // 2024-01-01: only alpha was updated afterwards, the inbox note has no date and uses its embedding time
+        let updated_after = SearchFilter { updated_after: Some(1_704_067_200), ..folder };
+        assert_eq!(search(updated_after), vec!["/vault/projects/alpha.md"]);
+        assert!(search(SearchFilter { folder: Some("/vault/proj".to_string()), ..SearchFilter::default() }).is_empty());
+    }
+
     #[test]
     fn test_recency_weighting() {
         use std::time::{SystemTime, UNIX_EPOCH};
@@ -2643,4 +3565,199 @@ mod tests {
         println!("   - Results returned: {} (target: ≤10)", results.len());
         println!("   - Memory usage estimate: ~{}KB", results.len() * 1024 / 1000); // Rough estimate
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_reciprocal_rank_fusion_combines_rankings() {
+        let semantic_only = create_test_entry(vec![1.0, 0.0], "semantic.md", "chunk1");
+        let both = create_test_entry(vec![0.9, 0.1], "both.md", "chunk1");
+        let lexical_only = create_test_entry(vec![0.0, 1.0], "lexical.md", "chunk1");
+        let excluded = create_test_entry(vec![0.1, 0.9], "current.md", "chunk1");
+        
+        let vector_results = vec![
+            SearchResult { entry: semantic_only.clone(), similarity: 0.95 },
+            SearchResult { entry: both.clone(), similarity: 0.90 },
+        ];
+        let lexical_results = vec![
+            (SearchResult { entry: lexical_only.clone(), similarity: 0.10 }, 3.0),
+            (SearchResult { entry: excluded, similarity: 0.20 }, 9.0),
+            (SearchResult { entry: both.clone(), similarity: 0.90 }, 2.0),
+        ];
+        let config = SearchConfig {
+            exclude_current_file: Some("current.md".to_string()),
+            ..SearchConfig::default()
+        };
+        
+        let results = SimilaritySearch::reciprocal_rank_fusion(vector_results.clone(), lexical_results.clone(), 10, &config);
+        
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].entry.id, both.id);
+        assert_eq!(results[0].vector_rank, Some(2));
+        assert_eq!(results[0].lexical_rank, Some(2));
+        assert_eq!(results[0].lexical_score, Some(2.0));
+        assert!(results.iter().all(|r| r.entry.metadata.file_path != "current.md"));
+        
+        // Turning off the lexical ranking reduces fusion to the vector order
+        let vector_only = SearchConfig { lexical_weight: 0.0, ..config };
+        let results = SimilaritySearch::reciprocal_rank_fusion(vector_results, lexical_results, 2, &vector_only);
+        assert_eq!(results[0].entry.id, semantic_only.id);
+        assert_eq!(results[1].entry.id, both.id);
+    }
+    
+    #[test]
+    fn test_score_aggregation() {
+        let scores = [0.5, 0.9, 0.7];
+        
+        assert!((ScoreAggregation::Max.aggregate(&scores) - 0.9).abs() < 1e-6);
+        assert!((ScoreAggregation::Mean.aggregate(&scores) - 0.7).abs() < 1e-6);
+        assert!((ScoreAggregation::TopNSum { n: 2 }.aggregate(&scores) - 1.6).abs() < 1e-6);
+        assert_eq!(ScoreAggregation::Mean.aggregate(&[]), 0.0);
+        
+        let parsed: ScoreAggregation = serde_json::from_str(r#"{"top_n_sum": {"n": 3}}"#).unwrap();
+        assert_eq!(parsed, ScoreAggregation::TopNSum { n: 3 });
+    }
+    
+    #[test]
+    fn test_multi_query_note_search_aggregates_per_note() {
+        let queries = vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0]];
+        let entries = vec![
+            create_test_entry(vec![1.0, 0.0, 0.0], "source.md", "chunk1"),
+            // One passage matching the first query exactly
+            create_test_entry(vec![1.0, 0.05, 0.0], "focused.md", "chunk1"),
+            // Two passages, each matching one query fairly well
+            create_test_entry(vec![0.8, 0.0, 0.6], "broad.md", "chunk1"),
+            create_test_entry(vec![0.0, 0.8, 0.6], "broad.md", "chunk2"),
+        ];
+        let config = SearchConfig {
+            min_threshold: 0.5,
+            max_results: 0,
+            enable_diversity_filter: false,
+            enable_recency_weighting: false,
+            exclude_current_file: Some("source.md".to_string()),
+            ..SearchConfig::default()
+        };
+        
+        let by_max = SimilaritySearch::multi_query_note_search(&queries, &entries, 10, ScoreAggregation::Max, &config).unwrap();
+        assert_eq!(by_max.len(), 2);
+        assert_eq!(by_max[0].entry.metadata.file_path, "focused.md");
+        assert!(by_max.iter().all(|r| r.entry.metadata.file_path != "source.md"));
+        
+        let by_sum = SimilaritySearch::multi_query_note_search(&queries, &entries, 10, ScoreAggregation::TopNSum { n: 3 }, &config).unwrap();
+        assert_eq!(by_sum[0].entry.metadata.file_path, "broad.md");
+        assert!((by_sum[0].similarity - 1.6).abs() < 1e-5);
+        
+        // With diversity filtering, near-duplicate notes collapse to the best one
+        let duplicate = create_test_entry(vec![1.0, 0.04, 0.0], "copy.md", "chunk1");
+        let entries = [entries, vec![duplicate]].concat();
+        let diverse = SearchConfig { enable_diversity_filter: true, ..config };
+        let results = SimilaritySearch::multi_query_note_search(&queries, &entries, 10, ScoreAggregation::Max, &diverse).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(SimilaritySearch::multi_query_note_search(&queries, &entries, 0, ScoreAggregation::Max, &diverse).is_err());
+    }
+    
+    fn compressed_views(vector: &[f32]) -> Vec<(crate::vector_db::compression::CompressedVector, f32)> {
+        use crate::vector_db::compression::{VectorCompressionAlgorithm, VectorCompressionConfig, VectorCompressor};
+        
+        // (compressed vector, expected accuracy of its score)
+        [
+            (VectorCompressionAlgorithm::Quantized8Bit, 2e-2),
+            (VectorCompressionAlgorithm::Quantized16Bit, 1e-4),
+            (VectorCompressionAlgorithm::Float16, 1e-3),
+        ]
+        .into_iter()
+        .map(|(algorithm, tolerance)| {
+            let mut compressor = VectorCompressor::new(VectorCompressionConfig { algorithm, ..Default::default() }).unwrap();
+            (compressor.compress_vector(vector, "v").unwrap(), tolerance)
+        })
+        .collect()
+    }
+    
+    #[test]
+    fn test_cosine_similarity_quantized_kernels() {
+        // Lengths below, at and off the 8-lane boundary exercise the remainders
+        for dimension in [3, 8, 37, 768] {
+            let query: Vec<f32> = (0..dimension).map(|i| ((i * 13 % 17) as f32 - 8.0) / 8.0).collect();
+            let target: Vec<f32> = (0..dimension).map(|i| ((i * 7 % 11) as f32 - 4.5) / 5.0).collect();
+            let exact = SimilaritySearch::cosine_similarity(&query, &target).unwrap();
+            
+            let raw = SimilaritySearch::cosine_similarity_quantized(&query, QuantizedVectorRef::Float32(&target)).unwrap();
+            assert!((raw - exact).abs() < 1e-5);
+            
+            for (compressed, tolerance) in compressed_views(&target) {
+                let view = compressed.quantized_view().unwrap();
+                assert_eq!(view.dimension(), dimension);
+                assert!(view.size_bytes() < dimension * 4);
+                
+                // In-place score equals scoring the decompressed vector
+                let decoded = SimilaritySearch::cosine_similarity(&query, &view.to_vec()).unwrap();
+                let score = SimilaritySearch::cosine_similarity_quantized(&query, view).unwrap();
+                assert!((score - decoded).abs() < 1e-5, "{:?}: {} vs {}", compressed.algorithm, score, decoded);
+                assert!((score - exact).abs() < tolerance, "{:?}: {} vs {}", compressed.algorithm, score, exact);
+                
+                // SIMD and scalar kernels agree
+                let (dot, sum_sq) = SimilaritySearch::quantized_dot_and_norm(&query, view);
+                let (scalar_dot, scalar_sum_sq) = SimilaritySearch::quantized_dot_and_norm_scalar(&query, view);
+                assert!((dot - scalar_dot).abs() <= 1e-4 * scalar_dot.abs().max(1.0));
+                assert!((sum_sq - scalar_sum_sq).abs() <= 1e-4 * scalar_sum_sq.max(1.0));
+            }
+        }
+        
+        let view = QuantizedVectorRef::Float32(&[1.0, 0.0]);
+        assert!(matches!(
+            SimilaritySearch::cosine_similarity_quantized(&[1.0, 0.0, 0.0], view),
+            Err(SimilarityError::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            SimilaritySearch::cosine_similarity_quantized(&[0.0, 0.0], view),
+            Err(SimilarityError::ZeroMagnitude)
+        ));
+    }
+    
+    #[test]
+    fn test_quantized_k_nearest_neighbors_with_rerank() {
+        use crate::vector_db::compression::{VectorCompressionConfig, VectorCompressor};
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        
+        let mut rng = StdRng::seed_from_u64(22);
+        let database: Vec<Vec<f32>> = (0..1000)
+            .map(|_| (0..64).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let query: Vec<f32> = database[123].iter().map(|v| v + rng.gen_range(-0.3..0.3)).collect();
+        
+        let mut compressor = VectorCompressor::new(VectorCompressionConfig::default()).unwrap();
+        let compressed: Vec<_> = database.iter().map(|v| compressor.compress_vector(v, "v").unwrap()).collect();
+        let views: Vec<QuantizedVectorRef<'_>> = compressed.iter().map(|c| c.quantized_view().unwrap()).collect();
+        
+        let mut exact: Vec<(usize, f32)> = database
+            .iter()
+            .map(|v| SimilaritySearch::cosine_similarity(&query, v).unwrap())
+            .enumerate()
+            .collect();
+        exact.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        let exact_top: Vec<usize> = exact.iter().take(10).map(|(i, _)| *i).collect();
+        
+        let config = QuantizedSearchConfig { min_threshold: -1.0, ..Default::default() };
+        let quantized = SimilaritySearch::quantized_k_nearest_neighbors(&query, &views, 10, &config, None).unwrap();
+        assert_eq!(quantized.len(), 10);
+        assert_eq!(quantized[0].index, 123);
+        assert!(quantized.iter().all(|hit| !hit.exact));
+        assert!(quantized.windows(2).all(|w| w[0].similarity >= w[1].similarity));
+        let recall = quantized.iter().filter(|hit| exact_top.contains(&hit.index)).count();
+        assert!(recall >= 7, "quantized recall@10: {}", recall);
+        
+        // Re-ranking restores the exact top-k and exact scores
+        let lookup = |index: usize| Some(database[index].clone());
+        let reranked = SimilaritySearch::quantized_k_nearest_neighbors(&query, &views, 10, &config, Some(&lookup)).unwrap();
+        assert_eq!(reranked.iter().map(|hit| hit.index).collect::<Vec<_>>(), exact_top);
+        assert!(reranked.iter().zip(&exact).all(|(hit, (_, score))| hit.exact && (hit.similarity - score).abs() < 1e-5));
+        
+        // Thresholds apply to exact scores after re-ranking
+        let strict = QuantizedSearchConfig { min_threshold: exact[4].1, ..config.clone() };
+        let reranked = SimilaritySearch::quantized_k_nearest_neighbors(&query, &views, 10, &strict, Some(&lookup)).unwrap();
+        assert_eq!(reranked.len(), 5);
+        
+        // Parallel scoring gives the same ranking
+        let parallel = QuantizedSearchConfig { parallel_threshold: 1, ..config.clone() };
+        assert_eq!(SimilaritySearch::quantized_k_nearest_neighbors(&query, &views, 10, &parallel, None).unwrap(), quantized);
+        assert!(SimilaritySearch::quantized_k_nearest_neighbors(&query, &views, 0, &config, None).is_err());
+    }
+}
@@ -9,12 +9,13 @@ use crate::similarity_search::{
     ConcurrentSearchManager, GlobalSearchMetrics, BenchmarkReport,
 };
 use crate::vector_db::types::EmbeddingEntry;
+use crate::globals::VECTOR_DATABASE;
 use std::sync::Arc;
 use once_cell::sync::Lazy;
 use serde::{Serialize, Deserialize};
 
 // Global concurrent search manager
-static SEARCH_MANAGER: Lazy<ConcurrentSearchManager> = Lazy::new(|| {
+pub(crate) static SEARCH_MANAGER: Lazy<ConcurrentSearchManager> = Lazy::new(|| {
     ConcurrentSearchManager::new(PerformanceConfig::default())
 });
 
@@ -176,6 +177,11 @@ pub async fn optimized_batch_search_similar_notes(
 }
 
 /// Execute approximate nearest neighbors search for large datasets
+/// 
+/// Uses the persistent HNSW index of the global vector database when it is
+/// initialized; candidates are resolved against `database_entries`. Falls back
+/// to an index built over `database_entries` when the persistent index is
+/// unavailable or does not cover the provided entries.
 #[tauri::command]
 pub async fn approximate_search_similar_notes(
     request: SearchRequest,
@@ -184,7 +190,32 @@ pub async fn approximate_search_similar_notes(
     let config = request.config.unwrap_or_default();
     let perf_config = request.perf_config.unwrap_or_default();
     
+    let persistent_index = if database_entries.len() >= perf_config.approximate_threshold {
+        let db_guard = VECTOR_DATABASE.read().await;
+        match db_guard.as_ref() {
+            Some(db) if db.ann_index_len().await > 0 => Some(db.ann_index()),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    
     let result = SEARCH_MANAGER.execute_search(move || {
+        if let Some(ann_index) = persistent_index {
+            let index = ann_index.blocking_read();
+            let indexed = SimilaritySearch::approximate_nearest_neighbors_with_index(
+                &request.query_vector,
+                &index,
+                &database_entries,
+                request.k,
+                &config,
+                &perf_config,
+            )?;
+            if !indexed.results.is_empty() {
+                return Ok(indexed);
+            }
+        }
+        
         SimilaritySearch::approximate_nearest_neighbors(
             &request.query_vector,
             &database_entries,
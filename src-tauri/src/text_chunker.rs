@@ -9,7 +9,15 @@
 //! - **Fixed-size chunking:** Configurable character/token limits
 //! - **Semantic chunking:** Sentence and paragraph boundary detection
 //! - **Overlap management:** Configurable overlap between chunks for context continuity
+//! - **Token limits:** Chunks split to fit the embedding model's context, counted with
+//!   the model's tokenizer
+//! - **Chunk hierarchy:** Sections, paragraphs and sentence windows, where the small
+//!   windows are embedded and link to the larger chunks enclosing them
 //! - **Metadata tracking:** Rich chunk metadata for context reconstruction
+//! - **Block structures:** Frontmatter, callouts, blockquotes, footnotes, math and HTML
+//!   blocks are embedded, stripped or kept intact by markdown-aware chunking
+//! - **Transclusions:** Embeds and block references can be replaced by the text they
+//!   point at, supplied by a `TransclusionResolver`
 //! - **Performance optimized:** Efficient processing using standard Rust string handling
 //!
 //! ## Architecture
@@ -38,8 +46,13 @@
 
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 use std::time::Instant;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::note_metadata::split_frontmatter;
+use crate::tokenizer::{load_tokenizer, CharHeuristicTokenizer, Tokenizer, SPECIAL_TOKEN_COUNT};
 
 /// Errors that can occur during text chunking operations
 #[derive(Debug, Clone, PartialEq)]
@@ -69,6 +82,13 @@ impl std::error::Error for ChunkError {}
 
 pub type ChunkResult<T> = Result<T, ChunkError>;
 
+/// Smallest accepted `max_tokens`, leaving room for text besides the special tokens
+const MIN_MAX_TOKENS: usize = 16;
+
+/// Context length assumed for embedding models that do not report one,
+/// matching the smallest common BERT-style embedding models
+pub const UNKNOWN_CONTEXT_LENGTH: usize = 512;
+
 /// Performance metrics for chunking operations
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
@@ -188,6 +208,18 @@ pub struct ChunkConfig {
     pub preserve_markdown_links: bool,
     /// Whether to strip markdown formatting from content
     pub strip_markdown_formatting: bool,
+    /// How markdown-aware chunking treats each kind of block structure
+    #[serde(default)]
+    pub block_handling: BlockHandlingConfig,
+    /// Maximum tokens per chunk, including the model's special tokens (`None`: no token limit)
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// Tokens repeated between the pieces of a chunk split to respect `max_tokens`
+    #[serde(default)]
+    pub overlap_tokens: usize,
+    /// Parent/child chunk hierarchy used by `chunk_for_embedding`
+    #[serde(default)]
+    pub hierarchy: HierarchyConfig,
 }
 
 impl Default for ChunkConfig {
@@ -203,10 +235,116 @@ impl Default for ChunkConfig {
             preserve_code_blocks: true,
             preserve_markdown_links: true,
             strip_markdown_formatting: false,
+            block_handling: BlockHandlingConfig::default(),
+            max_tokens: None,
+            overlap_tokens: 0,
+            hierarchy: HierarchyConfig::default(),
+        }
+    }
+}
+
+/// How markdown-aware chunking treats a block structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockHandling {
+    /// Chunked like the surrounding text, so the block may be split
+    Embed,
+    /// Left out of chunk content; chunk positions still refer to the original text
+    Strip,
+    /// Never split: the block ends up whole in one chunk, even if that chunk is oversized
+    #[default]
+    KeepIntact,
+}
+
+/// Handling of each kind of block structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BlockHandlingConfig {
+    /// YAML frontmatter at the start of the note
+    pub frontmatter: BlockHandling,
+    /// Obsidian-style `> [!note]` callouts
+    pub callouts: BlockHandling,
+    /// Plain `>` blockquotes
+    pub blockquotes: BlockHandling,
+    /// `[^label]: ...` footnote definitions
+    pub footnotes: BlockHandling,
+    /// `$$ ... $$` math blocks
+    pub math_blocks: BlockHandling,
+    /// Raw HTML blocks and comments
+    pub html_blocks: BlockHandling,
+}
+
+impl BlockHandlingConfig {
+    /// The same handling for every kind of block
+    pub fn all(handling: BlockHandling) -> Self {
+        Self {
+            frontmatter: handling,
+            callouts: handling,
+            blockquotes: handling,
+            footnotes: handling,
+            math_blocks: handling,
+            html_blocks: handling,
         }
     }
 }
 
+/// Granularity of a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkLevel {
+    /// A chunk from one of the flat chunking strategies
+    #[default]
+    Flat,
+    /// A heading and everything up to the next heading
+    Section,
+    /// A block of text between blank lines
+    Paragraph,
+    /// A few consecutive sentences of a paragraph
+    SentenceWindow,
+}
+
+/// Settings for the parent/child chunk hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HierarchyConfig {
+    /// Embed sentence windows linked to their paragraph and section instead of flat chunks
+    pub enabled: bool,
+    /// Sentences per window
+    pub window_sentences: usize,
+    /// Sentences shared by consecutive windows of a paragraph
+    pub window_overlap_sentences: usize,
+}
+
+impl Default for HierarchyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_sentences: 3,
+            window_overlap_sentences: 1,
+        }
+    }
+}
+
+/// Reference from a chunk to a larger chunk enclosing it
+/// 
+/// Parents are not embedded themselves; their text is read back from the
+/// note by span and checked against `text_hash`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParentChunk {
+    /// Identifier of the parent chunk
+    pub chunk_id: String,
+    /// Granularity of the parent chunk
+    pub level: ChunkLevel,
+    /// Byte offset where the parent starts in the source text
+    pub start_position: usize,
+    /// Byte offset where the parent ends in the source text
+    pub end_position: usize,
+    /// SHA-256 hash of the parent text
+    pub text_hash: String,
+    /// First 100 characters of the parent text
+    pub preview: String,
+}
+
 impl ChunkConfig {
     /// Validates the configuration parameters
     pub fn validate(&self) -> ChunkResult<()> {
@@ -234,8 +372,62 @@ impl ChunkConfig {
             ));
         }
         
+        if let Some(max_tokens) = self.max_tokens {
+            if max_tokens < MIN_MAX_TOKENS {
+                return Err(ChunkError::InvalidConfig(
+                    format!("max_tokens must be at least {}", MIN_MAX_TOKENS)
+                ));
+            }
+            
+            if self.overlap_tokens > max_tokens / 2 {
+                return Err(ChunkError::InvalidConfig(
+                    "overlap_tokens should not exceed half of max_tokens".to_string()
+                ));
+            }
+        }
+        
+        if self.hierarchy.window_sentences == 0 {
+            return Err(ChunkError::InvalidConfig(
+                "window_sentences must be at least 1".to_string()
+            ));
+        }
+        
+        if self.hierarchy.window_overlap_sentences >= self.hierarchy.window_sentences {
+            return Err(ChunkError::InvalidConfig(
+                "window_overlap_sentences must be less than window_sentences".to_string()
+            ));
+        }
+        
         Ok(())
     }
+    
+    /// Limits chunks to an embedding model's context length in tokens
+    /// 
+    /// A smaller `max_tokens` that is already configured is kept.
+    pub fn with_context_length(mut self, context_length: usize) -> Self {
+        let max_tokens = self.max_tokens.map_or(context_length, |max_tokens| max_tokens.min(context_length));
+        self.max_tokens = Some(max_tokens);
+        self.overlap_tokens = self.overlap_tokens.min(max_tokens / 2);
+        self
+    }
+    
+    /// Checks that every chunk fits an embedding model's context length
+    /// 
+    /// Without `max_tokens`, chunks fit only if `max_chunk_size` does: a
+    /// character never counts as more than one token.
+    pub fn validate_context_length(&self, context_length: usize) -> ChunkResult<()> {
+        match self.max_tokens {
+            Some(max_tokens) if max_tokens > context_length => Err(ChunkError::InvalidConfig(format!(
+                "max_tokens ({}) exceeds the model's context length of {} tokens",
+                max_tokens, context_length
+            ))),
+            None if self.max_chunk_size + SPECIAL_TOKEN_COUNT > context_length => Err(ChunkError::InvalidConfig(format!(
+                "max_chunk_size ({} characters) may exceed the model's context length of {} tokens; set max_tokens",
+                self.max_chunk_size, context_length
+            ))),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Markdown-specific metadata for chunks
@@ -287,6 +479,38 @@ pub struct ChunkMetadata {
     pub context: HashMap<String, String>,
     /// Markdown-specific metadata
     pub markdown: Option<MarkdownMetadata>,
+    /// Content-addressed identity, stable when other parts of the document change
+    /// (empty until assigned by `ChunkProcessor::assign_chunk_ids`)
+    #[serde(default)]
+    pub chunk_id: String,
+    /// Granularity of the chunk
+    #[serde(default)]
+    pub level: ChunkLevel,
+    /// Chunks enclosing this one, nearest first (empty for flat chunks)
+    #[serde(default)]
+    pub parents: Vec<ParentChunk>,
+}
+
+impl ChunkMetadata {
+    /// Derives a chunk identity from its heading path and content
+    /// 
+    /// The ID has the form `{heading hash}-{content hash}`, so it only changes
+    /// when the chunk's own text or its section changes, never when chunks are
+    /// inserted or removed before it.
+    pub fn content_id(heading_path: &[String], content: &str) -> String {
+        let heading_digest = Sha256::digest(heading_path.join("\u{1f}").as_bytes());
+        let content_digest = Sha256::digest(content.as_bytes());
+        format!(
+            "{}-{}",
+            &format!("{:x}", heading_digest)[..8],
+            &format!("{:x}", content_digest)[..16]
+        )
+    }
+    
+    /// Identifier of the chunk directly enclosing this one, if any
+    pub fn parent_id(&self) -> Option<&str> {
+        self.parents.first().map(|parent| parent.chunk_id.as_str())
+    }
 }
 
 impl Default for ChunkMetadata {
@@ -305,6 +529,9 @@ impl Default for ChunkMetadata {
             next_overlap_size: 0,
             context: HashMap::new(),
             markdown: None,
+            chunk_id: String::new(),
+            level: ChunkLevel::Flat,
+            parents: Vec::new(),
         }
     }
 }
@@ -318,6 +545,17 @@ pub struct TextChunk {
     pub metadata: ChunkMetadata,
 }
 
+/// Chunks of a document at every level of the parent/child hierarchy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkHierarchy {
+    /// Heading blocks: a heading and everything up to the next heading
+    pub sections: Vec<TextChunk>,
+    /// Blank-line separated blocks, each with its section as parent
+    pub paragraphs: Vec<TextChunk>,
+    /// Sentence windows, each with its paragraph and section as parents
+    pub windows: Vec<TextChunk>,
+}
+
 /// Result of chunking operation with performance metrics
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChunkingResult {
@@ -605,12 +843,85 @@ pub enum MarkdownElement {
     Header(usize, String, usize), // level, text, position
     CodeBlock(Option<String>, String, usize, usize), // language, content, start, end
     Link(String, String, Option<String>, usize), // text, url, title, position
+    WikiLink(String, Option<String>, usize), // target (with optional #heading), alias, position
     List(bool, Vec<String>, usize), // ordered, items, position
     Table(Vec<String>, Vec<Vec<String>>, usize), // headers, rows, position
     Paragraph(String, usize), // content, position
     LineBreak(usize), // position
+    Frontmatter(String, usize, usize), // yaml, start, end
+    Callout(String, Option<String>, String, usize, usize), // kind, title, content, start, end
+    Blockquote(String, usize, usize), // content, start, end
+    FootnoteDefinition(String, String, usize, usize), // label, content, start, end
+    MathBlock(String, usize, usize), // content, start, end
+    HtmlBlock(String, usize, usize), // content, start, end
+}
+
+/// Extract the links of a markdown document
+///
+/// Returns `MarkdownElement::Link` and `MarkdownElement::WikiLink` elements in
+/// document order. Links inside fenced code blocks are skipped.
+pub fn extract_markdown_links(text: &str) -> Vec<MarkdownElement> {
+    let mut links = Vec::new();
+    MarkdownParser::default().extract_links_from_elements(&mut links, text);
+    links
+}
+
+/// Byte ranges of the fenced code blocks of a markdown document
+///
+/// An unclosed fence runs to the end of the text.
+pub fn fenced_code_ranges(text: &str) -> Vec<(usize, usize)> {
+    MarkdownParser::default().fenced_code_ranges(text)
 }
 
+/// Extract the inline `#tags` of a markdown document
+///
+/// Returns tags in document order without the leading `#`. A tag starts after
+/// whitespace or at the start of a line, may contain letters, digits, `_`,
+/// `-` and `/` (for nested tags), and must not be purely numeric. Headings,
+/// URL fragments, inline code and fenced code blocks are skipped.
+pub fn extract_inline_tags(text: &str) -> Vec<String> {
+    let parser = MarkdownParser::default();
+    let code_ranges = parser.fenced_code_ranges(text);
+    let mut tags = Vec::new();
+    let mut in_inline_code = false;
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        if code_ranges.iter().any(|&(start, end)| position >= start && position < end) {
+            previous = Some(c);
+            continue;
+        }
+        if c == '`' {
+            in_inline_code = !in_inline_code;
+        } else if c == '\n' {
+            // Inline code never spans paragraphs in practice; recover from a stray backtick
+            in_inline_code = in_inline_code && previous != Some('\n');
+        } else if c == '#' && !in_inline_code && previous.is_none_or(char::is_whitespace) {
+            let mut tag = String::new();
+            while let Some(&(_, next)) = chars.peek() {
+                if next.is_alphanumeric() || matches!(next, '_' | '-' | '/') {
+                    tag.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let tag = tag.trim_end_matches('/');
+            if tag.chars().any(|c| !c.is_ascii_digit()) {
+                tags.push(tag.to_string());
+            }
+            previous = tag.chars().last().or(Some(c));
+            continue;
+        }
+        previous = Some(c);
+    }
+    tags
+}
+
+/// Byte range `start..end` of a block in the source text
+type ByteRange = (usize, usize);
+
 /// Lightweight markdown parser for chunking purposes
 #[derive(Debug, Clone)]
 struct MarkdownParser {
@@ -618,6 +929,8 @@ struct MarkdownParser {
     header_patterns: Vec<&'static str>,
     /// Code block patterns
     code_block_patterns: Vec<&'static str>,
+    /// Tags that open a raw HTML block
+    html_block_tags: Vec<&'static str>,
 }
 
 impl Default for MarkdownParser {
@@ -625,6 +938,12 @@ impl Default for MarkdownParser {
         Self {
             header_patterns: vec!["######", "#####", "####", "###", "##", "#"],
             code_block_patterns: vec!["```", "~~~"],
+            html_block_tags: vec![
+                "address", "article", "aside", "audio", "blockquote", "canvas", "center", "details",
+                "dialog", "div", "dl", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2",
+                "h3", "h4", "h5", "h6", "header", "hr", "iframe", "main", "nav", "ol", "p", "pre",
+                "script", "section", "style", "summary", "svg", "table", "textarea", "ul", "video",
+            ],
         }
     }
 }
@@ -655,6 +974,15 @@ impl MarkdownParser {
                 continue;
             }
             
+            // Check for YAML frontmatter (only at the start of the note)
+            if i == 0 {
+                if let Some((frontmatter, consumed_lines)) = self.parse_frontmatter(text) {
+                    elements.push(frontmatter);
+                    i += consumed_lines;
+                    continue;
+                }
+            }
+            
             // Check for headers (ATX style: # ## ### etc.)
             if let Some(header) = self.parse_atx_header(line, line_pos) {
                 elements.push(header);
@@ -662,6 +990,17 @@ impl MarkdownParser {
                 continue;
             }
             
+            // Check for block structures (math, HTML, quotes and callouts, footnotes)
+            let block = self.parse_math_block(&lines[i..], line_pos, text.len())
+                .or_else(|| self.parse_html_block(&lines[i..], line_pos, text.len()))
+                .or_else(|| self.parse_quote_block(&lines[i..], line_pos, text.len()))
+                .or_else(|| self.parse_footnote_definition(&lines[i..], line_pos, text.len()));
+            if let Some((block, consumed_lines)) = block {
+                elements.push(block);
+                i += consumed_lines;
+                continue;
+            }
+            
             // Check for setext headers (underlined with = or -)
             if i + 1 < lines.len() {
                 if let Some(header) = self.parse_setext_header(lines[i], lines[i + 1], line_pos) {
@@ -774,6 +1113,132 @@ impl MarkdownParser {
         None
     }
     
+    /// Parses YAML frontmatter delimited by `---` at the start of the text
+    fn parse_frontmatter(&self, text: &str) -> Option<(MarkdownElement, usize)> {
+        let (yaml, body_start) = split_frontmatter(text)?;
+        let consumed = text[..body_start].lines().count();
+        Some((MarkdownElement::Frontmatter(yaml.to_string(), 0, body_start), consumed))
+    }
+    
+    /// Parses `$$` math blocks, which may open and close on the same line
+    fn parse_math_block(&self, lines: &[&str], start_pos: usize, text_len: usize) -> Option<(MarkdownElement, usize)> {
+        let opening = lines.first()?.trim().strip_prefix("$$")?;
+        
+        if let Some(formula) = opening.strip_suffix("$$") {
+            let end = Self::block_end(lines, start_pos, 1, text_len);
+            return Some((MarkdownElement::MathBlock(formula.trim().to_string(), start_pos, end), 1));
+        }
+        
+        for (i, &line) in lines[1..].iter().enumerate() {
+            if let Some(closing) = line.trim().strip_suffix("$$") {
+                let consumed = i + 2; // +1 for opening line, +1 for current line
+                
+                // Formula text next to the delimiters belongs to the block
+                let mut content: Vec<&str> = Vec::new();
+                content.extend(Some(opening.trim()).filter(|text| !text.is_empty()));
+                content.extend(&lines[1..consumed - 1]);
+                content.extend(Some(closing.trim()).filter(|text| !text.is_empty()));
+                
+                let end = Self::block_end(lines, start_pos, consumed, text_len);
+                return Some((MarkdownElement::MathBlock(content.join("\n"), start_pos, end), consumed));
+            }
+        }
+        
+        // An unclosed block is ordinary text
+        None
+    }
+    
+    /// Parses raw HTML blocks, which run to the next blank line, and HTML comments
+    fn parse_html_block(&self, lines: &[&str], start_pos: usize, text_len: usize) -> Option<(MarkdownElement, usize)> {
+        let first_line = lines.first()?;
+        let trimmed = first_line.trim_start();
+        if first_line.len() - trimmed.len() > 3 {
+            return None; // Indented code, not HTML
+        }
+        
+        let consumed = if trimmed.starts_with("<!--") {
+            lines.iter().position(|line| line.contains("-->"))? + 1
+        } else {
+            let tag = trimmed.strip_prefix('<')?;
+            let tag = tag.strip_prefix('/').unwrap_or(tag);
+            let name_len = tag.chars().take_while(char::is_ascii_alphanumeric).count();
+            let (name, rest) = tag.split_at(name_len);
+            if !self.html_block_tags.contains(&name.to_ascii_lowercase().as_str()) {
+                return None;
+            }
+            if !(rest.is_empty() || rest.starts_with(['>', '/', ' ', '\t'])) {
+                return None;
+            }
+            lines.iter().position(|line| line.trim().is_empty()).unwrap_or(lines.len())
+        };
+        
+        let end = Self::block_end(lines, start_pos, consumed, text_len);
+        Some((MarkdownElement::HtmlBlock(lines[..consumed].join("\n"), start_pos, end), consumed))
+    }
+    
+    /// Parses `>` blockquotes; a quote starting with `[!kind]` is an Obsidian callout
+    fn parse_quote_block(&self, lines: &[&str], start_pos: usize, text_len: usize) -> Option<(MarkdownElement, usize)> {
+        let consumed = lines.iter().take_while(|line| line.trim_start().starts_with('>')).count();
+        if consumed == 0 {
+            return None;
+        }
+        
+        let quoted: Vec<&str> = lines[..consumed]
+            .iter()
+            .map(|line| {
+                let rest = &line.trim_start()[1..];
+                rest.strip_prefix(' ').unwrap_or(rest)
+            })
+            .collect();
+        let end = Self::block_end(lines, start_pos, consumed, text_len);
+        
+        // Callout header: [!kind] with an optional fold marker (+/-) and title
+        if let Some((kind, rest)) = quoted[0].trim_start().strip_prefix("[!").and_then(|header| header.split_once(']')) {
+            let kind = kind.trim().to_lowercase();
+            if !kind.is_empty() && !kind.contains(char::is_whitespace) {
+                let title = rest.trim_start_matches(['+', '-']).trim();
+                let title = (!title.is_empty()).then(|| title.to_string());
+                let content = quoted[1..].join("\n");
+                return Some((MarkdownElement::Callout(kind, title, content, start_pos, end), consumed));
+            }
+        }
+        
+        Some((MarkdownElement::Blockquote(quoted.join("\n"), start_pos, end), consumed))
+    }
+    
+    /// Parses `[^label]: text` footnote definitions with their indented continuation lines
+    fn parse_footnote_definition(&self, lines: &[&str], start_pos: usize, text_len: usize) -> Option<(MarkdownElement, usize)> {
+        let (label, first_text) = lines.first()?.strip_prefix("[^")?.split_once("]:")?;
+        if label.is_empty() || label.contains(char::is_whitespace) {
+            return None;
+        }
+        
+        let is_continuation = |line: &str| (line.starts_with("  ") || line.starts_with('\t')) && !line.trim().is_empty();
+        let mut content = vec![first_text.trim()];
+        let mut consumed = 1;
+        
+        while let Some(&line) = lines.get(consumed) {
+            if is_continuation(line) {
+                content.push(line.trim());
+            } else if line.trim().is_empty() && lines.get(consumed + 1).is_some_and(|next| is_continuation(next)) {
+                // A blank line continues the definition when an indented paragraph follows
+                content.push("");
+            } else {
+                break;
+            }
+            consumed += 1;
+        }
+        
+        let end = Self::block_end(lines, start_pos, consumed, text_len);
+        Some((MarkdownElement::FootnoteDefinition(label.to_string(), content.join("\n"), start_pos, end), consumed))
+    }
+    
+    /// Byte offset just past the first `consumed` lines of a block starting at `start_pos`
+    fn block_end(lines: &[&str], start_pos: usize, consumed: usize, text_len: usize) -> usize {
+        let block_len: usize = lines[..consumed].iter().map(|line| line.len() + 1).sum(); // +1 for newline
+        (start_pos + block_len).min(text_len)
+    }
+    
     /// Parses markdown tables
     fn parse_table(&self, lines: &[&str], start_pos: usize) -> Option<(MarkdownElement, usize)> {
         if lines.len() < 2 {
@@ -888,18 +1353,43 @@ impl MarkdownParser {
     }
     
     /// Extracts links from all elements
+    ///
+    /// Finds `[text](url)` links and `[[target|alias]]` wikilinks (including
+    /// `![[embeds]]`), skipping fenced code blocks.
     fn extract_links_from_elements(&self, elements: &mut Vec<MarkdownElement>, text: &str) {
         // Simple link extraction without regex dependency
-        // Look for [text](url) patterns
+        let code_ranges = self.fenced_code_ranges(text);
+        let in_code = |pos: usize| code_ranges.iter().any(|&(start, end)| pos >= start && pos < end);
         let mut search_pos = 0;
         
         while let Some(bracket_start) = text[search_pos..].find('[') {
             let abs_bracket_start = search_pos + bracket_start;
             
+            // Wikilinks: [[target#heading|alias]]
+            if text[abs_bracket_start..].starts_with("[[") {
+                if let Some(close) = text[abs_bracket_start + 2..].find("]]") {
+                    let inner = &text[abs_bracket_start + 2..abs_bracket_start + 2 + close];
+                    if !inner.contains('\n') && !inner.contains('[') && !in_code(abs_bracket_start) {
+                        let (target, alias) = match inner.split_once('|') {
+                            Some((target, alias)) => (target.trim(), Some(alias.trim().to_string())),
+                            None => (inner.trim(), None),
+                        };
+                        if !target.is_empty() {
+                            elements.push(MarkdownElement::WikiLink(target.to_string(), alias, abs_bracket_start));
+                        }
+                        search_pos = abs_bracket_start + 2 + close + 2;
+                        continue;
+                    }
+                }
+                search_pos = abs_bracket_start + 1;
+                continue;
+            }
+            
+            // Look for [text](url) patterns
             if let Some(bracket_end) = text[abs_bracket_start..].find(']') {
                 let abs_bracket_end = abs_bracket_start + bracket_end;
                 
-                if abs_bracket_end + 1 < text.len() && text.chars().nth(abs_bracket_end + 1) == Some('(') {
+                if text[abs_bracket_end + 1..].starts_with('(') {
                     if let Some(paren_end) = text[abs_bracket_end + 2..].find(')') {
                         let abs_paren_end = abs_bracket_end + 2 + paren_end;
                         
@@ -915,12 +1405,14 @@ impl MarkdownParser {
                             (link_url.trim().to_string(), None)
                         };
                         
-                        elements.push(MarkdownElement::Link(
-                            link_text.to_string(), 
-                            url, 
-                            title, 
-                            abs_bracket_start
-                        ));
+                        if !in_code(abs_bracket_start) {
+                            elements.push(MarkdownElement::Link(
+                                link_text.to_string(), 
+                                url, 
+                                title, 
+                                abs_bracket_start
+                            ));
+                        }
                         
                         search_pos = abs_paren_end + 1;
                     } else {
@@ -935,6 +1427,36 @@ impl MarkdownParser {
         }
     }
     
+    /// Finds byte ranges of fenced code blocks, including their fences
+    fn fenced_code_ranges(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        let mut open: Option<(&str, usize)> = None;
+        let mut position = 0;
+        
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim();
+            match open {
+                Some((fence, start)) if trimmed.starts_with(fence) => {
+                    ranges.push((start, position + line.len()));
+                    open = None;
+                }
+                Some(_) => {}
+                None => {
+                    open = self.code_block_patterns.iter()
+                        .find(|&&fence| trimmed.starts_with(fence))
+                        .map(|&fence| (fence, position));
+                }
+            }
+            position += line.len();
+        }
+        
+        // An unclosed fence runs to the end of the text
+        if let Some((_, start)) = open {
+            ranges.push((start, text.len()));
+        }
+        ranges
+    }
+    
     /// Finds markdown header boundaries in text
     fn find_header_boundaries(&self, text: &str) -> Vec<usize> {
         let elements = self.parse(text);
@@ -1014,6 +1536,10 @@ impl MarkdownParser {
                 MarkdownElement::Link(text, url, title, _) => {
                     metadata.links.push((text.clone(), url.clone(), title.clone()));
                 },
+                MarkdownElement::WikiLink(target, alias, _) => {
+                    let text = alias.clone().unwrap_or_else(|| target.clone());
+                    metadata.links.push((text, target.clone(), None));
+                },
                 MarkdownElement::List(ordered, items, _) => {
                     metadata.lists.push((*ordered, items.clone()));
                 },
@@ -1061,6 +1587,23 @@ impl MarkdownParser {
     }
 }
 
+/// Text that replaces a byte range of a note in the content of the chunk containing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transclusion {
+    /// Start of the replaced range in the note
+    pub start: usize,
+    /// End of the replaced range in the note (exclusive)
+    pub end: usize,
+    /// Replacement text; empty to drop the range
+    pub text: String,
+}
+
+/// Supplies the transclusions of a note, such as embeds and block references
+pub trait TransclusionResolver: fmt::Debug + Send + Sync {
+    /// Transclusions of `text` in document order, without overlaps
+    fn transclusions(&self, text: &str) -> Vec<Transclusion>;
+}
+
 /// Main text chunking processor
 #[derive(Debug, Clone)]
 pub struct ChunkProcessor {
@@ -1072,6 +1615,10 @@ pub struct ChunkProcessor {
     markdown_parser: MarkdownParser,
     /// Performance monitoring enabled
     monitor_performance: bool,
+    /// Token counter for `max_tokens` limits
+    tokenizer: Arc<dyn Tokenizer>,
+    /// Transclusions applied by `chunk_for_embedding`
+    transclusion_resolver: Option<Arc<dyn TransclusionResolver>>,
 }
 
 impl ChunkProcessor {
@@ -1084,6 +1631,8 @@ impl ChunkProcessor {
             boundary_detector: BoundaryDetector::default(),
             markdown_parser: MarkdownParser::default(),
             monitor_performance: true,
+            tokenizer: Arc::new(CharHeuristicTokenizer::default()),
+            transclusion_resolver: None,
         })
     }
     
@@ -1096,6 +1645,8 @@ impl ChunkProcessor {
             boundary_detector: BoundaryDetector::default(),
             markdown_parser: MarkdownParser::default(),
             monitor_performance: false,
+            tokenizer: Arc::new(CharHeuristicTokenizer::default()),
+            transclusion_resolver: None,
         })
     }
     
@@ -1117,11 +1668,51 @@ impl ChunkProcessor {
             preserve_code_blocks: true,
             preserve_markdown_links: false, // Skip for performance
             strip_markdown_formatting: false,
+            block_handling: BlockHandlingConfig::default(),
+            max_tokens: None,
+            overlap_tokens: 0,
+            hierarchy: HierarchyConfig::default(),
         };
         
         Self::new(config)
     }
     
+    /// Creates a chunk processor whose chunks fit an embedding model
+    /// 
+    /// Tokens are counted with the model's tokenizer (see [`load_tokenizer`]),
+    /// and chunks are limited to `context_length`, or to
+    /// [`UNKNOWN_CONTEXT_LENGTH`] when the model's is unknown. The chunk
+    /// hierarchy is enabled, so `chunk_for_embedding` yields sentence windows.
+    pub fn for_embedding_model(model_name: &str, context_length: Option<usize>) -> ChunkResult<Self> {
+        let context_length = context_length.unwrap_or(UNKNOWN_CONTEXT_LENGTH);
+        let mut config = ChunkConfig::default().with_context_length(context_length);
+        config.hierarchy.enabled = true;
+        
+        Ok(Self::new(config)?.with_tokenizer(load_tokenizer(model_name)))
+    }
+    
+    /// Replaces the tokenizer used for `max_tokens` limits
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+    
+    /// Returns the tokenizer used for `max_tokens` limits
+    pub fn tokenizer(&self) -> &dyn Tokenizer {
+        self.tokenizer.as_ref()
+    }
+    
+    /// Sets the resolver whose transclusions `chunk_for_embedding` applies
+    pub fn with_transclusion_resolver(mut self, resolver: Arc<dyn TransclusionResolver>) -> Self {
+        self.transclusion_resolver = Some(resolver);
+        self
+    }
+    
+    /// Number of tokens the embedding model sees for `text`, special tokens included
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.tokenizer.count_tokens(text) + SPECIAL_TOKEN_COUNT
+    }
+    
     /// Returns the current configuration
     pub fn config(&self) -> &ChunkConfig {
         &self.config
@@ -1145,12 +1736,244 @@ impl ChunkProcessor {
             return Err(ChunkError::InvalidInput("Input text is empty".to_string()));
         }
         
-        match self.config.strategy {
-            ChunkingStrategy::FixedSize => self.chunk_fixed_size(text),
-            ChunkingStrategy::Semantic => self.chunk_semantic(text),
-            ChunkingStrategy::Hybrid => self.chunk_hybrid(text),
-            ChunkingStrategy::MarkdownAware => self.chunk_markdown_aware(text),
+        let chunks = match self.config.strategy {
+            ChunkingStrategy::FixedSize => self.chunk_fixed_size(text)?,
+            ChunkingStrategy::Semantic => self.chunk_semantic(text)?,
+            ChunkingStrategy::Hybrid => self.chunk_hybrid(text)?,
+            ChunkingStrategy::MarkdownAware => self.chunk_markdown_aware(text)?,
+        };
+        
+        self.enforce_token_limit(chunks)
+    }
+    
+    /// Returns the heading path (outermost heading first) for each chunk
+    /// 
+    /// The path lists the headings in effect at each chunk's start position,
+    /// including a heading that starts the chunk itself. The document is parsed
+    /// once regardless of the number of chunks, so this works for every
+    /// chunking strategy, not only `MarkdownAware`.
+    pub fn heading_paths(&self, text: &str, chunks: &[TextChunk]) -> Vec<Vec<String>> {
+        let elements = self.markdown_parser.parse(text);
+        
+        chunks
+            .iter()
+            .map(|chunk| {
+                self.markdown_parser
+                    .build_structure_context(&elements, chunk.metadata.start_position + 1)
+                    .into_iter()
+                    .map(|(_, heading)| heading)
+                    .collect()
+            })
+            .collect()
+    }
+    
+    /// Assigns content-addressed IDs to chunks
+    /// 
+    /// `heading_paths` must come from `heading_paths` for the same chunks.
+    /// Identical chunks under the same heading get `-2`, `-3`, ... suffixes in
+    /// document order so every ID in a document is unique.
+    pub fn assign_chunk_ids(&self, chunks: &mut [TextChunk], heading_paths: &[Vec<String>]) {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        
+        for (index, chunk) in chunks.iter_mut().enumerate() {
+            let heading_path = heading_paths.get(index).map(Vec::as_slice).unwrap_or(&[]);
+            let base_id = ChunkMetadata::content_id(heading_path, &chunk.content);
+            let occurrences = seen.entry(base_id.clone()).or_insert(0);
+            *occurrences += 1;
+            
+            chunk.metadata.chunk_id = if *occurrences == 1 {
+                base_id
+            } else {
+                format!("{}-{}", base_id, occurrences)
+            };
+        }
+    }
+    
+    /// Chunks the input text into the chunks that get embedded
+    /// 
+    /// With the hierarchy enabled these are the sentence windows of
+    /// `chunk_hierarchy`, otherwise the flat chunks of `chunk_text`. Chunk IDs
+    /// are assigned either way.
+    /// 
+    /// With a transclusion resolver, every transclusion that lies inside a
+    /// chunk's span is then replaced in the chunk's content. Positions and IDs
+    /// still refer to the note itself. Chunks the inlined text pushes over
+    /// `max_tokens` are split again; the pieces keep the span of their chunk.
+    pub fn chunk_for_embedding(&self, text: &str) -> ChunkResult<Vec<TextChunk>> {
+        let mut chunks = if self.config.hierarchy.enabled {
+            self.chunk_hierarchy(text)?.windows
+        } else {
+            let mut chunks = self.chunk_text(text)?;
+            let heading_paths = self.heading_paths(text, &chunks);
+            self.assign_chunk_ids(&mut chunks, &heading_paths);
+            chunks
+        };
+        
+        if let Some(resolver) = &self.transclusion_resolver {
+            let transclusions = resolver.transclusions(text);
+            if !transclusions.is_empty() {
+                self.apply_transclusions(text, &mut chunks, &transclusions);
+                chunks = self.split_transcluded_chunks(chunks)?;
+            }
         }
+        Ok(chunks)
+    }
+    
+    /// Splits chunks that inlined transclusions pushed over `max_tokens`
+    /// 
+    /// Pieces keep the heading part of the chunk's ID and get their own
+    /// content hash, like every other chunk ID.
+    fn split_transcluded_chunks(&self, chunks: Vec<TextChunk>) -> ChunkResult<Vec<TextChunk>> {
+        let Some(max_tokens) = self.config.max_tokens else {
+            return Ok(chunks);
+        };
+        
+        if chunks.iter().all(|chunk| self.count_tokens(&chunk.content) <= max_tokens) {
+            return Ok(chunks);
+        }
+        
+        let mut result = Vec::with_capacity(chunks.len() + 1);
+        for chunk in chunks {
+            if self.count_tokens(&chunk.content) <= max_tokens {
+                result.push(chunk);
+                continue;
+            }
+            
+            let (start, end) = (chunk.metadata.start_position, chunk.metadata.end_position);
+            let heading_digest = chunk.metadata.chunk_id.split('-').next().unwrap_or_default().to_string();
+            for mut piece in self.split_chunk_by_tokens(chunk, max_tokens) {
+                // Offsets into inlined text do not map back to the note
+                piece.metadata.start_position = start;
+                piece.metadata.end_position = end;
+                let content_digest = Sha256::digest(piece.content.as_bytes());
+                piece.metadata.chunk_id = format!("{}-{}", heading_digest, &format!("{:x}", content_digest)[..16]);
+                result.push(piece);
+            }
+        }
+        
+        self.finalize_chunks_metadata_optimized(result)
+    }
+    
+    /// Replaces transclusions in the content of the chunks whose span contains them
+    fn apply_transclusions(&self, text: &str, chunks: &mut [TextChunk], transclusions: &[Transclusion]) {
+        if transclusions.is_empty() {
+            return;
+        }
+        
+        for chunk in chunks.iter_mut() {
+            let (start, end) = (chunk.metadata.start_position, chunk.metadata.end_position);
+            let mut content = String::with_capacity(chunk.content.len());
+            let mut rest = chunk.content.as_str();
+            let mut replaced = false;
+            
+            for transclusion in transclusions.iter().filter(|t| t.start >= start && t.end <= end && t.start < t.end) {
+                // Chunk content is not always a verbatim copy of its span, so the original text is searched for
+                let original = &text[transclusion.start..transclusion.end];
+                if let Some(offset) = rest.find(original) {
+                    content.push_str(&rest[..offset]);
+                    content.push_str(&transclusion.text);
+                    rest = &rest[offset + original.len()..];
+                    replaced = true;
+                }
+            }
+            
+            if replaced {
+                content.push_str(rest);
+                chunk.metadata.character_count = content.len();
+                chunk.metadata.word_count = self.count_words_optimized(&content);
+                chunk.content = content;
+            }
+        }
+    }
+    
+    /// Splits text into sections, paragraphs and sentence windows
+    /// 
+    /// Sections start at every heading, paragraphs are separated by blank
+    /// lines outside code fences, and windows hold `window_sentences`
+    /// sentences of a paragraph. Windows longer than `max_chunk_size` or
+    /// `max_tokens` are split further. Frontmatter is left out. Every chunk gets
+    /// a content-addressed ID, and windows and paragraphs list their parents.
+    pub fn chunk_hierarchy(&self, text: &str) -> ChunkResult<ChunkHierarchy> {
+        if text.is_empty() {
+            return Err(ChunkError::InvalidInput("Input text is empty".to_string()));
+        }
+        
+        let body_start = split_frontmatter(text).map_or(0, |(_, body_start)| body_start);
+        let mut section_starts = vec![body_start];
+        section_starts.extend(
+            self.markdown_parser
+                .parse(text)
+                .iter()
+                .filter_map(|element| match element {
+                    MarkdownElement::Header(_, _, position) if *position > body_start => Some(*position),
+                    _ => None,
+                }),
+        );
+        section_starts.push(text.len());
+        section_starts.dedup();
+        
+        let mut hierarchy = ChunkHierarchy::default();
+        // Index of the enclosing paragraph and section of every window
+        let mut window_parents: Vec<(usize, usize)> = Vec::new();
+        let mut paragraph_sections: Vec<usize> = Vec::new();
+        
+        for bounds in section_starts.windows(2) {
+            let Some((section_start, section_end)) = trimmed_range(text, bounds[0], bounds[1]) else {
+                continue;
+            };
+            let section_index = hierarchy.sections.len();
+            hierarchy.sections.push(self.hierarchy_chunk(text, section_start, section_end, ChunkLevel::Section));
+            
+            for (paragraph_start, paragraph_end) in self.paragraph_ranges(text, section_start, section_end) {
+                let paragraph_index = hierarchy.paragraphs.len();
+                hierarchy.paragraphs.push(self.hierarchy_chunk(text, paragraph_start, paragraph_end, ChunkLevel::Paragraph));
+                paragraph_sections.push(section_index);
+                
+                for (window_start, window_end) in self.sentence_window_ranges(text, paragraph_start, paragraph_end) {
+                    for (start, end) in self.split_range_by_size(text, window_start, window_end) {
+                        hierarchy.windows.push(self.hierarchy_chunk(text, start, end, ChunkLevel::SentenceWindow));
+                        window_parents.push((paragraph_index, section_index));
+                    }
+                }
+            }
+        }
+        
+        for (chunks, prefix) in [(&mut hierarchy.sections, "section-"), (&mut hierarchy.paragraphs, "paragraph-"), (&mut hierarchy.windows, "")] {
+            let heading_paths = self.heading_paths(text, chunks);
+            self.assign_chunk_ids(chunks, &heading_paths);
+            for chunk in chunks.iter_mut() {
+                chunk.metadata.chunk_id.insert_str(0, prefix);
+            }
+        }
+        
+        for (paragraph, &section_index) in hierarchy.paragraphs.iter_mut().zip(&paragraph_sections) {
+            paragraph.metadata.parents = vec![parent_chunk(&hierarchy.sections[section_index])];
+        }
+        for (window, &(paragraph_index, section_index)) in hierarchy.windows.iter_mut().zip(&window_parents) {
+            window.metadata.parents = vec![
+                parent_chunk(&hierarchy.paragraphs[paragraph_index]),
+                parent_chunk(&hierarchy.sections[section_index]),
+            ];
+        }
+        
+        // Consecutive windows of a paragraph share sentences
+        for index in 1..hierarchy.windows.len() {
+            let previous_end = hierarchy.windows[index - 1].metadata.end_position;
+            let start = hierarchy.windows[index].metadata.start_position;
+            if window_parents[index - 1].0 == window_parents[index].0 && previous_end > start {
+                hierarchy.windows[index - 1].metadata.has_next_overlap = true;
+                hierarchy.windows[index - 1].metadata.next_overlap_size = previous_end - start;
+                hierarchy.windows[index].metadata.has_previous_overlap = true;
+                hierarchy.windows[index].metadata.previous_overlap_size = previous_end - start;
+            }
+        }
+        
+        hierarchy.sections = self.finalize_chunks_metadata_optimized(hierarchy.sections)?;
+        hierarchy.paragraphs = self.finalize_chunks_metadata_optimized(hierarchy.paragraphs)?;
+        hierarchy.windows = self.enforce_token_limit(hierarchy.windows)?;
+        hierarchy.windows = self.finalize_chunks_metadata_optimized(hierarchy.windows)?;
+        
+        Ok(hierarchy)
     }
     
     /// Chunks the input text with performance monitoring
@@ -1169,6 +1992,7 @@ impl ChunkProcessor {
             ChunkingStrategy::Hybrid => self.chunk_hybrid(text)?,
             ChunkingStrategy::MarkdownAware => self.chunk_markdown_aware(text)?,
         };
+        let chunks = self.enforce_token_limit(chunks)?;
         
         // Calculate metrics
         let processing_time = start_time.elapsed();
@@ -1187,7 +2011,8 @@ impl ChunkProcessor {
         
         // For very large documents, use streaming approach
         if text.len() > 100_000 {
-            return self.chunk_large_text_streaming(text);
+            let chunks = self.chunk_large_text_streaming(text)?;
+            return self.enforce_token_limit(chunks);
         }
         
         // For smaller documents, use regular chunking
@@ -1389,27 +2214,35 @@ impl ChunkProcessor {
             Vec::new()
         };
         
+        // Block structures are never split, and stripped ones are left out of chunk content
+        let (atomic_ranges, stripped_ranges) = self.block_ranges(&elements);
+        
         let mut position = 0;
         
         while position < text_len {
             let target_end = (position + self.config.max_chunk_size).min(text_len);
             
-            // Check if we're inside a code block - if so, preserve it intact
-            let in_code_block = code_block_boundaries.iter()
+            // Check if we're inside a code block or at a block structure - if so, preserve it intact
+            let in_block = code_block_boundaries.iter()
+                .chain(&atomic_ranges)
                 .find(|(start, end)| position >= *start && position < *end);
                 
-            let chunk_end = if let Some((_, code_end)) = in_code_block {
-                // Include the entire code block
-                (*code_end).min(text_len)
+            let chunk_end = if let Some((_, block_end)) = in_block {
+                // Include the entire block
+                (*block_end).min(text_len)
             } else {
-                // Find the best boundary considering markdown structure
-                self.find_markdown_boundary(text, target_end, &header_boundaries, position)
+                // Find the best boundary considering markdown structure, outside of blocks
+                let boundary = self.find_markdown_boundary(text, target_end, &header_boundaries, position);
+                self.boundary_outside_blocks(boundary, position, &atomic_ranges)
             };
             
-            let chunk_content = text[position..chunk_end].to_string();
-            if !chunk_content.trim().is_empty() && 
-               chunk_content.len() >= self.config.min_chunk_size || 
-               chunk_end >= text_len {
+            // Size limits apply to the original text so stripping never drops the text around a block
+            let chunk_content = Self::text_without_ranges(text, position, chunk_end, &stripped_ranges);
+            let fully_stripped = chunk_content.trim().is_empty() && chunk_content.len() < chunk_end - position;
+            if !fully_stripped && (
+               !chunk_content.trim().is_empty() && 
+               chunk_end - position >= self.config.min_chunk_size || 
+               chunk_end >= text_len) {
                 
                 let metadata = self.create_markdown_chunk_metadata(
                     &chunk_content,
@@ -1435,14 +2268,18 @@ impl ChunkProcessor {
             }
             
             // Calculate next position with overlap, but respect markdown boundaries
-            let next_position = if in_code_block.is_some() {
-                // Don't overlap code blocks
+            let next_position = if in_block.is_some() || atomic_ranges.iter().any(|(start, _)| *start == chunk_end) {
+                // Don't overlap blocks, or repeat text before a block that starts the next chunk
                 chunk_end
             } else {
-                chunk_end.saturating_sub(self.config.overlap_size)
+                // Never start the next chunk inside a block
+                let overlap_start = chunk_end.saturating_sub(self.config.overlap_size);
+                atomic_ranges.iter()
+                    .find(|(start, end)| overlap_start > *start && overlap_start < *end)
+                    .map_or(overlap_start, |(_, end)| (*end).min(chunk_end))
             };
             
-            position = if next_position >= chunk_end {
+            position = if next_position >= chunk_end || next_position <= position {
                 chunk_end
             } else {
                 next_position
@@ -1452,6 +2289,69 @@ impl ChunkProcessor {
         self.finalize_chunks_metadata_optimized(chunks)
     }
     
+    /// Byte ranges of block structures that must not be split, and the subset left out of chunks
+    /// 
+    /// Which structures are listed follows `block_handling`. Both lists are sorted by position.
+    fn block_ranges(&self, elements: &[MarkdownElement]) -> (Vec<ByteRange>, Vec<ByteRange>) {
+        let handling = &self.config.block_handling;
+        let mut atomic_ranges = Vec::new();
+        let mut stripped_ranges = Vec::new();
+        
+        // Block elements are parsed in document order, so the ranges come out sorted
+        for element in elements {
+            let (block_handling, range) = match element {
+                MarkdownElement::Frontmatter(_, start, end) => (handling.frontmatter, (*start, *end)),
+                MarkdownElement::Callout(_, _, _, start, end) => (handling.callouts, (*start, *end)),
+                MarkdownElement::Blockquote(_, start, end) => (handling.blockquotes, (*start, *end)),
+                MarkdownElement::FootnoteDefinition(_, _, start, end) => (handling.footnotes, (*start, *end)),
+                MarkdownElement::MathBlock(_, start, end) => (handling.math_blocks, (*start, *end)),
+                MarkdownElement::HtmlBlock(_, start, end) => (handling.html_blocks, (*start, *end)),
+                _ => continue,
+            };
+            match block_handling {
+                BlockHandling::Embed => {}
+                BlockHandling::KeepIntact => atomic_ranges.push(range),
+                BlockHandling::Strip => {
+                    atomic_ranges.push(range);
+                    stripped_ranges.push(range);
+                }
+            }
+        }
+        
+        (atomic_ranges, stripped_ranges)
+    }
+    
+    /// Moves a chunk boundary that falls inside a block to the block's start, or past
+    /// its end when ending before it would leave a chunk below `min_chunk_size`
+    fn boundary_outside_blocks(&self, boundary: usize, current_pos: usize, atomic_ranges: &[ByteRange]) -> usize {
+        match atomic_ranges.iter().find(|(start, end)| boundary > *start && boundary < *end) {
+            Some(&(start, _)) if start > current_pos && start - current_pos >= self.config.min_chunk_size => start,
+            Some(&(_, end)) => end,
+            None => boundary,
+        }
+    }
+    
+    /// Text between `start` and `end` without the parts covered by sorted `ranges`
+    fn text_without_ranges(text: &str, start: usize, end: usize, ranges: &[ByteRange]) -> String {
+        let mut content = String::with_capacity(end - start);
+        let mut cursor = start;
+        
+        for &(range_start, range_end) in ranges {
+            if range_end <= cursor || range_start >= end {
+                continue;
+            }
+            if range_start > cursor {
+                content.push_str(&text[cursor..range_start]);
+            }
+            cursor = range_end.min(end);
+        }
+        
+        if cursor < end {
+            content.push_str(&text[cursor..end]);
+        }
+        content
+    }
+    
     /// Finds the best boundary for markdown-aware chunking
     fn find_markdown_boundary(&self, text: &str, target_pos: usize, header_boundaries: &[usize], current_pos: usize) -> usize {
         if target_pos >= text.len() {
@@ -1509,6 +2409,9 @@ impl ChunkProcessor {
             next_overlap_size: if has_next_overlap { self.config.overlap_size } else { 0 },
             context: HashMap::new(),
             markdown: None,
+            chunk_id: String::new(),
+            level: ChunkLevel::Flat,
+            parents: Vec::new(),
         }
     }
     
@@ -1533,10 +2436,17 @@ impl ChunkProcessor {
                     MarkdownElement::Header(_, _, pos) => *pos,
                     MarkdownElement::CodeBlock(_, _, start, _) => *start,
                     MarkdownElement::Link(_, _, _, pos) => *pos,
+                    MarkdownElement::WikiLink(_, _, pos) => *pos,
                     MarkdownElement::List(_, _, pos) => *pos,
                     MarkdownElement::Table(_, _, pos) => *pos,
                     MarkdownElement::Paragraph(_, pos) => *pos,
                     MarkdownElement::LineBreak(pos) => *pos,
+                    MarkdownElement::Frontmatter(_, start, _) => *start,
+                    MarkdownElement::Callout(_, _, _, start, _) => *start,
+                    MarkdownElement::Blockquote(_, start, _) => *start,
+                    MarkdownElement::FootnoteDefinition(_, _, start, _) => *start,
+                    MarkdownElement::MathBlock(_, start, _) => *start,
+                    MarkdownElement::HtmlBlock(_, start, _) => *start,
                 };
                 element_pos >= start_pos && element_pos < end_pos
             })
@@ -1568,6 +2478,9 @@ impl ChunkProcessor {
             next_overlap_size: if has_next_overlap { self.config.overlap_size } else { 0 },
             context: HashMap::new(),
             markdown: Some(markdown_meta),
+            chunk_id: String::new(),
+            level: ChunkLevel::Flat,
+            parents: Vec::new(),
         }
     }
     
@@ -1713,6 +2626,270 @@ impl ChunkProcessor {
         Ok(chunks)
     }
     
+    /// Creates a chunk of the hierarchy for a byte range of the text
+    fn hierarchy_chunk(&self, text: &str, start: usize, end: usize, level: ChunkLevel) -> TextChunk {
+        let content = &text[start..end];
+        let mut metadata = self.create_chunk_metadata_optimized(content, start, end, 0, false, false);
+        metadata.level = level;
+        TextChunk::new(content.to_string(), metadata)
+    }
+    
+    /// Byte ranges of the blank-line separated blocks of a section
+    /// 
+    /// Blank lines inside code fences do not end a block, and a heading line
+    /// is kept with the block that follows it.
+    fn paragraph_ranges(&self, text: &str, start: usize, end: usize) -> Vec<ByteRange> {
+        let mut ranges: Vec<ByteRange> = Vec::new();
+        let mut paragraph_start = start;
+        let mut in_fence = false;
+        let mut line_start = start;
+        
+        while line_start < end {
+            let line_end = text[line_start..end].find('\n').map_or(end, |offset| line_start + offset + 1);
+            let line = text[line_start..line_end].trim();
+            if line.starts_with("```") || line.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            if line.is_empty() && !in_fence {
+                ranges.extend(trimmed_range(text, paragraph_start, line_start));
+                paragraph_start = line_end;
+            }
+            line_start = line_end;
+        }
+        ranges.extend(trimmed_range(text, paragraph_start, end));
+        
+        // Merge heading-only blocks into the block after them
+        let mut merged: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+        let mut pending_start: Option<usize> = None;
+        let last_index = ranges.len().saturating_sub(1);
+        for (index, (range_start, range_end)) in ranges.into_iter().enumerate() {
+            let block = &text[range_start..range_end];
+            if block.starts_with('#') && !block.contains('\n') && index < last_index {
+                pending_start.get_or_insert(range_start);
+                continue;
+            }
+            merged.push((pending_start.take().unwrap_or(range_start), range_end));
+        }
+        merged
+    }
+    
+    /// Byte ranges of the sentence windows of a paragraph
+    fn sentence_window_ranges(&self, text: &str, start: usize, end: usize) -> Vec<ByteRange> {
+        let sentences = self.sentence_ranges(text, start, end);
+        let window = self.config.hierarchy.window_sentences;
+        let step = window - self.config.hierarchy.window_overlap_sentences;
+        
+        let mut ranges = Vec::new();
+        let mut first = 0;
+        while first < sentences.len() {
+            let last = (first + window).min(sentences.len()) - 1;
+            ranges.push((sentences[first].0, sentences[last].1));
+            if last + 1 >= sentences.len() {
+                break;
+            }
+            first += step;
+        }
+        ranges
+    }
+    
+    /// Byte ranges of the sentences of a paragraph
+    /// 
+    /// Sentences end at `.`, `!`, `?` or `…` followed by whitespace, after
+    /// heading lines and at line breaks before list items, table rows, quotes
+    /// and headings. A code block is a single sentence.
+    fn sentence_ranges(&self, text: &str, start: usize, end: usize) -> Vec<ByteRange> {
+        let paragraph = &text[start..end];
+        if paragraph.starts_with("```") || paragraph.starts_with("~~~") {
+            return vec![(start, end)];
+        }
+        
+        let mut ranges = Vec::new();
+        let mut sentence_start = 0;
+        let mut chars = paragraph.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            let ends_sentence = match c {
+                '.' | '!' | '?' | '…' => chars.peek().is_none_or(|&(_, next)| next.is_whitespace()),
+                '\n' => {
+                    let line = paragraph[..index].rsplit('\n').next().unwrap_or_default();
+                    line.trim_start().starts_with('#')
+                        || paragraph[index + 1..]
+                            .trim_start_matches([' ', '\t'])
+                            .starts_with(|next: char| matches!(next, '-' | '*' | '+' | '|' | '>' | '#') || next.is_ascii_digit())
+                }
+                _ => false,
+            };
+            if ends_sentence {
+                let sentence_end = index + c.len_utf8();
+                ranges.extend(trimmed_range(text, start + sentence_start, start + sentence_end));
+                sentence_start = sentence_end;
+            }
+        }
+        ranges.extend(trimmed_range(text, start + sentence_start, end));
+        ranges
+    }
+    
+    /// Splits a byte range into pieces of at most `max_chunk_size` bytes at whitespace
+    fn split_range_by_size(&self, text: &str, start: usize, end: usize) -> Vec<ByteRange> {
+        let max_chunk_size = self.config.max_chunk_size;
+        let mut ranges = Vec::new();
+        let mut piece_start = start;
+        
+        while end - piece_start > max_chunk_size {
+            let limit = self.boundary_detector.find_char_boundary_at_or_before(text, piece_start + max_chunk_size);
+            let cut = text[piece_start..limit]
+                .rfind(char::is_whitespace)
+                .map(|offset| piece_start + offset)
+                .filter(|&cut| cut > piece_start)
+                .unwrap_or(limit);
+            ranges.extend(trimmed_range(text, piece_start, cut));
+            piece_start = trimmed_range(text, cut, end).map_or(end, |(next_start, _)| next_start);
+        }
+        ranges.extend(trimmed_range(text, piece_start, end));
+        ranges
+    }
+    
+    /// Splits chunks that exceed `max_tokens` into pieces that fit
+    /// 
+    /// Each piece ends at the last sentence end or whitespace that keeps it
+    /// within the limit, and consecutive pieces share about `overlap_tokens`
+    /// tokens. Chunks are returned unchanged when no token limit is configured.
+    fn enforce_token_limit(&self, chunks: Vec<TextChunk>) -> ChunkResult<Vec<TextChunk>> {
+        let Some(max_tokens) = self.config.max_tokens else {
+            return Ok(chunks);
+        };
+        
+        if chunks.iter().all(|chunk| self.count_tokens(&chunk.content) <= max_tokens) {
+            return Ok(chunks);
+        }
+        
+        let mut result = Vec::with_capacity(chunks.len() + 1);
+        for chunk in chunks {
+            if self.count_tokens(&chunk.content) <= max_tokens {
+                result.push(chunk);
+            } else {
+                result.extend(self.split_chunk_by_tokens(chunk, max_tokens));
+            }
+        }
+        
+        self.finalize_chunks_metadata_optimized(result)
+    }
+    
+    /// Splits one oversized chunk into pieces of at most `max_tokens` tokens
+    fn split_chunk_by_tokens(&self, chunk: TextChunk, max_tokens: usize) -> Vec<TextChunk> {
+        let content = chunk.content.as_str();
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let mut start = 0;
+        
+        while start < content.len() {
+            let rest = &content[start..];
+            let end = if self.count_tokens(rest) <= max_tokens {
+                content.len()
+            } else {
+                start + self.token_limited_prefix_len(rest, max_tokens)
+            };
+            ranges.push((start, end));
+            
+            if end >= content.len() {
+                break;
+            }
+            
+            let next_start = end - self.token_overlap_len(&content[start..end]);
+            start = if next_start > start { next_start } else { end };
+            // Pieces start at a word, not at the whitespace the previous one ended on
+            start += content[start..].len() - content[start..].trim_start().len();
+        }
+        
+        let piece_count = ranges.len();
+        let chunk_start = chunk.metadata.start_position;
+        let chunk_end = chunk.metadata.end_position;
+        
+        ranges
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| {
+                let piece = &content[start..end];
+                let mut metadata = chunk.metadata.clone();
+                metadata.start_position = (chunk_start + start).min(chunk_end);
+                metadata.end_position = (chunk_start + end).min(chunk_end);
+                metadata.character_count = piece.len();
+                metadata.word_count = self.count_words_optimized(piece);
+                metadata.sentence_count = self.count_sentences_optimized(piece);
+                
+                if index > 0 {
+                    let previous_end = ranges[index - 1].1;
+                    metadata.has_previous_overlap = previous_end > start;
+                    metadata.previous_overlap_size = previous_end.saturating_sub(start);
+                }
+                if index + 1 < piece_count {
+                    let next_start = ranges[index + 1].0;
+                    metadata.has_next_overlap = end > next_start;
+                    metadata.next_overlap_size = end.saturating_sub(next_start);
+                }
+                
+                TextChunk { content: piece.to_string(), metadata }
+            })
+            .collect()
+    }
+    
+    /// Length of the longest prefix of `text` within `max_tokens`, cut at a sentence end or whitespace if possible
+    fn token_limited_prefix_len(&self, text: &str, max_tokens: usize) -> usize {
+        let boundaries: Vec<usize> = text
+            .char_indices()
+            .map(|(index, _)| index)
+            .skip(1)
+            .chain(std::iter::once(text.len()))
+            .collect();
+        
+        // Binary search for the last character boundary whose prefix fits
+        let (mut low, mut high) = (0, boundaries.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if self.count_tokens(&text[..boundaries[mid - 1]]) <= max_tokens {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        // Always make progress, even if a single character is over the limit
+        let fit = boundaries[low.saturating_sub(1)];
+        
+        let prefix = &text[..fit];
+        let min_cut = fit / 2;
+        let sentence_cut = prefix
+            .rfind(['.', '!', '?', '\n'])
+            .map(|index| index + 1)
+            .filter(|&index| index > min_cut);
+        let word_cut = prefix.rfind(char::is_whitespace).filter(|&index| index > min_cut);
+        
+        match sentence_cut.or(word_cut) {
+            Some(cut) if self.count_tokens(&text[..cut]) <= max_tokens => cut,
+            _ => fit,
+        }
+    }
+    
+    /// Length of the trailing words of `piece` that make up at most `overlap_tokens` tokens
+    fn token_overlap_len(&self, piece: &str) -> usize {
+        if self.config.overlap_tokens == 0 {
+            return 0;
+        }
+        
+        let mut overlap_start = piece.len();
+        let word_starts = piece
+            .char_indices()
+            .filter(|&(index, c)| index > 0 && !c.is_whitespace() && piece[..index].ends_with(char::is_whitespace))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        
+        for &word_start in word_starts.iter().rev() {
+            if self.tokenizer.count_tokens(&piece[word_start..]) > self.config.overlap_tokens {
+                break;
+            }
+            overlap_start = word_start;
+        }
+        
+        piece.len() - overlap_start
+    }
+    
     /// Creates metadata for a chunk (optimized version)
     fn create_chunk_metadata_optimized(
         &self,
@@ -1745,10 +2922,33 @@ impl ChunkProcessor {
             next_overlap_size: if has_next_overlap { self.config.overlap_size } else { 0 },
             context: HashMap::with_capacity(4), // Pre-allocate with expected capacity
             markdown: None,
+            chunk_id: String::new(),
+            level: ChunkLevel::Flat,
+            parents: Vec::new(),
         }
     }
 }
 
+/// Narrows a byte range to its text without surrounding whitespace (`None` if blank)
+fn trimmed_range(text: &str, start: usize, end: usize) -> Option<ByteRange> {
+    let slice = &text[start..end];
+    let trimmed_start = start + (slice.len() - slice.trim_start().len());
+    let trimmed_end = start + slice.trim_end().len();
+    (trimmed_start < trimmed_end).then_some((trimmed_start, trimmed_end))
+}
+
+/// Reference to a chunk of the hierarchy as the parent of another chunk
+fn parent_chunk(chunk: &TextChunk) -> ParentChunk {
+    ParentChunk {
+        chunk_id: chunk.metadata.chunk_id.clone(),
+        level: chunk.metadata.level,
+        start_position: chunk.metadata.start_position,
+        end_position: chunk.metadata.end_position,
+        text_hash: format!("{:x}", Sha256::digest(chunk.content.as_bytes())),
+        preview: chunk.content.chars().take(100).collect(),
+    }
+}
+
 /// Utility functions for text analysis
 impl ChunkProcessor {
     /// Calculates optimal chunk size based on text characteristics
@@ -1839,6 +3039,10 @@ impl ChunkProcessor {
             preserve_code_blocks: true,
             preserve_markdown_links: true,
             strip_markdown_formatting: false,
+            block_handling: BlockHandlingConfig::default(),
+            max_tokens: self.config.max_tokens,
+            overlap_tokens: self.config.overlap_tokens,
+            hierarchy: self.config.hierarchy,
         }
     }
 }
@@ -1918,6 +3122,181 @@ mod tests {
         assert!(ChunkProcessor::new(invalid_config).is_err());
     }
 
+    #[test]
+    fn test_context_length_validation() {
+        let config = ChunkConfig::default().with_context_length(512);
+        assert_eq!(config.max_tokens, Some(512));
+        assert!(config.validate().is_ok());
+        assert!(config.validate_context_length(512).is_ok());
+        assert!(config.validate_context_length(256).is_err());
+
+        // A smaller configured limit is kept
+        let config = ChunkConfig { max_tokens: Some(128), ..ChunkConfig::default() }.with_context_length(512);
+        assert_eq!(config.max_tokens, Some(128));
+
+        // Without a token limit only max_chunk_size bounds the chunk
+        assert!(ChunkConfig::default().validate_context_length(8192).is_ok());
+        assert!(ChunkConfig::default().validate_context_length(512).is_err());
+
+        let invalid_config = ChunkConfig { max_tokens: Some(64), overlap_tokens: 40, ..ChunkConfig::default() };
+        assert!(invalid_config.validate().is_err());
+        let invalid_config = ChunkConfig { max_tokens: Some(4), ..ChunkConfig::default() };
+        assert!(invalid_config.validate().is_err());
+    }
+
+    #[test]
+    fn test_token_limit_splits_oversized_chunks() {
+        let tokenizer = crate::tokenizer::WordPieceTokenizer::from_vocab_file("test_fixtures/tokenizers/wordpiece_vocab.txt").unwrap();
+        let config = ChunkConfig { max_tokens: Some(32), overlap_tokens: 4, ..ChunkConfig::default() };
+        let processor = ChunkProcessor::new(config).unwrap().with_tokenizer(Arc::new(tokenizer));
+
+        let text = "The embedding model reads each note. A chunk of the note is unaffordable! ".repeat(12);
+        let chunks = processor.chunk_text(&text).unwrap();
+
+        assert!(chunks.len() > 1);
+        for (index, chunk) in chunks.iter().enumerate() {
+            assert!(processor.count_tokens(&chunk.content) <= 32, "chunk {} has {} tokens", index, processor.count_tokens(&chunk.content));
+            assert_eq!(chunk.metadata.chunk_index, index);
+            assert_eq!(chunk.metadata.total_chunks, chunks.len());
+            assert!(text[chunk.metadata.start_position..chunk.metadata.end_position].contains(chunk.content.trim()));
+        }
+        assert!(chunks.windows(2).any(|pair| pair[0].metadata.has_next_overlap && pair[1].metadata.has_previous_overlap));
+
+        // CJK text runs about one token per character, far beyond its character count suggests
+        let cjk_text = "笔记本记录".repeat(60);
+        let processor = ChunkProcessor::for_embedding_model("unknown-model", Some(64)).unwrap();
+        let chunks = processor.chunk_text(&cjk_text).unwrap();
+        assert!(chunks.len() >= 5);
+        assert!(chunks.iter().all(|chunk| processor.count_tokens(&chunk.content) <= 64));
+        assert_eq!(chunks.iter().map(|chunk| chunk.content.as_str()).collect::<String>(), cjk_text);
+
+        // A model without a reported context length gets the conservative limit
+        let processor = ChunkProcessor::for_embedding_model("unknown-model", None).unwrap();
+        assert_eq!(processor.config().max_tokens, Some(UNKNOWN_CONTEXT_LENGTH));
+    }
+
+    #[test]
+    fn test_chunk_hierarchy_links_windows_to_parents() {
+        let text = "---\ntags: [backup]\n---\nIntro line before any heading.\n\n\
+# Backups\n\nBackups run nightly. They go to the NAS. Old copies expire after a month. The NAS is encrypted.\n\n\
+```sh\nrsync -a src/ nas:/backup/\n\n# not a heading\n```\n\n\
+## Restore\n\n- Mount the share\n- Run rsync backwards\n";
+        let processor = ChunkProcessor::new(ChunkConfig::default()).unwrap();
+        let hierarchy = processor.chunk_hierarchy(text).unwrap();
+
+        let sections: Vec<&str> = hierarchy.sections.iter().map(|chunk| chunk.content.lines().next().unwrap()).collect();
+        assert_eq!(sections, vec!["Intro line before any heading.", "# Backups", "## Restore"]);
+        assert!(hierarchy.sections.iter().all(|chunk| chunk.metadata.parents.is_empty()));
+
+        // Headings stay with the block after them; the code block survives its blank line
+        let paragraphs: Vec<&str> = hierarchy.paragraphs.iter().map(|chunk| chunk.content.as_str()).collect();
+        assert_eq!(paragraphs.len(), 4);
+        assert!(paragraphs[1].starts_with("# Backups\n\nBackups run nightly."));
+        assert!(paragraphs[2].starts_with("```sh") && paragraphs[2].ends_with("```"));
+        assert_eq!(paragraphs[3], "## Restore\n\n- Mount the share\n- Run rsync backwards");
+
+        let windows: Vec<&str> = hierarchy.windows.iter().map(|chunk| chunk.content.as_str()).collect();
+        assert_eq!(windows[1], "# Backups\n\nBackups run nightly. They go to the NAS.");
+        assert_eq!(windows[2], "They go to the NAS. Old copies expire after a month. The NAS is encrypted.");
+        assert!(windows.iter().all(|window| !window.contains("tags:")));
+        assert!(hierarchy.windows[2].metadata.has_previous_overlap);
+
+        for window in &hierarchy.windows {
+            assert_eq!(&text[window.metadata.start_position..window.metadata.end_position], window.content);
+            assert_eq!(window.metadata.level, ChunkLevel::SentenceWindow);
+            let levels: Vec<ChunkLevel> = window.metadata.parents.iter().map(|parent| parent.level).collect();
+            assert_eq!(levels, vec![ChunkLevel::Paragraph, ChunkLevel::Section]);
+            let paragraph = &window.metadata.parents[0];
+            assert!(paragraph.start_position <= window.metadata.start_position && window.metadata.end_position <= paragraph.end_position);
+            assert!(paragraph.chunk_id.starts_with("paragraph-"));
+            assert!(!window.metadata.chunk_id.starts_with("paragraph-"));
+        }
+        assert_eq!(hierarchy.windows.last().unwrap().metadata.parents[1].chunk_id, hierarchy.sections[2].metadata.chunk_id);
+
+        let mut with_hierarchy = ChunkConfig::default();
+        with_hierarchy.hierarchy.enabled = true;
+        let processor = ChunkProcessor::new(with_hierarchy).unwrap();
+        assert_eq!(processor.chunk_for_embedding(text).unwrap(), hierarchy.windows);
+    }
+
+    #[test]
+    fn test_chunk_for_embedding_applies_transclusions() {
+        #[derive(Debug)]
+        struct EmbedResolver;
+
+        impl TransclusionResolver for EmbedResolver {
+            fn transclusions(&self, text: &str) -> Vec<Transclusion> {
+                let start = text.find("![[Plan]]").unwrap();
+                let marker = text.find(" ^goal").unwrap();
+                vec![
+                    Transclusion { start, end: start + "![[Plan]]".len(), text: "Ship the beta in May.".to_string() },
+                    Transclusion { start: marker, end: marker + " ^goal".len(), text: String::new() },
+                ]
+            }
+        }
+
+        let text = "# Roadmap\n\nThe plan for this quarter:\n\n![[Plan]]\n\nKeep scope small. ^goal\n";
+        let mut config = ChunkConfig::default();
+        config.hierarchy.enabled = true;
+        let plain = ChunkProcessor::new(config.clone()).unwrap().chunk_for_embedding(text).unwrap();
+        let resolved = ChunkProcessor::new(config)
+            .unwrap()
+            .with_transclusion_resolver(Arc::new(EmbedResolver))
+            .chunk_for_embedding(text)
+            .unwrap();
+
+        assert_eq!(plain.len(), resolved.len());
+        let contents: Vec<&str> = resolved.iter().map(|chunk| chunk.content.as_str()).collect();
+        assert!(contents.contains(&"Ship the beta in May."));
+        assert!(contents.contains(&"Keep scope small."));
+        for (before, after) in plain.iter().zip(&resolved) {
+            // Positions and IDs keep referring to the note
+            assert_eq!(before.metadata.start_position, after.metadata.start_position);
+            assert_eq!(before.metadata.chunk_id, after.metadata.chunk_id);
+            assert_eq!(after.metadata.character_count, after.content.len());
+        }
+    }
+
+    #[test]
+    fn test_chunk_for_embedding_splits_chunks_grown_by_transclusions() {
+        #[derive(Debug)]
+        struct LongEmbed;
+
+        impl TransclusionResolver for LongEmbed {
+            fn transclusions(&self, text: &str) -> Vec<Transclusion> {
+                let start = text.find("![[Spec]]").unwrap();
+                let spec = (0..60).map(|i| format!("Requirement {} must hold.", i)).collect::<Vec<_>>().join(" ");
+                vec![Transclusion { start, end: start + "![[Spec]]".len(), text: spec }]
+            }
+        }
+
+        let text = "# Design\n\nSee the spec: ![[Spec]]\n\nThat is all.\n";
+        let mut config = ChunkConfig::default();
+        config.hierarchy.enabled = true;
+        config.max_tokens = Some(64);
+        let processor = ChunkProcessor::new(config).unwrap().with_transclusion_resolver(Arc::new(LongEmbed));
+        let chunks = processor.chunk_for_embedding(text).unwrap();
+
+        // The inlined spec is split into pieces within the limit
+        assert!(chunks.len() > 3);
+        assert!(chunks.iter().all(|chunk| processor.count_tokens(&chunk.content) <= 64));
+        assert!(chunks.iter().any(|chunk| chunk.content.contains("Requirement 59")));
+
+        // Pieces keep the span of the chunk they were cut from, but get IDs of their own
+        let pieces: Vec<&TextChunk> = chunks.iter().filter(|chunk| chunk.content.contains("Requirement")).collect();
+        assert!(pieces.len() > 1);
+        for piece in &pieces {
+            assert_eq!(piece.metadata.start_position, pieces[0].metadata.start_position);
+            assert_eq!(piece.metadata.end_position, pieces[0].metadata.end_position);
+        }
+        let ids: std::collections::HashSet<&str> = chunks.iter().map(|chunk| chunk.metadata.chunk_id.as_str()).collect();
+        assert_eq!(ids.len(), chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.metadata.chunk_index, index);
+            assert_eq!(chunk.metadata.total_chunks, chunks.len());
+        }
+    }
+
     #[test]
     fn test_fixed_size_chunking() {
         let mut config = ChunkConfig::default();
@@ -2360,6 +3739,97 @@ And some final text."
         }
     }
 
+    #[test]
+    fn test_heading_paths_follow_document_structure() {
+        let text = "# Projects\n\nIntro paragraph for the projects note.\n\n## Alpha\n\nDetails about alpha.\n\n### Risks\n\nRisk notes.\n\n## Beta\n\nDetails about beta.";
+        let processor = ChunkProcessor::with_default_config().unwrap();
+        
+        let chunk_at = |needle: &str| {
+            let start_position = text.find(needle).unwrap();
+            TextChunk::new(needle.to_string(), ChunkMetadata { start_position, ..ChunkMetadata::default() })
+        };
+        let chunks = vec![
+            chunk_at("Intro paragraph"),
+            chunk_at("### Risks"),
+            chunk_at("Details about beta"),
+        ];
+        
+        let paths = processor.heading_paths(text, &chunks);
+        assert_eq!(paths[0], vec!["Projects"]);
+        assert_eq!(paths[1], vec!["Projects", "Alpha", "Risks"]);
+        assert_eq!(paths[2], vec!["Projects", "Beta"]);
+    }
+
+    #[test]
+    fn test_chunk_ids_survive_insertions_above() {
+        let processor = ChunkProcessor::with_default_config().unwrap();
+        let chunk_ids = |text: &str, pieces: &[&str]| {
+            let mut chunks: Vec<TextChunk> = pieces
+                .iter()
+                .map(|piece| {
+                    let start_position = text.find(piece).unwrap();
+                    TextChunk::new(piece.to_string(), ChunkMetadata { start_position, ..ChunkMetadata::default() })
+                })
+                .collect();
+            let paths = processor.heading_paths(text, &chunks);
+            processor.assign_chunk_ids(&mut chunks, &paths);
+            chunks.into_iter().map(|chunk| chunk.metadata.chunk_id).collect::<Vec<_>>()
+        };
+
+        let original = "# Notes\n\nFirst paragraph.\n\n## Later\n\nSecond paragraph.";
+        let edited = "# Notes\n\nInserted paragraph.\n\nFirst paragraph.\n\n## Later\n\nSecond paragraph.";
+
+        let before = chunk_ids(original, &["First paragraph.", "Second paragraph."]);
+        let after = chunk_ids(edited, &["Inserted paragraph.", "First paragraph.", "Second paragraph."]);
+        assert!(before.iter().all(|id| !id.is_empty()));
+        assert_eq!(before[..], after[1..]);
+        assert!(!before.contains(&after[0]));
+
+        // Same text under the same heading is disambiguated, different headings are not
+        let section = vec!["Notes".to_string()];
+        let other = vec!["Other".to_string()];
+        let mut chunks = vec![
+            TextChunk::new("Repeated".to_string(), ChunkMetadata::default()),
+            TextChunk::new("Repeated".to_string(), ChunkMetadata::default()),
+            TextChunk::new("Repeated".to_string(), ChunkMetadata::default()),
+        ];
+        processor.assign_chunk_ids(&mut chunks, &[section.clone(), section.clone(), other.clone()]);
+        let base_id = ChunkMetadata::content_id(&section, "Repeated");
+        assert_eq!(chunks[0].metadata.chunk_id, base_id);
+        assert_eq!(chunks[1].metadata.chunk_id, format!("{}-2", base_id));
+        assert_eq!(chunks[2].metadata.chunk_id, ChunkMetadata::content_id(&other, "Repeated"));
+    }
+
+    #[test]
+    fn test_extract_markdown_links() {
+        let text = "Café → see [[Project Alpha#Risks|the risks]] and ![[diagram.png]].\n\
+                    Also [notes](notes/todo.md \"Todo\") and [[ ]].\n\
+                    ```\n[[Not a link]] [nope](nope.md)\n```\n\
+                    Back to [[Beta]].";
+
+        let links = extract_markdown_links(text);
+        assert_eq!(links.len(), 4);
+        assert_eq!(
+            links[0],
+            MarkdownElement::WikiLink("Project Alpha#Risks".to_string(), Some("the risks".to_string()), text.find("[[Project").unwrap())
+        );
+        assert!(matches!(&links[1], MarkdownElement::WikiLink(target, None, _) if target == "diagram.png"));
+        assert!(matches!(&links[2], MarkdownElement::Link(text, url, Some(title), _)
+            if text == "notes" && url == "notes/todo.md" && title == "Todo"));
+        assert!(matches!(&links[3], MarkdownElement::WikiLink(target, None, _) if target == "Beta"));
+    }
+
+    #[test]
+    fn test_extract_inline_tags() {
+        let text = "#inbox idea for #project/alpha, not a heading:\n\
+                    # Heading\n\
+                    See https://example.com/page#section and `#code` or issue #123.\n\
+                    ```\n#fenced\n```\n\
+                    Done #réunion-2024 #todo_later.";
+
+        assert_eq!(extract_inline_tags(text), vec!["inbox", "project/alpha", "réunion-2024", "todo_later"]);
+    }
+
     #[test]
     fn test_markdown_header_boundaries() {
         let mut config = ChunkConfig::default();
@@ -2624,6 +4094,87 @@ And some final text."
         }
     }
 
+    #[test]
+    fn test_markdown_parser_block_structures() {
+        let content = load_test_fixture("block_structures.md").unwrap();
+        let elements = MarkdownParser::default().parse(&content);
+        
+        let frontmatter = elements.iter().find_map(|element| match element {
+            MarkdownElement::Frontmatter(yaml, start, end) => Some((yaml.clone(), *start, *end)),
+            _ => None,
+        });
+        let (yaml, start, end) = frontmatter.expect("frontmatter");
+        assert!(yaml.starts_with("title: Block Structures"));
+        assert_eq!((start, &content[end..end + 1]), (0, "#"));
+        
+        assert!(elements.iter().any(|element| matches!(element,
+            MarkdownElement::Callout(kind, Some(title), body, _, _)
+                if kind == "warning" && title == "Keep callouts together" && body.ends_with("closes the callout."))));
+        assert!(elements.iter().any(|element| matches!(element,
+            MarkdownElement::Blockquote(body, _, _) if body.starts_with("A plain blockquote") && body.lines().count() == 2)));
+        assert!(elements.iter().any(|element| matches!(element,
+            MarkdownElement::MathBlock(formula, _, _) if formula.starts_with("E = mc^2") && formula.lines().count() == 2)));
+        assert!(elements.iter().any(|element| matches!(element,
+            MarkdownElement::HtmlBlock(html, _, _) if html.starts_with("<details>") && html.ends_with("</details>"))));
+        assert!(elements.iter().any(|element| matches!(element,
+            MarkdownElement::HtmlBlock(html, _, _) if html.starts_with("<!--"))));
+        assert!(elements.iter().any(|element| matches!(element,
+            MarkdownElement::FootnoteDefinition(label, body, _, end)
+                if label == "prose" && body.ends_with("same footnote.") && *end == content.len())));
+        
+        // Frontmatter lines are not mistaken for headings or setext underlines
+        let headers: Vec<&str> = elements.iter().filter_map(|element| match element {
+            MarkdownElement::Header(_, text, _) => Some(text.as_str()),
+            _ => None,
+        }).collect();
+        assert_eq!(headers, vec!["Block Structures", "Math", "Raw HTML"]);
+    }
+    
+    /// Chunk a fixture with markdown-aware chunking and compare against its golden file
+    ///
+    /// Run with `UPDATE_GOLDEN=1` to rewrite the golden file after an intended change.
+    fn assert_golden_chunks(fixture: &str, handling: BlockHandling, golden: &str) {
+        let content = load_test_fixture(fixture).unwrap();
+        let processor = ChunkProcessor::new(ChunkConfig {
+            strategy: ChunkingStrategy::MarkdownAware,
+            max_chunk_size: 200,
+            overlap_size: 20,
+            min_chunk_size: 20,
+            block_handling: BlockHandlingConfig::all(handling),
+            ..ChunkConfig::default()
+        }).unwrap();
+        
+        let rendered: String = processor.chunk_text(&content).unwrap()
+            .iter()
+            .map(|chunk| format!(
+                "=== chunk {} [{}..{}] ===\n{}\n",
+                chunk.metadata.chunk_index, chunk.metadata.start_position, chunk.metadata.end_position, chunk.content
+            ))
+            .collect();
+        
+        let golden_path = format!("test_fixtures/markdown/golden/{}", golden);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&golden_path, &rendered).unwrap();
+        }
+        let expected = std::fs::read_to_string(&golden_path).unwrap();
+        assert_eq!(rendered, expected, "chunks differ from {}", golden_path);
+    }
+    
+    #[test]
+    fn test_block_structures_kept_intact_golden() {
+        assert_golden_chunks("block_structures.md", BlockHandling::KeepIntact, "block_structures.keep_intact.txt");
+    }
+    
+    #[test]
+    fn test_block_structures_stripped_golden() {
+        assert_golden_chunks("block_structures.md", BlockHandling::Strip, "block_structures.strip.txt");
+    }
+    
+    #[test]
+    fn test_block_structures_embedded_golden() {
+        assert_golden_chunks("block_structures.md", BlockHandling::Embed, "block_structures.embed.txt");
+    }
+    
     /// Load test file content from fixtures directory
     fn load_test_fixture(filename: &str) -> Result<String, std::io::Error> {
         use std::fs;
@@ -0,0 +1,349 @@
+//! # Embedding Model Tokenizers
+//!
+//! Token counting for chunk sizing. Embedding models limit their input in
+//! tokens, not characters, and the ratio between the two varies a lot between
+//! prose, code and CJK text, so chunks sized in characters alone can overflow
+//! the model's context and get silently truncated.
+//!
+//! ## Tokenizers
+//!
+//! - [`WordPieceTokenizer`]: BERT-style WordPiece counting from the model's
+//!   `vocab.txt`, which is what `nomic-embed-text`, `mxbai-embed-large` and
+//!   the other common Ollama embedding models use
+//! - [`CharHeuristicTokenizer`]: a conservative estimate from character
+//!   classes, used when no vocabulary is installed for the model
+//!
+//! Vocabularies are looked up at `~/.ainote/tokenizers/{model}/vocab.txt`,
+//! with the model's tag (`:latest`, `:v1.5`, ...) dropped from the name, and
+//! then in the app's bundled `tokenizers/{vocabulary}/vocab.txt` resources,
+//! which cover the models sharing BERT's uncased vocabulary.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use thiserror::Error;
+
+/// Tokens an encoder adds around every input (`[CLS]` and `[SEP]`)
+pub const SPECIAL_TOKEN_COUNT: usize = 2;
+
+/// Words longer than this are a single unknown token, as in BERT's tokenizer
+const MAX_WORD_CHARS: usize = 100;
+
+/// Directory of the vocabularies bundled with the app, set at startup
+static BUNDLED_TOKENIZER_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Errors that can occur when loading a tokenizer
+#[derive(Error, Debug, Clone)]
+pub enum TokenizerError {
+    #[error("Failed to read vocabulary {path}: {message}")]
+    IOError { path: String, message: String },
+
+    #[error("Invalid vocabulary {path}: {message}")]
+    InvalidVocabulary { path: String, message: String },
+}
+
+pub type TokenizerResult<T> = Result<T, TokenizerError>;
+
+/// Counts the tokens an embedding model sees for a text
+pub trait Tokenizer: fmt::Debug + Send + Sync {
+    /// Number of tokens in `text`, excluding the special tokens added around it
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Short description for logs
+    fn name(&self) -> String;
+}
+
+/// BERT-style WordPiece token counter
+///
+/// Text is split on whitespace, punctuation and CJK characters, then every word
+/// is split into the longest vocabulary entries from the left, continuation
+/// pieces being looked up with a `##` prefix. A word that cannot be split that
+/// way counts as one unknown token.
+#[derive(Debug, Clone)]
+pub struct WordPieceTokenizer {
+    vocab: HashSet<String>,
+    lowercase: bool,
+}
+
+impl WordPieceTokenizer {
+    /// Loads a `vocab.txt` with one token per line
+    pub fn from_vocab_file(path: impl AsRef<Path>) -> TokenizerResult<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| TokenizerError::IOError {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+
+        let tokenizer = Self::from_tokens(content.lines().map(str::to_string));
+        if tokenizer.vocab.is_empty() {
+            return Err(TokenizerError::InvalidVocabulary {
+                path: path.display().to_string(),
+                message: "vocabulary is empty".to_string(),
+            });
+        }
+
+        Ok(tokenizer)
+    }
+
+    /// Builds a tokenizer from vocabulary entries
+    ///
+    /// Input is lowercased unless the vocabulary contains uppercase words,
+    /// which is how cased and uncased BERT vocabularies differ.
+    pub fn from_tokens<I: IntoIterator<Item = String>>(tokens: I) -> Self {
+        let vocab: HashSet<String> = tokens
+            .into_iter()
+            .map(|token| token.trim_end_matches(['\r', '\n']).to_string())
+            .filter(|token| !token.is_empty())
+            .collect();
+        let lowercase = !vocab
+            .iter()
+            .any(|token| !token.starts_with('[') && token.chars().any(char::is_uppercase));
+
+        Self { vocab, lowercase }
+    }
+
+    /// Number of entries in the vocabulary
+    pub fn vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
+
+    /// Counts the WordPiece tokens of a single word
+    fn count_word(&self, word: &str) -> usize {
+        if word.chars().count() > MAX_WORD_CHARS {
+            return 1;
+        }
+
+        let mut count = 0;
+        let mut start = 0;
+        let mut probe = String::with_capacity(word.len() + 2);
+
+        while start < word.len() {
+            let mut end = word.len();
+            let matched = loop {
+                let piece = &word[start..end];
+                probe.clear();
+                if start > 0 {
+                    probe.push_str("##");
+                }
+                probe.push_str(piece);
+                if self.vocab.contains(&probe) {
+                    break true;
+                }
+
+                match piece.char_indices().next_back() {
+                    Some((last, _)) if last > 0 => end = start + last,
+                    _ => break false,
+                }
+            };
+
+            if !matched {
+                // The whole word becomes [UNK]
+                return 1;
+            }
+            count += 1;
+            start = end;
+        }
+
+        count
+    }
+}
+
+impl Tokenizer for WordPieceTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        let mut count = 0;
+        let mut word = String::new();
+
+        for c in text.chars() {
+            if c.is_whitespace() || c.is_control() {
+                if !word.is_empty() {
+                    count += self.count_word(&word);
+                    word.clear();
+                }
+            } else if is_punctuation(c) || is_cjk(c) {
+                if !word.is_empty() {
+                    count += self.count_word(&word);
+                    word.clear();
+                }
+                count += 1;
+            } else if self.lowercase {
+                word.extend(c.to_lowercase());
+            } else {
+                word.push(c);
+            }
+        }
+
+        if !word.is_empty() {
+            count += self.count_word(&word);
+        }
+
+        count
+    }
+
+    fn name(&self) -> String {
+        format!("wordpiece ({} entries)", self.vocab.len())
+    }
+}
+
+/// Token estimate from character classes, used when no vocabulary is available
+///
+/// ASCII words count one token per `ascii_chars_per_token` characters, rounded
+/// up per word, punctuation and every non-ASCII character count one token each.
+/// This errs on the high side for BERT-style vocabularies, so chunks sized by
+/// it still fit the model.
+#[derive(Debug, Clone)]
+pub struct CharHeuristicTokenizer {
+    /// Average ASCII word characters per token
+    pub ascii_chars_per_token: usize,
+}
+
+impl Default for CharHeuristicTokenizer {
+    fn default() -> Self {
+        Self { ascii_chars_per_token: 3 }
+    }
+}
+
+impl Tokenizer for CharHeuristicTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        let chars_per_token = self.ascii_chars_per_token.max(1);
+        let mut count = 0;
+        let mut word_chars: usize = 0;
+
+        for c in text.chars() {
+            if c.is_ascii_alphanumeric() {
+                word_chars += 1;
+                continue;
+            }
+
+            count += word_chars.div_ceil(chars_per_token);
+            word_chars = 0;
+            if !c.is_whitespace() && !c.is_control() {
+                count += 1;
+            }
+        }
+
+        count + word_chars.div_ceil(chars_per_token)
+    }
+
+    fn name(&self) -> String {
+        format!("character heuristic ({} chars/token)", self.ascii_chars_per_token)
+    }
+}
+
+/// Location of a model's WordPiece vocabulary
+pub fn tokenizer_vocab_path(model_name: &str) -> Option<PathBuf> {
+    let base_name = model_name.split(':').next().unwrap_or(model_name).replace('/', "_");
+    if base_name.is_empty() {
+        return None;
+    }
+
+    dirs::home_dir().map(|home| home.join(".ainote").join("tokenizers").join(base_name).join("vocab.txt"))
+}
+
+/// Register the directory of the vocabularies bundled as app resources
+///
+/// Only the first call takes effect.
+pub fn set_bundled_tokenizer_dir(dir: PathBuf) {
+    let _ = BUNDLED_TOKENIZER_DIR.set(dir);
+}
+
+/// Bundled vocabulary a model's tokenizer uses, if the app ships one
+fn bundled_vocabulary(model_name: &str) -> Option<&'static str> {
+    let base_name = model_name.split(':').next().unwrap_or(model_name);
+    let base_name = base_name.rsplit('/').next().unwrap_or(base_name);
+
+    match base_name {
+        "nomic-embed-text" | "mxbai-embed-large" | "all-minilm" | "snowflake-arctic-embed" | "bge-large" => {
+            Some("bert-base-uncased")
+        }
+        _ => None,
+    }
+}
+
+/// Location of the bundled vocabulary for a model, in `bundled_dir`
+fn bundled_vocab_path(bundled_dir: &Path, model_name: &str) -> Option<PathBuf> {
+    bundled_vocabulary(model_name).map(|vocabulary| bundled_dir.join(vocabulary).join("vocab.txt"))
+}
+
+/// Tokenizer for an embedding model
+///
+/// Uses the model's WordPiece vocabulary when one is installed or bundled,
+/// preferring an installed one, and falls back to [`CharHeuristicTokenizer`]
+/// otherwise.
+pub fn load_tokenizer(model_name: &str) -> Arc<dyn Tokenizer> {
+    let bundled_path = BUNDLED_TOKENIZER_DIR.get().and_then(|dir| bundled_vocab_path(dir, model_name));
+    if let Some(path) = tokenizer_vocab_path(model_name).into_iter().chain(bundled_path).find(|path| path.exists()) {
+        match WordPieceTokenizer::from_vocab_file(&path) {
+            Ok(tokenizer) => {
+                log::debug!("Loaded {} tokenizer for {}", tokenizer.name(), model_name);
+                return Arc::new(tokenizer);
+            }
+            Err(e) => log::warn!("Falling back to estimated token counts for {}: {}", model_name, e),
+        }
+    }
+
+    Arc::new(CharHeuristicTokenizer::default())
+}
+
+/// Punctuation and symbols, which BERT splits into tokens of their own
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace())
+}
+
+/// CJK ideographs, which BERT tokenizes one character at a time
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF
+        | 0x3400..=0x4DBF
+        | 0x20000..=0x2A6DF
+        | 0x2A700..=0x2B73F
+        | 0x2B740..=0x2B81F
+        | 0x2B820..=0x2CEAF
+        | 0xF900..=0xFAFF
+        | 0x2F800..=0x2FA1F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_VOCAB: &str = "test_fixtures/tokenizers/wordpiece_vocab.txt";
+
+    #[test]
+    fn test_wordpiece_counts_match_vocabulary_splits() {
+        let tokenizer = WordPieceTokenizer::from_vocab_file(TEST_VOCAB).unwrap();
+
+        // un ##afford ##able note ##s !, embed ##ding model ##s, [UNK]
+        assert_eq!(tokenizer.count_tokens("Unaffordable notes!"), 6);
+        assert_eq!(tokenizer.count_tokens("embedding models"), 4);
+        assert_eq!(tokenizer.count_tokens("xyzzy"), 1);
+        assert_eq!(tokenizer.count_tokens("笔记本"), 3);
+        assert_eq!(tokenizer.count_tokens("   "), 0);
+
+        assert!(WordPieceTokenizer::from_vocab_file("test_fixtures/tokenizers/missing.txt").is_err());
+    }
+
+    #[test]
+    fn test_heuristic_overestimates_wordpiece() {
+        let wordpiece = WordPieceTokenizer::from_vocab_file(TEST_VOCAB).unwrap();
+        let heuristic = CharHeuristicTokenizer::default();
+
+        assert_eq!(heuristic.count_tokens("fn main() {}"), 7);
+        assert_eq!(heuristic.count_tokens("笔记本"), 3);
+        for text in ["Unaffordable notes!", "embedding models", "笔记本 notes"] {
+            assert!(heuristic.count_tokens(text) >= wordpiece.count_tokens(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_bundled_vocab_path_ignores_tags_and_unknown_models() {
+        let dir = Path::new("resources/tokenizers");
+        let expected = Some(dir.join("bert-base-uncased").join("vocab.txt"));
+
+        assert_eq!(bundled_vocab_path(dir, "nomic-embed-text:v1.5"), expected);
+        assert_eq!(bundled_vocab_path(dir, "library/mxbai-embed-large:latest"), expected);
+        assert_eq!(bundled_vocab_path(dir, "bge-m3"), None);
+    }
+}
@@ -0,0 +1,1144 @@
+//! # Vault Compatibility
+//!
+//! Maps the conventions of Obsidian and Logseq vaults onto aiNote, so vaults
+//! created with those apps can be scanned and indexed as they are.
+//!
+//! ## Detection
+//!
+//! - **Obsidian**: a `.obsidian/` directory. `app.json` provides the ignore list
+//!   (`userIgnoreFilters`: path prefixes, or regular expressions written as
+//!   `/pattern/`) and the attachment folder (`attachmentFolderPath`).
+//!   `.obsidian/` and `.trash/` are always ignored.
+//! - **Logseq**: a `logseq/config.edn` file. Its `:hidden` list is the ignore
+//!   list and attachments live in `assets/`. The `logseq/` directory (settings
+//!   and page backups) is always ignored.
+//! - Anything else is a plain vault without an ignore list.
+//!
+//! ## Transclusions
+//!
+//! `VaultCompat::transclusions` finds the embeds and block references of a
+//! note together with the text they stand for, which
+//! `ChunkProcessor::chunk_for_embedding` puts into the chunk content in their
+//! place:
+//!
+//! - `![[Note]]`, `![[Note#Heading]]`, `![[Note#^block]]`: the note body, the
+//!   section or the block
+//! - `[[Note#^block]]`: the link alias, or else the block text
+//! - Logseq `((uuid))` and `{{embed ((uuid))}}`: the block, with its children
+//!   when embedded
+//! - Logseq `{{embed [[Page]]}}`: the page
+//! - Obsidian `^block` markers and Logseq `id::` properties: removed
+//!
+//! Targets resolve like wikilinks in the link graph, then by Logseq page name
+//! (`a___b.md` is the page `a/b`, `journals/2024_01_15.md` is `Jan 15th, 2024`),
+//! then by frontmatter aliases and Logseq `alias::` and `title::` properties.
+//! Transcluded text is not expanded further and is cut at
+//! `max_transclusion_chars`. Attachment embeds keep their syntax; they are
+//! looked up in the configured attachment folder and reported when missing.
+//! A note is re-embedded when it changes, not when a note it embeds changes.
+//!
+//! ## Unmapped Features
+//!
+//! `VaultCompat::report` lists what aiNote could not map: unreadable
+//! settings, invalid ignore patterns, canvas and org-mode files, plugins and
+//! macros whose syntax is indexed as written, and references that do not
+//! resolve.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+use walkdir::WalkDir;
+
+use crate::link_graph::has_non_markdown_extension;
+use crate::note_metadata::{split_frontmatter, NoteMetadata};
+use crate::text_chunker::{extract_markdown_links, fenced_code_ranges, MarkdownElement, Transclusion, TransclusionResolver};
+
+/// Characters of transcluded text kept by default (the default chunk size)
+pub const DEFAULT_MAX_TRANSCLUSION_CHARS: usize = 1000;
+
+/// Logseq's default journal title format, the only one mapped to page names
+const LOGSEQ_DEFAULT_JOURNAL_FORMAT: &str = "MMM do, yyyy";
+
+/// Obsidian plugins whose syntax is indexed as written, with what that means
+const OBSIDIAN_PLUGIN_NOTES: [(&str, &str); 5] = [
+    ("dataview", "Dataview queries are indexed as query text, not as their results"),
+    ("templater-obsidian", "Templater commands are indexed as written"),
+    ("obsidian-excalidraw-plugin", "Excalidraw drawings (.excalidraw.md) are indexed as raw drawing data"),
+    ("obsidian-kanban", "Kanban boards are indexed as plain markdown lists"),
+    ("obsidian-tasks-plugin", "Tasks queries are indexed as query text, not as their results"),
+];
+
+const UUID_PATTERN: &str = r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}";
+
+/// Logseq `{{embed ((uuid))}}` and `{{embed [[Page]]}}`
+static EMBED_MACRO: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"\{{\{{embed\s+(?:\(\(({})\)\)|\[\[([^\]\n]+)\]\])\s*\}}\}}", UUID_PATTERN)).unwrap()
+});
+
+/// Logseq `((uuid))` block references
+static BLOCK_REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"\(\(({})\)\)", UUID_PATTERN)).unwrap());
+
+/// Obsidian `^block-id` markers at the end of a line
+static BLOCK_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)(?:^|[ \t]+)\^([A-Za-z0-9-]+)[ \t]*$").unwrap());
+
+/// Logseq `id::` properties, with the line break before them
+static BLOCK_ID_PROPERTY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"(?m)\r?\n[ \t]*id::[ \t]*({})[ \t]*$", UUID_PATTERN)).unwrap()
+});
+
+/// Logseq `key:: value` properties
+static PROPERTY: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*([A-Za-z][\w-]*)::\s?(.*)$").unwrap());
+
+/// Logseq `{{macro ...}}` calls
+static MACRO: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{([A-Za-z][\w-]*)").unwrap());
+
+/// Errors that can occur while opening a vault
+#[derive(Error, Debug)]
+pub enum VaultCompatError {
+    #[error("Vault path does not exist or is not a directory: {path}")]
+    VaultNotFound { path: String },
+}
+
+pub type VaultCompatResult<T> = Result<T, VaultCompatError>;
+
+/// App a vault was created with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultFlavor {
+    Plain,
+    Obsidian,
+    Logseq,
+}
+
+/// Where a vault keeps its attachments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "path")]
+pub enum AttachmentFolder {
+    /// The vault root (`/`)
+    VaultRoot,
+    /// The folder of the note (`./`)
+    NoteFolder,
+    /// A folder inside the note's folder (`./assets`)
+    NoteSubfolder(String),
+    /// A folder relative to the vault root
+    Folder(String),
+}
+
+impl AttachmentFolder {
+    /// Interpret Obsidian's `attachmentFolderPath` setting
+    fn from_setting(setting: &str) -> Self {
+        match setting.trim() {
+            "" | "/" => Self::VaultRoot,
+            "." | "./" => Self::NoteFolder,
+            setting => match setting.strip_prefix("./") {
+                Some(subfolder) => Self::NoteSubfolder(subfolder.trim_matches('/').to_string()),
+                None => Self::Folder(setting.trim_matches('/').to_string()),
+            },
+        }
+    }
+
+    /// Vault-relative attachment folder for a note in `note_folder`
+    fn resolve(&self, note_folder: &str) -> String {
+        match self {
+            Self::VaultRoot => String::new(),
+            Self::NoteFolder => note_folder.to_string(),
+            Self::NoteSubfolder(subfolder) => join_key(note_folder, subfolder),
+            Self::Folder(folder) => folder.clone(),
+        }
+    }
+}
+
+/// Something a vault uses that aiNote does not map
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnmappedFeature {
+    /// Short name of the feature
+    pub feature: String,
+    /// What happens to it instead
+    pub detail: String,
+}
+
+/// Layout and settings of a vault
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultProfile {
+    /// App the vault was created with
+    pub flavor: VaultFlavor,
+    /// Ignore entries as written in the vault's settings
+    pub ignore_filters: Vec<String>,
+    /// Where attachments are kept
+    pub attachment_folder: AttachmentFolder,
+    /// Settings that could not be mapped
+    pub unmapped_features: Vec<UnmappedFeature>,
+    /// Paths ignored together with everything below them
+    #[serde(skip)]
+    ignored_paths: Vec<String>,
+    /// Obsidian filters: prefixes of the path (directories end with `/`)
+    #[serde(skip)]
+    ignored_prefixes: Vec<String>,
+    /// Obsidian filters written as regular expressions
+    #[serde(skip)]
+    ignored_patterns: Vec<Regex>,
+    /// Whether journal pages are named with Logseq's default title format
+    #[serde(skip)]
+    journal_titles: bool,
+}
+
+impl VaultProfile {
+    fn new(flavor: VaultFlavor, attachment_folder: AttachmentFolder) -> Self {
+        Self {
+            flavor,
+            ignore_filters: Vec::new(),
+            attachment_folder,
+            unmapped_features: Vec::new(),
+            ignored_paths: Vec::new(),
+            ignored_prefixes: Vec::new(),
+            ignored_patterns: Vec::new(),
+            journal_titles: false,
+        }
+    }
+
+    /// Detect the layout of a vault and read its settings
+    ///
+    /// Unreadable settings are reported as unmapped features and defaults
+    /// are used instead, so detection never fails.
+    pub fn detect(vault_path: &Path) -> Self {
+        if vault_path.join(".obsidian").is_dir() {
+            Self::obsidian(vault_path)
+        } else if vault_path.join("logseq").join("config.edn").is_file() {
+            Self::logseq(vault_path)
+        } else {
+            Self::new(VaultFlavor::Plain, AttachmentFolder::VaultRoot)
+        }
+    }
+
+    fn obsidian(vault_path: &Path) -> Self {
+        let mut profile = Self::new(VaultFlavor::Obsidian, AttachmentFolder::VaultRoot);
+        profile.ignored_paths = vec![".obsidian".to_string(), ".trash".to_string()];
+        let config_dir = vault_path.join(".obsidian");
+
+        match read_json(&config_dir.join("app.json")) {
+            Ok(Some(app)) => {
+                let filters = app.get("userIgnoreFilters").and_then(JsonValue::as_array);
+                for filter in filters.into_iter().flatten().filter_map(JsonValue::as_str) {
+                    profile.add_obsidian_filter(filter);
+                }
+                if let Some(folder) = app.get("attachmentFolderPath").and_then(JsonValue::as_str) {
+                    profile.attachment_folder = AttachmentFolder::from_setting(folder);
+                }
+            }
+            Ok(None) => {}
+            Err(message) => profile.unmapped("Obsidian settings", format!("{}; default settings are used", message)),
+        }
+
+        if let Ok(Some(JsonValue::Array(plugins))) = read_json(&config_dir.join("community-plugins.json")) {
+            for plugin in plugins.iter().filter_map(JsonValue::as_str) {
+                if let Some((_, detail)) = OBSIDIAN_PLUGIN_NOTES.iter().find(|(id, _)| *id == plugin) {
+                    profile.unmapped(&format!("Plugin {}", plugin), detail.to_string());
+                }
+            }
+        }
+        profile
+    }
+
+    fn logseq(vault_path: &Path) -> Self {
+        let mut profile = Self::new(VaultFlavor::Logseq, AttachmentFolder::Folder("assets".to_string()));
+        profile.ignored_paths = vec!["logseq".to_string()];
+        profile.journal_titles = true;
+
+        let config = match std::fs::read_to_string(vault_path.join("logseq").join("config.edn")) {
+            Ok(config) => strip_edn_comments(&config),
+            Err(e) => {
+                profile.unmapped("Logseq settings", format!("Failed to read config.edn: {}; default settings are used", e));
+                return profile;
+            }
+        };
+
+        for entry in edn_strings(&config, ":hidden") {
+            let entry = entry.trim().trim_matches('/');
+            if !entry.is_empty() {
+                profile.ignore_filters.push(format!("/{}", entry));
+                profile.ignored_paths.push(entry.to_string());
+            }
+        }
+        if edn_scalar(&config, ":preferred-format").is_some_and(|format| format.eq_ignore_ascii_case("org")) {
+            profile.unmapped("Org-mode pages", "New pages are written in org-mode, which is not indexed".to_string());
+        }
+        if let Some(format) = edn_scalar(&config, ":journal/page-title-format") {
+            if format != LOGSEQ_DEFAULT_JOURNAL_FORMAT {
+                profile.journal_titles = false;
+                profile.unmapped(
+                    "Journal titles",
+                    format!("Links to journal pages by their \"{}\" title do not resolve", format),
+                );
+            }
+        }
+        profile
+    }
+
+    fn add_obsidian_filter(&mut self, filter: &str) {
+        let filter = filter.trim();
+        if filter.is_empty() {
+            return;
+        }
+        self.ignore_filters.push(filter.to_string());
+
+        match filter.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+            Some(pattern) => match Regex::new(pattern) {
+                Ok(pattern) => self.ignored_patterns.push(pattern),
+                Err(e) => self.unmapped(&format!("Ignore filter {}", filter), format!("Invalid regular expression, not applied: {}", e)),
+            },
+            None => self.ignored_prefixes.push(filter.to_string()),
+        }
+    }
+
+    fn unmapped(&mut self, feature: &str, detail: String) {
+        self.unmapped_features.push(UnmappedFeature { feature: feature.to_string(), detail });
+    }
+
+    /// Whether a vault-relative path (with `/` separators) is ignored
+    pub fn is_ignored(&self, relative_path: &str, is_dir: bool) -> bool {
+        let relative_path = relative_path.trim_start_matches('/');
+        let candidate = if is_dir { format!("{}/", relative_path) } else { relative_path.to_string() };
+
+        self.ignored_paths.iter().any(|path| relative_path == path || relative_path.starts_with(&format!("{}/", path)))
+            || self.ignored_prefixes.iter().any(|prefix| candidate.starts_with(prefix.as_str()))
+            || self.ignored_patterns.iter().any(|pattern| pattern.is_match(&candidate))
+    }
+
+    /// Whether a path inside the vault is ignored; paths outside it never are
+    pub fn ignores(&self, vault_path: &Path, path: &Path) -> bool {
+        relative_key(vault_path, path).is_some_and(|key| !key.is_empty() && self.is_ignored(&key, path.is_dir()))
+    }
+}
+
+/// Reference that could not be resolved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnresolvedReference {
+    /// Vault-relative path of the note containing the reference
+    pub note_path: String,
+    /// The reference as written
+    pub reference: String,
+}
+
+/// Transclusions of a note
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoteTransclusions {
+    /// Resolved embeds and references, and markers to remove, in document order
+    pub transclusions: Vec<Transclusion>,
+    /// Embeds and references that could not be resolved, as written
+    pub unresolved: Vec<String>,
+    /// Absolute paths of the other notes text was transcluded from, sorted
+    pub sources: Vec<PathBuf>,
+}
+
+/// What aiNote makes of a vault
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultCompatibilityReport {
+    /// App the vault was created with
+    pub flavor: VaultFlavor,
+    /// Where attachments are kept
+    pub attachment_folder: AttachmentFolder,
+    /// Ignore entries as written in the vault's settings
+    pub ignore_filters: Vec<String>,
+    /// Notes that are scanned and indexed
+    pub note_count: usize,
+    /// Features that could not be mapped
+    pub unmapped_features: Vec<UnmappedFeature>,
+    /// Embeds and references that do not resolve
+    pub unresolved_references: Vec<UnresolvedReference>,
+}
+
+/// Aliases, titles and block IDs of the notes of a vault
+#[derive(Debug, Default)]
+struct NoteDirectory {
+    /// Notes by lowercase alias or Logseq title
+    by_alias: HashMap<String, usize>,
+    /// Notes holding each Logseq block UUID (lowercase)
+    block_ids: HashMap<String, usize>,
+}
+
+/// Notes of a vault with the settings needed to map its conventions
+#[derive(Debug)]
+pub struct VaultCompat {
+    vault_path: PathBuf,
+    profile: VaultProfile,
+    /// Vault-relative paths of the notes with `/` separators, sorted
+    notes: Vec<String>,
+    /// Notes by lowercase file name
+    by_file_name: HashMap<String, Vec<usize>>,
+    /// Notes by lowercase Logseq page name
+    by_page_name: HashMap<String, Vec<usize>>,
+    /// Lowercase file names of the attachments anywhere in the vault
+    attachments: HashSet<String>,
+    /// Files that are not indexed, by extension
+    unindexed_files: BTreeMap<String, usize>,
+    /// Read from every note on first use
+    directory: OnceLock<NoteDirectory>,
+    max_transclusion_chars: usize,
+}
+
+impl VaultCompat {
+    /// Detect a vault's layout and list its notes
+    ///
+    /// Hidden and ignored files are left out. Note contents are only read
+    /// when an alias or a Logseq block UUID has to be resolved.
+    pub fn open(vault_path: impl Into<PathBuf>) -> VaultCompatResult<Self> {
+        let vault_path = vault_path.into();
+        if !vault_path.is_dir() {
+            return Err(VaultCompatError::VaultNotFound { path: vault_path.display().to_string() });
+        }
+        let profile = VaultProfile::detect(&vault_path);
+
+        let mut notes = Vec::new();
+        let mut attachments = HashSet::new();
+        let mut unindexed_files = BTreeMap::new();
+        let entries = WalkDir::new(&vault_path).into_iter().filter_entry(|entry| {
+            entry.depth() == 0
+                || (!entry.file_name().to_string_lossy().starts_with('.') && !profile.ignores(&vault_path, entry.path()))
+        });
+        for entry in entries.filter_map(Result::ok).filter(|entry| entry.file_type().is_file()) {
+            let Some(key) = relative_key(&vault_path, entry.path()) else {
+                continue;
+            };
+            let extension = entry.path().extension().map(|extension| extension.to_string_lossy().to_lowercase());
+            match extension.as_deref() {
+                Some("md") => notes.push(key),
+                Some(extension) => {
+                    attachments.insert(entry.file_name().to_string_lossy().to_lowercase());
+                    if matches!(extension, "canvas" | "org") {
+                        *unindexed_files.entry(extension.to_string()).or_insert(0) += 1;
+                    }
+                }
+                None => {}
+            }
+        }
+        notes.sort();
+
+        let mut by_file_name: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_page_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, key) in notes.iter().enumerate() {
+            let file_name = key.rsplit('/').next().unwrap_or(key);
+            by_file_name.entry(file_name.to_lowercase()).or_default().push(index);
+            if profile.flavor == VaultFlavor::Logseq {
+                for page_name in logseq_page_names(key, profile.journal_titles) {
+                    by_page_name.entry(page_name).or_default().push(index);
+                }
+            }
+        }
+
+        log::info!("🧭 Vault {:?} opened as {:?} with {} notes", vault_path, profile.flavor, notes.len());
+        Ok(Self {
+            vault_path,
+            profile,
+            notes,
+            by_file_name,
+            by_page_name,
+            attachments,
+            unindexed_files,
+            directory: OnceLock::new(),
+            max_transclusion_chars: DEFAULT_MAX_TRANSCLUSION_CHARS,
+        })
+    }
+
+    /// Limit the characters of transcluded text (longer text is cut)
+    pub fn with_max_transclusion_chars(mut self, max_transclusion_chars: usize) -> Self {
+        self.max_transclusion_chars = max_transclusion_chars.max(1);
+        self
+    }
+
+    /// Root directory of the vault
+    pub fn vault_path(&self) -> &Path {
+        &self.vault_path
+    }
+
+    /// Layout and settings of the vault
+    pub fn profile(&self) -> &VaultProfile {
+        &self.profile
+    }
+
+    /// Absolute paths of the notes, sorted
+    pub fn note_paths(&self) -> Vec<PathBuf> {
+        self.notes.iter().map(|key| self.vault_path.join(key)).collect()
+    }
+
+    /// Whether a path inside the vault is ignored
+    pub fn ignores(&self, path: &Path) -> bool {
+        self.profile.ignores(&self.vault_path, path)
+    }
+
+    /// Whether a path lies inside the vault
+    pub fn contains_path(&self, path: &Path) -> bool {
+        relative_key(&self.vault_path, path).is_some()
+    }
+
+    /// Transclusion resolver for one note, for `ChunkProcessor::with_transclusion_resolver`
+    pub fn resolver_for(self: &Arc<Self>, note_path: &Path) -> Arc<dyn TransclusionResolver> {
+        Arc::new(NoteTransclusionResolver { vault: Arc::clone(self), note_path: note_path.to_path_buf() })
+    }
+
+    /// Find the embeds, block references and block markers of a note
+    ///
+    /// `text` is the content of the note at `note_path`. Plain links are left
+    /// alone; block links are replaced by their alias or the block text.
+    pub fn transclusions(&self, note_path: &Path, text: &str) -> NoteTransclusions {
+        let source_key = relative_key(&self.vault_path, note_path);
+        let source = source_key.as_ref().and_then(|key| self.notes.binary_search(key).ok());
+        let code_ranges = fenced_code_ranges(text);
+        let in_code = |position: usize| code_ranges.iter().any(|&(start, end)| position >= start && position < end);
+        let mut result = NoteTransclusions::default();
+
+        // Logseq embed macros; the wikilinks inside them belong to the macro
+        for captures in EMBED_MACRO.captures_iter(text) {
+            let whole = captures.get(0).unwrap();
+            if in_code(whole.start()) {
+                continue;
+            }
+            let resolved = match (captures.get(1), captures.get(2)) {
+                (Some(uuid), _) => self.logseq_block(uuid.as_str(), true),
+                (_, Some(page)) => self.resolve(source, page.as_str()).and_then(|note| Some((note, self.note_body(note)?))),
+                _ => None,
+            };
+            self.push_resolved(&mut result, whole.start(), whole.end(), whole.as_str(), resolved);
+        }
+
+        for captures in BLOCK_REFERENCE.captures_iter(text) {
+            let whole = captures.get(0).unwrap();
+            if in_code(whole.start()) || overlaps(&result.transclusions, whole.start(), whole.end()) {
+                continue;
+            }
+            let resolved = self.logseq_block(&captures[1], false);
+            self.push_resolved(&mut result, whole.start(), whole.end(), whole.as_str(), resolved);
+        }
+
+        for element in extract_markdown_links(text) {
+            let MarkdownElement::WikiLink(raw_target, alias, position) = element else {
+                continue;
+            };
+            let Some(end) = text[position..].find("]]").map(|close| position + close + 2) else {
+                continue;
+            };
+            let embed = position > 0 && text.as_bytes()[position - 1] == b'!';
+            let start = if embed { position - 1 } else { position };
+            if overlaps(&result.transclusions, start, end) {
+                continue;
+            }
+
+            let (target, subpath) = match raw_target.split_once('#') {
+                Some((target, subpath)) => (target.trim(), Some(subpath.trim())),
+                None => (raw_target.trim(), None),
+            };
+            if has_non_markdown_extension(target) {
+                if embed && !self.attachment_exists(source_key.as_deref(), target) {
+                    result.unresolved.push(text[start..end].to_string());
+                }
+                continue;
+            }
+
+            let block_id = subpath.and_then(|subpath| subpath.strip_prefix('^'));
+            if !embed {
+                match (block_id, alias) {
+                    (None, _) => continue,
+                    (Some(_), Some(alias)) => {
+                        result.transclusions.push(Transclusion { start, end, text: alias });
+                        continue;
+                    }
+                    (Some(_), None) => {}
+                }
+            }
+
+            let resolved = self.resolve(source, target).and_then(|note| {
+                let content = self.read_note(note)?;
+                let text = match (block_id, subpath) {
+                    (Some(block_id), _) => obsidian_block(&content, block_id),
+                    (None, Some(heading)) if !heading.is_empty() => heading_section(&content, heading),
+                    _ => Some(self.body_of(&content)),
+                }?;
+                Some((note, self.clean(&text)))
+            });
+            self.push_resolved(&mut result, start, end, &text[start..end], resolved);
+        }
+
+        // Block IDs are bookkeeping, not content
+        let markers = match self.profile.flavor {
+            VaultFlavor::Logseq => &*BLOCK_ID_PROPERTY,
+            _ => &*BLOCK_MARKER,
+        };
+        for marker in markers.find_iter(text) {
+            if !in_code(marker.start()) && !overlaps(&result.transclusions, marker.start(), marker.end()) {
+                result.transclusions.push(Transclusion { start: marker.start(), end: marker.end(), text: String::new() });
+            }
+        }
+
+        result.transclusions.sort_by_key(|transclusion| transclusion.start);
+        result.sources.sort();
+        result.sources.dedup();
+        result.sources.retain(|path| path != note_path);
+        result
+    }
+
+    /// Describe how the vault is mapped, reading every note for unresolved references
+    pub fn report(&self) -> VaultCompatibilityReport {
+        let mut unmapped_features = self.profile.unmapped_features.clone();
+        for (extension, count) in &self.unindexed_files {
+            let feature = match extension.as_str() {
+                "canvas" => "Canvas files",
+                _ => "Org-mode pages",
+            };
+            unmapped_features.push(UnmappedFeature {
+                feature: feature.to_string(),
+                detail: format!("{} .{} files are not indexed", count, extension),
+            });
+        }
+
+        let mut unresolved_references = Vec::new();
+        let mut macros: BTreeMap<String, usize> = BTreeMap::new();
+        for (index, key) in self.notes.iter().enumerate() {
+            let Some(content) = self.read_note(index) else {
+                continue;
+            };
+            let transclusions = self.transclusions(&self.vault_path.join(key), &content);
+            unresolved_references.extend(
+                transclusions.unresolved.into_iter().map(|reference| UnresolvedReference { note_path: key.clone(), reference }),
+            );
+            if self.profile.flavor == VaultFlavor::Logseq {
+                let names: HashSet<String> = MACRO.captures_iter(&content).map(|captures| captures[1].to_lowercase()).collect();
+                for name in names.into_iter().filter(|name| name != "embed") {
+                    *macros.entry(name).or_insert(0) += 1;
+                }
+            }
+        }
+        for (name, notes) in macros {
+            unmapped_features.push(UnmappedFeature {
+                feature: format!("Macro {{{{{}}}}}", name),
+                detail: format!("Indexed as written in {} notes", notes),
+            });
+        }
+
+        VaultCompatibilityReport {
+            flavor: self.profile.flavor,
+            attachment_folder: self.profile.attachment_folder.clone(),
+            ignore_filters: self.profile.ignore_filters.clone(),
+            note_count: self.notes.len(),
+            unmapped_features,
+            unresolved_references,
+        }
+    }
+
+    /// Resolve a link target to a note: by path, then page name, then alias
+    fn resolve(&self, source: Option<usize>, target: &str) -> Option<usize> {
+        let target = target.trim();
+        if target.is_empty() {
+            // [[#^block]] points at the note itself
+            return source;
+        }
+
+        let name = target.trim_start_matches('/').to_lowercase();
+        let name = name.strip_suffix(".md").unwrap_or(&name);
+        let path = format!("{}.md", name);
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+        let suffix = format!("/{}", path);
+
+        self.by_file_name
+            .get(file_name)
+            .and_then(|candidates| {
+                candidates
+                    .iter()
+                    .copied()
+                    .filter(|&index| {
+                        let key = self.notes[index].to_lowercase();
+                        key == path || key.ends_with(&suffix)
+                    })
+                    .min_by_key(|&index| (self.notes[index].len(), index))
+            })
+            .or_else(|| self.by_page_name.get(name).and_then(|candidates| candidates.first().copied()))
+            .or_else(|| self.directory().by_alias.get(name).copied())
+    }
+
+    fn directory(&self) -> &NoteDirectory {
+        self.directory.get_or_init(|| {
+            let mut directory = NoteDirectory::default();
+            for index in 0..self.notes.len() {
+                let Some(content) = self.read_note(index) else {
+                    continue;
+                };
+                for alias in NoteMetadata::parse(&content).aliases {
+                    directory.by_alias.entry(alias.to_lowercase()).or_insert(index);
+                }
+                if self.profile.flavor != VaultFlavor::Logseq {
+                    continue;
+                }
+                for (key, value) in page_properties(&content) {
+                    let names = match key.as_str() {
+                        "alias" => value.split(',').map(str::to_string).collect(),
+                        "title" => vec![value],
+                        _ => Vec::new(),
+                    };
+                    for name in names {
+                        let name = name.trim().trim_start_matches("[[").trim_end_matches("]]").trim().to_lowercase();
+                        if !name.is_empty() {
+                            directory.by_alias.entry(name).or_insert(index);
+                        }
+                    }
+                }
+                for captures in BLOCK_ID_PROPERTY.captures_iter(&content) {
+                    directory.block_ids.insert(captures[1].to_lowercase(), index);
+                }
+            }
+            directory
+        })
+    }
+
+    fn read_note(&self, index: usize) -> Option<String> {
+        let path = self.vault_path.join(&self.notes[index]);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Some(content),
+            Err(e) => {
+                log::warn!("⚠️ Skipping unreadable note {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Body of a note without frontmatter and page properties
+    fn note_body(&self, index: usize) -> Option<String> {
+        self.read_note(index).map(|content| self.clean(&self.body_of(&content)))
+    }
+
+    fn body_of(&self, content: &str) -> String {
+        let body = split_frontmatter(content).map_or(content, |(_, body_start)| &content[body_start..]);
+        if self.profile.flavor != VaultFlavor::Logseq {
+            return body.to_string();
+        }
+        let properties = body.lines().take_while(|line| PROPERTY.is_match(line)).count();
+        body.lines().skip(properties).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Note holding a Logseq block and the block's text, without its properties
+    fn logseq_block(&self, uuid: &str, with_children: bool) -> Option<(usize, String)> {
+        let note = *self.directory().block_ids.get(&uuid.to_lowercase())?;
+        let content = self.read_note(note)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let property = lines.iter().position(|line| {
+            line.trim().strip_prefix("id::").is_some_and(|value| value.trim().eq_ignore_ascii_case(uuid))
+        })?;
+
+        // The block is the nearest bullet above its id property that is indented less
+        let property_indent = indentation(lines[property]);
+        let bullet = (0..property).rev().find(|&line| is_list_item(lines[line]) && indentation(lines[line]) < property_indent)?;
+        let bullet_indent = indentation(lines[bullet]);
+        let block_end = bullet + 1 + lines[bullet + 1..]
+            .iter()
+            .take_while(|line| line.trim().is_empty() || indentation(line) > bullet_indent)
+            .count();
+
+        let mut block = Vec::new();
+        for (offset, line) in lines[bullet..block_end].iter().enumerate() {
+            if offset > 0 && !with_children && is_list_item(line) {
+                break;
+            }
+            if offset == 0 || !PROPERTY.is_match(line) {
+                block.push(*line);
+            }
+        }
+        let text = block.join("\n");
+        let text = text.trim();
+        Some((note, self.clean(text.strip_prefix("- ").unwrap_or(text))))
+    }
+
+    /// Drop block markers from transcluded text and cut it to length
+    fn clean(&self, text: &str) -> String {
+        let text = BLOCK_MARKER.replace_all(text, "");
+        let text = BLOCK_ID_PROPERTY.replace_all(&text, "");
+        truncate_text(text.trim(), self.max_transclusion_chars)
+    }
+
+    fn push_resolved(&self, result: &mut NoteTransclusions, start: usize, end: usize, original: &str, resolved: Option<(usize, String)>) {
+        match resolved {
+            Some((note, text)) => {
+                result.transclusions.push(Transclusion { start, end, text });
+                result.sources.push(self.vault_path.join(&self.notes[note]));
+            }
+            None => result.unresolved.push(original.to_string()),
+        }
+    }
+
+    /// Whether an embedded attachment exists, honoring the attachment folder setting
+    fn attachment_exists(&self, source_key: Option<&str>, target: &str) -> bool {
+        let note_folder = source_key
+            .and_then(|key| key.rsplit_once('/'))
+            .map_or("", |(folder, _)| folder);
+        let target = target.trim_start_matches('/');
+        let candidates = [
+            join_key(&self.profile.attachment_folder.resolve(note_folder), target),
+            join_key(note_folder, target),
+            target.to_string(),
+        ];
+        if candidates.iter().any(|candidate| self.vault_path.join(candidate).is_file()) {
+            return true;
+        }
+        // Obsidian also finds attachments by file name anywhere in the vault
+        let file_name = target.rsplit('/').next().unwrap_or(target);
+        !target.contains('/') && self.attachments.contains(&file_name.to_lowercase())
+    }
+}
+
+/// `TransclusionResolver` for one note of a vault
+#[derive(Debug)]
+struct NoteTransclusionResolver {
+    vault: Arc<VaultCompat>,
+    note_path: PathBuf,
+}
+
+impl TransclusionResolver for NoteTransclusionResolver {
+    fn transclusions(&self, text: &str) -> Vec<Transclusion> {
+        let resolved = self.vault.transclusions(&self.note_path, text);
+        if !resolved.unresolved.is_empty() {
+            log::debug!("🔗 Unresolved references in {:?}: {:?}", self.note_path, resolved.unresolved);
+        }
+        resolved.transclusions
+    }
+}
+
+/// Section of a note starting at a heading (nested headings use the last part)
+fn heading_section(content: &str, heading: &str) -> Option<String> {
+    let heading = heading.rsplit('#').next().unwrap_or(heading).trim();
+    let lines: Vec<&str> = content.lines().collect();
+    let heading_level = |line: &str| {
+        let level = line.chars().take_while(|&c| c == '#').count();
+        ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+    };
+
+    let start = lines.iter().position(|line| {
+        heading_level(line).is_some_and(|level| line[level..].trim().eq_ignore_ascii_case(heading))
+    })?;
+    let level = heading_level(lines[start])?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| heading_level(line).is_some_and(|other| other <= level))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    Some(lines[start..end].join("\n"))
+}
+
+/// Block of a note marked with `^id`: a list item with its children, or a paragraph
+fn obsidian_block(content: &str, block_id: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let marker = format!("^{}", block_id);
+    let marked = lines.iter().position(|line| {
+        let line = line.trim_end();
+        line == marker || line.ends_with(&format!(" {}", marker)) || line.ends_with(&format!("\t{}", marker))
+    })?;
+    let paragraph_start = |end: usize| {
+        (0..end).rev().find(|&line| lines[line].trim().is_empty()).map_or(0, |blank| blank + 1)
+    };
+
+    let (start, end) = if lines[marked].trim() == marker {
+        // A marker on its own line names the block above it
+        (paragraph_start(marked), marked)
+    } else if is_list_item(lines[marked]) {
+        let indent = indentation(lines[marked]);
+        let children = lines[marked + 1..]
+            .iter()
+            .take_while(|line| !line.trim().is_empty() && indentation(line) > indent)
+            .count();
+        (marked, marked + 1 + children)
+    } else {
+        (paragraph_start(marked), marked + 1)
+    };
+    Some(lines[start..end].join("\n"))
+}
+
+/// Page names of a Logseq page: namespaces use `/`, journals their default title
+fn logseq_page_names(key: &str, journal_titles: bool) -> Vec<String> {
+    let file_name = key.rsplit('/').next().unwrap_or(key);
+    let stem = file_name.strip_suffix(".md").unwrap_or(file_name);
+    let mut names = vec![stem.replace("___", "/").replace("%2F", "/").to_lowercase()];
+    if journal_titles && key.starts_with("journals/") {
+        if let Ok(date) = NaiveDate::parse_from_str(stem, "%Y_%m_%d") {
+            names.push(journal_title(date).to_lowercase());
+        }
+    }
+    names
+}
+
+/// Journal title in Logseq's default `MMM do, yyyy` format
+fn journal_title(date: NaiveDate) -> String {
+    let day = date.format("%-d").to_string();
+    let suffix = match (day.as_str(), day.chars().last()) {
+        ("11" | "12" | "13", _) => "th",
+        (_, Some('1')) => "st",
+        (_, Some('2')) => "nd",
+        (_, Some('3')) => "rd",
+        _ => "th",
+    };
+    format!("{} {}{}, {}", date.format("%b"), day, suffix, date.format("%Y"))
+}
+
+/// Leading `key:: value` properties of a Logseq page (lowercase keys)
+fn page_properties(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map_while(|line| PROPERTY.captures(line))
+        .map(|captures| (captures[1].to_lowercase(), captures[2].trim().to_string()))
+        .collect()
+}
+
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") || line == "-" || {
+        let digits = line.chars().take_while(char::is_ascii_digit).count();
+        digits > 0 && line[digits..].starts_with(". ")
+    }
+}
+
+/// Indentation width of a line, counting a tab as four spaces
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+fn overlaps(transclusions: &[Transclusion], start: usize, end: usize) -> bool {
+    transclusions.iter().any(|transclusion| start < transclusion.end && transclusion.start < end)
+}
+
+/// Cut text to at most `max_chars` characters, at a word boundary when possible
+fn truncate_text(text: &str, max_chars: usize) -> String {
+    let Some((cut, _)) = text.char_indices().nth(max_chars) else {
+        return text.to_string();
+    };
+    let cut = text[..cut].rfind(char::is_whitespace).filter(|&space| space > 0).unwrap_or(cut);
+    format!("{}…", text[..cut].trim_end())
+}
+
+/// Vault-relative path with `/` separators, or `None` outside the vault
+fn relative_key(vault_path: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(vault_path).ok()?;
+    Some(relative.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+fn join_key(folder: &str, name: &str) -> String {
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", folder, name)
+    }
+}
+
+/// Read a JSON settings file; `Ok(None)` if it does not exist
+fn read_json(path: &Path) -> Result<Option<JsonValue>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    serde_json::from_str(&content).map(Some).map_err(|e| format!("Failed to parse {:?}: {}", path, e))
+}
+
+/// Drop `;` comments from EDN, outside of strings
+fn strip_edn_comments(config: &str) -> String {
+    let mut stripped = String::with_capacity(config.len());
+    for line in config.lines() {
+        let mut in_string = false;
+        let mut previous = None;
+        for c in line.chars() {
+            if c == ';' && !in_string {
+                break;
+            }
+            if c == '"' && previous != Some('\\') {
+                in_string = !in_string;
+            }
+            stripped.push(c);
+            previous = Some(c);
+        }
+        stripped.push('\n');
+    }
+    stripped
+}
+
+/// Strings of the EDN vector stored under `key`
+fn edn_strings(config: &str, key: &str) -> Vec<String> {
+    let vector = Regex::new(&format!(r"{}\s*\[([^\]]*)\]", regex::escape(key))).unwrap();
+    let Some(captures) = vector.captures(config) else {
+        return Vec::new();
+    };
+    static STRING: Lazy<Regex> = Lazy::new(|| Regex::new(r#""((?:[^"\\]|\\.)*)""#).unwrap());
+    STRING.captures_iter(&captures[1]).map(|string| string[1].to_string()).collect()
+}
+
+/// String or keyword value stored under `key`, without quotes or leading `:`
+fn edn_scalar(config: &str, key: &str) -> Option<String> {
+    let scalar = Regex::new(&format!(r#"{}\s+(?:"([^"]*)"|:([\w/.-]+))"#, regex::escape(key))).unwrap();
+    let captures = scalar.captures(config)?;
+    captures.get(1).or_else(|| captures.get(2)).map(|value| value.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_file(vault: &Path, relative: &str, content: &str) -> PathBuf {
+        let path = vault.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn replacement<'a>(transclusions: &'a NoteTransclusions, text: &str, original: &str) -> Option<&'a str> {
+        let start = text.find(original)?;
+        transclusions
+            .transclusions
+            .iter()
+            .find(|transclusion| transclusion.start == start && transclusion.end == start + original.len())
+            .map(|transclusion| transclusion.text.as_str())
+    }
+
+    fn obsidian_vault() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = temp_dir.path();
+        write_file(
+            vault,
+            ".obsidian/app.json",
+            r#"{"userIgnoreFilters": ["Archive/", "/\\.excalidraw\\.md$/", "/[unclosed/"], "attachmentFolderPath": "./attachments"}"#,
+        );
+        write_file(vault, ".obsidian/community-plugins.json", r#"["dataview", "calendar"]"#);
+        write_file(vault, ".trash/Deleted.md", "Gone");
+        write_file(vault, "Archive/Old.md", "Old plans");
+        write_file(vault, "Drawing.excalidraw.md", "compressed-json");
+        write_file(vault, "Board.canvas", "{}");
+        write_file(
+            vault,
+            "Projects/Plan.md",
+            "---\naliases: [Roadmap]\n---\n# Plan\n\nShip the beta in May.\n\n## Risks\n\nHiring is slow.\n- Budget cuts ^budget\n  - Q3 review\n- Other\n\n# Later\n\nNothing yet.\n",
+        );
+        write_file(vault, "Projects/attachments/diagram.png", "png");
+        write_file(vault, "Ideas.md", "A loose idea.\n^idea\n");
+        temp_dir
+    }
+
+    #[test]
+    fn test_obsidian_profile_and_ignore_filters() {
+        let temp_dir = obsidian_vault();
+        let vault = VaultCompat::open(temp_dir.path()).unwrap();
+        let profile = vault.profile();
+
+        assert_eq!(profile.flavor, VaultFlavor::Obsidian);
+        assert_eq!(profile.attachment_folder, AttachmentFolder::NoteSubfolder("attachments".to_string()));
+        assert!(profile.is_ignored("Archive", true));
+        assert!(profile.is_ignored("Archive/Old.md", false));
+        assert!(profile.is_ignored(".trash/Deleted.md", false));
+        assert!(profile.is_ignored("Drawing.excalidraw.md", false));
+        assert!(!profile.is_ignored("Archived.md", false));
+        assert!(!profile.is_ignored("Projects/Plan.md", false));
+
+        let notes: Vec<PathBuf> = vault.note_paths();
+        assert_eq!(notes, vec![temp_dir.path().join("Ideas.md"), temp_dir.path().join("Projects/Plan.md")]);
+
+        let report = vault.report();
+        let features: Vec<&str> = report.unmapped_features.iter().map(|feature| feature.feature.as_str()).collect();
+        assert_eq!(features, vec!["Ignore filter /[unclosed/", "Plugin dataview", "Canvas files"]);
+        assert_eq!(report.note_count, 2);
+    }
+
+    #[test]
+    fn test_obsidian_embeds_and_block_links() {
+        let temp_dir = obsidian_vault();
+        let vault = VaultCompat::open(temp_dir.path()).unwrap();
+        let note_path = temp_dir.path().join("Projects/Daily.md");
+        let text = "Goals: ![[Plan#Risks]]\nBudget: [[Plan#^budget]] and [[Plan#^budget|the cut]]\n\
+                    Whole: ![[Roadmap]] ![[diagram.png]] ![[missing.png]] ![[Nowhere]]\n\
+                    Idea: ![[Ideas#^idea]]\nSee [[Plan]]. Mine ^goal\n```\n![[Plan]]\n```\n";
+        let transclusions = vault.transclusions(&note_path, text);
+
+        let risks = replacement(&transclusions, text, "![[Plan#Risks]]").unwrap();
+        assert!(risks.starts_with("## Risks\n\nHiring is slow."));
+        assert!(risks.contains("- Budget cuts\n") && !risks.contains("^budget") && !risks.contains("Later"));
+        assert_eq!(replacement(&transclusions, text, "[[Plan#^budget]]"), Some("- Budget cuts\n  - Q3 review"));
+        assert_eq!(replacement(&transclusions, text, "[[Plan#^budget|the cut]]"), Some("the cut"));
+        // Aliases resolve, and the frontmatter is left out
+        let whole = replacement(&transclusions, text, "![[Roadmap]]").unwrap();
+        assert!(whole.starts_with("# Plan") && whole.ends_with("Nothing yet."));
+        assert_eq!(replacement(&transclusions, text, "![[Ideas#^idea]]"), Some("A loose idea."));
+        assert_eq!(replacement(&transclusions, text, " ^goal"), Some(""));
+        assert_eq!(replacement(&transclusions, text, "[[Plan]]"), None);
+
+        assert_eq!(transclusions.unresolved, vec!["![[missing.png]]", "![[Nowhere]]"]);
+        assert_eq!(transclusions.sources, vec![temp_dir.path().join("Ideas.md"), temp_dir.path().join("Projects/Plan.md")]);
+        assert!(transclusions.transclusions.windows(2).all(|pair| pair[0].end <= pair[1].start));
+        // The fenced embed is untouched
+        assert!(transclusions.transclusions.iter().all(|transclusion| transclusion.start < text.find("```").unwrap()));
+    }
+
+    #[test]
+    fn test_logseq_pages_and_block_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault_path = temp_dir.path();
+        write_file(
+            vault_path,
+            "logseq/config.edn",
+            "{:preferred-format :markdown\n ;; :hidden [\"/pages\"]\n :hidden [\"/private\" \"pages/draft.md\"]\n :journal/page-title-format \"MMM do, yyyy\"}\n",
+        );
+        write_file(vault_path, "logseq/bak/pages/Project.md", "- backup");
+        write_file(vault_path, "private/Secret.md", "- hidden");
+        write_file(vault_path, "pages/draft.md", "- draft");
+        write_file(
+            vault_path,
+            "pages/Project___Alpha.md",
+            "alias:: Alpha\ntags:: work\n\n- Kickoff on Monday\n  id:: 64a1f0c2-1111-4222-8333-944455556666\n\t- Invite the team\n- Budget review {{query (todo now)}}\n",
+        );
+        write_file(vault_path, "journals/2024_01_15.md", "- Wrote the [[Alpha]] brief\n");
+        write_file(vault_path, "assets/photo.png", "png");
+
+        let vault = VaultCompat::open(vault_path).unwrap();
+        assert_eq!(vault.profile().flavor, VaultFlavor::Logseq);
+        assert_eq!(vault.profile().ignore_filters, vec!["/private", "/pages/draft.md"]);
+        assert_eq!(vault.note_paths().len(), 2);
+
+        let note_path = vault_path.join("pages/Notes.md");
+        let text = "- Ref: ((64a1f0c2-1111-4222-8333-944455556666))\n\
+                    - {{embed ((64a1f0c2-1111-4222-8333-944455556666))}}\n\
+                    - {{embed [[Project/Alpha]]}}\n\
+                    - {{embed [[Jan 15th, 2024]]}}\n\
+                    - ((00000000-0000-4000-8000-000000000000))\n\
+                    - Own block\n  id:: 11111111-2222-4333-8444-555555555555\n";
+        let transclusions = vault.transclusions(&note_path, text);
+
+        assert_eq!(replacement(&transclusions, text, "((64a1f0c2-1111-4222-8333-944455556666))"), Some("Kickoff on Monday"));
+        assert_eq!(
+            replacement(&transclusions, text, "{{embed ((64a1f0c2-1111-4222-8333-944455556666))}}"),
+            Some("Kickoff on Monday\n\t- Invite the team")
+        );
+        let page = replacement(&transclusions, text, "{{embed [[Project/Alpha]]}}").unwrap();
+        assert!(page.starts_with("- Kickoff on Monday\n\t- Invite") && !page.contains("alias::") && !page.contains("id::"));
+        assert_eq!(replacement(&transclusions, text, "{{embed [[Jan 15th, 2024]]}}"), Some("- Wrote the [[Alpha]] brief"));
+        assert_eq!(replacement(&transclusions, text, "\n  id:: 11111111-2222-4333-8444-555555555555"), Some(""));
+        assert_eq!(transclusions.unresolved, vec!["((00000000-0000-4000-8000-000000000000))"]);
+        assert_eq!(transclusions.sources, vec![vault_path.join("journals/2024_01_15.md"), vault_path.join("pages/Project___Alpha.md")]);
+
+        let report = vault.report();
+        assert!(report.unmapped_features.iter().any(|feature| feature.feature == "Macro {{query}}"));
+    }
+
+    #[test]
+    fn test_plain_vault_and_truncation() {
+        let temp_dir = TempDir::new().unwrap();
+        write_file(temp_dir.path(), ".obsidian-like/Note.md", "hidden");
+        write_file(temp_dir.path(), "Long.md", &"word ".repeat(50));
+        let vault = VaultCompat::open(temp_dir.path()).unwrap().with_max_transclusion_chars(22);
+
+        assert_eq!(vault.profile().flavor, VaultFlavor::Plain);
+        assert!(!vault.profile().is_ignored("Long.md", false));
+        let text = "![[Long]]";
+        let transclusions = vault.transclusions(&temp_dir.path().join("Other.md"), text);
+        assert_eq!(transclusions.transclusions[0].text, "word word word word…");
+
+        assert!(matches!(VaultCompat::open(temp_dir.path().join("missing")), Err(VaultCompatError::VaultNotFound { .. })));
+        assert_eq!(journal_title(NaiveDate::from_ymd_opt(2024, 3, 22).unwrap()), "Mar 22nd, 2024");
+        assert_eq!(journal_title(NaiveDate::from_ymd_opt(2024, 3, 11).unwrap()), "Mar 11th, 2024");
+    }
+}
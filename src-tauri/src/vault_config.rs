@@ -0,0 +1,500 @@
+//! # Vault Configuration
+//!
+//! Settings of a vault, kept in `{vault}/.ainote/config.toml` next to its
+//! vector storage, so they travel with the vault and survive restarts.
+//!
+//! ## Format
+//!
+//! ```toml
+//! version = 1
+//!
+//! [pipeline]
+//! worker_count = 4
+//! embedding_model = "nomic-embed-text"
+//!
+//! [chunking]
+//! max_chunk_size = 800
+//!
+//! [chunking.hierarchy]
+//! enabled = false
+//!
+//! [search]
+//! rrf_k = 40.0
+//!
+//! [file_monitor]
+//! debounce_ms = 500
+//! ```
+//!
+//! Each section holds fields of a subsystem's configuration struct:
+//!
+//! - `[pipeline]`: `PipelineConfig`
+//! - `[chunking]`: `ChunkConfig`
+//! - `[search]`: `SearchConfig`
+//! - `[incremental]`: `IncrementalConfig`
+//! - `[maintenance]`: `MaintenanceConfig`
+//! - `[file_monitor]`: `FileMonitorConfig`
+//!
+//! Keys that are left out keep their defaults, and a vault without the file
+//! uses the defaults throughout.
+//!
+//! ## Validation
+//!
+//! The whole file is rejected, naming the offending key, when it has a key no
+//! section knows, a value of the wrong type or out of range, or a `version`
+//! newer than this build supports. An invalid file never replaces a
+//! configuration that is already in use.
+//!
+//! ## Applying
+//!
+//! `globals::open_vault_config` loads the file when a vault is opened, and
+//! again through `FileMonitor` whenever it changes. Search and file monitoring
+//! follow a reload right away; pipeline and chunking settings apply from the
+//! next indexing run, and maintenance and incremental update settings when
+//! those systems are next enabled.
+
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use thiserror::Error;
+
+use crate::file_monitor::FileMonitorConfig;
+use crate::indexing_pipeline::PipelineConfig;
+use crate::similarity_search::{SearchConfig, SIMILARITY_THRESHOLD_RANGE};
+use crate::text_chunker::ChunkConfig;
+use crate::vector_db::incremental::IncrementalConfig;
+use crate::vector_db::maintenance::MaintenanceConfig;
+
+/// Newest config file version this build reads
+pub const VAULT_CONFIG_VERSION: u32 = 1;
+
+/// File name of the vault config inside `{vault}/.ainote/`
+pub const VAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Sections of the config file, one per subsystem
+const SECTIONS: [&str; 6] = ["pipeline", "chunking", "search", "incremental", "maintenance", "file_monitor"];
+
+/// Errors that can occur while loading a vault config
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum VaultConfigError {
+    #[error("Failed to read {path}: {message}")]
+    IOError { path: String, message: String },
+
+    #[error("{path} is not valid TOML: {message}")]
+    ParseError { path: String, message: String },
+
+    #[error("{path} has no `version` key; add `version = {}` at the top", VAULT_CONFIG_VERSION)]
+    MissingVersion { path: String },
+
+    #[error("{path} has version {found}, but this version of aiNote reads up to version {supported}")]
+    UnsupportedVersion { path: String, found: i64, supported: u32 },
+
+    #[error("Unknown key `{key}` in {path}; expected one of: {expected}")]
+    UnknownKey { path: String, key: String, expected: String },
+
+    #[error("Invalid value for `{key}` in {path}: {message}")]
+    InvalidValue { path: String, key: String, message: String },
+}
+
+pub type VaultConfigResult<T> = Result<T, VaultConfigError>;
+
+/// Configuration of every subsystem for one vault
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultConfig {
+    /// Version of the config file format
+    pub version: u32,
+    /// Indexing pipeline settings
+    pub pipeline: PipelineConfig,
+    /// Chunking settings
+    pub chunking: ChunkConfig,
+    /// Search settings used as the base of every query
+    pub search: SearchConfig,
+    /// Incremental update settings
+    pub incremental: IncrementalConfig,
+    /// Database maintenance settings
+    pub maintenance: MaintenanceConfig,
+    /// File monitoring settings
+    pub file_monitor: FileMonitorConfig,
+    /// Keys set in the file, by section, to apply over other base configurations
+    #[serde(skip)]
+    overrides: JsonMap<String, JsonValue>,
+    /// Vault the configuration was loaded for
+    #[serde(skip)]
+    vault_path: Option<PathBuf>,
+}
+
+impl Default for VaultConfig {
+    fn default() -> Self {
+        Self {
+            version: VAULT_CONFIG_VERSION,
+            pipeline: PipelineConfig::default(),
+            chunking: ChunkConfig::default(),
+            search: SearchConfig::default(),
+            incremental: IncrementalConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            file_monitor: FileMonitorConfig::default(),
+            overrides: JsonMap::new(),
+            vault_path: None,
+        }
+    }
+}
+
+impl VaultConfig {
+    /// Path of the config file of a vault
+    pub fn path(vault_path: &Path) -> PathBuf {
+        vault_path.join(".ainote").join(VAULT_CONFIG_FILE)
+    }
+
+    /// Default configuration for a vault without a config file
+    pub fn for_vault(vault_path: &Path) -> Self {
+        Self { vault_path: Some(vault_path.to_path_buf()), ..Self::default() }
+    }
+
+    /// Load the config file of a vault; defaults if it does not exist
+    pub fn load(vault_path: &Path) -> VaultConfigResult<Self> {
+        let path = Self::path(vault_path);
+        let config = match std::fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content, &path)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                return Err(VaultConfigError::IOError {
+                    path: path.display().to_string(),
+                    message: e.to_string(),
+                });
+            }
+        };
+        Ok(Self { vault_path: Some(vault_path.to_path_buf()), ..config })
+    }
+
+    /// Vault the configuration was loaded for (`None` for plain defaults)
+    pub fn vault_path(&self) -> Option<&Path> {
+        self.vault_path.as_deref()
+    }
+
+    /// Parse and validate config file contents; `path` is used in error messages
+    pub fn parse(content: &str, path: &Path) -> VaultConfigResult<Self> {
+        let path = path.display().to_string();
+        let table: toml::Table = toml::from_str(content).map_err(|e| {
+            let message = match e.span() {
+                Some(span) => format!("line {}: {}", content[..span.start].lines().count().max(1), e.message()),
+                None => e.message().to_string(),
+            };
+            VaultConfigError::ParseError { path: path.clone(), message }
+        })?;
+
+        let mut config = Self::default();
+        let mut version = None;
+        for (key, value) in table {
+            if key == "version" {
+                version = Some(value);
+                continue;
+            }
+            if !SECTIONS.contains(&key.as_str()) {
+                return Err(VaultConfigError::UnknownKey {
+                    path,
+                    key,
+                    expected: format!("version, {}", SECTIONS.join(", ")),
+                });
+            }
+            let toml::Value::Table(section) = value else {
+                return Err(VaultConfigError::InvalidValue {
+                    path,
+                    message: format!("expected a table (`[{}]`)", key),
+                    key,
+                });
+            };
+            let overrides = serde_json::to_value(section).map_err(|e| VaultConfigError::InvalidValue {
+                path: path.clone(),
+                key: key.clone(),
+                message: e.to_string(),
+            })?;
+            config.overrides.insert(key, overrides);
+        }
+
+        config.version = match version {
+            None => return Err(VaultConfigError::MissingVersion { path }),
+            Some(toml::Value::Integer(found)) if found > VAULT_CONFIG_VERSION as i64 => {
+                return Err(VaultConfigError::UnsupportedVersion { path, found, supported: VAULT_CONFIG_VERSION });
+            }
+            Some(toml::Value::Integer(found)) if found >= 1 => found as u32,
+            Some(value) => {
+                return Err(VaultConfigError::InvalidValue {
+                    path,
+                    key: "version".to_string(),
+                    message: format!("expected a positive integer, found {}", value),
+                });
+            }
+        };
+
+        config.pipeline = config.resolve("pipeline", PipelineConfig::default(), &path)?;
+        config.chunking = config.resolve("chunking", ChunkConfig::default(), &path)?;
+        config.search = config.resolve("search", SearchConfig::default(), &path)?;
+        config.incremental = config.resolve("incremental", IncrementalConfig::default(), &path)?;
+        config.maintenance = config.resolve("maintenance", MaintenanceConfig::default(), &path)?;
+        config.file_monitor = config.resolve("file_monitor", FileMonitorConfig::default(), &path)?;
+        config.validate(&path)?;
+        Ok(config)
+    }
+
+    /// Chunking keys of the file applied over `base`, e.g. a chunk configuration sized for the embedding model
+    pub fn chunk_config_over(&self, base: ChunkConfig) -> ChunkConfig {
+        match self.resolve("chunking", base.clone(), VAULT_CONFIG_FILE) {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("⚠️ Ignoring [chunking] of the vault config: {}", e);
+                base
+            }
+        }
+    }
+
+    /// Whether the file sets any key of a section
+    pub fn has_section(&self, section: &str) -> bool {
+        self.overrides.contains_key(section)
+    }
+
+    /// Apply the keys a section sets over `base`
+    fn resolve<T: Serialize + DeserializeOwned>(&self, section: &str, base: T, path: &str) -> VaultConfigResult<T> {
+        let Some(JsonValue::Object(overrides)) = self.overrides.get(section) else {
+            return Ok(base);
+        };
+        let invalid = |key: String, message: String| VaultConfigError::InvalidValue { path: path.to_string(), key, message };
+
+        let base = serde_json::to_value(&base).map_err(|e| invalid(section.to_string(), e.to_string()))?;
+        let mut merged = base.clone();
+        if let JsonValue::Object(target) = &mut merged {
+            merge_overrides(target, overrides, section).map_err(|(key, expected)| VaultConfigError::UnknownKey {
+                path: path.to_string(),
+                key,
+                expected,
+            })?;
+        }
+
+        serde_json::from_value(merged).map_err(|e| {
+            // Name the key whose value does not fit by applying the keys one at a time
+            let key = leaf_keys(overrides, section)
+                .into_iter()
+                .find(|(_, single)| {
+                    let mut merged = base.clone();
+                    if let JsonValue::Object(target) = &mut merged {
+                        let _ = merge_overrides(target, single, section);
+                    }
+                    serde_json::from_value::<T>(merged).is_err()
+                })
+                .map_or_else(|| section.to_string(), |(key, _)| key);
+            invalid(key, e.to_string())
+        })
+    }
+
+    /// Check value ranges the types alone do not capture
+    fn validate(&self, path: &str) -> VaultConfigResult<()> {
+        let invalid = |key: &str, message: &str| {
+            Err(VaultConfigError::InvalidValue { path: path.to_string(), key: key.to_string(), message: message.to_string() })
+        };
+
+        if self.pipeline.worker_count == 0 {
+            return invalid("pipeline.worker_count", "must be at least 1");
+        }
+        if self.pipeline.max_queue_size == 0 {
+            return invalid("pipeline.max_queue_size", "must be at least 1");
+        }
+        if self.pipeline.file_timeout_seconds == 0 {
+            return invalid("pipeline.file_timeout_seconds", "must be at least 1");
+        }
+        if self.pipeline.embedding_model.trim().is_empty() {
+            return invalid("pipeline.embedding_model", "must not be empty");
+        }
+        if let Err(e) = self.chunking.validate() {
+            return invalid("chunking", &e.to_string());
+        }
+        if !SIMILARITY_THRESHOLD_RANGE.contains(&self.search.min_threshold) {
+            return invalid("search.min_threshold", "must be between -1.0 and 1.0");
+        }
+        if self.search.vector_weight < 0.0 || self.search.lexical_weight < 0.0 {
+            return invalid("search.vector_weight", "fusion weights must not be negative");
+        }
+        if self.search.rrf_k <= 0.0 {
+            return invalid("search.rrf_k", "must be greater than 0");
+        }
+        if self.incremental.max_batch_size == 0 {
+            return invalid("incremental.max_batch_size", "must be at least 1");
+        }
+        if self.maintenance.maintenance_interval_seconds == 0 {
+            return invalid("maintenance.maintenance_interval_seconds", "must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&self.maintenance.compaction_threshold) {
+            return invalid("maintenance.compaction_threshold", "must be between 0.0 and 1.0");
+        }
+        if self.file_monitor.monitored_extensions.is_empty() {
+            return invalid("file_monitor.monitored_extensions", "must list at least one extension");
+        }
+        Ok(())
+    }
+}
+
+/// Merge the keys of `overrides` into `target`, rejecting keys `target` does not have
+///
+/// Nested tables are merged key by key. An empty object in `target` (a map
+/// such as `search.filter.fields`) takes any keys. Returns the dotted path of
+/// the first unknown key and the keys that were expected instead.
+fn merge_overrides(target: &mut JsonMap<String, JsonValue>, overrides: &JsonMap<String, JsonValue>, prefix: &str) -> Result<(), (String, String)> {
+    let expected = target.keys().cloned().collect::<Vec<_>>().join(", ");
+    for (key, value) in overrides {
+        let path = format!("{}.{}", prefix, key);
+        let Some(slot) = target.get_mut(key) else {
+            return Err((path, expected));
+        };
+        match (slot, value) {
+            (JsonValue::Object(existing), JsonValue::Object(nested)) if !existing.is_empty() => {
+                merge_overrides(existing, nested, &path)?;
+            }
+            (slot, value) => *slot = value.clone(),
+        }
+    }
+    Ok(())
+}
+
+/// Every key of a section as a dotted path, with the overrides setting only that key
+fn leaf_keys(overrides: &JsonMap<String, JsonValue>, prefix: &str) -> Vec<(String, JsonMap<String, JsonValue>)> {
+    let mut keys = Vec::new();
+    for (key, value) in overrides {
+        let path = format!("{}.{}", prefix, key);
+        match value {
+            JsonValue::Object(nested) if !nested.is_empty() => {
+                for (nested_path, single) in leaf_keys(nested, &path) {
+                    let mut wrapped = JsonMap::new();
+                    wrapped.insert(key.clone(), JsonValue::Object(single));
+                    keys.push((nested_path, wrapped));
+                }
+            }
+            _ => {
+                let mut single = JsonMap::new();
+                single.insert(key.clone(), value.clone());
+                keys.push((path, single));
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn parse(content: &str) -> VaultConfigResult<VaultConfig> {
+        VaultConfig::parse(content, Path::new("config.toml"))
+    }
+
+    #[test]
+    fn test_parse_applies_sections_over_defaults() {
+        let config = parse(
+            r#"
+            version = 1
+
+            [pipeline]
+            worker_count = 3
+            embedding_model = "mxbai-embed-large"
+
+            [chunking]
+            max_chunk_size = 800
+            strategy = "MarkdownAware"
+
+            [chunking.hierarchy]
+            window_sentences = 5
+
+            [search]
+            rrf_k = 40.0
+
+            [search.filter.fields]
+            status = "published"
+
+            [file_monitor]
+            debounce_ms = 250
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.pipeline.worker_count, 3);
+        assert_eq!(config.pipeline.embedding_model, "mxbai-embed-large");
+        assert_eq!(config.pipeline.max_queue_size, PipelineConfig::default().max_queue_size);
+        assert_eq!(config.chunking.max_chunk_size, 800);
+        assert_eq!(config.chunking.hierarchy.window_sentences, 5);
+        assert_eq!(config.chunking.hierarchy.window_overlap_sentences, 1);
+        assert_eq!(config.search.rrf_k, 40.0);
+        assert_eq!(config.search.filter.fields.get("status").map(String::as_str), Some("published"));
+        assert_eq!(config.file_monitor.debounce_ms, 250);
+        assert_eq!(config.maintenance.maintenance_interval_seconds, MaintenanceConfig::default().maintenance_interval_seconds);
+        assert!(config.has_section("chunking") && !config.has_section("maintenance"));
+
+        // Only the keys the file sets replace a different base
+        let base = ChunkConfig { max_tokens: Some(256), ..ChunkConfig::default() };
+        let chunking = config.chunk_config_over(base);
+        assert_eq!((chunking.max_chunk_size, chunking.max_tokens), (800, Some(256)));
+    }
+
+    #[test]
+    fn test_parse_reports_invalid_files() {
+        let error = |content: &str| parse(content).unwrap_err();
+
+        assert!(matches!(error("version = 1\n[pipeline\n"), VaultConfigError::ParseError { message, .. } if message.starts_with("line 2: ")));
+        assert!(matches!(error("[pipeline]\nworker_count = 2\n"), VaultConfigError::MissingVersion { .. }));
+        assert!(matches!(error("version = 2\n"), VaultConfigError::UnsupportedVersion { found: 2, .. }));
+        assert!(matches!(error("version = \"1\"\n"), VaultConfigError::InvalidValue { key, .. } if key == "version"));
+
+        match error("version = 1\n[pipline]\nworker_count = 2\n") {
+            VaultConfigError::UnknownKey { key, expected, .. } => {
+                assert_eq!(key, "pipline");
+                assert!(expected.contains("pipeline"));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        match error("version = 1\n[chunking.hierarchy]\nwindow_size = 4\n") {
+            VaultConfigError::UnknownKey { key, expected, .. } => {
+                assert_eq!(key, "chunking.hierarchy.window_size");
+                assert!(expected.contains("window_sentences"));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        match error("version = 1\n[chunking]\nmin_chunk_size = 10\nmax_chunk_size = \"large\"\n") {
+            VaultConfigError::InvalidValue { key, .. } => assert_eq!(key, "chunking.max_chunk_size"),
+            other => panic!("unexpected error: {}", other),
+        }
+        match error("version = 1\n[pipeline]\nworker_count = 0\n") {
+            VaultConfigError::InvalidValue { key, message, .. } => {
+                assert_eq!(key, "pipeline.worker_count");
+                assert_eq!(message, "must be at least 1");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        assert!(matches!(
+            error("version = 1\n[chunking]\nmax_chunk_size = 5000\n"),
+            VaultConfigError::InvalidValue { key, .. } if key == "chunking"
+        ));
+        assert!(matches!(error("version = 1\nsearch = 3\n"), VaultConfigError::InvalidValue { key, .. } if key == "search"));
+
+        // Thresholds follow the cosine range accepted by the search commands
+        let content = "version = 1\n[search]\nmin_threshold = -0.2\n";
+        assert_eq!(VaultConfig::parse(content, Path::new("config.toml")).unwrap().search.min_threshold, -0.2);
+        assert!(matches!(
+            error("version = 1\n[search]\nmin_threshold = 1.5\n"),
+            VaultConfigError::InvalidValue { key, .. } if key == "search.min_threshold"
+        ));
+    }
+
+    #[test]
+    fn test_load_from_vault() {
+        let vault = TempDir::new().unwrap();
+        let defaults = VaultConfig::load(vault.path()).unwrap();
+        assert_eq!(defaults.version, VAULT_CONFIG_VERSION);
+        assert_eq!(defaults.vault_path(), Some(vault.path()));
+
+        std::fs::create_dir_all(vault.path().join(".ainote")).unwrap();
+        std::fs::write(VaultConfig::path(vault.path()), "version = 1\n[incremental]\nmax_batch_size = 10\n").unwrap();
+        assert_eq!(VaultConfig::load(vault.path()).unwrap().incremental.max_batch_size, 10);
+
+        std::fs::write(VaultConfig::path(vault.path()), "version = 1\n[incremental]\nmax_batch = 10\n").unwrap();
+        let message = VaultConfig::load(vault.path()).unwrap_err().to_string();
+        assert!(message.starts_with("Unknown key `incremental.max_batch` in "));
+        assert!(message.contains("config.toml"));
+    }
+}
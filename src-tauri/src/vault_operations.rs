@@ -4,6 +4,7 @@ use crate::errors::{FileSystemError, FileSystemResult};
 use crate::validation;
 use crate::types::FileInfo;
 use crate::performance::{time_operation, PerformanceTracker};
+use crate::vault_compat::VaultProfile;
 // File monitoring is now handled by the enhanced file_monitor module
 
 /// Chunked scanning for very large vaults to avoid UI blocking
@@ -54,8 +55,11 @@ pub fn scan_vault_files_internal(vault_path: &str) -> FileSystemResult<Vec<FileI
         let mut files = Vec::with_capacity(256); // Pre-allocate for typical vaults
         let mut directories = Vec::with_capacity(32); // Track directories to scan
         
+        // Obsidian and Logseq vaults list folders and files to leave out
+        let profile = VaultProfile::detect(vault_path);
+
         // Efficient non-recursive scanning using a work queue
-        scan_directory_iterative(vault_path, &mut files, &mut directories)?;
+        scan_directory_iterative(vault_path, &profile, &mut files, &mut directories)?;
         
         tracker.checkpoint("scanning_complete");
         
@@ -72,13 +76,14 @@ pub fn scan_vault_files_internal(vault_path: &str) -> FileSystemResult<Vec<FileI
 /// Optimized iterative directory scanning to avoid stack overflow and improve performance
 fn scan_directory_iterative(
     root_path: &Path, 
+    profile: &VaultProfile,
     files: &mut Vec<FileInfo>, 
     work_queue: &mut Vec<std::path::PathBuf>
 ) -> FileSystemResult<()> {
     work_queue.push(root_path.to_path_buf());
     
     while let Some(current_dir) = work_queue.pop() {
-        if let Err(e) = scan_single_directory(&current_dir, files, work_queue) {
+        if let Err(e) = scan_single_directory(&current_dir, root_path, profile, files, work_queue) {
             // Log error but continue with other directories
             eprintln!("Warning: Error scanning directory {}: {}", current_dir.display(), e);
         }
@@ -90,6 +95,8 @@ fn scan_directory_iterative(
 /// Scan a single directory efficiently with early filtering and batch processing
 fn scan_single_directory(
     dir: &Path, 
+    root_path: &Path,
+    profile: &VaultProfile,
     files: &mut Vec<FileInfo>, 
     work_queue: &mut Vec<std::path::PathBuf>
 ) -> FileSystemResult<()> {
@@ -114,6 +121,9 @@ fn scan_single_directory(
         };
 
         let path = entry.path();
+        if profile.ignores(root_path, &path) {
+            continue;
+        }
         
         // Fast path check for .md extension before metadata call
         if path.is_file() {
@@ -525,6 +535,21 @@ mod tests {
         assert_eq!(file_files[0].name, "note.md");
     }
 
+    #[test]
+    fn test_scan_vault_files_honors_obsidian_ignore_filters() {
+        let env = TestEnv::new();
+
+        env.create_test_file(".obsidian/app.json", r#"{"userIgnoreFilters": ["Archive/"]}"#).unwrap();
+        env.create_test_file(".trash/deleted.md", "# Deleted").unwrap();
+        env.create_test_file("Archive/old.md", "# Old").unwrap();
+        env.create_test_file("notes/current.md", "# Current").unwrap();
+
+        let files = scan_vault_files_internal(&env.get_path()).unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+
+        assert_eq!(names, vec!["notes", "current.md"]);
+    }
+
     #[test]
     fn test_vault_scanning_comprehensive() {
         let env = TestEnv::new();
@@ -0,0 +1,481 @@
+//! # Vault Text Search
+//!
+//! Finds every occurrence of a literal string or a regular expression in the
+//! vault's markdown notes, grep style, and optionally replaces them.
+//!
+//! ## Search
+//!
+//! Notes come from the same walk as the file tree (`scan_vault_files_internal`)
+//! and are searched in parallel with rayon. Each match reports its file, line
+//! and column (both 1-based, columns in characters) with the surrounding
+//! lines as context. Matches are returned in path order; when there are more
+//! than `max_matches`, the first ones are kept and the report is marked as
+//! truncated while `total_matches` still counts all of them.
+//!
+//! ## Replace
+//!
+//! Find-and-replace rewrites notes one at a time. Every note is locked with
+//! `FileLockGuard`, re-read under the lock and backed up with
+//! `validation::create_backup` before it is written, like the editor's own
+//! saves. A note that cannot be rewritten (for example because it is locked by
+//! another write) is reported as skipped and the others are still processed.
+//!
+//! ## Cancellation
+//!
+//! Both operations check a `CancellationToken` between notes. A cancelled
+//! search returns `VaultSearchError::Cancelled`; a cancelled replace stops
+//! before the next note and reports what was already written.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use rayon::prelude::*;
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::errors::{FileSystemError, FileSystemResult, IOErrorContext};
+use crate::file_locks::FileLockGuard;
+use crate::indexing_pipeline::CancellationToken;
+use crate::validation;
+use crate::vault_operations::scan_vault_files_internal;
+use crate::vector_db::incremental::IncrementalConfig;
+
+/// Errors that can occur while searching the vault
+#[derive(Error, Debug)]
+pub enum VaultSearchError {
+    #[error("Invalid search pattern: {message}")]
+    InvalidPattern { message: String },
+
+    #[error("File system error: {0}")]
+    FileSystem(#[from] FileSystemError),
+
+    #[error("Search cancelled")]
+    Cancelled,
+}
+
+pub type VaultSearchResult<T> = Result<T, VaultSearchError>;
+
+/// What to search for and where
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VaultSearchOptions {
+    /// Text or regular expression to find
+    pub pattern: String,
+    /// Treat `pattern` as a regular expression instead of literal text
+    pub is_regex: bool,
+    /// Match letter case exactly
+    pub case_sensitive: bool,
+    /// Only match whole words
+    pub whole_word: bool,
+    /// Number of lines of context before and after each match
+    pub context_lines: usize,
+    /// Maximum number of matches returned (0 = unlimited)
+    pub max_matches: usize,
+    /// Vault-relative paths to skip; a single name such as `.git` is skipped
+    /// at any depth
+    pub excluded_paths: Vec<PathBuf>,
+}
+
+impl Default for VaultSearchOptions {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            is_regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            context_lines: 2,
+            max_matches: 1000,
+            excluded_paths: IncrementalConfig::default().excluded_paths,
+        }
+    }
+}
+
+/// A single occurrence of the pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextMatch {
+    /// Note containing the match
+    pub file_path: String,
+    /// Line of the match start (1-based)
+    pub line: usize,
+    /// Character column of the match start (1-based)
+    pub column: usize,
+    /// Matched text (may span several lines for multi-line regexes)
+    pub matched_text: String,
+    /// Full text of the line containing the match start
+    pub line_text: String,
+    /// Lines before the match line, in document order
+    pub context_before: Vec<String>,
+    /// Lines after the match line, in document order
+    pub context_after: Vec<String>,
+}
+
+/// Outcome of a vault search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultSearchReport {
+    /// Matches in path and document order
+    pub matches: Vec<TextMatch>,
+    /// Number of notes searched
+    pub files_searched: usize,
+    /// Number of notes with at least one match
+    pub files_with_matches: usize,
+    /// Number of matches found, including those cut by `max_matches`
+    pub total_matches: usize,
+    /// Whether matches were left out because of `max_matches`
+    pub truncated: bool,
+    /// Time spent searching
+    pub search_time_ms: f64,
+}
+
+/// A note that find-and-replace could not rewrite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub file_path: String,
+    pub reason: String,
+}
+
+/// Outcome of a vault-wide find-and-replace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplaceReport {
+    /// Notes that were rewritten
+    pub changed_files: Vec<String>,
+    /// Number of replaced matches across all notes
+    pub replacements: usize,
+    /// Backups written before the notes were changed
+    pub backups: Vec<String>,
+    /// Notes with matches that were left unchanged
+    pub skipped: Vec<SkippedFile>,
+    /// Whether the replace stopped early because it was cancelled
+    pub cancelled: bool,
+}
+
+/// A compiled vault search
+#[derive(Debug, Clone)]
+pub struct VaultSearch {
+    regex: Regex,
+    options: VaultSearchOptions,
+}
+
+impl VaultSearch {
+    /// Compile the search pattern
+    pub fn new(options: VaultSearchOptions) -> VaultSearchResult<Self> {
+        if options.pattern.is_empty() {
+            return Err(VaultSearchError::InvalidPattern {
+                message: "Pattern cannot be empty".to_string(),
+            });
+        }
+
+        let mut pattern = if options.is_regex {
+            options.pattern.clone()
+        } else {
+            regex::escape(&options.pattern)
+        };
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| VaultSearchError::InvalidPattern { message: e.to_string() })?;
+
+        Ok(Self { regex, options })
+    }
+
+    /// Search every note of the vault
+    pub fn search(&self, vault_path: &str, cancellation_token: &CancellationToken) -> VaultSearchResult<VaultSearchReport> {
+        let start_time = Instant::now();
+        let notes = self.note_paths(vault_path)?;
+
+        // Count matches first so only the notes within `max_matches` build full results
+        let matching: Vec<(PathBuf, String, usize)> = notes
+            .par_iter()
+            .filter_map(|path| {
+                if cancellation_token.is_cancelled() {
+                    return None;
+                }
+                let content = read_note(path)?;
+                let count = self.regex.find_iter(&content).count();
+                (count > 0).then(|| (path.clone(), content, count))
+            })
+            .collect();
+        if cancellation_token.is_cancelled() {
+            return Err(VaultSearchError::Cancelled);
+        }
+
+        let total_matches: usize = matching.iter().map(|(_, _, count)| count).sum();
+        let limit = if self.options.max_matches > 0 { self.options.max_matches } else { usize::MAX };
+        let mut remaining = limit;
+        let budgets: Vec<usize> = matching
+            .iter()
+            .map(|(_, _, count)| {
+                let budget = (*count).min(remaining);
+                remaining -= budget;
+                budget
+            })
+            .collect();
+
+        let matches: Vec<TextMatch> = matching
+            .par_iter()
+            .zip(budgets.par_iter())
+            .filter(|(_, budget)| **budget > 0)
+            .flat_map_iter(|((path, content, _), budget)| {
+                self.find_in_content(&path.to_string_lossy(), content, *budget)
+            })
+            .collect();
+
+        log::info!("🔎 Found {} matches of '{}' in {} of {} notes",
+                   total_matches, self.options.pattern, matching.len(), notes.len());
+
+        Ok(VaultSearchReport {
+            truncated: matches.len() < total_matches,
+            matches,
+            files_searched: notes.len(),
+            files_with_matches: matching.len(),
+            total_matches,
+            search_time_ms: start_time.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+
+    /// Replace every match in the vault
+    ///
+    /// In regex mode `replacement` may refer to capture groups (`$1`, `${name}`);
+    /// in literal mode it is inserted as is.
+    pub fn replace(
+        &self,
+        vault_path: &str,
+        replacement: &str,
+        cancellation_token: &CancellationToken,
+    ) -> VaultSearchResult<ReplaceReport> {
+        let notes = self.note_paths(vault_path)?;
+        let candidates: Vec<PathBuf> = notes
+            .par_iter()
+            .filter(|path| read_note(path).is_some_and(|content| self.regex.is_match(&content)))
+            .cloned()
+            .collect();
+
+        let mut report = ReplaceReport::default();
+        for path in candidates {
+            if cancellation_token.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+
+            let file_path = path.to_string_lossy().to_string();
+            match self.replace_in_note(&file_path, replacement) {
+                Ok(Some((count, backup))) => {
+                    report.replacements += count;
+                    report.changed_files.push(file_path);
+                    report.backups.extend(backup);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!("⚠️ Skipping {} during replace: {}", file_path, e);
+                    report.skipped.push(SkippedFile { file_path, reason: e.user_message() });
+                }
+            }
+        }
+
+        log::info!("✏️ Replaced {} matches of '{}' in {} notes ({} skipped)",
+                   report.replacements, self.options.pattern, report.changed_files.len(), report.skipped.len());
+        Ok(report)
+    }
+
+    /// Rewrite one note, returning the replacement count and backup path
+    fn replace_in_note(&self, file_path: &str, replacement: &str) -> FileSystemResult<Option<(usize, Option<String>)>> {
+        let path = Path::new(file_path);
+        let _lock = FileLockGuard::acquire(file_path)?;
+
+        // Re-read under the lock, the note may have changed since it was matched
+        let content = fs::read_to_string(path).with_path_context(file_path, "read")?;
+        let count = self.regex.find_iter(&content).count();
+        if count == 0 {
+            return Ok(None);
+        }
+
+        let replaced = if self.options.is_regex {
+            self.regex.replace_all(&content, replacement)
+        } else {
+            self.regex.replace_all(&content, NoExpand(replacement))
+        };
+        if replaced == content {
+            return Ok(None);
+        }
+        validation::validate_file_size(&replaced, file_path)?;
+
+        let backup = validation::create_backup(path)?;
+        fs::write(path, replaced.as_bytes()).with_path_context(file_path, "replace")?;
+        let _ = validation::cleanup_old_backups(path); // Don't fail on cleanup errors
+
+        Ok(Some((count, backup)))
+    }
+
+    /// Find up to `limit` matches in a note's content
+    fn find_in_content(&self, file_path: &str, content: &str, limit: usize) -> Vec<TextMatch> {
+        let lines: Vec<&str> = content.lines().collect();
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        let context_lines = self.options.context_lines;
+        let to_strings = |range: &[&str]| range.iter().map(|line| line.to_string()).collect::<Vec<_>>();
+
+        self.regex
+            .find_iter(content)
+            .take(limit)
+            .map(|found| {
+                // A match at the very end of a note ending with a newline sits past the last line
+                let line_index = line_starts.partition_point(|&start| start <= found.start()) - 1;
+                let column = content[line_starts[line_index]..found.start()].chars().count() + 1;
+                let context_start = line_index.saturating_sub(context_lines).min(lines.len());
+                let context_end = (line_index + 1 + context_lines).min(lines.len());
+
+                TextMatch {
+                    file_path: file_path.to_string(),
+                    line: line_index + 1,
+                    column,
+                    matched_text: found.as_str().to_string(),
+                    line_text: lines.get(line_index).map(|line| line.to_string()).unwrap_or_default(),
+                    context_before: to_strings(&lines[context_start..line_index.min(lines.len())]),
+                    context_after: to_strings(lines.get(line_index + 1..context_end).unwrap_or_default()),
+                }
+            })
+            .collect()
+    }
+
+    /// Markdown notes of the vault outside the excluded paths, sorted by path
+    fn note_paths(&self, vault_path: &str) -> VaultSearchResult<Vec<PathBuf>> {
+        let vault_root = Path::new(vault_path);
+        let mut notes: Vec<PathBuf> = scan_vault_files_internal(vault_path)?
+            .into_iter()
+            .filter(|file| !file.is_dir)
+            .map(|file| PathBuf::from(file.path))
+            .filter(|path| !self.is_excluded(path.strip_prefix(vault_root).unwrap_or(path)))
+            .collect();
+        notes.sort();
+        Ok(notes)
+    }
+
+    /// Whether a vault-relative path falls under an excluded path
+    fn is_excluded(&self, relative_path: &Path) -> bool {
+        self.options.excluded_paths.iter().any(|excluded| {
+            if excluded.components().count() == 1 {
+                relative_path.components().any(|component| component.as_os_str() == excluded.as_os_str())
+            } else {
+                relative_path.starts_with(excluded)
+            }
+        })
+    }
+}
+
+/// Read a note for searching; unreadable notes are skipped
+fn read_note(path: &Path) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            log::debug!("⚠️ Skipping unreadable note {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_vault() -> TempDir {
+        let vault = TempDir::new().unwrap();
+        fs::create_dir_all(vault.path().join("projects")).unwrap();
+        fs::create_dir_all(vault.path().join(".git")).unwrap();
+        fs::write(vault.path().join("a.md"), "intro\nTODO: write tests\nmore\n").unwrap();
+        fs::write(vault.path().join("projects/b.md"), "# Plan\n- [ ] todo one\n- [ ] ÉtÉ TODO two\n").unwrap();
+        fs::write(vault.path().join(".git/c.md"), "TODO hidden\n").unwrap();
+        vault
+    }
+
+    #[test]
+    fn test_search_reports_positions_and_context() {
+        let vault = create_vault();
+        let vault_path = vault.path().to_string_lossy().to_string();
+        let search = VaultSearch::new(VaultSearchOptions {
+            pattern: "todo".to_string(),
+            context_lines: 1,
+            ..VaultSearchOptions::default()
+        })
+        .unwrap();
+
+        let report = search.search(&vault_path, &CancellationToken::new()).unwrap();
+        assert_eq!(report.files_searched, 2, "excluded .git note must not be searched");
+        assert_eq!(report.total_matches, 3);
+        assert!(!report.truncated);
+
+        let first = &report.matches[0];
+        assert!(first.file_path.ends_with("a.md"));
+        assert_eq!((first.line, first.column), (2, 1));
+        assert_eq!(first.context_before, vec!["intro"]);
+        assert_eq!(first.context_after, vec!["more"]);
+
+        // Columns count characters, not bytes
+        let accented = &report.matches[2];
+        assert_eq!((accented.line, accented.column, accented.matched_text.as_str()), (3, 11, "TODO"));
+        assert!(accented.context_after.is_empty());
+
+        let case_sensitive = VaultSearch::new(VaultSearchOptions {
+            pattern: r"TODO\W+(\w+)".to_string(),
+            is_regex: true,
+            case_sensitive: true,
+            max_matches: 1,
+            ..VaultSearchOptions::default()
+        })
+        .unwrap();
+        let report = case_sensitive.search(&vault_path, &CancellationToken::new()).unwrap();
+        assert_eq!(report.total_matches, 2);
+        assert_eq!(report.matches.len(), 1);
+        assert!(report.truncated);
+        assert_eq!(report.matches[0].matched_text, "TODO: write");
+
+        assert!(matches!(
+            VaultSearch::new(VaultSearchOptions { pattern: "(".to_string(), is_regex: true, ..VaultSearchOptions::default() }),
+            Err(VaultSearchError::InvalidPattern { .. })
+        ));
+
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        assert!(matches!(search.search(&vault_path, &cancelled), Err(VaultSearchError::Cancelled)));
+    }
+
+    #[test]
+    fn test_replace_rewrites_notes_with_backups() {
+        let vault = create_vault();
+        let vault_path = vault.path().to_string_lossy().to_string();
+        let search = VaultSearch::new(VaultSearchOptions {
+            pattern: r"- \[ \] (.+)".to_string(),
+            is_regex: true,
+            ..VaultSearchOptions::default()
+        })
+        .unwrap();
+
+        let report = search.replace(&vault_path, "- [x] $1", &CancellationToken::new()).unwrap();
+        assert_eq!(report.replacements, 2);
+        assert_eq!(report.changed_files.len(), 1);
+        assert_eq!(report.backups.len(), 1);
+        assert_eq!(
+            fs::read_to_string(vault.path().join("projects/b.md")).unwrap(),
+            "# Plan\n- [x] todo one\n- [x] ÉtÉ TODO two\n"
+        );
+        assert!(Path::new(&report.backups[0]).exists());
+
+        // Literal replacements insert `$` as is, locked notes are skipped
+        let literal = VaultSearch::new(VaultSearchOptions { pattern: "more".to_string(), ..VaultSearchOptions::default() }).unwrap();
+        let note = vault.path().join("a.md").to_string_lossy().to_string();
+        let lock = FileLockGuard::acquire(&note).unwrap();
+        let report = literal.replace(&vault_path, "$1", &CancellationToken::new()).unwrap();
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.changed_files.is_empty());
+
+        drop(lock);
+        literal.replace(&vault_path, "$1", &CancellationToken::new()).unwrap();
+        assert_eq!(fs::read_to_string(&note).unwrap(), "intro\nTODO: write tests\n$1\n");
+    }
+}
@@ -6,18 +6,34 @@
 //! ## Features
 //!
 //! - **Vector Quantization**: 8-bit and 16-bit quantization for embeddings
+//! - **Half Precision**: IEEE 754 binary16 storage for embeddings
 //! - **Delta Compression**: Compress similar vectors using delta encoding
+//! - **Product Quantization**: One byte per sub-space against k-means trained codebooks,
+//!   with asymmetric-distance (ADC) search computed directly on the codes
 //! - **Batch Compression**: Efficiently compress batches of vectors together
 //! - **Lossless Options**: Maintain full accuracy when required
 //! - **Performance Optimized**: Fast compression/decompression for real-time use
 
 use std::collections::HashMap;
+use std::fs;
 use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::Path;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+/// File name of the product quantization codebook in the storage directory
+pub const PQ_CODEBOOK_FILE_NAME: &str = "pq_codebook.bin";
+
+/// Largest codebook that still fits one-byte codes
+const MAX_PQ_CENTROIDS: usize = 256;
 
 /// Errors that can occur during vector compression operations
 #[derive(Error, Debug)]
@@ -34,6 +50,12 @@ pub enum CompressionError {
     #[error("Vector dimension mismatch: expected {expected}, found {found}")]
     DimensionMismatch { expected: usize, found: usize },
     
+    #[error("Product quantization requires a trained codebook")]
+    CodebookMissing,
+    
+    #[error("Codebook training failed: {message}")]
+    TrainingFailed { message: String },
+    
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
     
@@ -58,6 +80,9 @@ pub struct VectorCompressionConfig {
     pub min_batch_size: usize,
     /// Delta similarity threshold (0.8-0.99)
     pub delta_similarity_threshold: f32,
+    /// Codebook training parameters for product quantization
+    #[serde(default)]
+    pub product_quantization: ProductQuantizationConfig,
 }
 
 impl Default for VectorCompressionConfig {
@@ -69,12 +94,41 @@ impl Default for VectorCompressionConfig {
             enable_batch_compression: true,
             min_batch_size: 10,
             delta_similarity_threshold: 0.85,
+            product_quantization: ProductQuantizationConfig::default(),
         }
     }
 }
 
-/// Advanced vector compression algorithms
+/// Codebook training parameters for product quantization
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductQuantizationConfig {
+    /// Number of sub-spaces the vector is split into (bytes per code)
+    pub num_subspaces: usize,
+    /// Centroids per sub-space codebook (at most 256)
+    pub num_centroids: usize,
+    /// Maximum k-means iterations per sub-space
+    pub training_iterations: usize,
+    /// Vectors sampled for training; larger sets are subsampled
+    pub max_training_samples: usize,
+    /// Seed for sampling and centroid initialization
+    pub seed: u64,
+}
+
+impl Default for ProductQuantizationConfig {
+    fn default() -> Self {
+        Self {
+            num_subspaces: 16,
+            num_centroids: MAX_PQ_CENTROIDS,
+            training_iterations: 20,
+            max_training_samples: 20_000,
+            seed: 42,
+        }
+    }
+}
+
+/// Advanced vector compression algorithms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum VectorCompressionAlgorithm {
     /// No compression (32-bit floats)
     None,
@@ -82,9 +136,11 @@ pub enum VectorCompressionAlgorithm {
     Quantized8Bit,
     /// 16-bit quantization (50% size reduction)
     Quantized16Bit,
+    /// IEEE 754 half-precision floats (50% size reduction, no per-vector parameters)
+    Float16,
     /// Delta compression with quantization
     DeltaQuantized,
-    /// Product quantization for large vectors
+    /// Product quantization against trained sub-space codebooks (one byte per sub-space)
     ProductQuantization,
 }
 
@@ -103,6 +159,107 @@ pub struct CompressedVector {
     pub delta_reference: Option<String>,
     /// Compression ratio achieved
     pub compression_ratio: f32,
+    /// Codebook the codes refer to (product quantization only)
+    #[serde(default)]
+    pub codebook_id: Option<String>,
+}
+
+impl CompressedVector {
+    /// Borrow the stored values for in-place scoring
+    /// 
+    /// Returns `None` for formats that cannot be read without decompressing
+    /// (gzip, delta and product quantization codes) or when the data does not
+    /// match the recorded dimension.
+    pub fn quantized_view(&self) -> Option<QuantizedVectorRef<'_>> {
+        let params = &self.quantization_params;
+        match self.algorithm {
+            VectorCompressionAlgorithm::Quantized8Bit => {
+                let codes = bincode_payload(&self.data, self.dimension, 1)?;
+                Some(QuantizedVectorRef::Quantized8Bit { codes, min_value: params.min_value, scale: params.scale })
+            }
+            VectorCompressionAlgorithm::Quantized16Bit => {
+                let codes = bincode_payload(&self.data, self.dimension, 2)?;
+                Some(QuantizedVectorRef::Quantized16Bit { codes, min_value: params.min_value, scale: params.scale })
+            }
+            VectorCompressionAlgorithm::Float16 => {
+                let halves = bincode_payload(&self.data, self.dimension, 2)?;
+                Some(QuantizedVectorRef::Float16 { halves })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Borrowed view of a stored vector that can be scored without decompression
+/// 
+/// Affine codes decode as `min_value + code * scale`; multi-byte codes and
+/// half floats are little-endian. The kernels in `SimilaritySearch` read these
+/// views directly, so searching compressed storage needs no `Vec<f32>` per entry.
+#[derive(Debug, Clone, Copy)]
+pub enum QuantizedVectorRef<'a> {
+    /// Uncompressed floats
+    Float32(&'a [f32]),
+    /// 8-bit affine codes, one byte per dimension
+    Quantized8Bit { codes: &'a [u8], min_value: f32, scale: f32 },
+    /// 16-bit affine codes, two bytes per dimension
+    Quantized16Bit { codes: &'a [u8], min_value: f32, scale: f32 },
+    /// IEEE 754 half floats, two bytes per dimension
+    Float16 { halves: &'a [u8] },
+}
+
+impl<'a> QuantizedVectorRef<'a> {
+    /// View raw affine codes of `bits` (8 or 16) width
+    pub fn affine(codes: &'a [u8], dimension: usize, bits: u8, min_value: f32, scale: f32) -> Option<Self> {
+        match bits {
+            8 if codes.len() == dimension => Some(Self::Quantized8Bit { codes, min_value, scale }),
+            16 if codes.len() == dimension * 2 => Some(Self::Quantized16Bit { codes, min_value, scale }),
+            _ => None,
+        }
+    }
+    
+    /// Number of dimensions
+    pub fn dimension(&self) -> usize {
+        match self {
+            Self::Float32(values) => values.len(),
+            Self::Quantized8Bit { codes, .. } => codes.len(),
+            Self::Quantized16Bit { codes, .. } => codes.len() / 2,
+            Self::Float16 { halves } => halves.len() / 2,
+        }
+    }
+    
+    /// Bytes used by the stored values
+    pub fn size_bytes(&self) -> usize {
+        match self {
+            Self::Float32(values) => std::mem::size_of_val(*values),
+            Self::Quantized8Bit { codes, .. } | Self::Quantized16Bit { codes, .. } => codes.len(),
+            Self::Float16 { halves } => halves.len(),
+        }
+    }
+    
+    /// Decode into floats
+    pub fn to_vec(&self) -> Vec<f32> {
+        match *self {
+            Self::Float32(values) => values.to_vec(),
+            Self::Quantized8Bit { codes, min_value, scale } => {
+                codes.iter().map(|&code| min_value + code as f32 * scale).collect()
+            }
+            Self::Quantized16Bit { codes, min_value, scale } => codes
+                .chunks_exact(2)
+                .map(|code| min_value + u16::from_le_bytes([code[0], code[1]]) as f32 * scale)
+                .collect(),
+            Self::Float16 { halves } => halves
+                .chunks_exact(2)
+                .map(|half| f16_bits_to_f32(u16::from_le_bytes([half[0], half[1]])))
+                .collect(),
+        }
+    }
+}
+
+/// Element bytes of a bincode-serialized `Vec<u8>`/`Vec<u16>` (u64 length prefix)
+fn bincode_payload(data: &[u8], dimension: usize, element_size: usize) -> Option<&[u8]> {
+    let (prefix, payload) = data.split_at_checked(8)?;
+    let length = u64::from_le_bytes(prefix.try_into().ok()?) as usize;
+    (length == dimension && payload.len() == dimension * element_size).then_some(payload)
 }
 
 /// Parameters for vector quantization
@@ -159,6 +316,8 @@ pub struct VectorCompressor {
     config: VectorCompressionConfig,
     /// Reference vectors for delta compression
     reference_vectors: HashMap<String, Vec<f32>>,
+    /// Trained codebook for product quantization
+    product_quantizer: Option<ProductQuantizer>,
 }
 
 impl VectorCompressor {
@@ -180,6 +339,7 @@ impl VectorCompressor {
         Ok(Self {
             config,
             reference_vectors: HashMap::new(),
+            product_quantizer: None,
         })
     }
     
@@ -201,6 +361,9 @@ impl VectorCompressor {
             VectorCompressionAlgorithm::Quantized16Bit => {
                 self.compress_quantized(vector, 16)?
             }
+            VectorCompressionAlgorithm::Float16 => {
+                self.compress_float16(vector)?
+            }
             VectorCompressionAlgorithm::DeltaQuantized => {
                 self.compress_delta_quantized(vector, vector_id)?
             }
@@ -231,6 +394,9 @@ impl VectorCompressor {
             VectorCompressionAlgorithm::Quantized16Bit => {
                 self.decompress_quantized(compressed, 16)
             }
+            VectorCompressionAlgorithm::Float16 => {
+                self.decompress_float16(compressed)
+            }
             VectorCompressionAlgorithm::DeltaQuantized => {
                 self.decompress_delta_quantized(compressed)
             }
@@ -247,6 +413,15 @@ impl VectorCompressor {
     ) -> CompressionResult<Vec<CompressedVector>> {
         let mut compressed_vectors = Vec::with_capacity(vectors.len());
         
+        // Product quantization needs a codebook; train one on the batch if none is loaded
+        if self.config.algorithm == VectorCompressionAlgorithm::ProductQuantization
+            && self.product_quantizer.is_none()
+            && !vectors.is_empty()
+        {
+            let training_vectors: Vec<Vec<f32>> = vectors.iter().map(|(_, vector)| vector.clone()).collect();
+            self.train_product_quantizer(&training_vectors)?;
+        }
+        
         if self.config.enable_batch_compression && vectors.len() >= self.config.min_batch_size {
             // Use batch compression for better ratios
             for (id, vector) in vectors {
@@ -269,6 +444,48 @@ impl VectorCompressor {
         self.reference_vectors.insert(id, vector);
     }
     
+    /// Train a product quantization codebook and use it for compression
+    /// 
+    /// Vectors compressed with a previous codebook can no longer be
+    /// decompressed by this compressor and must be re-encoded.
+    pub fn train_product_quantizer(&mut self, vectors: &[Vec<f32>]) -> CompressionResult<&ProductQuantizer> {
+        let quantizer = ProductQuantizer::train(vectors, &self.config.product_quantization)?;
+        Ok(self.product_quantizer.insert(quantizer))
+    }
+    
+    /// Use a previously trained (e.g. persisted) product quantization codebook
+    pub fn set_product_quantizer(&mut self, quantizer: ProductQuantizer) {
+        self.product_quantizer = Some(quantizer);
+    }
+    
+    /// Product quantization codebook in use, if any
+    pub fn product_quantizer(&self) -> Option<&ProductQuantizer> {
+        self.product_quantizer.as_ref()
+    }
+    
+    /// Rank product-quantized vectors by cosine similarity to a query
+    /// 
+    /// Scores are computed with asymmetric distance tables directly on the
+    /// codes; nothing is decompressed. Vectors encoded with another codebook
+    /// are skipped.
+    pub fn search_product_quantized<'a, I>(
+        &self,
+        query: &[f32],
+        candidates: I,
+        k: usize,
+    ) -> CompressionResult<Vec<(String, f32)>>
+    where
+        I: IntoIterator<Item = (&'a str, &'a CompressedVector)>,
+    {
+        let quantizer = self.product_quantizer.as_ref().ok_or(CompressionError::CodebookMissing)?;
+        let codes = candidates
+            .into_iter()
+            .filter(|(_, compressed)| compressed.codebook_id.as_deref() == Some(quantizer.id()))
+            .map(|(id, compressed)| (id, compressed.data.as_slice()));
+        
+        quantizer.search(query, codes, k)
+    }
+    
     /// Update compression configuration
     pub fn update_config(&mut self, config: VectorCompressionConfig) -> CompressionResult<()> {
         if !matches!(config.quantization_bits, 8 | 16 | 32) {
@@ -306,6 +523,7 @@ impl VectorCompressor {
             },
             delta_reference: None,
             compression_ratio: 0.0, // Will be calculated by caller
+            codebook_id: None,
         })
     }
     
@@ -344,6 +562,30 @@ impl VectorCompressor {
             quantization_params: params,
             delta_reference: None,
             compression_ratio: 0.0, // Will be calculated by caller
+            codebook_id: None,
+        })
+    }
+    
+    /// Half-precision compression
+    fn compress_float16(&self, vector: &[f32]) -> CompressionResult<CompressedVector> {
+        let halves: Vec<u16> = vector.iter().map(|&v| f32_to_f16_bits(v)).collect();
+        let data = bincode::serialize(&halves).map_err(|e| CompressionError::CompressionFailed {
+            message: e.to_string(),
+        })?;
+        
+        Ok(CompressedVector {
+            data,
+            dimension: vector.len(),
+            algorithm: VectorCompressionAlgorithm::Float16,
+            quantization_params: QuantizationParams {
+                min_value: 0.0,
+                max_value: 0.0,
+                scale: 1.0,
+                zero_point: 0,
+            },
+            delta_reference: None,
+            compression_ratio: 0.0, // Will be calculated by caller
+            codebook_id: None,
         })
     }
     
@@ -388,6 +630,7 @@ impl VectorCompressor {
                     quantization_params: params,
                     delta_reference: Some(ref_id),
                     compression_ratio: 0.0,
+                    codebook_id: None,
                 })
             }
             None => {
@@ -400,11 +643,24 @@ impl VectorCompressor {
         }
     }
     
-    /// Product quantization for large vectors (simplified implementation)
+    /// Product quantization against the trained codebook
     fn compress_product_quantization(&self, vector: &[f32]) -> CompressionResult<CompressedVector> {
-        // For now, fallback to 8-bit quantization
-        // Full product quantization would require clustering and codebooks
-        self.compress_quantized(vector, 8)
+        let quantizer = self.product_quantizer.as_ref().ok_or(CompressionError::CodebookMissing)?;
+        
+        Ok(CompressedVector {
+            data: quantizer.encode(vector)?,
+            dimension: vector.len(),
+            algorithm: VectorCompressionAlgorithm::ProductQuantization,
+            quantization_params: QuantizationParams {
+                min_value: 0.0,
+                max_value: 0.0,
+                scale: 1.0,
+                zero_point: 0,
+            },
+            delta_reference: None,
+            compression_ratio: 0.0, // Will be calculated by caller
+            codebook_id: Some(quantizer.id().to_string()),
+        })
     }
     
     // Private decompression methods
@@ -469,6 +725,23 @@ impl VectorCompressor {
         Ok(vector)
     }
     
+    /// Decompress half-precision vectors
+    fn decompress_float16(&self, compressed: &CompressedVector) -> CompressionResult<Vec<f32>> {
+        let halves: Vec<u16> = bincode::deserialize(&compressed.data)
+            .map_err(|e| CompressionError::DecompressionFailed {
+                message: e.to_string(),
+            })?;
+        
+        if halves.len() != compressed.dimension {
+            return Err(CompressionError::DimensionMismatch {
+                expected: compressed.dimension,
+                found: halves.len(),
+            });
+        }
+        
+        Ok(halves.into_iter().map(f16_bits_to_f32).collect())
+    }
+    
     /// Decompress delta-compressed vectors
     fn decompress_delta_quantized(&self, compressed: &CompressedVector) -> CompressionResult<Vec<f32>> {
         let reference_id = compressed.delta_reference.as_ref()
@@ -511,8 +784,25 @@ impl VectorCompressor {
     
     /// Decompress product quantized vectors
     fn decompress_product_quantization(&self, compressed: &CompressedVector) -> CompressionResult<Vec<f32>> {
-        // Fallback to regular quantization decompression
-        self.decompress_quantized(compressed, 8)
+        let quantizer = self.product_quantizer.as_ref().ok_or(CompressionError::CodebookMissing)?;
+        if compressed.codebook_id.as_deref() != Some(quantizer.id()) {
+            return Err(CompressionError::DecompressionFailed {
+                message: format!(
+                    "Vector was encoded with codebook {:?}, but {} is loaded",
+                    compressed.codebook_id, quantizer.id()
+                ),
+            });
+        }
+        
+        let vector = quantizer.decode(&compressed.data)?;
+        if vector.len() != compressed.dimension {
+            return Err(CompressionError::DimensionMismatch {
+                expected: compressed.dimension,
+                found: vector.len(),
+            });
+        }
+        
+        Ok(vector)
     }
     
     /// Find the best reference vector for delta compression
@@ -556,6 +846,7 @@ impl VectorCompressor {
             quantization_bits: self.config.quantization_bits,
             delta_enabled: self.config.enable_delta_compression,
             batch_enabled: self.config.enable_batch_compression,
+            codebook_id: self.product_quantizer.as_ref().map(|quantizer| quantizer.id().to_string()),
         }
     }
 }
@@ -573,6 +864,523 @@ pub struct CompressionStats {
     pub delta_enabled: bool,
     /// Whether batch compression is enabled
     pub batch_enabled: bool,
+    /// Product quantization codebook in use, if any
+    pub codebook_id: Option<String>,
+}
+
+/// Product quantizer with one k-means codebook per sub-space
+/// 
+/// A vector is split into `num_subspaces` contiguous sub-vectors and each is
+/// replaced by the index of its nearest centroid, so a code takes one byte per
+/// sub-space instead of four bytes per dimension. Similarities to a query are
+/// computed asymmetrically: the query stays in f32 and is compared to the
+/// centroids once per search through an [`AdcTable`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductQuantizer {
+    /// Identifier derived from the codebook contents
+    id: String,
+    /// Dimension of the encoded vectors
+    dimension: usize,
+    /// Centroids per sub-space
+    num_centroids: usize,
+    /// Start of every sub-space, followed by `dimension`
+    subspace_offsets: Vec<usize>,
+    /// Row-major centroids of every sub-space (`num_centroids` × sub-space width)
+    codebooks: Vec<Vec<f32>>,
+    /// Squared norm of every centroid, `num_centroids` per sub-space
+    centroid_sq_norms: Vec<f32>,
+    /// Number of vectors the codebooks were trained on
+    trained_on: usize,
+    /// Training timestamp (seconds since the Unix epoch)
+    trained_at: u64,
+    /// Mean squared reconstruction error on the training sample
+    quantization_error: f32,
+}
+
+impl ProductQuantizer {
+    /// Train sub-space codebooks with k-means
+    /// 
+    /// At most `max_training_samples` vectors are used, sampled with the
+    /// configured seed. When fewer vectors than `num_centroids` are given, the
+    /// codebooks get one centroid per vector.
+    pub fn train(vectors: &[Vec<f32>], config: &ProductQuantizationConfig) -> CompressionResult<Self> {
+        if config.num_subspaces == 0 || config.training_iterations == 0 {
+            return Err(CompressionError::TrainingFailed {
+                message: "num_subspaces and training_iterations must be at least 1".to_string(),
+            });
+        }
+        if config.num_centroids == 0 || config.num_centroids > MAX_PQ_CENTROIDS {
+            return Err(CompressionError::TrainingFailed {
+                message: format!("num_centroids must be between 1 and {}, got {}", MAX_PQ_CENTROIDS, config.num_centroids),
+            });
+        }
+        
+        let dimension = vectors.first().map(Vec::len).ok_or_else(|| CompressionError::TrainingFailed {
+            message: "no training vectors".to_string(),
+        })?;
+        if let Some(vector) = vectors.iter().find(|vector| vector.len() != dimension) {
+            return Err(CompressionError::DimensionMismatch { expected: dimension, found: vector.len() });
+        }
+        if dimension < config.num_subspaces {
+            return Err(CompressionError::TrainingFailed {
+                message: format!("{} dimensions cannot be split into {} sub-spaces", dimension, config.num_subspaces),
+            });
+        }
+        
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let samples: Vec<&[f32]> = if vectors.len() > config.max_training_samples.max(1) {
+            rand::seq::index::sample(&mut rng, vectors.len(), config.max_training_samples.max(1))
+                .into_iter()
+                .map(|index| vectors[index].as_slice())
+                .collect()
+        } else {
+            vectors.iter().map(Vec::as_slice).collect()
+        };
+        let num_centroids = config.num_centroids.min(samples.len());
+        
+        // Spread the remainder over the first sub-spaces so any dimension works
+        let base_width = dimension / config.num_subspaces;
+        let remainder = dimension % config.num_subspaces;
+        let mut subspace_offsets = Vec::with_capacity(config.num_subspaces + 1);
+        let mut offset = 0;
+        for subspace in 0..config.num_subspaces {
+            subspace_offsets.push(offset);
+            offset += base_width + usize::from(subspace < remainder);
+        }
+        subspace_offsets.push(dimension);
+        
+        let codebooks: Vec<Vec<f32>> = (0..config.num_subspaces)
+            .into_par_iter()
+            .map(|subspace| {
+                train_subspace_codebook(
+                    &samples,
+                    subspace_offsets[subspace]..subspace_offsets[subspace + 1],
+                    num_centroids,
+                    config.training_iterations,
+                    config.seed.wrapping_add(subspace as u64 + 1),
+                )
+            })
+            .collect();
+        
+        let mut quantizer = Self {
+            id: codebook_id(&codebooks),
+            dimension,
+            num_centroids,
+            subspace_offsets,
+            centroid_sq_norms: Vec::new(),
+            codebooks,
+            trained_on: samples.len(),
+            trained_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            quantization_error: 0.0,
+        };
+        quantizer.centroid_sq_norms = quantizer.compute_centroid_sq_norms();
+        quantizer.quantization_error = quantizer.mean_squared_error(&samples)?;
+        
+        Ok(quantizer)
+    }
+    
+    /// Identifier of the codebooks, stored with every code encoded by them
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    
+    /// Dimension of the encoded vectors
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+    
+    /// Number of sub-spaces, which is also the code length in bytes
+    pub fn num_subspaces(&self) -> usize {
+        self.codebooks.len()
+    }
+    
+    /// Centroids per sub-space
+    pub fn num_centroids(&self) -> usize {
+        self.num_centroids
+    }
+    
+    /// Number of vectors the codebooks were trained on
+    pub fn trained_on(&self) -> usize {
+        self.trained_on
+    }
+    
+    /// Training timestamp (seconds since the Unix epoch)
+    pub fn trained_at(&self) -> u64 {
+        self.trained_at
+    }
+    
+    /// Mean squared reconstruction error on the training sample
+    pub fn quantization_error(&self) -> f32 {
+        self.quantization_error
+    }
+    
+    /// Bytes per encoded vector
+    pub fn code_size_bytes(&self) -> usize {
+        self.num_subspaces()
+    }
+    
+    /// Bytes taken by the codebooks themselves
+    pub fn codebook_size_bytes(&self) -> usize {
+        self.codebooks.iter().map(|codebook| codebook.len() * std::mem::size_of::<f32>()).sum()
+    }
+    
+    /// Encode a vector as one centroid index per sub-space
+    pub fn encode(&self, vector: &[f32]) -> CompressionResult<Vec<u8>> {
+        self.check_dimension(vector)?;
+        
+        Ok((0..self.num_subspaces())
+            .map(|subspace| {
+                let sub_vector = &vector[self.subspace_range(subspace)];
+                nearest_centroid(&self.codebooks[subspace], sub_vector).0 as u8
+            })
+            .collect())
+    }
+    
+    /// Reconstruct a vector from its code
+    pub fn decode(&self, codes: &[u8]) -> CompressionResult<Vec<f32>> {
+        self.check_codes(codes)?;
+        
+        let mut vector = Vec::with_capacity(self.dimension);
+        for (subspace, &code) in codes.iter().enumerate() {
+            let width = self.subspace_range(subspace).len();
+            let start = code as usize * width;
+            vector.extend_from_slice(&self.codebooks[subspace][start..start + width]);
+        }
+        
+        Ok(vector)
+    }
+    
+    /// Mean squared reconstruction error over a set of vectors
+    pub fn mean_squared_error<V: AsRef<[f32]>>(&self, vectors: &[V]) -> CompressionResult<f32> {
+        if vectors.is_empty() {
+            return Ok(0.0);
+        }
+        
+        let mut total = 0.0f64;
+        for vector in vectors {
+            let vector = vector.as_ref();
+            let decoded = self.decode(&self.encode(vector)?)?;
+            total += squared_distance(vector, &decoded) as f64;
+        }
+        
+        Ok((total / vectors.len() as f64) as f32)
+    }
+    
+    /// Precompute the query's distances to every centroid for ADC scoring
+    pub fn distance_table(&self, query: &[f32]) -> CompressionResult<AdcTable> {
+        self.check_dimension(query)?;
+        
+        let table_len = self.num_subspaces() * self.num_centroids;
+        let mut dot = Vec::with_capacity(table_len);
+        let mut squared_distances = Vec::with_capacity(table_len);
+        
+        for (subspace, codebook) in self.codebooks.iter().enumerate() {
+            let range = self.subspace_range(subspace);
+            let sub_query = &query[range.clone()];
+            let query_sq_norm: f32 = sub_query.iter().map(|x| x * x).sum();
+            
+            for (centroid_index, centroid) in codebook.chunks_exact(range.len()).enumerate() {
+                let product: f32 = sub_query.iter().zip(centroid).map(|(a, b)| a * b).sum();
+                let centroid_sq_norm = self.centroid_sq_norms[subspace * self.num_centroids + centroid_index];
+                dot.push(product);
+                squared_distances.push((query_sq_norm - 2.0 * product + centroid_sq_norm).max(0.0));
+            }
+        }
+        
+        Ok(AdcTable {
+            num_centroids: self.num_centroids,
+            dot,
+            squared_distances,
+            centroid_sq_norms: self.centroid_sq_norms.clone(),
+            query_norm: query.iter().map(|x| x * x).sum::<f32>().sqrt(),
+        })
+    }
+    
+    /// Top `k` codes by cosine similarity to `query`, computed with ADC
+    /// 
+    /// Codes of the wrong length are skipped. Results are sorted by
+    /// similarity (descending).
+    pub fn search<'a, I>(&self, query: &[f32], codes: I, k: usize) -> CompressionResult<Vec<(String, f32)>>
+    where
+        I: IntoIterator<Item = (&'a str, &'a [u8])>,
+    {
+        let table = self.distance_table(query)?;
+        let mut scored: Vec<(&str, f32)> = codes
+            .into_iter()
+            .filter(|(_, code)| code.len() == self.num_subspaces())
+            .map(|(id, code)| (id, table.cosine_similarity(code)))
+            .collect();
+        
+        let by_score = |a: &(&str, f32), b: &(&str, f32)| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal);
+        if scored.len() > k && k > 0 {
+            scored.select_nth_unstable_by(k - 1, by_score);
+            scored.truncate(k);
+        }
+        scored.sort_by(by_score);
+        scored.truncate(k);
+        
+        Ok(scored.into_iter().map(|(id, score)| (id.to_string(), score)).collect())
+    }
+    
+    /// Persist the codebooks (written to a temporary file, then renamed)
+    pub fn save_to_file(&self, path: &Path) -> CompressionResult<()> {
+        let data = bincode::serialize(self).map_err(|e| CompressionError::CompressionFailed {
+            message: format!("Failed to serialize codebook: {}", e),
+        })?;
+        
+        let temp_path = path.with_extension("bin.tmp");
+        fs::write(&temp_path, &data)?;
+        fs::rename(&temp_path, path)?;
+        
+        Ok(())
+    }
+    
+    /// Load codebooks saved by [`ProductQuantizer::save_to_file`]
+    pub fn load_from_file(path: &Path) -> CompressionResult<Self> {
+        let data = fs::read(path)?;
+        let quantizer: Self = bincode::deserialize(&data).map_err(|e| CompressionError::DecompressionFailed {
+            message: format!("Failed to deserialize codebook: {}", e),
+        })?;
+        
+        if quantizer.subspace_offsets.len() != quantizer.codebooks.len() + 1
+            || quantizer.centroid_sq_norms.len() != quantizer.codebooks.len() * quantizer.num_centroids
+        {
+            return Err(CompressionError::DecompressionFailed {
+                message: format!("Codebook {} is inconsistent", path.display()),
+            });
+        }
+        
+        Ok(quantizer)
+    }
+    
+    fn subspace_range(&self, subspace: usize) -> Range<usize> {
+        self.subspace_offsets[subspace]..self.subspace_offsets[subspace + 1]
+    }
+    
+    fn compute_centroid_sq_norms(&self) -> Vec<f32> {
+        let mut norms = Vec::with_capacity(self.num_subspaces() * self.num_centroids);
+        for (subspace, codebook) in self.codebooks.iter().enumerate() {
+            let width = self.subspace_range(subspace).len();
+            norms.extend(codebook.chunks_exact(width).map(|centroid| centroid.iter().map(|x| x * x).sum::<f32>()));
+        }
+        norms
+    }
+    
+    fn check_dimension(&self, vector: &[f32]) -> CompressionResult<()> {
+        if vector.len() != self.dimension {
+            return Err(CompressionError::DimensionMismatch {
+                expected: self.dimension,
+                found: vector.len(),
+            });
+        }
+        Ok(())
+    }
+    
+    fn check_codes(&self, codes: &[u8]) -> CompressionResult<()> {
+        if codes.len() != self.num_subspaces() {
+            return Err(CompressionError::DecompressionFailed {
+                message: format!("Expected {} codes, found {}", self.num_subspaces(), codes.len()),
+            });
+        }
+        if let Some(&code) = codes.iter().find(|&&code| code as usize >= self.num_centroids) {
+            return Err(CompressionError::DecompressionFailed {
+                message: format!("Code {} exceeds the codebook size {}", code, self.num_centroids),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Query-to-centroid lookup tables for asymmetric distance computation
+/// 
+/// Built once per query by [`ProductQuantizer::distance_table`]; scoring a
+/// code then takes one table lookup per sub-space.
+#[derive(Debug, Clone)]
+pub struct AdcTable {
+    num_centroids: usize,
+    /// Dot product of each query sub-vector with each centroid
+    dot: Vec<f32>,
+    /// Squared distance of each query sub-vector to each centroid
+    squared_distances: Vec<f32>,
+    /// Squared norm of each centroid
+    centroid_sq_norms: Vec<f32>,
+    /// Norm of the full query
+    query_norm: f32,
+}
+
+impl AdcTable {
+    /// Squared Euclidean distance between the query and the encoded vector
+    pub fn squared_distance(&self, codes: &[u8]) -> f32 {
+        self.sum_lookups(&self.squared_distances, codes)
+    }
+    
+    /// Cosine similarity between the query and the encoded vector
+    /// 
+    /// Sub-spaces are disjoint, so both the dot product and the squared norm
+    /// of the reconstruction are sums of per-sub-space lookups.
+    pub fn cosine_similarity(&self, codes: &[u8]) -> f32 {
+        let dot = self.sum_lookups(&self.dot, codes);
+        let norm = self.sum_lookups(&self.centroid_sq_norms, codes).sqrt();
+        
+        if self.query_norm > 0.0 && norm > 0.0 {
+            dot / (self.query_norm * norm)
+        } else {
+            0.0
+        }
+    }
+    
+    fn sum_lookups(&self, table: &[f32], codes: &[u8]) -> f32 {
+        codes
+            .iter()
+            .enumerate()
+            .map(|(subspace, &code)| table[subspace * self.num_centroids + code as usize])
+            .sum()
+    }
+}
+
+/// Lloyd's k-means on one sub-space, returning row-major centroids
+fn train_subspace_codebook(
+    samples: &[&[f32]],
+    range: Range<usize>,
+    num_centroids: usize,
+    iterations: usize,
+    seed: u64,
+) -> Vec<f32> {
+    let width = range.len();
+    let mut rng = StdRng::seed_from_u64(seed);
+    
+    // Initialize from distinct samples
+    let mut centroids = Vec::with_capacity(num_centroids * width);
+    for index in rand::seq::index::sample(&mut rng, samples.len(), num_centroids) {
+        centroids.extend_from_slice(&samples[index][range.clone()]);
+    }
+    
+    let mut assignments = vec![usize::MAX; samples.len()];
+    for _ in 0..iterations {
+        let mut changed = false;
+        for (sample, assignment) in samples.iter().zip(assignments.iter_mut()) {
+            let (nearest, _) = nearest_centroid(&centroids, &sample[range.clone()]);
+            if *assignment != nearest {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        
+        let mut sums = vec![0.0f32; num_centroids * width];
+        let mut counts = vec![0usize; num_centroids];
+        for (sample, &assignment) in samples.iter().zip(&assignments) {
+            counts[assignment] += 1;
+            for (sum, value) in sums[assignment * width..(assignment + 1) * width].iter_mut().zip(&sample[range.clone()]) {
+                *sum += value;
+            }
+        }
+        
+        for centroid_index in 0..num_centroids {
+            let centroid = &mut centroids[centroid_index * width..(centroid_index + 1) * width];
+            if counts[centroid_index] == 0 {
+                // Re-seed empty clusters from a random sample
+                let sample = samples[rng.gen_range(0..samples.len())];
+                centroid.copy_from_slice(&sample[range.clone()]);
+            } else {
+                let count = counts[centroid_index] as f32;
+                for (value, sum) in centroid.iter_mut().zip(&sums[centroid_index * width..(centroid_index + 1) * width]) {
+                    *value = sum / count;
+                }
+            }
+        }
+    }
+    
+    centroids
+}
+
+/// Index and squared distance of the centroid closest to `sub_vector`
+fn nearest_centroid(centroids: &[f32], sub_vector: &[f32]) -> (usize, f32) {
+    centroids
+        .chunks_exact(sub_vector.len().max(1))
+        .map(|centroid| squared_distance(centroid, sub_vector))
+        .enumerate()
+        .fold((0, f32::INFINITY), |best, (index, distance)| if distance < best.1 { (index, distance) } else { best })
+}
+
+fn squared_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// Short content hash identifying a set of codebooks
+fn codebook_id(codebooks: &[Vec<f32>]) -> String {
+    let mut hasher = Sha256::new();
+    for codebook in codebooks {
+        hasher.update((codebook.len() as u64).to_le_bytes());
+        for value in codebook {
+            hasher.update(value.to_le_bytes());
+        }
+    }
+    format!("pq-{:x}", hasher.finalize())[..19].to_string()
+}
+
+/// Convert an `f32` to IEEE 754 half-precision bits (round to nearest, ties to even)
+pub fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    
+    // Infinity and NaN (keeping NaN quiet)
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    
+    if half_exponent <= 0 {
+        // Subnormal half (or zero when too small)
+        if half_exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let round_bit = 1u32 << (shift - 1);
+        let mut half_mantissa = mantissa >> shift;
+        if mantissa & round_bit != 0 && mantissa & (3 * round_bit - 1) != 0 {
+            half_mantissa += 1;
+        }
+        return sign | half_mantissa as u16;
+    }
+    
+    // A rounding carry into the exponent is still the correctly rounded value
+    let mut half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1fff;
+    if remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1) {
+        half += 1;
+    }
+    sign | half as u16
+}
+
+/// Convert IEEE 754 half-precision bits to an `f32`
+pub fn f16_bits_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    
+    let bits = match exponent {
+        0 => {
+            // Zero or subnormal: mantissa * 2^-24
+            let magnitude = mantissa as f32 / (1u32 << 24) as f32;
+            return if sign != 0 { -magnitude } else { magnitude };
+        }
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
 }
 
 #[cfg(test)]
@@ -658,6 +1466,37 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_float16_compression_decompression() {
+        let config = VectorCompressionConfig {
+            algorithm: VectorCompressionAlgorithm::Float16,
+            ..Default::default()
+        };
+        
+        let mut compressor = VectorCompressor::new(config).unwrap();
+        let original_vector = vec![0.1, 0.5, -0.3, 0.8, -1.0, 1.0, 3.0e-6, 1234.5];
+        
+        let compressed = compressor.compress_vector(&original_vector, "test").unwrap();
+        assert!(compressed.compression_ratio < 1.0);
+        
+        let decompressed = compressor.decompress_vector(&compressed).unwrap();
+        for (orig, decomp) in original_vector.iter().zip(decompressed.iter()) {
+            assert!((orig - decomp).abs() <= orig.abs() * 1e-3 + 1e-7, "Original: {}, Decompressed: {}", orig, decomp);
+        }
+        
+        // Exact encodings and special values
+        assert_eq!(f32_to_f16_bits(1.0), 0x3c00);
+        assert_eq!(f32_to_f16_bits(-2.0), 0xc000);
+        assert_eq!(f32_to_f16_bits(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16_bits(1.0e6), 0x7c00);
+        assert_eq!(f32_to_f16_bits(5.960_464_5e-8), 0x0001);
+        assert_eq!(f16_bits_to_f32(0x0001), 5.960_464_5e-8);
+        assert!(f16_bits_to_f32(f32_to_f16_bits(f32::NAN)).is_nan());
+        // Ties round to even: 1 + 2^-11 is halfway between 1.0 and the next half
+        assert_eq!(f32_to_f16_bits(1.0 + 1.0 / 2048.0), 0x3c00);
+        assert_eq!(f32_to_f16_bits(1.0 + 3.0 / 2048.0), 0x3c02);
+    }
+    
     #[test]
     fn test_delta_compression() {
         let config = VectorCompressionConfig {
@@ -701,4 +1540,145 @@ mod tests {
         assert!(stats.delta_enabled);
         assert!(stats.batch_enabled);
     }
-}
\ No newline at end of file
+    
+    /// Clustered unit vectors, roughly how note embeddings spread out
+    fn clustered_vectors(count: usize, dimension: usize, clusters: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let centers: Vec<Vec<f32>> = (0..clusters)
+            .map(|_| (0..dimension).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        
+        (0..count)
+            .map(|i| {
+                let vector: Vec<f32> = centers[i % clusters]
+                    .iter()
+                    .map(|&c| c + rng.gen_range(-0.35..0.35))
+                    .collect();
+                let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+                vector.into_iter().map(|x| x / norm).collect()
+            })
+            .collect()
+    }
+    
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+        let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        dot / (norm_a * norm_b)
+    }
+    
+    fn pq_config() -> ProductQuantizationConfig {
+        ProductQuantizationConfig {
+            num_subspaces: 16,
+            num_centroids: 64,
+            training_iterations: 15,
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_product_quantization_roundtrip() {
+        let vectors = clustered_vectors(600, 64, 12, 7);
+        let config = VectorCompressionConfig {
+            algorithm: VectorCompressionAlgorithm::ProductQuantization,
+            product_quantization: pq_config(),
+            ..Default::default()
+        };
+        let mut compressor = VectorCompressor::new(config).unwrap();
+        
+        // No codebook yet
+        assert!(matches!(compressor.compress_vector(&vectors[0], "v0"), Err(CompressionError::CodebookMissing)));
+        
+        // Batch compression trains one on the batch
+        let batch: Vec<(String, Vec<f32>)> = vectors.iter().enumerate().map(|(i, v)| (format!("v{}", i), v.clone())).collect();
+        let compressed = compressor.compress_batch(&batch).unwrap();
+        let quantizer = compressor.product_quantizer().unwrap();
+        assert_eq!(quantizer.num_subspaces(), 16);
+        assert_eq!(compressed[0].data.len(), 16);
+        assert_eq!(compressed[0].codebook_id.as_deref(), Some(quantizer.id()));
+        assert!((compressed[0].compression_ratio - 16.0 / 256.0).abs() < 1e-6);
+        
+        let decompressed = compressor.decompress_vector(&compressed[0]).unwrap();
+        assert_eq!(decompressed.len(), 64);
+        assert!(cosine(&decompressed, &vectors[0]) > 0.9);
+        
+        // Codes from another codebook are rejected instead of decoded into garbage
+        let mut other = VectorCompressor::new(VectorCompressionConfig {
+            algorithm: VectorCompressionAlgorithm::ProductQuantization,
+            product_quantization: ProductQuantizationConfig { seed: 1, ..pq_config() },
+            ..Default::default()
+        }).unwrap();
+        other.train_product_quantizer(&clustered_vectors(300, 64, 5, 99)).unwrap();
+        assert!(matches!(other.decompress_vector(&compressed[0]), Err(CompressionError::DecompressionFailed { .. })));
+        assert!(matches!(
+            ProductQuantizer::train(&vectors, &ProductQuantizationConfig { num_subspaces: 65, ..pq_config() }),
+            Err(CompressionError::TrainingFailed { .. })
+        ));
+    }
+    
+    #[test]
+    fn test_product_quantization_adc_recall_and_memory() {
+        let dimension = 64;
+        // Queries are held out of the same distribution as the database
+        let mut database = clustered_vectors(2100, dimension, 100, 11);
+        let queries = database.split_off(2000);
+        let quantizer = ProductQuantizer::train(&database, &pq_config()).unwrap();
+        
+        let ids: Vec<String> = (0..database.len()).map(|i| i.to_string()).collect();
+        let codes: Vec<Vec<u8>> = database.iter().map(|v| quantizer.encode(v).unwrap()).collect();
+        
+        let k = 10;
+        let shortlist = 50;
+        let mut hits = 0;
+        let mut shortlist_hits = 0;
+        for query in &queries {
+            let mut exact: Vec<(usize, f32)> = database.iter().map(|v| cosine(query, v)).enumerate().collect();
+            exact.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+            let exact_top: Vec<String> = exact.iter().take(k).map(|(i, _)| i.to_string()).collect();
+            
+            let approximate = quantizer
+                .search(query, ids.iter().map(String::as_str).zip(codes.iter().map(Vec::as_slice)), shortlist)
+                .unwrap();
+            assert_eq!(approximate.len(), shortlist);
+            assert!(approximate.windows(2).all(|w| w[0].1 >= w[1].1));
+            hits += approximate[..k].iter().filter(|(id, _)| exact_top.contains(id)).count();
+            shortlist_hits += approximate.iter().filter(|(id, _)| exact_top.contains(id)).count();
+        }
+        let recall = hits as f32 / (queries.len() * k) as f32;
+        let shortlist_recall = shortlist_hits as f32 / (queries.len() * k) as f32;
+        
+        let f32_bytes = database.len() * dimension * std::mem::size_of::<f32>();
+        let pq_bytes = database.len() * quantizer.code_size_bytes() + quantizer.codebook_size_bytes();
+        let savings = 1.0 - pq_bytes as f32 / f32_bytes as f32;
+        println!(
+            "PQ recall@{}: {:.3}, recall@{} in top {}: {:.3}, memory {} -> {} bytes ({:.1}% saved)",
+            k, recall, k, shortlist, shortlist_recall, f32_bytes, pq_bytes, savings * 100.0
+        );
+        
+        assert!(recall >= 0.55, "recall@{} too low: {}", k, recall);
+        assert!(shortlist_recall >= 0.95, "recall@{} in top {} too low: {}", k, shortlist, shortlist_recall);
+        assert!(savings > 0.85, "memory savings too low: {}", savings);
+        
+        // ADC scores match scoring the decoded vectors
+        let table = quantizer.distance_table(&queries[0]).unwrap();
+        let decoded = quantizer.decode(&codes[0]).unwrap();
+        assert!((table.cosine_similarity(&codes[0]) - cosine(&queries[0], &decoded)).abs() < 1e-4);
+        let distance: f32 = queries[0].iter().zip(&decoded).map(|(a, b)| (a - b) * (a - b)).sum();
+        assert!((table.squared_distance(&codes[0]) - distance).abs() < 1e-4);
+    }
+    
+    #[test]
+    fn test_product_quantizer_persistence() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(PQ_CODEBOOK_FILE_NAME);
+        let vectors = clustered_vectors(300, 32, 6, 3);
+        let quantizer = ProductQuantizer::train(&vectors, &ProductQuantizationConfig { num_subspaces: 8, ..pq_config() }).unwrap();
+        
+        quantizer.save_to_file(&path).unwrap();
+        let loaded = ProductQuantizer::load_from_file(&path).unwrap();
+        
+        assert_eq!(loaded.id(), quantizer.id());
+        assert_eq!(loaded.encode(&vectors[5]).unwrap(), quantizer.encode(&vectors[5]).unwrap());
+        assert!(ProductQuantizer::load_from_file(&temp_dir.path().join("missing.bin")).is_err());
+    }
+}
@@ -79,6 +79,7 @@ impl Default for EnhancedCacheConfig {
                 persist_to_disk: false, // L1 is memory-only for speed
                 cache_file_path: None,
                 enable_metrics: true,
+                max_disk_bytes: 256 * 1024 * 1024,
             },
             l2_config: CacheConfig {
                 max_entries: 2000,
@@ -86,6 +87,7 @@ impl Default for EnhancedCacheConfig {
                 persist_to_disk: true, // L2 can persist
                 cache_file_path: None,
                 enable_metrics: true,
+                max_disk_bytes: 256 * 1024 * 1024,
             },
             eviction_policy: EvictionPolicy::AdaptiveLRULFU,
             enable_pattern_learning: true,
@@ -14,6 +14,8 @@ use crate::vector_db::types::{
     VectorStorageConfig, VectorDbError, VectorDbResult,
 };
 use crate::vector_db::atomic::utils as atomic_utils;
+use crate::vector_db::segment;
+use crate::vector_db::wal::WAL_FILE_NAME;
 
 /// File system operations manager for vector database
 pub struct FileOperations {
@@ -465,10 +467,12 @@ impl FileOperations {
     fn is_storage_file(&self, path: &Path) -> bool {
         if let Some(file_name) = path.file_name() {
             let file_str = file_name.to_string_lossy();
-            file_str.starts_with("vector_") && 
-            (file_str.ends_with(".json") || 
-             file_str.ends_with(".json.gz") ||
-             file_str.ends_with(".json.lz4"))
+            segment::is_segment_file_name(&file_str) ||
+            file_str == WAL_FILE_NAME ||
+            (file_str.starts_with("vector_") && 
+             (file_str.ends_with(".json") || 
+              file_str.ends_with(".json.gz") ||
+              file_str.ends_with(".json.lz4")))
         } else {
             false
         }
@@ -821,6 +825,9 @@ mod tests {
         assert!(file_ops.is_storage_file(Path::new("vector_123.json")));
         assert!(file_ops.is_storage_file(Path::new("vector_456.json.gz")));
         assert!(file_ops.is_storage_file(Path::new("vector_789.json.lz4")));
+        assert!(file_ops.is_storage_file(Path::new("segment_0000000001.seg")));
+        assert!(file_ops.is_storage_file(Path::new("wal.log")));
+        assert!(!file_ops.is_storage_file(Path::new("segment_0000000001.seg.tmp")));
         assert!(!file_ops.is_storage_file(Path::new("other_file.json")));
         assert!(!file_ops.is_storage_file(Path::new("vector_file.txt")));
     }
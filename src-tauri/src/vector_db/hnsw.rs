@@ -0,0 +1,1060 @@
+//! HNSW Approximate Nearest Neighbor Index
+//!
+//! This module implements a Hierarchical Navigable Small World (HNSW) graph
+//! for approximate nearest neighbor search over embedding vectors. It replaces
+//! random-sampling approximations with a proper graph index whose recall stays
+//! stable as the vault grows and is reproducible from one run to the next.
+//!
+//! ## Features
+//!
+//! - **Incremental Updates**: Entries are inserted and removed without a full rebuild
+//! - **Deterministic Levels**: Layer assignment uses a seeded generator stored with the index
+//! - **Cosine Similarity**: Vectors are normalized on insert so scoring is a dot product
+//! - **Persistence**: Compact bincode snapshot saved next to the storage files
+//!
+//! ## Algorithm
+//!
+//! Each node is assigned a maximum layer drawn from an exponential distribution.
+//! Upper layers are sparse "express lanes" used for greedy descent, while layer 0
+//! contains every node. Search descends greedily from the entry point to layer 0
+//! and then runs a best-first beam search of width `ef`.
+//!
+//! ## Performance Characteristics
+//!
+//! - **Insert**: O(log n) expected distance computations per layer
+//! - **Search**: O(log n) expected, tunable accuracy through `ef_search`
+//! - **Memory**: One normalized copy of each vector plus ~`2 * m` links per node
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::vector_db::types::{EmbeddingEntry, VectorDbError, VectorDbResult};
+
+/// File name of the persisted HNSW snapshot inside the storage directory
+pub const HNSW_INDEX_FILE_NAME: &str = "hnsw_index.bin";
+
+/// Configuration for the HNSW graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Maximum number of links per node on upper layers
+    pub m: usize,
+    /// Maximum number of links per node on layer 0 (usually `2 * m`)
+    pub m0: usize,
+    /// Beam width used while inserting nodes
+    pub ef_construction: usize,
+    /// Default beam width used while searching
+    pub ef_search: usize,
+    /// Seed for the level generator (keeps graphs reproducible)
+    pub seed: u64,
+    /// Number of updates between automatic snapshots to disk (0 = only on explicit save)
+    pub persist_every_n_updates: usize,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            m0: 32,
+            ef_construction: 100,
+            ef_search: 100,
+            seed: 0x5eed_a1b2_c3d4_e5f6,
+            persist_every_n_updates: 64,
+        }
+    }
+}
+
+/// A single node of the HNSW graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HnswNode {
+    /// Embedding entry ID this node represents
+    id: String,
+    /// Normalized vector used for scoring
+    vector: Vec<f32>,
+    /// Neighbor slots for each layer, `neighbors[0]` is the base layer
+    neighbors: Vec<Vec<usize>>,
+    /// Slots linking to this node on each layer (rebuilt after loading)
+    #[serde(skip)]
+    referrers: Vec<Vec<usize>>,
+}
+
+impl HnswNode {
+    fn level(&self) -> usize {
+        self.neighbors.len() - 1
+    }
+}
+
+/// Candidate wrapper ordered by similarity (higher is better)
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    similarity: f32,
+    slot: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity
+            .partial_cmp(&other.similarity)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.slot.cmp(&self.slot))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hierarchical Navigable Small World index over embedding vectors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    /// Graph configuration
+    config: HnswConfig,
+    /// Node storage, `None` marks a free slot
+    nodes: Vec<Option<HnswNode>>,
+    /// Entry ID to slot mapping
+    id_to_slot: HashMap<String, usize>,
+    /// Slots released by removals and available for reuse
+    free_slots: Vec<usize>,
+    /// Slot of the current entry point
+    entry_point: Option<usize>,
+    /// Vector dimension accepted by this index
+    dimension: Option<usize>,
+    /// State of the level generator
+    rng_state: u64,
+    /// Updates applied since the last snapshot
+    #[serde(skip)]
+    pending_updates: usize,
+    /// Updates covered by snapshots taken but not yet written
+    #[serde(skip)]
+    unwritten_updates: usize,
+}
+
+impl HnswIndex {
+    /// Create an empty index with the given configuration
+    pub fn new(config: HnswConfig) -> Self {
+        let rng_state = config.seed;
+        Self {
+            config,
+            nodes: Vec::new(),
+            id_to_slot: HashMap::new(),
+            free_slots: Vec::new(),
+            entry_point: None,
+            dimension: None,
+            rng_state,
+            pending_updates: 0,
+            unwritten_updates: 0,
+        }
+    }
+
+    /// Build an index from a set of embedding entries
+    ///
+    /// Entries whose dimension does not match the first entry are skipped.
+    pub fn build_from_entries(config: HnswConfig, entries: &[EmbeddingEntry]) -> Self {
+        let mut index = Self::new(config);
+        for entry in entries {
+            if let Err(e) = index.insert(&entry.id, &entry.vector) {
+                eprintln!("⚠️ Skipping entry {} while building HNSW index: {}", entry.id, e);
+            }
+        }
+        index.pending_updates = 0;
+        index
+    }
+
+    /// Number of vectors in the index
+    pub fn len(&self) -> usize {
+        self.id_to_slot.len()
+    }
+
+    /// Check whether the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.id_to_slot.is_empty()
+    }
+
+    /// Check whether an entry is present in the index
+    pub fn contains(&self, id: &str) -> bool {
+        self.id_to_slot.contains_key(id)
+    }
+
+    /// Normalized vector stored for an entry
+    pub fn vector(&self, id: &str) -> Option<&[f32]> {
+        let slot = *self.id_to_slot.get(id)?;
+        self.nodes[slot].as_ref().map(|node| node.vector.as_slice())
+    }
+
+    /// Vector dimension accepted by the index (None while empty)
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    /// Get the index configuration
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// List all entry IDs in the index
+    pub fn ids(&self) -> Vec<String> {
+        self.id_to_slot.keys().cloned().collect()
+    }
+
+    /// Remove all nodes while keeping the configuration
+    pub fn clear(&mut self) {
+        let config = self.config.clone();
+        *self = Self::new(config);
+        self.pending_updates = 1;
+    }
+
+    /// Insert or replace a vector in the index
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Embedding entry ID
+    /// * `vector` - Raw embedding vector (normalized internally)
+    pub fn insert(&mut self, id: &str, vector: &[f32]) -> VectorDbResult<()> {
+        if vector.is_empty() {
+            return Err(VectorDbError::InvalidEntry {
+                reason: "cannot index an empty vector".to_string(),
+            });
+        }
+        if let Some(dimension) = self.dimension {
+            if dimension != vector.len() && !self.is_empty() {
+                return Err(VectorDbError::InvalidEntry {
+                    reason: format!(
+                        "HNSW index dimension mismatch: expected {}, found {}",
+                        dimension,
+                        vector.len()
+                    ),
+                });
+            }
+        }
+
+        if self.contains(id) {
+            self.remove(id);
+        }
+
+        let normalized = Self::normalize(vector);
+        let level = self.random_level();
+        let node = HnswNode {
+            id: id.to_string(),
+            vector: normalized,
+            neighbors: vec![Vec::new(); level + 1],
+            referrers: vec![Vec::new(); level + 1],
+        };
+
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.nodes[slot] = Some(node);
+                slot
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.id_to_slot.insert(id.to_string(), slot);
+        self.dimension = Some(vector.len());
+        self.pending_updates += 1;
+
+        let entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => {
+                self.entry_point = Some(slot);
+                return Ok(());
+            }
+        };
+
+        let query = self.node(slot).vector.clone();
+        let top_level = self.node(entry_point).level();
+        let mut current = Candidate {
+            similarity: self.similarity_to(&query, entry_point),
+            slot: entry_point,
+        };
+
+        // Greedy descent through the layers above the new node's level
+        for layer in (level + 1..=top_level).rev() {
+            current = self.greedy_search_layer(&query, current, layer);
+        }
+
+        // Connect the node on every layer it participates in
+        let mut entry_points = vec![current];
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates = self.search_layer(&query, &entry_points, self.config.ef_construction, layer);
+            let max_links = self.max_links(layer);
+            let selected = self.select_neighbors(&candidates, max_links, Some(slot));
+
+            self.set_neighbors(slot, layer, selected.clone());
+            for &neighbor in &selected {
+                self.link(neighbor, slot, layer);
+            }
+
+            entry_points = candidates;
+        }
+
+        if level > top_level {
+            self.entry_point = Some(slot);
+        }
+
+        Ok(())
+    }
+
+    /// Change the entry ID of a node without touching the graph
+    ///
+    /// An entry already stored under `new_id` is removed first.
+    ///
+    /// # Returns
+    ///
+    /// True if `old_id` was present
+    pub fn rename(&mut self, old_id: &str, new_id: &str) -> bool {
+        if old_id == new_id {
+            return self.contains(old_id);
+        }
+        if !self.contains(old_id) {
+            return false;
+        }
+        self.remove(new_id);
+
+        let slot = self.id_to_slot.remove(old_id).expect("checked above");
+        if let Some(node) = self.nodes[slot].as_mut() {
+            node.id = new_id.to_string();
+        }
+        self.id_to_slot.insert(new_id.to_string(), slot);
+        self.pending_updates += 1;
+        true
+    }
+
+    /// Remove an entry from the index
+    ///
+    /// Neighbors that linked to the removed node are reconnected using the
+    /// removed node's own neighborhood so the graph stays navigable. They are
+    /// found through the node's reverse links, so the cost depends on its
+    /// in-degree rather than the size of the index.
+    ///
+    /// # Returns
+    ///
+    /// True if the entry was present and removed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let slot = match self.id_to_slot.remove(id) {
+            Some(slot) => slot,
+            None => return false,
+        };
+
+        let removed = self.nodes[slot].take().expect("slot mapped to a live node");
+        self.free_slots.push(slot);
+        self.pending_updates += 1;
+
+        // Repair every node that still points at the removed slot
+        for layer in 0..=removed.level() {
+            for &neighbor in &removed.neighbors[layer] {
+                if self.is_live(neighbor) {
+                    self.node_mut(neighbor).referrers[layer].retain(|&r| r != slot);
+                }
+            }
+
+            for &referrer in &removed.referrers[layer] {
+                if !self.is_live(referrer) {
+                    continue;
+                }
+                let mut pool: HashSet<usize> = self.node(referrer).neighbors[layer]
+                    .iter()
+                    .copied()
+                    .filter(|&n| n != slot)
+                    .collect();
+                for &candidate in &removed.neighbors[layer] {
+                    if candidate != referrer && candidate != slot && self.is_live(candidate)
+                        && self.node(candidate).level() >= layer
+                    {
+                        pool.insert(candidate);
+                    }
+                }
+
+                let query = self.node(referrer).vector.clone();
+                let mut candidates: Vec<Candidate> = pool
+                    .into_iter()
+                    .map(|n| Candidate { similarity: self.similarity_to(&query, n), slot: n })
+                    .collect();
+                candidates.sort_by(|a, b| b.cmp(a));
+
+                let max_links = self.max_links(layer);
+                let selected = self.select_neighbors(&candidates, max_links, Some(referrer));
+                self.set_neighbors(referrer, layer, selected);
+            }
+        }
+
+        if self.entry_point == Some(slot) {
+            self.entry_point = self
+                .live_slots()
+                .max_by_key(|&s| (self.node(s).level(), std::cmp::Reverse(s)));
+        }
+
+        if self.id_to_slot.is_empty() {
+            self.nodes.clear();
+            self.free_slots.clear();
+            self.entry_point = None;
+            self.dimension = None;
+        }
+
+        true
+    }
+
+    /// Search for the `k` nearest neighbors of a query vector
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector (normalized internally)
+    /// * `k` - Number of neighbors to return
+    /// * `ef` - Beam width; defaults to `ef_search` and is raised to at least `k`
+    ///
+    /// # Returns
+    ///
+    /// `(entry_id, cosine_similarity)` pairs sorted by similarity (descending)
+    pub fn search(&self, query: &[f32], k: usize, ef: Option<usize>) -> VectorDbResult<Vec<(String, f32)>> {
+        if k == 0 || self.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some(dimension) = self.dimension {
+            if dimension != query.len() {
+                return Err(VectorDbError::InvalidEntry {
+                    reason: format!(
+                        "HNSW query dimension mismatch: expected {}, found {}",
+                        dimension,
+                        query.len()
+                    ),
+                });
+            }
+        }
+
+        let entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => return Ok(Vec::new()),
+        };
+
+        let query = Self::normalize(query);
+        let ef = ef.unwrap_or(self.config.ef_search).max(k);
+
+        let mut current = Candidate {
+            similarity: self.similarity_to(&query, entry_point),
+            slot: entry_point,
+        };
+        for layer in (1..=self.node(entry_point).level()).rev() {
+            current = self.greedy_search_layer(&query, current, layer);
+        }
+
+        let candidates = self.search_layer(&query, &[current], ef, 0);
+        Ok(candidates
+            .into_iter()
+            .take(k)
+            .map(|c| (self.node(c.slot).id.clone(), c.similarity))
+            .collect())
+    }
+
+    /// Whether enough updates have accumulated to warrant a snapshot
+    pub fn needs_persist(&self) -> bool {
+        self.config.persist_every_n_updates > 0
+            && self.pending_updates.saturating_sub(self.unwritten_updates) >= self.config.persist_every_n_updates
+    }
+
+    /// Whether there are updates not yet written to disk
+    pub fn is_dirty(&self) -> bool {
+        self.pending_updates > 0
+    }
+
+    /// Save the index to disk atomically (write to temp file, then rename)
+    pub fn save_to_file(&mut self, path: &Path) -> VectorDbResult<()> {
+        let (data, updates) = self.snapshot()?;
+        let result = Self::write_snapshot(path, &data);
+        self.finish_snapshot(updates, result.is_ok());
+        result
+    }
+
+    /// Serialize the index, returning the data and the updates it covers
+    ///
+    /// Pair with [`HnswIndex::write_snapshot`] to do the file write after
+    /// releasing whatever lock guards the index, then report the outcome with
+    /// [`HnswIndex::finish_snapshot`]. Until then the index stays dirty but does
+    /// not ask for another snapshot of the same updates.
+    pub fn snapshot(&mut self) -> VectorDbResult<(Vec<u8>, usize)> {
+        let data = bincode::serialize(&*self).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to serialize HNSW index: {}", e),
+        })?;
+        let updates = self.pending_updates.saturating_sub(self.unwritten_updates);
+        self.unwritten_updates = self.pending_updates;
+        Ok((data, updates))
+    }
+
+    /// Record whether a snapshot covering `updates` reached the disk
+    ///
+    /// A failed write leaves its updates pending so the next update
+    /// triggers a new snapshot.
+    pub fn finish_snapshot(&mut self, updates: usize, written: bool) {
+        self.unwritten_updates = self.unwritten_updates.saturating_sub(updates);
+        if written {
+            self.pending_updates = self.pending_updates.saturating_sub(updates);
+        }
+    }
+
+    /// Write a snapshot produced by [`HnswIndex::snapshot`] atomically
+    pub fn write_snapshot(path: &Path, data: &[u8]) -> VectorDbResult<()> {
+        let temp_path = path.with_extension("bin.tmp");
+        fs::write(&temp_path, data).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to write HNSW index: {}", e),
+        })?;
+        fs::rename(&temp_path, path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to finalize HNSW index file: {}", e),
+        })
+    }
+
+    /// Load an index snapshot from disk
+    pub fn load_from_file(path: &Path) -> VectorDbResult<Self> {
+        let data = fs::read(path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to read HNSW index: {}", e),
+        })?;
+        let mut index: Self = bincode::deserialize(&data).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to deserialize HNSW index: {}", e),
+        })?;
+        index.rebuild_referrers();
+        Ok(index)
+    }
+
+    /// Get index statistics
+    pub fn stats(&self) -> HnswStats {
+        let live: Vec<&HnswNode> = self.nodes.iter().flatten().collect();
+        let max_level = live.iter().map(|n| n.level()).max().unwrap_or(0);
+        let base_links: usize = live.iter().map(|n| n.neighbors[0].len()).sum();
+
+        HnswStats {
+            node_count: live.len(),
+            max_level,
+            dimension: self.dimension.unwrap_or(0),
+            avg_base_degree: if live.is_empty() { 0.0 } else { base_links as f64 / live.len() as f64 },
+            memory_usage_estimate: live
+                .iter()
+                .map(|n| {
+                    n.vector.len() * std::mem::size_of::<f32>()
+                        + n.id.len()
+                        + n.neighbors.iter().map(|l| l.len() * std::mem::size_of::<usize>()).sum::<usize>()
+                })
+                .sum(),
+        }
+    }
+
+    // Private helper methods
+
+    fn node(&self, slot: usize) -> &HnswNode {
+        self.nodes[slot].as_ref().expect("HNSW slot refers to a live node")
+    }
+
+    fn node_mut(&mut self, slot: usize) -> &mut HnswNode {
+        self.nodes[slot].as_mut().expect("HNSW slot refers to a live node")
+    }
+
+    fn is_live(&self, slot: usize) -> bool {
+        self.nodes.get(slot).map(|n| n.is_some()).unwrap_or(false)
+    }
+
+    fn live_slots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, node)| node.as_ref().map(|_| slot))
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 { self.config.m0 } else { self.config.m }
+    }
+
+    fn similarity_to(&self, query: &[f32], slot: usize) -> f32 {
+        Self::dot(query, &self.node(slot).vector)
+    }
+
+    /// Dot product with eight independent accumulators so the loop vectorizes
+    fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let mut lanes = [0.0f32; 8];
+        let chunks_a = a.chunks_exact(8);
+        let chunks_b = b.chunks_exact(8);
+        let tail: f32 = chunks_a
+            .remainder()
+            .iter()
+            .zip(chunks_b.remainder())
+            .map(|(x, y)| x * y)
+            .sum();
+        for (ca, cb) in chunks_a.zip(chunks_b) {
+            for i in 0..8 {
+                lanes[i] += ca[i] * cb[i];
+            }
+        }
+        lanes.iter().sum::<f32>() + tail
+    }
+
+    fn normalize(vector: &[f32]) -> Vec<f32> {
+        let magnitude = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if magnitude > 0.0 && magnitude.is_finite() {
+            vector.iter().map(|x| x / magnitude).collect()
+        } else {
+            vector.to_vec()
+        }
+    }
+
+    /// Draw a level from the exponential distribution with `mL = 1 / ln(m)`
+    fn random_level(&mut self) -> usize {
+        // SplitMix64 step
+        self.rng_state = self.rng_state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        // Uniform in (0, 1]
+        let uniform = ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let level_multiplier = 1.0 / (self.config.m.max(2) as f64).ln();
+        ((-uniform.ln() * level_multiplier).floor() as usize).min(16)
+    }
+
+    /// Greedy hill-climb on a single layer (beam width 1)
+    fn greedy_search_layer(&self, query: &[f32], start: Candidate, layer: usize) -> Candidate {
+        let mut current = start;
+        loop {
+            let mut improved = false;
+            for &neighbor in &self.node(current.slot).neighbors[layer] {
+                let similarity = self.similarity_to(query, neighbor);
+                if similarity > current.similarity {
+                    current = Candidate { similarity, slot: neighbor };
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Best-first beam search on a single layer
+    ///
+    /// Returns up to `ef` candidates sorted by similarity (descending).
+    fn search_layer(&self, query: &[f32], entry_points: &[Candidate], ef: usize, layer: usize) -> Vec<Candidate> {
+        // Only the slots actually reached are tracked, so a query never pays for the index size
+        let mut visited: HashSet<usize> = HashSet::with_capacity(ef * 4);
+        // Max-heap of candidates to expand (best first)
+        let mut to_visit: BinaryHeap<Candidate> = BinaryHeap::new();
+        // Min-heap of current results (worst on top)
+        let mut results: BinaryHeap<std::cmp::Reverse<Candidate>> = BinaryHeap::new();
+
+        for &entry in entry_points {
+            if visited.insert(entry.slot) {
+                to_visit.push(entry);
+                results.push(std::cmp::Reverse(entry));
+                if results.len() > ef {
+                    results.pop();
+                }
+            }
+        }
+
+        while let Some(candidate) = to_visit.pop() {
+            let worst = results.peek().map(|r| r.0.similarity).unwrap_or(f32::MIN);
+            if candidate.similarity < worst && results.len() >= ef {
+                break;
+            }
+
+            let node = self.node(candidate.slot);
+            if node.level() < layer {
+                continue;
+            }
+
+            for &neighbor in &node.neighbors[layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let similarity = self.similarity_to(query, neighbor);
+                let worst = results.peek().map(|r| r.0.similarity).unwrap_or(f32::MIN);
+                if results.len() < ef || similarity > worst {
+                    let next = Candidate { similarity, slot: neighbor };
+                    to_visit.push(next);
+                    results.push(std::cmp::Reverse(next));
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        let mut sorted: Vec<Candidate> = results.into_iter().map(|r| r.0).collect();
+        sorted.sort_by(|a, b| b.cmp(a));
+        sorted
+    }
+
+    /// Neighbor selection heuristic from the HNSW paper
+    ///
+    /// A candidate is kept only if it is closer to the query than to any
+    /// already selected neighbor, which spreads links across clusters. Remaining
+    /// slots are filled with the closest discarded candidates.
+    fn select_neighbors(&self, candidates: &[Candidate], max_links: usize, exclude: Option<usize>) -> Vec<usize> {
+        let mut selected: Vec<Candidate> = Vec::with_capacity(max_links);
+        let mut discarded: Vec<Candidate> = Vec::new();
+
+        for &candidate in candidates {
+            if Some(candidate.slot) == exclude {
+                continue;
+            }
+            if selected.len() >= max_links {
+                break;
+            }
+            let candidate_vector = &self.node(candidate.slot).vector;
+            let dominated = selected.iter().any(|chosen| {
+                Self::dot(candidate_vector, &self.node(chosen.slot).vector) > candidate.similarity
+            });
+            if dominated {
+                discarded.push(candidate);
+            } else {
+                selected.push(candidate);
+            }
+        }
+
+        for candidate in discarded {
+            if selected.len() >= max_links {
+                break;
+            }
+            selected.push(candidate);
+        }
+
+        selected.into_iter().map(|c| c.slot).collect()
+    }
+
+    /// Add a link `from -> to` on a layer, pruning `from` if it exceeds its budget
+    fn link(&mut self, from: usize, to: usize, layer: usize) {
+        let max_links = self.max_links(layer);
+        let node = self.node(from);
+        if node.level() < layer || node.neighbors[layer].contains(&to) {
+            return;
+        }
+        let mut neighbors = node.neighbors[layer].clone();
+        neighbors.push(to);
+        if neighbors.len() <= max_links {
+            self.set_neighbors(from, layer, neighbors);
+            return;
+        }
+
+        let base = self.node(from).vector.clone();
+        let mut candidates: Vec<Candidate> = neighbors
+            .iter()
+            .map(|&n| Candidate { similarity: self.similarity_to(&base, n), slot: n })
+            .collect();
+        candidates.sort_by(|a, b| b.cmp(a));
+        let pruned = self.select_neighbors(&candidates, max_links, Some(from));
+        self.set_neighbors(from, layer, pruned);
+    }
+
+    /// Replace the links of `from` on a layer and keep the reverse links in sync
+    fn set_neighbors(&mut self, from: usize, layer: usize, neighbors: Vec<usize>) {
+        let previous = std::mem::take(&mut self.node_mut(from).neighbors[layer]);
+        for &old in &previous {
+            if !neighbors.contains(&old) && self.is_live(old) {
+                self.node_mut(old).referrers[layer].retain(|&r| r != from);
+            }
+        }
+        for &new in &neighbors {
+            if !previous.contains(&new) {
+                self.node_mut(new).referrers[layer].push(from);
+            }
+        }
+        self.node_mut(from).neighbors[layer] = neighbors;
+    }
+
+    /// Recompute the reverse links from the forward links
+    fn rebuild_referrers(&mut self) {
+        for node in self.nodes.iter_mut().flatten() {
+            node.referrers = vec![Vec::new(); node.neighbors.len()];
+        }
+        for slot in 0..self.nodes.len() {
+            let links: Vec<(usize, usize)> = match &self.nodes[slot] {
+                Some(node) => node
+                    .neighbors
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(layer, neighbors)| neighbors.iter().map(move |&n| (layer, n)))
+                    .collect(),
+                None => continue,
+            };
+            for (layer, neighbor) in links {
+                if let Some(target) = self.nodes[neighbor].as_mut() {
+                    if layer < target.referrers.len() {
+                        target.referrers[layer].push(slot);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for HnswIndex {
+    fn default() -> Self {
+        Self::new(HnswConfig::default())
+    }
+}
+
+/// Statistics about the HNSW index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswStats {
+    /// Number of indexed vectors
+    pub node_count: usize,
+    /// Highest layer in the graph
+    pub max_level: usize,
+    /// Vector dimension
+    pub dimension: usize,
+    /// Average number of links per node on layer 0
+    pub avg_base_degree: f64,
+    /// Estimated memory usage in bytes
+    pub memory_usage_estimate: usize,
+}
+
+impl HnswStats {
+    /// Generate a human-readable summary of index statistics
+    pub fn summary(&self) -> String {
+        format!(
+            "HNSW Index: {} vectors ({}D), {} layers, {:.1} avg links, {:.1} KB memory",
+            self.node_count,
+            self.dimension,
+            self.max_level + 1,
+            self.avg_base_degree,
+            self.memory_usage_estimate as f64 / 1024.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::similarity_search::{SearchConfig, SimilaritySearch};
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use tempfile::TempDir;
+
+    fn random_entries(count: usize, dimension: usize, seed: u64) -> Vec<EmbeddingEntry> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|i| {
+                let vector: Vec<f32> = (0..dimension).map(|_| rng.gen_range(-1.0..1.0)).collect();
+                EmbeddingEntry::new(
+                    vector,
+                    format!("/vault/note_{}.md", i % 50),
+                    format!("chunk_{}", i),
+                    &format!("text for entry {}", i),
+                    "test-model".to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn exact_config() -> SearchConfig {
+        SearchConfig {
+            min_threshold: -1.0,
+            max_results: 0,
+            early_termination: false,
+            normalize_query: false,
+            enable_diversity_filter: false,
+            enable_recency_weighting: false,
+            ..SearchConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_insert_and_search_exact_match() {
+        let entries = random_entries(200, 16, 7);
+        let index = HnswIndex::build_from_entries(HnswConfig::default(), &entries);
+
+        assert_eq!(index.len(), 200);
+        assert_eq!(index.dimension(), Some(16));
+
+        let results = index.search(&entries[42].vector, 1, None).unwrap();
+        assert_eq!(results[0].0, entries[42].id);
+        assert!((results[0].1 - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_recall_against_exact_knn() {
+        let entries = random_entries(2000, 32, 42);
+        let queries = random_entries(50, 32, 4242);
+        let index = HnswIndex::build_from_entries(HnswConfig::default(), &entries);
+        let config = exact_config();
+        let k = 10;
+
+        let mut hits = 0;
+        for query in &queries {
+            let exact = SimilaritySearch::k_nearest_neighbors(&query.vector, &entries, k, &config).unwrap();
+            let exact_ids: HashSet<&str> = exact.iter().map(|r| r.entry.id.as_str()).collect();
+            let approx = index.search(&query.vector, k, None).unwrap();
+            hits += approx.iter().filter(|(id, _)| exact_ids.contains(id.as_str())).count();
+        }
+
+        let recall = hits as f64 / (queries.len() * k) as f64;
+        println!("HNSW recall@{}: {:.3}", k, recall);
+        assert!(recall >= 0.9, "recall@{} too low: {:.3}", k, recall);
+    }
+
+    #[test]
+    fn test_remove_keeps_graph_searchable() {
+        let entries = random_entries(500, 16, 11);
+        let mut index = HnswIndex::build_from_entries(HnswConfig::default(), &entries);
+
+        for entry in entries.iter().step_by(2) {
+            assert!(index.remove(&entry.id));
+        }
+        assert!(!index.remove(&entries[0].id));
+        assert_eq!(index.len(), 250);
+
+        for entry in entries.iter().skip(1).step_by(2).take(25) {
+            let results = index.search(&entry.vector, 1, None).unwrap();
+            assert_eq!(results[0].0, entry.id);
+        }
+
+        let removed: HashSet<&str> = entries.iter().step_by(2).map(|e| e.id.as_str()).collect();
+        let results = index.search(&entries[0].vector, 20, None).unwrap();
+        assert!(results.iter().all(|(id, _)| !removed.contains(id.as_str())));
+    }
+
+    #[test]
+    fn test_reverse_links_match_forward_links() {
+        let entries = random_entries(300, 16, 5);
+        let mut index = HnswIndex::build_from_entries(HnswConfig::default(), &entries);
+        for entry in entries.iter().step_by(3) {
+            index.remove(&entry.id);
+        }
+        for entry in entries.iter().step_by(6) {
+            index.insert(&entry.id, &entry.vector).unwrap();
+        }
+
+        let reverse_links = |index: &HnswIndex| {
+            let mut links: Vec<(usize, usize, usize)> = Vec::new();
+            for slot in index.live_slots() {
+                for (layer, referrers) in index.node(slot).referrers.iter().enumerate() {
+                    links.extend(referrers.iter().map(|&r| (layer, r, slot)));
+                }
+            }
+            links.sort_unstable();
+            links
+        };
+
+        let mut forward: Vec<(usize, usize, usize)> = Vec::new();
+        for slot in index.live_slots() {
+            for (layer, neighbors) in index.node(slot).neighbors.iter().enumerate() {
+                for &n in neighbors {
+                    assert!(index.is_live(n), "link to a removed slot");
+                    forward.push((layer, slot, n));
+                }
+            }
+        }
+        forward.sort_unstable();
+        assert_eq!(reverse_links(&index), forward);
+
+        let mut rebuilt = index.clone();
+        rebuilt.rebuild_referrers();
+        assert_eq!(reverse_links(&rebuilt), forward);
+    }
+
+    #[test]
+    fn test_rename_keeps_vector_and_neighbors() {
+        let entries = random_entries(100, 16, 3);
+        let mut index = HnswIndex::build_from_entries(HnswConfig::default(), &entries);
+
+        assert!(index.rename(&entries[5].id, "moved"));
+        assert!(!index.rename(&entries[5].id, "again"));
+        assert!(!index.contains(&entries[5].id));
+        assert_eq!(index.len(), 100);
+
+        let results = index.search(&entries[5].vector, 1, None).unwrap();
+        assert_eq!(results[0].0, "moved");
+
+        // Renaming onto an existing ID replaces that entry
+        assert!(index.rename("moved", &entries[6].id));
+        assert_eq!(index.len(), 99);
+        assert_eq!(index.search(&entries[5].vector, 1, None).unwrap()[0].0, entries[6].id);
+    }
+
+    #[test]
+    fn test_dimension_mismatch_rejected() {
+        let mut index = HnswIndex::default();
+        index.insert("a", &[0.1, 0.2, 0.3]).unwrap();
+
+        assert!(index.insert("b", &[0.1, 0.2]).is_err());
+        assert!(index.search(&[0.1, 0.2], 1, None).is_err());
+
+        index.remove("a");
+        assert!(index.insert("b", &[0.1, 0.2]).is_ok());
+    }
+
+    #[test]
+    fn test_reinsert_replaces_vector() {
+        let mut index = HnswIndex::default();
+        index.insert("a", &[1.0, 0.0]).unwrap();
+        index.insert("b", &[0.0, 1.0]).unwrap();
+        index.insert("a", &[0.0, 1.0]).unwrap();
+
+        assert_eq!(index.len(), 2);
+        let results = index.search(&[0.0, 1.0], 2, None).unwrap();
+        assert!(results.iter().all(|(_, similarity)| (*similarity - 1.0).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(HNSW_INDEX_FILE_NAME);
+        let entries = random_entries(300, 16, 3);
+
+        let mut index = HnswIndex::build_from_entries(HnswConfig::default(), &entries);
+        index.save_to_file(&path).unwrap();
+        assert!(!index.is_dirty());
+
+        let loaded = HnswIndex::load_from_file(&path).unwrap();
+        assert_eq!(loaded.len(), index.len());
+
+        let query = &entries[10].vector;
+        assert_eq!(
+            index.search(query, 5, None).unwrap(),
+            loaded.search(query, 5, None).unwrap()
+        );
+
+        // The index stays dirty until the snapshot write is reported
+        index.remove(&entries[0].id);
+        let (data, updates) = index.snapshot().unwrap();
+        assert!(index.is_dirty());
+        HnswIndex::write_snapshot(&path, &data).unwrap();
+        index.finish_snapshot(updates, true);
+        assert!(!index.is_dirty());
+        assert_eq!(HnswIndex::load_from_file(&path).unwrap().len(), 299);
+    }
+
+    #[test]
+    fn test_failed_snapshot_write_requests_another_snapshot() {
+        let config = HnswConfig {
+            persist_every_n_updates: 2,
+            ..HnswConfig::default()
+        };
+        let mut index = HnswIndex::new(config);
+        index.insert("a", &[1.0, 0.0]).unwrap();
+        index.insert("b", &[0.0, 1.0]).unwrap();
+        assert!(index.needs_persist());
+
+        // A snapshot in flight is not requested again
+        let (_, updates) = index.snapshot().unwrap();
+        assert!(!index.needs_persist());
+
+        index.finish_snapshot(updates, false);
+        assert!(index.is_dirty());
+        assert!(index.needs_persist());
+
+        let (_, updates) = index.snapshot().unwrap();
+        index.insert("c", &[1.0, 1.0]).unwrap();
+        index.finish_snapshot(updates, true);
+        assert!(index.is_dirty());
+        assert!(!index.needs_persist());
+    }
+}
@@ -46,6 +46,8 @@ pub struct IndexingSystem {
     chunk_index: Arc<RwLock<HashMap<String, HashMap<String, String>>>>, // file_path -> (chunk_id -> entry_id)
     /// Timestamp range index for temporal queries
     timestamp_index: Arc<RwLock<BTreeMap<u64, Vec<String>>>>,
+    /// Note tag to embedding IDs mapping
+    tag_index: Arc<RwLock<HashMap<String, Vec<String>>>>,
     /// Full entry ID to metadata mapping for quick lookups
     metadata_index: Arc<RwLock<HashMap<String, IndexMetadata>>>,
     /// Storage backend for data operations
@@ -75,6 +77,9 @@ pub struct IndexMetadata {
     pub updated_at: u64,
     /// Vector dimension count
     pub dimension: usize,
+    /// Tags of the note the entry belongs to
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl IndexMetadata {
@@ -89,6 +94,7 @@ impl IndexMetadata {
             created_at: entry.created_at,
             updated_at: entry.updated_at,
             dimension: entry.vector.len(),
+            tags: entry.metadata.tags(),
         }
     }
 }
@@ -106,6 +112,8 @@ struct SerializableIndexData {
     chunk_index: HashMap<String, HashMap<String, String>>,
     /// Timestamp index data
     timestamp_index: BTreeMap<u64, Vec<String>>,
+    /// Tag index data
+    tag_index: HashMap<String, Vec<String>>,
     /// Metadata index data
     metadata_index: HashMap<String, IndexMetadata>,
     /// Index creation timestamp
@@ -133,6 +141,7 @@ impl IndexingSystem {
             content_hash_index: Arc::new(RwLock::new(HashMap::new())),
             chunk_index: Arc::new(RwLock::new(HashMap::new())),
             timestamp_index: Arc::new(RwLock::new(BTreeMap::new())),
+            tag_index: Arc::new(RwLock::new(HashMap::new())),
             metadata_index: Arc::new(RwLock::new(HashMap::new())),
             storage,
             persist_indexes,
@@ -210,6 +219,14 @@ impl IndexingSystem {
                 .push(entry.id.clone());
         }
         
+        // Update tag index
+        {
+            let mut tag_index = self.tag_index.write().await;
+            for tag in &metadata.tags {
+                tag_index.entry(tag.clone()).or_default().push(entry.id.clone());
+            }
+        }
+        
         // Update metadata index
         {
             let mut metadata_index = self.metadata_index.write().await;
@@ -291,6 +308,19 @@ impl IndexingSystem {
                     }
                 }
             }
+            
+            // Remove from tag index
+            {
+                let mut tag_index = self.tag_index.write().await;
+                for tag in &meta.tags {
+                    if let Some(tag_entries) = tag_index.get_mut(tag) {
+                        tag_entries.retain(|id| id != entry_id);
+                        if tag_entries.is_empty() {
+                            tag_index.remove(tag);
+                        }
+                    }
+                }
+            }
         }
         
         // Remove from metadata index
@@ -403,6 +433,20 @@ impl IndexingSystem {
         result
     }
 
+    /// Find embedding entries by note tag
+    /// 
+    /// # Arguments
+    /// 
+    /// * `tag` - The tag to search for, without `#` (case-insensitive)
+    /// 
+    /// # Returns
+    /// 
+    /// Vector of entry IDs whose note carries the tag
+    pub async fn find_by_tag(&self, tag: &str) -> Vec<String> {
+        let tag_index = self.tag_index.read().await;
+        tag_index.get(&tag.trim_start_matches('#').to_lowercase()).cloned().unwrap_or_default()
+    }
+
     /// Get all indexed file paths
     /// 
     /// # Returns
@@ -423,6 +467,16 @@ impl IndexingSystem {
         model_index.keys().cloned().collect()
     }
 
+    /// Get all indexed note tags
+    /// 
+    /// # Returns
+    /// 
+    /// Vector of all tags carried by indexed notes
+    pub async fn get_indexed_tags(&self) -> Vec<String> {
+        let tag_index = self.tag_index.read().await;
+        tag_index.keys().cloned().collect()
+    }
+
     /// Get metadata for a specific entry
     /// 
     /// # Arguments
@@ -448,6 +502,7 @@ impl IndexingSystem {
         let hash_index = self.content_hash_index.read().await;
         let chunk_index = self.chunk_index.read().await;
         let timestamp_index = self.timestamp_index.read().await;
+        let tag_index = self.tag_index.read().await;
         let metadata_index = self.metadata_index.read().await;
         
         IndexStats {
@@ -462,6 +517,7 @@ impl IndexingSystem {
                 &hash_index,
                 &chunk_index,
                 &timestamp_index,
+                &tag_index,
                 &metadata_index,
             ),
         }
@@ -503,6 +559,7 @@ impl IndexingSystem {
         self.content_hash_index.write().await.clear();
         self.chunk_index.write().await.clear();
         self.timestamp_index.write().await.clear();
+        self.tag_index.write().await.clear();
         self.metadata_index.write().await.clear();
     }
 
@@ -518,6 +575,7 @@ impl IndexingSystem {
             content_hash_index: self.content_hash_index.read().await.clone(),
             chunk_index: self.chunk_index.read().await.clone(),
             timestamp_index: self.timestamp_index.read().await.clone(),
+            tag_index: self.tag_index.read().await.clone(),
             metadata_index: self.metadata_index.read().await.clone(),
             created_at: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -558,6 +616,7 @@ impl IndexingSystem {
         *self.content_hash_index.write().await = index_data.content_hash_index;
         *self.chunk_index.write().await = index_data.chunk_index;
         *self.timestamp_index.write().await = index_data.timestamp_index;
+        *self.tag_index.write().await = index_data.tag_index;
         *self.metadata_index.write().await = index_data.metadata_index;
 
         eprintln!("📂 Loaded indexes from {}", self.index_file_path);
@@ -571,6 +630,7 @@ impl IndexingSystem {
         hash_index: &HashMap<String, String>,
         chunk_index: &HashMap<String, HashMap<String, String>>,
         timestamp_index: &BTreeMap<u64, Vec<String>>,
+        tag_index: &HashMap<String, Vec<String>>,
         metadata_index: &HashMap<String, IndexMetadata>,
     ) -> usize {
         let mut total = 0;
@@ -599,6 +659,10 @@ impl IndexingSystem {
         total += timestamp_index.len() * 8; // u64 keys
         total += timestamp_index.values().map(|v| v.iter().map(|s| s.len()).sum::<usize>()).sum::<usize>();
 
+        // Tag index
+        total += tag_index.keys().map(|k| k.len()).sum::<usize>();
+        total += tag_index.values().map(|v| v.iter().map(|s| s.len()).sum::<usize>()).sum::<usize>();
+
         // Metadata index
         total += metadata_index.keys().map(|k| k.len()).sum::<usize>();
         total += metadata_index.len() * std::mem::size_of::<IndexMetadata>();
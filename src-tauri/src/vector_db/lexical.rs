@@ -0,0 +1,491 @@
+//! BM25 Lexical Index
+//!
+//! This module implements an inverted index with Okapi BM25 scoring over the
+//! same chunks that are embedded into the vector database. Embedding search is
+//! good at meaning but weak at exact terms such as identifiers, names and
+//! acronyms; the lexical index covers those so both rankings can be fused.
+//!
+//! ## Features
+//!
+//! - **Incremental Updates**: Chunks are added and removed together with their embeddings
+//! - **Identifier Friendly Tokens**: `snake_case`, `CamelCase` and dotted names stay searchable
+//! - **Persistence**: Compact bincode snapshot saved next to the storage files
+//!
+//! ## Scoring
+//!
+//! ```text
+//! score(q, d) = Σ idf(t) * tf(t, d) * (k1 + 1) / (tf(t, d) + k1 * (1 - b + b * |d| / avgdl))
+//! idf(t)      = ln(1 + (N - df(t) + 0.5) / (df(t) + 0.5))
+//! ```
+//!
+//! Documents are keyed by embedding entry ID so results can be resolved and
+//! fused with cosine results from `SimilaritySearch`.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::vector_db::types::{VectorDbError, VectorDbResult};
+
+/// File name of the persisted lexical index snapshot inside the storage directory
+pub const LEXICAL_INDEX_FILE_NAME: &str = "lexical_index.bin";
+
+/// Configuration for BM25 scoring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bm25Config {
+    /// Term frequency saturation (typical range 1.2 - 2.0)
+    pub k1: f32,
+    /// Document length normalization (0.0 = none, 1.0 = full)
+    pub b: f32,
+    /// Number of updates between automatic snapshots to disk (0 = only on explicit save)
+    pub persist_every_n_updates: usize,
+}
+
+impl Default for Bm25Config {
+    fn default() -> Self {
+        Self {
+            k1: 1.2,
+            b: 0.75,
+            persist_every_n_updates: 64,
+        }
+    }
+}
+
+/// Inverted index with BM25 ranking, keyed by embedding entry ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bm25Index {
+    /// Scoring configuration
+    config: Bm25Config,
+    /// Term to (entry ID to term frequency) postings
+    postings: HashMap<String, HashMap<String, u32>>,
+    /// Token count of each indexed document
+    doc_lengths: HashMap<String, u32>,
+    /// Sum of all document lengths
+    total_length: u64,
+    /// Distinct terms of each indexed document, so updates touch only its postings
+    #[serde(skip)]
+    doc_terms: HashMap<String, Vec<String>>,
+    /// Updates applied since the last snapshot
+    #[serde(skip)]
+    pending_updates: usize,
+    /// Updates covered by snapshots taken but not yet written
+    #[serde(skip)]
+    unwritten_updates: usize,
+}
+
+impl Bm25Index {
+    /// Create an empty index with the given configuration
+    pub fn new(config: Bm25Config) -> Self {
+        Self {
+            config,
+            postings: HashMap::new(),
+            doc_lengths: HashMap::new(),
+            total_length: 0,
+            doc_terms: HashMap::new(),
+            pending_updates: 0,
+            unwritten_updates: 0,
+        }
+    }
+
+    /// Number of indexed documents
+    pub fn len(&self) -> usize {
+        self.doc_lengths.len()
+    }
+
+    /// Whether the index contains no documents
+    pub fn is_empty(&self) -> bool {
+        self.doc_lengths.is_empty()
+    }
+
+    /// Whether a document with this entry ID is indexed
+    pub fn contains(&self, id: &str) -> bool {
+        self.doc_lengths.contains_key(id)
+    }
+
+    /// Get the scoring configuration
+    pub fn config(&self) -> &Bm25Config {
+        &self.config
+    }
+
+    /// Entry IDs of all indexed documents
+    pub fn ids(&self) -> Vec<String> {
+        self.doc_lengths.keys().cloned().collect()
+    }
+
+    /// Remove every document from the index
+    pub fn clear(&mut self) {
+        self.postings.clear();
+        self.doc_lengths.clear();
+        self.doc_terms.clear();
+        self.total_length = 0;
+        self.pending_updates += 1;
+    }
+
+    /// Index the text of a chunk, replacing any previous text for the same ID
+    pub fn insert(&mut self, id: &str, text: &str) {
+        self.remove(id);
+
+        let tokens = Self::tokenize(text);
+        let mut term_frequencies: HashMap<String, u32> = HashMap::new();
+        for token in &tokens {
+            *term_frequencies.entry(token.clone()).or_insert(0) += 1;
+        }
+
+        let mut terms = Vec::with_capacity(term_frequencies.len());
+        for (term, frequency) in term_frequencies {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(id.to_string(), frequency);
+            terms.push(term);
+        }
+        self.doc_terms.insert(id.to_string(), terms);
+
+        self.doc_lengths.insert(id.to_string(), tokens.len() as u32);
+        self.total_length += tokens.len() as u64;
+        self.pending_updates += 1;
+    }
+
+    /// Remove a document from the index
+    ///
+    /// # Returns
+    ///
+    /// `true` if the document was indexed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let length = match self.doc_lengths.remove(id) {
+            Some(length) => length,
+            None => return false,
+        };
+
+        for term in self.doc_terms.remove(id).unwrap_or_default() {
+            if let Some(documents) = self.postings.get_mut(&term) {
+                documents.remove(id);
+                if documents.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+        self.total_length -= length as u64;
+        self.pending_updates += 1;
+        true
+    }
+
+    /// Move a document to another entry ID, keeping its terms
+    ///
+    /// A document already indexed under `new_id` is replaced.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `old_id` was indexed
+    pub fn rename(&mut self, old_id: &str, new_id: &str) -> bool {
+        if old_id == new_id {
+            return self.contains(old_id);
+        }
+        let length = match self.doc_lengths.remove(old_id) {
+            Some(length) => length,
+            None => return false,
+        };
+        self.remove(new_id);
+
+        let terms = self.doc_terms.remove(old_id).unwrap_or_default();
+        for term in &terms {
+            if let Some(documents) = self.postings.get_mut(term) {
+                if let Some(frequency) = documents.remove(old_id) {
+                    documents.insert(new_id.to_string(), frequency);
+                }
+            }
+        }
+        self.doc_terms.insert(new_id.to_string(), terms);
+        self.doc_lengths.insert(new_id.to_string(), length);
+        self.pending_updates += 1;
+        true
+    }
+
+    /// Rank documents against a text query
+    ///
+    /// # Returns
+    ///
+    /// Up to `k` `(entry_id, score)` pairs sorted by BM25 score (descending).
+    /// Documents that share no term with the query are not returned.
+    pub fn search(&self, query: &str, k: usize) -> Vec<(String, f32)> {
+        if k == 0 || self.is_empty() {
+            return Vec::new();
+        }
+
+        let document_count = self.doc_lengths.len() as f32;
+        let average_length = (self.total_length as f32 / document_count).max(1.0);
+        let query_terms: HashSet<String> = Self::tokenize(query).into_iter().collect();
+
+        let mut scores: HashMap<&str, f32> = HashMap::new();
+        for term in &query_terms {
+            let documents = match self.postings.get(term) {
+                Some(documents) => documents,
+                None => continue,
+            };
+
+            let document_frequency = documents.len() as f32;
+            let idf = (1.0 + (document_count - document_frequency + 0.5) / (document_frequency + 0.5)).ln();
+
+            for (id, &frequency) in documents {
+                let frequency = frequency as f32;
+                let length = self.doc_lengths.get(id).copied().unwrap_or(0) as f32;
+                let normalization = self.config.k1 * (1.0 - self.config.b + self.config.b * length / average_length);
+                *scores.entry(id.as_str()).or_insert(0.0) +=
+                    idf * frequency * (self.config.k1 + 1.0) / (frequency + normalization);
+            }
+        }
+
+        let mut ranked: Vec<(String, f32)> = scores
+            .into_iter()
+            .map(|(id, score)| (id.to_string(), score))
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        ranked.truncate(k);
+        ranked
+    }
+
+    /// Split text into lowercase index terms
+    ///
+    /// Terms are runs of alphanumeric characters and underscores, so
+    /// `parse_config` and `HNSW` stay whole. Dotted and hyphenated names are
+    /// indexed both as parts and joined (`vector_db.rs` yields `vector_db`,
+    /// `rs` and `vector_db.rs`) so exact identifiers rank first.
+    pub fn tokenize(text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+
+        for word in text.split(|c: char| c.is_whitespace()) {
+            let parts: Vec<String> = word
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|part| !part.is_empty())
+                .map(|part| part.to_lowercase())
+                .collect();
+
+            if parts.len() > 1 {
+                let compound = word
+                    .trim_matches(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .to_lowercase();
+                if compound.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-') {
+                    tokens.push(compound);
+                }
+            }
+            tokens.extend(parts);
+        }
+
+        tokens
+    }
+
+    /// Whether enough updates have accumulated to warrant a snapshot
+    pub fn needs_persist(&self) -> bool {
+        self.config.persist_every_n_updates > 0
+            && self.pending_updates.saturating_sub(self.unwritten_updates) >= self.config.persist_every_n_updates
+    }
+
+    /// Whether there are updates not yet written to disk
+    pub fn is_dirty(&self) -> bool {
+        self.pending_updates > 0
+    }
+
+    /// Save the index to disk atomically (write to temp file, then rename)
+    pub fn save_to_file(&mut self, path: &Path) -> VectorDbResult<()> {
+        let (data, updates) = self.snapshot()?;
+        let result = Self::write_snapshot(path, &data);
+        self.finish_snapshot(updates, result.is_ok());
+        result
+    }
+
+    /// Serialize the index, returning the data and the updates it covers
+    ///
+    /// Pair with [`Bm25Index::write_snapshot`] to do the file write after
+    /// releasing whatever lock guards the index, then report the outcome with
+    /// [`Bm25Index::finish_snapshot`]. Until then the index stays dirty but does
+    /// not ask for another snapshot of the same updates.
+    pub fn snapshot(&mut self) -> VectorDbResult<(Vec<u8>, usize)> {
+        let data = bincode::serialize(&*self).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to serialize lexical index: {}", e),
+        })?;
+        let updates = self.pending_updates.saturating_sub(self.unwritten_updates);
+        self.unwritten_updates = self.pending_updates;
+        Ok((data, updates))
+    }
+
+    /// Record whether a snapshot covering `updates` reached the disk
+    ///
+    /// A failed write leaves its updates pending so the next update
+    /// triggers a new snapshot.
+    pub fn finish_snapshot(&mut self, updates: usize, written: bool) {
+        self.unwritten_updates = self.unwritten_updates.saturating_sub(updates);
+        if written {
+            self.pending_updates = self.pending_updates.saturating_sub(updates);
+        }
+    }
+
+    /// Write a snapshot produced by [`Bm25Index::snapshot`] atomically
+    pub fn write_snapshot(path: &Path, data: &[u8]) -> VectorDbResult<()> {
+        let temp_path = path.with_extension("bin.tmp");
+        fs::write(&temp_path, data).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to write lexical index: {}", e),
+        })?;
+        fs::rename(&temp_path, path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to finalize lexical index file: {}", e),
+        })
+    }
+
+    /// Load an index snapshot from disk
+    pub fn load_from_file(path: &Path) -> VectorDbResult<Self> {
+        let data = fs::read(path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to read lexical index: {}", e),
+        })?;
+        let mut index: Self = bincode::deserialize(&data).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to deserialize lexical index: {}", e),
+        })?;
+        index.rebuild_doc_terms();
+        Ok(index)
+    }
+
+    /// Derive each document's term list from the postings
+    fn rebuild_doc_terms(&mut self) {
+        self.doc_terms.clear();
+        for (term, documents) in &self.postings {
+            for id in documents.keys() {
+                self.doc_terms.entry(id.clone()).or_default().push(term.clone());
+            }
+        }
+    }
+
+    /// Get index statistics
+    pub fn stats(&self) -> Bm25Stats {
+        Bm25Stats {
+            document_count: self.doc_lengths.len(),
+            term_count: self.postings.len(),
+            avg_document_length: if self.doc_lengths.is_empty() {
+                0.0
+            } else {
+                self.total_length as f64 / self.doc_lengths.len() as f64
+            },
+        }
+    }
+}
+
+impl Default for Bm25Index {
+    fn default() -> Self {
+        Self::new(Bm25Config::default())
+    }
+}
+
+/// Statistics about the lexical index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bm25Stats {
+    /// Number of indexed chunks
+    pub document_count: usize,
+    /// Number of distinct terms
+    pub term_count: usize,
+    /// Average chunk length in tokens
+    pub avg_document_length: f64,
+}
+
+impl Bm25Stats {
+    /// Generate a human-readable summary of index statistics
+    pub fn summary(&self) -> String {
+        format!(
+            "Lexical Index: {} chunks, {} terms, {:.1} avg tokens/chunk",
+            self.document_count, self.term_count, self.avg_document_length
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_index() -> Bm25Index {
+        let mut index = Bm25Index::default();
+        index.insert("a", "The HNSW index speeds up approximate search over embeddings.");
+        index.insert("b", "Call parse_config before opening the vault.");
+        index.insert("c", "Embeddings capture meaning; search ranks notes by meaning.");
+        index
+    }
+
+    #[test]
+    fn test_tokenize_keeps_identifiers() {
+        let tokens = Bm25Index::tokenize("Use parse_config in vector_db.rs (HNSW)!");
+        assert!(tokens.contains(&"parse_config".to_string()));
+        assert!(tokens.contains(&"vector_db.rs".to_string()));
+        assert!(tokens.contains(&"vector_db".to_string()));
+        assert!(tokens.contains(&"rs".to_string()));
+        assert!(tokens.contains(&"hnsw".to_string()));
+        assert!(!tokens.iter().any(|t| t.is_empty()));
+    }
+
+    #[test]
+    fn test_exact_term_ranks_first() {
+        let index = sample_index();
+
+        let results = index.search("parse_config", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "b");
+
+        let results = index.search("hnsw search", 10);
+        assert_eq!(results[0].0, "a");
+        assert!(results.iter().any(|(id, _)| id == "c"));
+        assert!(results.iter().all(|(_, score)| *score > 0.0));
+    }
+
+    #[test]
+    fn test_remove_and_reinsert() {
+        let mut index = sample_index();
+        assert!(index.remove("b"));
+        assert!(!index.remove("b"));
+        assert!(index.search("parse_config", 10).is_empty());
+        assert!(!index.postings.contains_key("parse_config"));
+        assert_eq!(index.len(), 2);
+
+        index.insert("a", "Rewritten chunk about backups");
+        assert!(index.search("hnsw", 10).is_empty());
+        assert_eq!(index.search("backups", 10)[0].0, "a");
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_rename_moves_terms() {
+        let mut index = sample_index();
+        let before = index.search("parse_config", 10);
+
+        assert!(index.rename("b", "moved"));
+        assert!(!index.rename("b", "again"));
+        assert!(!index.contains("b"));
+        assert_eq!(index.len(), 3);
+
+        let after = index.search("parse_config", 10);
+        assert_eq!(after[0].0, "moved");
+        assert_eq!(after[0].1, before[0].1);
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(LEXICAL_INDEX_FILE_NAME);
+
+        let mut index = sample_index();
+        index.save_to_file(&path).unwrap();
+        assert!(!index.is_dirty());
+
+        let loaded = Bm25Index::load_from_file(&path).unwrap();
+        assert_eq!(loaded.len(), index.len());
+        assert_eq!(loaded.search("embeddings", 5), index.search("embeddings", 5));
+
+        // Term lists are not persisted but rebuilt, so updates still find every posting
+        let mut loaded = loaded;
+        assert!(loaded.rename("a", "moved"));
+        assert_eq!(loaded.search("hnsw", 5)[0].0, "moved");
+        assert!(loaded.remove("moved"));
+        assert!(loaded.search("hnsw", 5).is_empty());
+        assert!(!loaded.postings.contains_key("hnsw"));
+    }
+}
@@ -5,12 +5,16 @@
 //! 
 //! ## Features
 //! 
-//! - **File-based storage**: JSON serialization with optional compression
+//! - **Segment storage**: Append-only binary segments with raw f32 vector blocks
+//! - **Crash safety**: Write-ahead log replayed on open, atomic segment writes
 //! - **Data integrity**: Checksum validation and version compatibility
 //! - **Atomic operations**: Safe concurrent access with file locking
-//! - **Compression support**: Gzip compression for storage efficiency
-//! - **Backup system**: Automatic backup creation for data safety
+//! - **Compression support**: Gzip or LZ4 compression of entry metadata
+//! - **Backup system**: Automatic backup of newly written segments
 //! - **Metrics tracking**: Performance and storage statistics
+//! - **Approximate search**: Persistent HNSW graph index kept in sync with storage
+//! - **Hybrid search**: BM25 lexical index over chunk text fused with vector ranking
+//! - **Model namespaces**: Embeddings grouped per model, searches use the active model only
 //! 
 //! ## Architecture
 //! 
@@ -21,10 +25,10 @@
 //!    - `EmbeddingMetadata`: Associated metadata (file path, chunk ID, etc.)
 //!    - `VectorStorageConfig`: Configuration for storage behavior
 //! 
-//! 2. **Storage** (`storage.rs`): File operations and data persistence
+//! 2. **Storage** (`storage.rs`, `segment.rs`, `wal.rs`): Data persistence
 //!    - `VectorStorage`: Main storage engine with CRUD operations
-//!    - Compression and decompression handling
-//!    - Index management for fast retrieval
+//!    - Write-ahead log and memtable flushed into immutable segments
+//!    - Segment merging during compaction, migration of JSON batch files
 //! 
 //! 3. **Database** (this file): High-level database interface
 //!    - `VectorDatabase`: Main database API
@@ -68,36 +72,25 @@
 //! 
 //! ## Data Format
 //! 
-//! The storage format uses JSON serialization with optional compression:
+//! Storage format version 2 keeps entries in immutable segment files named
+//! `segment_{sequence}.seg`, plus a `wal.log` for mutations not yet flushed:
 //! 
-//! ```json
-//! {
-//!   "header": {
-//!     "version": {"major": 1, "minor": 0, "patch": 0},
-//!     "compression": "Gzip",
-//!     "entry_count": 100,
-//!     "created_at": 1635724800
-//!   },
-//!   "entries": [
-//!     {
-//!       "id": "sha256_hash",
-//!       "vector": [0.1, 0.2, 0.3, ...],
-//!       "metadata": {
-//!         "file_path": "/path/to/file.md",
-//!         "chunk_id": "chunk_1",
-//!         "created_at": 1635724800,
-//!         "text_hash": "content_hash",
-//!         "model_name": "embedding-model"
-//!       }
-//!     }
-//!   ]
-//! }
+//! ```text
+//! [magic "AINOTSEG"][header length: u32 LE][header: bincode]
+//! [vector block: little-endian f32 values]
+//! [record block: length-prefixed bincode metadata records]
 //! ```
+//! 
+//! The header carries the `DataVersion`, entry count, checksum and the
+//! tombstones of entries deleted since the previous segment. JSON batch files
+//! (`vector_*.json[.gz|.lz4]`, format 1.x) are migrated into a segment the
+//! first time the storage is opened and moved to `backups/legacy`.
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::{Mutex, RwLock};
 
 pub mod types;
 pub mod storage;
@@ -118,6 +111,12 @@ pub mod automatic_cleanup;
 pub mod metrics_collector;
 pub mod monitored_search;
 pub mod optimization_scheduler;
+pub mod hnsw;
+pub mod lexical;
+pub mod segment;
+pub mod wal;
+pub mod model_namespace;
+pub mod quantized_store;
 
 
 use types::{EmbeddingEntry, StorageMetrics, VectorStorageConfig, VectorDbResult, VectorDbError};
@@ -127,8 +126,58 @@ use indexing::{IndexingSystem, IndexStats};
 use incremental::{IncrementalUpdateManager, IncrementalConfig, UpdateStats};
 use file_ops::{FileOperations, InitializationStatus, CleanupResult, BackupResult, RecoveryResult, FileSystemMetrics};
 use maintenance::{MaintenanceManager, MaintenanceConfig, MaintenanceStats};
+use hnsw::{HnswConfig, HnswIndex, HnswStats, HNSW_INDEX_FILE_NAME};
+use model_namespace::{ModelNamespaceStats, NamespaceState, NAMESPACE_STATE_FILE_NAME};
+use lexical::{Bm25Config, Bm25Index, Bm25Stats, LEXICAL_INDEX_FILE_NAME};
+use compression::{ProductQuantizationConfig, ProductQuantizer, VectorCompressionConfig, PQ_CODEBOOK_FILE_NAME};
+use optimization_scheduler::CodebookRetrainingHook;
+use quantized_store::QuantizedVectorStore;
+use crate::rag::load_chunk_text;
+use crate::similarity_search::{HybridSearchResult, QuantizedSearchConfig, SearchConfig, SearchResult, SimilaritySearch};
 use rebuilding::{IndexRebuilder, HealthChecker, RebuildingConfig, HealthCheckConfig, RebuildResult, HealthCheckResult, RebuildProgress};
 
+/// Candidates fetched from each ranking per requested hybrid search result
+const HYBRID_CANDIDATE_MULTIPLIER: usize = 4;
+
+/// Shared index a snapshot was taken from
+#[derive(Debug, Clone, Copy)]
+enum SnapshotIndex {
+    Ann,
+    Lexical,
+}
+
+/// Index snapshot serialized under its lock and written to disk after release
+struct PendingSnapshot {
+    /// Index the snapshot was taken from
+    index: SnapshotIndex,
+    /// Destination file
+    path: PathBuf,
+    /// Serialized index
+    data: Vec<u8>,
+    /// Index updates covered by the snapshot
+    updates: usize,
+    /// Order in which the snapshot was taken, so a stale one never overwrites a newer one
+    generation: u64,
+}
+
+impl SnapshotIndex {
+    /// Writes a snapshot of this index to a file atomically
+    fn writer(self) -> fn(&Path, &[u8]) -> VectorDbResult<()> {
+        match self {
+            SnapshotIndex::Ann => HnswIndex::write_snapshot,
+            SnapshotIndex::Lexical => Bm25Index::write_snapshot,
+        }
+    }
+    
+    /// Index name used in log messages
+    fn label(self) -> &'static str {
+        match self {
+            SnapshotIndex::Ann => "ANN index",
+            SnapshotIndex::Lexical => "lexical index",
+        }
+    }
+}
+
 /// High-level vector database interface
 /// 
 /// This struct provides the main API for interacting with the vector storage system.
@@ -163,6 +212,22 @@ pub struct VectorDatabase {
     index_rebuilder: Option<IndexRebuilder>,
     /// Health checker for index validation and health monitoring
     health_checker: Option<HealthChecker>,
+    /// HNSW graph over the active model namespace
+    ann_index: Arc<RwLock<HnswIndex>>,
+    /// Model whose embeddings are searched (lock before `ann_index`)
+    active_model: Arc<RwLock<Option<String>>>,
+    /// Compressed codes for the ANN index vectors
+    quantized_vectors: Arc<QuantizedVectorStore>,
+    /// Location of the persisted namespace state
+    namespace_state_path: PathBuf,
+    /// BM25 inverted index over chunk text for lexical search
+    lexical_index: Arc<RwLock<Bm25Index>>,
+    /// Location of the persisted lexical index snapshot
+    lexical_index_path: PathBuf,
+    /// Source of snapshot generations
+    snapshot_generation: AtomicU64,
+    /// Generation last written per snapshot file; held while writing so writes do not interleave
+    snapshot_writes: Mutex<HashMap<PathBuf, u64>>,
 }
 
 impl VectorDatabase {
@@ -186,6 +251,26 @@ impl VectorDatabase {
             None
         };
         
+        // Resolve the active model namespace, then load its ANN index snapshot
+        // and reconcile it with the storage contents
+        let namespace_state_path = Path::new(&config.storage_dir).join(NAMESPACE_STATE_FILE_NAME);
+        let active_model = Self::load_active_model(&storage, &namespace_state_path).await;
+        let ann_index = Arc::new(RwLock::new(
+            Self::load_ann_index(&storage, Path::new(&config.storage_dir), active_model.as_deref()).await,
+        ));
+        
+        // Codes for the index vectors are encoded with the persisted codebook, if one was trained
+        let compression_config = VectorCompressionConfig {
+            algorithm: config.vector_compression_algorithm.clone(),
+            ..VectorCompressionConfig::default()
+        };
+        let quantizer = Self::read_product_quantizer(&Path::new(&config.storage_dir).join(PQ_CODEBOOK_FILE_NAME));
+        let quantized_vectors = Arc::new(QuantizedVectorStore::new(ann_index.clone(), compression_config, quantizer)?);
+        
+        // Load the lexical index snapshot the same way
+        let lexical_index_path = Path::new(&config.storage_dir).join(LEXICAL_INDEX_FILE_NAME);
+        let lexical_index = Self::load_lexical_index(&storage, &lexical_index_path).await;
+        
         Ok(Self {
             storage,
             file_ops,
@@ -201,6 +286,14 @@ impl VectorDatabase {
             maintenance_manager: None, // Initialized on demand via enable_maintenance
             index_rebuilder: None, // Initialized on demand via enable_index_rebuilding
             health_checker: None, // Initialized on demand via enable_health_checks
+            ann_index,
+            active_model: Arc::new(RwLock::new(active_model)),
+            quantized_vectors,
+            namespace_state_path,
+            lexical_index: Arc::new(RwLock::new(lexical_index)),
+            lexical_index_path,
+            snapshot_generation: AtomicU64::new(0),
+            snapshot_writes: Mutex::new(HashMap::new()),
         })
     }
     
@@ -234,13 +327,31 @@ impl VectorDatabase {
         original_text: &str,
         model_name: impl Into<String>,
     ) -> VectorDbResult<String> {
-        let entry = EmbeddingEntry::new(
+        self.store_embedding_with_metadata(vector, file_path, chunk_id, original_text, model_name, HashMap::new())
+            .await
+    }
+    
+    /// Store a new embedding together with custom metadata
+    /// 
+    /// Same as [`Self::store_embedding`], but `custom_metadata` is attached to the
+    /// entry's metadata (e.g. the heading path of the source chunk).
+    pub async fn store_embedding_with_metadata(
+        &self,
+        vector: Vec<f32>,
+        file_path: impl Into<String>,
+        chunk_id: impl Into<String>,
+        original_text: &str,
+        model_name: impl Into<String>,
+        custom_metadata: HashMap<String, String>,
+    ) -> VectorDbResult<String> {
+        let mut entry = EmbeddingEntry::new(
             vector,
             file_path.into(),
             chunk_id.into(),
             original_text,
             model_name.into(),
         );
+        entry.metadata.custom_metadata.extend(custom_metadata);
         
         let entry_id = entry.id.clone();
         
@@ -250,6 +361,10 @@ impl VectorDatabase {
         // Store in persistent storage
         self.storage.store_entries(vec![entry.clone()]).await?;
         
+        // Keep the ANN and lexical indexes in sync
+        self.index_vectors(std::slice::from_ref(&entry)).await;
+        self.index_texts(&[(entry_id.as_str(), original_text)]).await;
+        
         // Update cache
         self.update_cache(entry_id.clone(), entry).await;
         
@@ -259,7 +374,8 @@ impl VectorDatabase {
     /// Store multiple embeddings in a batch operation
     /// 
     /// This is more efficient than storing embeddings individually as it minimizes
-    /// I/O operations and maintains data consistency.
+    /// I/O operations and maintains data consistency. Entries carry no original
+    /// text, so the lexical index gets each chunk's text cut out of its note.
     pub async fn store_embeddings_batch(&self, entries: Vec<EmbeddingEntry>) -> VectorDbResult<Vec<String>> {
         if entries.is_empty() {
             return Ok(vec![]);
@@ -275,6 +391,16 @@ impl VectorDatabase {
         // Store in persistent storage
         self.storage.store_entries(entries.clone()).await?;
         
+        // Keep the ANN and lexical indexes in sync
+        self.index_vectors(&entries).await;
+        let texts = Self::load_chunk_texts(&entries).await;
+        let documents: Vec<(&str, &str)> = entries
+            .iter()
+            .zip(&texts)
+            .map(|(entry, text)| (entry.id.as_str(), text.as_str()))
+            .collect();
+        self.index_texts(&documents).await;
+        
         // Update cache for each entry
         for entry in entries {
             self.update_cache(entry.id.clone(), entry).await;
@@ -342,6 +468,7 @@ impl VectorDatabase {
             
             // Store updated entry
             self.storage.store_entries(vec![entry.clone()]).await?;
+            self.index_vectors(std::slice::from_ref(&entry)).await;
             
             // Update cache
             self.update_cache(entry_id.to_string(), entry).await;
@@ -358,8 +485,27 @@ impl VectorDatabase {
         
         if deleted {
             // Remove from cache
-            let mut cache = self.cache.write().await;
-            cache.remove(entry_id);
+            {
+                let mut cache = self.cache.write().await;
+                cache.remove(entry_id);
+            }
+            
+            // Remove from the ANN index
+            let ann_snapshot = {
+                let active_model = self.active_model.read().await;
+                let mut ann_index = self.ann_index.write().await;
+                ann_index.remove(entry_id);
+                self.ann_snapshot_if_needed(&mut ann_index, active_model.as_deref())
+            };
+            self.persist_snapshot(ann_snapshot).await;
+            
+            // Remove from the lexical index
+            let lexical_snapshot = {
+                let mut lexical_index = self.lexical_index.write().await;
+                lexical_index.remove(entry_id);
+                self.lexical_snapshot_if_needed(&mut lexical_index)
+            };
+            self.persist_snapshot(lexical_snapshot).await;
         }
         
         Ok(deleted)
@@ -389,7 +535,10 @@ impl VectorDatabase {
             cache.clear();
         }
         
-        self.storage.compact_storage().await
+        let result = self.storage.compact_storage().await?;
+        self.save_ann_index().await?;
+        self.save_lexical_index().await?;
+        Ok(result)
     }
     
     /// Validate database integrity and return a detailed report
@@ -412,17 +561,17 @@ impl VectorDatabase {
     
     /// Find embeddings by file path
     /// 
-    /// This is useful for finding all embeddings associated with a specific file
+    /// This is useful for finding all embeddings associated with a specific file.
+    /// The storage index records each entry's source file, so only the
+    /// matching entries are read.
     pub async fn find_embeddings_by_file(&self, file_path: &str) -> VectorDbResult<Vec<EmbeddingEntry>> {
-        let all_ids = self.list_embedding_ids().await;
-        let all_entries = self.retrieve_embeddings(&all_ids).await?;
-        
-        let matching_entries = all_entries
-            .into_iter()
-            .filter(|entry| entry.metadata.file_path == file_path)
-            .collect();
-        
-        Ok(matching_entries)
+        let entry_ids = self.storage.list_entry_ids_for_file(file_path).await;
+        self.retrieve_embeddings(&entry_ids).await
+    }
+    
+    /// Whether any embedding is stored for a file, without reading the entries
+    pub async fn has_embeddings_for_file(&self, file_path: &str) -> bool {
+        !self.storage.list_entry_ids_for_file(file_path).await.is_empty()
     }
     
     /// Find embeddings by model name
@@ -455,7 +604,147 @@ impl VectorDatabase {
         
         Ok(deleted_count)
     }
-    
+
+    /// Delete and store embeddings as one atomic storage mutation
+    ///
+    /// Each new entry is paired with its original chunk text, which feeds the
+    /// lexical index. Deletes apply before the new entries, so after a crash
+    /// storage holds either the old set or the new one, never both. The ANN
+    /// index, lexical index and cache follow once storage has committed.
+    pub async fn replace_embeddings(
+        &self,
+        deleted_ids: &[String],
+        entries: Vec<(EmbeddingEntry, &str)>,
+    ) -> VectorDbResult<()> {
+        if deleted_ids.is_empty() && entries.is_empty() {
+            return Ok(());
+        }
+        let (entries, texts): (Vec<EmbeddingEntry>, Vec<&str>) = entries.into_iter().unzip();
+
+        self.storage.replace_entries(deleted_ids, entries.clone()).await?;
+
+        {
+            let mut cache = self.cache.write().await;
+            for entry_id in deleted_ids {
+                cache.remove(entry_id);
+            }
+        }
+
+        if !deleted_ids.is_empty() {
+            let (ann_snapshot, lexical_snapshot) = {
+                let active_model = self.active_model.read().await;
+                let mut ann_index = self.ann_index.write().await;
+                let mut lexical_index = self.lexical_index.write().await;
+                for entry_id in deleted_ids {
+                    ann_index.remove(entry_id);
+                    lexical_index.remove(entry_id);
+                }
+                (
+                    self.ann_snapshot_if_needed(&mut ann_index, active_model.as_deref()),
+                    self.lexical_snapshot_if_needed(&mut lexical_index),
+                )
+            };
+            self.persist_snapshot(ann_snapshot).await;
+            self.persist_snapshot(lexical_snapshot).await;
+        }
+
+        self.index_vectors(&entries).await;
+        let documents: Vec<(&str, &str)> = entries
+            .iter()
+            .zip(texts)
+            .map(|(entry, text)| (entry.id.as_str(), text))
+            .collect();
+        self.index_texts(&documents).await;
+
+        for entry in entries {
+            self.update_cache(entry.id.clone(), entry).await;
+        }
+
+        Ok(())
+    }
+
+    /// Move embeddings to new file paths without re-embedding
+    ///
+    /// Each `(old_path, new_path)` pair re-keys the old file's entries to the
+    /// new path, keeping their vectors. Entries already stored under a new
+    /// path are replaced. All moves are committed to storage as one atomic
+    /// mutation, then the ANN index, lexical index and cache follow.
+    ///
+    /// # Returns
+    ///
+    /// Number of entries moved
+    pub async fn move_file_embeddings(&self, moves: &[(String, String)]) -> VectorDbResult<usize> {
+        let mut deleted_ids = Vec::new();
+        let mut renamed_ids = Vec::new();
+        let mut moved_entries = Vec::new();
+
+        for (old_path, new_path) in moves {
+            if old_path == new_path {
+                continue;
+            }
+
+            deleted_ids.extend(self.storage.list_entry_ids_for_file(new_path).await);
+            for entry in self.find_embeddings_by_file(old_path).await? {
+                let mut moved = entry.clone();
+                moved.metadata.file_path = new_path.clone();
+                moved.metadata.touch();
+                moved.id = EmbeddingEntry::generate_id(
+                    new_path,
+                    &moved.metadata.chunk_id,
+                    &moved.metadata.text_hash,
+                    &moved.metadata.model_name,
+                );
+                deleted_ids.push(entry.id.clone());
+                renamed_ids.push((entry.id, moved.id.clone()));
+                moved_entries.push(moved);
+            }
+        }
+
+        if deleted_ids.is_empty() {
+            return Ok(0);
+        }
+
+        self.storage.replace_entries(&deleted_ids, moved_entries.clone()).await?;
+
+        {
+            let mut cache = self.cache.write().await;
+            for entry_id in &deleted_ids {
+                cache.remove(entry_id);
+            }
+        }
+
+        let renamed_old_ids: std::collections::HashSet<&str> =
+            renamed_ids.iter().map(|(old_id, _)| old_id.as_str()).collect();
+        let (ann_snapshot, lexical_snapshot) = {
+            let active_model = self.active_model.read().await;
+            let mut ann_index = self.ann_index.write().await;
+            let mut lexical_index = self.lexical_index.write().await;
+            for entry_id in &deleted_ids {
+                if !renamed_old_ids.contains(entry_id.as_str()) {
+                    ann_index.remove(entry_id);
+                    lexical_index.remove(entry_id);
+                }
+            }
+            for (old_id, new_id) in &renamed_ids {
+                ann_index.rename(old_id, new_id);
+                lexical_index.rename(old_id, new_id);
+            }
+            (
+                self.ann_snapshot_if_needed(&mut ann_index, active_model.as_deref()),
+                self.lexical_snapshot_if_needed(&mut lexical_index),
+            )
+        };
+        self.persist_snapshot(ann_snapshot).await;
+        self.persist_snapshot(lexical_snapshot).await;
+
+        for entry in moved_entries {
+            self.update_cache(entry.id.clone(), entry).await;
+        }
+
+        eprintln!("📦 Moved {} embeddings across {} file moves", renamed_ids.len(), moves.len());
+        Ok(renamed_ids.len())
+    }
+
     /// Get storage directory path
     pub fn get_storage_path(&self) -> PathBuf {
         PathBuf::from(&self.config.storage_dir)
@@ -471,6 +760,517 @@ impl VectorDatabase {
         self.list_embedding_ids().await.len()
     }
 
+    // === Approximate Nearest Neighbor Search ===
+    
+    /// Search for similar embeddings using the HNSW index
+    /// 
+    /// Candidates come from the graph index instead of a full scan and are then
+    /// filtered and ranked with the same pipeline as exact k-NN search. Only
+    /// the active model namespace is indexed, so only its entries are returned.
+    /// With a metadata filter in `config` the matching entries are scanned
+    /// exactly instead, since the graph cannot skip non-matching neighbors.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_vector` - Query embedding
+    /// * `k` - Number of results to return
+    /// * `config` - Search configuration (threshold, filters, diversity)
+    /// 
+    /// # Returns
+    /// 
+    /// Search results sorted by similarity (descending)
+    pub async fn approximate_search(
+        &self,
+        query_vector: &[f32],
+        k: usize,
+        config: &SearchConfig,
+    ) -> VectorDbResult<Vec<SearchResult>> {
+        if !config.filter.is_empty() {
+            return self.filtered_search(query_vector, k, config).await;
+        }
+        
+        let (candidates, active_model) = {
+            let active_model = self.active_model.read().await;
+            let ann_index = self.ann_index.read().await;
+            let candidates = SimilaritySearch::search_ann_index(query_vector, &ann_index, k, config)
+                .map_err(|e| VectorDbError::InvalidEntry { reason: e.to_string() })?;
+            (candidates, active_model.clone())
+        };
+        
+        self.rank_index_candidates(candidates, active_model.as_deref(), k, config).await
+    }
+    
+    /// Search for similar embeddings by scoring the compressed ANN index vectors
+    /// 
+    /// Every indexed vector is scored on its compressed code, and the best
+    /// candidates are re-ranked with their f32 vectors before the usual
+    /// filtering and ranking. Results match an exact scan of the active model
+    /// namespace closely without loading its embeddings from storage. Like
+    /// `approximate_search`, a metadata filter falls back to an exact scan.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_vector` - Query embedding
+    /// * `k` - Number of results to return
+    /// * `config` - Search configuration (threshold, filters, diversity)
+    /// 
+    /// # Returns
+    /// 
+    /// Search results sorted by similarity (descending)
+    pub async fn compressed_search(
+        &self,
+        query_vector: &[f32],
+        k: usize,
+        config: &SearchConfig,
+    ) -> VectorDbResult<Vec<SearchResult>> {
+        if !config.filter.is_empty() {
+            return self.filtered_search(query_vector, k, config).await;
+        }
+        
+        let quantized_config = QuantizedSearchConfig {
+            min_threshold: config.min_threshold,
+            ..QuantizedSearchConfig::default()
+        };
+        let (candidates, active_model) = {
+            let active_model = self.active_model.read().await;
+            self.quantized_vectors.sync().await;
+            let candidates = self.quantized_vectors
+                .search(query_vector, SimilaritySearch::candidate_fetch_count(k, config), &quantized_config)
+                .await
+                .map_err(|e| VectorDbError::InvalidEntry { reason: e.to_string() })?;
+            (candidates, active_model.clone())
+        };
+        
+        self.rank_index_candidates(candidates, active_model.as_deref(), k, config).await
+    }
+    
+    /// Exact scan of the active namespace entries matching the metadata filter
+    async fn filtered_search(
+        &self,
+        query_vector: &[f32],
+        k: usize,
+        config: &SearchConfig,
+    ) -> VectorDbResult<Vec<SearchResult>> {
+        let entries: Vec<EmbeddingEntry> = self
+            .active_namespace_entries()
+            .await?
+            .into_iter()
+            .filter(|entry| entry.vector.len() == query_vector.len() && config.filter.matches(entry))
+            .collect();
+        SimilaritySearch::k_nearest_neighbors(query_vector, &entries, k, config)
+            .map_err(|e| VectorDbError::InvalidEntry { reason: e.to_string() })
+    }
+    
+    /// Resolve `(entry_id, similarity)` candidates from the ANN index and rank them
+    async fn rank_index_candidates(
+        &self,
+        candidates: Vec<(String, f32)>,
+        active_model: Option<&str>,
+        k: usize,
+        config: &SearchConfig,
+    ) -> VectorDbResult<Vec<SearchResult>> {
+        let similarities: HashMap<String, f32> = candidates.into_iter().collect();
+        let ids: Vec<String> = similarities.keys().cloned().collect();
+        let resolved = self
+            .retrieve_embeddings(&ids)
+            .await?
+            .into_iter()
+            .filter(|entry| model_namespace::belongs_to(entry, active_model))
+            .filter_map(|entry| {
+                let similarity = *similarities.get(&entry.id)?;
+                Some(SearchResult { entry, similarity })
+            })
+            .collect();
+        
+        Ok(SimilaritySearch::rank_ann_candidates(resolved, k, config))
+    }
+    
+    /// Get a shared handle to the HNSW index
+    /// 
+    /// Used by the index rebuilder and by search commands that need direct
+    /// access to the graph.
+    pub fn ann_index(&self) -> Arc<RwLock<HnswIndex>> {
+        self.ann_index.clone()
+    }
+    
+    /// Get the number of vectors in the HNSW index
+    pub async fn ann_index_len(&self) -> usize {
+        self.ann_index.read().await.len()
+    }
+    
+    /// Get statistics about the HNSW index
+    pub async fn get_ann_index_stats(&self) -> HnswStats {
+        self.ann_index.read().await.stats()
+    }
+    
+    /// Write the HNSW index snapshot to disk
+    pub async fn save_ann_index(&self) -> VectorDbResult<()> {
+        let snapshot = {
+            let active_model = self.active_model.read().await;
+            let mut ann_index = self.ann_index.write().await;
+            let snapshot = ann_index.snapshot()?;
+            self.pending_snapshot(SnapshotIndex::Ann, self.ann_index_path(active_model.as_deref()), snapshot)
+        };
+        self.write_index_snapshot(snapshot).await
+    }
+    
+    /// Rebuild the HNSW index from every entry of the active model in storage
+    /// 
+    /// # Returns
+    /// 
+    /// Number of vectors in the rebuilt index
+    pub async fn rebuild_ann_index(&self) -> VectorDbResult<usize> {
+        // Work on a copy of the model name so a namespace switch is not
+        // blocked for the length of the rebuild
+        let model_name = self.active_model().await;
+        let all_ids = self.storage.list_entry_ids().await;
+        let entries: Vec<EmbeddingEntry> = self
+            .storage
+            .retrieve_entries(&all_ids)
+            .await?
+            .into_iter()
+            .filter(|entry| model_namespace::belongs_to(entry, model_name.as_deref()))
+            .collect();
+        let config = self.ann_index.read().await.config().clone();
+        
+        let mut rebuilt = Self::build_ann_index(config, entries).await?;
+        let snapshot = self.pending_snapshot(SnapshotIndex::Ann, self.ann_index_path(model_name.as_deref()), rebuilt.snapshot()?);
+        let updates = snapshot.updates;
+        self.write_snapshot(snapshot).await?;
+        rebuilt.finish_snapshot(updates, true);
+        let count = rebuilt.len();
+        
+        let active_model = self.active_model.read().await;
+        if *active_model == model_name {
+            *self.ann_index.write().await = rebuilt;
+        } else {
+            eprintln!("⚠️ Active model changed during ANN index rebuild; keeping the new namespace's index");
+        }
+        Ok(count)
+    }
+    
+    /// Build an HNSW index on the blocking thread pool
+    async fn build_ann_index(config: HnswConfig, entries: Vec<EmbeddingEntry>) -> VectorDbResult<HnswIndex> {
+        tokio::task::spawn_blocking(move || HnswIndex::build_from_entries(config, &entries))
+            .await
+            .map_err(|e| VectorDbError::Storage {
+                message: format!("ANN index build failed: {}", e),
+            })
+    }
+
+    // === Lexical and Hybrid Search ===
+    
+    /// Rank stored chunks against a text query with BM25
+    /// 
+    /// # Returns
+    /// 
+    /// Up to `k` `(entry_id, score)` pairs sorted by BM25 score (descending)
+    pub async fn lexical_search(&self, query_text: &str, k: usize) -> Vec<(String, f32)> {
+        self.lexical_index.read().await.search(query_text, k)
+    }
+    
+    /// Search with both the query text and its embedding, fusing the rankings
+    /// 
+    /// Vector candidates come from the HNSW index when it is populated and from
+    /// an exact scan otherwise; lexical candidates come from the BM25 index.
+    /// Both are restricted to the active model namespace.
+    /// Both lists are fused with reciprocal-rank fusion using the weights in
+    /// `config`. The similarity threshold only applies to vector candidates, so
+    /// exact term matches are kept even when their embedding is not close.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_text` - Query text for lexical matching
+    /// * `query_vector` - Embedding of the same query
+    /// * `k` - Number of results to return
+    /// * `config` - Search configuration (threshold, filters, fusion weights)
+    /// 
+    /// # Returns
+    /// 
+    /// Results sorted by fused score (descending)
+    pub async fn hybrid_search(
+        &self,
+        query_text: &str,
+        query_vector: &[f32],
+        k: usize,
+        config: &SearchConfig,
+    ) -> VectorDbResult<Vec<HybridSearchResult>> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        
+        // Over-fetch both rankings so fusion has overlap to work with
+        let candidate_count = k * HYBRID_CANDIDATE_MULTIPLIER;
+        let candidate_config = SearchConfig {
+            max_results: 0,
+            enable_diversity_filter: false,
+            ..config.clone()
+        };
+        
+        let vector_results = if self.ann_index_len().await > 0 || !config.filter.is_empty() {
+            self.approximate_search(query_vector, candidate_count, &candidate_config).await?
+        } else {
+            let entries: Vec<EmbeddingEntry> = self
+                .active_namespace_entries()
+                .await?
+                .into_iter()
+                .filter(|entry| entry.vector.len() == query_vector.len())
+                .collect();
+            SimilaritySearch::k_nearest_neighbors(query_vector, &entries, candidate_count, &candidate_config)
+                .map_err(|e| VectorDbError::InvalidEntry { reason: e.to_string() })?
+        };
+        
+        // Filtered searches rank every lexical match so the filter cannot leave the list short
+        let lexical_count = if config.filter.is_empty() {
+            candidate_count
+        } else {
+            self.lexical_index.read().await.len()
+        };
+        let lexical_scores: HashMap<String, f32> = self
+            .lexical_search(query_text, lexical_count)
+            .await
+            .into_iter()
+            .collect();
+        let lexical_ids: Vec<String> = lexical_scores.keys().cloned().collect();
+        let active_model = self.active_model().await;
+        let lexical_results = self
+            .retrieve_embeddings(&lexical_ids)
+            .await?
+            .into_iter()
+            .filter(|entry| model_namespace::belongs_to(entry, active_model.as_deref()))
+            .filter(|entry| config.filter.matches(entry))
+            .filter_map(|entry| {
+                let score = *lexical_scores.get(&entry.id)?;
+                let similarity = SimilaritySearch::cosine_similarity(query_vector, &entry.vector).unwrap_or(0.0);
+                Some((SearchResult { entry, similarity }, score))
+            })
+            .collect();
+        
+        Ok(SimilaritySearch::reciprocal_rank_fusion(vector_results, lexical_results, k, config))
+    }
+    
+    /// Get statistics about the lexical index
+    pub async fn get_lexical_index_stats(&self) -> Bm25Stats {
+        self.lexical_index.read().await.stats()
+    }
+    
+    /// Write the lexical index snapshot to disk
+    pub async fn save_lexical_index(&self) -> VectorDbResult<()> {
+        let snapshot = {
+            let mut lexical_index = self.lexical_index.write().await;
+            let snapshot = lexical_index.snapshot()?;
+            self.pending_snapshot(SnapshotIndex::Lexical, self.lexical_index_path.clone(), snapshot)
+        };
+        self.write_index_snapshot(snapshot).await
+    }
+
+    // === Product Quantization ===
+    
+    /// Location of the product quantization codebook, next to the storage
+    /// 
+    /// Codebooks are trained by the optimization scheduler through the hook
+    /// returned by `codebook_retraining_hook`.
+    pub fn product_quantizer_path(&self) -> PathBuf {
+        Path::new(&self.config.storage_dir).join(PQ_CODEBOOK_FILE_NAME)
+    }
+    
+    /// Load the persisted product quantization codebook, if one was trained
+    pub fn load_product_quantizer(&self) -> Option<ProductQuantizer> {
+        Self::read_product_quantizer(&self.product_quantizer_path())
+    }
+    
+    /// Codebook retraining for the optimization scheduler
+    /// 
+    /// Trains on the ANN index vectors and re-encodes the stored codes
+    /// whenever the scheduler replaces the codebook.
+    pub fn codebook_retraining_hook(&self) -> CodebookRetrainingHook {
+        CodebookRetrainingHook {
+            source: self.quantized_vectors.clone(),
+            codebook_path: self.product_quantizer_path(),
+            config: ProductQuantizationConfig::default(),
+        }
+    }
+    
+    /// Compressed codes for the ANN index vectors
+    pub fn quantized_vectors(&self) -> Arc<QuantizedVectorStore> {
+        self.quantized_vectors.clone()
+    }
+    
+    fn read_product_quantizer(path: &Path) -> Option<ProductQuantizer> {
+        if !path.exists() {
+            return None;
+        }
+        
+        match ProductQuantizer::load_from_file(path) {
+            Ok(quantizer) => Some(quantizer),
+            Err(e) => {
+                eprintln!("⚠️ Failed to load product quantization codebook: {}", e);
+                None
+            }
+        }
+    }
+
+    // === Model Namespaces ===
+    
+    /// Get the model whose embeddings are searched
+    /// 
+    /// `None` only for a database that has never stored an embedding; the
+    /// first stored entry's model becomes the active one.
+    pub async fn active_model(&self) -> Option<String> {
+        self.active_model.read().await.clone()
+    }
+    
+    /// Get every entry of the active model namespace
+    /// 
+    /// This is the candidate set for exact (full scan) similarity search.
+    pub async fn active_namespace_entries(&self) -> VectorDbResult<Vec<EmbeddingEntry>> {
+        let active_model = self.active_model().await;
+        let all_ids = self.list_embedding_ids().await;
+        Ok(self
+            .retrieve_embeddings(&all_ids)
+            .await?
+            .into_iter()
+            .filter(|entry| model_namespace::belongs_to(entry, active_model.as_deref()))
+            .collect())
+    }
+    
+    /// List the model namespaces present in storage, active namespace first
+    pub async fn list_model_namespaces(&self) -> VectorDbResult<Vec<ModelNamespaceStats>> {
+        let active_model = self.active_model().await;
+        let all_ids = self.list_embedding_ids().await;
+        let entries = self.storage.retrieve_entries(&all_ids).await?;
+        Ok(model_namespace::summarize_namespaces(&entries, active_model.as_deref()))
+    }
+    
+    /// Make a model namespace the one that serves searches
+    /// 
+    /// The HNSW graph for the namespace is built on the blocking thread pool
+    /// and persisted, together with the namespace state file, while the
+    /// current namespace keeps serving queries. The index locks are taken
+    /// only to swap in the new graph; entries stored or deleted in between
+    /// trigger another catch-up round first, so no search ever sees a mix of
+    /// both models.
+    /// 
+    /// # Returns
+    /// 
+    /// Number of vectors in the new namespace's index
+    pub async fn activate_model_namespace(&self, model_name: &str) -> VectorDbResult<usize> {
+        if self.active_model().await.as_deref() == Some(model_name) {
+            return Ok(self.ann_index_len().await);
+        }
+        
+        let all_ids = self.storage.list_entry_ids().await;
+        let (entries, other_entries): (Vec<EmbeddingEntry>, Vec<EmbeddingEntry>) = self
+            .storage
+            .retrieve_entries(&all_ids)
+            .await?
+            .into_iter()
+            .partition(|entry| entry.metadata.model_name == model_name);
+        if entries.is_empty() {
+            return Err(VectorDbError::ModelNamespace {
+                message: format!("No embeddings stored for model '{}'", model_name),
+            });
+        }
+        let mut other_ids: std::collections::HashSet<String> = other_entries.into_iter().map(|entry| entry.id).collect();
+        
+        let config = self.ann_index.read().await.config().clone();
+        let mut rebuilt = Self::build_ann_index(config, entries).await?;
+        
+        loop {
+            // Catch up with writes that happened while the graph was being built
+            let stored_ids: std::collections::HashSet<String> = self.storage.list_entry_ids().await.into_iter().collect();
+            self.catch_up_namespace_index(&mut rebuilt, model_name, &stored_ids, &mut other_ids).await?;
+            
+            let snapshot = self.pending_snapshot(SnapshotIndex::Ann, self.ann_index_path(Some(model_name)), rebuilt.snapshot()?);
+            let updates = snapshot.updates;
+            self.write_snapshot(snapshot).await?;
+            rebuilt.finish_snapshot(updates, true);
+            self.save_namespace_state(model_name).await?;
+            
+            let mut active_model = self.active_model.write().await;
+            let mut ann_index = self.ann_index.write().await;
+            let current_ids = self.storage.list_entry_ids().await;
+            if current_ids.len() == stored_ids.len() && current_ids.iter().all(|id| stored_ids.contains(id)) {
+                let count = rebuilt.len();
+                *ann_index = rebuilt;
+                *active_model = Some(model_name.to_string());
+                
+                eprintln!("🔀 Active embedding model switched to '{}' ({} vectors)", model_name, count);
+                return Ok(count);
+            }
+        }
+    }
+    
+    /// Bring an index built for `model_name` up to date with storage
+    /// 
+    /// Entries deleted since the build are dropped and entries stored for the
+    /// model since then are added. IDs found to belong to other models are
+    /// remembered in `other_ids` so they are not read again.
+    async fn catch_up_namespace_index(
+        &self,
+        index: &mut HnswIndex,
+        model_name: &str,
+        stored_ids: &std::collections::HashSet<String>,
+        other_ids: &mut std::collections::HashSet<String>,
+    ) -> VectorDbResult<()> {
+        for stale_id in index.ids().into_iter().filter(|id| !stored_ids.contains(id)) {
+            index.remove(&stale_id);
+        }
+        let new_ids: Vec<String> = stored_ids
+            .iter()
+            .filter(|id| !index.contains(id) && !other_ids.contains(*id))
+            .cloned()
+            .collect();
+        for entry in self.storage.retrieve_entries(&new_ids).await? {
+            if entry.metadata.model_name != model_name {
+                other_ids.insert(entry.id);
+            } else if let Err(e) = index.insert(&entry.id, &entry.vector) {
+                eprintln!("⚠️ Entry {} not added to ANN index: {}", entry.id, e);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Persist the active model on the blocking thread pool
+    async fn save_namespace_state(&self, model_name: &str) -> VectorDbResult<()> {
+        let state = NamespaceState::new(Some(model_name.to_string()));
+        let path = self.namespace_state_path.clone();
+        tokio::task::spawn_blocking(move || state.save(&path))
+            .await
+            .map_err(|e| VectorDbError::Storage {
+                message: format!("Model namespace state writer failed: {}", e),
+            })?
+    }
+    
+    /// Delete every embedding of an inactive model namespace
+    /// 
+    /// # Returns
+    /// 
+    /// Number of embeddings deleted
+    pub async fn delete_model_namespace(&self, model_name: &str) -> VectorDbResult<usize> {
+        if self.active_model().await.as_deref() == Some(model_name) {
+            return Err(VectorDbError::ModelNamespace {
+                message: format!("Cannot delete the active model namespace '{}'", model_name),
+            });
+        }
+        
+        let mut deleted_count = 0;
+        for entry in self.find_embeddings_by_model(model_name).await? {
+            if self.delete_embedding(&entry.id).await? {
+                deleted_count += 1;
+            }
+        }
+        
+        let snapshot_path = self.ann_index_path(Some(model_name));
+        if snapshot_path.exists() {
+            if let Err(e) = std::fs::remove_file(&snapshot_path) {
+                eprintln!("⚠️ Failed to remove ANN index for '{}': {}", model_name, e);
+            }
+        }
+        
+        Ok(deleted_count)
+    }
+
     // === New Operations Interface Methods ===
     
     /// Get reference to core CRUD operations
@@ -557,6 +1357,35 @@ impl VectorDatabase {
         }
     }
     
+    /// Find embeddings by note tag using indexing system
+    /// 
+    /// This method provides fast lookup of the embeddings of notes carrying
+    /// a tag (from their frontmatter or an inline `#tag`) using the indexing
+    /// system if available.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `tag` - The tag to search for, with or without `#` (case-insensitive)
+    /// 
+    /// # Returns
+    /// 
+    /// Vector of embedding entries of notes carrying the tag
+    pub async fn find_embeddings_by_tag_indexed(&self, tag: &str) -> VectorDbResult<Vec<EmbeddingEntry>> {
+        if let Some(indexing) = &self.indexing_system {
+            let entry_ids = indexing.find_by_tag(tag).await;
+            self.retrieve_embeddings(&entry_ids).await
+        } else {
+            // Fallback to scanning all entries if indexing is not available
+            let tag = tag.trim_start_matches('#').to_lowercase();
+            let all_ids = self.list_embedding_ids().await;
+            let all_entries = self.retrieve_embeddings(&all_ids).await?;
+            Ok(all_entries
+                .into_iter()
+                .filter(|entry| entry.metadata.tags().contains(&tag))
+                .collect())
+        }
+    }
+    
     /// Find embeddings by timestamp range using indexing system
     /// 
     /// This method provides fast lookup of embeddings created within a
@@ -638,7 +1467,9 @@ impl VectorDatabase {
     /// 
     /// This creates a point-in-time backup that can be used for recovery.
     /// Backup creation is atomic and safe to run during normal operations.
+    /// Entries still in the write-ahead log are flushed into a segment first.
     pub async fn create_backup(&self) -> VectorDbResult<BackupResult> {
+        self.storage.flush().await?;
         self.file_ops.create_backup().await
     }
     
@@ -930,11 +1761,12 @@ impl VectorDatabase {
     /// Result indicating success or failure of initialization
     pub async fn enable_index_rebuilding(&mut self, config: RebuildingConfig) -> VectorDbResult<()> {
         let operations = VectorOperations::new(self.storage.clone(), self.config.clone());
-        let index_rebuilder = IndexRebuilder::new(
+        let mut index_rebuilder = IndexRebuilder::new(
             self.storage.clone(),
             operations,
             config,
         );
+        index_rebuilder.set_ann_index(self.ann_index.clone(), self.active_model.clone(), self.get_storage_path());
         
         self.index_rebuilder = Some(index_rebuilder);
         
@@ -1397,6 +2229,285 @@ impl VectorDatabase {
     
     // Private helper methods
     
+    async fn index_vectors(&self, entries: &[EmbeddingEntry]) {
+        let mut active_model = self.active_model.write().await;
+        if active_model.is_none() {
+            // The first stored model becomes the active namespace
+            if let Some(entry) = entries.first() {
+                let model_name = entry.metadata.model_name.clone();
+                if let Err(e) = NamespaceState::new(Some(model_name.clone())).save(&self.namespace_state_path) {
+                    eprintln!("⚠️ Failed to persist model namespace state: {}", e);
+                }
+                *active_model = Some(model_name);
+            }
+        }
+        
+        let mut ann_index = self.ann_index.write().await;
+        for entry in entries.iter().filter(|entry| model_namespace::belongs_to(entry, active_model.as_deref())) {
+            // Storage remains the source of truth; a vector the index cannot
+            // accept (e.g. an unexpected dimension) is only skipped
+            if let Err(e) = ann_index.insert(&entry.id, &entry.vector) {
+                eprintln!("⚠️ Entry {} not added to ANN index: {}", entry.id, e);
+            }
+        }
+        let snapshot = self.ann_snapshot_if_needed(&mut ann_index, active_model.as_deref());
+        drop(ann_index);
+        drop(active_model);
+        self.persist_snapshot(snapshot).await;
+    }
+    
+    /// Serialize the ANN index if enough updates have accumulated
+    /// 
+    /// Called with the index locked; the returned snapshot is written with
+    /// [`Self::persist_snapshot`] once the lock is released so searches are
+    /// not blocked on disk I/O.
+    fn ann_snapshot_if_needed(&self, ann_index: &mut HnswIndex, active_model: Option<&str>) -> Option<PendingSnapshot> {
+        if !ann_index.needs_persist() {
+            return None;
+        }
+        match ann_index.snapshot() {
+            Ok(snapshot) => Some(self.pending_snapshot(SnapshotIndex::Ann, self.ann_index_path(active_model), snapshot)),
+            Err(e) => {
+                eprintln!("⚠️ Failed to persist ANN index: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// Stamp a serialized snapshot with the next generation
+    /// 
+    /// Must be called while the snapshotted index is still locked so
+    /// generations follow the order of the index contents.
+    fn pending_snapshot(&self, index: SnapshotIndex, path: PathBuf, (data, updates): (Vec<u8>, usize)) -> PendingSnapshot {
+        PendingSnapshot {
+            index,
+            path,
+            data,
+            updates,
+            generation: self.snapshot_generation.fetch_add(1, Ordering::SeqCst),
+        }
+    }
+    
+    /// Write an automatic snapshot, logging failures
+    async fn persist_snapshot(&self, snapshot: Option<PendingSnapshot>) {
+        if let Some(snapshot) = snapshot {
+            let label = snapshot.index.label();
+            if let Err(e) = self.write_index_snapshot(snapshot).await {
+                eprintln!("⚠️ Failed to persist {}: {}", label, e);
+            }
+        }
+    }
+    
+    /// Write a snapshot of the shared ANN or lexical index and report the
+    /// outcome back to it, so a failed write is retried on a later update
+    async fn write_index_snapshot(&self, snapshot: PendingSnapshot) -> VectorDbResult<()> {
+        let (index, updates) = (snapshot.index, snapshot.updates);
+        let result = self.write_snapshot(snapshot).await;
+        match index {
+            SnapshotIndex::Ann => self.ann_index.write().await.finish_snapshot(updates, result.is_ok()),
+            SnapshotIndex::Lexical => self.lexical_index.write().await.finish_snapshot(updates, result.is_ok()),
+        }
+        result
+    }
+    
+    /// Write a snapshot taken under an index lock on the blocking thread pool
+    /// 
+    /// Writes to the same file are serialized, and a snapshot older than the
+    /// one already on disk is dropped.
+    async fn write_snapshot(&self, snapshot: PendingSnapshot) -> VectorDbResult<()> {
+        let mut written = self.snapshot_writes.lock().await;
+        if written.get(&snapshot.path).is_some_and(|&generation| generation > snapshot.generation) {
+            return Ok(());
+        }
+        
+        let PendingSnapshot { index, path, data, generation, .. } = snapshot;
+        let (write, target) = (index.writer(), path.clone());
+        tokio::task::spawn_blocking(move || write(&target, &data))
+            .await
+            .map_err(|e| VectorDbError::Storage {
+                message: format!("{} snapshot writer failed: {}", index.label(), e),
+            })??;
+        written.insert(path, generation);
+        Ok(())
+    }
+    
+    fn ann_index_path(&self, model_name: Option<&str>) -> PathBuf {
+        model_namespace::ann_index_path(Path::new(&self.config.storage_dir), model_name)
+    }
+    
+    /// Read the active model from the namespace state file
+    /// 
+    /// Databases written before namespaces existed adopt the model with the
+    /// most stored entries.
+    async fn load_active_model(storage: &VectorStorage, state_path: &Path) -> Option<String> {
+        match NamespaceState::load(state_path) {
+            Ok(Some(state)) => return state.active_model,
+            Ok(None) => {}
+            Err(e) => eprintln!("⚠️ Discarding unreadable model namespace state: {}", e),
+        }
+        
+        let all_ids = storage.list_entry_ids().await;
+        if all_ids.is_empty() {
+            return None;
+        }
+        let entries = match storage.retrieve_entries(&all_ids).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("⚠️ Failed to load entries for model namespace detection: {}", e);
+                return None;
+            }
+        };
+        
+        let active_model = model_namespace::dominant_model(&entries)?;
+        if let Err(e) = NamespaceState::new(Some(active_model.clone())).save(state_path) {
+            eprintln!("⚠️ Failed to persist model namespace state: {}", e);
+        }
+        Some(active_model)
+    }
+    
+    /// Load the ANN index snapshot of the active namespace, dropping entries
+    /// storage no longer knows about and inserting entries the snapshot is missing
+    /// 
+    /// A snapshot written before namespaces existed is taken over by the
+    /// active namespace.
+    async fn load_ann_index(storage: &VectorStorage, storage_dir: &Path, active_model: Option<&str>) -> HnswIndex {
+        let path = model_namespace::ann_index_path(storage_dir, active_model);
+        let legacy_path = storage_dir.join(HNSW_INDEX_FILE_NAME);
+        let snapshot_path = if !path.exists() && legacy_path.exists() { &legacy_path } else { &path };
+        
+        let mut ann_index = if snapshot_path.exists() {
+            HnswIndex::load_from_file(snapshot_path).unwrap_or_else(|e| {
+                eprintln!("⚠️ Discarding unreadable ANN index: {}", e);
+                HnswIndex::new(HnswConfig::default())
+            })
+        } else {
+            HnswIndex::new(HnswConfig::default())
+        };
+        
+        let stored_ids: std::collections::HashSet<String> = storage.list_entry_ids().await.into_iter().collect();
+        for stale_id in ann_index.ids().into_iter().filter(|id| !stored_ids.contains(id)) {
+            ann_index.remove(&stale_id);
+        }
+        
+        let missing_ids: Vec<String> = stored_ids
+            .into_iter()
+            .filter(|id| !ann_index.contains(id))
+            .collect();
+        if !missing_ids.is_empty() {
+            match storage.retrieve_entries(&missing_ids).await {
+                Ok(entries) => {
+                    for entry in entries.iter().filter(|entry| model_namespace::belongs_to(entry, active_model)) {
+                        if let Err(e) = ann_index.insert(&entry.id, &entry.vector) {
+                            eprintln!("⚠️ Entry {} not added to ANN index: {}", entry.id, e);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("⚠️ Failed to load entries for ANN index: {}", e),
+            }
+        }
+        
+        if ann_index.is_dirty() || snapshot_path != &path {
+            if let Err(e) = ann_index.save_to_file(&path) {
+                eprintln!("⚠️ Failed to persist ANN index: {}", e);
+            } else if snapshot_path != &path {
+                let _ = std::fs::remove_file(snapshot_path);
+            }
+        }
+        
+        ann_index
+    }
+    
+    /// Recover the full text of chunks stored without it
+    /// 
+    /// Storage keeps only a preview of each chunk, so the text is cut out of
+    /// its note on the blocking thread pool. A note that changed since the
+    /// chunk was indexed falls back to the preview.
+    async fn load_chunk_texts(entries: &[EmbeddingEntry]) -> Vec<String> {
+        let owned = entries.to_vec();
+        tokio::task::spawn_blocking(move || {
+            let mut notes = HashMap::new();
+            owned.iter().map(|entry| load_chunk_text(entry, &mut notes)).collect()
+        })
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("⚠️ Failed to load chunk texts: {}", e);
+            entries.iter().map(|entry| entry.metadata.content_preview.clone()).collect()
+        })
+    }
+    
+    async fn index_texts(&self, documents: &[(&str, &str)]) {
+        let snapshot = {
+            let mut lexical_index = self.lexical_index.write().await;
+            for (entry_id, text) in documents {
+                lexical_index.insert(entry_id, text);
+            }
+            self.lexical_snapshot_if_needed(&mut lexical_index)
+        };
+        self.persist_snapshot(snapshot).await;
+    }
+    
+    /// Serialize the lexical index if enough updates have accumulated
+    /// 
+    /// Like [`Self::ann_snapshot_if_needed`], the file write happens after the
+    /// lock is released.
+    fn lexical_snapshot_if_needed(&self, lexical_index: &mut Bm25Index) -> Option<PendingSnapshot> {
+        if !lexical_index.needs_persist() {
+            return None;
+        }
+        match lexical_index.snapshot() {
+            Ok(snapshot) => Some(self.pending_snapshot(SnapshotIndex::Lexical, self.lexical_index_path.clone(), snapshot)),
+            Err(e) => {
+                eprintln!("⚠️ Failed to persist lexical index: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// Load the lexical index snapshot, dropping entries storage no longer
+    /// knows about
+    /// 
+    /// Entries missing from the snapshot are indexed by their chunk text, cut
+    /// out of the note.
+    async fn load_lexical_index(storage: &VectorStorage, path: &Path) -> Bm25Index {
+        let mut lexical_index = if path.exists() {
+            Bm25Index::load_from_file(path).unwrap_or_else(|e| {
+                eprintln!("⚠️ Discarding unreadable lexical index: {}", e);
+                Bm25Index::new(Bm25Config::default())
+            })
+        } else {
+            Bm25Index::new(Bm25Config::default())
+        };
+        
+        let stored_ids: std::collections::HashSet<String> = storage.list_entry_ids().await.into_iter().collect();
+        for stale_id in lexical_index.ids().into_iter().filter(|id| !stored_ids.contains(id)) {
+            lexical_index.remove(&stale_id);
+        }
+        
+        let missing_ids: Vec<String> = stored_ids
+            .into_iter()
+            .filter(|id| !lexical_index.contains(id))
+            .collect();
+        if !missing_ids.is_empty() {
+            match storage.retrieve_entries(&missing_ids).await {
+                Ok(entries) => {
+                    let texts = Self::load_chunk_texts(&entries).await;
+                    for (entry, text) in entries.iter().zip(&texts) {
+                        lexical_index.insert(&entry.id, text);
+                    }
+                }
+                Err(e) => eprintln!("⚠️ Failed to load entries for lexical index: {}", e),
+            }
+        }
+        
+        if lexical_index.is_dirty() {
+            if let Err(e) = lexical_index.save_to_file(path) {
+                eprintln!("⚠️ Failed to persist lexical index: {}", e);
+            }
+        }
+        
+        lexical_index
+    }
+    
     /// Update the in-memory cache with an entry
     async fn update_cache(&self, entry_id: String, entry: EmbeddingEntry) {
         let mut cache = self.cache.write().await;
@@ -0,0 +1,219 @@
+//! Embedding Model Namespaces
+//!
+//! Vectors produced by different embedding models live in unrelated spaces and
+//! usually have different dimensions, so comparing them yields either a
+//! dimension mismatch or meaningless scores. Entries are therefore grouped into
+//! one namespace per `EmbeddingMetadata::model_name`, and exactly one namespace
+//! is *active*: it backs the HNSW index and every search.
+//!
+//! ## Switching Models
+//!
+//! Each namespace keeps its own HNSW snapshot (`hnsw_index.{model}.bin`), so
+//! the graph for a new model can be built and written while the previous one
+//! keeps serving queries. The active model is recorded in
+//! `model_namespace.json`; rewriting that file is the commit point of a switch,
+//! and a crash before it leaves the previous namespace active.
+//!
+//! Databases written before namespaces existed have no state file. The model
+//! with the most entries is adopted as the active one the first time they are
+//! opened.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::vector_db::hnsw::HNSW_INDEX_FILE_NAME;
+use crate::vector_db::types::{EmbeddingEntry, VectorDbError, VectorDbResult};
+
+/// File name of the persisted namespace state inside the storage directory
+pub const NAMESPACE_STATE_FILE_NAME: &str = "model_namespace.json";
+
+/// Persisted namespace state
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NamespaceState {
+    /// Model whose embeddings are searched (None until the first entry is stored)
+    pub active_model: Option<String>,
+    /// Timestamp when the active model was last changed
+    pub activated_at: u64,
+}
+
+impl NamespaceState {
+    /// Create a state with the given active model, stamped with the current time
+    pub fn new(active_model: Option<String>) -> Self {
+        let activated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self { active_model, activated_at }
+    }
+
+    /// Load the state file, returning `None` if it does not exist
+    pub fn load(path: &Path) -> VectorDbResult<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to read model namespace state: {}", e),
+        })?;
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+
+    /// Write the state file atomically
+    pub fn save(&self, path: &Path) -> VectorDbResult<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, &data).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to write model namespace state: {}", e),
+        })?;
+        fs::rename(&temp_path, path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to finalize model namespace state: {}", e),
+        })?;
+        Ok(())
+    }
+}
+
+/// Summary of the entries stored for one model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelNamespaceStats {
+    /// Embedding model name
+    pub model_name: String,
+    /// Number of stored embeddings
+    pub entry_count: usize,
+    /// Number of distinct source files
+    pub file_count: usize,
+    /// Vector dimension (0 if entries disagree)
+    pub dimension: usize,
+    /// Whether searches use this namespace
+    pub is_active: bool,
+}
+
+/// Check whether an entry belongs to a namespace (`None` matches every entry)
+pub fn belongs_to(entry: &EmbeddingEntry, model_name: Option<&str>) -> bool {
+    match model_name {
+        Some(model_name) => entry.metadata.model_name == model_name,
+        None => true,
+    }
+}
+
+/// Path of the HNSW snapshot for a namespace
+///
+/// Without an active model the pre-namespace `hnsw_index.bin` is used.
+pub fn ann_index_path(storage_dir: &Path, model_name: Option<&str>) -> PathBuf {
+    match model_name {
+        Some(model_name) => {
+            let key: String = model_name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+                .collect();
+            storage_dir.join(format!("hnsw_index.{}.bin", key))
+        }
+        None => storage_dir.join(HNSW_INDEX_FILE_NAME),
+    }
+}
+
+/// Pick the model with the most entries (ties resolved by name)
+pub fn dominant_model(entries: &[EmbeddingEntry]) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in entries {
+        *counts.entry(entry.metadata.model_name.as_str()).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(model_name, _)| model_name.to_string())
+}
+
+/// Group entries by model, active namespace first and the rest by name
+pub fn summarize_namespaces(entries: &[EmbeddingEntry], active_model: Option<&str>) -> Vec<ModelNamespaceStats> {
+    let mut grouped: HashMap<&str, Vec<&EmbeddingEntry>> = HashMap::new();
+    for entry in entries {
+        grouped.entry(entry.metadata.model_name.as_str()).or_default().push(entry);
+    }
+
+    let mut namespaces: Vec<ModelNamespaceStats> = grouped
+        .into_iter()
+        .map(|(model_name, entries)| {
+            let files: HashSet<&str> = entries.iter().map(|e| e.metadata.file_path.as_str()).collect();
+            let dimensions: HashSet<usize> = entries.iter().map(|e| e.vector.len()).collect();
+            ModelNamespaceStats {
+                model_name: model_name.to_string(),
+                entry_count: entries.len(),
+                file_count: files.len(),
+                dimension: if dimensions.len() == 1 { dimensions.into_iter().next().unwrap_or(0) } else { 0 },
+                is_active: Some(model_name) == active_model,
+            }
+        })
+        .collect();
+
+    namespaces.sort_by(|a, b| b.is_active.cmp(&a.is_active).then_with(|| a.model_name.cmp(&b.model_name)));
+    namespaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_entry(file: &str, chunk: &str, model: &str, dimension: usize) -> EmbeddingEntry {
+        EmbeddingEntry::new(
+            vec![0.5; dimension],
+            file.to_string(),
+            chunk.to_string(),
+            &format!("{} {}", file, chunk),
+            model.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(NAMESPACE_STATE_FILE_NAME);
+
+        assert_eq!(NamespaceState::load(&path).unwrap(), None);
+
+        let state = NamespaceState::new(Some("nomic-embed-text".to_string()));
+        state.save(&path).unwrap();
+        assert_eq!(NamespaceState::load(&path).unwrap(), Some(state));
+    }
+
+    #[test]
+    fn test_ann_index_path_per_model() {
+        let dir = Path::new("/vault/.ainote/vectors");
+
+        assert_eq!(ann_index_path(dir, None), dir.join(HNSW_INDEX_FILE_NAME));
+        assert_eq!(
+            ann_index_path(dir, Some("mxbai-embed-large:latest")),
+            dir.join("hnsw_index.mxbai-embed-large_latest.bin")
+        );
+        assert_ne!(ann_index_path(dir, Some("model-a")), ann_index_path(dir, Some("model-b")));
+    }
+
+    #[test]
+    fn test_dominant_model_and_summary() {
+        let entries = vec![
+            create_entry("/a.md", "chunk_0", "small", 4),
+            create_entry("/a.md", "chunk_1", "small", 4),
+            create_entry("/b.md", "chunk_0", "small", 4),
+            create_entry("/a.md", "chunk_0", "large", 8),
+        ];
+
+        assert_eq!(dominant_model(&entries), Some("small".to_string()));
+        assert_eq!(dominant_model(&[]), None);
+
+        let namespaces = summarize_namespaces(&entries, Some("large"));
+        assert_eq!(namespaces.len(), 2);
+        assert_eq!(namespaces[0].model_name, "large");
+        assert!(namespaces[0].is_active);
+        assert_eq!(namespaces[0].dimension, 8);
+        assert_eq!(namespaces[1].entry_count, 3);
+        assert_eq!(namespaces[1].file_count, 2);
+        assert!(!namespaces[1].is_active);
+
+        assert!(belongs_to(&entries[0], Some("small")));
+        assert!(!belongs_to(&entries[0], Some("large")));
+        assert!(belongs_to(&entries[0], None));
+    }
+}
@@ -9,6 +9,8 @@
 //! - **Automatic Scheduling**: Time-based, usage-based, and size-based triggers
 //! - **Background Execution**: Non-blocking optimization pipeline execution
 //! - **Pipeline Orchestration**: Coordinated deduplication → compression → cleanup
+//! - **Codebook Retraining**: Product quantization codebooks retrained on the current
+//!   embeddings when a training source is registered
 //! - **Intelligent Triggers**: Adaptive scheduling based on system conditions
 //! - **Performance Monitoring Integration**: Leverages existing monitoring system
 //! - **Configuration Management**: Flexible optimization parameters
@@ -23,6 +25,7 @@
 //! - **Performance-based**: When performance degrades below thresholds
 
 use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::sync::{Arc, atomic::{AtomicU64, AtomicBool, Ordering}};
 use std::time::{Duration, SystemTime};
 use tokio::sync::{RwLock, Mutex, mpsc, oneshot};
@@ -30,8 +33,10 @@ use tokio::time::{interval, sleep, timeout, Instant};
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc, Timelike, Datelike};
 use thiserror::Error;
+use async_trait::async_trait;
 
 use crate::vector_db::types::VectorDbError;
+use crate::vector_db::compression::{CompressionError, ProductQuantizationConfig, ProductQuantizer};
 use crate::vector_db::deduplication::DeduplicationConfig;
 use crate::vector_db::performance_monitor::{IndexPerformanceMonitor, OperationType, OperationStatus};
 
@@ -115,6 +120,12 @@ pub struct OptimizationSchedulerConfig {
     pub enable_compression: bool,
     /// Enable maintenance cleanup in optimization pipeline
     pub enable_maintenance_cleanup: bool,
+    /// Retrain the product quantization codebook during compression (needs a registered hook)
+    #[serde(default = "default_enable_codebook_retraining")]
+    pub enable_codebook_retraining: bool,
+    /// Relative reduction in quantization error required to replace the codebook (0.0-1.0)
+    #[serde(default = "default_codebook_min_improvement")]
+    pub codebook_min_improvement: f64,
     /// Maximum optimization duration (minutes)
     pub max_optimization_duration_minutes: u64,
     /// Number of parallel optimization workers
@@ -139,6 +150,14 @@ pub struct OptimizationSchedulerConfig {
     pub log_retention_days: u32,
 }
 
+fn default_enable_codebook_retraining() -> bool {
+    true
+}
+
+fn default_codebook_min_improvement() -> f64 {
+    0.05
+}
+
 impl Default for OptimizationSchedulerConfig {
     fn default() -> Self {
         Self {
@@ -168,6 +187,8 @@ impl Default for OptimizationSchedulerConfig {
             enable_deduplication: true,
             enable_compression: true,
             enable_maintenance_cleanup: true,
+            enable_codebook_retraining: default_enable_codebook_retraining(),
+            codebook_min_improvement: default_codebook_min_improvement(), // Replace on 5% lower error
             max_optimization_duration_minutes: 60,
             parallel_workers: 2,
             
@@ -272,6 +293,9 @@ pub struct OptimizationPipelineResult {
     pub deduplication_result: Option<DeduplicationSummary>,
     /// Compression results (if enabled)
     pub compression_result: Option<CompressionResult>,
+    /// Codebook retraining results (if a retraining hook is registered)
+    #[serde(default)]
+    pub codebook_retraining_result: Option<CodebookRetrainingResult>,
     /// Maintenance cleanup results (if enabled)
     pub maintenance_result: Option<MaintenanceResult>,
     
@@ -319,6 +343,54 @@ pub struct CompressionResult {
     pub compression_time_ms: f64,
 }
 
+/// Product quantization codebook retraining results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodebookRetrainingResult {
+    /// Codebook in use after this run
+    pub codebook_id: String,
+    /// Codebook in use before this run, if any
+    pub previous_codebook_id: Option<String>,
+    /// Number of vectors the codebooks were evaluated on
+    pub vectors_sampled: usize,
+    /// Mean squared reconstruction error of the new codebook on the sample
+    pub quantization_error: f32,
+    /// Error of the previous codebook on the same sample (if compatible)
+    pub previous_quantization_error: Option<f32>,
+    /// Whether the new codebook replaced the previous one
+    pub replaced: bool,
+    /// Time taken for training in milliseconds
+    pub training_time_ms: f64,
+}
+
+/// Supplies the vectors product quantization codebooks are trained on
+#[async_trait]
+pub trait CodebookTrainingSource: Send + Sync {
+    /// Up to `max_samples` stored embedding vectors
+    async fn training_vectors(&self, max_samples: usize) -> Vec<Vec<f32>>;
+    
+    /// Called after a retrained codebook replaced the persisted one
+    /// 
+    /// Sources holding codes encoded with the previous codebook re-encode
+    /// them here.
+    async fn codebook_replaced(&self, _quantizer: &ProductQuantizer) {}
+}
+
+/// Codebook retraining registered with the scheduler
+/// 
+/// The scheduler retrains the codebook at `codebook_path` on vectors from
+/// `source` during the compression stage, and replaces it only when the new
+/// codebook quantizes the current embeddings noticeably better. The source is
+/// then told about the new codebook so it can re-encode its codes.
+#[derive(Clone)]
+pub struct CodebookRetrainingHook {
+    /// Source of training vectors
+    pub source: Arc<dyn CodebookTrainingSource>,
+    /// Where the codebook is persisted (usually next to the storage)
+    pub codebook_path: PathBuf,
+    /// Training parameters
+    pub config: ProductQuantizationConfig,
+}
+
 /// Maintenance cleanup results
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MaintenanceResult {
@@ -443,6 +515,8 @@ pub struct AutomaticOptimizationScheduler {
     optimization_sender: mpsc::UnboundedSender<OptimizationRequest>,
     /// Optimization result channel
     result_receiver: Arc<Mutex<Option<mpsc::UnboundedReceiver<OptimizationPipelineResult>>>>,
+    /// Product quantization codebook retraining (if registered)
+    codebook_retraining: Arc<RwLock<Option<CodebookRetrainingHook>>>,
 }
 
 /// Internal optimization request structure
@@ -474,6 +548,7 @@ impl AutomaticOptimizationScheduler {
             scheduler_task: None,
             optimization_sender,
             result_receiver: Arc::new(Mutex::new(Some(result_receiver))),
+            codebook_retraining: Arc::new(RwLock::new(None)),
         }
     }
     
@@ -594,6 +669,21 @@ impl AutomaticOptimizationScheduler {
         &self.config
     }
     
+    /// Register (or remove) product quantization codebook retraining
+    pub async fn set_codebook_retraining_hook(&self, hook: Option<CodebookRetrainingHook>) {
+        *self.codebook_retraining.write().await = hook;
+    }
+    
+    /// Retrain the product quantization codebook now, outside the pipeline
+    pub async fn retrain_codebook(&self) -> OptimizationResult<CodebookRetrainingResult> {
+        let hook = self.codebook_retraining.read().await.clone()
+            .ok_or_else(|| OptimizationSchedulerError::Configuration {
+                message: "No codebook retraining hook registered".to_string(),
+            })?;
+        
+        Self::run_codebook_retraining_stage(&hook, self.config.codebook_min_improvement).await
+    }
+    
     // Private implementation methods
     
     /// Start the main scheduler background task
@@ -605,6 +695,7 @@ impl AutomaticOptimizationScheduler {
         let current_optimization = Arc::clone(&self.current_optimization);
         let optimization_history = Arc::clone(&self.optimization_history);
         let performance_monitor = Arc::clone(&self.performance_monitor);
+        let codebook_retraining = Arc::clone(&self.codebook_retraining);
         
         // Extract receiver for optimization processing
         let mut optimization_receiver = {
@@ -639,6 +730,7 @@ impl AutomaticOptimizationScheduler {
                         &usage_tracker,
                         &current_optimization,
                         &performance_monitor,
+                        &codebook_retraining,
                     ).await;
                     
                     match optimization_result {
@@ -678,6 +770,7 @@ impl AutomaticOptimizationScheduler {
                                 status: OptimizationStatus::Failed,
                                 deduplication_result: None,
                                 compression_result: None,
+                                codebook_retraining_result: None,
                                 maintenance_result: None,
                                 resource_usage: OptimizationResourceUsage {
                                     peak_cpu_usage_percent: 0.0,
@@ -889,6 +982,7 @@ impl AutomaticOptimizationScheduler {
         _usage_tracker: &UsageTracker,
         current_optimization: &Arc<RwLock<Option<OptimizationPipelineResult>>>,
         performance_monitor: &IndexPerformanceMonitor,
+        codebook_retraining: &Arc<RwLock<Option<CodebookRetrainingHook>>>,
     ) -> OptimizationResult<OptimizationPipelineResult> {
         let optimization_id = format!("opt_{}", Utc::now().timestamp_millis());
         let start_time = Utc::now();
@@ -907,6 +1001,7 @@ impl AutomaticOptimizationScheduler {
             status: OptimizationStatus::Running,
             deduplication_result: None,
             compression_result: None,
+            codebook_retraining_result: None,
             maintenance_result: None,
             resource_usage: OptimizationResourceUsage {
                 peak_cpu_usage_percent: 0.0,
@@ -983,6 +1078,26 @@ impl AutomaticOptimizationScheduler {
                     eprintln!("⚠️ {}", warning);
                 }
             }
+            
+            let hook = codebook_retraining.read().await.clone();
+            if let Some(hook) = hook.filter(|_| config.enable_codebook_retraining) {
+                eprintln!("🧮 Retraining product quantization codebook...");
+                
+                match Self::run_codebook_retraining_stage(&hook, config.codebook_min_improvement).await {
+                    Ok(retraining_result) => {
+                        eprintln!("✅ Codebook retraining completed: {} (error {:.5}, replaced: {})",
+                                  retraining_result.codebook_id,
+                                  retraining_result.quantization_error,
+                                  retraining_result.replaced);
+                        result.codebook_retraining_result = Some(retraining_result);
+                    },
+                    Err(e) => {
+                        let warning = format!("Codebook retraining failed: {}", e);
+                        warnings.push(warning.clone());
+                        eprintln!("⚠️ {}", warning);
+                    }
+                }
+            }
         }
         
         // Stage 3: Maintenance cleanup (if enabled)
@@ -1092,6 +1207,88 @@ impl AutomaticOptimizationScheduler {
         Ok(mock_result)
     }
     
+    /// Retrain the product quantization codebook on the current embeddings
+    /// 
+    /// The previous codebook is evaluated on the same sample and kept unless
+    /// the new one lowers the quantization error by `min_improvement`, so
+    /// stored codes are only invalidated when retraining pays off. A previous
+    /// codebook of another dimension or shape is always replaced.
+    async fn run_codebook_retraining_stage(
+        hook: &CodebookRetrainingHook,
+        min_improvement: f64,
+    ) -> OptimizationResult<CodebookRetrainingResult> {
+        let vectors = hook.source.training_vectors(hook.config.max_training_samples).await;
+        if vectors.is_empty() {
+            return Err(OptimizationSchedulerError::PipelineFailed {
+                message: "No embeddings available for codebook training".to_string(),
+            });
+        }
+        
+        let previous = if hook.codebook_path.exists() {
+            match ProductQuantizer::load_from_file(&hook.codebook_path) {
+                Ok(previous) => Some(previous),
+                Err(e) => {
+                    eprintln!("⚠️ Ignoring unreadable codebook {}: {}", hook.codebook_path.display(), e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
+        // Training is CPU bound; keep it off the async workers
+        let config = hook.config.clone();
+        let training_start = Instant::now();
+        let training = tokio::task::spawn_blocking(move || {
+            let quantizer = ProductQuantizer::train(&vectors, &config)?;
+            let quantization_error = quantizer.mean_squared_error(&vectors)?;
+            let previous_quantization_error = previous
+                .as_ref()
+                .filter(|previous| {
+                    previous.dimension() == quantizer.dimension()
+                        && previous.num_subspaces() == quantizer.num_subspaces()
+                })
+                .map(|previous| previous.mean_squared_error(&vectors))
+                .transpose()?;
+            let previous_codebook_id = previous.map(|previous| previous.id().to_string());
+            Ok::<_, CompressionError>((quantizer, previous_codebook_id, quantization_error, previous_quantization_error))
+        })
+        .await
+        .map_err(|e| OptimizationSchedulerError::BackgroundTask {
+            message: format!("Codebook training task failed: {}", e),
+        })?
+        .map_err(|e| OptimizationSchedulerError::PipelineFailed {
+            message: e.to_string(),
+        })?;
+        let (quantizer, previous_codebook_id, quantization_error, previous_quantization_error) = training;
+        
+        let replaced = match previous_quantization_error {
+            Some(previous_error) => (quantization_error as f64) < previous_error as f64 * (1.0 - min_improvement),
+            None => true,
+        };
+        if replaced {
+            quantizer.save_to_file(&hook.codebook_path)
+                .map_err(|e| OptimizationSchedulerError::PipelineFailed {
+                    message: format!("Failed to save codebook: {}", e),
+                })?;
+            hook.source.codebook_replaced(&quantizer).await;
+        }
+        
+        Ok(CodebookRetrainingResult {
+            codebook_id: if replaced {
+                quantizer.id().to_string()
+            } else {
+                previous_codebook_id.clone().unwrap_or_else(|| quantizer.id().to_string())
+            },
+            previous_codebook_id,
+            vectors_sampled: quantizer.trained_on(),
+            quantization_error,
+            previous_quantization_error,
+            replaced,
+            training_time_ms: training_start.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+    
     /// Run the maintenance cleanup stage of optimization
     async fn run_maintenance_stage(_optimization_id: &str) -> OptimizationResult<MaintenanceResult> {
         // In a real implementation, this would:
@@ -1175,6 +1372,7 @@ mod tests {
             status: OptimizationStatus::Scheduled,
             deduplication_result: None,
             compression_result: None,
+            codebook_retraining_result: None,
             maintenance_result: None,
             resource_usage: OptimizationResourceUsage {
                 peak_cpu_usage_percent: 0.0,
@@ -1237,4 +1435,56 @@ mod tests {
         assert!(!config.enable_automatic_optimization);
         assert_eq!(config.optimization_interval_hours, 12);
     }
-}
\ No newline at end of file
+    
+    struct FixedVectors {
+        vectors: Vec<Vec<f32>>,
+        replaced_with: Mutex<Vec<String>>,
+    }
+    
+    #[async_trait]
+    impl CodebookTrainingSource for FixedVectors {
+        async fn training_vectors(&self, max_samples: usize) -> Vec<Vec<f32>> {
+            self.vectors.iter().take(max_samples).cloned().collect()
+        }
+        
+        async fn codebook_replaced(&self, quantizer: &ProductQuantizer) {
+            self.replaced_with.lock().await.push(quantizer.id().to_string());
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_codebook_retraining_hook() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let codebook_path = temp_dir.path().join("pq_codebook.bin");
+        let vectors: Vec<Vec<f32>> = (0..200)
+            .map(|i| (0..16).map(|d| ((i * 7 + d * 3) % 11) as f32 / 11.0 - 0.5).collect())
+            .collect();
+        
+        let monitor = Arc::new(IndexPerformanceMonitor::new(MonitoringConfig::default()));
+        let scheduler = AutomaticOptimizationScheduler::new(OptimizationSchedulerConfig::default(), monitor);
+        assert!(scheduler.retrain_codebook().await.is_err());
+        
+        let source = Arc::new(FixedVectors { vectors, replaced_with: Mutex::new(Vec::new()) });
+        scheduler.set_codebook_retraining_hook(Some(CodebookRetrainingHook {
+            source: source.clone(),
+            codebook_path: codebook_path.clone(),
+            config: ProductQuantizationConfig { num_subspaces: 4, num_centroids: 16, ..Default::default() },
+        })).await;
+        
+        // First run has nothing to compare against and writes the codebook
+        let first = scheduler.retrain_codebook().await.unwrap();
+        assert!(first.replaced);
+        assert!(first.previous_codebook_id.is_none());
+        assert_eq!(first.vectors_sampled, 200);
+        let saved = ProductQuantizer::load_from_file(&codebook_path).unwrap();
+        assert_eq!(saved.id(), first.codebook_id);
+        assert_eq!(*source.replaced_with.lock().await, vec![first.codebook_id.clone()]);
+        
+        // Same vectors and seed: no improvement, the stored codebook stays
+        let second = scheduler.retrain_codebook().await.unwrap();
+        assert!(!second.replaced);
+        assert_eq!(second.previous_codebook_id.as_deref(), Some(first.codebook_id.as_str()));
+        assert_eq!(ProductQuantizer::load_from_file(&codebook_path).unwrap().id(), first.codebook_id);
+        assert_eq!(source.replaced_with.lock().await.len(), 1);
+    }
+}
@@ -20,7 +20,7 @@ use flate2::write::GzEncoder;
 use flate2::Compression;
 
 use crate::vector_db::types::{EmbeddingEntry, EmbeddingMetadata};
-use crate::vector_db::compression::VectorCompressor;
+use crate::vector_db::compression::{QuantizedVectorRef, VectorCompressor};
 
 /// Errors that can occur during optimized storage operations
 #[derive(Error, Debug)]
@@ -223,6 +223,22 @@ pub enum CompactVector {
     },
 }
 
+impl CompactVector {
+    /// Borrow raw or compressed values for in-place scoring
+    /// 
+    /// Compressed data holds raw affine codes (`bits` wide, little-endian).
+    /// References and shared vectors live in the engine's pools and return `None`.
+    pub fn quantized_view(&self) -> Option<QuantizedVectorRef<'_>> {
+        match self {
+            CompactVector::Raw(vector) => Some(QuantizedVectorRef::Float32(vector)),
+            CompactVector::Compressed { data, dimension, params } => {
+                QuantizedVectorRef::affine(data, *dimension, params.bits, params.min_value, params.scale)
+            }
+            CompactVector::Reference { .. } | CompactVector::Shared { .. } => None,
+        }
+    }
+}
+
 /// Compact compression parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactCompressionParams {
@@ -633,14 +649,18 @@ impl OptimizedStorageEngine {
     
     fn decompress_vector(
         &self,
-        _data: &[u8],
-        _dimension: usize,
-        _params: &CompactCompressionParams,
+        data: &[u8],
+        dimension: usize,
+        params: &CompactCompressionParams,
     ) -> OptimizedStorageResult<Vec<f32>> {
-        // Placeholder - would implement actual decompression
-        Err(OptimizedStorageError::InvalidFormat {
-            message: "Vector decompression not implemented".to_string(),
-        })
+        QuantizedVectorRef::affine(data, dimension, params.bits, params.min_value, params.scale)
+            .map(|view| view.to_vec())
+            .ok_or_else(|| OptimizedStorageError::InvalidFormat {
+                message: format!(
+                    "Compressed vector has {} bytes, expected {} dimensions of {} bits",
+                    data.len(), dimension, params.bits
+                ),
+            })
     }
 }
 
@@ -755,4 +775,27 @@ mod tests {
         assert_eq!(entries[0].id, deserialized[0].id);
         assert_eq!(entries[1].id, deserialized[1].id);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_compressed_compact_vector_view() {
+        let engine = OptimizedStorageEngine::new(OptimizedStorageConfig::default()).unwrap();
+        let params = CompactCompressionParams { min_value: -1.0, max_value: 1.0, scale: 2.0 / 255.0, bits: 8 };
+        let compact = CompactVector::Compressed { data: vec![0, 255, 128], dimension: 3, params };
+        
+        let view = compact.quantized_view().unwrap();
+        assert_eq!(view.dimension(), 3);
+        let decompressed = engine.reconstruct_vector(&compact).unwrap();
+        assert_eq!(decompressed, view.to_vec());
+        assert!((decompressed[0] + 1.0).abs() < 1e-6 && (decompressed[1] - 1.0).abs() < 1e-6);
+        
+        // Codes that do not match the dimension are rejected
+        let truncated = CompactVector::Compressed {
+            data: vec![0, 255],
+            dimension: 3,
+            params: CompactCompressionParams { min_value: -1.0, max_value: 1.0, scale: 2.0 / 255.0, bits: 8 },
+        };
+        assert!(truncated.quantized_view().is_none());
+        assert!(engine.reconstruct_vector(&truncated).is_err());
+        assert!(CompactVector::Shared { shared_id: "s".to_string() }.quantized_view().is_none());
+    }
+}
@@ -0,0 +1,277 @@
+//! Compressed Codes for the ANN Index
+//!
+//! `QuantizedVectorStore` keeps a compressed copy of every vector in the HNSW
+//! index, encoded with the database's `VectorCompressor`. Codes are derived
+//! from the index rather than written alongside it: entry IDs are content
+//! derived, so `sync` only has to encode IDs it has not seen and drop IDs the
+//! index no longer holds.
+//!
+//! `search` scores the codes with the quantized kernels of `SimilaritySearch`
+//! and re-ranks the shortlist against the f32 index vectors.
+//!
+//! The store is also the training source for product quantization codebooks.
+//! It samples the normalized index vectors the codes are built from, and when
+//! the optimization scheduler replaces the codebook it re-encodes every code
+//! that referred to the previous one.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use super::compression::{CompressedVector, ProductQuantizer, QuantizedVectorRef, VectorCompressionConfig, VectorCompressor};
+use super::hnsw::HnswIndex;
+use super::optimization_scheduler::CodebookTrainingSource;
+use super::types::{VectorDbError, VectorDbResult};
+use crate::similarity_search::{QuantizedSearchConfig, SimilarityResult, SimilaritySearch};
+
+/// Compressed codes for the vectors of an ANN index
+pub struct QuantizedVectorStore {
+    /// Index the codes are derived from (lock before `state`)
+    ann_index: Arc<RwLock<HnswIndex>>,
+    /// Compressor and the codes it produced
+    state: RwLock<QuantizedState>,
+}
+
+struct QuantizedState {
+    compressor: VectorCompressor,
+    codes: HashMap<String, CompressedVector>,
+}
+
+impl QuantizedVectorStore {
+    /// Create an empty store over `ann_index`, using `quantizer` as codebook if one was trained
+    pub fn new(
+        ann_index: Arc<RwLock<HnswIndex>>,
+        config: VectorCompressionConfig,
+        quantizer: Option<ProductQuantizer>,
+    ) -> VectorDbResult<Self> {
+        let mut compressor = VectorCompressor::new(config)
+            .map_err(|e| VectorDbError::Compression { message: e.to_string() })?;
+        if let Some(quantizer) = quantizer {
+            compressor.set_product_quantizer(quantizer);
+        }
+
+        Ok(Self {
+            ann_index,
+            state: RwLock::new(QuantizedState { compressor, codes: HashMap::new() }),
+        })
+    }
+
+    /// Bring the codes in line with the ANN index and return how many there are
+    ///
+    /// Vectors that cannot be encoded (e.g. product quantization before a
+    /// codebook was trained) are left without a code.
+    pub async fn sync(&self) -> usize {
+        let ann_index = self.ann_index.read().await;
+        let mut state = self.state.write().await;
+        let state = &mut *state;
+
+        state.codes.retain(|id, _| ann_index.contains(id));
+        for id in ann_index.ids() {
+            if state.codes.contains_key(&id) {
+                continue;
+            }
+            let Some(vector) = ann_index.vector(&id) else { continue };
+            match state.compressor.compress_vector(vector, &id) {
+                Ok(compressed) => {
+                    state.codes.insert(id, compressed);
+                }
+                Err(e) => {
+                    eprintln!("⚠️ Failed to compress vectors for the ANN index: {}", e);
+                    break;
+                }
+            }
+        }
+
+        state.codes.len()
+    }
+
+    /// Number of stored codes
+    pub async fn len(&self) -> usize {
+        self.state.read().await.codes.len()
+    }
+
+    /// Whether no codes are stored
+    pub async fn is_empty(&self) -> bool {
+        self.state.read().await.codes.is_empty()
+    }
+
+    /// Code stored for an entry
+    pub async fn code(&self, id: &str) -> Option<CompressedVector> {
+        self.state.read().await.codes.get(id).cloned()
+    }
+
+    /// Rank the coded vectors against a query, re-ranking the shortlist exactly
+    ///
+    /// Codes are scored in place where the format allows it; product
+    /// quantization and delta codes are decompressed first. The best
+    /// `k * rerank_factor` candidates are then re-scored against their f32
+    /// vectors from the ANN index.
+    ///
+    /// # Returns
+    ///
+    /// Up to `k` `(entry_id, similarity)` pairs sorted by similarity (descending)
+    pub async fn search(
+        &self,
+        query_vector: &[f32],
+        k: usize,
+        config: &QuantizedSearchConfig,
+    ) -> SimilarityResult<Vec<(String, f32)>> {
+        let ann_index = self.ann_index.read().await;
+        let state = self.state.read().await;
+
+        let decompressed: HashMap<&str, Vec<f32>> = state.codes.iter()
+            .filter(|(_, compressed)| compressed.quantized_view().is_none())
+            .filter_map(|(id, compressed)| {
+                let vector = state.compressor.decompress_vector(compressed).ok()?;
+                Some((id.as_str(), vector))
+            })
+            .collect();
+        let (ids, candidates): (Vec<&str>, Vec<QuantizedVectorRef<'_>>) = state.codes.iter()
+            .filter_map(|(id, compressed)| {
+                let view = compressed.quantized_view()
+                    .or_else(|| decompressed.get(id.as_str()).map(|vector| QuantizedVectorRef::Float32(vector)))?;
+                Some((id.as_str(), view))
+            })
+            .unzip();
+
+        let exact_vector = |index: usize| ann_index.vector(ids[index]).map(<[f32]>::to_vec);
+        let hits = SimilaritySearch::quantized_k_nearest_neighbors(query_vector, &candidates, k, config, Some(&exact_vector))?;
+        Ok(hits.into_iter().map(|hit| (ids[hit.index].to_string(), hit.similarity)).collect())
+    }
+
+    /// ID of the product quantization codebook in use, if any
+    pub async fn codebook_id(&self) -> Option<String> {
+        let state = self.state.read().await;
+        state.compressor.product_quantizer().map(|quantizer| quantizer.id().to_string())
+    }
+}
+
+#[async_trait]
+impl CodebookTrainingSource for QuantizedVectorStore {
+    /// Samples the ANN index, so codebooks match the vectors that get encoded
+    async fn training_vectors(&self, max_samples: usize) -> Vec<Vec<f32>> {
+        let ann_index = self.ann_index.read().await;
+        let mut ids = ann_index.ids();
+        ids.sort_unstable();
+
+        // Evenly spaced sample; every entry when there are few enough
+        let step = ids.len().div_ceil(max_samples.max(1)).max(1);
+        ids.iter()
+            .step_by(step)
+            .filter_map(|id| ann_index.vector(id).map(<[f32]>::to_vec))
+            .collect()
+    }
+
+    async fn codebook_replaced(&self, quantizer: &ProductQuantizer) {
+        let ann_index = self.ann_index.read().await;
+        let mut state = self.state.write().await;
+        let state = &mut *state;
+        state.compressor.set_product_quantizer(quantizer.clone());
+
+        // Only product quantization codes refer to a codebook
+        let stale: Vec<String> = state.codes.iter()
+            .filter(|(_, compressed)| compressed.codebook_id.is_some())
+            .map(|(id, _)| id.clone())
+            .collect();
+        let mut failed = 0;
+        for id in stale {
+            let reencoded = ann_index.vector(&id)
+                .and_then(|vector| state.compressor.compress_vector(vector, &id).ok());
+            match reencoded {
+                Some(compressed) => {
+                    state.codes.insert(id, compressed);
+                }
+                None => {
+                    state.codes.remove(&id);
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            eprintln!("⚠️ Dropped {} codes that could not be re-encoded with codebook {}", failed, quantizer.id());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::compression::{ProductQuantizationConfig, VectorCompressionAlgorithm};
+    use crate::vector_db::hnsw::HnswConfig;
+
+    fn index_with(vectors: &[Vec<f32>]) -> Arc<RwLock<HnswIndex>> {
+        let mut index = HnswIndex::new(HnswConfig::default());
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(&format!("entry_{}", i), vector).unwrap();
+        }
+        Arc::new(RwLock::new(index))
+    }
+
+    fn sample_vectors(count: usize, seed: usize) -> Vec<Vec<f32>> {
+        (0..count)
+            .map(|i| (0..16).map(|d| ((i * 7 + d * (3 + seed)) % 11) as f32 / 11.0 - 0.5).collect())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sync_follows_the_ann_index() {
+        let ann_index = index_with(&sample_vectors(20, 0));
+        let store = QuantizedVectorStore::new(ann_index.clone(), VectorCompressionConfig::default(), None).unwrap();
+        assert!(store.is_empty().await);
+
+        assert_eq!(store.sync().await, 20);
+        let code = store.code("entry_3").await.unwrap();
+        assert_eq!(code.algorithm, VectorCompressionAlgorithm::Quantized8Bit);
+
+        ann_index.write().await.remove("entry_3");
+        ann_index.write().await.insert("entry_new", &[0.5; 16]).unwrap();
+        assert_eq!(store.sync().await, 20);
+        assert!(store.code("entry_3").await.is_none());
+        assert!(store.code("entry_new").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_search_reranks_with_index_vectors() {
+        let vectors = sample_vectors(10, 1);
+        let store = QuantizedVectorStore::new(index_with(&vectors), VectorCompressionConfig::default(), None).unwrap();
+        store.sync().await;
+
+        let config = QuantizedSearchConfig { min_threshold: -1.0, ..Default::default() };
+        let hits = store.search(&vectors[7], 3, &config).await.unwrap();
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].0, "entry_7");
+        assert!((hits[0].1 - 1.0).abs() < 1e-5);
+        assert!(hits.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+
+    #[tokio::test]
+    async fn test_codebook_replacement_reencodes_codes() {
+        let pq_config = ProductQuantizationConfig { num_subspaces: 4, num_centroids: 16, ..Default::default() };
+        let vectors = sample_vectors(200, 0);
+        let first = ProductQuantizer::train(&vectors, &pq_config).unwrap();
+        let config = VectorCompressionConfig {
+            algorithm: VectorCompressionAlgorithm::ProductQuantization,
+            product_quantization: pq_config.clone(),
+            ..Default::default()
+        };
+
+        let store = QuantizedVectorStore::new(index_with(&vectors), config, Some(first.clone())).unwrap();
+        assert_eq!(store.codebook_id().await.as_deref(), Some(first.id()));
+        assert_eq!(store.sync().await, 200);
+        assert_eq!(store.code("entry_0").await.unwrap().codebook_id.as_deref(), Some(first.id()));
+
+        let training = store.training_vectors(50).await;
+        assert_eq!(training.len(), 50);
+        let second = ProductQuantizer::train(&sample_vectors(200, 2), &pq_config).unwrap();
+        assert_ne!(second.id(), first.id());
+
+        store.codebook_replaced(&second).await;
+        assert_eq!(store.codebook_id().await.as_deref(), Some(second.id()));
+        assert_eq!(store.len().await, 200);
+        let code = store.code("entry_0").await.unwrap();
+        assert_eq!(code.codebook_id.as_deref(), Some(second.id()));
+    }
+}
@@ -35,6 +35,8 @@ use serde::{Serialize, Deserialize};
 use crate::vector_db::types::{VectorDbError, VectorDbResult};
 use crate::vector_db::storage::VectorStorage;
 use crate::vector_db::operations::VectorOperations;
+use crate::vector_db::hnsw::HnswIndex;
+use crate::vector_db::model_namespace;
 
 /// Configuration for index rebuilding operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -497,6 +499,12 @@ pub struct IndexRebuilder {
     progress_callback: Option<ProgressCallback>,
     /// Cancellation flag
     cancelled: Arc<AtomicBool>,
+    /// ANN index rebuilt during the index structure phase (if attached)
+    ann_index: Option<Arc<RwLock<HnswIndex>>>,
+    /// Active model namespace of the attached ANN index
+    active_model: Option<Arc<RwLock<Option<String>>>>,
+    /// Storage directory holding the ANN index snapshots
+    storage_dir: Option<PathBuf>,
 }
 
 impl IndexRebuilder {
@@ -515,9 +523,30 @@ impl IndexRebuilder {
             progress: Arc::new(RwLock::new(initial_progress)),
             progress_callback: None,
             cancelled: Arc::new(AtomicBool::new(false)),
+            ann_index: None,
+            active_model: None,
+            storage_dir: None,
         }
     }
     
+    /// Attach the HNSW index so it is rebuilt from storage during a full rebuild
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ann_index` - Shared index used by the database for approximate search
+    /// * `active_model` - Model namespace the index covers
+    /// * `storage_dir` - Directory where the rebuilt index is persisted
+    pub fn set_ann_index(
+        &mut self,
+        ann_index: Arc<RwLock<HnswIndex>>,
+        active_model: Arc<RwLock<Option<String>>>,
+        storage_dir: PathBuf,
+    ) {
+        self.ann_index = Some(ann_index);
+        self.active_model = Some(active_model);
+        self.storage_dir = Some(storage_dir);
+    }
+    
     /// Set progress callback for UI updates
     pub fn set_progress_callback(&mut self, callback: ProgressCallback) {
         self.progress_callback = Some(callback);
@@ -629,10 +658,12 @@ impl IndexRebuilder {
         // Phase 5: Rebuild index structure
         self.update_progress(RebuildPhase::RebuildingIndex, "Rebuilding index structure").await;
         
-        // Note: Index structure rebuilding would be implemented here in a full implementation
-        // For now, the processing of embeddings above constitutes the main rebuild work
-        if self.config.enable_debug_logging {
-            eprintln!("🏗️ Index structure rebuild completed (processing embeddings is the main rebuild work)");
+        if let Err(e) = self.rebuild_ann_index(&all_ids).await {
+            let error_msg = format!("ANN index rebuild failed: {}", e);
+            errors.push(error_msg.clone());
+            if self.config.enable_debug_logging {
+                eprintln!("❌ {}", error_msg);
+            }
         }
         
         if self.check_cancelled().await? {
@@ -709,6 +740,64 @@ impl IndexRebuilder {
         Ok(result)
     }
     
+    /// Rebuild the attached HNSW index from the stored embeddings
+    /// 
+    /// The new graph is built off to the side and swapped in at the end, so
+    /// searches keep using the previous index while the rebuild runs. Only
+    /// entries of the active model namespace are indexed; if the active model
+    /// changes during the rebuild, the result is discarded.
+    async fn rebuild_ann_index(&self, embedding_ids: &[String]) -> VectorDbResult<()> {
+        let ann_index = match &self.ann_index {
+            Some(ann_index) => ann_index,
+            None => return Ok(()),
+        };
+        let namespace = match &self.active_model {
+            Some(active_model) => active_model.read().await.clone(),
+            None => None,
+        };
+        
+        let hnsw_config = ann_index.read().await.config().clone();
+        let mut rebuilt = HnswIndex::new(hnsw_config);
+        
+        for batch in embedding_ids.chunks(self.config.rebuild_batch_size.max(1)) {
+            if self.check_cancelled().await? {
+                return Ok(());
+            }
+            for entry in self.storage.retrieve_entries(batch).await? {
+                if !model_namespace::belongs_to(&entry, namespace.as_deref()) {
+                    continue;
+                }
+                if let Err(e) = rebuilt.insert(&entry.id, &entry.vector) {
+                    if self.config.enable_debug_logging {
+                        eprintln!("⚠️ Skipping {} in ANN index: {}", entry.id, e);
+                    }
+                }
+            }
+        }
+        
+        let active_model = match &self.active_model {
+            Some(active_model) => Some(active_model.read().await),
+            None => None,
+        };
+        if active_model.as_ref().is_some_and(|active_model| **active_model != namespace) {
+            if self.config.enable_debug_logging {
+                eprintln!("⚠️ Active model changed during rebuild, keeping the new namespace index");
+            }
+            return Ok(());
+        }
+        
+        if let Some(storage_dir) = &self.storage_dir {
+            rebuilt.save_to_file(&model_namespace::ann_index_path(storage_dir, namespace.as_deref()))?;
+        }
+        
+        if self.config.enable_debug_logging {
+            eprintln!("🏗️ ANN index rebuilt: {}", rebuilt.stats().summary());
+        }
+        
+        *ann_index.write().await = rebuilt;
+        Ok(())
+    }
+    
     /// Process embeddings sequentially
     async fn process_embeddings_sequential(&self, embedding_ids: &[String]) -> VectorDbResult<(usize, Vec<String>)> {
         let mut processed_count = 0;
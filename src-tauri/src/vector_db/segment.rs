@@ -0,0 +1,516 @@
+//! Binary Segment Files
+//!
+//! A segment is an immutable storage file holding a batch of embedding
+//! entries. Segments are written when the write-ahead log is flushed and when
+//! storage is compacted; they are never modified afterwards.
+//!
+//! ## Layout
+//!
+//! ```text
+//! [magic: 8 bytes][header length: u32 LE][header: bincode SegmentHeader]
+//! [vector block: little-endian f32 values of every entry, back to back]
+//! [record block: ([record length: u32 LE][record bytes])*]
+//! ```
+//!
+//! Record bytes are a bincode `SegmentRecord` compressed with the algorithm
+//! named in the header. Vectors stay uncompressed so that a single entry can
+//! be read with two positioned reads and without touching its neighbours.
+//!
+//! Segments are applied in sequence order when storage is opened. Tombstones
+//! in a segment delete entries written by older segments, and a snapshot
+//! segment (produced by compaction) replaces everything before it.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::vector_db::storage::{compress_bytes, decompress_bytes};
+use crate::vector_db::types::{
+    CompressionAlgorithm, EmbeddingEntry, EmbeddingMetadata, StorageFileHeader,
+    VectorDbError, VectorDbResult,
+};
+
+/// Magic bytes identifying a segment file
+pub const SEGMENT_MAGIC: &[u8; 8] = b"AINOTSEG";
+
+/// File name prefix of segment files
+pub const SEGMENT_FILE_PREFIX: &str = "segment_";
+
+/// File name extension of segment files
+pub const SEGMENT_FILE_EXTENSION: &str = ".seg";
+
+/// Upper bound for a serialized header, guards against reading garbage lengths
+const MAX_HEADER_LEN: u32 = 64 * 1024 * 1024;
+
+/// Header stored at the start of every segment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentHeader {
+    /// Common storage file header (version, compression, entry count, checksum)
+    pub file_header: StorageFileHeader,
+    /// Sequence number, segments are replayed in ascending order
+    pub sequence: u64,
+    /// Whether this segment contains every live entry (older segments are obsolete)
+    pub snapshot: bool,
+    /// IDs deleted since the previous segment was written
+    pub tombstones: Vec<String>,
+    /// Size of the vector block in bytes
+    pub vector_block_len: u64,
+    /// Size of the record block in bytes
+    pub record_block_len: u64,
+}
+
+/// Metadata part of an entry, stored in the record block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SegmentRecord {
+    id: String,
+    metadata: EmbeddingMetadata,
+    created_at: u64,
+    updated_at: u64,
+    /// Byte offset of the vector within the vector block
+    vector_offset: u64,
+    /// Number of f32 components in the vector
+    dimension: u32,
+}
+
+/// Position of a record inside a segment file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordLocation {
+    /// Absolute file offset of the record bytes (after the length prefix)
+    pub offset: u64,
+    /// Length of the record bytes
+    pub len: u32,
+}
+
+/// Entry ID, source file and record position, as kept by the storage index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedRecord {
+    /// Entry ID
+    pub id: String,
+    /// Path of the note the entry was embedded from
+    pub file_path: String,
+    /// Position of the record inside the segment
+    pub location: RecordLocation,
+}
+
+/// Result of writing a segment
+#[derive(Debug)]
+pub struct WrittenSegment {
+    /// Header that was written
+    pub header: SegmentHeader,
+    /// Record location for every entry, in input order
+    pub locations: Vec<IndexedRecord>,
+    /// Total file size in bytes
+    pub file_size: u64,
+}
+
+/// Build the file name for a segment sequence number
+pub fn segment_file_name(sequence: u64) -> String {
+    format!("{}{:010}{}", SEGMENT_FILE_PREFIX, sequence, SEGMENT_FILE_EXTENSION)
+}
+
+/// Parse the sequence number from a segment file name
+pub fn parse_segment_sequence(file_name: &str) -> Option<u64> {
+    file_name
+        .strip_prefix(SEGMENT_FILE_PREFIX)?
+        .strip_suffix(SEGMENT_FILE_EXTENSION)?
+        .parse()
+        .ok()
+}
+
+/// Write a segment atomically (temporary file, fsync, rename)
+pub fn write_segment(
+    path: &Path,
+    sequence: u64,
+    snapshot: bool,
+    entries: &[EmbeddingEntry],
+    tombstones: Vec<String>,
+    compression: CompressionAlgorithm,
+    enable_checksums: bool,
+) -> VectorDbResult<WrittenSegment> {
+    let mut vector_block = Vec::with_capacity(entries.iter().map(|e| e.vector.len() * 4).sum());
+    let mut record_block = Vec::new();
+    let mut record_spans = Vec::with_capacity(entries.len());
+    let mut uncompressed_size = 0;
+
+    for entry in entries {
+        let record = SegmentRecord {
+            id: entry.id.clone(),
+            metadata: entry.metadata.clone(),
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+            vector_offset: vector_block.len() as u64,
+            dimension: entry.vector.len() as u32,
+        };
+        for value in &entry.vector {
+            vector_block.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let raw_record = bincode::serialize(&record).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to serialize segment record: {}", e),
+        })?;
+        uncompressed_size += raw_record.len();
+        let record_bytes = compress_bytes(&compression, &raw_record)?;
+
+        record_block.extend_from_slice(&(record_bytes.len() as u32).to_le_bytes());
+        record_spans.push((entry, record_block.len() as u64, record_bytes.len() as u32));
+        record_block.extend_from_slice(&record_bytes);
+    }
+    uncompressed_size += vector_block.len();
+
+    let mut file_header = StorageFileHeader::new(compression, entries.len());
+    file_header.uncompressed_size = uncompressed_size;
+    if enable_checksums {
+        let mut hasher = Sha256::new();
+        hasher.update(&vector_block);
+        hasher.update(&record_block);
+        file_header.checksum = Some(format!("{:x}", hasher.finalize()));
+    }
+
+    let header = SegmentHeader {
+        file_header,
+        sequence,
+        snapshot,
+        tombstones,
+        vector_block_len: vector_block.len() as u64,
+        record_block_len: record_block.len() as u64,
+    };
+    let header_bytes = bincode::serialize(&header).map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to serialize segment header: {}", e),
+    })?;
+
+    let data_start = (SEGMENT_MAGIC.len() + 4 + header_bytes.len()) as u64;
+    let record_block_start = data_start + vector_block.len() as u64;
+
+    let temp_path = path.with_extension("seg.tmp");
+    let file = File::create(&temp_path).map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to create segment file: {}", e),
+    })?;
+    let mut writer = BufWriter::new(file);
+    let write_result = writer.write_all(SEGMENT_MAGIC)
+        .and_then(|_| writer.write_all(&(header_bytes.len() as u32).to_le_bytes()))
+        .and_then(|_| writer.write_all(&header_bytes))
+        .and_then(|_| writer.write_all(&vector_block))
+        .and_then(|_| writer.write_all(&record_block))
+        .and_then(|_| writer.flush());
+    write_result.map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to write segment file: {}", e),
+    })?;
+    writer.get_ref().sync_all().map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to sync segment file: {}", e),
+    })?;
+    drop(writer);
+
+    fs::rename(&temp_path, path).map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to finalize segment file: {}", e),
+    })?;
+    sync_parent_dir(path);
+
+    let locations = record_spans
+        .into_iter()
+        .map(|(entry, offset, len)| IndexedRecord {
+            id: entry.id.clone(),
+            file_path: entry.metadata.file_path.clone(),
+            location: RecordLocation { offset: record_block_start + offset, len },
+        })
+        .collect();
+
+    Ok(WrittenSegment {
+        header,
+        locations,
+        file_size: record_block_start + record_block.len() as u64,
+    })
+}
+
+/// Parse the magic bytes and header from the start of a segment
+pub fn read_header<R: Read>(reader: &mut R) -> VectorDbResult<(SegmentHeader, u64)> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic).map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to read segment magic: {}", e),
+    })?;
+    if &magic != SEGMENT_MAGIC {
+        return Err(VectorDbError::Storage {
+            message: "Not a segment file (bad magic bytes)".to_string(),
+        });
+    }
+
+    let mut len_bytes = [0u8; 4];
+    reader.read_exact(&mut len_bytes).map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to read segment header length: {}", e),
+    })?;
+    let header_len = u32::from_le_bytes(len_bytes);
+    if header_len > MAX_HEADER_LEN {
+        return Err(VectorDbError::Storage {
+            message: format!("Segment header length {} exceeds limit", header_len),
+        });
+    }
+
+    let mut header_bytes = vec![0u8; header_len as usize];
+    reader.read_exact(&mut header_bytes).map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to read segment header: {}", e),
+    })?;
+    let header: SegmentHeader = bincode::deserialize(&header_bytes).map_err(|e| VectorDbError::Storage {
+        message: format!("Failed to deserialize segment header: {}", e),
+    })?;
+    header.file_header.validate_compatibility()?;
+
+    let data_start = (SEGMENT_MAGIC.len() + 4) as u64 + header_len as u64;
+    Ok((header, data_start))
+}
+
+/// Random-access reader over a single segment file
+pub struct SegmentReader {
+    file: File,
+    header: SegmentHeader,
+    data_start: u64,
+}
+
+impl SegmentReader {
+    /// Open a segment and parse its header
+    pub fn open(path: &Path) -> VectorDbResult<Self> {
+        let mut file = File::open(path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to open segment {}: {}", path.display(), e),
+        })?;
+        let (header, data_start) = read_header(&mut file)?;
+        Ok(Self { file, header, data_start })
+    }
+
+    /// Segment header
+    pub fn header(&self) -> &SegmentHeader {
+        &self.header
+    }
+
+    /// Read a single entry by record location
+    pub fn read_entry(&mut self, location: RecordLocation) -> VectorDbResult<EmbeddingEntry> {
+        let record_bytes = self.read_at(location.offset, location.len as usize)?;
+        let record = self.decode_record(&record_bytes)?;
+        let vector_bytes = self.read_at(self.data_start + record.vector_offset, record.dimension as usize * 4)?;
+        Ok(Self::assemble_entry(record, &vector_bytes))
+    }
+
+    /// Read every entry in the segment together with its record location
+    pub fn read_all(&mut self) -> VectorDbResult<Vec<(EmbeddingEntry, RecordLocation)>> {
+        let vector_block = self.read_at(self.data_start, self.header.vector_block_len as usize)?;
+        let record_block_start = self.data_start + self.header.vector_block_len;
+        let record_block = self.read_at(record_block_start, self.header.record_block_len as usize)?;
+
+        let mut entries = Vec::with_capacity(self.header.file_header.entry_count);
+        for (offset, len) in Self::record_spans(&record_block)? {
+            let record = self.decode_record(&record_block[offset..offset + len])?;
+            let start = record.vector_offset as usize;
+            let end = start + record.dimension as usize * 4;
+            if end > vector_block.len() {
+                return Err(VectorDbError::Storage {
+                    message: format!("Vector of entry {} lies outside the vector block", record.id),
+                });
+            }
+            let location = RecordLocation {
+                offset: record_block_start + offset as u64,
+                len: len as u32,
+            };
+            entries.push((Self::assemble_entry(record, &vector_block[start..end]), location));
+        }
+
+        Ok(entries)
+    }
+
+    /// Read only the entry IDs, source files and record locations (no vectors)
+    pub fn read_locations(&mut self) -> VectorDbResult<Vec<IndexedRecord>> {
+        let record_block_start = self.data_start + self.header.vector_block_len;
+        let record_block = self.read_at(record_block_start, self.header.record_block_len as usize)?;
+
+        Self::record_spans(&record_block)?
+            .into_iter()
+            .map(|(offset, len)| {
+                let record = self.decode_record(&record_block[offset..offset + len])?;
+                Ok(IndexedRecord {
+                    id: record.id,
+                    file_path: record.metadata.file_path,
+                    location: RecordLocation { offset: record_block_start + offset as u64, len: len as u32 },
+                })
+            })
+            .collect()
+    }
+
+    /// Verify the body checksum stored in the header (no-op when checksums are disabled)
+    pub fn verify_checksum(&mut self) -> VectorDbResult<()> {
+        let expected = match &self.header.file_header.checksum {
+            Some(checksum) => checksum.clone(),
+            None => return Ok(()),
+        };
+        let body_len = (self.header.vector_block_len + self.header.record_block_len) as usize;
+        let body = self.read_at(self.data_start, body_len)?;
+        let mut hasher = Sha256::new();
+        hasher.update(&body);
+        if format!("{:x}", hasher.finalize()) != expected {
+            return Err(VectorDbError::ChecksumMismatch);
+        }
+        Ok(())
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> VectorDbResult<Vec<u8>> {
+        let mut buffer = vec![0u8; len];
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.file.read_exact(&mut buffer))
+            .map_err(|e| VectorDbError::Storage {
+                message: format!("Failed to read segment data at offset {}: {}", offset, e),
+            })?;
+        Ok(buffer)
+    }
+
+    fn decode_record(&self, bytes: &[u8]) -> VectorDbResult<SegmentRecord> {
+        let raw_record = decompress_bytes(&self.header.file_header.compression, bytes)?;
+        bincode::deserialize(&raw_record).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to deserialize segment record: {}", e),
+        })
+    }
+
+    /// Split the record block into (offset, length) spans of record bytes
+    fn record_spans(record_block: &[u8]) -> VectorDbResult<Vec<(usize, usize)>> {
+        let mut spans = Vec::new();
+        let mut position = 0;
+        while position < record_block.len() {
+            if position + 4 > record_block.len() {
+                return Err(VectorDbError::Storage {
+                    message: "Truncated record length in segment".to_string(),
+                });
+            }
+            let mut len_bytes = [0u8; 4];
+            len_bytes.copy_from_slice(&record_block[position..position + 4]);
+            let len = u32::from_le_bytes(len_bytes) as usize;
+            position += 4;
+            if position + len > record_block.len() {
+                return Err(VectorDbError::Storage {
+                    message: "Truncated record in segment".to_string(),
+                });
+            }
+            spans.push((position, len));
+            position += len;
+        }
+        Ok(spans)
+    }
+
+    fn assemble_entry(record: SegmentRecord, vector_bytes: &[u8]) -> EmbeddingEntry {
+        let vector = vector_bytes
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        EmbeddingEntry {
+            id: record.id,
+            vector,
+            metadata: record.metadata,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+        }
+    }
+}
+
+/// Whether a file name belongs to the current segment format
+pub fn is_segment_file_name(file_name: &str) -> bool {
+    parse_segment_sequence(file_name).is_some()
+}
+
+/// Best-effort fsync of the directory so a rename survives a crash
+pub(crate) fn sync_parent_dir(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::types::DataVersion;
+    use tempfile::TempDir;
+
+    fn create_entry(chunk: &str, vector: Vec<f32>) -> EmbeddingEntry {
+        EmbeddingEntry::new(
+            vector,
+            "/test/segment.md".to_string(),
+            chunk.to_string(),
+            &format!("Text of {}", chunk),
+            "test-model".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_segment_file_names() {
+        let name = segment_file_name(42);
+        assert_eq!(name, "segment_0000000042.seg");
+        assert_eq!(parse_segment_sequence(&name), Some(42));
+        assert_eq!(parse_segment_sequence("vector_1_2.json.gz"), None);
+        assert_eq!(parse_segment_sequence("segment_abc.seg"), None);
+    }
+
+    #[test]
+    fn test_segment_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(segment_file_name(1));
+        let entries = vec![
+            create_entry("a", vec![0.5, -1.25, 3.0]),
+            create_entry("b", vec![1.0, 2.0, 3.0, 4.0, 5.0]),
+        ];
+
+        let written = write_segment(
+            &path,
+            1,
+            false,
+            &entries,
+            vec!["deleted".to_string()],
+            CompressionAlgorithm::Lz4,
+            true,
+        ).unwrap();
+        assert_eq!(written.locations.len(), 2);
+        assert_eq!(written.file_size, fs::metadata(&path).unwrap().len());
+
+        let mut reader = SegmentReader::open(&path).unwrap();
+        assert_eq!(reader.header().sequence, 1);
+        assert_eq!(reader.header().tombstones, vec!["deleted".to_string()]);
+        assert_eq!(reader.header().file_header.version, DataVersion::CURRENT);
+        reader.verify_checksum().unwrap();
+
+        let single = reader.read_entry(written.locations[1].location).unwrap();
+        assert_eq!(single.id, entries[1].id);
+        assert_eq!(single.vector, entries[1].vector);
+
+        let all = reader.read_all().unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].0.vector, entries[0].vector);
+        assert_eq!(all[0].0.metadata.chunk_id, "a");
+        assert_eq!(all[0].1, written.locations[0].location);
+        assert_eq!(written.locations[0].file_path, entries[0].metadata.file_path);
+        assert_eq!(reader.read_locations().unwrap(), written.locations);
+    }
+
+    #[test]
+    fn test_segment_checksum_detects_corruption() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(segment_file_name(7));
+        let written = write_segment(
+            &path,
+            7,
+            false,
+            &[create_entry("a", vec![0.1, 0.2, 0.3])],
+            Vec::new(),
+            CompressionAlgorithm::None,
+            true,
+        ).unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        let vector_start = (written.file_size - written.header.record_block_len - written.header.vector_block_len) as usize;
+        bytes[vector_start] ^= 0xFF;
+        fs::write(&path, &bytes).unwrap();
+
+        let mut reader = SegmentReader::open(&path).unwrap();
+        assert!(matches!(reader.verify_checksum(), Err(VectorDbError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn test_read_header_rejects_invalid_data() {
+        assert!(read_header(&mut &b"invalid json data"[..]).is_err());
+    }
+}
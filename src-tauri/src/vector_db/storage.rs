@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -9,15 +10,19 @@ use serde::{Deserialize, Serialize};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use sha2::{Sha256, Digest};
 use lz4::{Decoder, EncoderBuilder};
 
+use crate::vector_db::segment::{self, IndexedRecord, RecordLocation, SegmentReader};
+use crate::vector_db::wal::{WalRecord, WriteAheadLog, WAL_FILE_NAME};
 use crate::vector_db::types::{
     EmbeddingEntry, VectorStorageConfig, StorageFileHeader, StorageMetrics,
-    CompressionAlgorithm, VectorDbError, VectorDbResult,
+    CompressionAlgorithm, DataVersion, VectorDbError, VectorDbResult,
 };
 
-/// Container for a batch of embedding entries with metadata
+/// Backup subdirectory that receives JSON batch files after migration
+const LEGACY_BACKUP_DIR: &str = "legacy";
+
+/// Container for a batch of embedding entries in the legacy JSON file format
 #[derive(Debug, Serialize, Deserialize)]
 struct StorageBatch {
     /// File header with metadata
@@ -26,14 +31,20 @@ struct StorageBatch {
     pub entries: Vec<EmbeddingEntry>,
 }
 
-/// File-based vector storage system with compression and integrity checking
+/// Append-only vector storage built from binary segments and a write-ahead log
+///
+/// Writes are logged to the WAL and kept in a memtable until
+/// `max_entries_per_file` mutations have accumulated, then flushed into an
+/// immutable segment. Opening the storage replays segments and the WAL, so the
+/// index survives restarts; `compact_storage` merges segments and drops
+/// deleted or superseded records.
 pub struct VectorStorage {
     /// Storage configuration
     config: VectorStorageConfig,
     /// Storage directory path
     storage_path: PathBuf,
-    /// In-memory index mapping entry IDs to file locations
-    index: Arc<RwLock<HashMap<String, FileLocation>>>,
+    /// Segment index, memtable and WAL, locked together so every mutation is logged and applied atomically
+    state: Arc<RwLock<StorageState>>,
     /// Storage metrics
     metrics: Arc<RwLock<StorageMetrics>>,
 }
@@ -41,648 +52,881 @@ pub struct VectorStorage {
 /// Location of an entry within the storage system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct FileLocation {
-    /// Storage file name
+    /// Segment file name
     file_name: String,
-    /// Entry index within the file
-    entry_index: usize,
+    /// Path of the note the entry was embedded from
+    file_path: String,
+    /// Absolute offset of the entry record within the segment
+    record_offset: u64,
+    /// Length of the entry record in bytes
+    record_len: u32,
     /// Timestamp when entry was indexed
     indexed_at: u64,
 }
 
+impl FileLocation {
+    fn new(file_name: &str, record: IndexedRecord) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            file_path: record.file_path,
+            record_offset: record.location.offset,
+            record_len: record.location.len,
+            indexed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+
+    fn record(&self) -> RecordLocation {
+        RecordLocation {
+            offset: self.record_offset,
+            len: self.record_len,
+        }
+    }
+}
+
+/// Mutable state of the storage engine
+struct StorageState {
+    /// Entries persisted in segments
+    index: HashMap<String, FileLocation>,
+    /// Entries logged to the WAL but not yet flushed to a segment
+    memtable: HashMap<String, EmbeddingEntry>,
+    /// IDs deleted since the last flush, written as tombstones of the next segment
+    pending_deletes: HashSet<String>,
+    /// Write-ahead log backing the memtable and pending deletes
+    wal: WriteAheadLog,
+    /// Sequence number of the next segment
+    next_sequence: u64,
+}
+
+impl StorageState {
+    fn apply_put(&mut self, entry: EmbeddingEntry) {
+        self.index.remove(&entry.id);
+        self.pending_deletes.remove(&entry.id);
+        self.memtable.insert(entry.id.clone(), entry);
+    }
+
+    fn apply_delete(&mut self, entry_id: &str) -> bool {
+        let in_memtable = self.memtable.remove(entry_id).is_some();
+        let in_segment = self.index.remove(entry_id).is_some();
+        if in_memtable || in_segment {
+            self.pending_deletes.insert(entry_id.to_string());
+        }
+        in_memtable || in_segment
+    }
+
+    fn apply_wal_record(&mut self, record: WalRecord) {
+        match record {
+            WalRecord::Put(entry) => self.apply_put(*entry),
+            WalRecord::Delete(entry_id) => {
+                self.apply_delete(&entry_id);
+            }
+            WalRecord::Batch(records) => {
+                for record in records {
+                    self.apply_wal_record(record);
+                }
+            }
+        }
+    }
+
+    fn contains(&self, entry_id: &str) -> bool {
+        self.memtable.contains_key(entry_id) || self.index.contains_key(entry_id)
+    }
+
+    fn entry_ids_for_file(&self, file_path: &str) -> Vec<String> {
+        let persisted = self.index.iter().filter(|(_, location)| location.file_path == file_path);
+        let buffered = self.memtable.iter().filter(|(_, entry)| entry.metadata.file_path == file_path);
+        persisted.map(|(id, _)| id.clone()).chain(buffered.map(|(id, _)| id.clone())).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.index.len() + self.memtable.len()
+    }
+
+    fn pending_mutations(&self) -> usize {
+        self.memtable.len() + self.pending_deletes.len()
+    }
+}
+
 impl VectorStorage {
     /// Create a new vector storage instance
+    ///
+    /// Replays existing segments and the write-ahead log, and migrates JSON
+    /// batch files written by format version 1.x into a segment.
     pub fn new(config: VectorStorageConfig) -> VectorDbResult<Self> {
         let storage_path = PathBuf::from(&config.storage_dir);
-        
+
         // Create storage directory if it doesn't exist
         if !storage_path.exists() {
             fs::create_dir_all(&storage_path).map_err(|e| VectorDbError::Storage {
                 message: format!("Failed to create storage directory: {}", e),
             })?;
         }
-        
+
+        let (mut index, mut next_sequence) = Self::replay_segments(&storage_path)?;
+        Self::migrate_legacy_files(&storage_path, &config, &mut index, &mut next_sequence)?;
+
+        let (wal, wal_records) = WriteAheadLog::open(&storage_path.join(WAL_FILE_NAME))?;
+        let mut state = StorageState {
+            index,
+            memtable: HashMap::new(),
+            pending_deletes: HashSet::new(),
+            wal,
+            next_sequence,
+        };
+        for record in wal_records {
+            state.apply_wal_record(record);
+        }
+
+        if state.len() > 0 {
+            eprintln!("📂 Opened vector storage with {} entries ({} pending in write-ahead log)",
+                      state.len(), state.pending_mutations());
+        }
+
         let storage = Self {
             config,
             storage_path,
-            index: Arc::new(RwLock::new(HashMap::new())),
+            state: Arc::new(RwLock::new(state)),
             metrics: Arc::new(RwLock::new(StorageMetrics::default())),
         };
-        
-        // Index will be built lazily during first operations
-        
+
         Ok(storage)
     }
-    
+
     /// Store a batch of embedding entries
+    ///
+    /// Entries are durable once this returns: they are synced to the
+    /// write-ahead log before being applied to the memtable.
     pub async fn store_entries(&self, entries: Vec<EmbeddingEntry>) -> VectorDbResult<Vec<String>> {
         if entries.is_empty() {
             return Ok(vec![]);
         }
-        
+
         // Validate all entries
         for entry in &entries {
             entry.validate()?;
         }
-        
-        let file_name = self.generate_file_name();
-        let file_path = self.storage_path.join(&file_name);
-        
-        // Create storage batch
-        let header = StorageFileHeader::new(
-            self.config.compression_algorithm.clone(),
-            entries.len(),
-        );
-        
-        let batch = StorageBatch {
-            header,
-            entries: entries.clone(),
-        };
-        
-        // Serialize and compress data
-        let serialized_data = serde_json::to_vec(&batch)?;
-        let (compressed_data, _checksum) = self.compress_and_checksum(&serialized_data)?;
-        
-        // Write to file
-        fs::write(&file_path, &compressed_data).map_err(|e| VectorDbError::Storage {
-            message: format!("Failed to write storage file: {}", e),
-        })?;
-        
-        // Update index
-        let entry_ids = {
-            let mut index = self.index.write().await;
-            let entry_ids = entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
-            
-            for (i, entry) in entries.iter().enumerate() {
-                let location = FileLocation {
-                    file_name: file_name.clone(),
-                    entry_index: i,
-                    indexed_at: SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs(),
-                };
-                index.insert(entry.id.clone(), location);
+
+        let entry_ids = entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        let records = entries.iter()
+            .map(|entry| WalRecord::Put(Box::new(entry.clone())))
+            .collect::<Vec<_>>();
+
+        let flushed_segment = {
+            let mut state = self.state.write().await;
+            state.wal.append(&records)?;
+            for entry in entries {
+                state.apply_put(entry);
             }
-            
-            entry_ids
+            self.flush_if_needed(&mut state)?
         }; // Drop the write lock here
-        
-        // Update metrics (after dropping the write lock)
-        if self.config.enable_metrics {
-            self.update_metrics().await;
-        }
-        
-        // Create backup if enabled
-        if self.config.auto_backup {
-            self.create_backup(&file_path).await?;
-        }
-        
-        eprintln!("📦 Stored {} embedding entries to {}", entries.len(), file_name);
+
+        self.finish_mutation(flushed_segment).await?;
         Ok(entry_ids)
     }
-    
+
     /// Retrieve an embedding entry by ID
     pub async fn retrieve_entry(&self, entry_id: &str) -> VectorDbResult<Option<EmbeddingEntry>> {
-        let index = self.index.read().await;
-        let location = match index.get(entry_id) {
+        let state = self.state.read().await;
+        if let Some(entry) = state.memtable.get(entry_id) {
+            return Ok(Some(entry.clone()));
+        }
+
+        let location = match state.index.get(entry_id) {
             Some(loc) => loc,
             None => return Ok(None),
         };
-        
-        let file_path = self.storage_path.join(&location.file_name);
-        let batch = self.load_batch(&file_path).await?;
-        
-        if location.entry_index < batch.entries.len() {
-            let entry = batch.entries[location.entry_index].clone();
-            // Verify entry ID matches (data integrity check)
-            if entry.id == entry_id {
-                Ok(Some(entry))
-            } else {
-                Err(VectorDbError::Storage {
-                    message: format!("Entry ID mismatch in storage file: expected {}, found {}", 
-                                   entry_id, entry.id),
-                })
-            }
+
+        let mut reader = SegmentReader::open(&self.storage_path.join(&location.file_name))?;
+        let entry = reader.read_entry(location.record())?;
+
+        // Verify entry ID matches (data integrity check)
+        if entry.id == entry_id {
+            Ok(Some(entry))
         } else {
             Err(VectorDbError::Storage {
-                message: format!("Invalid entry index {} in file {}", 
-                               location.entry_index, location.file_name),
+                message: format!("Entry ID mismatch in segment {}: expected {}, found {}",
+                               location.file_name, entry_id, entry.id),
             })
         }
     }
-    
+
     /// Retrieve multiple entries by their IDs
     pub async fn retrieve_entries(&self, entry_ids: &[String]) -> VectorDbResult<Vec<EmbeddingEntry>> {
         let mut results = Vec::with_capacity(entry_ids.len());
-        let mut file_cache: HashMap<String, StorageBatch> = HashMap::new();
-        
-        let index = self.index.read().await;
-        
+        let mut readers: HashMap<String, SegmentReader> = HashMap::new();
+
+        let state = self.state.read().await;
+
         for entry_id in entry_ids {
-            if let Some(location) = index.get(entry_id) {
-                // Load file if not in cache
-                if !file_cache.contains_key(&location.file_name) {
-                    let file_path = self.storage_path.join(&location.file_name);
-                    let batch = self.load_batch(&file_path).await?;
-                    file_cache.insert(location.file_name.clone(), batch);
-                }
-                
-                // Get entry from cached batch
-                if let Some(batch) = file_cache.get(&location.file_name) {
-                    if location.entry_index < batch.entries.len() {
-                        let entry = batch.entries[location.entry_index].clone();
-                        if entry.id == *entry_id {
-                            results.push(entry);
-                        }
+            if let Some(entry) = state.memtable.get(entry_id) {
+                results.push(entry.clone());
+                continue;
+            }
+
+            if let Some(location) = state.index.get(entry_id) {
+                // Open each segment once per call
+                let reader = match readers.entry(location.file_name.clone()) {
+                    Entry::Occupied(slot) => slot.into_mut(),
+                    Entry::Vacant(slot) => {
+                        slot.insert(SegmentReader::open(&self.storage_path.join(&location.file_name))?)
                     }
+                };
+
+                let entry = reader.read_entry(location.record())?;
+                if entry.id == *entry_id {
+                    results.push(entry);
                 }
             }
         }
-        
+
         Ok(results)
     }
-    
+
     /// Delete an embedding entry
+    ///
+    /// The delete is logged and becomes a tombstone in the next segment;
+    /// records in older segments are dropped during compaction.
     pub async fn delete_entry(&self, entry_id: &str) -> VectorDbResult<bool> {
-        let mut index = self.index.write().await;
-        
-        if index.remove(entry_id).is_some() {
-            // Note: This is a logical delete from index
-            // Physical file cleanup happens during compaction
-            eprintln!("🗑️ Logically deleted entry: {}", entry_id);
-            Ok(true)
-        } else {
-            Ok(false)
+        let flushed_segment = {
+            let mut state = self.state.write().await;
+            if !state.contains(entry_id) {
+                return Ok(false);
+            }
+
+            state.wal.append(&[WalRecord::Delete(entry_id.to_string())])?;
+            state.apply_delete(entry_id);
+            self.flush_if_needed(&mut state)?
+        };
+
+        eprintln!("🗑️ Deleted entry: {}", entry_id);
+        self.finish_mutation(flushed_segment).await?;
+        Ok(true)
+    }
+
+    /// Delete and store entries as one atomic mutation
+    ///
+    /// Deletes are applied before the new entries, so an entry can be
+    /// replaced under a different ID. The whole change is a single
+    /// write-ahead log frame: after a crash either all of it or none of it
+    /// is replayed.
+    pub async fn replace_entries(&self, deleted_ids: &[String], entries: Vec<EmbeddingEntry>) -> VectorDbResult<()> {
+        for entry in &entries {
+            entry.validate()?;
         }
+
+        let flushed_segment = {
+            let mut state = self.state.write().await;
+            let mut records: Vec<WalRecord> = deleted_ids
+                .iter()
+                .filter(|entry_id| state.contains(entry_id))
+                .map(|entry_id| WalRecord::Delete(entry_id.clone()))
+                .collect();
+            records.extend(entries.into_iter().map(|entry| WalRecord::Put(Box::new(entry))));
+            if records.is_empty() {
+                return Ok(());
+            }
+
+            let batch = WalRecord::Batch(records);
+            state.wal.append(std::slice::from_ref(&batch))?;
+            state.apply_wal_record(batch);
+            self.flush_if_needed(&mut state)?
+        };
+
+        self.finish_mutation(flushed_segment).await
     }
-    
+
     /// List all entry IDs in storage
     pub async fn list_entry_ids(&self) -> Vec<String> {
-        let index = self.index.read().await;
-        index.keys().cloned().collect()
+        let state = self.state.read().await;
+        state.index.keys().chain(state.memtable.keys()).cloned().collect()
     }
-    
+
+    /// List the IDs of the entries embedded from one file
+    ///
+    /// Served from the in-memory index, so no segment is read.
+    pub async fn list_entry_ids_for_file(&self, file_path: &str) -> Vec<String> {
+        self.state.read().await.entry_ids_for_file(file_path)
+    }
+
+    /// Flush the memtable into a new segment and truncate the write-ahead log
+    pub async fn flush(&self) -> VectorDbResult<()> {
+        let flushed_segment = {
+            let mut state = self.state.write().await;
+            self.flush_locked(&mut state)?
+        };
+
+        self.finish_mutation(flushed_segment).await
+    }
+
     /// Get storage metrics
     pub async fn get_metrics(&self) -> StorageMetrics {
         let metrics = self.metrics.read().await;
         metrics.clone()
     }
-    
+
     /// Get current storage configuration
     pub fn get_config(&self) -> &VectorStorageConfig {
         &self.config
     }
-    
+
     /// Update storage configuration
     pub fn update_config(&mut self, new_config: VectorStorageConfig) {
         self.config = new_config;
     }
-    
+
     /// Rebuild the index from existing storage files (async version)
+    ///
+    /// Segments are replayed from disk; entries still in the memtable and
+    /// deletes not yet flushed keep precedence over segment records.
     pub async fn rebuild_index_async(&self) -> VectorDbResult<()> {
-        if !self.storage_path.exists() {
-            return Ok(());
-        }
-        
-        let new_index = HashMap::new();
-        
-        // Scan storage directory for files
-        let entries = fs::read_dir(&self.storage_path).map_err(|e| VectorDbError::Storage {
-            message: format!("Failed to read storage directory: {}", e),
-        })?;
-        
-        for entry in entries {
-            let entry = entry.map_err(|e| VectorDbError::Storage {
-                message: format!("Failed to read directory entry: {}", e),
-            })?;
-            
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if file_name.starts_with("vector_") && file_name.ends_with(".json") {
-                        // For now, just add empty entries to avoid loading files during tests
-                        // In real implementation, this would load and index file contents
-                        eprintln!("📁 Found storage file: {}", file_name);
-                    }
-                }
-            }
-        }
-        
-        // Update index with new data
-        let mut index = self.index.write().await;
-        for (key, location) in new_index {
-            index.insert(key, location);
+        let mut state = self.state.write().await;
+        let (mut index, next_sequence) = Self::replay_segments(&self.storage_path)?;
+
+        for entry_id in state.pending_deletes.iter().chain(state.memtable.keys()) {
+            index.remove(entry_id);
         }
-        
-        eprintln!("🔍 Rebuilt index with {} entries from {} files", 
-                  index.len(), 
+        state.index = index;
+        state.next_sequence = state.next_sequence.max(next_sequence);
+
+        eprintln!("🔍 Rebuilt index with {} entries from {} files",
+                  state.len(),
                   self.count_storage_files().unwrap_or(0));
-        
+
         Ok(())
     }
-    
-    /// Compact storage by removing deleted entries and optimizing file sizes
+
+    /// Compact storage by merging all segments into a single snapshot segment
+    ///
+    /// Deleted and superseded records are dropped. The snapshot is written
+    /// before old segments are removed; if compaction is interrupted, replay
+    /// ignores every segment older than the newest snapshot.
     pub async fn compact_storage(&self) -> VectorDbResult<CompactionResult> {
         eprintln!("🗜️ Starting storage compaction...");
-        
-        let index = self.index.read().await;
-        let file_groups = self.group_entries_by_file(&index);
-        drop(index); // Release read lock
-        
+
         let mut compaction_result = CompactionResult::default();
-        let mut new_index = HashMap::new();
-        
-        for (file_name, entry_ids) in file_groups {
-            let file_path = self.storage_path.join(&file_name);
-            
-            // Load existing entries
-            let batch = self.load_batch(&file_path).await?;
-            
-            // Filter out deleted entries (not in current index)
-            let valid_entries: Vec<EmbeddingEntry> = batch.entries
-                .into_iter()
-                .filter(|entry| entry_ids.contains(&entry.id))
-                .collect();
-            
-            if valid_entries.is_empty() {
-                // Delete empty file
-                if fs::remove_file(&file_path).is_ok() {
-                    compaction_result.files_removed += 1;
-                }
-                continue;
+        let merged_segment = {
+            let mut state = self.state.write().await;
+            self.flush_locked(&mut state)?;
+
+            let segments = Self::list_segment_files(&self.storage_path)?;
+            let mut live_counts: HashMap<String, usize> = HashMap::new();
+            for location in state.index.values() {
+                *live_counts.entry(location.file_name.clone()).or_default() += 1;
             }
-            
-            if valid_entries.len() < batch.header.entry_count {
-                // Rewrite file with valid entries only
-                let new_file_name = self.generate_file_name();
-                let new_file_path = self.storage_path.join(&new_file_name);
-                
-                let new_header = StorageFileHeader::new(
-                    self.config.compression_algorithm.clone(),
-                    valid_entries.len(),
-                );
-                
-                let new_batch = StorageBatch {
-                    header: new_header,
-                    entries: valid_entries.clone(),
-                };
-                
-                let serialized_data = serde_json::to_vec(&new_batch)?;
-                let (compressed_data, _) = self.compress_and_checksum(&serialized_data)?;
-                
-                fs::write(&new_file_path, &compressed_data).map_err(|e| VectorDbError::Storage {
-                    message: format!("Failed to write compacted file: {}", e),
-                })?;
-                
-                // Update index for this file
-                for (i, entry) in valid_entries.iter().enumerate() {
-                    let location = FileLocation {
-                        file_name: new_file_name.clone(),
-                        entry_index: i,
-                        indexed_at: SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
-                    };
-                    new_index.insert(entry.id.clone(), location);
-                }
-                
-                // Remove old file
-                if fs::remove_file(&file_path).is_ok() {
-                    compaction_result.files_compacted += 1;
-                }
+
+            if segments.len() == 1 && self.is_segment_optimal(&segments[0].1, &live_counts)? {
+                // Single segment without dead records is already optimal
+                None
             } else {
-                // File is already optimal, keep existing index entries
-                for (i, entry) in valid_entries.iter().enumerate() {
-                    let location = FileLocation {
-                        file_name: file_name.clone(),
-                        entry_index: i,
-                        indexed_at: SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
-                    };
-                    new_index.insert(entry.id.clone(), location);
+                let mut entry_ids = state.index.keys().cloned().collect::<Vec<_>>();
+                entry_ids.sort();
+                let entries = self.read_segment_entries(&state.index, &entry_ids)?;
+
+                let mut new_index = HashMap::new();
+                let merged_path = if entries.is_empty() {
+                    None
+                } else {
+                    let sequence = state.next_sequence;
+                    let file_name = segment::segment_file_name(sequence);
+                    let path = self.storage_path.join(&file_name);
+                    let written = segment::write_segment(
+                        &path,
+                        sequence,
+                        true,
+                        &entries,
+                        Vec::new(),
+                        self.record_compression(),
+                        self.config.enable_checksums,
+                    )?;
+                    state.next_sequence += 1;
+
+                    for record in written.locations {
+                        new_index.insert(record.id.clone(), FileLocation::new(&file_name, record));
+                    }
+                    Some(path)
+                };
+
+                // Remove merged segments only after the snapshot is durable
+                for (_, file_name) in &segments {
+                    if fs::remove_file(self.storage_path.join(file_name)).is_ok() {
+                        if live_counts.contains_key(file_name) {
+                            compaction_result.files_compacted += 1;
+                        } else {
+                            compaction_result.files_removed += 1;
+                        }
+                    }
                 }
+                segment::sync_parent_dir(&self.storage_path.join(WAL_FILE_NAME));
+
+                state.index = new_index;
+                merged_path
             }
-        }
-        
-        // Update index with compacted results
-        let mut index = self.index.write().await;
-        *index = new_index;
-        compaction_result.entries_remaining = index.len();
-        
-        // Update metrics
-        if self.config.enable_metrics {
-            self.update_metrics().await;
-        }
-        
+        };
+
+        compaction_result.entries_remaining = self.state.read().await.len();
+        self.finish_mutation(merged_segment).await?;
+
         eprintln!("✅ Compaction completed: {} files removed, {} files compacted, {} entries remaining",
                   compaction_result.files_removed,
                   compaction_result.files_compacted,
                   compaction_result.entries_remaining);
-        
+
         Ok(compaction_result)
     }
-    
+
     /// Validate storage integrity
     pub async fn validate_integrity(&self) -> VectorDbResult<IntegrityReport> {
         let mut report = IntegrityReport::default();
-        let index = self.index.read().await;
-        
+        let state = self.state.read().await;
+
         // Group entries by file for efficient validation
-        let file_groups = self.group_entries_by_file(&index);
-        
+        let file_groups = self.group_entries_by_file(&state.index);
+
         for (file_name, expected_entry_ids) in file_groups {
             let file_path = self.storage_path.join(&file_name);
-            
-            match self.load_batch(&file_path).await {
+
+            // Opening the segment validates magic bytes and header compatibility
+            let loaded = SegmentReader::open(&file_path).and_then(|mut reader| {
+                reader.verify_checksum()?;
+                let entry_count = reader.header().file_header.entry_count;
+                Ok((entry_count, reader.read_all()?))
+            });
+
+            match loaded {
+                Ok((entry_count, entries)) => {
+                    // Validate entry count
+                    if entries.len() != entry_count {
+                        report.errors.push(format!(
+                            "File {}: entry count mismatch (header: {}, actual: {})",
+                            file_name, entry_count, entries.len()
+                        ));
+                    }
+
+                    // Validate live entries; superseded and deleted records wait for compaction
+                    let mut found_entries = 0;
+                    for (entry, record) in &entries {
+                        let is_live = state.index.get(&entry.id).is_some_and(|location| {
+                            location.file_name == file_name && location.record_offset == record.offset
+                        });
+                        if !is_live {
+                            continue;
+                        }
+
+                        found_entries += 1;
+                        if let Err(e) = entry.validate() {
+                            report.errors.push(format!("File {} entry {}: {}", file_name, entry.id, e));
+                        } else {
+                            report.valid_entries += 1;
+                        }
+                        report.indexed_entries += 1;
+                    }
+
+                    if found_entries != expected_entry_ids.len() {
+                        report.errors.push(format!(
+                            "File {}: {} indexed entries not found in segment",
+                            file_name, expected_entry_ids.len() - found_entries
+                        ));
+                    }
+
+                    report.valid_files += 1;
+                }
+                Err(e) => {
+                    report.corrupted_files += 1;
+                    report.errors.push(format!("File {}: Failed to load - {}", file_name, e));
+                }
+            }
+        }
+
+        // Entries still in the memtable
+        for entry in state.memtable.values() {
+            if let Err(e) = entry.validate() {
+                report.errors.push(format!("Write-ahead log entry {}: {}", entry.id, e));
+            } else {
+                report.valid_entries += 1;
+            }
+            report.indexed_entries += 1;
+        }
+
+        Ok(report)
+    }
+
+    // Private helper methods
+
+    /// Flush when enough mutations have accumulated in the memtable
+    fn flush_if_needed(&self, state: &mut StorageState) -> VectorDbResult<Option<PathBuf>> {
+        if state.pending_mutations() >= self.config.max_entries_per_file.max(1) {
+            self.flush_locked(state)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Write the memtable and pending deletes into a new segment
+    fn flush_locked(&self, state: &mut StorageState) -> VectorDbResult<Option<PathBuf>> {
+        if state.memtable.is_empty() && state.pending_deletes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut entries = state.memtable.values().cloned().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut tombstones = state.pending_deletes.iter().cloned().collect::<Vec<_>>();
+        tombstones.sort();
+
+        let sequence = state.next_sequence;
+        let file_name = segment::segment_file_name(sequence);
+        let file_path = self.storage_path.join(&file_name);
+        let written = segment::write_segment(
+            &file_path,
+            sequence,
+            false,
+            &entries,
+            tombstones,
+            self.record_compression(),
+            self.config.enable_checksums,
+        )?;
+        state.next_sequence += 1;
+
+        for record in written.locations {
+            state.index.insert(record.id.clone(), FileLocation::new(&file_name, record));
+        }
+        state.memtable.clear();
+        state.pending_deletes.clear();
+
+        // The segment is durable, the logged mutations are no longer needed
+        state.wal.reset()?;
+
+        eprintln!("📦 Flushed {} embedding entries and {} deletes to {}",
+                  entries.len(), written.header.tombstones.len(), file_name);
+        Ok(Some(file_path))
+    }
+
+    /// Update metrics and back up a newly written segment
+    async fn finish_mutation(&self, new_segment: Option<PathBuf>) -> VectorDbResult<()> {
+        if self.config.enable_metrics {
+            self.update_metrics().await;
+        }
+
+        if let Some(segment_path) = new_segment {
+            if self.config.auto_backup {
+                self.create_backup(&segment_path).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compression applied to segment records
+    fn record_compression(&self) -> CompressionAlgorithm {
+        if self.config.enable_compression {
+            self.config.compression_algorithm.clone()
+        } else {
+            CompressionAlgorithm::None
+        }
+    }
+
+    /// Whether a segment holds only live records and no tombstones
+    fn is_segment_optimal(&self, file_name: &str, live_counts: &HashMap<String, usize>) -> VectorDbResult<bool> {
+        let reader = SegmentReader::open(&self.storage_path.join(file_name))?;
+        let header = reader.header();
+        Ok(header.tombstones.is_empty()
+            && live_counts.get(file_name).copied().unwrap_or(0) == header.file_header.entry_count)
+    }
+
+    /// Read entries from segments, opening each segment once
+    fn read_segment_entries(
+        &self,
+        index: &HashMap<String, FileLocation>,
+        entry_ids: &[String],
+    ) -> VectorDbResult<Vec<EmbeddingEntry>> {
+        let mut readers: HashMap<String, SegmentReader> = HashMap::new();
+        let mut entries = Vec::with_capacity(entry_ids.len());
+
+        for entry_id in entry_ids {
+            if let Some(location) = index.get(entry_id) {
+                let reader = match readers.entry(location.file_name.clone()) {
+                    Entry::Occupied(slot) => slot.into_mut(),
+                    Entry::Vacant(slot) => {
+                        slot.insert(SegmentReader::open(&self.storage_path.join(&location.file_name))?)
+                    }
+                };
+                entries.push(reader.read_entry(location.record())?);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// List segment files sorted by sequence number, removing unfinished temporary segments
+    fn list_segment_files(storage_path: &Path) -> VectorDbResult<Vec<(u64, String)>> {
+        let entries = fs::read_dir(storage_path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to read storage directory: {}", e),
+        })?;
+
+        let mut segments = Vec::new();
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.ends_with(".seg.tmp") {
+                // Left behind by a write that never completed
+                let _ = fs::remove_file(entry.path());
+            } else if let Some(sequence) = segment::parse_segment_sequence(&file_name) {
+                segments.push((sequence, file_name));
+            }
+        }
+
+        segments.sort();
+        Ok(segments)
+    }
+
+    /// Build the segment index by replaying segments in sequence order
+    ///
+    /// Returns the index and the next free sequence number.
+    fn replay_segments(storage_path: &Path) -> VectorDbResult<(HashMap<String, FileLocation>, u64)> {
+        let segments = Self::list_segment_files(storage_path)?;
+        let next_sequence = segments.last().map(|(sequence, _)| sequence + 1).unwrap_or(1);
+
+        let mut readers = Vec::with_capacity(segments.len());
+        for (_, file_name) in segments {
+            match SegmentReader::open(&storage_path.join(&file_name)) {
+                Ok(reader) => readers.push((file_name, reader)),
+                Err(e) => eprintln!("⚠️ Skipping unreadable segment {}: {}", file_name, e),
+            }
+        }
+
+        // Segments older than the newest snapshot are leftovers of an interrupted compaction
+        if let Some(snapshot_position) = readers.iter().rposition(|(_, reader)| reader.header().snapshot) {
+            for (file_name, _) in readers.drain(..snapshot_position) {
+                eprintln!("🧹 Removing segment {} superseded by compaction snapshot", file_name);
+                let _ = fs::remove_file(storage_path.join(&file_name));
+            }
+        }
+
+        let mut index = HashMap::new();
+        for (file_name, mut reader) in readers {
+            for entry_id in &reader.header().tombstones {
+                index.remove(entry_id);
+            }
+
+            match reader.read_locations() {
+                Ok(locations) => {
+                    for record in locations {
+                        index.insert(record.id.clone(), FileLocation::new(&file_name, record));
+                    }
+                }
+                Err(e) => eprintln!("⚠️ Skipping unreadable records in segment {}: {}", file_name, e),
+            }
+        }
+
+        Ok((index, next_sequence))
+    }
+
+    /// Migrate JSON batch files (format 1.x) into a segment
+    ///
+    /// Entries already present in segments win over legacy copies. Migrated
+    /// files are moved to `backups/legacy` rather than deleted.
+    fn migrate_legacy_files(
+        storage_path: &Path,
+        config: &VectorStorageConfig,
+        index: &mut HashMap<String, FileLocation>,
+        next_sequence: &mut u64,
+    ) -> VectorDbResult<()> {
+        let mut legacy_files = fs::read_dir(storage_path)
+            .map_err(|e| VectorDbError::Storage {
+                message: format!("Failed to read storage directory: {}", e),
+            })?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && Self::is_legacy_storage_file(path))
+            .collect::<Vec<_>>();
+
+        if legacy_files.is_empty() {
+            return Ok(());
+        }
+        // File names start with a millisecond timestamp, later batches override earlier ones
+        legacy_files.sort();
+
+        let mut migrated: HashMap<String, EmbeddingEntry> = HashMap::new();
+        let mut migrated_files = Vec::new();
+        for path in legacy_files {
+            match Self::load_legacy_batch(&path) {
                 Ok(batch) => {
-                    // Validate header compatibility
-                    if let Err(e) = batch.header.validate_compatibility() {
-                        report.errors.push(format!("File {}: {}", file_name, e));
-                        continue;
-                    }
-                    
-                    // Validate entry count
-                    if batch.entries.len() != batch.header.entry_count {
-                        report.errors.push(format!(
-                            "File {}: entry count mismatch (header: {}, actual: {})",
-                            file_name, batch.header.entry_count, batch.entries.len()
-                        ));
-                    }
-                    
-                    // Validate each entry
-                    for (i, entry) in batch.entries.iter().enumerate() {
-                        if let Err(e) = entry.validate() {
-                            report.errors.push(format!("File {} entry {}: {}", file_name, i, e));
-                        } else {
-                            report.valid_entries += 1;
-                        }
-                        
-                        // Check if entry is in index
-                        if expected_entry_ids.contains(&entry.id) {
-                            report.indexed_entries += 1;
-                        } else {
-                            report.orphaned_entries += 1;
+                    for entry in batch.entries {
+                        if !index.contains_key(&entry.id) {
+                            migrated.insert(entry.id.clone(), entry);
                         }
                     }
-                    
-                    report.valid_files += 1;
-                }
-                Err(e) => {
-                    report.corrupted_files += 1;
-                    report.errors.push(format!("File {}: Failed to load - {}", file_name, e));
+                    migrated_files.push(path);
                 }
+                Err(e) => eprintln!("⚠️ Cannot migrate legacy storage file {}: {}", path.display(), e),
             }
         }
-        
-        Ok(report)
-    }
-    
-    // Private helper methods
-    
-    /// Load a storage batch from file
-    async fn load_batch(&self, file_path: &Path) -> VectorDbResult<StorageBatch> {
-        let compressed_data = fs::read(file_path).map_err(|e| VectorDbError::Storage {
-            message: format!("Failed to read storage file: {}", e),
-        })?;
-        
-        let (decompressed_data, _) = self.decompress_and_verify(&compressed_data)?;
-        let batch: StorageBatch = serde_json::from_slice(&decompressed_data)?;
-        
-        // Validate header compatibility
-        batch.header.validate_compatibility()?;
-        
-        Ok(batch)
-    }
-    
-    /// Compress data and compute checksum
-    fn compress_and_checksum(&self, data: &[u8]) -> VectorDbResult<(Vec<u8>, Option<String>)> {
-        let compressed_data = match &self.config.compression_algorithm {
-            CompressionAlgorithm::None => data.to_vec(),
-            CompressionAlgorithm::Gzip => {
-                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-                encoder.write_all(data).map_err(|e| VectorDbError::Compression {
-                    message: format!("Gzip compression failed: {}", e),
-                })?;
-                encoder.finish().map_err(|e| VectorDbError::Compression {
-                    message: format!("Gzip compression failed: {}", e),
-                })?
+
+        if !migrated.is_empty() {
+            let mut entries = migrated.into_values().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.id.cmp(&b.id));
+
+            let sequence = *next_sequence;
+            let file_name = segment::segment_file_name(sequence);
+            let compression = if config.enable_compression {
+                config.compression_algorithm.clone()
+            } else {
+                CompressionAlgorithm::None
+            };
+            let written = segment::write_segment(
+                &storage_path.join(&file_name),
+                sequence,
+                false,
+                &entries,
+                Vec::new(),
+                compression,
+                config.enable_checksums,
+            )?;
+            *next_sequence += 1;
+
+            for record in written.locations {
+                index.insert(record.id.clone(), FileLocation::new(&file_name, record));
             }
-            CompressionAlgorithm::Lz4 => {
-                let mut encoder = EncoderBuilder::new()
-                    .level(1) // Fast compression level
-                    .build(Vec::new())
-                    .map_err(|e| VectorDbError::Compression {
-                        message: format!("LZ4 encoder creation failed: {}", e),
-                    })?;
-                
-                encoder.write_all(data).map_err(|e| VectorDbError::Compression {
-                    message: format!("LZ4 compression failed: {}", e),
-                })?;
-                
-                let (compressed_data, result) = encoder.finish();
-                result.map_err(|e| VectorDbError::Compression {
-                    message: format!("LZ4 compression finalization failed: {}", e),
+            eprintln!("📦 Migrated {} entries from {} JSON storage files to {}",
+                      entries.len(), migrated_files.len(), file_name);
+        }
+
+        // Keep the originals until the user deletes them
+        let legacy_backup_dir = storage_path.join("backups").join(LEGACY_BACKUP_DIR);
+        fs::create_dir_all(&legacy_backup_dir).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to create legacy backup directory: {}", e),
+        })?;
+        for path in migrated_files {
+            if let Some(file_name) = path.file_name() {
+                fs::rename(&path, legacy_backup_dir.join(file_name)).map_err(|e| VectorDbError::Storage {
+                    message: format!("Failed to move migrated storage file: {}", e),
                 })?;
-                
-                compressed_data
             }
-        };
-        
-        let checksum = if self.config.enable_checksums {
-            let mut hasher = Sha256::new();
-            hasher.update(&compressed_data);
-            Some(format!("{:x}", hasher.finalize()))
-        } else {
-            None
-        };
-        
-        Ok((compressed_data, checksum))
+        }
+
+        Ok(())
     }
-    
-    /// Decompress data and verify checksum
-    fn decompress_and_verify(&self, compressed_data: &[u8]) -> VectorDbResult<(Vec<u8>, Option<String>)> {
-        let decompressed_data = match &self.config.compression_algorithm {
-            CompressionAlgorithm::None => compressed_data.to_vec(),
-            CompressionAlgorithm::Gzip => {
-                let mut decoder = GzDecoder::new(compressed_data);
-                let mut decompressed = Vec::new();
-                decoder.read_to_end(&mut decompressed).map_err(|e| VectorDbError::Compression {
-                    message: format!("Gzip decompression failed: {}", e),
-                })?;
-                decompressed
-            }
-            CompressionAlgorithm::Lz4 => {
-                let mut decoder = Decoder::new(compressed_data)
-                    .map_err(|e| VectorDbError::Compression {
-                        message: format!("LZ4 decoder creation failed: {}", e),
-                    })?;
-                
-                let mut decompressed = Vec::new();
-                decoder.read_to_end(&mut decompressed).map_err(|e| VectorDbError::Compression {
-                    message: format!("LZ4 decompression failed: {}", e),
-                })?;
-                
-                decompressed
-            }
-        };
-        
-        let checksum = if self.config.enable_checksums {
-            let mut hasher = Sha256::new();
-            hasher.update(compressed_data);
-            Some(format!("{:x}", hasher.finalize()))
+
+    /// Whether a path is a JSON batch file written by format 1.x
+    fn is_legacy_storage_file(path: &Path) -> bool {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        file_name.starts_with("vector_") &&
+        (file_name.ends_with(".json") ||
+         file_name.ends_with(".json.gz") ||
+         file_name.ends_with(".json.lz4"))
+    }
+
+    /// Load a legacy JSON storage batch from file
+    fn load_legacy_batch(file_path: &Path) -> VectorDbResult<StorageBatch> {
+        let compressed_data = fs::read(file_path).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to read storage file: {}", e),
+        })?;
+
+        // Legacy files were named after their compression algorithm
+        let file_name = file_path.to_string_lossy();
+        let compression = if file_name.ends_with(".gz") {
+            CompressionAlgorithm::Gzip
+        } else if file_name.ends_with(".lz4") {
+            CompressionAlgorithm::Lz4
         } else {
-            None
+            CompressionAlgorithm::None
         };
-        
-        Ok((decompressed_data, checksum))
-    }
-    
-    /// Generate a unique file name for storage
-    fn generate_file_name(&self) -> String {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis();
-        
-        let extension = self.config.compression_algorithm.file_extension();
-        // Use timestamp and a simple counter instead of random for better determinism
-        let counter = (timestamp % 10000) as u32;
-        format!("vector_{}_{}.json{}", timestamp, counter, extension)
+
+        let decompressed_data = decompress_bytes(&compression, &compressed_data)?;
+        let batch: StorageBatch = serde_json::from_slice(&decompressed_data)?;
+
+        if !DataVersion::JSON_BATCH.is_compatible(&batch.header.version) {
+            return Err(VectorDbError::VersionIncompatible {
+                expected: DataVersion::JSON_BATCH.version_string(),
+                found: batch.header.version.version_string(),
+            });
+        }
+
+        Ok(batch)
     }
-    
-    
+
     /// Group entries by their storage file
     fn group_entries_by_file(&self, index: &HashMap<String, FileLocation>) -> HashMap<String, Vec<String>> {
         let mut file_groups: HashMap<String, Vec<String>> = HashMap::new();
-        
+
         for (entry_id, location) in index {
             file_groups
                 .entry(location.file_name.clone())
                 .or_default()
                 .push(entry_id.clone());
         }
-        
+
         file_groups
     }
-    
-    /// Count storage files in directory
+
+    /// Count segment files in directory
     fn count_storage_files(&self) -> VectorDbResult<usize> {
         let entries = fs::read_dir(&self.storage_path).map_err(|e| VectorDbError::Storage {
             message: format!("Failed to read storage directory: {}", e),
         })?;
-        
+
         let count = entries
             .filter_map(|entry| entry.ok())
             .filter(|entry| {
-                entry.path().is_file() && 
-                entry.file_name().to_string_lossy().starts_with("vector_")
+                entry.path().is_file() &&
+                segment::is_segment_file_name(&entry.file_name().to_string_lossy())
             })
             .count();
-        
+
         Ok(count)
     }
-    
+
     /// Update storage metrics
     async fn update_metrics(&self) {
-        let index = self.index.read().await;
-        let total_entries = index.len();
+        let (total_entries, wal_size) = {
+            let state = self.state.read().await;
+            (state.len(), state.wal.len_bytes() as usize)
+        };
         let file_count = self.count_storage_files().unwrap_or(0);
-        
+
         // Calculate actual storage sizes
         let (total_size, uncompressed_size) = self.calculate_storage_sizes().await.unwrap_or((0, 0));
-        
+
         let mut metrics = self.metrics.write().await;
-        metrics.update(total_entries, file_count, total_size, uncompressed_size);
+        metrics.update(total_entries, file_count, total_size + wal_size, uncompressed_size + wal_size);
     }
-    
-    /// Calculate actual storage sizes by scanning files
+
+    /// Calculate actual segment sizes by scanning files
     async fn calculate_storage_sizes(&self) -> VectorDbResult<(usize, usize)> {
         let mut total_compressed_size = 0;
         let mut total_uncompressed_size = 0;
-        
-        // Read storage directory
-        let entries = fs::read_dir(&self.storage_path).map_err(|e| VectorDbError::Storage {
-            message: format!("Failed to read storage directory: {}", e),
-        })?;
-        
-        for entry in entries {
-            let entry = entry.map_err(|e| VectorDbError::Storage {
-                message: format!("Failed to read directory entry: {}", e),
-            })?;
-            
-            let path = entry.path();
-            if path.is_file() {
-                let file_name = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("");
-                
-                // Process vector storage files
-                if file_name.starts_with("vector_") && file_name.contains(".json") {
-                    // Get compressed file size
-                    if let Ok(metadata) = fs::metadata(&path) {
-                        total_compressed_size += metadata.len() as usize;
-                        
-                        // Try to get uncompressed size from file header
-                        if let Ok(compressed_data) = fs::read(&path) {
-                            match self.load_batch_header_only(&compressed_data) {
-                                Ok(header) => {
-                                    total_uncompressed_size += header.uncompressed_size;
-                                }
-                                Err(_) => {
-                                    // If we can't read header, estimate uncompressed size
-                                    // Use a conservative multiplier based on compression algorithm
-                                    let multiplier = match &self.config.compression_algorithm {
-                                        CompressionAlgorithm::None => 1.0,
-                                        CompressionAlgorithm::Gzip => 3.0, // Typical 3:1 ratio
-                                        CompressionAlgorithm::Lz4 => 2.0,  // Typical 2:1 ratio
-                                    };
-                                    total_uncompressed_size += (compressed_data.len() as f64 * multiplier) as usize;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+
+        for (_, file_name) in Self::list_segment_files(&self.storage_path)? {
+            let path = self.storage_path.join(&file_name);
+            let file_size = match fs::metadata(&path) {
+                Ok(metadata) => metadata.len() as usize,
+                Err(_) => continue,
+            };
+            total_compressed_size += file_size;
+
+            // Uncompressed size is recorded in the segment header
+            total_uncompressed_size += match SegmentReader::open(&path) {
+                Ok(reader) => reader.header().file_header.uncompressed_size,
+                Err(_) => file_size,
+            };
         }
-        
+
         Ok((total_compressed_size, total_uncompressed_size))
     }
-    
-    /// Load only the header from compressed data for size estimation
-    fn load_batch_header_only(&self, compressed_data: &[u8]) -> VectorDbResult<StorageFileHeader> {
-        let (decompressed_data, _) = self.decompress_and_verify(compressed_data)?;
-        
-        // Try to deserialize just the header portion
-        // First, deserialize to a generic Value to extract header
-        let value: serde_json::Value = serde_json::from_slice(&decompressed_data)?;
-        
-        if let Some(header_value) = value.get("header") {
-            let header: StorageFileHeader = serde_json::from_value(header_value.clone())?;
-            header.validate_compatibility()?;
-            Ok(header)
-        } else {
-            Err(VectorDbError::Storage {
-                message: "Missing header in storage file".to_string(),
-            })
-        }
-    }
-    
+
     /// Create a backup of a storage file
     async fn create_backup(&self, file_path: &Path) -> VectorDbResult<()> {
         if !self.config.auto_backup {
             return Ok(());
         }
-        
+
         let backup_dir = self.storage_path.join("backups");
         if !backup_dir.exists() {
             fs::create_dir_all(&backup_dir).map_err(|e| VectorDbError::Storage {
                 message: format!("Failed to create backup directory: {}", e),
             })?;
         }
-        
+
         if let Some(file_name) = file_path.file_name() {
             let timestamp = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -690,18 +934,18 @@ impl VectorStorage {
                 .as_secs();
             let backup_name = format!("{}_{}.backup", timestamp, file_name.to_string_lossy());
             let backup_path = backup_dir.join(backup_name);
-            
+
             fs::copy(file_path, &backup_path).map_err(|e| VectorDbError::Storage {
                 message: format!("Failed to create backup: {}", e),
             })?;
-            
+
             // Clean up old backups
             self.cleanup_old_backups(&backup_dir).await;
         }
-        
+
         Ok(())
     }
-    
+
     /// Clean up old backup files beyond the configured limit
     async fn cleanup_old_backups(&self, backup_dir: &Path) {
         if let Ok(entries) = fs::read_dir(backup_dir) {
@@ -709,14 +953,14 @@ impl VectorStorage {
                 .filter_map(|entry| entry.ok())
                 .filter(|entry| entry.file_name().to_string_lossy().ends_with(".backup"))
                 .collect();
-            
+
             // Sort by modification time (newest first)
             backup_files.sort_by(|a, b| {
                 let time_a = a.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
                 let time_b = b.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
                 time_b.cmp(&time_a)
             });
-            
+
             // Remove excess backups
             for backup_file in backup_files.iter().skip(self.config.max_backups) {
                 let _ = fs::remove_file(backup_file.path());
@@ -725,6 +969,73 @@ impl VectorStorage {
     }
 }
 
+/// Compress data with the given algorithm
+pub(crate) fn compress_bytes(algorithm: &CompressionAlgorithm, data: &[u8]) -> VectorDbResult<Vec<u8>> {
+    let compressed_data = match algorithm {
+        CompressionAlgorithm::None => data.to_vec(),
+        CompressionAlgorithm::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).map_err(|e| VectorDbError::Compression {
+                message: format!("Gzip compression failed: {}", e),
+            })?;
+            encoder.finish().map_err(|e| VectorDbError::Compression {
+                message: format!("Gzip compression failed: {}", e),
+            })?
+        }
+        CompressionAlgorithm::Lz4 => {
+            let mut encoder = EncoderBuilder::new()
+                .level(1) // Fast compression level
+                .build(Vec::new())
+                .map_err(|e| VectorDbError::Compression {
+                    message: format!("LZ4 encoder creation failed: {}", e),
+                })?;
+
+            encoder.write_all(data).map_err(|e| VectorDbError::Compression {
+                message: format!("LZ4 compression failed: {}", e),
+            })?;
+
+            let (compressed_data, result) = encoder.finish();
+            result.map_err(|e| VectorDbError::Compression {
+                message: format!("LZ4 compression finalization failed: {}", e),
+            })?;
+
+            compressed_data
+        }
+    };
+
+    Ok(compressed_data)
+}
+
+/// Decompress data written with the given algorithm
+pub(crate) fn decompress_bytes(algorithm: &CompressionAlgorithm, compressed_data: &[u8]) -> VectorDbResult<Vec<u8>> {
+    let decompressed_data = match algorithm {
+        CompressionAlgorithm::None => compressed_data.to_vec(),
+        CompressionAlgorithm::Gzip => {
+            let mut decoder = GzDecoder::new(compressed_data);
+            let mut decompressed = Vec::new();
+            decoder.read_to_end(&mut decompressed).map_err(|e| VectorDbError::Compression {
+                message: format!("Gzip decompression failed: {}", e),
+            })?;
+            decompressed
+        }
+        CompressionAlgorithm::Lz4 => {
+            let mut decoder = Decoder::new(compressed_data)
+                .map_err(|e| VectorDbError::Compression {
+                    message: format!("LZ4 decoder creation failed: {}", e),
+                })?;
+
+            let mut decompressed = Vec::new();
+            decoder.read_to_end(&mut decompressed).map_err(|e| VectorDbError::Compression {
+                message: format!("LZ4 decompression failed: {}", e),
+            })?;
+
+            decompressed
+        }
+    };
+
+    Ok(decompressed_data)
+}
+
 /// Result of storage compaction operation
 #[derive(Debug, Default, Clone)]
 pub struct CompactionResult {
@@ -921,13 +1232,15 @@ mod tests {
     #[test]
     fn test_file_location() {
         let location = FileLocation {
-            file_name: "test_file.json".to_string(),
-            entry_index: 42,
+            file_name: "segment_0000000001.seg".to_string(),
+            file_path: "/test/file1.md".to_string(),
+            record_offset: 4096,
+            record_len: 42,
             indexed_at: 1234567890,
         };
         
-        assert_eq!(location.file_name, "test_file.json");
-        assert_eq!(location.entry_index, 42);
+        assert_eq!(location.file_name, "segment_0000000001.seg");
+        assert_eq!(location.record(), RecordLocation { offset: 4096, len: 42 });
         assert_eq!(location.indexed_at, 1234567890);
     }
     
@@ -951,42 +1264,23 @@ mod tests {
     /// Test LZ4 compression and decompression functionality
     #[test]
     fn test_lz4_compression_unit() {
-        let temp_dir = TempDir::new().unwrap();
-        let config = VectorStorageConfig {
-            storage_dir: temp_dir.path().to_string_lossy().to_string(),
-            enable_compression: true,
-            compression_algorithm: CompressionAlgorithm::Lz4,
-            max_entries_per_file: 100,
-            enable_checksums: true,
-            auto_backup: false,
-            max_backups: 0,
-            enable_metrics: false,
-            enable_vector_compression: false,
-            vector_compression_algorithm: crate::vector_db::types::VectorCompressionAlgorithm::None,
-            enable_lazy_loading: false,
-            lazy_loading_threshold: 1000,
-        };
-        
-        let storage = VectorStorage::new(config).unwrap();
-        
         // Test data that should compress well
         let test_data = b"This is a test string for LZ4 compression. It contains repeated patterns. Repeated patterns. Repeated patterns.";
         
         // Test compression
-        let compress_result = storage.compress_and_checksum(test_data);
+        let compress_result = compress_bytes(&CompressionAlgorithm::Lz4, test_data);
         assert!(compress_result.is_ok(), "LZ4 compression should succeed");
-        let (compressed_data, checksum) = compress_result.unwrap();
+        let compressed_data = compress_result.unwrap();
         
         // Compressed data should be smaller than original (for this repetitive content)
         assert!(compressed_data.len() < test_data.len(), 
                "Compressed size ({}) should be smaller than original size ({})", 
                compressed_data.len(), test_data.len());
-        assert!(checksum.is_some(), "Checksum should be generated when enabled");
         
         // Test decompression
-        let decompress_result = storage.decompress_and_verify(&compressed_data);
+        let decompress_result = decompress_bytes(&CompressionAlgorithm::Lz4, &compressed_data);
         assert!(decompress_result.is_ok(), "LZ4 decompression should succeed");
-        let (decompressed_data, _) = decompress_result.unwrap();
+        let decompressed_data = decompress_result.unwrap();
         
         // Verify data integrity
         assert_eq!(decompressed_data, test_data.to_vec(), 
@@ -1000,41 +1294,11 @@ mod tests {
     /// Test Gzip vs LZ4 compression comparison
     #[test]
     fn test_compression_algorithms_comparison() {
-        let temp_dir = TempDir::new().unwrap();
-        let base_config = VectorStorageConfig {
-            storage_dir: temp_dir.path().to_string_lossy().to_string(),
-            enable_compression: true,
-            compression_algorithm: CompressionAlgorithm::None, // Will be overridden
-            max_entries_per_file: 100,
-            enable_checksums: false, // Disable for simpler comparison
-            auto_backup: false,
-            max_backups: 0,
-            enable_metrics: false,
-            enable_vector_compression: false,
-            vector_compression_algorithm: crate::vector_db::types::VectorCompressionAlgorithm::None,
-            enable_lazy_loading: false,
-            lazy_loading_threshold: 1000,
-        };
-        
         let test_data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(50).into_bytes();
         
-        // Test None compression
-        let mut config_none = base_config.clone();
-        config_none.compression_algorithm = CompressionAlgorithm::None;
-        let storage_none = VectorStorage::new(config_none).unwrap();
-        let (none_data, _) = storage_none.compress_and_checksum(&test_data).unwrap();
-        
-        // Test Gzip compression
-        let mut config_gzip = base_config.clone();
-        config_gzip.compression_algorithm = CompressionAlgorithm::Gzip;
-        let storage_gzip = VectorStorage::new(config_gzip).unwrap();
-        let (gzip_data, _) = storage_gzip.compress_and_checksum(&test_data).unwrap();
-        
-        // Test LZ4 compression
-        let mut config_lz4 = base_config.clone();
-        config_lz4.compression_algorithm = CompressionAlgorithm::Lz4;
-        let storage_lz4 = VectorStorage::new(config_lz4).unwrap();
-        let (lz4_data, _) = storage_lz4.compress_and_checksum(&test_data).unwrap();
+        let none_data = compress_bytes(&CompressionAlgorithm::None, &test_data).unwrap();
+        let gzip_data = compress_bytes(&CompressionAlgorithm::Gzip, &test_data).unwrap();
+        let lz4_data = compress_bytes(&CompressionAlgorithm::Lz4, &test_data).unwrap();
         
         // Verify sizes
         assert_eq!(none_data.len(), test_data.len(), "None compression should not change size");
@@ -1049,13 +1313,9 @@ mod tests {
                 (1.0 - lz4_data.len() as f64 / test_data.len() as f64) * 100.0);
         
         // Verify decompression works for all
-        let (none_decompressed, _) = storage_none.decompress_and_verify(&none_data).unwrap();
-        let (gzip_decompressed, _) = storage_gzip.decompress_and_verify(&gzip_data).unwrap();
-        let (lz4_decompressed, _) = storage_lz4.decompress_and_verify(&lz4_data).unwrap();
-        
-        assert_eq!(none_decompressed, test_data);
-        assert_eq!(gzip_decompressed, test_data);
-        assert_eq!(lz4_decompressed, test_data);
+        assert_eq!(decompress_bytes(&CompressionAlgorithm::None, &none_data).unwrap(), test_data);
+        assert_eq!(decompress_bytes(&CompressionAlgorithm::Gzip, &gzip_data).unwrap(), test_data);
+        assert_eq!(decompress_bytes(&CompressionAlgorithm::Lz4, &lz4_data).unwrap(), test_data);
         
         println!("✅ All compression algorithms work correctly");
     }
@@ -1086,9 +1346,9 @@ mod tests {
         // But we can test the method exists and the storage directory exists
         assert!(temp_dir.path().exists(), "Storage directory should exist");
         
-        // Test load_batch_header_only with invalid data
+        // Test segment header parsing with invalid data
         let invalid_data = b"invalid json data";
-        let header_result = storage.load_batch_header_only(invalid_data);
+        let header_result = segment::read_header(&mut &invalid_data[..]);
         assert!(header_result.is_err(), "Invalid data should fail header parsing");
         
         // Test that the method handles compression algorithm multipliers correctly
@@ -1124,15 +1384,225 @@ mod tests {
         };
         
         let storage = VectorStorage::new(config).unwrap();
-        let file_name = storage.generate_file_name();
+        assert_eq!(storage.record_compression(), CompressionAlgorithm::Lz4);
         
-        assert!(file_name.ends_with(".lz4"), 
-               "Generated filename should end with .lz4 for LZ4 compression: {}", file_name);
-        assert!(file_name.starts_with("vector_"), 
-               "Generated filename should start with vector_: {}", file_name);
-        assert!(file_name.contains(".json"), 
-               "Generated filename should contain .json: {}", file_name);
+        // Segment files are named by sequence number, independent of compression
+        let file_name = segment::segment_file_name(3);
+        assert!(file_name.starts_with("segment_"), 
+               "Segment filename should start with segment_: {}", file_name);
+        assert!(file_name.ends_with(".seg"), 
+               "Segment filename should end with .seg: {}", file_name);
         
         println!("✅ Compression file extensions test passed: {}", file_name);
     }
-}
\ No newline at end of file
+
+    fn create_persistent_config(storage_dir: &Path, max_entries_per_file: usize) -> VectorStorageConfig {
+        VectorStorageConfig {
+            storage_dir: storage_dir.to_string_lossy().to_string(),
+            compression_algorithm: CompressionAlgorithm::Lz4,
+            max_entries_per_file,
+            auto_backup: false,
+            enable_metrics: false,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries_survive_reopen_from_wal() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_persistent_config(temp_dir.path(), 100);
+        let entry = create_test_entry("1", "/test/file1.md", "Logged but never flushed");
+
+        {
+            let storage = VectorStorage::new(config.clone()).unwrap();
+            storage.store_entries(vec![entry.clone()]).await.unwrap();
+            assert_eq!(storage.count_storage_files().unwrap(), 0);
+        }
+
+        let reopened = VectorStorage::new(config).unwrap();
+        let retrieved = reopened.retrieve_entry(&entry.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.vector, entry.vector);
+        assert_eq!(retrieved.metadata.file_path, "/test/file1.md");
+    }
+
+    #[tokio::test]
+    async fn test_flush_and_deletes_survive_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_persistent_config(temp_dir.path(), 2);
+        let entries = [
+            create_test_entry("1", "/test/file1.md", "First test document"),
+            create_test_entry("2", "/test/file2.md", "Second test document"),
+            create_test_entry("3", "/test/file3.md", "Third test document"),
+        ];
+
+        {
+            let storage = VectorStorage::new(config.clone()).unwrap();
+            // Two entries reach the flush threshold and become a segment
+            storage.store_entries(entries[..2].to_vec()).await.unwrap();
+            assert_eq!(storage.count_storage_files().unwrap(), 1);
+
+            storage.store_entries(vec![entries[2].clone()]).await.unwrap();
+            assert!(storage.delete_entry(&entries[0].id).await.unwrap());
+            assert!(!storage.delete_entry(&entries[0].id).await.unwrap());
+            // The delete is the second pending mutation and triggers a flush with a tombstone
+            assert_eq!(storage.count_storage_files().unwrap(), 2);
+        }
+
+        let reopened = VectorStorage::new(config).unwrap();
+        let mut ids = reopened.list_entry_ids().await;
+        ids.sort();
+        let mut expected = vec![entries[1].id.clone(), entries[2].id.clone()];
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert!(reopened.retrieve_entry(&entries[0].id).await.unwrap().is_none());
+
+        let retrieved = reopened.retrieve_entries(&expected).await.unwrap();
+        assert_eq!(retrieved.len(), 2);
+        assert!(reopened.validate_integrity().await.unwrap().is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_list_entry_ids_for_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_persistent_config(temp_dir.path(), 2);
+        let entries = [
+            create_test_entry("1", "/test/file1.md", "First chunk of file one"),
+            create_test_entry("2", "/test/file1.md", "Second chunk of file one"),
+            create_test_entry("3", "/test/file2.md", "Only chunk of file two"),
+        ];
+
+        {
+            let storage = VectorStorage::new(config.clone()).unwrap();
+            // The first two entries are flushed, the third stays in the memtable
+            storage.store_entries(entries.to_vec()).await.unwrap();
+            let mut ids = storage.list_entry_ids_for_file("/test/file1.md").await;
+            ids.sort();
+            let mut expected = vec![entries[0].id.clone(), entries[1].id.clone()];
+            expected.sort();
+            assert_eq!(ids, expected);
+            assert_eq!(storage.list_entry_ids_for_file("/test/file2.md").await, vec![entries[2].id.clone()]);
+        }
+
+        let reopened = VectorStorage::new(config).unwrap();
+        assert!(reopened.delete_entry(&entries[0].id).await.unwrap());
+        assert_eq!(reopened.list_entry_ids_for_file("/test/file1.md").await, vec![entries[1].id.clone()]);
+        assert!(reopened.list_entry_ids_for_file("/test/missing.md").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replace_entries_survives_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_persistent_config(temp_dir.path(), 100);
+        let original = create_test_entry("1", "/test/old.md", "Chunk that moves with its file");
+        let mut moved = original.clone();
+        moved.metadata.file_path = "/test/new.md".to_string();
+        moved.id = EmbeddingEntry::generate_id(
+            "/test/new.md",
+            &moved.metadata.chunk_id,
+            &moved.metadata.text_hash,
+            &moved.metadata.model_name,
+        );
+
+        {
+            let storage = VectorStorage::new(config.clone()).unwrap();
+            storage.store_entries(vec![original.clone()]).await.unwrap();
+            storage.replace_entries(std::slice::from_ref(&original.id), vec![moved.clone()]).await.unwrap();
+            assert!(storage.list_entry_ids_for_file("/test/old.md").await.is_empty());
+        }
+
+        let reopened = VectorStorage::new(config).unwrap();
+        assert_eq!(reopened.list_entry_ids().await, vec![moved.id.clone()]);
+        assert_eq!(reopened.list_entry_ids_for_file("/test/new.md").await, vec![moved.id.clone()]);
+        let retrieved = reopened.retrieve_entry(&moved.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.vector, original.vector);
+    }
+
+    #[tokio::test]
+    async fn test_compaction_merges_segments() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_persistent_config(temp_dir.path(), 1);
+        let storage = VectorStorage::new(config.clone()).unwrap();
+
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            let entry = create_test_entry(&i.to_string(), "/test/file.md", &format!("Document {}", i));
+            ids.extend(storage.store_entries(vec![entry]).await.unwrap());
+        }
+        storage.delete_entry(&ids[0]).await.unwrap();
+        assert_eq!(storage.count_storage_files().unwrap(), 5);
+
+        let result = storage.compact_storage().await.unwrap();
+        assert_eq!(result.entries_remaining, 3);
+        assert_eq!(result.files_compacted + result.files_removed, 5);
+        assert_eq!(storage.count_storage_files().unwrap(), 1);
+
+        // A second compaction has nothing to merge
+        let result = storage.compact_storage().await.unwrap();
+        assert_eq!(result.files_compacted + result.files_removed, 0);
+
+        drop(storage);
+        let reopened = VectorStorage::new(config).unwrap();
+        assert_eq!(reopened.list_entry_ids().await.len(), 3);
+        assert_eq!(reopened.retrieve_entries(&ids).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replay_ignores_segments_before_snapshot() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_entry("1", "/test/file.md", "Deleted before compaction");
+
+        // Leftover of an interrupted compaction: an old segment still holding a deleted entry
+        segment::write_segment(
+            &temp_dir.path().join(segment::segment_file_name(1)),
+            1, false, std::slice::from_ref(&entry), Vec::new(), CompressionAlgorithm::None, false,
+        ).unwrap();
+        segment::write_segment(
+            &temp_dir.path().join(segment::segment_file_name(2)),
+            2, true, &[], Vec::new(), CompressionAlgorithm::None, false,
+        ).unwrap();
+
+        let storage = VectorStorage::new(create_persistent_config(temp_dir.path(), 100)).unwrap();
+        assert!(storage.list_entry_ids().await.is_empty());
+        assert!(!temp_dir.path().join(segment::segment_file_name(1)).exists());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_json_files_are_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let entries = vec![
+            create_test_entry("1", "/test/file1.md", "First legacy document"),
+            create_test_entry("2", "/test/file2.md", "Second legacy document"),
+        ];
+
+        let mut header = StorageFileHeader::new(CompressionAlgorithm::Gzip, entries.len());
+        header.version = DataVersion::JSON_BATCH;
+        let batch = StorageBatch { header, entries: entries.clone() };
+        let compressed = compress_bytes(&CompressionAlgorithm::Gzip, &serde_json::to_vec(&batch).unwrap()).unwrap();
+        fs::write(temp_dir.path().join("vector_1700000000000_0.json.gz"), compressed).unwrap();
+
+        let storage = VectorStorage::new(create_persistent_config(temp_dir.path(), 100)).unwrap();
+        let retrieved = storage.retrieve_entry(&entries[1].id).await.unwrap().unwrap();
+        assert_eq!(retrieved.vector, entries[1].vector);
+        assert_eq!(storage.list_entry_ids().await.len(), 2);
+        assert_eq!(storage.count_storage_files().unwrap(), 1);
+
+        assert!(!temp_dir.path().join("vector_1700000000000_0.json.gz").exists());
+        assert!(temp_dir.path().join("backups").join(LEGACY_BACKUP_DIR)
+            .join("vector_1700000000000_0.json.gz").exists());
+    }
+
+    #[test]
+    fn test_legacy_batch_with_unknown_version_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut header = StorageFileHeader::new(CompressionAlgorithm::None, 0);
+        header.version = DataVersion { major: 0, minor: 9, patch: 0 };
+        let batch = StorageBatch { header, entries: Vec::new() };
+        let path = temp_dir.path().join("vector_1_0.json");
+        fs::write(&path, serde_json::to_vec(&batch).unwrap()).unwrap();
+
+        assert!(matches!(
+            VectorStorage::load_legacy_batch(&path),
+            Err(VectorDbError::VersionIncompatible { .. })
+        ));
+    }
+}
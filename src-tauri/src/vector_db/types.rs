@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use thiserror::Error;
 
+use crate::text_chunker::{ChunkLevel, ParentChunk};
+
 /// Errors that can occur during vector database operations
 #[derive(Error, Debug)]
 pub enum VectorDbError {
@@ -24,6 +26,9 @@ pub enum VectorDbError {
     
     #[error("Storage error: {message}")]
     Storage { message: String },
+    
+    #[error("Model namespace error: {message}")]
+    ModelNamespace { message: String },
 }
 
 pub type VectorDbResult<T> = Result<T, VectorDbError>;
@@ -40,8 +45,15 @@ pub struct DataVersion {
 }
 
 impl DataVersion {
-    /// Current data format version
+    /// Current data format version (binary segments with a write-ahead log)
     pub const CURRENT: DataVersion = DataVersion {
+        major: 2,
+        minor: 0,
+        patch: 0,
+    };
+    
+    /// Last version of the JSON batch file format, migrated to segments on open
+    pub const JSON_BATCH: DataVersion = DataVersion {
         major: 1,
         minor: 0,
         patch: 0,
@@ -66,6 +78,39 @@ impl Default for DataVersion {
     }
 }
 
+/// Custom metadata key for the heading path of a chunk (JSON array of headings)
+pub const HEADING_PATH_METADATA_KEY: &str = "heading_path";
+
+/// Custom metadata key for the byte range of a chunk in its source file (`start..end`)
+pub const CHUNK_SPAN_METADATA_KEY: &str = "chunk_span";
+
+/// Custom metadata key for the chunks enclosing a chunk (JSON array of parents, nearest first)
+pub const PARENT_CHUNKS_METADATA_KEY: &str = "parent_chunks";
+
+/// Custom metadata key for the note's tags (JSON array, lowercase, without `#`)
+pub const TAGS_METADATA_KEY: &str = "tags";
+
+/// Custom metadata key for the note's frontmatter aliases (JSON array)
+pub const ALIASES_METADATA_KEY: &str = "aliases";
+
+/// Custom metadata key for the note's frontmatter fields (JSON object)
+pub const FRONTMATTER_METADATA_KEY: &str = "frontmatter";
+
+/// Custom metadata key for the note's frontmatter creation date (Unix seconds)
+pub const NOTE_CREATED_METADATA_KEY: &str = "note_created";
+
+/// Custom metadata key for the note's frontmatter update date (Unix seconds)
+pub const NOTE_UPDATED_METADATA_KEY: &str = "note_updated";
+
+/// Custom metadata keys that describe the whole note rather than the chunk
+pub const NOTE_METADATA_KEYS: [&str; 5] = [
+    TAGS_METADATA_KEY,
+    ALIASES_METADATA_KEY,
+    FRONTMATTER_METADATA_KEY,
+    NOTE_CREATED_METADATA_KEY,
+    NOTE_UPDATED_METADATA_KEY,
+];
+
 /// Metadata associated with an embedding entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingMetadata {
@@ -145,6 +190,137 @@ impl EmbeddingMetadata {
         self.custom_metadata.get(key)
     }
     
+    /// Record the markdown heading path (outermost first) of the source chunk
+    pub fn set_heading_path(&mut self, heading_path: &[String]) {
+        match Self::encode_heading_path(heading_path) {
+            Some(encoded) => {
+                self.custom_metadata.insert(HEADING_PATH_METADATA_KEY.to_string(), encoded);
+            }
+            None => {
+                self.custom_metadata.remove(HEADING_PATH_METADATA_KEY);
+            }
+        }
+    }
+    
+    /// Encode a heading path for storage under [`HEADING_PATH_METADATA_KEY`]
+    /// 
+    /// Returns `None` for an empty path so that chunks without headings carry
+    /// no heading metadata at all.
+    pub fn encode_heading_path(heading_path: &[String]) -> Option<String> {
+        if heading_path.is_empty() {
+            return None;
+        }
+        serde_json::to_string(heading_path).ok()
+    }
+    
+    /// Get the markdown heading path of the source chunk (empty if unknown)
+    pub fn heading_path(&self) -> Vec<String> {
+        self.custom_metadata
+            .get(HEADING_PATH_METADATA_KEY)
+            .and_then(|encoded| serde_json::from_str(encoded).ok())
+            .unwrap_or_default()
+    }
+    
+    /// Encode a chunk's byte range for storage under [`CHUNK_SPAN_METADATA_KEY`]
+    pub fn encode_chunk_span(start: usize, end: usize) -> String {
+        format!("{}..{}", start, end)
+    }
+    
+    /// Get the byte range of the source chunk within its file, if recorded
+    /// 
+    /// The range refers to the file as it was when the chunk was indexed; check
+    /// the text against `text_hash` before trusting it.
+    pub fn chunk_span(&self) -> Option<(usize, usize)> {
+        let (start, end) = self.custom_metadata.get(CHUNK_SPAN_METADATA_KEY)?.split_once("..")?;
+        let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+        (start <= end).then_some((start, end))
+    }
+    
+    /// Encode a chunk's parents for storage under [`PARENT_CHUNKS_METADATA_KEY`]
+    /// 
+    /// Returns `None` for flat chunks, which carry no parent metadata at all.
+    pub fn encode_parent_chunks(parents: &[ParentChunk]) -> Option<String> {
+        if parents.is_empty() {
+            return None;
+        }
+        serde_json::to_string(parents).ok()
+    }
+    
+    /// Get the chunks enclosing the source chunk, nearest first
+    pub fn parent_chunks(&self) -> Vec<ParentChunk> {
+        self.custom_metadata
+            .get(PARENT_CHUNKS_METADATA_KEY)
+            .and_then(|encoded| serde_json::from_str(encoded).ok())
+            .unwrap_or_default()
+    }
+    
+    /// Get the ID of the chunk directly enclosing the source chunk, if any
+    pub fn parent_id(&self) -> Option<String> {
+        self.parent_chunks().into_iter().next().map(|parent| parent.chunk_id)
+    }
+    
+    /// Metadata describing the enclosing chunk at `level` instead of the source chunk
+    /// 
+    /// The result has the parent's ID, span, preview and text hash, so the
+    /// parent text can be read back from the note like any chunk's. Returns
+    /// `None` when the chunk has no parent at that level.
+    pub fn parent_metadata(&self, level: ChunkLevel) -> Option<EmbeddingMetadata> {
+        let parents = self.parent_chunks();
+        let position = parents.iter().position(|parent| parent.level == level)?;
+        let parent = &parents[position];
+        
+        let mut metadata = self.clone();
+        metadata.chunk_id = parent.chunk_id.clone();
+        metadata.content_preview = parent.preview.clone();
+        metadata.text_length = parent.end_position - parent.start_position;
+        metadata.text_hash = parent.text_hash.clone();
+        metadata.custom_metadata.insert(
+            CHUNK_SPAN_METADATA_KEY.to_string(),
+            Self::encode_chunk_span(parent.start_position, parent.end_position),
+        );
+        match Self::encode_parent_chunks(&parents[position + 1..]) {
+            Some(encoded) => metadata.custom_metadata.insert(PARENT_CHUNKS_METADATA_KEY.to_string(), encoded),
+            None => metadata.custom_metadata.remove(PARENT_CHUNKS_METADATA_KEY),
+        };
+        Some(metadata)
+    }
+    
+    /// Get the tags of the source note (empty if it has none)
+    pub fn tags(&self) -> Vec<String> {
+        self.decode_list(TAGS_METADATA_KEY)
+    }
+    
+    /// Get the frontmatter aliases of the source note
+    pub fn aliases(&self) -> Vec<String> {
+        self.decode_list(ALIASES_METADATA_KEY)
+    }
+    
+    /// Get the frontmatter fields of the source note
+    pub fn frontmatter(&self) -> serde_json::Map<String, serde_json::Value> {
+        self.custom_metadata
+            .get(FRONTMATTER_METADATA_KEY)
+            .and_then(|encoded| serde_json::from_str(encoded).ok())
+            .unwrap_or_default()
+    }
+    
+    /// When the source note was last updated, in Unix seconds
+    /// 
+    /// Uses the note's frontmatter date when it has one and falls back to
+    /// when this entry was last written.
+    pub fn note_updated_at(&self) -> u64 {
+        self.custom_metadata
+            .get(NOTE_UPDATED_METADATA_KEY)
+            .and_then(|timestamp| timestamp.parse().ok())
+            .unwrap_or(self.updated_at)
+    }
+    
+    fn decode_list(&self, key: &str) -> Vec<String> {
+        self.custom_metadata
+            .get(key)
+            .and_then(|encoded| serde_json::from_str(encoded).ok())
+            .unwrap_or_default()
+    }
+    
     /// Create content preview from original text (first 100 chars)
     pub fn create_preview(text: &str) -> String {
         if text.len() <= 100 {
@@ -229,7 +405,7 @@ impl EmbeddingEntry {
             original_text,
         );
         
-        let id = Self::generate_id(&file_path, &chunk_id, &metadata.text_hash);
+        let id = Self::generate_id(&file_path, &chunk_id, &metadata.text_hash, &metadata.model_name);
         
         Self {
             id,
@@ -241,13 +417,18 @@ impl EmbeddingEntry {
     }
     
     /// Generate a unique ID for the embedding entry
-    pub fn generate_id(file_path: &str, chunk_id: &str, text_hash: &str) -> String {
+    /// 
+    /// The model name is part of the ID so the same chunk embedded by two
+    /// models is stored as two entries, one in each model namespace.
+    pub fn generate_id(file_path: &str, chunk_id: &str, text_hash: &str, model_name: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(file_path.as_bytes());
         hasher.update(b":");
         hasher.update(chunk_id.as_bytes());
         hasher.update(b":");
         hasher.update(text_hash.as_bytes());
+        hasher.update(b":");
+        hasher.update(model_name.as_bytes());
         format!("{:x}", hasher.finalize())
     }
     
@@ -413,19 +594,7 @@ pub enum CompressionAlgorithm {
 }
 
 /// Advanced vector compression algorithms (re-exported from compression module)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum VectorCompressionAlgorithm {
-    /// No compression (32-bit floats)
-    None,
-    /// 8-bit quantization (75% size reduction)
-    Quantized8Bit,
-    /// 16-bit quantization (50% size reduction)
-    Quantized16Bit,
-    /// Delta compression with quantization
-    DeltaQuantized,
-    /// Product quantization for large vectors
-    ProductQuantization,
-}
+pub use crate::vector_db::compression::VectorCompressionAlgorithm;
 
 impl CompressionAlgorithm {
     /// Get file extension for the compression algorithm
@@ -540,21 +709,23 @@ mod tests {
 
     #[test]
     fn test_data_version_compatibility() {
-        let current = DataVersion::CURRENT; // 1.0.0
-        let newer_minor = DataVersion { major: 1, minor: 1, patch: 0 };
-        let incompatible_major = DataVersion { major: 2, minor: 0, patch: 0 };
-        let older_minor = DataVersion { major: 1, minor: 0, patch: 0 };
+        let current = DataVersion::CURRENT; // 2.0.0
+        let newer_minor = DataVersion { major: 2, minor: 1, patch: 0 };
+        let incompatible_major = DataVersion { major: 3, minor: 0, patch: 0 };
+        let older_minor = DataVersion { major: 2, minor: 0, patch: 0 };
         
-        // Current version (1.0.0) cannot read newer minor version (1.1.0)
+        // Current version (2.0.0) cannot read newer minor version (2.1.0)
         assert!(!current.is_compatible(&newer_minor));
-        // Current version (1.0.0) cannot read different major version (2.0.0)
+        // Current version (2.0.0) cannot read different major version (3.0.0)
         assert!(!current.is_compatible(&incompatible_major));
-        // Current version (1.0.0) can read same version (1.0.0)
+        // Current version (2.0.0) can read same version (2.0.0)
         assert!(current.is_compatible(&current));
-        // Newer minor version (1.1.0) can read older minor version (1.0.0)
+        // Newer minor version (2.1.0) can read older minor version (2.0.0)
         assert!(newer_minor.is_compatible(&older_minor));
-        // Newer minor version (1.1.0) can read same version (1.1.0)
+        // Newer minor version (2.1.0) can read same version (2.1.0)
         assert!(newer_minor.is_compatible(&newer_minor));
+        // JSON batch files (1.0.0) are migrated rather than read directly
+        assert!(!current.is_compatible(&DataVersion::JSON_BATCH));
     }
 
     #[test]
@@ -577,6 +748,45 @@ mod tests {
         assert_eq!(metadata.text_hash.len(), 64); // SHA-256 hex string
     }
 
+    #[test]
+    fn test_heading_path_round_trip() {
+        let mut metadata = EmbeddingMetadata::new(
+            "/path/to/file.md".to_string(),
+            "chunk_1".to_string(),
+            "preview".to_string(),
+            7,
+            "test-model".to_string(),
+            "preview",
+        );
+        assert!(metadata.heading_path().is_empty());
+        
+        let path = vec!["Projects".to_string(), "Alpha > Beta".to_string()];
+        metadata.set_heading_path(&path);
+        assert_eq!(metadata.heading_path(), path);
+        
+        metadata.set_heading_path(&[]);
+        assert!(metadata.get_custom_metadata(HEADING_PATH_METADATA_KEY).is_none());
+    }
+
+    #[test]
+    fn test_chunk_span_round_trip() {
+        let mut metadata = EmbeddingMetadata::new(
+            "/path/to/file.md".to_string(),
+            "chunk_1".to_string(),
+            "preview".to_string(),
+            7,
+            "test-model".to_string(),
+            "preview",
+        );
+        assert_eq!(metadata.chunk_span(), None);
+
+        metadata.custom_metadata.insert(CHUNK_SPAN_METADATA_KEY.to_string(), EmbeddingMetadata::encode_chunk_span(12, 40));
+        assert_eq!(metadata.chunk_span(), Some((12, 40)));
+
+        metadata.custom_metadata.insert(CHUNK_SPAN_METADATA_KEY.to_string(), "40..12".to_string());
+        assert_eq!(metadata.chunk_span(), None);
+    }
+
     #[test]
     fn test_embedding_entry_creation() {
         let vector = vec![0.1, 0.2, 0.3, 0.4, 0.5];
@@ -653,12 +863,14 @@ mod tests {
         let chunk_id = "chunk_1";
         let text_hash = "abcdef1234567890";
         
-        let id1 = EmbeddingEntry::generate_id(file_path, chunk_id, text_hash);
-        let id2 = EmbeddingEntry::generate_id(file_path, chunk_id, text_hash);
-        let id3 = EmbeddingEntry::generate_id(file_path, "chunk_2", text_hash);
+        let id1 = EmbeddingEntry::generate_id(file_path, chunk_id, text_hash, "model-a");
+        let id2 = EmbeddingEntry::generate_id(file_path, chunk_id, text_hash, "model-a");
+        let id3 = EmbeddingEntry::generate_id(file_path, "chunk_2", text_hash, "model-a");
+        let id4 = EmbeddingEntry::generate_id(file_path, chunk_id, text_hash, "model-b");
         
         assert_eq!(id1, id2);
         assert_ne!(id1, id3);
+        assert_ne!(id1, id4);
         assert_eq!(id1.len(), 64); // SHA-256 hex string length
     }
 
@@ -0,0 +1,275 @@
+//! Write-Ahead Log for Vector Storage
+//!
+//! Every mutation of `VectorStorage` is appended to the log and synced before
+//! it is applied in memory. Mutations accumulate in a memtable until enough
+//! entries are buffered to be flushed into a segment, after which the log is
+//! truncated. On open the log is replayed on top of the segments, so nothing
+//! acknowledged to a caller is lost if the process dies before a flush.
+//!
+//! ## Framing
+//!
+//! ```text
+//! [payload length: u32 LE][checksum: first 4 bytes of SHA-256(payload)][payload: bincode WalRecord]
+//! ```
+//!
+//! A frame that is cut short or fails its checksum marks the end of the log; it
+//! can only be the tail of an append that was interrupted by a crash, and it is
+//! discarded when the log is reopened.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::vector_db::types::{EmbeddingEntry, VectorDbError, VectorDbResult};
+
+/// File name of the write-ahead log inside the storage directory
+pub const WAL_FILE_NAME: &str = "wal.log";
+
+/// Size of the length and checksum prefix of each frame
+const FRAME_HEADER_LEN: usize = 8;
+
+/// A single logged mutation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WalRecord {
+    /// Insert or replace an entry
+    Put(Box<EmbeddingEntry>),
+    /// Delete an entry by ID
+    Delete(String),
+    /// Mutations that take effect together; being one frame, a torn append
+    /// drops all of them
+    Batch(Vec<WalRecord>),
+}
+
+/// Append-only log file
+pub struct WriteAheadLog {
+    file: File,
+    len: u64,
+}
+
+impl WriteAheadLog {
+    /// Open (or create) the log and return the records it contains
+    pub fn open(path: &Path) -> VectorDbResult<(Self, Vec<WalRecord>)> {
+        let data = if path.exists() {
+            fs::read(path).map_err(|e| VectorDbError::Storage {
+                message: format!("Failed to read write-ahead log: {}", e),
+            })?
+        } else {
+            Vec::new()
+        };
+
+        let (records, valid_len) = Self::decode_frames(&data);
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| VectorDbError::Storage {
+                message: format!("Failed to open write-ahead log: {}", e),
+            })?;
+
+        if valid_len < data.len() {
+            eprintln!(
+                "⚠️ Discarding {} bytes of incomplete write-ahead log tail",
+                data.len() - valid_len
+            );
+            file.set_len(valid_len as u64).map_err(|e| VectorDbError::Storage {
+                message: format!("Failed to truncate write-ahead log: {}", e),
+            })?;
+            file.sync_all().map_err(|e| VectorDbError::Storage {
+                message: format!("Failed to sync write-ahead log: {}", e),
+            })?;
+        }
+
+        let wal = Self {
+            file,
+            len: valid_len as u64,
+        };
+        Ok((wal, records))
+    }
+
+    /// Append records and sync them to disk
+    pub fn append(&mut self, records: &[WalRecord]) -> VectorDbResult<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut buffer = Vec::new();
+        for record in records {
+            let payload = bincode::serialize(record).map_err(|e| VectorDbError::Storage {
+                message: format!("Failed to serialize write-ahead log record: {}", e),
+            })?;
+            buffer.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(&Self::frame_checksum(&payload));
+            buffer.extend_from_slice(&payload);
+        }
+
+        self.file.write_all(&buffer).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to append to write-ahead log: {}", e),
+        })?;
+        self.file.sync_data().map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to sync write-ahead log: {}", e),
+        })?;
+
+        self.len += buffer.len() as u64;
+        Ok(())
+    }
+
+    /// Drop all records (called once they are persisted in a segment)
+    pub fn reset(&mut self) -> VectorDbResult<()> {
+        self.file.set_len(0).map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to truncate write-ahead log: {}", e),
+        })?;
+        self.file.sync_all().map_err(|e| VectorDbError::Storage {
+            message: format!("Failed to sync write-ahead log: {}", e),
+        })?;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Current size of the log in bytes
+    pub fn len_bytes(&self) -> u64 {
+        self.len
+    }
+
+    /// Decode frames until the first incomplete or corrupt one
+    fn decode_frames(data: &[u8]) -> (Vec<WalRecord>, usize) {
+        let mut records = Vec::new();
+        let mut position = 0;
+
+        while position + FRAME_HEADER_LEN <= data.len() {
+            let mut len_bytes = [0u8; 4];
+            len_bytes.copy_from_slice(&data[position..position + 4]);
+            let payload_len = u32::from_le_bytes(len_bytes) as usize;
+            let payload_start = position + FRAME_HEADER_LEN;
+            let payload_end = payload_start + payload_len;
+            if payload_end > data.len() {
+                break;
+            }
+
+            let payload = &data[payload_start..payload_end];
+            if data[position + 4..payload_start] != Self::frame_checksum(payload) {
+                break;
+            }
+            match bincode::deserialize::<WalRecord>(payload) {
+                Ok(record) => records.push(record),
+                Err(_) => break,
+            }
+            position = payload_end;
+        }
+
+        (records, position)
+    }
+
+    fn frame_checksum(payload: &[u8]) -> [u8; 4] {
+        let digest = Sha256::digest(payload);
+        [digest[0], digest[1], digest[2], digest[3]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_entry(chunk: &str) -> EmbeddingEntry {
+        EmbeddingEntry::new(
+            vec![0.1, 0.2, 0.3],
+            "/test/wal.md".to_string(),
+            chunk.to_string(),
+            &format!("Text of {}", chunk),
+            "test-model".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_wal_replays_appended_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(WAL_FILE_NAME);
+        let entry = create_entry("a");
+
+        {
+            let (mut wal, records) = WriteAheadLog::open(&path).unwrap();
+            assert!(records.is_empty());
+            wal.append(&[WalRecord::Put(Box::new(entry.clone()))]).unwrap();
+            wal.append(&[WalRecord::Delete("other".to_string())]).unwrap();
+            assert!(wal.len_bytes() > 0);
+        }
+
+        let (wal, records) = WriteAheadLog::open(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(matches!(&records[0], WalRecord::Put(e) if e.id == entry.id && e.vector == entry.vector));
+        assert!(matches!(&records[1], WalRecord::Delete(id) if id == "other"));
+        assert_eq!(wal.len_bytes(), fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_wal_discards_torn_tail() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(WAL_FILE_NAME);
+
+        {
+            let (mut wal, _) = WriteAheadLog::open(&path).unwrap();
+            wal.append(&[WalRecord::Put(Box::new(create_entry("a")))]).unwrap();
+            wal.append(&[WalRecord::Put(Box::new(create_entry("b")))]).unwrap();
+        }
+
+        // Simulate a crash in the middle of the second append
+        let full_len = fs::metadata(&path).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(full_len - 5).unwrap();
+        drop(file);
+
+        let (mut wal, records) = WriteAheadLog::open(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(fs::metadata(&path).unwrap().len() < full_len - 5);
+
+        wal.append(&[WalRecord::Delete("c".to_string())]).unwrap();
+        drop(wal);
+        let (_, records) = WriteAheadLog::open(&path).unwrap();
+        assert_eq!(records.len(), 2);
+    }
+
+    #[test]
+    fn test_wal_batch_is_all_or_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(WAL_FILE_NAME);
+        let batch = WalRecord::Batch(vec![
+            WalRecord::Delete("a".to_string()),
+            WalRecord::Put(Box::new(create_entry("b"))),
+        ]);
+
+        {
+            let (mut wal, _) = WriteAheadLog::open(&path).unwrap();
+            wal.append(std::slice::from_ref(&batch)).unwrap();
+        }
+        let (_, records) = WriteAheadLog::open(&path).unwrap();
+        assert!(matches!(&records[..], [WalRecord::Batch(inner)] if inner.len() == 2));
+
+        // Cutting the frame short loses the whole batch
+        let full_len = fs::metadata(&path).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(full_len - 5).unwrap();
+        drop(file);
+        let (_, records) = WriteAheadLog::open(&path).unwrap();
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn test_wal_reset() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(WAL_FILE_NAME);
+
+        let (mut wal, _) = WriteAheadLog::open(&path).unwrap();
+        wal.append(&[WalRecord::Put(Box::new(create_entry("a")))]).unwrap();
+        wal.reset().unwrap();
+        assert_eq!(wal.len_bytes(), 0);
+        wal.append(&[WalRecord::Delete("a".to_string())]).unwrap();
+        drop(wal);
+
+        let (_, records) = WriteAheadLog::open(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(matches!(&records[0], WalRecord::Delete(_)));
+    }
+}
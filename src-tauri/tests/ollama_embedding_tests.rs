@@ -0,0 +1,132 @@
+//! Ollama Batch Embedding Tests
+//!
+//! Tests for `EmbeddingGenerator::generate_batch_embeddings` against a
+//! wiremock stand-in for Ollama's `/api/embed` and `/api/embeddings` endpoints.
+
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+use ainote_lib::embedding_generator::{EmbeddingConfig, EmbeddingError, EmbeddingGenerator};
+use ainote_lib::ollama_client::OllamaConfig;
+
+fn generator_for(server: &MockServer, batch_size: usize) -> EmbeddingGenerator {
+    EmbeddingGenerator::with_config(
+        OllamaConfig { base_url: server.uri(), ..Default::default() },
+        EmbeddingConfig {
+            batch_size,
+            max_retries: 0,
+            preprocess_text: false,
+            warmup_connections: false,
+            health_check_interval_seconds: 0,
+            ..Default::default()
+        },
+    )
+}
+
+/// Answer `/api/embed` with one `[text length, 1.0]` vector per input
+fn embed_by_length(request: &Request) -> ResponseTemplate {
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let embeddings: Vec<Vec<f32>> = body["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|input| vec![input.as_str().unwrap().len() as f32, 1.0])
+        .collect();
+    ResponseTemplate::new(200).set_body_json(json!({"model": body["model"], "embeddings": embeddings}))
+}
+
+#[tokio::test]
+async fn test_batch_embeddings_split_by_batch_size() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/embed"))
+        .and(body_partial_json(json!({"model": "nomic-embed-text"})))
+        .respond_with(embed_by_length)
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let texts: Vec<String> = ["a", "bb", "", "dddd", "eeeee", "ffffff"].iter().map(|t| t.to_string()).collect();
+    let embeddings = generator_for(&server, 2)
+        .generate_batch_embeddings(texts.clone(), "nomic-embed-text".to_string())
+        .await
+        .unwrap();
+
+    // Five non-empty texts in batches of two, results back in input order
+    assert_eq!(embeddings.len(), texts.len());
+    for (text, embedding) in texts.iter().zip(&embeddings) {
+        if text.is_empty() {
+            assert!(embedding.is_empty());
+        } else {
+            assert_eq!(embedding, &vec![text.len() as f32, 1.0]);
+        }
+    }
+
+    let requests = server.received_requests().await.unwrap();
+    let batch_sizes: Vec<usize> = requests
+        .iter()
+        .map(|request| serde_json::from_slice::<serde_json::Value>(&request.body).unwrap()["input"].as_array().unwrap().len())
+        .collect();
+    assert_eq!(batch_sizes, vec![2, 2, 1]);
+}
+
+#[tokio::test]
+async fn test_rejected_batch_maps_error_to_item() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/embed"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({"error": "input length exceeds context length"})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/embeddings"))
+        .and(body_partial_json(json!({"prompt": "far too long"})))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({"error": "input length exceeds context length"})))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"embedding": [0.5, 0.5]})))
+        .mount(&server)
+        .await;
+
+    let texts = vec!["fine".to_string(), "also fine".to_string(), "far too long".to_string()];
+    let error = generator_for(&server, 8)
+        .generate_batch_embeddings(texts, "nomic-embed-text".to_string())
+        .await
+        .unwrap_err();
+
+    match error {
+        EmbeddingError::BatchItem { index, source } => {
+            assert_eq!(index, 2);
+            assert!(matches!(*source, EmbeddingError::Api { status_code: 400, .. }));
+        }
+        other => panic!("expected a batch item error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_batch_response_length_mismatch_falls_back_to_single_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/embed"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"embeddings": [[1.0, 0.0]]})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"embedding": [0.0, 1.0]})))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let embeddings = generator_for(&server, 8)
+        .generate_batch_embeddings(vec!["one".to_string(), "two".to_string()], "nomic-embed-text".to_string())
+        .await
+        .unwrap();
+    assert_eq!(embeddings, vec![vec![0.0, 1.0], vec![0.0, 1.0]]);
+}
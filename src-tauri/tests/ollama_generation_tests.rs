@@ -0,0 +1,258 @@
+//! Ollama Text Generation Tests
+//!
+//! Tests for the streaming `generate` and `chat` methods of `OllamaClient`,
+//! run against a wiremock stand-in for the Ollama HTTP API.
+
+use std::time::{Duration, Instant};
+use serde_json::json;
+use tokio::sync::watch;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use ainote_lib::ollama_client::{
+    ChatMessage, ChatRequest, ChatRole, GenerateRequest, GenerationOptions, OllamaClient, OllamaClientError,
+    OllamaConfig,
+};
+
+fn client_for(server: &MockServer) -> OllamaClient {
+    OllamaClient::with_config(OllamaConfig {
+        base_url: server.uri(),
+        timeout_ms: 5000,
+        max_retries: 2,
+        initial_retry_delay_ms: 10,
+        max_retry_delay_ms: 20,
+        ..Default::default()
+    })
+}
+
+fn ndjson(lines: &[serde_json::Value]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn generate_request(prompt: &str) -> GenerateRequest {
+    GenerateRequest {
+        model: "llama3.2".to_string(),
+        prompt: prompt.to_string(),
+        system: None,
+        options: GenerationOptions::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_generate_streams_tokens_and_forwards_options() {
+    let server = MockServer::start().await;
+    let body = ndjson(&[
+        json!({"model": "llama3.2", "response": "Hello", "done": false}),
+        json!({"model": "llama3.2", "response": ", ", "done": false}),
+        json!({"model": "llama3.2", "response": "world", "done": false}),
+        json!({"model": "llama3.2", "response": "", "done": true, "done_reason": "stop",
+               "prompt_eval_count": 12, "eval_count": 3}),
+    ]);
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .and(body_partial_json(json!({
+            "model": "llama3.2",
+            "prompt": "Say hello",
+            "system": "Be brief",
+            "stream": true,
+            "options": {"temperature": 0.5, "num_ctx": 4096, "stop": ["\n\n"]}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let request = GenerateRequest {
+        system: Some("Be brief".to_string()),
+        options: GenerationOptions {
+            temperature: Some(0.5),
+            num_ctx: Some(4096),
+            stop: vec!["\n\n".to_string()],
+            ..Default::default()
+        },
+        ..generate_request("Say hello")
+    };
+
+    let mut tokens = Vec::new();
+    let response = client_for(&server)
+        .generate(&request, None, |token| tokens.push(token.to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(tokens, vec!["Hello", ", ", "world"]);
+    assert_eq!(response.content, "Hello, world");
+    assert_eq!(response.done_reason.as_deref(), Some("stop"));
+    assert_eq!(response.prompt_eval_count, Some(12));
+    assert_eq!(response.eval_count, Some(3));
+}
+
+#[tokio::test]
+async fn test_chat_streams_assistant_message() {
+    let server = MockServer::start().await;
+    let body = ndjson(&[
+        json!({"message": {"role": "assistant", "content": "Project "}, "done": false}),
+        json!({"message": {"role": "assistant", "content": "Notes"}, "done": false}),
+        json!({"message": {"role": "assistant", "content": ""}, "done": true, "done_reason": "stop"}),
+    ]);
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .and(body_partial_json(json!({
+            "messages": [
+                {"role": "system", "content": "You name notes"},
+                {"role": "user", "content": "Suggest a title"}
+            ],
+            "stream": true
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let request = ChatRequest {
+        model: "llama3.2".to_string(),
+        messages: vec![
+            ChatMessage { role: ChatRole::System, content: "You name notes".to_string() },
+            ChatMessage { role: ChatRole::User, content: "Suggest a title".to_string() },
+        ],
+        options: GenerationOptions::default(),
+    };
+
+    let mut token_count = 0;
+    let response = client_for(&server)
+        .chat(&request, None, |_| token_count += 1)
+        .await
+        .unwrap();
+
+    assert_eq!(token_count, 2);
+    assert_eq!(response.content, "Project Notes");
+}
+
+#[tokio::test]
+async fn test_generate_retries_server_errors_with_backoff() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(ndjson(&[
+            json!({"response": "ok", "done": true}),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let response = client_for(&server)
+        .generate(&generate_request("Retry please"), None, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(response.content, "ok");
+}
+
+#[tokio::test]
+async fn test_generate_does_not_retry_client_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({"error": "model 'missing' not found"})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let error = client_for(&server)
+        .generate(&generate_request("Hello"), None, |_| {})
+        .await
+        .unwrap_err();
+
+    match error {
+        OllamaClientError::HttpError { status_code, message } => {
+            assert_eq!(status_code, 404);
+            assert!(message.contains("not found"));
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_generate_reports_stream_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(ndjson(&[
+            json!({"response": "Partial", "done": false}),
+            json!({"error": "out of memory"}),
+        ])))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(ndjson(&[
+            json!({"message": {"role": "assistant", "content": "cut"}, "done": false}),
+        ])))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    let error = client.generate(&generate_request("Hello"), None, |_| {}).await.unwrap_err();
+    assert!(matches!(error, OllamaClientError::GenerationError { ref message } if message.contains("out of memory")));
+
+    let request = ChatRequest {
+        model: "llama3.2".to_string(),
+        messages: vec![ChatMessage { role: ChatRole::User, content: "Hi".to_string() }],
+        options: GenerationOptions::default(),
+    };
+    let error = client.chat(&request, None, |_| {}).await.unwrap_err();
+    assert!(matches!(error, OllamaClientError::GenerationError { ref message } if message.contains("ended before completion")));
+}
+
+#[tokio::test]
+async fn test_generate_can_be_cancelled() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(ndjson(&[json!({"response": "late", "done": true})]))
+                .set_delay(Duration::from_secs(5)),
+        )
+        .mount(&server)
+        .await;
+
+    let (cancel_sender, cancel_receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let _ = cancel_sender.send(true);
+    });
+
+    let start = Instant::now();
+    let error = client_for(&server)
+        .generate(&generate_request("Take your time"), Some(cancel_receiver), |_| {})
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, OllamaClientError::Cancelled));
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn test_generate_rejects_invalid_requests() {
+    let server = MockServer::start().await;
+    let client = client_for(&server);
+
+    let error = client.generate(&generate_request("   "), None, |_| {}).await.unwrap_err();
+    assert!(matches!(error, OllamaClientError::GenerationError { .. }));
+
+    let request = ChatRequest {
+        model: "llama3.2".to_string(),
+        messages: Vec::new(),
+        options: GenerationOptions::default(),
+    };
+    let error = client.chat(&request, None, |_| {}).await.unwrap_err();
+    assert!(matches!(error, OllamaClientError::GenerationError { .. }));
+
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
@@ -9,10 +9,11 @@ use std::time::{Duration, Instant};
 use tempfile::TempDir;
 use tokio::time::timeout;
 
+use ainote_lib::similarity_search::{SearchConfig, SimilaritySearch};
 use ainote_lib::vector_db::{
     VectorDatabase,
     types::{
-        EmbeddingEntry, VectorStorageConfig, CompressionAlgorithm,
+        EmbeddingEntry, EmbeddingMetadata, VectorStorageConfig, CompressionAlgorithm, CHUNK_SPAN_METADATA_KEY,
     },
 };
 
@@ -257,6 +258,273 @@ async fn test_batch_operations() {
 
 // === File Operations and Atomic Writes Tests ===
 
+#[tokio::test]
+async fn test_approximate_search_tracks_store_and_delete() {
+    let (config, __temp_dir) = TestConfigFactory::minimal_config();
+    let db = VectorDatabase::new(config.clone()).await.unwrap();
+    let search_config = SearchConfig {
+        min_threshold: -1.0,
+        ..SearchConfig::default()
+    };
+    
+    let entries: Vec<EmbeddingEntry> = (0..200)
+        .map(|i| {
+            let vector = (0..64).map(|d| ((i * 64 + d) as f32 * 0.618).sin()).collect();
+            EmbeddingEntry::new(
+                vector,
+                format!("/test/ann_{}.md", i),
+                "chunk_0".to_string(),
+                &format!("ann {}", i),
+                "test-model-v1".to_string(),
+            )
+        })
+        .collect();
+    db.store_embeddings_batch(entries.clone()).await.unwrap();
+    assert_eq!(db.get_ann_index_stats().await.node_count, 200);
+    
+    // Every stored vector is its own nearest neighbor
+    let target = &entries[17];
+    let results = db.approximate_search(&target.vector, 5, &search_config).await.unwrap();
+    assert_eq!(results[0].entry.id, target.id);
+    assert!((results[0].similarity - 1.0).abs() < 1e-5);
+    
+    // Deleted entries disappear from approximate results
+    assert!(db.delete_embedding(&target.id).await.unwrap());
+    let results = db.approximate_search(&target.vector, 5, &search_config).await.unwrap();
+    assert!(results.iter().all(|r| r.entry.id != target.id));
+    assert_eq!(db.get_ann_index_stats().await.node_count, 199);
+    
+    // The snapshot of the model namespace is written next to the storage files
+    db.save_ann_index().await.unwrap();
+    assert!(std::path::Path::new(&config.storage_dir).join("hnsw_index.test-model-v1.bin").exists());
+}
+
+#[tokio::test]
+async fn test_compressed_search_matches_exact_scan() {
+    let (mut config, __temp_dir) = TestConfigFactory::minimal_config();
+    config.enable_vector_compression = true;
+    config.vector_compression_algorithm = ainote_lib::vector_db::types::VectorCompressionAlgorithm::Quantized8Bit;
+    let db = VectorDatabase::new(config).await.unwrap();
+    let search_config = SearchConfig {
+        min_threshold: -1.0,
+        early_termination: false,
+        normalize_query: false,
+        enable_diversity_filter: false,
+        enable_recency_weighting: false,
+        ..SearchConfig::default()
+    };
+    
+    let entries: Vec<EmbeddingEntry> = (0..200)
+        .map(|i| {
+            let vector = (0..64).map(|d| ((i * 64 + d) as f32 * 0.618).sin()).collect();
+            EmbeddingEntry::new(
+                vector,
+                format!("/test/compressed_{}.md", i),
+                "chunk_0".to_string(),
+                &format!("compressed {}", i),
+                "test-model-v1".to_string(),
+            )
+        })
+        .collect();
+    db.store_embeddings_batch(entries.clone()).await.unwrap();
+    
+    let query: Vec<f32> = entries[42].vector.iter().enumerate()
+        .map(|(d, value)| value + (d as f32 * 0.3).cos() * 0.2)
+        .collect();
+    let exact = SimilaritySearch::k_nearest_neighbors(&query, &entries, 10, &search_config).unwrap();
+    let compressed = db.compressed_search(&query, 10, &search_config).await.unwrap();
+    assert_eq!(db.quantized_vectors().len().await, 200);
+    
+    // Re-ranked scores are exact, so the ranking matches a full scan
+    assert_eq!(
+        compressed.iter().map(|r| &r.entry.id).collect::<Vec<_>>(),
+        exact.iter().map(|r| &r.entry.id).collect::<Vec<_>>()
+    );
+    assert!((compressed[0].similarity - exact[0].similarity).abs() < 1e-5);
+    
+    // Deleted entries lose their codes on the next search
+    assert!(db.delete_embedding(&exact[0].entry.id).await.unwrap());
+    let compressed = db.compressed_search(&query, 10, &search_config).await.unwrap();
+    assert!(compressed.iter().all(|r| r.entry.id != exact[0].entry.id));
+    assert_eq!(db.quantized_vectors().len().await, 199);
+}
+
+#[tokio::test]
+async fn test_hybrid_search_finds_exact_terms() {
+    let (config, __temp_dir) = TestConfigFactory::minimal_config();
+    let db = VectorDatabase::new(config).await.unwrap();
+    
+    let texts = [
+        "Backups run nightly and are copied to the NAS.",
+        "The retry policy is configured with MAX_RETRY_BUDGET in settings.",
+        "Weekly review of goals and habits.",
+    ];
+    let mut ids = Vec::new();
+    for (i, text) in texts.iter().enumerate() {
+        let vector = vec![1.0 - i as f32 * 0.4, i as f32 * 0.4, 0.1];
+        ids.push(
+            db.store_embedding(vector, format!("/test/hybrid_{}.md", i), "chunk_0", text, "test-model-v1")
+                .await
+                .unwrap(),
+        );
+    }
+    assert_eq!(db.get_lexical_index_stats().await.document_count, 3);
+    
+    // The query vector points at the backups note, the query text names the retry setting
+    let query_vector = vec![1.0, 0.0, 0.1];
+    let results = db
+        .hybrid_search("max_retry_budget", &query_vector, 3, &SearchConfig::default())
+        .await
+        .unwrap();
+    let retry = results.iter().find(|r| r.entry.id == ids[1]).expect("lexical match missing");
+    assert_eq!(retry.lexical_rank, Some(1));
+    assert!(retry.lexical_score.unwrap() > 0.0);
+    
+    // Deleted chunks leave the lexical index too
+    assert!(db.delete_embedding(&ids[1]).await.unwrap());
+    assert!(db.lexical_search("max_retry_budget", 5).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_lexical_index_uses_full_chunk_text() {
+    let (config, __temp_dir) = TestConfigFactory::minimal_config();
+    let note_path = __temp_dir.path().join("long_note.md");
+    let chunk = format!("{} The restore drill uses the zanzibar checklist.", "Backups run nightly. ".repeat(10));
+    std::fs::write(&note_path, &chunk).unwrap();
+
+    let mut entry = EmbeddingEntry::new(
+        vec![1.0, 0.0, 0.1],
+        note_path.to_string_lossy().to_string(),
+        "chunk_0".to_string(),
+        &chunk,
+        "test-model-v1".to_string(),
+    );
+    entry.metadata.custom_metadata.insert(
+        CHUNK_SPAN_METADATA_KEY.to_string(),
+        EmbeddingMetadata::encode_chunk_span(0, chunk.len()),
+    );
+    assert!(!entry.metadata.content_preview.contains("zanzibar"));
+
+    // Batches carry no text, so the chunk is read back from its note
+    {
+        let db = VectorDatabase::new(config.clone()).await.unwrap();
+        db.store_embeddings_batch(vec![entry.clone()]).await.unwrap();
+        assert_eq!(db.lexical_search("zanzibar", 5).await[0].0, entry.id);
+        db.save_lexical_index().await.unwrap();
+    }
+
+    // Entries missing from the lexical snapshot are backfilled the same way
+    std::fs::remove_file(std::path::Path::new(&config.storage_dir).join("lexical_index.bin")).unwrap();
+    let db = VectorDatabase::new(config).await.unwrap();
+    assert_eq!(db.lexical_search("zanzibar", 5).await[0].0, entry.id);
+}
+
+#[tokio::test]
+async fn test_move_file_embeddings_keeps_vectors_and_indexes() {
+    let (config, __temp_dir) = TestConfigFactory::minimal_config();
+    let search_config = SearchConfig {
+        min_threshold: -1.0,
+        ..SearchConfig::default()
+    };
+
+    let moved_vector = vec![0.9, 0.1, 0.2];
+    {
+        let db = VectorDatabase::new(config.clone()).await.unwrap();
+        db.store_embedding(moved_vector.clone(), "/vault/old.md", "chunk_0", "Quarterly roadmap draft", "test-model-v1")
+            .await
+            .unwrap();
+        db.store_embedding(vec![0.1, 0.9, 0.2], "/vault/new.md", "chunk_0", "Stale note in the way", "test-model-v1")
+            .await
+            .unwrap();
+
+        let moves = vec![("/vault/old.md".to_string(), "/vault/new.md".to_string())];
+        assert_eq!(db.move_file_embeddings(&moves).await.unwrap(), 1);
+
+        assert!(db.find_embeddings_by_file("/vault/old.md").await.unwrap().is_empty());
+        let moved = db.find_embeddings_by_file("/vault/new.md").await.unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].vector, moved_vector);
+
+        // Both indexes answer with the new ID and forgot the replaced entry
+        let results = db.approximate_search(&moved_vector, 5, &search_config).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.id, moved[0].id);
+        let lexical = db.lexical_search("roadmap", 5).await;
+        assert_eq!(lexical.len(), 1);
+        assert_eq!(lexical[0].0, moved[0].id);
+        assert!(db.lexical_search("stale", 5).await.is_empty());
+    }
+
+    // The move was persisted
+    let db = VectorDatabase::new(config).await.unwrap();
+    assert_eq!(db.count_embeddings().await, 1);
+    assert_eq!(db.find_embeddings_by_file("/vault/new.md").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_model_namespaces_coexist_and_switch() {
+    let (config, __temp_dir) = TestConfigFactory::minimal_config();
+    let search_config = SearchConfig {
+        min_threshold: -1.0,
+        max_results: 0,
+        enable_diversity_filter: false,
+        ..SearchConfig::default()
+    };
+    
+    let small_ids = {
+        let db = VectorDatabase::new(config.clone()).await.unwrap();
+        let mut small_ids = Vec::new();
+        for i in 0..10 {
+            let text = format!("note {} about gardening", i);
+            let small = vec![1.0, i as f32 * 0.1, 0.0];
+            let large = vec![0.0, 1.0, i as f32 * 0.1, 0.0, 0.5];
+            let path = format!("/test/ns_{}.md", i);
+            small_ids.push(db.store_embedding(small, path.clone(), "chunk_0", &text, "small-model").await.unwrap());
+            db.store_embedding(large, path, "chunk_0", &text, "large-model").await.unwrap();
+        }
+        
+        // The same chunk embedded by two models is stored twice, the first model stays active
+        assert_eq!(db.count_embeddings().await, 20);
+        assert_eq!(db.active_model().await.as_deref(), Some("small-model"));
+        assert_eq!(db.ann_index_len().await, 10);
+        
+        // Searches only return entries of the active model, including lexical matches
+        let results = db.approximate_search(&[1.0, 0.5, 0.0], 20, &search_config).await.unwrap();
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|r| r.entry.metadata.model_name == "small-model"));
+        let results = db.hybrid_search("gardening", &[1.0, 0.5, 0.0], 20, &search_config).await.unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|r| r.entry.metadata.model_name == "small-model"));
+        
+        // Unknown models cannot be activated and the active one cannot be deleted
+        assert!(db.activate_model_namespace("missing-model").await.is_err());
+        assert!(db.delete_model_namespace("small-model").await.is_err());
+        
+        // Switch to the other namespace
+        assert_eq!(db.activate_model_namespace("large-model").await.unwrap(), 10);
+        let results = db.approximate_search(&[0.0, 1.0, 0.5, 0.0, 0.5], 20, &search_config).await.unwrap();
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|r| r.entry.metadata.model_name == "large-model"));
+        small_ids
+    };
+    
+    // The active model and its index survive a reopen
+    let db = VectorDatabase::new(config).await.unwrap();
+    assert_eq!(db.active_model().await.as_deref(), Some("large-model"));
+    assert_eq!(db.ann_index_len().await, 10);
+    let namespaces = db.list_model_namespaces().await.unwrap();
+    assert_eq!(namespaces.len(), 2);
+    assert_eq!(namespaces[0].model_name, "large-model");
+    assert!(namespaces[0].is_active);
+    assert_eq!(namespaces[0].dimension, 5);
+    
+    // Dropping the previous namespace removes only its entries
+    assert_eq!(db.delete_model_namespace("small-model").await.unwrap(), 10);
+    assert_eq!(db.count_embeddings().await, 10);
+    assert!(db.retrieve_embedding(&small_ids[0]).await.unwrap().is_none());
+    assert_eq!(db.list_model_namespaces().await.unwrap().len(), 1);
+}
+
 #[tokio::test]
 async fn test_file_locking_and_atomic_operations() {
     let (config, __temp_dir) = TestConfigFactory::full_featured_config();